@@ -0,0 +1,13 @@
+// 启用 `grpc` feature 时编译 proto/weather.proto；使用内置 protoc，无需本机安装
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/weather.proto");
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("protoc 不可用");
+        std::env::set_var("PROTOC", protoc);
+        tonic_build::configure()
+            .build_client(false)
+            .compile_protos(&["proto/weather.proto"], &["proto"])
+            .expect("编译 proto 失败");
+    }
+}
@@ -0,0 +1,223 @@
+// 访问日志：独立于 tracing 日志的纯文本访问记录，每个请求一行，便于 GoAccess、awstats 等工具或日志平台直接处理
+// - ACCESS_LOG_FILE：日志文件路径，设置后开启，如 logs/access.log
+// - ACCESS_LOG_FORMAT：combined（默认）、common 或 json
+//   common/combined 为标准格式，行尾附加耗时毫秒数（同 nginx 的 $request_time 用法，解析器忽略多余字段即可）；
+//   json 每行一个对象，另含命中的路由模板与请求 ID
+// - ACCESS_LOG_ROTATION：daily（默认，写入 access.log.YYYY-MM-DD）、hourly 或 never（始终写同一文件，交给 logrotate）
+// - ACCESS_LOG_TRUSTED_PROXIES：可信代理的地址或网段，逗号分隔，如 127.0.0.1,10.0.0.0/8；
+//   只有来自这些地址的请求才采信 X-Forwarded-For 等转发头，未设置时记录连接的对端地址
+// 字节数为实际发出的响应体字节（压缩后），在响应体发送完毕（或客户端断开）时写出该行，耗时也计到此刻
+
+use std::{io::Write, net::SocketAddr, time::Instant};
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{ConnectInfo, MatchedPath, OriginalUri, Request},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use futures_util::TryStreamExt;
+use once_cell::sync::OnceCell;
+use serde_json::json;
+use tracing::info;
+use tracing_appender::{
+    non_blocking::{NonBlocking, WorkerGuard},
+    rolling,
+};
+
+use crate::{
+    client_ip::{self, Cidr},
+    config::{env_list, env_nonempty},
+    redact, request_id,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Common,
+    Combined,
+    Json,
+}
+
+struct Config {
+    path: String,
+    format: Format,
+    rotation: rolling::Rotation,
+    trusted: Vec<Cidr>,
+}
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+static WRITER: OnceCell<NonBlocking> = OnceCell::new();
+
+// 校验配置；文件在 start 中打开，doctor 只校验不创建文件
+pub fn init_from_env() -> anyhow::Result<()> {
+    let Some(path) = env_nonempty("ACCESS_LOG_FILE") else {
+        return Ok(());
+    };
+    let format = match env_nonempty("ACCESS_LOG_FORMAT").as_deref().map(str::to_ascii_lowercase).as_deref() {
+        None | Some("combined") => Format::Combined,
+        Some("common") => Format::Common,
+        Some("json") => Format::Json,
+        Some(other) => anyhow::bail!("ACCESS_LOG_FORMAT 无效：{}（可选 common、combined、json）", other),
+    };
+    let rotation = match env_nonempty("ACCESS_LOG_ROTATION").as_deref().map(str::to_ascii_lowercase).as_deref() {
+        None | Some("daily") => rolling::Rotation::DAILY,
+        Some("hourly") => rolling::Rotation::HOURLY,
+        Some("never") => rolling::Rotation::NEVER,
+        Some(other) => anyhow::bail!("ACCESS_LOG_ROTATION 无效：{}（可选 daily、hourly、never）", other),
+    };
+    let trusted = env_list("ACCESS_LOG_TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .iter()
+        .map(|raw| Cidr::parse(raw).ok_or_else(|| anyhow::anyhow!("ACCESS_LOG_TRUSTED_PROXIES 中的地址无效：{}", raw)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if std::path::Path::new(&path).file_name().is_none() {
+        anyhow::bail!("ACCESS_LOG_FILE 缺少文件名");
+    }
+    let _ = CONFIG.set(Config { path, format, rotation, trusted });
+    Ok(())
+}
+
+// 打开日志文件并启动写线程；返回的 guard 需持有到退出，drop 时写出剩余内容
+pub fn start() -> anyhow::Result<Option<WorkerGuard>> {
+    let Some(config) = CONFIG.get() else {
+        return Ok(None);
+    };
+    let path = std::path::Path::new(&config.path);
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(std::path::Path::new("."));
+    let name = path.file_name().unwrap_or_default();
+    let appender = rolling::RollingFileAppender::new(config.rotation.clone(), dir, name);
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let _ = WRITER.set(writer);
+    info!("access log enabled: {}", config.path);
+    Ok(Some(guard))
+}
+
+// 请求一侧的字段，响应体结束时与状态码、字节数、耗时一起写出
+struct Entry {
+    format: Format,
+    started: Instant,
+    time: chrono::DateTime<chrono::Local>,
+    client: String,
+    method: String,
+    target: String,
+    protocol: String,
+    route: Option<String>,
+    request_id: Option<String>,
+    referer: Option<String>,
+    user_agent: Option<String>,
+    status: u16,
+    bytes: u64,
+}
+
+impl Entry {
+    // 以方法累加，使闭包捕获整个 entry（而非仅 bytes 字段），流丢弃时才写出
+    fn count(&mut self, n: usize) {
+        self.bytes += n as u64;
+    }
+
+    fn line(&self) -> String {
+        let latency_ms = self.started.elapsed().as_millis() as u64;
+        if self.format == Format::Json {
+            let mut line = json!({
+                "time": self.time.to_rfc3339(),
+                "client_ip": self.client,
+                "method": self.method,
+                "path": self.target,
+                "protocol": self.protocol,
+                "route": self.route,
+                "status": self.status,
+                "bytes": self.bytes,
+                "latency_ms": latency_ms,
+                "referer": self.referer,
+                "user_agent": self.user_agent,
+                "request_id": self.request_id,
+            })
+            .to_string();
+            line.push('\n');
+            return line;
+        }
+        // CLF 中字节数为 0 时写 -，字符串字段中的双引号转义
+        let bytes = if self.bytes == 0 { "-".to_string() } else { self.bytes.to_string() };
+        let mut line = format!(
+            "{} - - [{}] \"{} {} {}\" {} {}",
+            self.client,
+            self.time.format("%d/%b/%Y:%H:%M:%S %z"),
+            self.method,
+            quote(&self.target),
+            self.protocol,
+            self.status,
+            bytes,
+        );
+        if self.format == Format::Combined {
+            line.push_str(&format!(
+                " \"{}\" \"{}\"",
+                quote(self.referer.as_deref().unwrap_or("-")),
+                quote(self.user_agent.as_deref().unwrap_or("-")),
+            ));
+        }
+        line.push_str(&format!(" {}\n", latency_ms));
+        line
+    }
+}
+
+impl Drop for Entry {
+    fn drop(&mut self) {
+        if let Some(writer) = WRITER.get() {
+            // 请求地址中可能带 api_key 等参数
+            let _ = writer.clone().write_all(redact::redact(&self.line()).as_bytes());
+        }
+    }
+}
+
+fn quote(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+}
+
+pub async fn middleware(req: Request, next: Next) -> Response {
+    let (Some(config), Some(_)) = (CONFIG.get(), WRITER.get()) else {
+        return next.run(req).await;
+    };
+    let peer = req.extensions().get::<ConnectInfo<SocketAddr>>().map(|c| c.0);
+    let client = client_ip::from_trusted(req.headers(), peer, &config.trusted)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let mut entry = Entry {
+        format: config.format,
+        started: Instant::now(),
+        time: chrono::Local::now(),
+        client,
+        method: req.method().to_string(),
+        // 原始请求地址（含 BASE_PATH 前缀）
+        target: req
+            .extensions()
+            .get::<OriginalUri>()
+            .map_or(req.uri(), |u| &u.0)
+            .path_and_query()
+            .map(|p| p.as_str())
+            .unwrap_or("/")
+            .to_string(),
+        protocol: format!("{:?}", req.version()),
+        route: req.extensions().get::<MatchedPath>().map(|p| p.as_str().to_string()),
+        request_id: req.headers().get(&request_id::X_REQUEST_ID).and_then(|v| v.to_str().ok()).map(str::to_string),
+        referer: header_str(req.headers(), header::REFERER),
+        user_agent: header_str(req.headers(), header::USER_AGENT),
+        status: 0,
+        bytes: 0,
+    };
+    let resp = next.run(req).await;
+    entry.status = resp.status().as_u16();
+    // 定长响应体直接记录长度并写出，保留 Content-Length
+    if let Some(len) = resp.body().size_hint().exact() {
+        entry.bytes = len;
+        return resp;
+    }
+    // 流式响应体（如压缩后的）随流出计数，流结束或被丢弃时 entry 随之 drop 并写出
+    let (parts, body) = resp.into_parts();
+    let stream = body.into_data_stream().inspect_ok(move |chunk| entry.count(chunk.len()));
+    Response::from_parts(parts, Body::from_stream(stream))
+}
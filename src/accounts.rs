@@ -0,0 +1,796 @@
+// 用户账户：邮箱 + 密码（argon2 哈希）注册登录，或邮件登录链接（需 `email` feature 与 SMTP 配置），登录后下发会话 Cookie，
+// 收藏地点、单位、语言与通知设置按用户保存在服务端，跨设备同步；用户、会话与偏好存于 SQLite
+// - ACCOUNTS：1 开启，接口为 /api/v1/auth/* 与 /api/v1/me/*
+// - ACCOUNTS_DB：数据库路径，默认 DATA_DIR/accounts.sqlite3
+// - ACCOUNTS_SESSION_DAYS：会话有效天数，默认 30
+// - ACCOUNTS_ALLOW_REGISTER：0 时关闭密码注册（邮件登录链接仍可为新邮箱建号），默认开启
+// 也可经外部身份提供方登录，见 oidc.rs
+// Cookie 为 HttpOnly、SameSite=Lax，PUBLIC_URL 为 https 时附加 Secure；登录链接基于 PUBLIC_URL，15 分钟内有效且只能使用一次
+// 打开登录链接只显示确认页，提交后才登录（邮件客户端与安全扫描预取链接不会消耗它）；同一邮箱、同一客户端 IP 的发送次数有上限
+
+use std::{net::SocketAddr, sync::Mutex, time::Duration};
+
+use argon2::{
+    password_hash::{rand_core::OsRng, rand_core::RngCore, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Query},
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Form, Json, Router,
+};
+use chrono::{DateTime, Utc};
+use once_cell::sync::{Lazy, OnceCell};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{
+    base_path,
+    cache::TtlCache,
+    client_ip,
+    config::{env_flag, env_nonempty, env_parse},
+    db_error, oidc, request_id, respond, scheduler, AppState, ErrorResp,
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS users (
+        id TEXT PRIMARY KEY,
+        email TEXT NOT NULL UNIQUE COLLATE NOCASE,
+        password_hash TEXT,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS sessions (
+        token TEXT PRIMARY KEY,
+        user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+        expires_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS login_links (
+        token TEXT PRIMARY KEY,
+        email TEXT NOT NULL,
+        expires_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS identities (
+        issuer TEXT NOT NULL,
+        subject TEXT NOT NULL,
+        user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+        PRIMARY KEY (issuer, subject)
+    );
+    CREATE TABLE IF NOT EXISTS preferences (
+        user_id TEXT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+        data TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+";
+
+const COOKIE: &str = "session";
+// 确认页下发的一次性 Cookie，提交时须与表单字段一致，防止他站代提交（登录 CSRF）
+const LINK_CSRF_COOKIE: &str = "link_csrf";
+const MIN_PASSWORD_LEN: usize = 8;
+const LINK_TTL_SECS: i64 = 15 * 60;
+const MAX_LOCATIONS: usize = 50;
+// 每个链接有效期内，同一邮箱 / 同一客户端 IP 最多发送的登录链接数
+const LINKS_PER_EMAIL: u32 = 3;
+const LINKS_PER_CLIENT: u32 = 10;
+
+// 登录链接发送计数，键为 email:<邮箱> 或 ip:<地址>
+static LINK_REQUESTS: Lazy<TtlCache<String, u32>> =
+    Lazy::new(|| TtlCache::new(Duration::from_secs(LINK_TTL_SECS as u64), 10_000));
+
+// 邮箱不存在或账户没有密码时校验此哈希，使耗时与密码错误相同，不能据此判断邮箱是否已注册
+static DUMMY_HASH: Lazy<String> = Lazy::new(|| {
+    let salt = SaltString::encode_b64(b"caiyun-dummy-salt").expect("固定盐");
+    Argon2::default().hash_password(b"dummy password", &salt).expect("固定哈希").to_string()
+});
+
+struct Accounts {
+    conn: Mutex<Connection>,
+    session_secs: i64,
+    allow_register: bool,
+    secure_cookie: bool,
+}
+
+static ACCOUNTS: OnceCell<Accounts> = OnceCell::new();
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    if !env_flag("ACCOUNTS") {
+        return Ok(());
+    }
+    let session_days = env_parse::<i64>("ACCOUNTS_SESSION_DAYS")?.unwrap_or(30);
+    if session_days <= 0 {
+        anyhow::bail!("ACCOUNTS_SESSION_DAYS 应为正整数");
+    }
+    let path = env_nonempty("ACCOUNTS_DB").unwrap_or_else(|| {
+        let dir = env_nonempty("DATA_DIR").unwrap_or_else(|| "data".into());
+        format!("{}/accounts.sqlite3", dir.trim_end_matches('/'))
+    });
+    if let Some(dir) = std::path::Path::new(&path).parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let conn = Connection::open(&path).map_err(|e| anyhow::anyhow!("无法打开 {}: {}", path, e))?;
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL; PRAGMA foreign_keys = ON;")?;
+    conn.execute_batch(SCHEMA)?;
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM users", [], |r| r.get(0))?;
+    info!("accounts enabled ({} user(s))", count);
+    let _ = ACCOUNTS.set(Accounts {
+        conn: Mutex::new(conn),
+        session_secs: session_days * 86_400,
+        allow_register: !matches!(env_nonempty("ACCOUNTS_ALLOW_REGISTER").as_deref(), Some("0" | "false" | "no" | "off")),
+        secure_cookie: env_nonempty("PUBLIC_URL").is_some_and(|u| u.starts_with("https://")),
+    });
+    // 提前算好，避免第一次登录不存在的邮箱时多花一次哈希的时间
+    tokio::task::spawn_blocking(|| Lazy::force(&DUMMY_HASH));
+    scheduler::register("accounts_prune", "15 * * * *", || async {
+        let Some(accounts) = ACCOUNTS.get() else { return Ok(()) };
+        let now = Utc::now().timestamp();
+        let conn = accounts.conn.lock().unwrap();
+        conn.execute("DELETE FROM sessions WHERE expires_at < ?1", [now])?;
+        conn.execute("DELETE FROM login_links WHERE expires_at < ?1", [now])?;
+        Ok(())
+    })
+}
+
+// 账户接口：不经 JSONP 包装与 API key 校验，避免跨站读取用户数据
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/auth/register", post(register))
+        .route("/auth/login", post(login))
+        .route("/auth/logout", post(logout))
+        .route("/auth/link", get(link_page).post(request_link))
+        .route("/auth/link/confirm", post(use_link))
+        .route("/auth/oidc/login", get(oidc::login))
+        .route("/auth/oidc/callback", get(oidc::callback))
+        .route("/me", get(me))
+        .route("/me/preferences", get(get_preferences).put(put_preferences))
+}
+
+// 32 字节随机数的十六进制
+pub(crate) fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn disabled_response() -> Response {
+    respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("未开启用户账户（ACCOUNTS=1）"))
+}
+
+fn bad_request(msg: &str) -> Response {
+    respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(msg))
+}
+
+fn valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !email.contains(char::is_whitespace)
+}
+
+#[derive(Serialize, Clone)]
+pub struct User {
+    pub id: String,
+    pub email: String,
+    created_at: String,
+}
+
+fn user_row(r: &rusqlite::Row) -> rusqlite::Result<User> {
+    Ok(User {
+        id: r.get(0)?,
+        email: r.get(1)?,
+        created_at: DateTime::from_timestamp(r.get(2)?, 0).unwrap_or_default().to_rfc3339(),
+    })
+}
+
+impl Accounts {
+    // SQLite 调用是同步的，放到阻塞线程池，避免占用异步工作线程
+    async fn run<T: Send + 'static>(&'static self, f: impl FnOnce(&Accounts) -> T + Send + 'static) -> T {
+        request_id::spawn_blocking(move || f(self)).await.expect("accounts task panicked")
+    }
+
+    fn find_user(&self, email: &str) -> rusqlite::Result<Option<(User, Option<String>)>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT id, email, created_at, password_hash FROM users WHERE email = ?1", [email], |r| {
+                Ok((user_row(r)?, r.get(3)?))
+            })
+            .optional()
+    }
+
+    // 不存在时新建（password_hash 为空表示只能用登录链接等方式登录）
+    fn find_or_create(&self, email: &str, password_hash: Option<&str>) -> rusqlite::Result<(User, bool)> {
+        if let Some((user, _)) = self.find_user(email)? {
+            return Ok((user, false));
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO users (id, email, password_hash, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![id, email, password_hash, Utc::now().timestamp()],
+        )?;
+        Ok((self.find_user(email)?.expect("刚插入的用户").0, true))
+    }
+
+    fn create_session(&self, user_id: &str) -> rusqlite::Result<String> {
+        let token = random_token();
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO sessions (token, user_id, expires_at) VALUES (?1, ?2, ?3)",
+            params![token, user_id, Utc::now().timestamp() + self.session_secs],
+        )?;
+        Ok(token)
+    }
+
+    fn session_user(&self, token: &str) -> rusqlite::Result<Option<User>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT u.id, u.email, u.created_at FROM sessions s JOIN users u ON u.id = s.user_id
+                 WHERE s.token = ?1 AND s.expires_at > ?2",
+                params![token, Utc::now().timestamp()],
+                user_row,
+            )
+            .optional()
+    }
+
+    fn cookie(&self, token: &str, max_age: i64) -> HeaderValue {
+        self.named_cookie(COOKIE, token, "Lax", max_age)
+    }
+
+    fn named_cookie(&self, name: &str, value: &str, same_site: &str, max_age: i64) -> HeaderValue {
+        let secure = if self.secure_cookie { "; Secure" } else { "" };
+        let value = format!(
+            "{}={}; Path={}; HttpOnly; SameSite={}; Max-Age={}{}",
+            name,
+            value,
+            base_path::join("/"),
+            same_site,
+            max_age,
+            secure
+        );
+        HeaderValue::from_str(&value).expect("cookie is ascii")
+    }
+
+    // 建立会话后跳转（邮件链接、OIDC 回调）
+    fn login_redirect(&self, user: &User) -> Response {
+        match self.create_session(&user.id) {
+            Ok(token) => {
+                let mut resp = Redirect::to(&base_path::join("/")).into_response();
+                resp.headers_mut().insert(header::SET_COOKIE, self.cookie(&token, self.session_secs));
+                resp
+            }
+            Err(e) => db_error(e),
+        }
+    }
+
+    // 建立会话并以 Set-Cookie 返回
+    fn login_response(&self, status: StatusCode, user: &User) -> Response {
+        match self.create_session(&user.id) {
+            Ok(token) => {
+                let mut resp = respond::json(status, &json!({ "user": user }));
+                resp.headers_mut().insert(header::SET_COOKIE, self.cookie(&token, self.session_secs));
+                resp
+            }
+            Err(e) => db_error(e),
+        }
+    }
+}
+
+// 请求中名为 name 的 Cookie（非空）
+pub(crate) fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(n, _)| *n == name)
+        .map(|(_, value)| value.to_string())
+        .filter(|t| !t.is_empty())
+}
+
+fn session_token(headers: &HeaderMap) -> Option<String> {
+    cookie_value(headers, COOKIE)
+}
+
+// 已登录用户；未开启账户时 404，未登录或会话过期时 401
+pub struct SessionUser(pub User);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for SessionUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let Some(accounts) = ACCOUNTS.get() else {
+            return Err(disabled_response());
+        };
+        let unauthorized = || respond::json(StatusCode::UNAUTHORIZED, &ErrorResp::with_code("LOGIN_REQUIRED", "未登录或会话已过期"));
+        let Some(token) = session_token(&parts.headers) else {
+            return Err(unauthorized());
+        };
+        match accounts.run(move |accounts| accounts.session_user(&token)).await {
+            Ok(Some(user)) => Ok(SessionUser(user)),
+            Ok(None) => Err(unauthorized()),
+            Err(e) => Err(db_error(e)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Credentials {
+    email: String,
+    password: String,
+}
+
+// 哈希较耗 CPU，放到阻塞线程池
+async fn hash_password(password: String) -> anyhow::Result<String> {
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|h| h.to_string())
+            .map_err(|e| anyhow::anyhow!("密码哈希失败: {}", e))
+    })
+    .await?
+}
+
+// hash 为 None（邮箱不存在或没有密码）时校验 DUMMY_HASH，结果总是 false
+async fn verify_password(password: String, hash: Option<String>) -> bool {
+    tokio::task::spawn_blocking(move || {
+        let verified = PasswordHash::new(hash.as_deref().unwrap_or(&DUMMY_HASH))
+            .is_ok_and(|h| Argon2::default().verify_password(password.as_bytes(), &h).is_ok());
+        verified && hash.is_some()
+    })
+    .await
+    .unwrap_or(false)
+}
+
+#[tracing::instrument(skip_all)]
+async fn register(Json(body): Json<Credentials>) -> Response {
+    let Some(accounts) = ACCOUNTS.get() else {
+        return disabled_response();
+    };
+    if !accounts.allow_register {
+        return respond::json(StatusCode::FORBIDDEN, &ErrorResp::new("未开放注册"));
+    }
+    let email = body.email.trim().to_ascii_lowercase();
+    if !valid_email(&email) {
+        return bad_request("邮箱地址无效");
+    }
+    if body.password.chars().count() < MIN_PASSWORD_LEN {
+        return bad_request("密码至少 8 个字符");
+    }
+    let lookup = email.clone();
+    match accounts.run(move |accounts| accounts.find_user(&lookup)).await {
+        Ok(Some(_)) => return respond::json(StatusCode::CONFLICT, &ErrorResp::new("该邮箱已注册")),
+        Ok(None) => {}
+        Err(e) => return db_error(e),
+    }
+    let hash = match hash_password(body.password).await {
+        Ok(h) => h,
+        Err(e) => return respond::json(StatusCode::INTERNAL_SERVER_ERROR, &ErrorResp::new(e.to_string())),
+    };
+    accounts
+        .run(move |accounts| match accounts.find_or_create(&email, Some(&hash)) {
+            Ok((user, true)) => accounts.login_response(StatusCode::CREATED, &user),
+            Ok((_, false)) => respond::json(StatusCode::CONFLICT, &ErrorResp::new("该邮箱已注册")),
+            Err(e) => db_error(e),
+        })
+        .await
+}
+
+#[tracing::instrument(skip_all)]
+async fn login(Json(body): Json<Credentials>) -> Response {
+    let Some(accounts) = ACCOUNTS.get() else {
+        return disabled_response();
+    };
+    let email = body.email.trim().to_ascii_lowercase();
+    let found = match accounts.run(move |accounts| accounts.find_user(&email)).await {
+        Ok(found) => found,
+        Err(e) => return db_error(e),
+    };
+    // 邮箱不存在与密码错误返回相同信息，耗时也相同
+    let (user, hash) = found.map_or((None, None), |(user, hash)| (Some(user), hash));
+    if verify_password(body.password, hash).await {
+        if let Some(user) = user {
+            return accounts.run(move |accounts| accounts.login_response(StatusCode::OK, &user)).await;
+        }
+    }
+    respond::json(StatusCode::UNAUTHORIZED, &ErrorResp::with_code("INVALID_CREDENTIALS", "邮箱或密码错误"))
+}
+
+async fn logout(headers: HeaderMap) -> Response {
+    let Some(accounts) = ACCOUNTS.get() else {
+        return disabled_response();
+    };
+    if let Some(token) = session_token(&headers) {
+        let deleted =
+            accounts.run(|accounts| accounts.conn.lock().unwrap().execute("DELETE FROM sessions WHERE token = ?1", [token]));
+        if let Err(e) = deleted.await {
+            return db_error(e);
+        }
+    }
+    let mut resp = StatusCode::NO_CONTENT.into_response();
+    resp.headers_mut().insert(header::SET_COOKIE, accounts.cookie("", 0));
+    resp
+}
+
+async fn me(SessionUser(user): SessionUser) -> Response {
+    respond::json(StatusCode::OK, &json!({ "user": user }))
+}
+
+#[derive(Deserialize)]
+pub struct LinkBody {
+    email: String,
+}
+
+// 发送登录链接；邮箱不存在时在首次使用链接时建号（受 ACCOUNTS_ALLOW_REGISTER 限制）
+#[tracing::instrument(skip_all)]
+async fn request_link(
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(body): Json<LinkBody>,
+) -> Response {
+    let Some(accounts) = ACCOUNTS.get() else {
+        return disabled_response();
+    };
+    let email = body.email.trim().to_ascii_lowercase();
+    if !valid_email(&email) {
+        return bad_request("邮箱地址无效");
+    }
+    let Some(base) = base_path::public_url() else {
+        return respond::json(StatusCode::SERVICE_UNAVAILABLE, &ErrorResp::new("未配置 PUBLIC_URL，无法生成登录链接"));
+    };
+    // 按邮箱的上限不受伪造转发头影响，防止向同一邮箱反复发信；按 IP 的上限限制单个客户端遍历邮箱
+    let client = client_ip::from_request(&headers, connect_info.map(|c| c.0));
+    let limits = client
+        .map(|ip| (format!("ip:{}", ip), LINKS_PER_CLIENT))
+        .into_iter()
+        .chain([(format!("email:{}", email), LINKS_PER_EMAIL)]);
+    for (key, limit) in limits {
+        let (count, reset) = LINK_REQUESTS.bump(key);
+        if count > limit {
+            let mut resp = respond::json(
+                StatusCode::TOO_MANY_REQUESTS,
+                &ErrorResp::with_code("RATE_LIMITED", "登录链接发送过于频繁，请稍后重试"),
+            );
+            resp.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(reset.as_secs().max(1)));
+            return resp;
+        }
+    }
+    let token = random_token();
+    let link = format!("{}/api/v1/auth/link?token={}", base, token);
+    let stored = email.clone();
+    let inserted = accounts.run(move |accounts| {
+        accounts.conn.lock().unwrap().execute(
+            "INSERT INTO login_links (token, email, expires_at) VALUES (?1, ?2, ?3)",
+            params![token, stored, Utc::now().timestamp() + LINK_TTL_SECS],
+        )
+    });
+    if let Err(e) = inserted.await {
+        return db_error(e);
+    }
+    match send_link(&email, &link).await {
+        Ok(()) => respond::json(StatusCode::ACCEPTED, &json!({ "ok": true })),
+        Err(e) => {
+            warn!("send login link failed: {}", e);
+            respond::json(StatusCode::SERVICE_UNAVAILABLE, &ErrorResp::new(format!("登录链接发送失败: {}", e)))
+        }
+    }
+}
+
+#[cfg(feature = "email")]
+async fn send_link(email: &str, link: &str) -> anyhow::Result<()> {
+    let html = format!(
+        "<!DOCTYPE html><meta charset=\"utf-8\"><p>点击以下链接登录（15 分钟内有效，仅可使用一次）：</p><p><a href=\"{0}\">{0}</a></p>",
+        link
+    );
+    crate::digest::send_mail(email, "登录链接", html).await
+}
+
+#[cfg(not(feature = "email"))]
+async fn send_link(_email: &str, _link: &str) -> anyhow::Result<()> {
+    anyhow::bail!("未启用 `email` feature")
+}
+
+#[derive(Deserialize)]
+pub struct LinkQuery {
+    token: String,
+}
+
+#[derive(Deserialize)]
+pub struct LinkConfirm {
+    token: String,
+    csrf: String,
+}
+
+fn invalid_link() -> Response {
+    respond::json(StatusCode::UNAUTHORIZED, &ErrorResp::new("登录链接无效或已过期"))
+}
+
+// 令牌为 random_token 生成的十六进制串，其余直接拒绝（也无需转义即可写入页面）
+fn valid_token(token: &str) -> bool {
+    !token.is_empty() && token.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+// 邮件中的登录链接：只显示确认页，不消耗链接；页面上的按钮提交到 use_link
+async fn link_page(Query(q): Query<LinkQuery>) -> Response {
+    let Some(accounts) = ACCOUNTS.get() else {
+        return disabled_response();
+    };
+    if !valid_token(&q.token) {
+        return invalid_link();
+    }
+    let page = accounts.run(move |accounts| {
+        let valid = accounts
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT 1 FROM login_links WHERE token = ?1 AND expires_at > ?2",
+                params![q.token, Utc::now().timestamp()],
+                |_| Ok(()),
+            )
+            .optional();
+        match valid {
+            Ok(Some(())) => {}
+            Ok(None) => return invalid_link(),
+            Err(e) => return db_error(e),
+        }
+        let csrf = random_token();
+        let html = format!(
+            "<!DOCTYPE html><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width\"><title>登录</title>\
+             <form method=\"post\" action=\"link/confirm\"><input type=\"hidden\" name=\"token\" value=\"{}\">\
+             <input type=\"hidden\" name=\"csrf\" value=\"{}\">\
+             <p>点击按钮完成登录（链接仅可使用一次）</p><button type=\"submit\">登录</button></form>",
+            q.token, csrf
+        );
+        let mut resp = Html(html).into_response();
+        let cookie = accounts.named_cookie(LINK_CSRF_COOKIE, &csrf, "Strict", LINK_TTL_SECS);
+        resp.headers_mut().insert(header::SET_COOKIE, cookie);
+        resp.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        resp.headers_mut().insert(header::REFERRER_POLICY, HeaderValue::from_static("no-referrer"));
+        resp
+    });
+    page.await
+}
+
+// 确认页提交：校验 CSRF Cookie 后使用登录链接，建立会话后跳转到首页；无论结果如何都清除 CSRF Cookie
+async fn use_link(headers: HeaderMap, Form(q): Form<LinkConfirm>) -> Response {
+    let Some(accounts) = ACCOUNTS.get() else {
+        return disabled_response();
+    };
+    let mut resp = if cookie_value(&headers, LINK_CSRF_COOKIE).as_deref() != Some(q.csrf.as_str()) {
+        respond::json(StatusCode::FORBIDDEN, &ErrorResp::new("登录确认已失效，请重新打开邮件中的链接"))
+    } else {
+        accounts.run(move |accounts| consume_link(accounts, &q.token)).await
+    };
+    resp.headers_mut().append(header::SET_COOKIE, accounts.named_cookie(LINK_CSRF_COOKIE, "", "Strict", 0));
+    resp
+}
+
+fn consume_link(accounts: &Accounts, token: &str) -> Response {
+    let found = accounts
+        .conn
+        .lock()
+        .unwrap()
+        .query_row(
+            "DELETE FROM login_links WHERE token = ?1 AND expires_at > ?2 RETURNING email",
+            params![token, Utc::now().timestamp()],
+            |r| r.get::<_, String>(0),
+        )
+        .optional();
+    let email = match found {
+        Ok(Some(email)) => email,
+        Ok(None) => return invalid_link(),
+        Err(e) => return db_error(e),
+    };
+    let user = match accounts.find_user(&email) {
+        Ok(Some((user, _))) => user,
+        Ok(None) if !accounts.allow_register => {
+            return respond::json(StatusCode::FORBIDDEN, &ErrorResp::new("未开放注册"));
+        }
+        Ok(None) => match accounts.find_or_create(&email, None) {
+            Ok((user, _)) => user,
+            Err(e) => return db_error(e),
+        },
+        Err(e) => return db_error(e),
+    };
+    accounts.login_redirect(&user)
+}
+
+pub fn enabled() -> bool {
+    ACCOUNTS.get().is_some()
+}
+
+// 外部身份（OIDC issuer + sub）登录：已关联的直接登录；否则按邮箱关联已有用户或新建用户。
+// 关联需身份提供方已验证邮箱；设有密码的用户注册时未验证邮箱（可能是他人抢注），须已以该用户登录（headers 中的会话）才关联
+pub async fn login_with_identity(
+    headers: &HeaderMap,
+    issuer: String,
+    subject: String,
+    email: Option<String>,
+    email_verified: bool,
+) -> Response {
+    let Some(accounts) = ACCOUNTS.get() else {
+        return disabled_response();
+    };
+    let session = session_token(headers);
+    let login = accounts.run(move |accounts| {
+        let signed_in = session.and_then(|t| accounts.session_user(&t).ok().flatten());
+        link_identity(accounts, &issuer, &subject, email.as_deref(), email_verified, signed_in.as_ref())
+    });
+    login.await
+}
+
+fn link_identity(
+    accounts: &Accounts,
+    issuer: &str,
+    subject: &str,
+    email: Option<&str>,
+    email_verified: bool,
+    signed_in: Option<&User>,
+) -> Response {
+    let linked = accounts
+        .conn
+        .lock()
+        .unwrap()
+        .query_row(
+            "SELECT u.id, u.email, u.created_at FROM identities i JOIN users u ON u.id = i.user_id
+             WHERE i.issuer = ?1 AND i.subject = ?2",
+            params![issuer, subject],
+            user_row,
+        )
+        .optional();
+    let user = match linked {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            let Some(email) = email.map(|e| e.trim().to_ascii_lowercase()).filter(|e| valid_email(e)) else {
+                return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new("身份提供方未返回有效的 email"));
+            };
+            let user = match accounts.find_user(&email) {
+                Ok(Some((user, _))) if signed_in.is_some_and(|s| s.id == user.id) => user,
+                Ok(Some(_)) if !email_verified => {
+                    return respond::json(
+                        StatusCode::CONFLICT,
+                        &ErrorResp::new("该邮箱已有账户，但身份提供方未验证此邮箱，无法关联"),
+                    );
+                }
+                Ok(Some((_, Some(_)))) => {
+                    return respond::json(
+                        StatusCode::CONFLICT,
+                        &ErrorResp::with_code("LOGIN_TO_LINK", "该邮箱已有设置密码的账户，请先用密码登录，再通过身份提供方登录以关联"),
+                    );
+                }
+                Ok(Some((user, None))) => user,
+                Ok(None) => match accounts.find_or_create(&email, None) {
+                    Ok((user, _)) => user,
+                    Err(e) => return db_error(e),
+                },
+                Err(e) => return db_error(e),
+            };
+            if let Err(e) = accounts.conn.lock().unwrap().execute(
+                "INSERT INTO identities (issuer, subject, user_id) VALUES (?1, ?2, ?3)",
+                params![issuer, subject, user.id],
+            ) {
+                return db_error(e);
+            }
+            user
+        }
+        Err(e) => return db_error(e),
+    };
+    accounts.login_redirect(&user)
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct SavedLocation {
+    name: String,
+    lng: f64,
+    lat: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+enum Units {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+struct Notifications {
+    #[serde(default)]
+    rain: bool,
+    #[serde(default)]
+    alerts: bool,
+    // 每日摘要发送时刻（0-23），None 为不发送
+    #[serde(default)]
+    digest_hour: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Preferences {
+    #[serde(default)]
+    locations: Vec<SavedLocation>,
+    #[serde(default)]
+    units: Units,
+    #[serde(default = "default_language")]
+    language: String,
+    #[serde(default)]
+    notifications: Notifications,
+}
+
+fn default_language() -> String {
+    "zh_CN".into()
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self { locations: Vec::new(), units: Units::default(), language: default_language(), notifications: Notifications::default() }
+    }
+}
+
+impl Preferences {
+    fn validate(&self) -> Result<(), String> {
+        if self.locations.len() > MAX_LOCATIONS {
+            return Err(format!("收藏地点最多 {} 个", MAX_LOCATIONS));
+        }
+        for l in &self.locations {
+            if l.name.trim().is_empty() || !(-180.0..=180.0).contains(&l.lng) || !(-90.0..=90.0).contains(&l.lat) {
+                return Err(format!("收藏地点无效: {}", l.name));
+            }
+        }
+        let lang = &self.language;
+        if lang.is_empty() || lang.len() > 16 || !lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(format!("language 无效: {}", lang));
+        }
+        if self.notifications.digest_hour.is_some_and(|h| h > 23) {
+            return Err("digest_hour 取值 0-23".into());
+        }
+        Ok(())
+    }
+}
+
+fn load_preferences(accounts: &Accounts, user_id: &str) -> rusqlite::Result<Preferences> {
+    let data: Option<String> = accounts
+        .conn
+        .lock()
+        .unwrap()
+        .query_row("SELECT data FROM preferences WHERE user_id = ?1", [user_id], |r| r.get(0))
+        .optional()?;
+    // 旧数据无法解析时按默认值处理，下次保存时覆盖
+    Ok(data.and_then(|d| serde_json::from_str(&d).ok()).unwrap_or_default())
+}
+
+async fn get_preferences(SessionUser(user): SessionUser) -> Response {
+    let accounts = ACCOUNTS.get().expect("SessionUser 已校验");
+    match accounts.run(move |accounts| load_preferences(accounts, &user.id)).await {
+        Ok(prefs) => respond::json(StatusCode::OK, &prefs),
+        Err(e) => db_error(e),
+    }
+}
+
+// 整体替换偏好设置，省略的字段恢复默认
+async fn put_preferences(SessionUser(user): SessionUser, Json(prefs): Json<Preferences>) -> Response {
+    let accounts = ACCOUNTS.get().expect("SessionUser 已校验");
+    if let Err(e) = prefs.validate() {
+        return bad_request(&e);
+    }
+    let data = serde_json::to_string(&prefs).expect("preferences serialize");
+    let saved = accounts.run(move |accounts| {
+        accounts.conn.lock().unwrap().execute(
+            "INSERT INTO preferences (user_id, data, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (user_id) DO UPDATE SET data = excluded.data, updated_at = excluded.updated_at",
+            params![user.id, data, Utc::now().timestamp()],
+        )
+    });
+    match saved.await {
+        Ok(_) => respond::json(StatusCode::OK, &prefs),
+        Err(e) => db_error(e),
+    }
+}
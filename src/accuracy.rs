@@ -0,0 +1,280 @@
+// 预报准确度跟踪：为配置的地点每小时记录实况气温与各日的最高/最低气温预报，
+// 过去的日子以实况采样的最大/最小值为实测最高/最低，与提前 N 天的预报比较得出误差统计（/api/v1/accuracy）
+// 日期按地点时区（同天气接口）；一天的实况采样少于 MIN_SAMPLES 次时不计入统计，避免停机期间的数据拉偏
+// - ACCURACY_LOCATIONS：`名称:经度,纬度`，分号分隔；设置后开启
+// - ACCURACY_DB：数据库路径，默认 DATA_DIR/accuracy.sqlite3
+// - 记录由定时任务 accuracy_record 执行（默认每小时），超过 MAX_DAYS 天的数据同时清理
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use axum::{extract::Query, http::StatusCode, response::Response};
+use chrono::{Days, NaiveDate, Timelike, Utc};
+use once_cell::sync::OnceCell;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::{
+    config::{env_locations, env_nonempty, NamedLocation},
+    db_error, respond, scheduler, typhoon, weather, AppState, ErrorResp,
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS observations (
+        location TEXT NOT NULL,
+        date TEXT NOT NULL,
+        hour INTEGER NOT NULL,
+        temperature REAL NOT NULL,
+        PRIMARY KEY (location, date, hour)
+    );
+    CREATE TABLE IF NOT EXISTS forecasts (
+        location TEXT NOT NULL,
+        issued TEXT NOT NULL,
+        target TEXT NOT NULL,
+        lead_days INTEGER NOT NULL,
+        high REAL NOT NULL,
+        low REAL NOT NULL,
+        PRIMARY KEY (location, issued, target)
+    );
+    CREATE INDEX IF NOT EXISTS forecasts_target ON forecasts (location, target);
+";
+
+// 一天至少 18 个整点有实况才视为完整
+const MIN_SAMPLES: i64 = 18;
+const MAX_DAYS: u64 = 365;
+// 查询坐标与跟踪地点相距不超过该距离（km）时视为同一地点
+const MATCH_KM: f64 = 10.0;
+
+struct Tracker {
+    conn: Mutex<Connection>,
+    locations: Vec<NamedLocation>,
+}
+
+static TRACKER: OnceCell<Tracker> = OnceCell::new();
+
+// 读取配置并注册记录任务；未设置 ACCURACY_LOCATIONS 时不做任何事
+pub fn spawn_from_env(state: AppState) -> anyhow::Result<()> {
+    let Some(locations) = env_locations("ACCURACY_LOCATIONS")? else {
+        return Ok(());
+    };
+    let path = env_nonempty("ACCURACY_DB").unwrap_or_else(|| {
+        let dir = env_nonempty("DATA_DIR").unwrap_or_else(|| "data".into());
+        format!("{}/accuracy.sqlite3", dir.trim_end_matches('/'))
+    });
+    if let Some(dir) = std::path::Path::new(&path).parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let conn = Connection::open(&path).map_err(|e| anyhow::anyhow!("无法打开 {}: {}", path, e))?;
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
+    conn.execute_batch(SCHEMA)?;
+    info!("forecast accuracy tracking {} location(s)", locations.len());
+    let _ = TRACKER.set(Tracker { conn: Mutex::new(conn), locations });
+    scheduler::register("accuracy_record", "0 * * * *", move || {
+        let state = state.clone();
+        async move {
+            let Some(tracker) = TRACKER.get() else { return Ok(()) };
+            let mut failed = 0;
+            for loc in &tracker.locations {
+                if let Err(e) = record_location(tracker, &state, loc).await {
+                    warn!(location = %loc.name, "record forecast accuracy failed: {}", e);
+                    failed += 1;
+                }
+            }
+            if failed > 0 {
+                anyhow::bail!("{} 个地点记录失败", failed);
+            }
+            Ok(())
+        }
+    })
+}
+
+async fn record_location(tracker: &Tracker, state: &AppState, loc: &NamedLocation) -> anyhow::Result<()> {
+    let raw = weather::fetch_raw(state, loc.lng, loc.lat).await?;
+    let now = Utc::now().with_timezone(&weather::location_offset(&raw, loc.lng));
+    let today = now.date_naive();
+    let temperature = raw
+        .pointer("/result/realtime/temperature")
+        .and_then(Value::as_f64)
+        .ok_or_else(|| anyhow::anyhow!("缺少实况气温"))?;
+    // 逐日预报：提前 lead_days 天（0 为当天）的最高/最低气温
+    let forecasts: Vec<(NaiveDate, i64, f64, f64)> = raw
+        .pointer("/result/daily/temperature")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|d| {
+            let target = NaiveDate::parse_from_str(d.get("date")?.as_str()?.get(..10)?, "%Y-%m-%d").ok()?;
+            let lead = (target - today).num_days();
+            (lead >= 0).then_some((target, lead, d.get("max")?.as_f64()?, d.get("min")?.as_f64()?))
+        })
+        .collect();
+
+    let mut conn = tracker.conn.lock().unwrap();
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT OR REPLACE INTO observations (location, date, hour, temperature) VALUES (?1, ?2, ?3, ?4)",
+        params![loc.name, today.to_string(), now.hour(), temperature],
+    )?;
+    // 同一天发布的预报以当天第一次为准：之后的预报会随实况逐渐修正，当天的预报到晚上几乎就是实况
+    for (target, lead, high, low) in forecasts {
+        tx.execute(
+            "INSERT OR IGNORE INTO forecasts (location, issued, target, lead_days, high, low) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![loc.name, today.to_string(), target.to_string(), lead, high, low],
+        )?;
+    }
+    if let Some(cutoff) = today.checked_sub_days(Days::new(MAX_DAYS + 1)) {
+        tx.execute("DELETE FROM observations WHERE location = ?1 AND date < ?2", params![loc.name, cutoff.to_string()])?;
+        tx.execute("DELETE FROM forecasts WHERE location = ?1 AND target < ?2", params![loc.name, cutoff.to_string()])?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct Observed {
+    high: f64,
+    low: f64,
+    samples: i64,
+}
+
+#[derive(Serialize)]
+struct Forecast {
+    lead_days: i64,
+    high: f64,
+    low: f64,
+}
+
+#[derive(Serialize)]
+struct Day {
+    date: String,
+    observed: Observed,
+    forecasts: Vec<Forecast>,
+}
+
+// 平均绝对误差与偏差（预报 - 实测，正值为预报偏高）
+#[derive(Serialize)]
+struct Error {
+    mae: f64,
+    bias: f64,
+}
+
+#[derive(Serialize)]
+struct LeadStats {
+    lead_days: i64,
+    samples: usize,
+    high: Error,
+    low: Error,
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+fn error(diffs: &[f64]) -> Error {
+    let n = diffs.len() as f64;
+    Error {
+        mae: round1(diffs.iter().map(|d| d.abs()).sum::<f64>() / n),
+        bias: round1(diffs.iter().sum::<f64>() / n),
+    }
+}
+
+fn observed(conn: &Connection, location: &str, from: &str, to: &str) -> rusqlite::Result<BTreeMap<String, Observed>> {
+    let mut stmt = conn.prepare(
+        "SELECT date, MAX(temperature), MIN(temperature), COUNT(*) FROM observations
+         WHERE location = ?1 AND date >= ?2 AND date <= ?3 GROUP BY date",
+    )?;
+    let rows = stmt.query_map(params![location, from, to], |r| {
+        Ok((r.get(0)?, Observed { high: r.get(1)?, low: r.get(2)?, samples: r.get(3)? }))
+    })?;
+    rows.collect()
+}
+
+#[derive(Deserialize)]
+pub struct AccuracyQuery {
+    lng: f64,
+    lat: f64,
+    days: Option<u64>,
+}
+
+// 最近 N 天（默认 30）的逐日实测与预报，以及按提前天数汇总的误差
+pub async fn accuracy(Query(q): Query<AccuracyQuery>) -> Response {
+    let Some(tracker) = TRACKER.get() else {
+        return respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("未开启预报准确度跟踪（ACCURACY_LOCATIONS）"));
+    };
+    let nearest = tracker
+        .locations
+        .iter()
+        .map(|l| (l, typhoon::distance_km(q.lng, q.lat, l.lng, l.lat)))
+        .filter(|(_, d)| *d <= MATCH_KM)
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+    let Some((loc, _)) = nearest else {
+        return respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("该地点未在 ACCURACY_LOCATIONS 中跟踪"));
+    };
+    let days = q.days.unwrap_or(30).clamp(1, MAX_DAYS);
+
+    let conn = tracker.conn.lock().unwrap();
+    let result = (|| -> rusqlite::Result<_> {
+        // 以最近一次记录的日期为今天，与记录时使用的地点时区一致
+        let latest: Option<String> =
+            conn.query_row("SELECT MAX(date) FROM observations WHERE location = ?1", [&loc.name], |r| r.get(0))?;
+        let Some(today) = latest.and_then(|d| d.parse::<NaiveDate>().ok()) else {
+            return Ok(None);
+        };
+        let from = today.checked_sub_days(Days::new(days)).unwrap_or(today).to_string();
+        let mut observed = observed(&conn, &loc.name, &from, &today.to_string())?;
+        let today_observed = observed.remove(&today.to_string());
+        let mut stmt = conn.prepare(
+            "SELECT target, lead_days, high, low FROM forecasts
+             WHERE location = ?1 AND target >= ?2 AND target < ?3 ORDER BY lead_days",
+        )?;
+        let mut forecasts: BTreeMap<String, Vec<Forecast>> = BTreeMap::new();
+        let rows = stmt.query_map(params![loc.name, from, today.to_string()], |r| {
+            Ok((r.get::<_, String>(0)?, Forecast { lead_days: r.get(1)?, high: r.get(2)?, low: r.get(3)? }))
+        })?;
+        for row in rows {
+            let (target, forecast) = row?;
+            forecasts.entry(target).or_default().push(forecast);
+        }
+        Ok(Some((today, observed, today_observed, forecasts)))
+    })();
+    drop(conn);
+    let (today, observed, today_observed, mut forecasts) = match result {
+        Ok(Some(r)) => r,
+        Ok(None) => (Default::default(), BTreeMap::new(), None, BTreeMap::new()),
+        Err(e) => return db_error(e),
+    };
+
+    let history: Vec<Day> = observed
+        .into_iter()
+        .rev()
+        .filter(|(_, o)| o.samples >= MIN_SAMPLES)
+        .map(|(date, observed)| {
+            let forecasts = forecasts.remove(&date).unwrap_or_default();
+            Day { date, observed, forecasts }
+        })
+        .collect();
+    let mut diffs: BTreeMap<i64, (Vec<f64>, Vec<f64>)> = BTreeMap::new();
+    for day in &history {
+        for f in &day.forecasts {
+            let (high, low) = diffs.entry(f.lead_days).or_default();
+            high.push(f.high - day.observed.high);
+            low.push(f.low - day.observed.low);
+        }
+    }
+    let leads: Vec<LeadStats> = diffs
+        .into_iter()
+        .map(|(lead_days, (high, low))| LeadStats { lead_days, samples: high.len(), high: error(&high), low: error(&low) })
+        .collect();
+
+    respond::json(
+        StatusCode::OK,
+        &json!({
+            "location": { "name": loc.name, "lng": loc.lng, "lat": loc.lat },
+            "days": days,
+            "leads": leads,
+            "today": today_observed.map(|o| json!({ "date": today.to_string(), "observed": o })),
+            "history": history,
+        }),
+    )
+}
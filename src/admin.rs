@@ -0,0 +1,92 @@
+// 管理接口：/api/v1/admin/* 与 /api/v1/weather/raw，需设置 ADMIN_TOKEN 并以 `Authorization: Bearer <token>` 访问；未设置时一律 404
+
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::{
+    api_keys, config::env_nonempty, doctor, hot_list, respond, scheduler, stats, upstream_cache, weather, webhooks, AppState, ErrorResp, WeatherQuery,
+};
+
+static ADMIN_TOKEN: Lazy<Option<String>> = Lazy::new(|| env_nonempty("ADMIN_TOKEN"));
+
+// 逐字节比较耗时与内容无关，避免通过响应时间猜测 token
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+pub async fn require_token(req: Request, next: Next) -> Response {
+    let Some(expected) = ADMIN_TOKEN.as_deref() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let given = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match given {
+        Some(token) if token_matches(token.trim(), expected) => next.run(req).await,
+        _ => respond::json(StatusCode::UNAUTHORIZED, &ErrorResp::new("未授权")),
+    }
+}
+
+async fn jobs() -> Response {
+    respond::json(StatusCode::OK, &serde_json::json!({ "jobs": scheduler::statuses() }))
+}
+
+#[derive(Deserialize)]
+pub struct RunQuery {
+    // true 时等任务执行完再返回其状态
+    #[serde(default)]
+    wait: bool,
+}
+
+async fn run_job(Path(name): Path<String>, Query(q): Query<RunQuery>) -> Response {
+    match scheduler::run_now(&name) {
+        Some(Some(handle)) if q.wait => match handle.await {
+            Ok(status) => respond::json(StatusCode::OK, &status),
+            Err(e) => respond::json(StatusCode::INTERNAL_SERVER_ERROR, &ErrorResp::new(format!("任务异常退出: {}", e))),
+        },
+        Some(Some(_)) => respond::json(StatusCode::ACCEPTED, &serde_json::json!({ "started": true })),
+        Some(None) => respond::json(StatusCode::CONFLICT, &ErrorResp::new("任务正在运行")),
+        None => respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("任务不存在")),
+    }
+}
+
+// 未经整形的彩云原始响应，便于排查格式化结果；不经上游结果缓存，token 只在服务端拼接
+async fn raw_weather(State(state): State<AppState>, Query(q): Query<WeatherQuery>) -> Response {
+    let mut res = match weather::fetch_raw_uncached(&state, q.lng, q.lat).await {
+        Ok(raw) => respond::json(StatusCode::OK, &raw),
+        Err(e) => respond::json(e.status(), &ErrorResp::new(e.to_string())),
+    };
+    res.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    res
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/weather/raw", get(raw_weather))
+        .route("/admin/jobs", get(jobs))
+        .route("/admin/jobs/:name/run", post(run_job))
+        .route("/admin/stats", get(stats::admin_stats))
+        .route("/admin/diagnostics", get(doctor::admin_diagnostics))
+        .route("/admin/webhooks", get(webhooks::admin_list))
+        .route("/admin/cache", get(upstream_cache::admin_list).delete(upstream_cache::admin_purge))
+        .route("/admin/cache/lookup", get(upstream_cache::admin_lookup))
+        .route("/admin/hot-list", get(hot_list::admin_list))
+        .route("/admin/keys", get(api_keys::list).post(api_keys::create))
+        .route("/admin/keys/:key", get(api_keys::get).patch(api_keys::update).delete(api_keys::delete))
+        .layer(axum::middleware::from_fn(require_token))
+}
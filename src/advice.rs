@@ -0,0 +1,211 @@
+// 生活建议：综合温度、湿度、风、紫外线与 AQI 计算 0-100 的舒适度，并按规则给出跑步/洗车/开窗通风是否适宜
+// 出行与穿衣提示（tips）由 tips 模块按规则文件生成
+// - ADVICE_COMFORT_TEMP：舒适体感温度区间 °C，默认 18,24
+// - ADVICE_COMFORT_HUMIDITY：舒适湿度区间 %，默认 40,60
+// - ADVICE_MAX_AQI：户外活动与开窗的 AQI 上限，默认 100
+// - ADVICE_MAX_WIND：跑步的风速上限 km/h，默认 29
+// - ADVICE_RAIN_PROBABILITY：洗车时今明两天可接受的最大降水概率 %，默认 30
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+use crate::{
+    config::{env_list, env_parse},
+    tips,
+};
+
+#[derive(Clone)]
+struct Thresholds {
+    comfort_temp: (f64, f64),
+    comfort_humidity: (f64, f64),
+    max_aqi: i64,
+    max_wind: f64,
+    rain_probability: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            comfort_temp: (18.0, 24.0),
+            comfort_humidity: (40.0, 60.0),
+            max_aqi: 100,
+            max_wind: 29.0,
+            rain_probability: 30.0,
+        }
+    }
+}
+
+static THRESHOLDS: OnceCell<Thresholds> = OnceCell::new();
+
+fn env_range(key: &str) -> anyhow::Result<Option<(f64, f64)>> {
+    let Some(items) = env_list(key) else {
+        return Ok(None);
+    };
+    let parsed: Vec<f64> = items.iter().filter_map(|s| s.parse().ok()).collect();
+    match parsed.as_slice() {
+        [lo, hi] if lo <= hi => Ok(Some((*lo, *hi))),
+        _ => anyhow::bail!("{} 应为 `下限,上限`，如 18,24", key),
+    }
+}
+
+// 启动时读取阈值；配置无效时直接报错
+pub fn init_from_env() -> anyhow::Result<()> {
+    let d = Thresholds::default();
+    let t = Thresholds {
+        comfort_temp: env_range("ADVICE_COMFORT_TEMP")?.unwrap_or(d.comfort_temp),
+        comfort_humidity: env_range("ADVICE_COMFORT_HUMIDITY")?.unwrap_or(d.comfort_humidity),
+        max_aqi: env_parse("ADVICE_MAX_AQI")?.unwrap_or(d.max_aqi),
+        max_wind: env_parse("ADVICE_MAX_WIND")?.unwrap_or(d.max_wind),
+        rain_probability: env_parse("ADVICE_RAIN_PROBABILITY")?.unwrap_or(d.rain_probability),
+    };
+    let _ = THRESHOLDS.set(t);
+    Ok(())
+}
+
+// 计算所需的输入，v1/v2 各自从响应数据中填充
+pub struct Conditions {
+    pub temperature: f64,
+    pub apparent_temperature: f64,
+    pub humidity: f64,
+    pub wind_speed: f64,
+    pub aqi: Option<i64>,
+    // 彩云紫外线等级 1-5
+    pub uv_level: Option<i64>,
+    pub skycon: String,
+    // 未来两小时是否有雨
+    pub will_rain: bool,
+    // 今明两天最大降水概率 %
+    pub rain_probability: f64,
+    // 紫外线指数 0-11
+    pub uv_index: Option<i64>,
+    // 今天的最低/最高气温 °C、降水概率 % 与降水量 mm
+    pub today_min_temp: Option<f64>,
+    pub today_max_temp: Option<f64>,
+    pub today_rain_probability: Option<f64>,
+    pub today_precipitation: Option<f64>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Advice {
+    pub score: i64,
+    pub level: &'static str,
+    pub suggestions: Vec<Suggestion>,
+    // 按优先级排列的出行与穿衣提示
+    pub tips: Vec<tips::Tip>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Suggestion {
+    pub activity: &'static str,
+    pub name: &'static str,
+    pub suitable: bool,
+    pub reason: String,
+}
+
+// 超出区间的距离
+fn outside(v: f64, (lo, hi): (f64, f64)) -> f64 {
+    if v < lo {
+        lo - v
+    } else if v > hi {
+        v - hi
+    } else {
+        0.0
+    }
+}
+
+fn is_precipitating(skycon: &str) -> bool {
+    ["RAIN", "SNOW", "SLEET", "HAIL"].iter().any(|k| skycon.contains(k))
+}
+
+fn score(c: &Conditions, t: &Thresholds) -> i64 {
+    let penalties = [
+        (outside(c.apparent_temperature, t.comfort_temp) * 3.0).min(40.0),
+        (outside(c.humidity, t.comfort_humidity) * 0.5).min(20.0),
+        (c.wind_speed - 19.0).clamp(0.0, 15.0),
+        c.uv_level.map_or(0.0, |uv| ((uv - 3) * 5).clamp(0, 10) as f64),
+        c.aqi.map_or(0.0, |aqi| ((aqi - 50) as f64 / 5.0).clamp(0.0, 30.0)),
+    ];
+    (100.0 - penalties.iter().sum::<f64>()).round().clamp(0.0, 100.0) as i64
+}
+
+fn level(score: i64) -> &'static str {
+    match score {
+        80.. => "舒适",
+        60..=79 => "较舒适",
+        40..=59 => "一般",
+        _ => "不舒适",
+    }
+}
+
+// 依次检查条件，第一个不满足的作为理由
+fn suggestion(activity: &'static str, name: &'static str, checks: &[(bool, String)], ok: &str) -> Suggestion {
+    match checks.iter().find(|(pass, _)| !pass) {
+        Some((_, reason)) => Suggestion { activity, name, suitable: false, reason: reason.clone() },
+        None => Suggestion { activity, name, suitable: true, reason: ok.to_string() },
+    }
+}
+
+pub fn evaluate(c: &Conditions) -> Advice {
+    let t = THRESHOLDS.get_or_init(Thresholds::default);
+    let raining = is_precipitating(&c.skycon) || c.will_rain;
+    // 缺少 AQI 时不作限制
+    let aqi_ok = !matches!(c.aqi, Some(aqi) if aqi > t.max_aqi);
+    let aqi_reason = format!("空气质量较差（AQI {}）", c.aqi.unwrap_or_default());
+    let temp = c.apparent_temperature;
+    let (lo, hi) = t.comfort_temp;
+
+    let suggestions = vec![
+        suggestion(
+            "running",
+            "跑步",
+            &[
+                (!raining, "有降水".into()),
+                (aqi_ok, aqi_reason.clone()),
+                (c.wind_speed <= t.max_wind, format!("风力较大（{:.0} km/h）", c.wind_speed)),
+                (temp >= lo - 10.0, format!("体感偏冷（{:.0}°C）", temp)),
+                (temp <= hi + 6.0, format!("体感偏热（{:.0}°C），易中暑", temp)),
+            ],
+            "天气条件适宜户外跑步",
+        ),
+        suggestion(
+            "car_washing",
+            "洗车",
+            &[
+                (!raining, "有降水".into()),
+                (
+                    c.rain_probability <= t.rain_probability,
+                    format!("今明两天降水概率 {:.0}%", c.rain_probability),
+                ),
+            ],
+            "今明两天降水可能性小",
+        ),
+        suggestion(
+            "ventilation",
+            "开窗通风",
+            &[
+                (aqi_ok, aqi_reason),
+                (!raining, "有降水，注意防潮".into()),
+                (temp >= lo - 13.0 && temp <= hi + 8.0, format!("室外体感 {:.0}°C，不宜长时间开窗", temp)),
+            ],
+            "空气较好，适合开窗通风",
+        ),
+    ];
+
+    let tips = tips::evaluate(&tips::Metrics {
+        temperature: Some(c.temperature),
+        apparent_temperature: Some(c.apparent_temperature),
+        min_temp: c.today_min_temp,
+        max_temp: c.today_max_temp,
+        humidity: Some(c.humidity),
+        wind_speed: Some(c.wind_speed),
+        aqi: c.aqi.map(|aqi| aqi as f64),
+        uv_index: c.uv_index.map(|uv| uv as f64),
+        rain_probability: c.today_rain_probability,
+        precipitation: c.today_precipitation,
+        will_rain: c.will_rain,
+    });
+
+    let score = score(c, t);
+    Advice { score, level: level(score), suggestions, tips }
+}
+
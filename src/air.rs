@@ -0,0 +1,51 @@
+// 逐小时空气质量：/api/v1/weather/air/hourly 输出未来各小时的 AQI 与 PM2.5，便于呼吸道敏感人群安排出行
+// 数据来自彩云 hourly.air_quality；其他数据源没有逐小时空气质量时返回空数组
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+};
+use serde_json::{json, Value};
+
+use crate::{respond, weather, AppState, ErrorResp, WeatherQuery};
+
+// 中国标准（HJ 633-2012）AQI 分级
+pub fn aqi_category(aqi: i64) -> &'static str {
+    match aqi {
+        ..=50 => "优",
+        51..=100 => "良",
+        101..=150 => "轻度污染",
+        151..=200 => "中度污染",
+        201..=300 => "重度污染",
+        _ => "严重污染",
+    }
+}
+
+fn hourly(raw: &Value) -> Vec<Value> {
+    let air = raw.pointer("/result/hourly/air_quality");
+    let series = |key: &str| air.and_then(|a| a.get(key)).and_then(Value::as_array).map(Vec::as_slice).unwrap_or(&[]);
+    let pm25 = series("pm25");
+    series("aqi")
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let aqi = item.pointer("/value/chn").and_then(Value::as_f64).map(|n| n.round() as i64);
+            json!({
+                "time": item.get("datetime"),
+                "aqi": aqi,
+                "aqi_usa": item.pointer("/value/usa").and_then(Value::as_f64).map(|n| n.round() as i64),
+                "category": aqi.map(aqi_category),
+                "pm25": pm25.get(i).and_then(|v| v.get("value")).and_then(Value::as_f64).map(|n| n.round() as i64),
+            })
+        })
+        .collect()
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn hourly_air(State(state): State<AppState>, Query(q): Query<WeatherQuery>) -> Response {
+    match weather::fetch_raw(&state, q.lng, q.lat).await {
+        Ok(raw) => respond::json(StatusCode::OK, &json!({ "hourly": hourly(&raw) })),
+        Err(e) => respond::json(e.status(), &ErrorResp::new(e.to_string())),
+    }
+}
@@ -0,0 +1,254 @@
+// 预警历史：记录上游返回过的预警（ID、发布区域、等级、发布与首次/最近出现时间）并去重，
+// 供 /api/v1/alerts/history 查询某地近期生效过的预警，推送类功能据此保证同一预警只通知一次
+// 每次实际请求上游后记录与该地点相关的预警（经 alerts 地理过滤），地点按 0.1°（约 10 km）网格归并，查询时包含相邻网格
+// - ALERT_HISTORY：1 开启
+// - ALERT_HISTORY_DB：数据库路径，默认 DATA_DIR/alert_history.sqlite3
+// - ALERT_HISTORY_RETENTION_DAYS：最近出现超过该天数的预警被清理（任务 alert_history_prune，每天一次），默认 30
+
+use std::sync::Mutex;
+
+use axum::{extract::Query, http::StatusCode, response::Response};
+use chrono::{DateTime, Utc};
+use once_cell::sync::OnceCell;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::{
+    alerts,
+    config::{env_flag, env_nonempty, env_parse},
+    db_error, respond, scheduler, ErrorResp,
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS alerts (
+        id TEXT PRIMARY KEY,
+        title TEXT NOT NULL,
+        code TEXT NOT NULL,
+        severity TEXT,
+        region TEXT NOT NULL,
+        adcode TEXT,
+        description TEXT NOT NULL,
+        source TEXT NOT NULL,
+        published_at INTEGER,
+        first_seen INTEGER NOT NULL,
+        last_seen INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS alert_cells (
+        alert_id TEXT NOT NULL REFERENCES alerts(id) ON DELETE CASCADE,
+        lng INTEGER NOT NULL,
+        lat INTEGER NOT NULL,
+        PRIMARY KEY (alert_id, lng, lat)
+    );
+    CREATE TABLE IF NOT EXISTS alert_notifications (
+        alert_id TEXT NOT NULL REFERENCES alerts(id) ON DELETE CASCADE,
+        target TEXT NOT NULL,
+        notified_at INTEGER NOT NULL,
+        PRIMARY KEY (alert_id, target)
+    );
+    CREATE INDEX IF NOT EXISTS alert_cells_location ON alert_cells (lng, lat);
+";
+
+const MAX_DAYS: i64 = 365;
+
+struct History {
+    conn: Mutex<Connection>,
+    retention_days: i64,
+}
+
+static HISTORY: OnceCell<History> = OnceCell::new();
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    if !env_flag("ALERT_HISTORY") {
+        return Ok(());
+    }
+    let retention_days = env_parse::<i64>("ALERT_HISTORY_RETENTION_DAYS")?.unwrap_or(30);
+    if retention_days <= 0 {
+        anyhow::bail!("ALERT_HISTORY_RETENTION_DAYS 应为正整数");
+    }
+    let path = env_nonempty("ALERT_HISTORY_DB").unwrap_or_else(|| {
+        let dir = env_nonempty("DATA_DIR").unwrap_or_else(|| "data".into());
+        format!("{}/alert_history.sqlite3", dir.trim_end_matches('/'))
+    });
+    if let Some(dir) = std::path::Path::new(&path).parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let conn = Connection::open(&path).map_err(|e| anyhow::anyhow!("无法打开 {}: {}", path, e))?;
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL; PRAGMA foreign_keys = ON;")?;
+    conn.execute_batch(SCHEMA)?;
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM alerts", [], |r| r.get(0))?;
+    info!("alert history enabled ({} alert(s) stored)", count);
+    let _ = HISTORY.set(History { conn: Mutex::new(conn), retention_days });
+    scheduler::register("alert_history_prune", "30 3 * * *", || async {
+        let Some(history) = HISTORY.get() else { return Ok(()) };
+        let cutoff = Utc::now().timestamp() - history.retention_days * 86_400;
+        let removed = history.conn.lock().unwrap().execute("DELETE FROM alerts WHERE last_seen < ?1", [cutoff])?;
+        if removed > 0 {
+            info!("pruned {} alert(s) from history", removed);
+        }
+        Ok(())
+    })
+}
+
+fn cell(lng: f64, lat: f64) -> (i64, i64) {
+    ((lng * 10.0).round() as i64, (lat * 10.0).round() as i64)
+}
+
+pub fn enabled() -> bool {
+    HISTORY.get().is_some()
+}
+
+// 预警等级：优先取标题中的颜色，否则按代码后两位（01 蓝 … 04 红）
+fn severity(title: &str, code: &str) -> Option<&'static str> {
+    const COLORS: [&str; 4] = ["蓝色", "黄色", "橙色", "红色"];
+    COLORS.into_iter().find(|c| title.contains(c)).or_else(|| {
+        let level: usize = code.get(code.len().checked_sub(2)?..)?.parse().ok()?;
+        COLORS.get(level.checked_sub(1)?).copied()
+    })
+}
+
+// 同一预警的去重键：上游 alertId，缺失时用标题
+pub fn alert_id(alert: &Value) -> Option<String> {
+    ["alertId", "id", "title"]
+        .iter()
+        .filter_map(|k| alert.get(*k).and_then(Value::as_str))
+        .find(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+// 每次实际请求上游后调用
+pub fn record(lng: f64, lat: f64, raw: &Value) {
+    let Some(history) = HISTORY.get() else {
+        return;
+    };
+    let adcodes = alerts::point_adcodes(raw);
+    let content = raw.pointer("/result/alert/content").and_then(Value::as_array);
+    let items: Vec<&Value> =
+        content.into_iter().flatten().filter(|a| alerts::relevant(a, lng, lat, &adcodes)).collect();
+    if items.is_empty() {
+        return;
+    }
+    let now = Utc::now().timestamp();
+    let (x, y) = cell(lng, lat);
+    let mut conn = history.conn.lock().unwrap();
+    let result = (|| -> rusqlite::Result<()> {
+        let tx = conn.transaction()?;
+        for a in items {
+            let Some(id) = alert_id(a) else { continue };
+            let text = |k: &str| a.get(k).and_then(Value::as_str).unwrap_or("").to_string();
+            let (title, code) = (text("title"), text("code"));
+            let region = Some(text("location")).filter(|s| !s.is_empty()).unwrap_or_else(|| text("city"));
+            tx.execute(
+                "INSERT INTO alerts (id, title, code, severity, region, adcode, description, source, published_at, first_seen, last_seen)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)
+                 ON CONFLICT (id) DO UPDATE SET last_seen = excluded.last_seen, description = excluded.description",
+                params![
+                    id,
+                    title,
+                    code,
+                    severity(&title, &code),
+                    region,
+                    Some(text("adcode")).filter(|s| !s.is_empty()),
+                    text("description"),
+                    text("source"),
+                    a.get("pubtimestamp").and_then(Value::as_f64).map(|t| t as i64),
+                    now,
+                ],
+            )?;
+            tx.execute("INSERT OR IGNORE INTO alert_cells (alert_id, lng, lat) VALUES (?1, ?2, ?3)", params![id, x, y])?;
+        }
+        tx.commit()
+    })();
+    if let Err(e) = result {
+        warn!("record alert history failed: {}", e);
+    }
+}
+
+// 首次针对该目标（如 telegram:<chat id>）通知此预警时返回 true；未开启预警历史时总是 false
+pub fn first_notice(alert_id: &str, target: &str) -> bool {
+    let Some(history) = HISTORY.get() else {
+        return false;
+    };
+    let conn = history.conn.lock().unwrap();
+    // 只对已记录的预警去重，外键保证预警清理后通知记录一并删除
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO alert_notifications (alert_id, target, notified_at)
+         SELECT id, ?2, ?3 FROM alerts WHERE id = ?1",
+        params![alert_id, target, Utc::now().timestamp()],
+    );
+    match inserted {
+        Ok(n) => n > 0,
+        Err(e) => {
+            warn!("record alert notification failed: {}", e);
+            false
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Item {
+    id: String,
+    title: String,
+    code: String,
+    severity: Option<String>,
+    region: String,
+    adcode: Option<String>,
+    description: String,
+    source: String,
+    published_at: Option<String>,
+    first_seen: String,
+    last_seen: String,
+}
+
+fn rfc3339(t: i64) -> String {
+    DateTime::from_timestamp(t, 0).unwrap_or_default().to_rfc3339()
+}
+
+#[derive(Deserialize)]
+pub struct HistoryQuery {
+    lng: f64,
+    lat: f64,
+    days: Option<i64>,
+}
+
+// 该地点附近最近 N 天（默认 7）内出现过的预警，按最近出现时间倒序
+pub async fn history(Query(q): Query<HistoryQuery>) -> Response {
+    let Some(history) = HISTORY.get() else {
+        return respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("未开启预警历史（ALERT_HISTORY=1）"));
+    };
+    let days = q.days.unwrap_or(7).clamp(1, MAX_DAYS);
+    let since = Utc::now().timestamp() - days * 86_400;
+    let (x, y) = cell(q.lng, q.lat);
+    let conn = history.conn.lock().unwrap();
+    let listed = conn
+        .prepare(
+            "SELECT id, title, code, severity, region, adcode, description, source, published_at, first_seen, last_seen
+             FROM alerts WHERE last_seen >= ?1 AND id IN (
+                 SELECT alert_id FROM alert_cells WHERE lng BETWEEN ?2 - 1 AND ?2 + 1 AND lat BETWEEN ?3 - 1 AND ?3 + 1
+             )
+             ORDER BY last_seen DESC, first_seen DESC",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map(params![since, x, y], |r| {
+                Ok(Item {
+                    id: r.get(0)?,
+                    title: r.get(1)?,
+                    code: r.get(2)?,
+                    severity: r.get(3)?,
+                    region: r.get(4)?,
+                    adcode: r.get(5)?,
+                    description: r.get(6)?,
+                    source: r.get(7)?,
+                    published_at: r.get::<_, Option<i64>>(8)?.map(rfc3339),
+                    first_seen: rfc3339(r.get(9)?),
+                    last_seen: rfc3339(r.get(10)?),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+        });
+    match listed {
+        Ok(alerts) => respond::json(StatusCode::OK, &json!({ "days": days, "alerts": alerts })),
+        Err(e) => db_error(e),
+    }
+}
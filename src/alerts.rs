@@ -0,0 +1,79 @@
+// 预警地理过滤：彩云返回请求点所在区域的全部预警（包括同一城市较远的区县），按行政区划与距离只保留与该地点相关的
+// 行政区划取自上游 alert.adcodes（请求点所属的省/市/区县），预警的 adcode 在其中即视为覆盖该点；
+// 距离为预警 latlon（发布区域中心）到请求点的球面距离。两者都开启时满足其一即保留，缺少相应字段的预警不参与该项判断
+// - ALERT_MATCH_ADCODE：1/true 时按行政区划过滤
+// - ALERT_RADIUS_KM：只保留该半径（km）内发布的预警，默认 0 不按距离过滤
+
+use once_cell::sync::OnceCell;
+use serde_json::Value;
+
+use crate::{
+    config::{env_flag, env_parse},
+    typhoon,
+};
+
+struct Filter {
+    match_adcode: bool,
+    radius_km: f64,
+}
+
+static FILTER: OnceCell<Filter> = OnceCell::new();
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    let radius_km = env_parse::<f64>("ALERT_RADIUS_KM")?.unwrap_or(0.0);
+    if radius_km < 0.0 {
+        anyhow::bail!("ALERT_RADIUS_KM 不能为负数");
+    }
+    let _ = FILTER.set(Filter { match_adcode: env_flag("ALERT_MATCH_ADCODE"), radius_km });
+    Ok(())
+}
+
+fn code(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+// 请求点所属的行政区划代码（上游 result.alert.adcodes）
+pub fn point_adcodes(raw: &Value) -> Vec<String> {
+    raw.pointer("/result/alert/adcodes")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|a| a.get("adcode").and_then(code))
+        .collect()
+}
+
+// 单条预警是否与请求点相关；未开启过滤时总是相关
+pub fn relevant(alert: &Value, lng: f64, lat: f64, adcodes: &[String]) -> bool {
+    let Some(filter) = FILTER.get() else {
+        return true;
+    };
+    let by_adcode = (filter.match_adcode && !adcodes.is_empty())
+        .then(|| alert.get("adcode").and_then(code))
+        .flatten()
+        .map(|c| adcodes.contains(&c));
+    // latlon 为 [纬度, 经度]
+    let by_distance = (filter.radius_km > 0.0)
+        .then(|| alert.get("latlon").and_then(Value::as_array))
+        .flatten()
+        .and_then(|p| Some((p.first()?.as_f64()?, p.get(1)?.as_f64()?)))
+        .map(|(alat, alng)| typhoon::distance_km(lng, lat, alng, alat) <= filter.radius_km);
+    match (by_adcode, by_distance) {
+        (None, None) => true,
+        (a, d) => a == Some(true) || d == Some(true),
+    }
+}
+
+// 就地去掉与请求点无关的预警（result.alert.content）
+pub fn retain(raw: &mut Value, lng: f64, lat: f64) {
+    if !FILTER.get().is_some_and(|f| f.match_adcode || f.radius_km > 0.0) {
+        return;
+    }
+    let adcodes = point_adcodes(raw);
+    if let Some(content) = raw.pointer_mut("/result/alert/content").and_then(Value::as_array_mut) {
+        content.retain(|a| relevant(a, lng, lat, &adcodes));
+    }
+}
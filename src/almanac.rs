@@ -0,0 +1,290 @@
+// 农历、节气与月相：按天文算法计算（太阳视黄经取 Meeus 低精度公式，朔望取 Meeus 第 49 章），无需查表
+// 农历规则：冬至所在月为十一月；两个十一月之间有 13 个月时，第一个不含中气的月份为闰月；日期按北京时间（UTC+8）划分
+// 精度为分钟级，节气或朔恰在午夜附近时个别日期可能相差一天
+
+use std::f64::consts::PI;
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+
+const SYNODIC_MONTH: f64 = 29.530588861;
+// J2000 后第一个朔（2000-01-06）的 JDE
+const NEW_MOON_EPOCH: f64 = 2451550.09766;
+// TT 与 UT 之差，近年约 69 秒
+const DELTA_T_DAYS: f64 = 69.0 / 86400.0;
+const BEIJING_OFFSET_DAYS: f64 = 8.0 / 24.0;
+
+// 按太阳视黄经 0°、15°、30°…… 排列，偶数位为中气
+const SOLAR_TERMS: [&str; 24] = [
+    "春分", "清明", "谷雨", "立夏", "小满", "芒种", "夏至", "小暑", "大暑", "立秋", "处暑", "白露",
+    "秋分", "寒露", "霜降", "立冬", "小雪", "大雪", "冬至", "小寒", "大寒", "立春", "雨水", "惊蛰",
+];
+const MONTH_NAMES: [&str; 12] = ["正", "二", "三", "四", "五", "六", "七", "八", "九", "十", "冬", "腊"];
+const STEMS: [&str; 10] = ["甲", "乙", "丙", "丁", "戊", "己", "庚", "辛", "壬", "癸"];
+const BRANCHES: [&str; 12] = ["子", "丑", "寅", "卯", "辰", "巳", "午", "未", "申", "酉", "戌", "亥"];
+const ZODIAC: [&str; 12] = ["鼠", "牛", "虎", "兔", "龙", "蛇", "马", "羊", "猴", "鸡", "狗", "猪"];
+
+#[derive(Serialize, Clone)]
+pub struct Lunar {
+    // 如「乙巳蛇年」
+    pub year: String,
+    // 如「闰六月」
+    pub month: String,
+    pub day: String,
+    pub leap: bool,
+    // 如「六月初九」
+    pub text: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Moon {
+    pub phase: &'static str,
+    // 0-100
+    pub illumination: i64,
+    // 距上次朔的天数
+    pub age: f64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Almanac {
+    pub lunar: Lunar,
+    pub solar_term: Option<&'static str>,
+    pub moon: Moon,
+}
+
+fn sin_deg(d: f64) -> f64 {
+    (d * PI / 180.0).sin()
+}
+
+// 北京时间某日 0 点对应的儒略日（UT）
+fn day_start_jd(date: NaiveDate) -> f64 {
+    date.num_days_from_ce() as f64 + 1721424.5 - BEIJING_OFFSET_DAYS
+}
+
+// 儒略日（TT）→ 北京时间日期
+fn beijing_date(jde: f64) -> NaiveDate {
+    let days = (jde - DELTA_T_DAYS + BEIJING_OFFSET_DAYS - 1721424.5).floor() as i32;
+    NaiveDate::from_num_days_from_ce_opt(days).unwrap_or_default()
+}
+
+// 太阳视黄经（度，0-360）
+fn sun_longitude(jde: f64) -> f64 {
+    let t = (jde - 2451545.0) / 36525.0;
+    let l0 = 280.46646 + 36000.76983 * t + 0.0003032 * t * t;
+    let m = 357.52911 + 35999.05029 * t - 0.0001537 * t * t;
+    let c = (1.914602 - 0.004817 * t - 0.000014 * t * t) * sin_deg(m)
+        + (0.019993 - 0.000101 * t) * sin_deg(2.0 * m)
+        + 0.000289 * sin_deg(3.0 * m);
+    let omega = 125.04 - 1934.136 * t;
+    (l0 + c - 0.00569 - 0.00478 * sin_deg(omega)).rem_euclid(360.0)
+}
+
+// 第 k 个朔（k=0 为 2000-01-06）的 JDE
+fn new_moon(k: i64) -> f64 {
+    let k = k as f64;
+    let t = k / 1236.85;
+    let jde = NEW_MOON_EPOCH + SYNODIC_MONTH * k + 0.00015437 * t * t - 0.00000015 * t * t * t;
+    let e = 1.0 - 0.002516 * t - 0.0000074 * t * t;
+    let m = 2.5534 + 29.1053567 * k - 0.0000014 * t * t;
+    let mp = 201.5643 + 385.81693528 * k + 0.0107582 * t * t + 0.00001238 * t * t * t;
+    let f = 160.7108 + 390.67050284 * k - 0.0016118 * t * t - 0.00000227 * t * t * t;
+    let omega = 124.7746 - 1.56375588 * k + 0.0020672 * t * t;
+    let correction = -0.4072 * sin_deg(mp) + 0.17241 * e * sin_deg(m) + 0.01608 * sin_deg(2.0 * mp)
+        + 0.01039 * sin_deg(2.0 * f)
+        + 0.00739 * e * sin_deg(mp - m)
+        - 0.00514 * e * sin_deg(mp + m)
+        + 0.00208 * e * e * sin_deg(2.0 * m)
+        - 0.00111 * sin_deg(mp - 2.0 * f)
+        - 0.00057 * sin_deg(mp + 2.0 * f)
+        + 0.00056 * e * sin_deg(2.0 * mp + m)
+        - 0.00042 * sin_deg(3.0 * mp)
+        + 0.00042 * e * sin_deg(m + 2.0 * f)
+        + 0.00038 * e * sin_deg(m - 2.0 * f)
+        - 0.00024 * e * sin_deg(2.0 * mp - m)
+        - 0.00017 * sin_deg(omega)
+        - 0.00007 * sin_deg(mp + 2.0 * m)
+        + 0.00004 * sin_deg(2.0 * mp - 2.0 * f)
+        + 0.00004 * sin_deg(3.0 * m)
+        + 0.00003 * sin_deg(mp + m - 2.0 * f)
+        + 0.00003 * sin_deg(2.0 * mp + 2.0 * f)
+        - 0.00003 * sin_deg(mp + m + 2.0 * f)
+        + 0.00003 * sin_deg(mp - m + 2.0 * f)
+        - 0.00002 * sin_deg(mp - m - 2.0 * f)
+        - 0.00002 * sin_deg(3.0 * mp + m)
+        + 0.00002 * sin_deg(4.0 * mp);
+    jde + correction
+}
+
+// 北京时间该日或之前最近一次朔的序号
+fn new_moon_on_or_before(date: NaiveDate) -> i64 {
+    let mut k = ((day_start_jd(date) - NEW_MOON_EPOCH) / SYNODIC_MONTH).floor() as i64;
+    while beijing_date(new_moon(k)) > date {
+        k -= 1;
+    }
+    while beijing_date(new_moon(k + 1)) <= date {
+        k += 1;
+    }
+    k
+}
+
+// 太阳视黄经到达 target 度的 JDE（从 estimate 附近迭代）
+fn solar_term_jde(target: f64, estimate: f64) -> f64 {
+    let mut jd = estimate;
+    for _ in 0..8 {
+        let diff = (target - sun_longitude(jd) + 540.0).rem_euclid(360.0) - 180.0;
+        jd += diff * 365.2422 / 360.0;
+    }
+    jd
+}
+
+// 某公历年冬至所在农历月（十一月）的朔序号
+fn month_eleven(year: i32) -> i64 {
+    let estimate = day_start_jd(NaiveDate::from_ymd_opt(year, 12, 21).unwrap_or_default());
+    new_moon_on_or_before(beijing_date(solar_term_jde(270.0, estimate)))
+}
+
+// 北京时间某日 0 点的太阳视黄经
+fn longitude_at(date: NaiveDate) -> f64 {
+    sun_longitude(day_start_jd(date) + DELTA_T_DAYS)
+}
+
+// 朔 k 开始的农历月是否含中气（黄经为 30° 整数倍）
+fn has_major_term(k: i64) -> bool {
+    let start = longitude_at(beijing_date(new_moon(k)));
+    let end = longitude_at(beijing_date(new_moon(k + 1)));
+    (start / 30.0).floor() != (end / 30.0).floor()
+}
+
+pub fn solar_term(date: NaiveDate) -> Option<&'static str> {
+    let start = longitude_at(date);
+    let end = longitude_at(date.succ_opt()?);
+    let index = (end / 15.0).floor();
+    ((start / 15.0).floor() != index).then(|| SOLAR_TERMS[index as usize % 24])
+}
+
+fn day_name(day: i64) -> String {
+    const DIGITS: [&str; 10] = ["", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+    match day {
+        10 => "初十".into(),
+        20 => "二十".into(),
+        30 => "三十".into(),
+        d if d < 10 => format!("初{}", DIGITS[d as usize]),
+        d if d < 20 => format!("十{}", DIGITS[d as usize - 10]),
+        d => format!("廿{}", DIGITS[d as usize - 20]),
+    }
+}
+
+pub fn lunar(date: NaiveDate) -> Lunar {
+    let k = new_moon_on_or_before(date);
+    // 所在岁（十一月到下一个十一月）
+    let (mut start, mut end) = (month_eleven(date.year() - 1), month_eleven(date.year()));
+    if k >= end {
+        (start, end) = (end, month_eleven(date.year() + 1));
+    }
+
+    // 从十一月起逐月编号；13 个月的岁里第一个无中气月为闰月
+    let mut leap_found = end - start == 12;
+    let mut number = 10; // 0 起：10 即十一月
+    let mut leap = false;
+    for i in start..=k {
+        if i == start {
+            continue;
+        }
+        if !leap_found && !has_major_term(i) {
+            leap_found = true;
+            leap = i == k;
+            continue;
+        }
+        number = (number + 1) % 12;
+        leap = false;
+    }
+
+    let day = (date - beijing_date(new_moon(k))).num_days() + 1;
+    // 十一、十二月若落在公历年初，属于上一农历年
+    let year = if number >= 10 && date.month() <= 6 { date.year() - 1 } else { date.year() };
+    let cycle = (year - 4).rem_euclid(60) as usize;
+    let month = format!("{}{}月", if leap { "闰" } else { "" }, MONTH_NAMES[number]);
+    let day = day_name(day);
+    Lunar {
+        year: format!("{}{}{}年", STEMS[cycle % 10], BRANCHES[cycle % 12], ZODIAC[cycle % 12]),
+        text: format!("{}{}", month, day),
+        month,
+        day,
+        leap,
+    }
+}
+
+// 以北京时间当日中午计算月相
+pub fn moon(date: NaiveDate) -> Moon {
+    let jde = day_start_jd(date) + 0.5 + DELTA_T_DAYS;
+    let mut k = ((jde - NEW_MOON_EPOCH) / SYNODIC_MONTH).floor() as i64;
+    while new_moon(k) > jde {
+        k -= 1;
+    }
+    while new_moon(k + 1) <= jde {
+        k += 1;
+    }
+    let age = jde - new_moon(k);
+    let fraction = age / SYNODIC_MONTH;
+    let phase = match fraction {
+        f if f < 0.0339 => "新月",
+        f if f < 0.2161 => "蛾眉月",
+        f if f < 0.2839 => "上弦月",
+        f if f < 0.4661 => "盈凸月",
+        f if f < 0.5339 => "满月",
+        f if f < 0.7161 => "亏凸月",
+        f if f < 0.7839 => "下弦月",
+        f if f < 0.9661 => "残月",
+        _ => "新月",
+    };
+    Moon {
+        phase,
+        illumination: ((1.0 - (2.0 * PI * fraction).cos()) / 2.0 * 100.0).round() as i64,
+        age: (age * 10.0).round() / 10.0,
+    }
+}
+
+pub fn for_date(date: NaiveDate) -> Almanac {
+    Almanac { lunar: lunar(date), solar_term: solar_term(date), moon: moon(date) }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{lunar, solar_term};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn new_year_starts_the_lunar_year() {
+        let l = lunar(date(2025, 1, 29));
+        assert_eq!((l.year.as_str(), l.text.as_str(), l.leap), ("乙巳蛇年", "正月初一", false));
+        let l = lunar(date(2025, 1, 28));
+        assert_eq!((l.year.as_str(), l.text.as_str()), ("甲辰龙年", "腊月廿九"));
+    }
+
+    #[test]
+    fn leap_months_follow_the_month_without_a_major_term() {
+        for (d, text, year) in [
+            (date(2025, 7, 25), "闰六月初一", "乙巳蛇年"),
+            (date(2023, 3, 22), "闰二月初一", "癸卯兔年"),
+            (date(2033, 12, 22), "闰冬月初一", "癸丑牛年"),
+        ] {
+            let l = lunar(d);
+            assert_eq!((l.text.as_str(), l.year.as_str(), l.leap), (text, year, true), "{}", d);
+        }
+        let l = lunar(date(2025, 6, 25));
+        assert_eq!((l.text.as_str(), l.leap), ("六月初一", false));
+    }
+
+    #[test]
+    fn solar_terms_fall_on_their_beijing_date() {
+        assert_eq!(solar_term(date(2025, 2, 3)), Some("立春"));
+        assert_eq!(solar_term(date(2025, 12, 21)), Some("冬至"));
+        assert_eq!(solar_term(date(2025, 2, 4)), None);
+        assert_eq!(solar_term(date(2025, 12, 22)), None);
+    }
+}
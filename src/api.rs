@@ -0,0 +1,75 @@
+// JSON API 路由：按版本组织，v1 挂在 /api/v1，v2 挂在 /api/v2
+// - /api/* 为 v1 的旧路径别名，响应附带 `Deprecation: true` 与指向 /api/v1 的 `Link: rel="successor-version"`
+// - 新版本在此新增 vN() 并挂到 /api/vN，已发布版本的路由与响应结构保持不变
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue},
+    middleware::{from_fn, from_fn_with_state, Next},
+    response::Response,
+    routing::get,
+    Router,
+};
+
+use crate::{accounts, accuracy, admin, air, alert_history, api_keys, base_path, brief, changes, client_config, extended, feature_flags, ha, influx, jsonp, og_image, radar, share, typhoon, voice, weather_v2, AppState};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .nest("/api/v1", v1())
+        .nest("/api/v2", v2())
+        .nest("/api", v1().layer(from_fn(deprecated)))
+}
+
+// v1 路由（相对路径，挂载前缀由 router() 决定）
+fn v1() -> Router<AppState> {
+    let api = Router::new()
+        .route("/config", get(client_config::config))
+        .route("/weather", get(crate::api_weather))
+        .route("/weather/influx", get(influx::weather))
+        .route("/weather/changes", get(changes::changes))
+        .route("/weather/air/hourly", get(air::hourly_air))
+        .route("/weather/extended", get(extended::extended).layer(from_fn_with_state("extended_forecast", feature_flags::gate)))
+        .route("/weather/brief", get(brief::brief).layer(from_fn_with_state("brief", feature_flags::gate)))
+        .route("/alerts/history", get(alert_history::history))
+        .route("/accuracy", get(accuracy::accuracy))
+        .route("/location/ip", get(crate::api_location_ip))
+        .route("/location/geocode", get(crate::api_location_geocode))
+        .route("/location/search", get(crate::api_location_search))
+        .route("/og-image", get(og_image::og_image))
+        .route("/share", axum::routing::post(share::create))
+        .route("/voice", axum::routing::post(voice::voice).layer(from_fn_with_state("voice", feature_flags::gate)))
+        .route("/share/:code", get(share::resolve))
+        .route("/share/:code/qr.png", get(share::qr))
+        .route("/ha/weather", get(ha::weather))
+        .route("/typhoon", get(typhoon::typhoon))
+        .route("/radar/:z/:x/:y", get(radar::tile));
+    #[cfg(feature = "email")]
+    let api = api
+        .route("/digest/subscribe", axum::routing::post(crate::digest::subscribe))
+        .route("/digest/unsubscribe", get(crate::digest::unsubscribe));
+    // JSON 接口支持 ?callback= JSONP 包装；API key 校验与 JSONP 均不作用于管理接口
+    api.layer(from_fn(jsonp::middleware))
+        .layer(from_fn(api_keys::middleware))
+        .merge(admin::router())
+        .merge(accounts::router())
+}
+
+// v2：强类型、camelCase 的天气响应；其余接口暂沿用 v1
+fn v2() -> Router<AppState> {
+    Router::new()
+        .route("/weather", get(weather_v2::weather))
+        .layer(from_fn(jsonp::middleware))
+        .layer(from_fn(api_keys::middleware))
+}
+
+// 旧路径：嵌套路由内看到的是去掉 /api 前缀后的路径
+async fn deprecated(req: Request, next: Next) -> Response {
+    let successor = format!("<{}/api/v1{}>; rel=\"successor-version\"", base_path::get(), req.uri().path());
+    let mut res = next.run(req).await;
+    let headers = res.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.append(header::LINK, link);
+    }
+    res
+}
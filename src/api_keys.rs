@@ -0,0 +1,493 @@
+// API key：按 key 限制每日请求额度与每分钟速率，key 与用量存于 SQLite，经管理接口 /api/v1/admin/keys 增删改查
+// - API_KEYS：optional 时带 key 的请求计量、不带的照常放行；required 时 /api/* 必须携带 key；未设置不启用
+// - API_KEYS_DB：数据库路径，默认 DATA_DIR/api_keys.sqlite3
+// - API_KEY_TIERS：速率等级，逗号分隔的 `等级=每分钟请求数`（0 为不限），默认 free=60,pro=600,unlimited=0；新建 key 默认 free
+// key 通过请求头 X-API-Key 或查询参数 api_key 传递；管理接口不受限制
+// 请求路径上只读写内存：key 的等级、额度与停用状态在启动时载入并随管理接口同步，用量计数每 10 秒（任务 api_keys_flush）、
+// 管理接口读取前与服务退出时写入数据库
+
+use std::{collections::HashMap, sync::Mutex};
+
+use axum::{
+    extract::{Path, Query, Request},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, Local, NaiveDate};
+use once_cell::sync::OnceCell;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    config::{env_list, env_nonempty},
+    db_error, respond, scheduler, ErrorResp,
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS api_keys (
+        key TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        tier TEXT NOT NULL,
+        daily_quota INTEGER,
+        disabled INTEGER NOT NULL DEFAULT 0,
+        created_at TEXT NOT NULL,
+        last_used_at TEXT
+    );
+    CREATE TABLE IF NOT EXISTS api_key_usage (
+        key TEXT NOT NULL REFERENCES api_keys(key) ON DELETE CASCADE,
+        date TEXT NOT NULL,
+        requests INTEGER NOT NULL,
+        PRIMARY KEY (key, date)
+    );
+";
+
+#[derive(Clone)]
+struct KeyMeta {
+    tier: String,
+    daily_quota: Option<i64>,
+    disabled: bool,
+}
+
+#[derive(Default)]
+struct Usage {
+    date: String,
+    // 今日请求数（含尚未写入数据库的部分）
+    today: HashMap<String, i64>,
+    // 尚未写入数据库的请求数与最近使用时间
+    pending: HashMap<(String, String), i64>,
+    last_used: HashMap<String, String>,
+}
+
+struct Keys {
+    conn: Mutex<Connection>,
+    required: bool,
+    tiers: HashMap<String, u32>,
+    meta: Mutex<HashMap<String, KeyMeta>>,
+    usage: Mutex<Usage>,
+    // key → (分钟序号, 本分钟请求数)
+    windows: Mutex<HashMap<String, (i64, u32)>>,
+}
+
+static KEYS: OnceCell<Keys> = OnceCell::new();
+
+enum Denied {
+    Invalid,
+    Quota,
+    Rate(u64),
+}
+
+fn parse_tiers() -> anyhow::Result<HashMap<String, u32>> {
+    let Some(items) = env_list("API_KEY_TIERS") else {
+        return Ok([("free", 60), ("pro", 600), ("unlimited", 0)].map(|(t, n)| (t.to_string(), n)).into());
+    };
+    let mut tiers = HashMap::new();
+    for item in items {
+        let Some((tier, n)) = item.split_once('=').and_then(|(t, n)| Some((t.trim(), n.trim().parse::<u32>().ok()?)))
+        else {
+            anyhow::bail!("无法解析 API_KEY_TIERS 中的项: {}", item);
+        };
+        tiers.insert(tier.to_string(), n);
+    }
+    if !tiers.contains_key("free") {
+        anyhow::bail!("API_KEY_TIERS 须包含默认等级 free");
+    }
+    Ok(tiers)
+}
+
+// 启动时打开数据库并建表
+pub fn init_from_env() -> anyhow::Result<()> {
+    let required = match env_nonempty("API_KEYS").map(|s| s.to_ascii_lowercase()).as_deref() {
+        None => return Ok(()),
+        Some("optional") => false,
+        Some("required") => true,
+        Some(other) => anyhow::bail!("API_KEYS 应为 optional 或 required，当前为 {}", other),
+    };
+    let path = env_nonempty("API_KEYS_DB").unwrap_or_else(|| {
+        let dir = env_nonempty("DATA_DIR").unwrap_or_else(|| "data".into());
+        format!("{}/api_keys.sqlite3", dir.trim_end_matches('/'))
+    });
+    if let Some(dir) = std::path::Path::new(&path).parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let conn = Connection::open(&path).map_err(|e| anyhow::anyhow!("无法打开 {}: {}", path, e))?;
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL; PRAGMA foreign_keys = ON;")?;
+    conn.execute_batch(SCHEMA)?;
+    let meta = conn
+        .prepare("SELECT key, tier, daily_quota, disabled FROM api_keys")?
+        .query_map([], |r| {
+            Ok((r.get::<_, String>(0)?, KeyMeta { tier: r.get(1)?, daily_quota: r.get(2)?, disabled: r.get(3)? }))
+        })?
+        .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+    let date = today().to_string();
+    let used = conn
+        .prepare("SELECT key, requests FROM api_key_usage WHERE date = ?1")?
+        .query_map([&date], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)))?
+        .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+    info!(required, "api keys enabled ({} key(s))", meta.len());
+    let _ = KEYS.set(Keys {
+        conn: Mutex::new(conn),
+        required,
+        tiers: parse_tiers()?,
+        meta: Mutex::new(meta),
+        usage: Mutex::new(Usage { date, today: used, ..Default::default() }),
+        windows: Mutex::new(HashMap::new()),
+    });
+    scheduler::register("api_keys_flush", "*/10 * * * * *", || async {
+        tokio::task::spawn_blocking(flush).await?;
+        Ok(())
+    })
+}
+
+// 把内存中的用量写入数据库；未启用时什么也不做
+pub fn flush() {
+    if let Some(keys) = KEYS.get() {
+        if let Err(e) = keys.flush() {
+            warn!("api key usage flush failed: {}", e);
+        }
+    }
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+impl Keys {
+    fn admit(&self, key: &str) -> Result<(), Denied> {
+        let Some(KeyMeta { tier, daily_quota, .. }) = self.meta.lock().unwrap().get(key).filter(|m| !m.disabled).cloned()
+        else {
+            return Err(Denied::Invalid);
+        };
+        let date = today().to_string();
+        let mut usage = self.usage.lock().unwrap();
+        if usage.date != date {
+            usage.today.clear();
+            usage.date.clone_from(&date);
+        }
+        let used = usage.today.get(key).copied().unwrap_or(0);
+        if daily_quota.is_some_and(|q| used >= q) {
+            return Err(Denied::Quota);
+        }
+
+        // 固定窗口计数：每个自然分钟重新计数
+        let limit = self.tiers.get(&tier).copied().unwrap_or(0);
+        if limit > 0 {
+            let now = Local::now().timestamp();
+            let mut windows = self.windows.lock().unwrap();
+            let window = windows.entry(key.to_string()).or_insert((now / 60, 0));
+            if window.0 != now / 60 {
+                *window = (now / 60, 0);
+            }
+            if window.1 >= limit {
+                return Err(Denied::Rate((60 - now % 60) as u64));
+            }
+            window.1 += 1;
+        }
+
+        *usage.today.entry(key.to_string()).or_default() += 1;
+        *usage.pending.entry((key.to_string(), date)).or_default() += 1;
+        usage.last_used.insert(key.to_string(), Local::now().to_rfc3339());
+        Ok(())
+    }
+
+    // 写入失败时把计数放回，下次再试；期间被删除的 key 跳过
+    fn flush(&self) -> rusqlite::Result<()> {
+        let (pending, last_used) = {
+            let mut usage = self.usage.lock().unwrap();
+            (std::mem::take(&mut usage.pending), std::mem::take(&mut usage.last_used))
+        };
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.conn.lock().unwrap();
+        let written = conn.transaction().and_then(|tx| {
+            for ((key, date), n) in &pending {
+                tx.execute(
+                    "INSERT INTO api_key_usage (key, date, requests)
+                     SELECT ?1, ?2, ?3 WHERE EXISTS (SELECT 1 FROM api_keys WHERE key = ?1)
+                     ON CONFLICT (key, date) DO UPDATE SET requests = requests + excluded.requests",
+                    params![key, date, n],
+                )?;
+            }
+            for (key, at) in &last_used {
+                tx.execute("UPDATE api_keys SET last_used_at = ?1 WHERE key = ?2", params![at, key])?;
+            }
+            tx.commit()
+        });
+        if written.is_err() {
+            let mut usage = self.usage.lock().unwrap();
+            for (k, n) in pending {
+                *usage.pending.entry(k).or_default() += n;
+            }
+            for (k, at) in last_used {
+                usage.last_used.entry(k).or_insert(at);
+            }
+        }
+        written
+    }
+
+    // 管理接口修改后同步内存中的等级、额度与停用状态
+    fn sync_meta(&self, info: &KeyInfo) {
+        let meta = KeyMeta { tier: info.tier.clone(), daily_quota: info.daily_quota, disabled: info.disabled };
+        self.meta.lock().unwrap().insert(info.key.clone(), meta);
+    }
+}
+
+fn given_key(req: &Request) -> Option<String> {
+    if let Some(key) = req.headers().get("x-api-key").and_then(|v| v.to_str().ok()) {
+        return Some(key.trim().to_string());
+    }
+    #[derive(Deserialize)]
+    struct KeyQuery {
+        api_key: Option<String>,
+    }
+    Query::<KeyQuery>::try_from_uri(req.uri()).ok()?.0.api_key
+}
+
+// 距本地次日零点的秒数
+fn secs_until_tomorrow() -> u64 {
+    let now = Local::now();
+    let midnight = (now.date_naive() + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default();
+    (midnight - now.naive_local()).num_seconds().max(1) as u64
+}
+
+fn too_many(code: &'static str, msg: &str, retry_after: u64) -> Response {
+    let mut res = respond::json(StatusCode::TOO_MANY_REQUESTS, &ErrorResp::with_code(code, msg));
+    res.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    res
+}
+
+pub async fn middleware(req: Request, next: Next) -> Response {
+    let Some(keys) = KEYS.get() else {
+        return next.run(req).await;
+    };
+    let Some(key) = given_key(&req).filter(|k| !k.is_empty()) else {
+        if keys.required {
+            return respond::json(StatusCode::UNAUTHORIZED, &ErrorResp::with_code("API_KEY_REQUIRED", "缺少 API key"));
+        }
+        return next.run(req).await;
+    };
+    match keys.admit(&key) {
+        Ok(()) => next.run(req).await,
+        Err(Denied::Invalid) => {
+            respond::json(StatusCode::UNAUTHORIZED, &ErrorResp::with_code("INVALID_API_KEY", "API key 无效或已停用"))
+        }
+        Err(Denied::Quota) => too_many("QUOTA_EXCEEDED", "今日请求额度已用完", secs_until_tomorrow()),
+        Err(Denied::Rate(retry_after)) => too_many("RATE_LIMITED", "请求过于频繁，请稍后重试", retry_after),
+    }
+}
+
+// ---- 管理接口 ----
+
+#[derive(Serialize)]
+struct KeyInfo {
+    key: String,
+    name: String,
+    tier: String,
+    daily_quota: Option<i64>,
+    disabled: bool,
+    created_at: String,
+    last_used_at: Option<String>,
+    requests_today: i64,
+    requests_total: i64,
+    // 仅详情接口返回：近 30 天每日请求数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    daily: Option<Vec<serde_json::Value>>,
+}
+
+const SELECT_KEYS: &str = "
+    SELECT k.key, k.name, k.tier, k.daily_quota, k.disabled, k.created_at, k.last_used_at,
+           COALESCE(SUM(CASE WHEN u.date = ?1 THEN u.requests END), 0),
+           COALESCE(SUM(u.requests), 0)
+    FROM api_keys k LEFT JOIN api_key_usage u ON u.key = k.key";
+
+fn key_info(r: &rusqlite::Row) -> rusqlite::Result<KeyInfo> {
+    Ok(KeyInfo {
+        key: r.get(0)?,
+        name: r.get(1)?,
+        tier: r.get(2)?,
+        daily_quota: r.get(3)?,
+        disabled: r.get(4)?,
+        created_at: r.get(5)?,
+        last_used_at: r.get(6)?,
+        requests_today: r.get(7)?,
+        requests_total: r.get(8)?,
+        daily: None,
+    })
+}
+
+fn find(conn: &Connection, key: &str) -> rusqlite::Result<Option<KeyInfo>> {
+    conn.query_row(
+        &format!("{} WHERE k.key = ?2 GROUP BY k.key", SELECT_KEYS),
+        params![today().to_string(), key],
+        key_info,
+    )
+    .optional()
+}
+
+fn disabled_response() -> Response {
+    respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("未开启 API key（API_KEYS=optional|required）"))
+}
+
+fn not_found() -> Response {
+    respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("API key 不存在"))
+}
+
+// 等级须在 API_KEY_TIERS 中，额度须为正数
+fn validate(keys: &Keys, tier: Option<&str>, quota: Option<i64>) -> Result<(), String> {
+    if let Some(tier) = tier.filter(|t| !keys.tiers.contains_key(*t)) {
+        let mut known: Vec<&str> = keys.tiers.keys().map(String::as_str).collect();
+        known.sort_unstable();
+        return Err(format!("未知等级 {}（可选 {}）", tier, known.join("、")));
+    }
+    if quota.is_some_and(|q| q <= 0) {
+        return Err("daily_quota 应为正整数".into());
+    }
+    Ok(())
+}
+
+pub async fn list() -> Response {
+    let Some(keys) = KEYS.get() else {
+        return disabled_response();
+    };
+    flush();
+    let conn = keys.conn.lock().unwrap();
+    let listed = conn
+        .prepare(&format!("{} GROUP BY k.key ORDER BY k.created_at", SELECT_KEYS))
+        .and_then(|mut stmt| stmt.query_map([today().to_string()], key_info)?.collect::<rusqlite::Result<Vec<_>>>());
+    match listed {
+        Ok(list) => respond::json(StatusCode::OK, &serde_json::json!({ "keys": list })),
+        Err(e) => db_error(e),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateBody {
+    name: String,
+    tier: Option<String>,
+    daily_quota: Option<i64>,
+}
+
+pub async fn create(Json(body): Json<CreateBody>) -> Response {
+    let Some(keys) = KEYS.get() else {
+        return disabled_response();
+    };
+    let name = body.name.trim();
+    if name.is_empty() {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new("缺少 name"));
+    }
+    let tier = body.tier.as_deref().unwrap_or("free");
+    if let Err(msg) = validate(keys, Some(tier), body.daily_quota) {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(msg));
+    }
+    let key = format!("ck_{}", uuid::Uuid::new_v4().simple());
+    let conn = keys.conn.lock().unwrap();
+    let created = conn
+        .execute(
+            "INSERT INTO api_keys (key, name, tier, daily_quota, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![key, name, tier, body.daily_quota, Local::now().to_rfc3339()],
+        )
+        .and_then(|_| find(&conn, &key));
+    match created {
+        Ok(Some(info)) => {
+            keys.sync_meta(&info);
+            respond::json(StatusCode::CREATED, &info)
+        }
+        Ok(None) => not_found(),
+        Err(e) => db_error(e),
+    }
+}
+
+pub async fn get(Path(key): Path<String>) -> Response {
+    let Some(keys) = KEYS.get() else {
+        return disabled_response();
+    };
+    flush();
+    let conn = keys.conn.lock().unwrap();
+    let since = (today() - Duration::days(29)).to_string();
+    let found = find(&conn, &key).and_then(|info| {
+        let Some(mut info) = info else { return Ok(None) };
+        let mut stmt =
+            conn.prepare("SELECT date, requests FROM api_key_usage WHERE key = ?1 AND date >= ?2 ORDER BY date DESC")?;
+        let daily = stmt
+            .query_map(params![key, since], |r| {
+                Ok(serde_json::json!({ "date": r.get::<_, String>(0)?, "requests": r.get::<_, i64>(1)? }))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        info.daily = Some(daily);
+        Ok(Some(info))
+    });
+    match found {
+        Ok(Some(info)) => respond::json(StatusCode::OK, &info),
+        Ok(None) => not_found(),
+        Err(e) => db_error(e),
+    }
+}
+
+// 省略的字段保持不变；daily_quota 传 null 取消额度限制
+#[derive(Deserialize)]
+pub struct UpdateBody {
+    name: Option<String>,
+    tier: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    daily_quota: Option<Option<i64>>,
+    disabled: Option<bool>,
+}
+
+// 区分字段缺失（None）与显式 null（Some(None)）
+fn present<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<Option<i64>>, D::Error> {
+    Option::<i64>::deserialize(d).map(Some)
+}
+
+pub async fn update(Path(key): Path<String>, Json(body): Json<UpdateBody>) -> Response {
+    let Some(keys) = KEYS.get() else {
+        return disabled_response();
+    };
+    if let Err(msg) = validate(keys, body.tier.as_deref(), body.daily_quota.flatten()) {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(msg));
+    }
+    let name = body.name.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    flush();
+    let conn = keys.conn.lock().unwrap();
+    let updated = conn
+        .execute(
+            "UPDATE api_keys SET
+                name = COALESCE(?2, name),
+                tier = COALESCE(?3, tier),
+                daily_quota = CASE WHEN ?4 THEN ?5 ELSE daily_quota END,
+                disabled = COALESCE(?6, disabled)
+             WHERE key = ?1",
+            params![key, name, body.tier, body.daily_quota.is_some(), body.daily_quota.flatten(), body.disabled],
+        )
+        .and_then(|_| find(&conn, &key));
+    match updated {
+        Ok(Some(info)) => {
+            keys.sync_meta(&info);
+            respond::json(StatusCode::OK, &info)
+        }
+        Ok(None) => not_found(),
+        Err(e) => db_error(e),
+    }
+}
+
+pub async fn delete(Path(key): Path<String>) -> Response {
+    let Some(keys) = KEYS.get() else {
+        return disabled_response();
+    };
+    keys.meta.lock().unwrap().remove(&key);
+    {
+        let mut usage = keys.usage.lock().unwrap();
+        usage.today.remove(&key);
+        usage.pending.retain(|(k, _), _| *k != key);
+        usage.last_used.remove(&key);
+    }
+    keys.windows.lock().unwrap().remove(&key);
+    let deleted = keys.conn.lock().unwrap().execute("DELETE FROM api_keys WHERE key = ?1", [&key]);
+    match deleted {
+        Ok(0) => not_found(),
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => db_error(e),
+    }
+}
@@ -0,0 +1,134 @@
+// 静态资源：启动时为 static/ 下的文件计算内容指纹，首页引用改写为带指纹的文件名（如 styles.1a2b3c4d.css）
+// 带指纹的路径长期缓存（immutable），其余静态文件每次协商（no-cache，配合 Last-Modified）
+// 存在 .br / .gz 预压缩文件且客户端支持时直接发送，不再即时压缩
+// - ASSET_FINGERPRINT=0：关闭指纹（本地改静态文件时免重启）
+
+use std::{collections::HashMap, path::Path};
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue, StatusCode, Uri},
+    middleware::{from_fn, Next},
+    response::Response,
+    Router,
+};
+use once_cell::sync::OnceCell;
+use tower_http::services::ServeDir;
+use tracing::info;
+
+use crate::config::env_nonempty;
+
+const STATIC_DIR: &str = "static";
+// 首页与 Service Worker 直接读取的文件
+const REQUIRED: &[&str] = &["index.html", "script.js", "styles.css", "sw.js"];
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+struct Manifest {
+    // 原始相对路径 → 带指纹的相对路径
+    hashed: HashMap<String, String>,
+    // 反向查找
+    original: HashMap<String, String>,
+}
+
+static MANIFEST: OnceCell<Manifest> = OnceCell::new();
+
+// FNV-1a：结果与编译器版本无关，多实例部署时指纹一致
+fn fingerprint(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf29ce484222325u64, |h, b| (h ^ *b as u64).wrapping_mul(0x100000001b3));
+    format!("{:08x}", hash >> 32)
+}
+
+fn hashed_name(rel: &str, hash: &str) -> String {
+    let (dir, file) = rel.rsplit_once('/').map_or(("", rel), |(d, f)| (d, f));
+    let file = match file.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}.{}.{}", stem, hash, ext),
+        _ => format!("{}.{}", file, hash),
+    };
+    if dir.is_empty() {
+        file
+    } else {
+        format!("{}/{}", dir, file)
+    }
+}
+
+fn walk(dir: &Path, prefix: &str, out: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let rel = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+        if entry.file_type()?.is_dir() {
+            walk(&entry.path(), &rel, out)?;
+        } else if !rel.ends_with(".br") && !rel.ends_with(".gz") && rel != "index.html" {
+            out.push(rel);
+        }
+    }
+    Ok(())
+}
+
+// 启动时计算指纹；static/ 不存在时跳过
+pub fn init_from_env() -> anyhow::Result<()> {
+    if env_nonempty("ASSET_FINGERPRINT").as_deref() == Some("0") || !Path::new(STATIC_DIR).is_dir() {
+        return Ok(());
+    }
+    let mut files = Vec::new();
+    walk(Path::new(STATIC_DIR), "", &mut files)?;
+    let mut manifest = Manifest { hashed: HashMap::new(), original: HashMap::new() };
+    for rel in files {
+        let hashed = hashed_name(&rel, &fingerprint(&std::fs::read(Path::new(STATIC_DIR).join(&rel))?));
+        manifest.original.insert(hashed.clone(), rel.clone());
+        manifest.hashed.insert(rel, hashed);
+    }
+    info!("fingerprinted {} static asset(s)", manifest.hashed.len());
+    let _ = MANIFEST.set(manifest);
+    Ok(())
+}
+
+// 自检：页面所需的静态文件是否齐全（相对工作目录查找）
+pub fn check() -> Result<String, String> {
+    let missing: Vec<&str> =
+        REQUIRED.iter().copied().filter(|f| !Path::new(STATIC_DIR).join(f).is_file()).collect();
+    if !missing.is_empty() {
+        return Err(format!("{}/ 下缺少 {}（工作目录是否正确？）", STATIC_DIR, missing.join(", ")));
+    }
+    Ok(match MANIFEST.get() {
+        Some(m) => format!("{} 个文件已计算指纹", m.hashed.len()),
+        None => "齐全（未计算指纹）".into(),
+    })
+}
+
+// 将 HTML 中带引号的 "/static/<文件>" 引用替换为带指纹的路径
+pub fn rewrite(html: &str) -> String {
+    let Some(manifest) = MANIFEST.get() else {
+        return html.to_string();
+    };
+    manifest.hashed.iter().fold(html.to_string(), |html, (rel, hashed)| {
+        html.replace(&format!("\"/static/{}\"", rel), &format!("\"/static/{}\"", hashed))
+    })
+}
+
+// 嵌套在 /static 下，看到的是去掉前缀后的路径
+async fn cache_headers(mut req: Request, next: Next) -> Response {
+    let original = MANIFEST
+        .get()
+        .and_then(|m| m.original.get(req.uri().path().trim_start_matches('/')))
+        .and_then(|rel| {
+            let query = req.uri().query().map(|q| format!("?{}", q)).unwrap_or_default();
+            format!("/{}{}", rel, query).parse::<Uri>().ok()
+        });
+    let immutable = original.is_some();
+    if let Some(uri) = original {
+        *req.uri_mut() = uri;
+    }
+    let mut res = next.run(req).await;
+    if res.status() == StatusCode::OK || res.status() == StatusCode::NOT_MODIFIED {
+        let value = if immutable { IMMUTABLE } else { "no-cache" };
+        res.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static(value));
+    }
+    res
+}
+
+pub fn service() -> Router {
+    Router::new()
+        .fallback_service(ServeDir::new(STATIC_DIR).precompressed_br().precompressed_gzip())
+        .layer(from_fn(cache_headers))
+}
@@ -0,0 +1,114 @@
+// 路径前缀部署：反向代理把 https://example.com/weather/ 转发到本服务（不去掉前缀）时，设置 BASE_PATH=/weather
+// - BASE_PATH：以 / 开头的路径前缀，末尾的 / 可省略；未设置时挂在根路径
+// 前缀在路由之前去掉，内部各路由与中间件看到的路径与未设前缀时相同；前缀以外的请求返回 404，/weather 跳转到 /weather/
+// 页面中的绝对地址（href/src 等属性）、跳转、站点对外地址（分享短链接、登录链接、卡片图片）与 manifest 均加上前缀
+
+use std::convert::Infallible;
+
+use axum::{
+    extract::Request,
+    http::{StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+    Router,
+};
+use once_cell::sync::OnceCell;
+use tower::ServiceExt;
+use tracing::info;
+
+use crate::config::env_nonempty;
+
+static BASE: OnceCell<String> = OnceCell::new();
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    let Some(raw) = env_nonempty("BASE_PATH") else {
+        return Ok(());
+    };
+    let base = raw.trim_end_matches('/');
+    if base.is_empty() {
+        return Ok(());
+    }
+    if !base.starts_with('/') || base.contains(['?', '#', ' ']) || base.contains("//") {
+        anyhow::bail!("BASE_PATH 应为以 / 开头的路径，如 /weather：{}", raw);
+    }
+    info!("serving under base path {}", base);
+    let _ = BASE.set(base.to_string());
+    Ok(())
+}
+
+// 未设置时为空串，可直接拼在绝对路径前
+pub fn get() -> &'static str {
+    BASE.get().map(String::as_str).unwrap_or("")
+}
+
+// 给站内绝对路径加上前缀
+pub fn join(path: &str) -> String {
+    format!("{}{}", get(), path)
+}
+
+// 站点对外地址（PUBLIC_URL）：未包含前缀时补上，已包含时原样返回
+pub fn public_url() -> Option<String> {
+    let url = env_nonempty("PUBLIC_URL")?;
+    let url = url.trim_end_matches('/');
+    Some(if url.ends_with(get()) { url.to_string() } else { format!("{}{}", url, get()) })
+}
+
+// HTML 中以 / 开头的 href/src/action/content 属性加上前缀（// 开头的协议相对地址除外）
+pub fn rewrite_html(html: &str) -> String {
+    let base = get();
+    if base.is_empty() {
+        return html.to_string();
+    }
+    ["href", "src", "action", "content"].iter().fold(html.to_string(), |html, attr| {
+        let from = format!("{}=\"/", attr);
+        let mut out = String::with_capacity(html.len());
+        let mut rest = html.as_str();
+        while let Some(i) = rest.find(&from) {
+            let (head, tail) = rest.split_at(i + from.len());
+            out.push_str(&head[..head.len() - 1]);
+            if !tail.starts_with('/') {
+                out.push_str(base);
+            }
+            out.push('/');
+            rest = tail;
+        }
+        out.push_str(rest);
+        out
+    })
+}
+
+fn strip(uri: &Uri, base: &str) -> Option<Uri> {
+    let rest = uri.path().strip_prefix(base)?;
+    if !rest.starts_with('/') {
+        return None;
+    }
+    let path_and_query = match uri.query() {
+        Some(q) => format!("{}?{}", rest, q),
+        None => rest.to_string(),
+    };
+    path_and_query.parse().ok()
+}
+
+// 去掉前缀后交给 app 路由；须在所有中间件之外，使内部看到的路径不含前缀
+pub fn nest(app: Router) -> Router {
+    let base = get();
+    if base.is_empty() {
+        return app;
+    }
+    let service = tower::service_fn(move |mut req: Request| {
+        let app = app.clone();
+        async move {
+            match strip(req.uri(), base) {
+                Some(uri) => {
+                    *req.uri_mut() = uri;
+                    app.oneshot(req).await
+                }
+                None if req.uri().path() == base => {
+                    let query = req.uri().query().map(|q| format!("?{}", q)).unwrap_or_default();
+                    Ok::<Response, Infallible>(Redirect::permanent(&format!("{}/{}", base, query)).into_response())
+                }
+                None => Ok((StatusCode::NOT_FOUND, "页面不存在").into_response()),
+            }
+        }
+    });
+    Router::new().fallback_service(service)
+}
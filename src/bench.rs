@@ -0,0 +1,291 @@
+// 压测：`caiyun-weather-rust bench` 按录制的请求列表回放流量到运行中的服务，按路由统计吞吐与延迟分位数
+// 目标服务以 MOCK_MODE=1 启动时不访问上游，测得的是整形、缓存与路由本身的开销，便于发现性能回退
+// 请求列表每行一个请求：`[方法] 路径?查询`（方法缺省为 GET），空行与 # 开头的行忽略，默认 bench/traffic.txt
+// 选项：
+//   --url <地址>        目标服务，默认 http://127.0.0.1:$PORT（PORT 默认 8000）
+//   --traffic <文件>    请求列表
+//   --concurrency <n>   并发连接数，默认 16
+//   --duration <秒>     持续时间，默认 10，可带小数
+//   --max-p99-ms <毫秒> 任一路由 p99 超过该值或有请求失败时以非零状态退出，用于 CI
+// `caiyun-weather-rust bench parse` 不需要运行中的服务，比较上游响应体的 JSON 解析耗时：
+//   --file <文件>       彩云响应样本，默认 fixtures/clear_day.json
+//   --iterations <n>    每种解析器的次数，默认 2000
+// 以 --features simd-json 构建时同时测 serde_json 与 simd-json，否则只测 serde_json
+
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use reqwest::{Client, Method};
+use serde_json::Value;
+
+struct Options {
+    url: String,
+    traffic: String,
+    concurrency: usize,
+    duration: Duration,
+    max_p99_ms: Option<f64>,
+}
+
+fn parse_args(args: &[String]) -> anyhow::Result<Options> {
+    let port = std::env::var("PORT").ok().and_then(|s| s.parse::<u16>().ok()).unwrap_or(8000);
+    let mut opts = Options {
+        url: format!("http://127.0.0.1:{}", port),
+        traffic: "bench/traffic.txt".into(),
+        concurrency: 16,
+        duration: Duration::from_secs(10),
+        max_p99_ms: None,
+    };
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| anyhow::anyhow!("{} 缺少参数值", flag));
+        match flag.as_str() {
+            "--url" => opts.url = value()?.trim_end_matches('/').to_string(),
+            "--traffic" => opts.traffic = value()?.clone(),
+            "--concurrency" => {
+                opts.concurrency = value()?.parse().map_err(|_| anyhow::anyhow!("--concurrency 应为正整数"))?;
+                if opts.concurrency == 0 {
+                    anyhow::bail!("--concurrency 应为正整数");
+                }
+            }
+            "--duration" => {
+                let secs: f64 = value()?.parse().map_err(|_| anyhow::anyhow!("--duration 应为秒数"))?;
+                if !(secs.is_finite() && secs > 0.0) {
+                    anyhow::bail!("--duration 应为正数秒");
+                }
+                opts.duration = Duration::from_secs_f64(secs);
+            }
+            "--max-p99-ms" => {
+                opts.max_p99_ms = Some(value()?.parse().map_err(|_| anyhow::anyhow!("--max-p99-ms 应为毫秒数"))?);
+            }
+            other => anyhow::bail!("未知参数: {}", other),
+        }
+    }
+    Ok(opts)
+}
+
+struct Request {
+    method: Method,
+    path: String,
+    // 统计分组：不含查询参数的路径
+    route: String,
+}
+
+fn load_traffic(path: &str) -> anyhow::Result<Vec<Request>> {
+    let text = std::fs::read_to_string(path).map_err(|e| anyhow::anyhow!("无法读取 {}: {}", path, e))?;
+    let mut requests = Vec::new();
+    for (no, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (method, target) = match line.split_once(char::is_whitespace) {
+            Some((m, t)) => {
+                let method = Method::from_bytes(m.as_bytes())
+                    .map_err(|_| anyhow::anyhow!("{} 第 {} 行: 无效的方法 {}", path, no + 1, m))?;
+                (method, t.trim())
+            }
+            None => (Method::GET, line),
+        };
+        if !target.starts_with('/') {
+            anyhow::bail!("{} 第 {} 行: 路径应以 / 开头", path, no + 1);
+        }
+        let route = target.split('?').next().unwrap_or(target).to_string();
+        requests.push(Request { method, path: target.to_string(), route });
+    }
+    if requests.is_empty() {
+        anyhow::bail!("{} 中没有请求", path);
+    }
+    Ok(requests)
+}
+
+#[derive(Default)]
+struct RouteStats {
+    latencies: Vec<Duration>,
+    errors: u64,
+}
+
+// 就近取分位数（已排序）
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn ms(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+async fn worker(
+    client: Client,
+    base: Arc<String>,
+    requests: Arc<Vec<Request>>,
+    start: usize,
+    deadline: Instant,
+) -> BTreeMap<String, RouteStats> {
+    let mut stats: BTreeMap<String, RouteStats> = BTreeMap::new();
+    let mut i = start;
+    while Instant::now() < deadline {
+        let req = &requests[i % requests.len()];
+        i += 1;
+        let started = Instant::now();
+        let ok = match client.request(req.method.clone(), format!("{}{}", base, req.path)).send().await {
+            // 读完响应体，计入完整的响应时间
+            Ok(resp) => {
+                let status = resp.status();
+                resp.bytes().await.is_ok() && !status.is_client_error() && !status.is_server_error()
+            }
+            Err(_) => false,
+        };
+        let entry = stats.entry(req.route.clone()).or_default();
+        entry.latencies.push(started.elapsed());
+        if !ok {
+            entry.errors += 1;
+        }
+    }
+    stats
+}
+
+type Parser = fn(Vec<u8>) -> anyhow::Result<Value>;
+
+fn parsers() -> Vec<(&'static str, Parser)> {
+    vec![
+        ("serde_json", |body| Ok(serde_json::from_slice(&body)?)),
+        #[cfg(feature = "simd-json")]
+        ("simd-json", |mut body| Ok(simd_json::serde::from_slice(&mut body)?)),
+    ]
+}
+
+fn parse_bench(args: &[String]) -> anyhow::Result<()> {
+    let mut file = "fixtures/clear_day.json".to_string();
+    let mut iterations: u32 = 2000;
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| anyhow::anyhow!("{} 缺少参数值", flag));
+        match flag.as_str() {
+            "--file" => file = value()?.clone(),
+            "--iterations" => {
+                iterations = value()?.parse().map_err(|_| anyhow::anyhow!("--iterations 应为正整数"))?;
+                if iterations == 0 {
+                    anyhow::bail!("--iterations 应为正整数");
+                }
+            }
+            other => anyhow::bail!("未知参数: {}", other),
+        }
+    }
+    let body = std::fs::read(&file).map_err(|e| anyhow::anyhow!("无法读取 {}: {}", file, e))?;
+    println!("解析 {}（{} 字节）各 {} 次", file, body.len(), iterations);
+    println!("{:<12} {:>12} {:>9} {:>9}", "parser", "total ms", "us/op", "MB/s");
+    let mut expected = None;
+    for (name, parse) in parsers() {
+        // 先解析一次：预热，并确认各解析器结果一致
+        let value = parse(body.clone()).map_err(|e| anyhow::anyhow!("{} 解析失败: {}", name, e))?;
+        if expected.get_or_insert_with(|| value.clone()) != &value {
+            anyhow::bail!("{} 的解析结果与 serde_json 不一致", name);
+        }
+        // 每次都复制一份输入（simd-json 会改写缓冲区），与上游读出响应体后的情形相同
+        let started = Instant::now();
+        for _ in 0..iterations {
+            std::hint::black_box(parse(std::hint::black_box(body.clone()))?);
+        }
+        let elapsed = started.elapsed();
+        let per_op = elapsed.as_secs_f64() / iterations as f64;
+        println!(
+            "{:<12} {:>12.1} {:>9.1} {:>9.1}",
+            name,
+            ms(elapsed),
+            per_op * 1e6,
+            body.len() as f64 / per_op / 1e6
+        );
+    }
+    Ok(())
+}
+
+pub async fn run(args: &[String]) -> anyhow::Result<()> {
+    if args.first().map(String::as_str) == Some("parse") {
+        return parse_bench(&args[1..]);
+    }
+    let opts = parse_args(args)?;
+    let requests = Arc::new(load_traffic(&opts.traffic)?);
+    let client = Client::builder()
+        .no_proxy()
+        .redirect(reqwest::redirect::Policy::none())
+        .pool_max_idle_per_host(opts.concurrency)
+        .build()?;
+    client
+        .get(format!("{}/", opts.url))
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("无法连接 {}: {}", opts.url, e.without_url()))?;
+    println!(
+        "回放 {} 条请求到 {}，并发 {}，持续 {:.1} 秒",
+        requests.len(),
+        opts.url,
+        opts.concurrency,
+        opts.duration.as_secs_f64()
+    );
+
+    let base = Arc::new(opts.url.clone());
+    let started = Instant::now();
+    let deadline = started + opts.duration;
+    let workers: Vec<_> = (0..opts.concurrency)
+        .map(|n| {
+            // 各连接从列表的不同位置开始，避免同时请求同一条
+            let offset = n * requests.len() / opts.concurrency;
+            tokio::spawn(worker(client.clone(), base.clone(), requests.clone(), offset, deadline))
+        })
+        .collect();
+    let mut routes: BTreeMap<String, RouteStats> = BTreeMap::new();
+    for w in workers {
+        for (route, s) in w.await? {
+            let entry = routes.entry(route).or_default();
+            entry.latencies.extend(s.latencies);
+            entry.errors += s.errors;
+        }
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let mut all = RouteStats::default();
+    println!(
+        "{:<32} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>7}",
+        "route", "requests", "rps", "p50 ms", "p90 ms", "p99 ms", "max ms", "errors"
+    );
+    let print = |route: &str, s: &mut RouteStats| {
+        s.latencies.sort_unstable();
+        let p99 = ms(percentile(&s.latencies, 99.0));
+        println!(
+            "{:<32} {:>9} {:>9.1} {:>9.2} {:>9.2} {:>9.2} {:>9.2} {:>7}",
+            route,
+            s.latencies.len(),
+            s.latencies.len() as f64 / elapsed,
+            ms(percentile(&s.latencies, 50.0)),
+            ms(percentile(&s.latencies, 90.0)),
+            p99,
+            ms(s.latencies.last().copied().unwrap_or_default()),
+            s.errors
+        );
+        p99
+    };
+    let mut failed = Vec::new();
+    for (route, s) in routes.iter_mut() {
+        all.latencies.extend_from_slice(&s.latencies);
+        all.errors += s.errors;
+        let p99 = print(route, s);
+        if opts.max_p99_ms.is_some_and(|max| p99 > max) {
+            failed.push(format!("{} p99 {:.2} ms", route, p99));
+        }
+        if s.errors > 0 {
+            failed.push(format!("{} 失败 {} 次", route, s.errors));
+        }
+    }
+    print("total", &mut all);
+
+    if opts.max_p99_ms.is_some() && !failed.is_empty() {
+        anyhow::bail!("未达标: {}", failed.join("; "));
+    }
+    Ok(())
+}
@@ -0,0 +1,34 @@
+// 请求体大小限制：声明的 Content-Length 超限时直接返回 413 与结构化错误，未声明长度（chunked）的在读取时截断
+// - MAX_REQUEST_BODY_BYTES：默认 65536；接口只接收小型 JSON（订阅、管理、GraphQL 查询）
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{config::env_parse, respond, ErrorResp};
+
+const DEFAULT_MAX: usize = 64 * 1024;
+
+pub fn from_env() -> anyhow::Result<usize> {
+    let max = env_parse::<usize>("MAX_REQUEST_BODY_BYTES")?.unwrap_or(DEFAULT_MAX);
+    if max == 0 {
+        anyhow::bail!("MAX_REQUEST_BODY_BYTES 应大于 0");
+    }
+    Ok(max)
+}
+
+pub async fn middleware(State(max): State<usize>, req: Request, next: Next) -> Response {
+    let declared = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if declared.is_some_and(|n| n > max as u64) {
+        let msg = format!("请求体超过 {} 字节", max);
+        return respond::json(StatusCode::PAYLOAD_TOO_LARGE, &ErrorResp::with_code("PAYLOAD_TOO_LARGE", msg));
+    }
+    next.run(req).await
+}
@@ -0,0 +1,214 @@
+// 一句话天气播报：/api/v1/weather/brief?lng=&lat=&lang=zh_CN&day=0 把整形后的天气数据套入模板，
+// 生成“今天多云转小雨，最高 29°C，空气质量优，出门记得带伞”这样的一段话，供聊天机器人与语音助手直接朗读
+// 各语言的模板、天气描述、AQI 等级与日期用词见 brief.toml；lang 可写 zh、zh-CN、en-US 等，未知语言返回 400
+// day 为 0-2（今天、明天、后天）；语音助手适配（voice.rs）复用此处的生成逻辑
+// - BRIEF_TEMPLATES_FILE：自定义模板文件（格式同 brief.toml），设置后替换内置模板；启动时校验模板语法
+
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{config::env_nonempty, location, respond, weather, AppState, ErrorResp};
+
+const BUILTIN: &str = include_str!("../brief.toml");
+pub const DEFAULT_LANG: &str = "zh_CN";
+// 当天降水概率达到该值时提醒带伞
+const UMBRELLA_PROBABILITY: i64 = 50;
+// v1 逐日预报共 3 天
+pub const MAX_DAY: usize = 2;
+
+#[derive(Deserialize)]
+struct RawLang {
+    template: String,
+    aqi: [String; 6],
+    days: [String; MAX_DAY + 1],
+    #[serde(default)]
+    skycon: BTreeMap<String, String>,
+}
+
+struct Lang {
+    aqi: [String; 6],
+    days: [String; MAX_DAY + 1],
+    skycon: BTreeMap<String, String>,
+}
+
+struct Templates {
+    env: minijinja::Environment<'static>,
+    langs: BTreeMap<String, Lang>,
+}
+
+fn parse(text: &str) -> anyhow::Result<Templates> {
+    let raw: BTreeMap<String, RawLang> = toml::from_str(text)?;
+    if raw.is_empty() {
+        anyhow::bail!("至少需要一种语言");
+    }
+    let mut env = minijinja::Environment::new();
+    let mut langs = BTreeMap::new();
+    for (name, lang) in raw {
+        env.add_template_owned(name.clone(), lang.template)
+            .map_err(|e| anyhow::anyhow!("{} 的模板无效: {}", name, e))?;
+        langs.insert(name, Lang { aqi: lang.aqi, days: lang.days, skycon: lang.skycon });
+    }
+    Ok(Templates { env, langs })
+}
+
+static BUILTIN_TEMPLATES: Lazy<Templates> = Lazy::new(|| parse(BUILTIN).expect("brief.toml 格式错误"));
+static CUSTOM_TEMPLATES: OnceCell<Templates> = OnceCell::new();
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    if let Some(path) = env_nonempty("BRIEF_TEMPLATES_FILE") {
+        let text = std::fs::read_to_string(&path).map_err(|e| anyhow::anyhow!("无法读取 {}: {}", path, e))?;
+        let templates = parse(&text).map_err(|e| anyhow::anyhow!("{} 无效: {}", path, e))?;
+        let _ = CUSTOM_TEMPLATES.set(templates);
+    }
+    Lazy::force(&BUILTIN_TEMPLATES);
+    Ok(())
+}
+
+fn templates() -> &'static Templates {
+    CUSTOM_TEMPLATES.get().unwrap_or(&BUILTIN_TEMPLATES)
+}
+
+// zh、zh-CN、zh_cn 均对应 zh_CN：先按全名（不分大小写、- 与 _ 等同）匹配，再按语言部分匹配；未指定时为 zh_CN
+pub fn resolve_lang(requested: Option<&str>) -> Result<&'static str, String> {
+    let langs = &templates().langs;
+    let requested = requested.unwrap_or(DEFAULT_LANG);
+    let normalized = requested.trim().replace('-', "_");
+    let primary = |s: &str| s.split('_').next().unwrap_or_default().to_ascii_lowercase();
+    langs
+        .keys()
+        .find(|name| name.eq_ignore_ascii_case(&normalized))
+        .or_else(|| langs.keys().find(|name| primary(name) == primary(&normalized)))
+        .map(String::as_str)
+        .ok_or_else(|| {
+            let available = langs.keys().cloned().collect::<Vec<_>>().join(", ");
+            format!("不支持的语言 {}（可选：{}）", requested, available)
+        })
+}
+
+// 0-2，或 today/tomorrow/day_after_tomorrow、今天/明天/后天
+pub fn parse_day(raw: &str) -> Option<usize> {
+    let day = match raw.trim().to_ascii_lowercase().as_str() {
+        "today" | "今天" => 0,
+        "tomorrow" | "明天" => 1,
+        "day_after_tomorrow" | "后天" => 2,
+        other => other.parse().ok()?,
+    };
+    (day <= MAX_DAY).then_some(day)
+}
+
+// 第 day 天（0 为今天）的逐日预报；上游首日不是今天时按“今天”所在位置偏移
+pub fn forecast_day(data: &weather::WeatherData, day: usize) -> Option<&Value> {
+    let days = data.daily.as_array()?;
+    let today = days.iter().position(|d| d["relativeDay"] == "今天").unwrap_or(0);
+    days.get(today + day)
+}
+
+fn aqi_index(aqi: i64) -> usize {
+    match aqi {
+        ..=50 => 0,
+        51..=100 => 1,
+        101..=150 => 2,
+        151..=200 => 3,
+        201..=300 => 4,
+        _ => 5,
+    }
+}
+
+fn describe(lang: &Lang, code: &str) -> String {
+    let code = code.replace("_NIGHT", "_DAY");
+    lang.skycon.get(&code).cloned().unwrap_or_else(|| weather::skycon_desc(&code).to_string())
+}
+
+// 实况类信息（当前降水、AQI、预警）只用于播报今天
+fn context(data: &weather::WeatherData, city: &str, lang: &Lang, day: usize) -> Value {
+    let forecast = forecast_day(data, day).unwrap_or(&Value::Null);
+    let is_today = day == 0;
+    let sky = |key: &str| forecast.get(key).and_then(Value::as_str).map(|code| describe(lang, code));
+    let current_sky = data.current.skycon.as_str().unwrap_or("CLEAR_DAY");
+    let rain_probability = forecast.pointer("/precipitation/probability").and_then(Value::as_i64).unwrap_or(0);
+    let raining = is_today && ["RAIN", "SNOW", "SLEET", "HAIL"].iter().any(|k| current_sky.contains(k));
+    let aqi = data.current.air_quality.pointer("/aqi/chn").and_then(Value::as_i64).filter(|_| is_today);
+    let alerts: Vec<&str> = data
+        .alerts
+        .as_array()
+        .filter(|_| is_today)
+        .map(|list| list.iter().filter_map(|a| a.get("title")?.as_str()).collect())
+        .unwrap_or_default();
+    let sky_all = sky("skycon").unwrap_or_else(|| describe(lang, current_sky));
+    json!({
+        "city": city,
+        "day": lang.days[day.min(MAX_DAY)],
+        "sky_day": sky("skycon_day").unwrap_or_else(|| sky_all.clone()),
+        "sky_night": sky("skycon_night").unwrap_or_else(|| sky_all.clone()),
+        "sky": sky_all,
+        "temperature": data.current.temperature,
+        "min_temp": forecast.get("min_temp"),
+        "max_temp": forecast.get("max_temp"),
+        "aqi": aqi,
+        "aqi_level": aqi.map(|v| lang.aqi[aqi_index(v)].clone()),
+        "rain_probability": rain_probability,
+        "umbrella": raining || rain_probability >= UMBRELLA_PROBABILITY,
+        "alerts": alerts,
+    })
+}
+
+// 可用的语言，按名称排序
+pub fn languages() -> Vec<&'static str> {
+    templates().langs.keys().map(String::as_str).collect()
+}
+
+// 该语言中第 day 天的说法，如“明天”、tomorrow
+pub fn day_label(lang: &str, day: usize) -> &'static str {
+    templates().langs.get(lang).map_or("", |l| l.days[day.min(MAX_DAY)].as_str())
+}
+
+// 按 resolve_lang 得到的语言生成播报
+pub fn compose(data: &weather::WeatherData, city: &str, lang: &str, day: usize) -> Result<String, minijinja::Error> {
+    let templates = templates();
+    let ctx = context(data, city, &templates.langs[lang], day);
+    let text = templates.env.get_template(lang)?.render(&ctx)?;
+    Ok(text.trim().to_string())
+}
+
+#[derive(Deserialize)]
+pub struct BriefQuery {
+    lng: f64,
+    lat: f64,
+    lang: Option<String>,
+    day: Option<String>,
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn brief(State(state): State<AppState>, Query(q): Query<BriefQuery>) -> Response {
+    let lang = match resolve_lang(q.lang.as_deref()) {
+        Ok(lang) => lang,
+        Err(e) => return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(e)),
+    };
+    let Some(day) = q.day.as_deref().map_or(Some(0), parse_day) else {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(format!("day 应在 0 到 {} 之间", MAX_DAY)));
+    };
+    let (data, city) = tokio::join!(
+        weather::fetch(&state, q.lng, q.lat),
+        location::reverse_geocode(&state, q.lat, q.lng)
+    );
+    let data = match data {
+        Ok(d) => d,
+        Err(e) => return respond::json(e.status(), &ErrorResp::new(e.to_string())),
+    };
+    if forecast_day(&data, day).is_none() {
+        return respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("数据源没有该日的预报"));
+    }
+    let city = if city == location::UNKNOWN_PLACE { "" } else { city.as_str() };
+    match compose(&data, city, lang, day) {
+        Ok(text) => respond::json(StatusCode::OK, &json!({ "lang": lang, "day": day, "text": text })),
+        Err(e) => respond::json(StatusCode::INTERNAL_SERVER_ERROR, &ErrorResp::new(format!("生成播报失败: {}", e))),
+    }
+}
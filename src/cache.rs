@@ -0,0 +1,83 @@
+// 进程内 TTL 缓存：过期时间从写入算起；容量有限，超出时先清理过期项，再淘汰最久未使用（写入或读取）的一项
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+struct Entry<V> {
+    inserted: Instant,
+    used: Instant,
+    value: V,
+}
+
+pub struct TtlCache<K, V> {
+    entries: Mutex<HashMap<K, Entry<V>>>,
+    ttl: Duration,
+    capacity: usize,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> TtlCache<K, V> {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            ttl,
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get_mut(key) {
+            Some(entry) if entry.inserted.elapsed() < self.ttl => {
+                entry.used = Instant::now();
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    // 取出并删除，已过期的视为不存在
+    pub fn take(&self, key: &K) -> Option<V> {
+        let entry = self.entries.lock().unwrap().remove(key)?;
+        (entry.inserted.elapsed() < self.ttl).then_some(entry.value)
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            let ttl = self.ttl;
+            entries.retain(|_, entry| entry.inserted.elapsed() < ttl);
+            if entries.len() >= self.capacity {
+                if let Some(lru) = entries.iter().min_by_key(|(_, entry)| entry.used).map(|(k, _)| k.clone()) {
+                    entries.remove(&lru);
+                }
+            }
+        }
+        let now = Instant::now();
+        entries.insert(key, Entry { inserted: now, used: now, value });
+    }
+}
+
+// 计数器：窗口从第一次计数起算，到期后重新计数
+impl<K: Eq + Hash + Clone> TtlCache<K, u32> {
+    // 计入一次，返回本窗口内的次数（含本次）与窗口剩余时间
+    pub fn bump(&self, key: K) -> (u32, Duration) {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            if let Some(entry) = entries.get_mut(&key).filter(|e| e.inserted.elapsed() < self.ttl) {
+                entry.value += 1;
+                entry.used = Instant::now();
+                return (entry.value, self.ttl.saturating_sub(entry.inserted.elapsed()));
+            }
+        }
+        self.insert(key, 1);
+        (1, self.ttl)
+    }
+}
@@ -0,0 +1,145 @@
+// 天气变化检测：/api/v1/weather/changes 比较某地点最新一次与上一次上游数据，列出值得提醒的变化，供通知类功能与客户端使用
+// 变化包括气温突变、天气现象改变、新发布的预警、一小时内开始降水；地点按经纬度两位小数（约 1 km）归并
+// 快照在每次实际请求上游后更新，开启上游结果缓存时命中缓存不产生新快照
+// - CHANGES_TEMPERATURE_DELTA：气温变化达到该值（°C）视为突变，默认 3
+
+use std::{collections::HashMap, sync::Mutex};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use once_cell::sync::{Lazy, OnceCell};
+use serde_json::{json, Value};
+
+use crate::{alerts, config::env_parse, respond, weather, AppState, ErrorResp, WeatherQuery};
+
+const MAX_LOCATIONS: usize = 10_000;
+// 与 nowcast 的有雨阈值一致（mm/h）
+const RAIN_THRESHOLD: f64 = 0.03;
+
+static TEMPERATURE_DELTA: OnceCell<f64> = OnceCell::new();
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    let delta = env_parse::<f64>("CHANGES_TEMPERATURE_DELTA")?.unwrap_or(3.0);
+    if delta <= 0.0 {
+        anyhow::bail!("CHANGES_TEMPERATURE_DELTA 应大于 0");
+    }
+    let _ = TEMPERATURE_DELTA.set(delta);
+    Ok(())
+}
+
+#[derive(Clone)]
+struct Snapshot {
+    at: DateTime<Utc>,
+    temperature: Option<f64>,
+    skycon: String,
+    // (alertId, 标题)
+    alerts: Vec<(String, String)>,
+    // 一小时内开始降水的分钟数，0 表示正在下
+    rain_in: Option<usize>,
+}
+
+type Key = (i32, i32);
+// (上一次, 最新一次)
+type Pair = (Option<Snapshot>, Snapshot);
+
+static SNAPSHOTS: Lazy<Mutex<HashMap<Key, Pair>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn key(lng: f64, lat: f64) -> Key {
+    ((lng * 100.0).round() as i32, (lat * 100.0).round() as i32)
+}
+
+fn snapshot(raw: &Value, lng: f64, lat: f64) -> Option<Snapshot> {
+    let result = raw.get("result")?;
+    let realtime = result.get("realtime")?;
+    let adcodes = alerts::point_adcodes(raw);
+    let alerts = result
+        .pointer("/alert/content")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|a| alerts::relevant(a, lng, lat, &adcodes))
+        .map(|a| {
+            let text = |k: &str| a.get(k).and_then(Value::as_str).unwrap_or("").to_string();
+            let title = text("title");
+            let id = Some(text("alertId")).filter(|id| !id.is_empty()).unwrap_or_else(|| title.clone());
+            (id, title)
+        })
+        .collect();
+    let rain_in = result
+        .pointer("/minutely/precipitation_2h")
+        .and_then(Value::as_array)
+        .and_then(|series| series.iter().take(60).position(|v| v.as_f64().unwrap_or(0.0) > RAIN_THRESHOLD));
+    Some(Snapshot {
+        at: Utc::now(),
+        temperature: realtime.get("temperature").and_then(Value::as_f64),
+        skycon: realtime.get("skycon").and_then(Value::as_str).unwrap_or("").to_string(),
+        alerts,
+        rain_in,
+    })
+}
+
+// 每次实际请求上游后调用：原最新快照变为上一次
+pub fn record(lng: f64, lat: f64, raw: &Value) {
+    let Some(latest) = snapshot(raw, lng, lat) else {
+        return;
+    };
+    let mut snapshots = SNAPSHOTS.lock().unwrap();
+    let key = key(lng, lat);
+    if snapshots.len() >= MAX_LOCATIONS && !snapshots.contains_key(&key) {
+        if let Some(oldest) = snapshots.iter().min_by_key(|(_, (_, s))| s.at).map(|(k, _)| *k) {
+            snapshots.remove(&oldest);
+        }
+    }
+    let previous = snapshots.remove(&key).map(|(_, s)| s);
+    snapshots.insert(key, (previous, latest));
+}
+
+fn diff(previous: &Snapshot, latest: &Snapshot) -> Vec<Value> {
+    let mut changes = Vec::new();
+    if let (Some(from), Some(to)) = (previous.temperature, latest.temperature) {
+        let delta = to - from;
+        if delta.abs() >= TEMPERATURE_DELTA.get().copied().unwrap_or(3.0) {
+            changes.push(json!({ "type": "temperature", "from": from, "to": to, "delta": (delta * 10.0).round() / 10.0 }));
+        }
+    }
+    if previous.skycon != latest.skycon && !latest.skycon.is_empty() {
+        changes.push(json!({
+            "type": "skycon",
+            "from": previous.skycon,
+            "to": latest.skycon,
+            "description": weather::skycon_desc(&latest.skycon),
+        }));
+    }
+    for (id, title) in &latest.alerts {
+        if !previous.alerts.iter().any(|(prev, _)| prev == id) {
+            changes.push(json!({ "type": "alert", "id": id, "title": title }));
+        }
+    }
+    if let (None, Some(minutes)) = (previous.rain_in, latest.rain_in) {
+        changes.push(json!({ "type": "rain_starting", "in_minutes": minutes }));
+    }
+    changes
+}
+
+// 先按常规流程获取天气（必要时请求上游并更新快照），再比较该地点的两次快照；尚无上一次时 changes 为空
+#[tracing::instrument(skip_all)]
+pub async fn changes(State(state): State<AppState>, Query(q): Query<WeatherQuery>) -> Response {
+    if let Err(e) = weather::fetch_raw(&state, q.lng, q.lat).await {
+        return respond::json(e.status(), &ErrorResp::new(e.to_string()));
+    }
+    let entry = SNAPSHOTS.lock().unwrap().get(&key(q.lng, q.lat)).cloned();
+    let body = match entry {
+        Some((previous, latest)) => json!({
+            "updated_at": latest.at.to_rfc3339(),
+            "previous_at": previous.as_ref().map(|p| p.at.to_rfc3339()),
+            "changes": previous.map(|p| diff(&p, &latest)).unwrap_or_default(),
+        }),
+        // 模拟模式不经上游，不记录快照
+        None => json!({ "updated_at": null, "previous_at": null, "changes": [] }),
+    };
+    respond::json(StatusCode::OK, &body)
+}
@@ -0,0 +1,88 @@
+// 故障注入：按概率让上游调用变慢或失败，用于在本地与测试中验证数据源回退、对冲、路由超时与过期缓存兜底等行为
+// 只在 upstream::send 中生效，命中失败时不发出请求，直接返回 503 响应（与上游故障走相同的错误处理）
+// - CHAOS_FAILURE_PERCENT：失败概率 0-100，设置任一 CHAOS_*_PERCENT 即开启
+// - CHAOS_DELAY_PERCENT / CHAOS_DELAY_MS：延迟概率与发出请求前等待的时长（默认 2000 毫秒，不计入上游超时）
+// - CHAOS_UPSTREAMS：只对这些上游注入（逗号分隔，如 caiyun,amap），默认全部
+// 仅用于开发与测试：release 构建中需再设置 CHAOS_ALLOW_RELEASE=1，否则忽略以上配置
+
+use std::time::Duration;
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum::http::StatusCode;
+use once_cell::sync::OnceCell;
+use reqwest::Response;
+use tracing::{debug, warn};
+
+use crate::config::{env_flag, env_list, env_parse};
+
+struct Chaos {
+    failure: f64,
+    delay: f64,
+    delay_for: Duration,
+    upstreams: Option<Vec<String>>,
+}
+
+static CHAOS: OnceCell<Chaos> = OnceCell::new();
+
+fn percent(key: &str) -> anyhow::Result<Option<f64>> {
+    match env_parse::<f64>(key)? {
+        Some(p) if (0.0..=100.0).contains(&p) => Ok(Some(p)),
+        Some(_) => anyhow::bail!("{} 应在 0 到 100 之间", key),
+        None => Ok(None),
+    }
+}
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    let (failure, delay) = (percent("CHAOS_FAILURE_PERCENT")?, percent("CHAOS_DELAY_PERCENT")?);
+    if failure.is_none() && delay.is_none() {
+        return Ok(());
+    }
+    if !cfg!(debug_assertions) && !env_flag("CHAOS_ALLOW_RELEASE") {
+        warn!("release 构建忽略故障注入配置（CHAOS_*），如确需开启请设置 CHAOS_ALLOW_RELEASE=1");
+        return Ok(());
+    }
+    let chaos = Chaos {
+        failure: failure.unwrap_or(0.0),
+        delay: delay.unwrap_or(0.0),
+        delay_for: Duration::from_millis(env_parse("CHAOS_DELAY_MS")?.unwrap_or(2000)),
+        upstreams: env_list("CHAOS_UPSTREAMS"),
+    };
+    warn!(
+        failure_percent = chaos.failure,
+        delay_percent = chaos.delay,
+        delay_ms = chaos.delay_for.as_millis() as u64,
+        upstreams = ?chaos.upstreams,
+        "fault injection enabled, upstream calls will randomly fail or slow down"
+    );
+    let _ = CHAOS.set(chaos);
+    Ok(())
+}
+
+pub fn enabled() -> bool {
+    CHAOS.get().is_some()
+}
+
+fn roll(percent: f64) -> bool {
+    percent > 0.0 && (OsRng.next_u32() as f64 / u32::MAX as f64) * 100.0 < percent
+}
+
+// 由 upstream::send 在发出请求前调用；返回 Some 时以该响应代替真实请求
+pub async fn inject(upstream: &str) -> Option<Response> {
+    let chaos = CHAOS.get()?;
+    if chaos.upstreams.as_ref().is_some_and(|list| !list.iter().any(|u| u == upstream)) {
+        return None;
+    }
+    if roll(chaos.delay) {
+        debug!(upstream, "chaos: delaying upstream call");
+        tokio::time::sleep(chaos.delay_for).await;
+    }
+    if !roll(chaos.failure) {
+        return None;
+    }
+    debug!(upstream, "chaos: failing upstream call");
+    let resp = axum::http::Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .body("chaos: injected failure")
+        .ok()?;
+    Some(Response::from(resp))
+}
@@ -0,0 +1,51 @@
+// 前端运行时配置：/api/v1/config 返回静态页面需要的非敏感设置，页面启动时读取，script.js 中不再写死默认城市与单位
+// 含默认城市（路径前缀租户下为该租户的城市）、播报可用语言、各数值的单位、已开启的功能、功能开关（FEATURE_FLAGS）与 BASE_PATH；不含任何密钥
+
+use axum::{extract::State, http::StatusCode, response::Response};
+use serde_json::json;
+
+use crate::{accounts, alert_history, base_path, brief, demo, feature_flags, icons, mock, oidc, provider, respond, share, tenant, AppState};
+
+#[tracing::instrument(skip_all)]
+pub async fn config(State(state): State<AppState>) -> Response {
+    let city = tenant::default_city();
+    #[cfg(feature = "email")]
+    let digest = crate::digest::enabled();
+    #[cfg(not(feature = "email"))]
+    let digest = false;
+    respond::json(
+        StatusCode::OK,
+        &json!({
+            "default_city": { "name": city.name, "lng": city.lng, "lat": city.lat },
+            "languages": brief::languages(),
+            "default_language": brief::DEFAULT_LANG,
+            // API 统一输出公制
+            "units": {
+                "system": "metric",
+                "temperature": "°C",
+                "wind_speed": "km/h",
+                "visibility": "km",
+                "pressure": "hPa",
+                "precipitation": "mm",
+                "concentration": "μg/m³",
+            },
+            "icon_set": icons::select(None).unwrap_or_default(),
+            "base_path": base_path::get(),
+            "features": {
+                // 数据源提供预警（仅 Open-Meteo 时为 false）
+                "alerts": provider::has_alerts(),
+                "alert_history": alert_history::enabled(),
+                // 15 天预报需彩云 token（模拟与演示模式除外）
+                "extended_forecast": feature_flags::enabled("extended_forecast")
+                    && (tenant::caiyun_token(&state).is_some() || mock::enabled() || demo::enabled()),
+                "share": share::enabled(),
+                "accounts": accounts::enabled(),
+                "oidc_login": oidc::enabled(),
+                "email_digest": digest,
+                "mock": mock::enabled(),
+                "demo": demo::enabled(),
+            },
+            "flags": feature_flags::snapshot(),
+        }),
+    )
+}
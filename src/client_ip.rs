@@ -0,0 +1,108 @@
+// 客户端 IP：依次取 CF-Connecting-IP、X-Forwarded-For 首项、X-Real-IP，均没有时用连接的对端地址（直连）
+// 地址按 std::net 解析：可带端口（1.2.3.4:80、[2001:db8::1]:443）与 IPv6 区域标识（fe80::1%eth0），
+// IPv4 映射的 IPv6 地址（双栈监听时的 ::ffff:1.2.3.4）还原为 IPv4
+
+use std::net::{IpAddr, SocketAddr};
+
+use axum::http::HeaderMap;
+
+pub fn parse(raw: &str) -> Option<IpAddr> {
+    let raw = raw.trim();
+    if let Ok(addr) = raw.parse::<SocketAddr>() {
+        return Some(addr.ip().to_canonical());
+    }
+    // [IPv6]、[IPv6%zone]:port
+    let host = match raw.strip_prefix('[') {
+        Some(rest) => rest.split(']').next()?,
+        None => raw,
+    };
+    let host = host.split('%').next()?;
+    host.parse::<IpAddr>().ok().map(|ip| ip.to_canonical())
+}
+
+pub fn from_request(headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let forwarded = header("cf-connecting-ip")
+        .or_else(|| header("x-forwarded-for").and_then(|s| s.split(',').next()))
+        .or_else(|| header("x-real-ip"));
+    match forwarded {
+        Some(raw) => parse(raw),
+        None => peer.map(|p| p.ip().to_canonical()),
+    }
+}
+
+// 回环、私有、链路本地、运营商级 NAT（100.64.0.0/10）与唯一本地地址：无法按 IP 定位
+pub fn is_local(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            v6.is_loopback() || v6.is_unspecified() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+// 网段：10.0.0.0/8、2001:db8::/32；不带前缀长度的单个地址视为 /32 或 /128
+#[derive(Clone, Copy, Debug)]
+pub struct Cidr {
+    net: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn parse(raw: &str) -> Option<Cidr> {
+        let (addr, prefix) = match raw.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix.parse::<u8>().ok()?)),
+            None => (raw.trim(), None),
+        };
+        let net = addr.parse::<IpAddr>().ok()?.to_canonical();
+        let max = if net.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        (prefix <= max).then_some(Cidr { net, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let bits = |ip: IpAddr| match ip {
+            IpAddr::V4(v4) => u32::from(v4) as u128,
+            IpAddr::V6(v6) => u128::from(v6),
+        };
+        let width = if self.net.is_ipv4() { 32 } else { 128 };
+        if ip.is_ipv4() != self.net.is_ipv4() {
+            return false;
+        }
+        if self.prefix == 0 {
+            return true;
+        }
+        let shift = width - self.prefix as u32;
+        bits(ip) >> shift == bits(self.net) >> shift
+    }
+}
+
+// 只采信可信代理给出的转发头：对端不在 trusted 内时直接用对端地址；
+// 否则自右向左跳过 X-Forwarded-For 中的可信代理，取第一个不可信的地址（全部可信时取最左项）。
+// 没有对端地址（Unix 套接字）时视为本机代理
+pub fn from_trusted(headers: &HeaderMap, peer: Option<SocketAddr>, trusted: &[Cidr]) -> Option<IpAddr> {
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|c| c.contains(ip));
+    let peer_ip = peer.map(|p| p.ip().to_canonical());
+    if peer_ip.is_some_and(|ip| !is_trusted(ip)) {
+        return peer_ip;
+    }
+    let chain: Vec<IpAddr> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(parse)
+        .collect();
+    if chain.is_empty() {
+        return from_request(headers, peer);
+    }
+    chain.iter().rev().find(|ip| !is_trusted(**ip)).or(chain.first()).copied()
+}
@@ -0,0 +1,82 @@
+// 响应压缩：按客户端 Accept-Encoding 选择 zstd / br / gzip；已带 Content-Encoding（如预压缩静态文件）的响应不再压缩
+// - COMPRESSION：启用的算法，逗号分隔，默认 zstd,br,gzip；off 关闭
+// - COMPRESSION_LEVEL：fastest、default（默认）、best 或具体数值（超出算法范围时取最接近的合法值）
+// - COMPRESSION_MIN_BYTES：小于该字节数的响应不压缩，默认 1024（最大 65535）
+// - COMPRESSION_SKIP_TYPES：不压缩的 Content-Type 前缀，逗号分隔，默认见 DEFAULT_SKIP_TYPES
+
+use std::sync::Arc;
+
+use axum::http::{header, Response};
+use tower_http::compression::{
+    predicate::{And, Predicate, SizeAbove},
+    CompressionLayer, CompressionLevel,
+};
+
+use crate::config::{env_list, env_nonempty, env_parse};
+
+const DEFAULT_MIN_BYTES: u16 = 1024;
+// 图片（含 SVG 图标与 PNG 卡片）与字体多为已压缩格式或体积很小；gRPC、SSE 需要逐条推送
+const DEFAULT_SKIP_TYPES: &[&str] = &["image/", "font/", "application/grpc", "text/event-stream"];
+
+// 按 Content-Type 前缀跳过
+#[derive(Clone)]
+pub struct SkipTypes(Arc<[String]>);
+
+impl Predicate for SkipTypes {
+    fn should_compress<B>(&self, response: &Response<B>) -> bool
+    where
+        B: axum::body::HttpBody,
+    {
+        let Some(content_type) = response.headers().get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()) else {
+            return true;
+        };
+        let content_type = content_type.to_ascii_lowercase();
+        !self.0.iter().any(|prefix| content_type.starts_with(prefix.as_str()))
+    }
+}
+
+fn parse_level(s: &str) -> anyhow::Result<CompressionLevel> {
+    Ok(match s.to_ascii_lowercase().as_str() {
+        "fastest" => CompressionLevel::Fastest,
+        "default" => CompressionLevel::Default,
+        "best" => CompressionLevel::Best,
+        n => CompressionLevel::Precise(
+            n.parse()
+                .map_err(|_| anyhow::anyhow!("COMPRESSION_LEVEL 应为 fastest/default/best 或整数: {}", s))?,
+        ),
+    })
+}
+
+pub fn layer_from_env() -> anyhow::Result<CompressionLayer<And<SizeAbove, SkipTypes>>> {
+    let algorithms = env_list("COMPRESSION").unwrap_or_else(|| vec!["zstd".into(), "br".into(), "gzip".into()]);
+    let off = algorithms.iter().any(|a| a.eq_ignore_ascii_case("off"));
+    let (mut zstd, mut br, mut gzip) = (false, false, false);
+    if !off {
+        for algorithm in &algorithms {
+            match algorithm.to_ascii_lowercase().as_str() {
+                "zstd" => zstd = true,
+                "br" => br = true,
+                "gzip" => gzip = true,
+                other => anyhow::bail!("COMPRESSION 不支持的算法: {}（可选 zstd、br、gzip、off）", other),
+            }
+        }
+    }
+
+    let level = env_nonempty("COMPRESSION_LEVEL")
+        .map(|s| parse_level(&s))
+        .transpose()?
+        .unwrap_or_default();
+    let min_bytes = env_parse::<u16>("COMPRESSION_MIN_BYTES")?.unwrap_or(DEFAULT_MIN_BYTES);
+    let skip_types: Arc<[String]> = env_list("COMPRESSION_SKIP_TYPES")
+        .unwrap_or_else(|| DEFAULT_SKIP_TYPES.iter().map(|s| s.to_string()).collect())
+        .into_iter()
+        .map(|s| s.to_ascii_lowercase())
+        .collect();
+
+    Ok(CompressionLayer::new()
+        .zstd(zstd)
+        .br(br)
+        .gzip(gzip)
+        .quality(level)
+        .compress_when(SizeAbove::new(min_bytes).and(SkipTypes(skip_types))))
+}
@@ -0,0 +1,70 @@
+// 环境变量读取助手（各模块按需读取自己的配置）
+
+use std::str::FromStr;
+
+// 读取非空环境变量（去除首尾空白）
+pub fn env_nonempty(key: &str) -> Option<String> {
+    std::env::var(key).ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+// 逗号分隔的列表，忽略空项
+pub fn env_list(key: &str) -> Option<Vec<String>> {
+    env_nonempty(key).map(|s| {
+        s.split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    })
+}
+
+// 解析为指定类型；未设置返回 Ok(None)，格式错误返回 Err 以便启动时暴露配置问题
+pub fn env_parse<T: FromStr>(key: &str) -> anyhow::Result<Option<T>> {
+    match env_nonempty(key) {
+        Some(s) => s
+            .parse()
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("无法解析 {}={}", key, s)),
+        None => Ok(None),
+    }
+}
+
+// 布尔开关：1/true/yes/on
+pub fn env_flag(key: &str) -> bool {
+    matches!(
+        env_nonempty(key).map(|s| s.to_ascii_lowercase()).as_deref(),
+        Some("1" | "true" | "yes" | "on")
+    )
+}
+
+// 命名坐标，如后台推送任务关注的地点
+#[derive(Clone, Debug)]
+pub struct NamedLocation {
+    pub name: String,
+    pub lng: f64,
+    pub lat: f64,
+}
+
+// 分号分隔的 `名称:经度,纬度` 列表，如 `home:116.40,39.90;office:121.47,31.23`
+pub fn env_locations(key: &str) -> anyhow::Result<Option<Vec<NamedLocation>>> {
+    let Some(raw) = env_nonempty(key) else {
+        return Ok(None);
+    };
+    raw.split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let parsed = item.split_once(':').and_then(|(name, coords)| {
+                let (lng, lat) = coords.split_once(',')?;
+                Some(NamedLocation {
+                    name: name.trim().to_string(),
+                    lng: lng.trim().parse().ok()?,
+                    lat: lat.trim().parse().ok()?,
+                })
+            });
+            parsed
+                .filter(|l| !l.name.is_empty())
+                .ok_or_else(|| anyhow::anyhow!("无法解析 {} 中的地点: {}", key, item))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map(Some)
+}
@@ -0,0 +1,165 @@
+// CORS 配置：未设置 CORS_ALLOW_ORIGINS 时为开发友好的宽松策略（任意来源、不带凭据）
+// - CORS_ALLOW_ORIGINS：逗号分隔；支持 `*`、完整来源 `https://a.com`、通配子域 `https://*.example.com`
+// - CORS_ALLOW_METHODS / CORS_ALLOW_HEADERS：逗号分隔，默认 GET,POST,OPTIONS / 回显请求头
+// - CORS_ALLOW_CREDENTIALS=1：允许携带 Cookie 等凭据（此时不能使用 `*` 来源）
+// - CORS_MAX_AGE：预检结果缓存秒数
+
+use std::time::Duration;
+
+use axum::http::{request::Parts, HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, Any, CorsLayer};
+
+use crate::config::{env_flag, env_list, env_parse};
+
+// 跨域时脚本可读取的响应头（响应签名，见 signing.rs）
+const EXPOSE_HEADERS: [HeaderName; 2] =
+    [HeaderName::from_static("x-signature"), HeaderName::from_static("x-signature-timestamp")];
+
+enum OriginRule {
+    Exact(String),
+    // scheme + 后缀，如 ("https", ".example.com")
+    Subdomain { scheme: String, suffix: String },
+}
+
+impl OriginRule {
+    fn parse(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim_end_matches('/');
+        let (scheme, host) = s
+            .split_once("://")
+            .ok_or_else(|| anyhow::anyhow!("CORS 来源需包含协议: {}", s))?;
+        match host.strip_prefix("*.") {
+            Some(rest) => Ok(Self::Subdomain {
+                scheme: scheme.to_ascii_lowercase(),
+                suffix: format!(".{}", rest.to_ascii_lowercase()),
+            }),
+            None => Ok(Self::Exact(s.to_ascii_lowercase())),
+        }
+    }
+
+    fn matches(&self, origin: &str) -> bool {
+        let origin = origin.to_ascii_lowercase();
+        match self {
+            Self::Exact(o) => origin == *o,
+            Self::Subdomain { scheme, suffix } => origin
+                .split_once("://")
+                .is_some_and(|(s, host)| s == scheme && host.len() > suffix.len() && host.ends_with(suffix.as_str())),
+        }
+    }
+}
+
+// 带凭据时浏览器不接受 `*`，配置为 * 时直接报错
+fn allow_origin(origins: &[String], credentials: bool) -> anyhow::Result<AllowOrigin> {
+    if origins.iter().any(|o| o == "*") {
+        if credentials {
+            anyhow::bail!("CORS_ALLOW_CREDENTIALS=1 时 CORS_ALLOW_ORIGINS 不能为 *");
+        }
+        return Ok(AllowOrigin::any());
+    }
+    let rules = origins
+        .iter()
+        .map(|o| OriginRule::parse(o))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(AllowOrigin::predicate(move |origin: &HeaderValue, _: &Parts| {
+        origin
+            .to_str()
+            .is_ok_and(|o| rules.iter().any(|r| r.matches(o)))
+    }))
+}
+
+pub fn layer_from_env() -> anyhow::Result<CorsLayer> {
+    let Some(origins) = env_list("CORS_ALLOW_ORIGINS") else {
+        return Ok(CorsLayer::new()
+            .allow_origin(Any)
+            .allow_methods(Any)
+            .allow_headers(Any)
+            .expose_headers(EXPOSE_HEADERS));
+    };
+    let credentials = env_flag("CORS_ALLOW_CREDENTIALS");
+    let allow_origin = allow_origin(&origins, credentials)?;
+
+    let methods = match env_list("CORS_ALLOW_METHODS") {
+        Some(list) => list
+            .iter()
+            .map(|m| m.to_ascii_uppercase().parse::<Method>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow::anyhow!("CORS_ALLOW_METHODS 无效: {}", e))?,
+        None => vec![Method::GET, Method::POST, Method::OPTIONS],
+    };
+    let headers = match env_list("CORS_ALLOW_HEADERS") {
+        Some(list) => AllowHeaders::list(
+            list.iter()
+                .map(|h| h.parse::<HeaderName>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| anyhow::anyhow!("CORS_ALLOW_HEADERS 无效: {}", e))?,
+        ),
+        None => AllowHeaders::mirror_request(),
+    };
+
+    let mut layer = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(AllowMethods::list(methods))
+        .allow_headers(headers)
+        .expose_headers(EXPOSE_HEADERS)
+        .allow_credentials(credentials);
+    if let Some(secs) = env_parse::<u64>("CORS_MAX_AGE")? {
+        layer = layer.max_age(Duration::from_secs(secs));
+    }
+    Ok(layer)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+    use tower_http::cors::CorsLayer;
+
+    use super::{allow_origin, OriginRule};
+
+    fn origins(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_exact_and_subdomain_rules() {
+        let exact = OriginRule::parse("https://App.example.com/").unwrap();
+        assert!(exact.matches("https://app.example.com"));
+        assert!(!exact.matches("http://app.example.com"));
+        assert!(!exact.matches("https://app.example.com.evil.test"));
+
+        let sub = OriginRule::parse("https://*.Example.com").unwrap();
+        assert!(sub.matches("https://a.example.com"));
+        assert!(sub.matches("https://A.B.example.com"));
+        assert!(!sub.matches("https://example.com"));
+        assert!(!sub.matches("https://.example.com"));
+        assert!(!sub.matches("https://evilexample.com"));
+        assert!(!sub.matches("http://a.example.com"));
+
+        assert!(OriginRule::parse("example.com").is_err());
+    }
+
+    #[test]
+    fn credentials_cannot_be_combined_with_any_origin() {
+        let err = allow_origin(&origins(&["https://a.test", "*"]), true).err().unwrap();
+        assert!(err.to_string().contains("不能为 *"), "{}", err);
+        assert!(allow_origin(&origins(&["*"]), false).is_ok());
+        assert!(allow_origin(&origins(&["https://*.a.test"]), true).is_ok());
+        assert!(allow_origin(&origins(&["a.test"]), false).is_err());
+    }
+
+    #[tokio::test]
+    async fn only_matching_origins_are_echoed_back() {
+        let layer = CorsLayer::new().allow_origin(allow_origin(&origins(&["https://*.a.test"]), true).unwrap());
+        let app = Router::new().route("/", get(|| async { "ok" })).layer(layer.allow_credentials(true));
+        for (origin, allowed) in [("https://www.a.test", true), ("https://a.test.evil", false)] {
+            let req = Request::builder().method(Method::GET).uri("/").header(header::ORIGIN, origin).body(Body::empty()).unwrap();
+            let resp = app.clone().oneshot(req).await.unwrap();
+            let echoed = resp.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).map(|v| v.to_str().unwrap().to_string());
+            assert_eq!(echoed, allowed.then(|| origin.to_string()), "{}", origin);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// 昼夜判断：按彩云 daily.astro 的日出日落时间判断逐小时是白天还是夜间，纠正 skycon 的昼夜变体
+// 上游逐小时 skycon 偶尔与实际昼夜不符（如 23 时仍为 CLEAR_DAY），在整形时按当地日出日落改写
+// 时间均取上游字符串中的当地日期与时分，不做时区换算；缺少当天 astro 时保持上游代码不变
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+// 日期 YYYY-MM-DD → (日出, 日落)，单位为当天的分钟数
+pub struct Daylight(HashMap<String, (u32, u32)>);
+
+// "05:21" → 321
+fn minutes(hhmm: &str) -> Option<u32> {
+    let (h, m) = hhmm.split_once(':')?;
+    let (h, m) = (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+impl Daylight {
+    // result 为彩云响应的 result 节点
+    pub fn from_result(result: &Value) -> Daylight {
+        let days = result
+            .pointer("/daily/astro")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|day| {
+                let date = day.get("date")?.as_str()?.get(..10)?;
+                let time = |key: &str| minutes(day.get(key)?.get("time")?.as_str()?);
+                Some((date.to_string(), (time("sunrise")?, time("sunset")?)))
+            })
+            .collect();
+        Daylight(days)
+    }
+
+    // datetime 形如 2025-07-28T23:00+08:00；日落早于日出（极昼地区跨午夜）时两者之间为夜间
+    pub fn is_day(&self, datetime: &str) -> Option<bool> {
+        let (sunrise, sunset) = *self.0.get(datetime.get(..10)?)?;
+        let t = minutes(datetime.get(11..16)?)?;
+        Some(if sunrise <= sunset { t >= sunrise && t < sunset } else { t >= sunrise || t < sunset })
+    }
+
+    // 只改写有昼夜之分的晴与少云，其他天气原样返回
+    pub fn skycon<'a>(&self, code: &'a str, datetime: &str) -> &'a str {
+        match (code, self.is_day(datetime)) {
+            ("CLEAR_NIGHT", Some(true)) => "CLEAR_DAY",
+            ("CLEAR_DAY", Some(false)) => "CLEAR_NIGHT",
+            ("PARTLY_CLOUDY_NIGHT", Some(true)) => "PARTLY_CLOUDY_DAY",
+            ("PARTLY_CLOUDY_DAY", Some(false)) => "PARTLY_CLOUDY_NIGHT",
+            _ => code,
+        }
+    }
+}
@@ -0,0 +1,688 @@
+// 演示模式：开启 DEMO_MODE 后天气接口不请求上游、也不需要 token，按坐标与日期生成伪随机的彩云同结构数据，供截图与前端开发使用
+// 与 MOCK_MODE 的固定场景不同，演示数据随地点和日期变化，同一地点（坐标两位小数）同一天的结果始终相同：
+// - 气温按纬度与季节给出，日内最低在凌晨、最高在午后；日出日落按纬度与日期计算
+// - 天气覆盖全部 skycon：晴、多云较常见，雨雪、雾、霾、沙尘、大风、冰雹偶尔出现；雨雪按气温互换，不会在炎热地区下雪
+// - 当前或下一小时有降水时生成逐分钟降水（正在下、即将开始或即将停止），短临描述由 nowcast 生成
+// - 暴雨、暴雪、大风、沙尘、大雾、霾、冰雹天气按概率附带对应预警，另有高温与寒潮预警
+// - DEMO_MODE：1/true 开启；与 MOCK_MODE 同时开启时以 MOCK_MODE 为准
+
+use std::f64::consts::PI;
+
+use chrono::{DateTime, Datelike, Days, FixedOffset, NaiveDate, TimeDelta, Timelike, Utc};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+
+use crate::{air, config::env_flag, mock, provider};
+
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M%:z";
+
+static ENABLED: Lazy<bool> = Lazy::new(|| env_flag("DEMO_MODE"));
+
+pub fn enabled() -> bool {
+    *ENABLED && !mock::enabled()
+}
+
+// 坐标乘以 100 取整
+type Key = (i32, i32);
+
+// (白天的 skycon, 权重)
+const SKYCONS: [(&str, u32); 20] = [
+    ("CLEAR_DAY", 14),
+    ("PARTLY_CLOUDY_DAY", 14),
+    ("CLOUDY", 10),
+    ("LIGHT_RAIN", 8),
+    ("MODERATE_RAIN", 5),
+    ("HEAVY_RAIN", 3),
+    ("STORM_RAIN", 2),
+    ("HAIL", 1),
+    ("SLEET", 2),
+    ("LIGHT_SNOW", 3),
+    ("MODERATE_SNOW", 2),
+    ("HEAVY_SNOW", 1),
+    ("STORM_SNOW", 1),
+    ("FOG", 3),
+    ("LIGHT_HAZE", 3),
+    ("MODERATE_HAZE", 2),
+    ("HEAVY_HAZE", 1),
+    ("DUST", 2),
+    ("SAND", 1),
+    ("WIND", 2),
+];
+
+const RAIN_SNOW: [(&str, &str); 4] = [
+    ("LIGHT_RAIN", "LIGHT_SNOW"),
+    ("MODERATE_RAIN", "MODERATE_SNOW"),
+    ("HEAVY_RAIN", "HEAVY_SNOW"),
+    ("STORM_RAIN", "STORM_SNOW"),
+];
+
+// (代码前两位, 类型, 预计出现的天气)；代码后两位为等级 01 蓝 … 04 红
+const ALERT_KINDS: [(&str, &str, &str); 10] = [
+    ("02", "暴雨", "短时强降水，局地伴有雷暴大风"),
+    ("03", "暴雪", "较大降雪，积雪深度明显增加"),
+    ("04", "寒潮", "强降温，最低气温明显下降"),
+    ("05", "大风", "平均风力 6 级以上的大风"),
+    ("06", "沙尘暴", "沙尘天气，能见度明显下降"),
+    ("07", "高温", "日最高气温 35°C 以上的高温天气"),
+    ("09", "雷电", "雷电活动，可能造成雷电灾害"),
+    ("10", "冰雹", "冰雹天气，伴有短时大风"),
+    ("12", "大雾", "大雾天气，能见度低于 500 米"),
+    ("13", "霾", "中度以上霾，空气质量较差"),
+];
+const ALERT_LEVELS: [(&str, &str); 4] = [("蓝色", "Ⅳ级/一般"), ("黄色", "Ⅲ级/较重"), ("橙色", "Ⅱ级/严重"), ("红色", "Ⅰ级/特别严重")];
+
+// SplitMix64：只依赖种子，同一种子得到同一序列
+struct Rng(u64);
+
+impl Rng {
+    // stream 区分同一地点同一天的不同用途（逐日、逐小时、预警等）
+    fn new(key: Key, date: NaiveDate, stream: u64) -> Self {
+        let coords = ((key.0 as u32 as u64) << 32) | key.1 as u32 as u64;
+        let mut rng = Rng(coords ^ (date.num_days_from_ce() as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ stream.rotate_left(40));
+        rng.next();
+        rng
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // [0, 1)
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn range(&mut self, (lo, hi): (f64, f64)) -> f64 {
+        lo + (hi - lo) * self.unit()
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        self.unit() < p
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[(T, u32)]) -> T {
+        let total: u32 = items.iter().map(|(_, w)| w).sum();
+        let mut n = (self.unit() * total as f64) as u32;
+        for (item, weight) in items {
+            if n < *weight {
+                return *item;
+            }
+            n -= weight;
+        }
+        items[items.len() - 1].0
+    }
+}
+
+// 降水强度范围 mm/h，分级与 nowcast 一致；无降水为 None
+fn intensity(sky: &str) -> Option<(f64, f64)> {
+    match sky {
+        "LIGHT_RAIN" | "LIGHT_SNOW" => Some((0.1, 0.9)),
+        "MODERATE_RAIN" | "MODERATE_SNOW" | "SLEET" => Some((0.9, 2.87)),
+        "HEAVY_RAIN" | "HEAVY_SNOW" | "HAIL" => Some((2.87, 12.8)),
+        "STORM_RAIN" | "STORM_SNOW" => Some((12.8, 30.0)),
+        _ => None,
+    }
+}
+
+// 寒冷时雨改为雪，温暖时雪改为雨
+fn for_temperature(sky: &'static str, avg: f64) -> &'static str {
+    for (rain, snow) in RAIN_SNOW {
+        if sky == rain && avg < -1.0 {
+            return snow;
+        }
+        if sky == snow && avg > 4.0 {
+            return rain;
+        }
+    }
+    sky
+}
+
+fn for_daylight(sky: &'static str, is_day: bool) -> &'static str {
+    match (sky, is_day) {
+        ("CLEAR_DAY", false) => "CLEAR_NIGHT",
+        ("PARTLY_CLOUDY_DAY", false) => "PARTLY_CLOUDY_NIGHT",
+        ("CLEAR_NIGHT", true) => "CLEAR_DAY",
+        ("PARTLY_CLOUDY_NIGHT", true) => "PARTLY_CLOUDY_DAY",
+        _ => sky,
+    }
+}
+
+fn cloud_range(sky: &str) -> (f64, f64) {
+    match sky {
+        "CLEAR_DAY" | "CLEAR_NIGHT" => (0.0, 0.15),
+        "PARTLY_CLOUDY_DAY" | "PARTLY_CLOUDY_NIGHT" => (0.3, 0.7),
+        "FOG" | "LIGHT_HAZE" | "MODERATE_HAZE" | "HEAVY_HAZE" | "DUST" | "SAND" | "WIND" => (0.2, 0.6),
+        _ => (0.8, 1.0),
+    }
+}
+
+fn humidity_range(sky: &str) -> (f64, f64) {
+    match sky {
+        "FOG" => (0.92, 1.0),
+        "SAND" | "DUST" | "WIND" => (0.15, 0.35),
+        "LIGHT_HAZE" | "MODERATE_HAZE" | "HEAVY_HAZE" => (0.6, 0.85),
+        "CLOUDY" => (0.6, 0.8),
+        s if intensity(s).is_some() => (0.85, 0.98),
+        _ => (0.35, 0.65),
+    }
+}
+
+// 能见度 km
+fn visibility_range(sky: &str) -> (f64, f64) {
+    match sky {
+        "FOG" => (0.2, 1.0),
+        "SAND" => (0.5, 3.0),
+        "HEAVY_HAZE" => (1.0, 3.0),
+        "MODERATE_HAZE" => (2.0, 5.0),
+        "DUST" => (3.0, 8.0),
+        "LIGHT_HAZE" => (5.0, 10.0),
+        s if intensity(s).is_some() => (3.0, 12.0),
+        _ => (15.0, 30.0),
+    }
+}
+
+fn aqi_range(sky: &str) -> (f64, f64) {
+    match sky {
+        "LIGHT_HAZE" => (101.0, 150.0),
+        "MODERATE_HAZE" => (151.0, 200.0),
+        "HEAVY_HAZE" => (201.0, 300.0),
+        "DUST" => (120.0, 200.0),
+        "SAND" => (200.0, 400.0),
+        s if intensity(s).is_some() => (10.0, 40.0),
+        _ => (20.0, 95.0),
+    }
+}
+
+// 中国标准 AQI → PM2.5 浓度 μg/m³（按分指数分段线性反推）
+fn pm25_of(aqi: f64) -> f64 {
+    const POINTS: [(f64, f64); 8] =
+        [(0.0, 0.0), (50.0, 35.0), (100.0, 75.0), (150.0, 115.0), (200.0, 150.0), (300.0, 250.0), (400.0, 350.0), (500.0, 500.0)];
+    POINTS
+        .windows(2)
+        .find(|w| aqi <= w[1].0)
+        .map(|w| w[0].1 + (aqi - w[0].0) / (w[1].0 - w[0].0) * (w[1].1 - w[0].1))
+        .unwrap_or(500.0)
+}
+
+fn round2(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
+// 某地某天的概况，逐小时数据在此基础上生成
+struct Day {
+    date: NaiveDate,
+    min: f64,
+    max: f64,
+    // 08-20 时与 20 时至次日 08 时
+    sky_day: &'static str,
+    sky_night: &'static str,
+    wind: f64,
+    wind_direction: f64,
+    pressure: f64,
+    aqi: f64,
+    // 当地时间（小时，可带小数）
+    sunrise: f64,
+    sunset: f64,
+    // 正午太阳高度角（度）
+    noon_elevation: f64,
+}
+
+struct Place {
+    key: Key,
+    lng: f64,
+    lat: f64,
+    offset: FixedOffset,
+}
+
+impl Place {
+    fn new(lng: f64, lat: f64) -> Self {
+        // 时区按经度估算，与天气整形的回退规则一致
+        let hours = (lng / 15.0).round() as i32;
+        let offset = FixedOffset::east_opt(hours * 3600).unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+        Self { key: ((lng * 100.0).round() as i32, (lat * 100.0).round() as i32), lng, lat, offset }
+    }
+
+    fn day(&self, date: NaiveDate) -> Day {
+        let mut rng = Rng::new(self.key, date, 1);
+        let doy = date.ordinal() as f64;
+        // 北半球 7 月中旬最热，南半球相反
+        let season = (2.0 * PI * (doy - 200.0) / 365.25).cos() * self.lat.signum();
+        let avg = 28.0 - 0.4 * self.lat.abs() + 0.3 * self.lat.abs() * season + rng.range((-3.0, 3.0));
+        let sky_day = for_temperature(rng.pick(&SKYCONS), avg);
+        let sky_night = if rng.chance(0.6) { sky_day } else { for_temperature(rng.pick(&SKYCONS), avg) };
+        let damp = intensity(sky_day).is_some() || matches!(sky_day, "CLOUDY" | "FOG");
+        let spread = rng.range((6.0, 12.0)) * if damp { 0.6 } else { 1.0 };
+        let wind = match sky_day {
+            "WIND" => rng.range((10.8, 17.0)),
+            "SAND" | "DUST" | "STORM_RAIN" | "STORM_SNOW" | "HAIL" => rng.range((6.0, 11.0)),
+            _ => rng.range((1.0, 5.5)),
+        };
+
+        // 太阳赤纬与昼长；极昼极夜时昼长限制在 0.5-23.5 小时，保持日出早于日落
+        let declination = 23.44 * (2.0 * PI * (284.0 + doy) / 365.0).sin();
+        let cos_h = (-self.lat.to_radians().tan() * declination.to_radians().tan()).clamp(-1.0, 1.0);
+        let day_length = (2.0 * cos_h.acos().to_degrees() / 15.0).clamp(0.5, 23.5);
+        let noon = 12.0 + (self.offset.local_minus_utc() as f64 / 3600.0 * 15.0 - self.lng) / 15.0;
+        Day {
+            date,
+            min: avg - spread / 2.0,
+            max: avg + spread / 2.0,
+            sky_day,
+            sky_night: for_daylight(sky_night, false),
+            wind,
+            wind_direction: rng.range((0.0, 360.0)),
+            pressure: 101_325.0 + rng.range((-1500.0, 1500.0)) - if intensity(sky_day).is_some() { 800.0 } else { 0.0 },
+            aqi: rng.range(aqi_range(sky_day)),
+            sunrise: noon - day_length / 2.0,
+            sunset: noon + day_length / 2.0,
+            noon_elevation: 90.0 - (self.lat - declination).abs(),
+        }
+    }
+
+    fn hour(&self, t: DateTime<FixedOffset>) -> Hour {
+        let date = t.date_naive();
+        let h = t.hour() as f64;
+        let day = self.day(date);
+        let (prev, next) = (date.pred_opt().map(|d| self.day(d)), date.succ_opt().map(|d| self.day(d)));
+        let sky = match t.hour() {
+            8..=19 => day.sky_day,
+            20.. => day.sky_night,
+            _ => prev.as_ref().map_or(day.sky_night, |d| d.sky_night),
+        };
+        // 05 时最低、14 时最高，其间平滑过渡，跨日时接上前后两天
+        let ease = |from: f64, to: f64, x: f64| from + (to - from) * (1.0 - (PI * x).cos()) / 2.0;
+        let temperature = match h {
+            h if h < 5.0 => ease(prev.as_ref().map_or(day.max, |d| d.max), day.min, (h + 10.0) / 15.0),
+            h if h < 14.0 => ease(day.min, day.max, (h - 5.0) / 9.0),
+            h => ease(day.max, next.as_ref().map_or(day.min, |d| d.min), (h - 14.0) / 15.0),
+        };
+        let is_day = h >= day.sunrise && h < day.sunset;
+        let sky = for_daylight(sky, is_day);
+        let mut rng = Rng::new(self.key, date, 100 + t.hour() as u64);
+        let precipitation = intensity(sky).map_or(0.0, |r| rng.range(r));
+        let cloud = rng.range(cloud_range(sky));
+        let aqi = day.aqi * rng.range((0.85, 1.15));
+        let sun = if is_day { (PI * (h - day.sunrise) / (day.sunset - day.sunrise)).sin() } else { 0.0 };
+        Hour {
+            time: t,
+            sky,
+            temperature: temperature + rng.range((-0.5, 0.5)),
+            precipitation,
+            probability: if precipitation > 0.0 { rng.range((70.0, 100.0)) } else { rng.range((0.0, 25.0)) }.round(),
+            humidity: rng.range(humidity_range(sky)),
+            cloud,
+            wind: day.wind * rng.range((0.6, 1.3)),
+            wind_direction: (day.wind_direction + rng.range((-30.0, 30.0))).rem_euclid(360.0),
+            pressure: day.pressure + rng.range((-150.0, 150.0)),
+            visibility: rng.range(visibility_range(sky)),
+            dswrf: 1000.0 * sun * day.noon_elevation.max(0.0).to_radians().sin() * (1.0 - 0.75 * cloud),
+            aqi,
+        }
+    }
+
+    fn hours_of(&self, date: NaiveDate) -> Vec<Hour> {
+        let start = date.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(self.offset).unwrap();
+        (0..24).map(|h| self.hour(start + TimeDelta::hours(h))).collect()
+    }
+}
+
+struct Hour {
+    time: DateTime<FixedOffset>,
+    sky: &'static str,
+    temperature: f64,
+    precipitation: f64,
+    probability: f64,
+    humidity: f64,
+    cloud: f64,
+    // m/s
+    wind: f64,
+    wind_direction: f64,
+    // Pa
+    pressure: f64,
+    visibility: f64,
+    dswrf: f64,
+    aqi: f64,
+}
+
+impl Hour {
+    fn uv(&self) -> f64 {
+        self.dswrf / 80.0
+    }
+
+    fn air_quality(&self) -> Value {
+        let aqi = self.aqi.round() as i64;
+        let usa = (self.aqi * 1.15 + 5.0).min(500.0).round() as i64;
+        let pm25 = pm25_of(self.aqi);
+        let dusty = matches!(self.sky, "DUST" | "SAND");
+        let mut rng = Rng::new((aqi as i32, self.time.hour() as i32), self.time.date_naive(), 5);
+        json!({
+            "pm25": pm25.round(),
+            "pm10": (if dusty { self.aqi * 1.6 } else { pm25 * 1.5 }).round(),
+            "o3": rng.range((20.0, 120.0)).round(),
+            "so2": rng.range((2.0, 15.0)).round(),
+            "no2": rng.range((8.0, 50.0)).round(),
+            "co": round2(rng.range((0.3, 1.2))),
+            "aqi": { "chn": aqi, "usa": usa },
+            "description": { "chn": air::aqi_category(aqi), "usa": air::aqi_category(usa) },
+        })
+    }
+}
+
+// (最小, 最大, 平均)
+fn stats(hours: &[Hour], f: impl Fn(&Hour) -> f64) -> (f64, f64, f64) {
+    let values: Vec<f64> = hours.iter().map(f).collect();
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    (min, max, values.iter().sum::<f64>() / values.len().max(1) as f64)
+}
+
+fn clock(hours: f64) -> String {
+    let minutes = (hours.rem_euclid(24.0) * 60.0).round() as i64 % (24 * 60);
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+fn life_index(day: &Day, hours: &[Hour], wet_tomorrow: bool) -> [Value; 5] {
+    let (_, max_uv, _) = stats(hours, Hour::uv);
+    let mut ultraviolet = provider::ultraviolet(max_uv);
+    let wet = hours.iter().any(|h| h.precipitation > 0.0);
+    let car_washing = match day.sky_day {
+        _ if wet || wet_tomorrow => ("4", "不宜"),
+        "DUST" | "SAND" | "WIND" | "MODERATE_HAZE" | "HEAVY_HAZE" => ("3", "较不宜"),
+        _ => ("1", "适宜"),
+    };
+    let dressing = match day.max {
+        t if t >= 32.0 => ("1", "炎热"),
+        t if t >= 26.0 => ("3", "热"),
+        t if t >= 20.0 => ("4", "温暖"),
+        t if t >= 14.0 => ("5", "舒适"),
+        t if t >= 8.0 => ("6", "凉"),
+        t if t >= 0.0 => ("7", "冷"),
+        _ => ("8", "寒冷"),
+    };
+    let comfort = match (day.min + day.max) / 2.0 {
+        t if t >= 30.0 => ("2", "很热"),
+        t if t >= 26.0 => ("3", "热"),
+        t if t >= 21.0 => ("4", "温暖"),
+        t if t >= 15.0 => ("5", "舒适"),
+        t if t >= 8.0 => ("6", "凉爽"),
+        t if t >= 0.0 => ("7", "冷"),
+        _ => ("9", "寒冷"),
+    };
+    let cold_risk = match day.min {
+        t if t < 0.0 || day.max - day.min > 10.0 => ("3", "易发"),
+        t if t < 10.0 => ("2", "较易发"),
+        _ => ("1", "少发"),
+    };
+    let date = format!("{}T00:00{}", day.date, hours[0].time.format("%:z"));
+    ultraviolet["date"] = date.clone().into();
+    let item = |(index, desc): (&str, &str)| json!({ "date": date, "index": index, "desc": desc });
+    [ultraviolet, item(car_washing), item(dressing), item(comfort), item(cold_risk)]
+}
+
+fn alerts(place: &Place, day: &Day, now: DateTime<FixedOffset>) -> Vec<Value> {
+    let mut rng = Rng::new(place.key, day.date, 3);
+    let mut kinds: Vec<&str> = Vec::new();
+    for sky in [day.sky_day, day.sky_night] {
+        let kind = match sky {
+            "STORM_RAIN" | "HEAVY_RAIN" => "暴雨",
+            "HEAVY_SNOW" | "STORM_SNOW" => "暴雪",
+            "WIND" => "大风",
+            "SAND" | "DUST" => "沙尘暴",
+            "HAIL" => "冰雹",
+            "FOG" => "大雾",
+            "MODERATE_HAZE" | "HEAVY_HAZE" => "霾",
+            _ => continue,
+        };
+        if rng.chance(0.7) {
+            kinds.push(kind);
+        }
+    }
+    if day.sky_day == "STORM_RAIN" || day.sky_day == "HAIL" {
+        kinds.push("雷电");
+    }
+    if day.max >= 35.0 {
+        kinds.push("高温");
+    }
+    if day.min <= -10.0 {
+        kinds.push("寒潮");
+    }
+    kinds.dedup();
+
+    // 当地 06-09 时发布；尚未到发布时刻时按刚刚发布处理
+    let midnight = day.date.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(place.offset).unwrap();
+    let published = (midnight + TimeDelta::minutes(rng.range((360.0, 540.0)) as i64)).min(now - TimeDelta::minutes(5));
+    kinds
+        .into_iter()
+        .filter_map(|kind| ALERT_KINDS.iter().find(|(_, name, _)| *name == kind))
+        .map(|(code, name, weather)| {
+            let level = ((rng.unit() * 4.0) as usize).min(3);
+            let (color, grade) = ALERT_LEVELS[level];
+            let code = format!("{}{:02}", code, level + 1);
+            json!({
+                "alertId": format!("demo_{}_{}_{}_{}", place.key.0, place.key.1, day.date.format("%Y%m%d"), code),
+                "title": format!("演示气象台发布{}{}预警[{}]", name, color, grade),
+                "code": code,
+                "status": "预警中",
+                "description": format!(
+                    "演示气象台{}发布{}{}预警信号：预计未来 24 小时内将出现{}，请注意防范。（演示数据）",
+                    published.format("%Y年%m月%d日%H时%M分"),
+                    name,
+                    color,
+                    weather
+                ),
+                "source": "演示数据",
+                "location": "演示地区",
+                "latlon": [place.lat, place.lng],
+                "pubtimestamp": published.timestamp() as f64,
+                "request_status": "ok",
+            })
+        })
+        .collect()
+}
+
+// 未来两小时逐分钟降水：当前小时有降水时持续（下一小时无降水则中途停止），否则下一小时有降水时在整点前后开始
+fn minutely(place: &Place, now: DateTime<FixedOffset>, current: &Hour, next: &Hour) -> Value {
+    let mut rng = Rng::new(place.key, now.date_naive(), 200 + now.hour() as u64);
+    let to_next_hour = 60.0 - now.minute() as f64;
+    let (start, end, level) = match (current.precipitation > 0.0, next.precipitation > 0.0) {
+        (true, true) => (0.0, 120.0, current.precipitation),
+        (true, false) => (0.0, rng.range((10.0, to_next_hour + 30.0)), current.precipitation),
+        (false, true) => (rng.range(((to_next_hour - 15.0).max(5.0), to_next_hour + 15.0)), 120.0, next.precipitation),
+        (false, false) => (120.0, 120.0, 0.0),
+    };
+    let series: Vec<f64> = (0..120)
+        .map(|m| {
+            let m = m as f64;
+            if m < start || m >= end {
+                return 0.0;
+            }
+            // 开始后 15 分钟内逐渐增强，其间小幅起伏
+            let ramp = if start > 0.0 { ((m - start) / 15.0).min(1.0) } else { 1.0 };
+            round2((level * ramp * (1.0 + 0.2 * (m / 9.0).sin())).max(0.04))
+        })
+        .collect();
+    let probability: Vec<f64> = series
+        .chunks(30)
+        .map(|c| round2(if c.iter().any(|v| *v > 0.0) { rng.range((0.8, 1.0)) } else { rng.range((0.0, 0.1)) }))
+        .collect();
+    json!({
+        "status": "ok",
+        "datasource": "demo",
+        "precipitation_2h": series,
+        "precipitation": &series[..60],
+        "probability": probability,
+        "description": "",
+    })
+}
+
+// 生成彩云同结构的响应：24 小时逐小时预报与 days 天逐日预报
+pub fn generate(lng: f64, lat: f64, days: usize) -> Value {
+    let place = Place::new(lng, lat);
+    let now = Utc::now().with_timezone(&place.offset);
+    let today = now.date_naive();
+    let hour_start = now.with_minute(0).and_then(|t| t.with_second(0)).and_then(|t| t.with_nanosecond(0)).unwrap_or(now);
+    let hours: Vec<Hour> = (0..24).map(|i| place.hour(hour_start + TimeDelta::hours(i))).collect();
+    let current = &hours[0];
+
+    let hourly_item = |f: &dyn Fn(&Hour) -> Value| -> Vec<Value> {
+        hours
+            .iter()
+            .map(|h| {
+                let mut item = f(h);
+                item["datetime"] = h.time.format(TIME_FORMAT).to_string().into();
+                item
+            })
+            .collect()
+    };
+    let hourly = json!({
+        "status": "ok",
+        "description": "",
+        "temperature": hourly_item(&|h| json!({ "value": round2(h.temperature) })),
+        "skycon": hourly_item(&|h| json!({ "value": h.sky })),
+        "precipitation": hourly_item(&|h| json!({ "value": round2(h.precipitation), "probability": h.probability })),
+        "humidity": hourly_item(&|h| json!({ "value": round2(h.humidity) })),
+        "cloudrate": hourly_item(&|h| json!({ "value": round2(h.cloud) })),
+        "wind": hourly_item(&|h| json!({ "speed": round2(h.wind), "direction": h.wind_direction.round() })),
+        "pressure": hourly_item(&|h| json!({ "value": h.pressure.round() })),
+        "visibility": hourly_item(&|h| json!({ "value": round2(h.visibility) })),
+        "dswrf": hourly_item(&|h| json!({ "value": h.dswrf.round() })),
+        "air_quality": {
+            "aqi": hourly_item(&|h| json!({ "value": h.air_quality()["aqi"] })),
+            "pm25": hourly_item(&|h| json!({ "value": h.air_quality()["pm25"] })),
+        },
+    });
+
+    let dates: Vec<NaiveDate> = (0..days as u64).filter_map(|i| today.checked_add_days(Days::new(i))).collect();
+    let summaries: Vec<(Day, Vec<Hour>)> = dates.iter().map(|d| (place.day(*d), place.hours_of(*d))).collect();
+    let mut daily: serde_json::Map<String, Value> = serde_json::Map::new();
+    let mut push = |key: &str, item: Value| {
+        if let Some(items) = daily.entry(key).or_insert_with(|| json!([])).as_array_mut() {
+            items.push(item);
+        }
+    };
+    let mut life: [Vec<Value>; 5] = Default::default();
+    for (day, hours) in &summaries {
+        let date = format!("{}T00:00{}", day.date, hours[0].time.format("%:z"));
+        let triple = |(min, max, avg): (f64, f64, f64)| {
+            json!({ "date": date, "min": round2(min), "max": round2(max), "avg": round2(avg) })
+        };
+        let precipitation = |part: &[Hour]| {
+            let mut item = triple(stats(part, |h| h.precipitation));
+            item["probability"] = stats(part, |h| h.probability).1.into();
+            item
+        };
+        // 08-20 时与 20 时至次日 08 时；次日按需生成，与请求的天数无关
+        let next = day.date.succ_opt().map(|d| place.hours_of(d)).unwrap_or_default();
+        let night: Vec<&Hour> = hours[20..].iter().chain(next.iter().take(8)).collect();
+        let night_precipitation = {
+            let values: Vec<f64> = night.iter().map(|h| h.precipitation).collect();
+            json!({
+                "date": date,
+                "min": round2(values.iter().copied().fold(f64::INFINITY, f64::min)),
+                "max": round2(values.iter().copied().fold(0.0, f64::max)),
+                "avg": round2(values.iter().sum::<f64>() / values.len() as f64),
+                "probability": night.iter().map(|h| h.probability).fold(0.0, f64::max),
+            })
+        };
+        let sky = if intensity(day.sky_day).is_none() && intensity(day.sky_night).is_some() {
+            for_daylight(day.sky_night, true)
+        } else {
+            day.sky_day
+        };
+        let aqi = stats(hours, |h| h.aqi);
+        let aqi_pair = |v: f64| json!({ "chn": v.round(), "usa": (v * 1.15 + 5.0).min(500.0).round() });
+        let wind = stats(hours, |h| h.wind);
+        let strongest = hours.iter().max_by(|a, b| a.wind.total_cmp(&b.wind)).unwrap();
+
+        push("astro", json!({ "date": date, "sunrise": { "time": clock(day.sunrise) }, "sunset": { "time": clock(day.sunset) } }));
+        push("precipitation", precipitation(hours));
+        push("precipitation_08h_20h", precipitation(&hours[8..20]));
+        push("precipitation_20h_32h", night_precipitation);
+        push("temperature", triple(stats(hours, |h| h.temperature)));
+        push(
+            "wind",
+            json!({
+                "date": date,
+                "max": { "speed": round2(wind.1), "direction": strongest.wind_direction.round() },
+                "min": { "speed": round2(wind.0), "direction": day.wind_direction.round() },
+                "avg": { "speed": round2(wind.2), "direction": day.wind_direction.round() },
+            }),
+        );
+        push("humidity", triple(stats(hours, |h| h.humidity)));
+        push("cloudrate", triple(stats(hours, |h| h.cloud)));
+        push("pressure", triple(stats(hours, |h| h.pressure)));
+        push("visibility", triple(stats(hours, |h| h.visibility)));
+        push("dswrf", triple(stats(hours, |h| h.dswrf)));
+        push("air_quality", json!({ "date": date, "max": aqi_pair(aqi.1), "avg": aqi_pair(aqi.2), "min": aqi_pair(aqi.0) }));
+        push("skycon", json!({ "date": date, "value": sky }));
+        push("skycon_08h_20h", json!({ "date": date, "value": day.sky_day }));
+        push("skycon_20h_32h", json!({ "date": date, "value": day.sky_night }));
+        let wet_tomorrow = next.iter().any(|h| h.precipitation > 0.0);
+        for (list, item) in life.iter_mut().zip(life_index(day, hours, wet_tomorrow)) {
+            list.push(item);
+        }
+    }
+    // 彩云逐日空气质量为 air_quality.aqi[]
+    if let Some(aqi) = daily.remove("air_quality") {
+        daily.insert("air_quality".into(), json!({ "aqi": aqi }));
+    }
+    let [ultraviolet, car_washing, dressing, comfort, cold_risk] = life;
+    daily.insert(
+        "life_index".into(),
+        json!({
+            "ultraviolet": ultraviolet,
+            "carWashing": car_washing,
+            "dressing": dressing,
+            "comfort": comfort,
+            "coldRisk": cold_risk,
+        }),
+    );
+    daily.insert("status".into(), "ok".into());
+
+    let today_summary = place.day(today);
+    let mut realtime_comfort = summaries.first().map(|(d, h)| life_index(d, h, false)[3].clone()).unwrap_or(Value::Null);
+    if let Some(obj) = realtime_comfort.as_object_mut() {
+        obj.remove("date");
+    }
+    json!({
+        "status": "ok",
+        "api_version": "v2.6",
+        "api_status": "active",
+        "lang": "zh_CN",
+        "unit": "metric",
+        "tzshift": place.offset.local_minus_utc(),
+        "server_time": now.timestamp(),
+        "location": [lat, lng],
+        "result": {
+            "realtime": {
+                "status": "ok",
+                "temperature": round2(current.temperature),
+                "humidity": round2(current.humidity),
+                "cloudrate": round2(current.cloud),
+                "skycon": current.sky,
+                "visibility": round2(current.visibility),
+                "dswrf": current.dswrf.round(),
+                "wind": { "speed": round2(current.wind), "direction": current.wind_direction.round() },
+                "pressure": current.pressure.round(),
+                "precipitation": {
+                    "local": { "status": "ok", "datasource": "demo", "intensity": round2(current.precipitation) },
+                    "nearest": { "status": "ok", "distance": 0.0, "intensity": round2(current.precipitation) },
+                },
+                "air_quality": current.air_quality(),
+                "life_index": {
+                    "ultraviolet": provider::ultraviolet(current.uv()),
+                    "comfort": realtime_comfort,
+                },
+            },
+            "minutely": minutely(&place, now, current, &hours[1]),
+            "hourly": hourly,
+            "daily": daily,
+            "alert": { "status": "ok", "content": alerts(&place, &today_summary, now) },
+            "primary": 0,
+        },
+    })
+}
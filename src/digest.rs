@@ -0,0 +1,247 @@
+// 每日天气邮件摘要（需 `email` feature）：订阅地址 + 地点 + 发送时刻，后台任务每天按时发送 HTML 邮件
+// - SMTP_HOST / SMTP_PORT / SMTP_USERNAME / SMTP_PASSWORD / SMTP_FROM
+// - SMTP_TLS：starttls（默认，端口 587）、tls（隐式 TLS，端口 465）或 none
+// - 发送时刻按服务器本地时区；退订链接基于 PUBLIC_URL
+// - 由定时任务 email_digest 每分钟检查一次（SCHEDULE_EMAIL_DIGEST 可调整）
+// 订阅保存在 DATA_DIR/digest_subscriptions.json
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Local, NaiveDate, Timelike};
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    base_path,
+    config::{env_nonempty, env_parse},
+    location, respond, scheduler, ssr,
+    store::JsonStore,
+    weather, AppState, ErrorResp,
+};
+
+const DEFAULT_HOUR: u32 = 7;
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Subscriber {
+    email: String,
+    name: String,
+    lng: f64,
+    lat: f64,
+    hour: u32,
+    // 退订凭据
+    token: String,
+    #[serde(default)]
+    last_sent: Option<NaiveDate>,
+}
+
+struct Digest {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    subscribers: JsonStore<Vec<Subscriber>>,
+}
+
+static DIGEST: OnceCell<Digest> = OnceCell::new();
+
+fn mailer_from_env(host: &str) -> anyhow::Result<AsyncSmtpTransport<Tokio1Executor>> {
+    let tls = env_nonempty("SMTP_TLS").unwrap_or_else(|| "starttls".into());
+    let mut builder = match tls.as_str() {
+        "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+        "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+        "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        other => anyhow::bail!("SMTP_TLS 取值应为 starttls/tls/none: {}", other),
+    };
+    if let Some(port) = env_parse::<u16>("SMTP_PORT")? {
+        builder = builder.port(port);
+    }
+    if let Some(user) = env_nonempty("SMTP_USERNAME") {
+        builder = builder.credentials(Credentials::new(user, env_nonempty("SMTP_PASSWORD").unwrap_or_default()));
+    }
+    Ok(builder.build())
+}
+
+// 读取 SMTP 配置并启动发送任务；未设置 SMTP_HOST 时订阅接口返回 503
+pub fn spawn_from_env(state: AppState) -> anyhow::Result<()> {
+    let Some(host) = env_nonempty("SMTP_HOST") else {
+        return Ok(());
+    };
+    let from = env_nonempty("SMTP_FROM")
+        .ok_or_else(|| anyhow::anyhow!("已设置 SMTP_HOST，但缺少 SMTP_FROM"))?
+        .parse::<Mailbox>()
+        .map_err(|e| anyhow::anyhow!("SMTP_FROM 无效: {}", e))?;
+    let digest = Digest {
+        mailer: mailer_from_env(&host)?,
+        from,
+        subscribers: JsonStore::open("digest_subscriptions")?,
+    };
+    let digest = DIGEST.get_or_init(|| digest);
+    info!("email digest enabled ({} subscriber(s))", digest.subscribers.read(|s| s.len()));
+
+    scheduler::register("email_digest", "* * * * *", move || {
+        let state = state.clone();
+        async move { digest.send_due(&state).await }
+    })
+}
+
+pub fn enabled() -> bool {
+    DIGEST.get().is_some()
+}
+
+fn unsubscribe_url(token: &str) -> Option<String> {
+    base_path::public_url().map(|base| format!("{}/api/v1/digest/unsubscribe?token={}", base, token))
+}
+
+impl Digest {
+    // 本地时间到达订阅时刻且今天尚未发送的订阅者
+    async fn send_due(&self, state: &AppState) -> anyhow::Result<()> {
+        let now = Local::now();
+        let today = now.date_naive();
+        let due: Vec<Subscriber> = self.subscribers.read(|subs| {
+            subs.iter()
+                .filter(|s| s.hour == now.hour() && s.last_sent != Some(today))
+                .cloned()
+                .collect()
+        });
+        let mut failed = 0;
+        for sub in due {
+            match self.send_one(state, &sub, today).await {
+                Ok(()) => {
+                    let saved = self.subscribers.update(|subs| {
+                        if let Some(s) = subs.iter_mut().find(|s| s.token == sub.token) {
+                            s.last_sent = Some(today);
+                        }
+                    });
+                    if let Err(e) = saved {
+                        warn!("保存摘要发送状态失败: {}", e);
+                    }
+                }
+                Err(e) => {
+                    warn!(email = %sub.email, "digest send failed: {}", e);
+                    failed += 1;
+                }
+            }
+        }
+        if failed > 0 {
+            anyhow::bail!("{} 封摘要发送失败", failed);
+        }
+        Ok(())
+    }
+
+    async fn send_one(&self, state: &AppState, sub: &Subscriber, today: NaiveDate) -> anyhow::Result<()> {
+        let weather = weather::fetch(state, sub.lng, sub.lat).await?;
+        let date = today.format("%m月%d日").to_string();
+        let html = ssr::TEMPLATES.get_template("digest.html")?.render(minijinja::context! {
+            name => sub.name,
+            date,
+            weather,
+            unsubscribe_url => unsubscribe_url(&sub.token),
+        })?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(sub.email.parse()?)
+            .subject(format!("{} {} 天气摘要", sub.name, date))
+            .header(ContentType::TEXT_HTML)
+            .body(html)?;
+        self.mailer.send(message).await?;
+        Ok(())
+    }
+}
+
+// 复用摘要的 SMTP 配置发送其他 HTML 邮件（如登录链接）
+pub async fn send_mail(to: &str, subject: &str, html: String) -> anyhow::Result<()> {
+    let Some(digest) = DIGEST.get() else {
+        anyhow::bail!("未配置邮件服务");
+    };
+    let message = Message::builder()
+        .from(digest.from.clone())
+        .to(to.parse()?)
+        .subject(subject)
+        .header(ContentType::TEXT_HTML)
+        .body(html)?;
+    digest.mailer.send(message).await?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct SubscribeBody {
+    email: String,
+    lng: f64,
+    lat: f64,
+    name: Option<String>,
+    hour: Option<u32>,
+}
+
+fn unavailable() -> Response {
+    respond::json(StatusCode::SERVICE_UNAVAILABLE, &ErrorResp::new("未配置邮件服务"))
+}
+
+// 同一邮箱重复订阅时覆盖地点与时刻
+#[tracing::instrument(skip_all)]
+pub async fn subscribe(State(state): State<AppState>, Json(body): Json<SubscribeBody>) -> Response {
+    let Some(digest) = DIGEST.get() else {
+        return unavailable();
+    };
+    let email = body.email.trim().to_string();
+    if email.parse::<lettre::Address>().is_err() {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new("邮箱地址无效"));
+    }
+    let hour = body.hour.unwrap_or(DEFAULT_HOUR);
+    if hour > 23 {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new("hour 取值 0-23"));
+    }
+    let name = match body.name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()) {
+        Some(n) => n,
+        None => location::reverse_geocode(&state, body.lat, body.lng).await,
+    };
+
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let sub = Subscriber { email, name, lng: body.lng, lat: body.lat, hour, token: token.clone(), last_sent: None };
+    let saved = digest.subscribers.update(|subs| {
+        subs.retain(|s| !s.email.eq_ignore_ascii_case(&sub.email));
+        subs.push(sub);
+    });
+    match saved {
+        Ok(()) => respond::json(
+            StatusCode::OK,
+            &serde_json::json!({ "ok": true, "hour": hour, "unsubscribe_token": token }),
+        ),
+        Err(e) => respond::json(StatusCode::INTERNAL_SERVER_ERROR, &ErrorResp::new(format!("保存订阅失败: {}", e))),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UnsubscribeQuery {
+    token: String,
+}
+
+// 邮件中的退订链接，返回简单的 HTML 提示
+pub async fn unsubscribe(Query(q): Query<UnsubscribeQuery>) -> Response {
+    let Some(digest) = DIGEST.get() else {
+        return unavailable();
+    };
+    let removed = digest.subscribers.update(|subs| {
+        let before = subs.len();
+        subs.retain(|s| s.token != q.token);
+        before != subs.len()
+    });
+    let (status, text) = match removed {
+        Ok(true) => (StatusCode::OK, "已退订每日天气摘要"),
+        Ok(false) => (StatusCode::NOT_FOUND, "退订链接无效或已退订"),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "退订失败，请稍后重试"),
+    };
+    (
+        status,
+        [(header::CONTENT_TYPE, HeaderValue::from_static("text/html; charset=utf-8"))],
+        format!("<!DOCTYPE html><meta charset=\"utf-8\"><title>退订</title><p>{}</p>", text),
+    )
+        .into_response()
+}
@@ -0,0 +1,168 @@
+// 上游域名解析：可改用进程内解析器（hickory，按 TTL 缓存），支持自定义 DNS 服务器（含 DoH）与静态 IP 绑定
+// 未设置时沿用系统 getaddrinfo；经 socks5h 代理的请求由代理端解析，不受影响
+// - UPSTREAM_DNS：逗号分隔的 DNS 服务器，`1.1.1.1`、`119.29.29.29:53`（UDP+TCP）或 `https://dns.alidns.com/dns-query`（DoH，
+//   域名形式的 DoH 地址在启动时用系统 DNS 解析一次）；`system` 表示读取 /etc/resolv.conf，仅启用缓存
+// - UPSTREAM_DNS_CACHE_SIZE：缓存记录数，默认 1024
+// - UPSTREAM_DNS_MIN_TTL_SECS：缓存最短保留秒数，上游 TTL 过短时减少查询
+// - UPSTREAM_HOSTS：静态绑定，优先于 DNS，如 `api.caiyunapp.com=1.2.3.4|1.2.3.5,restapi.amap.com=5.6.7.8`
+
+use std::{
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    sync::Arc,
+    time::Duration,
+};
+
+use hickory_resolver::{
+    config::{NameServerConfig, NameServerConfigGroup, ResolverConfig},
+    name_server::TokioConnectionProvider,
+    proto::xfer::Protocol,
+    TokioResolver,
+};
+use once_cell::sync::OnceCell;
+use reqwest::{
+    dns::{Name, Resolve, Resolving},
+    ClientBuilder,
+};
+use tracing::info;
+
+use crate::config::{env_list, env_parse};
+
+const DEFAULT_CACHE_SIZE: usize = 1024;
+
+struct HickoryResolve(TokioResolver);
+
+impl Resolve for HickoryResolve {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.0.clone();
+        Box::pin(async move {
+            let lookup = resolver.lookup_ip(name.as_str()).await?;
+            // 端口由 reqwest 按 URL 填充
+            let addrs: Vec<SocketAddr> = lookup.iter().map(|ip| SocketAddr::new(ip, 0)).collect();
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+#[derive(Default)]
+struct Dns {
+    resolver: Option<Arc<HickoryResolve>>,
+    pins: Vec<(String, Vec<SocketAddr>)>,
+}
+
+static DNS: OnceCell<Dns> = OnceCell::new();
+
+fn name_servers(entry: &str) -> anyhow::Result<Vec<NameServerConfig>> {
+    if let Some(rest) = entry.strip_prefix("https://") {
+        let url = reqwest::Url::parse(entry).map_err(|e| anyhow::anyhow!("UPSTREAM_DNS 地址无效: {} ({})", entry, e))?;
+        let host = url.host_str().unwrap_or(rest).trim_matches(['[', ']']).to_string();
+        let port = url.port().unwrap_or(443);
+        let ips: Vec<IpAddr> = match host.parse::<IpAddr>() {
+            Ok(ip) => vec![ip],
+            Err(_) => (host.as_str(), port)
+                .to_socket_addrs()
+                .map_err(|e| anyhow::anyhow!("无法解析 DoH 服务器 {}: {}", host, e))?
+                .map(|a| a.ip())
+                .collect(),
+        };
+        let endpoint = Some(url.path()).filter(|p| *p != "/").map(str::to_string);
+        return Ok(ips
+            .into_iter()
+            .map(|ip| NameServerConfig {
+                tls_dns_name: Some(host.clone()),
+                http_endpoint: endpoint.clone(),
+                ..NameServerConfig::new(SocketAddr::new(ip, port), Protocol::Https)
+            })
+            .collect());
+    }
+    let addr = entry
+        .parse::<SocketAddr>()
+        .or_else(|_| entry.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+        .map_err(|_| anyhow::anyhow!("UPSTREAM_DNS 应为 IP、IP:端口 或 https:// 地址: {}", entry))?;
+    Ok(vec![NameServerConfig::new(addr, Protocol::Udp), NameServerConfig::new(addr, Protocol::Tcp)])
+}
+
+fn resolver_from_env() -> anyhow::Result<Option<TokioResolver>> {
+    let Some(servers) = env_list("UPSTREAM_DNS") else {
+        return Ok(None);
+    };
+    let mut builder = if servers.len() == 1 && servers[0] == "system" {
+        TokioResolver::builder_tokio()?
+    } else {
+        let mut group = NameServerConfigGroup::new();
+        for entry in &servers {
+            if entry == "system" {
+                anyhow::bail!("UPSTREAM_DNS=system 不能与其他服务器同时使用");
+            }
+            group.extend(name_servers(entry)?);
+        }
+        TokioResolver::builder_with_config(ResolverConfig::from_parts(None, vec![], group), TokioConnectionProvider::default())
+    };
+    let opts = builder.options_mut();
+    opts.cache_size = env_parse("UPSTREAM_DNS_CACHE_SIZE")?.unwrap_or(DEFAULT_CACHE_SIZE);
+    if let Some(secs) = env_parse::<u64>("UPSTREAM_DNS_MIN_TTL_SECS")? {
+        opts.positive_min_ttl = Some(Duration::from_secs(secs));
+    }
+    info!("upstream dns: {}", servers.join(","));
+    Ok(Some(builder.build()))
+}
+
+fn pins_from_env() -> anyhow::Result<Vec<(String, Vec<SocketAddr>)>> {
+    env_list("UPSTREAM_HOSTS")
+        .unwrap_or_default()
+        .iter()
+        .map(|item| {
+            let (host, ips) = item
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("UPSTREAM_HOSTS 格式应为 域名=IP: {}", item))?;
+            let addrs = ips
+                .split('|')
+                .map(|ip| {
+                    ip.trim()
+                        .parse::<IpAddr>()
+                        .map(|ip| SocketAddr::new(ip, 0))
+                        .map_err(|_| anyhow::anyhow!("UPSTREAM_HOSTS 中的 IP 无效: {}", ip))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok((host.trim().to_ascii_lowercase(), addrs))
+        })
+        .collect()
+}
+
+// 需在建立上游 Client 之前调用
+pub fn init_from_env() -> anyhow::Result<()> {
+    let dns = Dns {
+        resolver: resolver_from_env()?.map(|r| Arc::new(HickoryResolve(r))),
+        pins: pins_from_env()?,
+    };
+    let _ = DNS.set(dns);
+    Ok(())
+}
+
+// 按上游请求相同的规则解析域名（静态绑定 > UPSTREAM_DNS > 系统解析），供自检使用
+pub async fn lookup(host: &str) -> anyhow::Result<Vec<IpAddr>> {
+    let dns = DNS.get();
+    if let Some((_, addrs)) = dns.and_then(|d| d.pins.iter().find(|(h, _)| h == host)) {
+        return Ok(addrs.iter().map(SocketAddr::ip).collect());
+    }
+    let ips: Vec<IpAddr> = match dns.and_then(|d| d.resolver.as_ref()) {
+        Some(resolver) => resolver.0.lookup_ip(host).await?.iter().collect(),
+        None => tokio::net::lookup_host((host, 0)).await?.map(|a| a.ip()).collect(),
+    };
+    if ips.is_empty() {
+        anyhow::bail!("没有解析到地址");
+    }
+    Ok(ips)
+}
+
+pub fn apply(mut builder: ClientBuilder) -> ClientBuilder {
+    let Some(dns) = DNS.get() else {
+        return builder;
+    };
+    if let Some(resolver) = &dns.resolver {
+        builder = builder.dns_resolver(resolver.clone());
+    }
+    for (host, addrs) in &dns.pins {
+        builder = builder.resolve_to_addrs(host, addrs);
+    }
+    builder
+}
@@ -0,0 +1,186 @@
+// 自检：`caiyun-weather-rust doctor` 校验配置、解析上游域名、对每个已配置的数据源发起一次真实请求、检查静态文件与内置模板，
+// 逐项输出绿/红结果，有失败项时以非零状态退出；部署后或排查故障时先跑一遍
+// 管理接口 GET /api/v1/admin/diagnostics 返回同样的检查（配置项即服务启动时的校验结果）与各功能开关的当前取值，有失败项时状态码为 503
+// 数据源请求不回退、不经上游结果缓存，直接反映各自的密钥与连通性；未配置密钥的数据源记为跳过
+
+use std::io::IsTerminal;
+
+use axum::{
+    extract::State,
+    http::{header, HeaderValue, StatusCode},
+    response::Response,
+};
+use futures_util::future::join_all;
+use serde::Serialize;
+
+use crate::{assets, chaos, demo, dns, feature_flags, mock, provider, redact, respond, ssr, weather::FetchError, AppState};
+
+// 探测用坐标：北京天安门
+const PROBE_LNG: f64 = 116.3975;
+const PROBE_LAT: f64 = 39.9087;
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Ok,
+    Fail,
+    Skip,
+}
+
+#[derive(Serialize)]
+struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, result: Result<String, String>) -> Self {
+        let (status, detail) = match result {
+            Ok(detail) => (Status::Ok, detail),
+            Err(detail) => (Status::Fail, detail),
+        };
+        Self { name: name.into(), status, detail: redact::redact(&detail), duration_ms: None }
+    }
+}
+
+fn config_summary() -> String {
+    let mut summary = format!("数据源 {}", provider::names().join(" → "));
+    if mock::enabled() {
+        summary.push_str("；MOCK_MODE 开启，天气接口返回模拟数据");
+    }
+    if demo::enabled() {
+        summary.push_str("；DEMO_MODE 开启，天气接口返回生成的演示数据");
+    }
+    if chaos::enabled() {
+        summary.push_str("；故障注入（CHAOS_*）开启，上游调用会随机变慢或失败");
+    }
+    summary
+}
+
+// 天气数据源与地理编码用到的上游主机
+fn upstream_hosts(state: &AppState) -> Vec<String> {
+    let urls = &state.base_urls;
+    let mut bases: Vec<&str> = provider::names()
+        .into_iter()
+        .map(|name| match name {
+            "qweather" => urls.qweather.as_str(),
+            "open_meteo" => urls.open_meteo.as_str(),
+            "openweathermap" => urls.openweathermap.as_str(),
+            _ => urls.caiyun.as_str(),
+        })
+        .collect();
+    bases.push(&urls.meituan);
+    if state.amap_key.is_some() {
+        bases.push(&urls.amap);
+    }
+    let mut hosts: Vec<String> = Vec::new();
+    for host in bases.iter().filter_map(|b| reqwest::Url::parse(b).ok()?.host_str().map(str::to_string)) {
+        if !hosts.contains(&host) {
+            hosts.push(host);
+        }
+    }
+    hosts
+}
+
+async fn resolve(host: String) -> Check {
+    let result = dns::lookup(&host).await.map_err(|e| format!("解析失败: {}", e)).map(|ips| {
+        ips.iter().take(4).map(ToString::to_string).collect::<Vec<_>>().join(", ")
+    });
+    Check::new(format!("dns {}", host), result)
+}
+
+// 上游错误状态携带的 URL 可能含 token，只保留状态码
+fn describe(e: &FetchError) -> String {
+    match e {
+        FetchError::Status(e) => match e.status() {
+            Some(status) => format!("上游返回 HTTP {}", status),
+            None => "上游返回错误状态".into(),
+        },
+        e => e.to_string(),
+    }
+}
+
+fn templates() -> Result<String, String> {
+    std::panic::catch_unwind(|| ssr::TEMPLATES.templates().count())
+        .map(|n| format!("{} 个模板", n))
+        .map_err(|_| "内置模板解析失败".into())
+}
+
+// 配置之外的各项检查；域名解析与数据源请求并发进行
+async fn diagnose(state: &AppState) -> Vec<Check> {
+    let (mut checks, probes) = tokio::join!(
+        join_all(upstream_hosts(state).into_iter().map(resolve)),
+        provider::probe_each(state, PROBE_LNG, PROBE_LAT)
+    );
+    for (name, elapsed, result) in probes {
+        let check = match result {
+            Ok(()) => Check::new(format!("provider {}", name), Ok("请求成功".into())),
+            Err(FetchError::NotConfigured(key)) => Check {
+                name: format!("provider {}", name),
+                status: Status::Skip,
+                detail: format!("未配置 {}", key),
+                duration_ms: None,
+            },
+            Err(e) => Check::new(format!("provider {}", name), Err(describe(&e))),
+        };
+        let duration_ms = (check.status != Status::Skip).then_some(elapsed.as_millis() as u64);
+        checks.push(Check { duration_ms, ..check });
+    }
+    checks.push(Check::new("static", assets::check()));
+    checks.push(Check::new("templates", templates()));
+    checks.push(Check::new("feature_flags", feature_flags::summary()));
+    checks
+}
+
+fn print(check: &Check, color: bool) {
+    let (mark, code) = match check.status {
+        Status::Ok => ("✓", "32"),
+        Status::Fail => ("✗", "31"),
+        Status::Skip => ("-", "90"),
+    };
+    let elapsed = check.duration_ms.map(|ms| format!(" ({} ms)", ms)).unwrap_or_default();
+    if color {
+        println!("\x1b[{}m{}\x1b[0m {:<28} {}{}", code, mark, check.name, check.detail, elapsed);
+    } else {
+        println!("{} {:<28} {}{}", mark, check.name, check.detail, elapsed);
+    }
+}
+
+// doctor 子命令
+pub async fn run() -> anyhow::Result<()> {
+    let color = std::io::stdout().is_terminal();
+    let state = match crate::init_from_env() {
+        Ok(state) => {
+            print(&Check::new("config", Ok(config_summary())), color);
+            state
+        }
+        Err(e) => {
+            print(&Check::new("config", Err(e.to_string())), color);
+            anyhow::bail!("配置无效，其余检查未执行");
+        }
+    };
+    let checks = diagnose(&state).await;
+    for check in &checks {
+        print(check, color);
+    }
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failed > 0 {
+        anyhow::bail!("{} 项检查未通过", failed);
+    }
+    Ok(())
+}
+
+// GET /api/v1/admin/diagnostics
+pub async fn admin_diagnostics(State(state): State<AppState>) -> Response {
+    let mut checks = vec![Check::new("config", Ok(config_summary()))];
+    checks.extend(diagnose(&state).await);
+    let ok = checks.iter().all(|c| c.status != Status::Fail);
+    let status = if ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let body = serde_json::json!({ "ok": ok, "checks": checks, "flags": feature_flags::snapshot() });
+    let mut res = respond::json(status, &body);
+    res.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    res
+}
@@ -0,0 +1,111 @@
+// 错误上报：可选接入 Sentry（需 `sentry` feature + SENTRY_DSN），上报 panic、上游解析失败与 5xx 响应
+// 请求上下文仅包含路由、request_id 与约 0.1° 精度的坐标，避免泄露精确位置；事件消息发送前经 redact 去掉 token、key
+
+use axum::{extract::Request, middleware::Next, response::Response};
+
+// 持有 Sentry 客户端，退出时 flush 未发送的事件
+pub struct Guard {
+    #[cfg(feature = "sentry")]
+    _client: Option<sentry::ClientInitGuard>,
+}
+
+pub fn init() -> Guard {
+    let dsn = std::env::var("SENTRY_DSN").ok().filter(|s| !s.trim().is_empty());
+
+    #[cfg(feature = "sentry")]
+    {
+        let client = dsn.map(|dsn| {
+            let environment = std::env::var("SENTRY_ENVIRONMENT").ok().map(Into::into);
+            sentry::init((
+                dsn,
+                sentry::ClientOptions {
+                    release: sentry::release_name!(),
+                    environment,
+                    before_send: Some(std::sync::Arc::new(|mut event| {
+                        event.message = event.message.map(|m| crate::redact::redact(&m));
+                        for exception in &mut event.exception.values {
+                            exception.value = exception.value.as_deref().map(crate::redact::redact);
+                        }
+                        Some(event)
+                    })),
+                    ..Default::default()
+                },
+            ))
+        });
+        if client.as_ref().is_some_and(|c| c.is_enabled()) {
+            tracing::info!("sentry error reporting enabled");
+        }
+        Guard { _client: client }
+    }
+
+    #[cfg(not(feature = "sentry"))]
+    {
+        if dsn.is_some() {
+            tracing::warn!("SENTRY_DSN 已设置，但未启用 `sentry` feature，忽略");
+        }
+        Guard {}
+    }
+}
+
+// 坐标保留 1 位小数（约 10km）
+#[cfg(feature = "sentry")]
+fn rounded_coord(query: &std::collections::HashMap<String, String>, key: &str) -> Option<String> {
+    let v: f64 = query.get(key)?.parse().ok()?;
+    Some(format!("{:.1}", v))
+}
+
+// 为每个请求绑定独立的 Hub 与上下文；处理结果为 5xx 时上报
+pub async fn middleware(req: Request, next: Next) -> Response {
+    #[cfg(feature = "sentry")]
+    {
+        use sentry::SentryFutureExt;
+
+        if sentry::Hub::current().client().is_none() {
+            return next.run(req).await;
+        }
+        let route = req
+            .extensions()
+            .get::<axum::extract::MatchedPath>()
+            .map(|p| p.as_str().to_string())
+            .unwrap_or_else(|| req.uri().path().to_string());
+        let query = axum::extract::Query::<std::collections::HashMap<String, String>>::try_from_uri(req.uri())
+            .map(|q| q.0)
+            .unwrap_or_default();
+
+        let hub = std::sync::Arc::new(sentry::Hub::new_from_top(sentry::Hub::current()));
+        hub.configure_scope(|scope| {
+            scope.set_tag("route", &route);
+            if let Some(id) = req.headers().get(&crate::request_id::X_REQUEST_ID).and_then(|v| v.to_str().ok()) {
+                scope.set_tag("request_id", id);
+            }
+            if let (Some(lng), Some(lat)) = (rounded_coord(&query, "lng"), rounded_coord(&query, "lat")) {
+                scope.set_extra("location", serde_json::json!({"lng": lng, "lat": lat}));
+            }
+        });
+
+        let resp = next.run(req).bind_hub(hub.clone()).await;
+        // 同一请求内已上报过（如上游解析失败）则不重复上报
+        if resp.status().is_server_error() && hub.last_event_id().is_none() {
+            hub.capture_message(
+                &format!("{} {}", resp.status().as_u16(), route),
+                sentry::Level::Error,
+            );
+        }
+        resp
+    }
+
+    #[cfg(not(feature = "sentry"))]
+    next.run(req).await
+}
+
+// 上游响应无法解析/格式化等非 5xx 但值得关注的错误
+pub fn capture_upstream_error(upstream: &str, err: &dyn std::fmt::Display) {
+    #[cfg(feature = "sentry")]
+    sentry::with_scope(
+        |scope| scope.set_tag("upstream", upstream),
+        || sentry::capture_message(&format!("上游数据异常: {}", err), sentry::Level::Error),
+    );
+
+    #[cfg(not(feature = "sentry"))]
+    let _ = (upstream, err);
+}
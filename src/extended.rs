@@ -0,0 +1,134 @@
+// 15 天扩展预报：/api/v1/weather/extended?lng=&lat=&days=15 请求彩云逐日预报（dailysteps），逐日字段同 /api/weather 的 daily
+// 部分套餐只提供较少天数：按上游实际返回的天数输出，complete 为 false 并在 missing_dates 中列出缺失的日期
+// weeks 按 7 天一组给出最低/最高气温、平均高低温、雨天数与累计降水，供周视图直接展示
+// 结果按约 1 km 网格与天数缓存 30 分钟（逐日预报更新慢，不必每次请求上游）
+// lang 同 /api/weather：缓存中为默认（中文）结果，返回前再按语言转换 daily
+
+use std::time::Duration;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+};
+use chrono::{Days, NaiveDate};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{cache::TtlCache, i18n, mock, respond, upstream_cache, weather, AppState, ErrorResp};
+
+const MAX_DAYS: usize = 15;
+// 降水概率达到该值记为雨天
+const RAIN_PROBABILITY: i64 = 50;
+
+// 键：(经度×100, 纬度×100, 天数, 自带 token 的租户)
+type Key = (i32, i32, usize, Option<&'static str>);
+
+static CACHE: Lazy<TtlCache<Key, Value>> = Lazy::new(|| TtlCache::new(Duration::from_secs(1800), 1024));
+
+#[derive(Deserialize)]
+pub struct ExtendedQuery {
+    lng: f64,
+    lat: f64,
+    days: Option<usize>,
+    lang: Option<String>,
+}
+
+// 上游逐日日期（YYYY-MM-DD），用于推算缺失的日期
+fn upstream_dates(raw: &Value) -> Vec<NaiveDate> {
+    raw.pointer("/result/daily/temperature")
+        .and_then(Value::as_array)
+        .map(|days| {
+            days.iter()
+                .filter_map(|d| d.get("date")?.as_str()?.get(..10))
+                .filter_map(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+// dates 为该周各天的完整日期（daily 中的 date 只有月-日）
+fn week_summary(days: &[Value], dates: &[NaiveDate]) -> Value {
+    let temps = |key: &str| days.iter().filter_map(|d| d.get(key)?.as_f64()).collect::<Vec<_>>();
+    let (lows, highs) = (temps("min_temp"), temps("max_temp"));
+    let mean = |v: &[f64]| (!v.is_empty()).then(|| round1(v.iter().sum::<f64>() / v.len() as f64));
+    json!({
+        "start": dates.first().map(|d| d.format("%Y-%m-%d").to_string()),
+        "end": dates.last().map(|d| d.format("%Y-%m-%d").to_string()),
+        "days": days.len(),
+        "min_temp": lows.iter().copied().reduce(f64::min),
+        "max_temp": highs.iter().copied().reduce(f64::max),
+        "avg_min_temp": mean(&lows),
+        "avg_max_temp": mean(&highs),
+        "rain_days": days
+            .iter()
+            .filter(|d| d.pointer("/precipitation/probability").and_then(Value::as_i64).unwrap_or(0) >= RAIN_PROBABILITY)
+            .count(),
+        "precipitation_total": round1(
+            days.iter().filter_map(|d| d.pointer("/precipitation/total").and_then(Value::as_f64)).sum::<f64>()
+        ),
+    })
+}
+
+fn build(raw: &Value, lng: f64, requested: usize) -> Value {
+    let daily = weather::format_daily(raw, lng, requested);
+    let dates = upstream_dates(raw);
+    let missing_dates: Vec<String> = match daily.len().checked_sub(1).and_then(|i| dates.get(i)) {
+        Some(last) => (1..=(requested - daily.len()) as u64)
+            .filter_map(|k| last.checked_add_days(Days::new(k)))
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .collect(),
+        None => Vec::new(),
+    };
+    json!({
+        "requested_days": requested,
+        "available_days": daily.len(),
+        "complete": daily.len() >= requested,
+        "missing_dates": missing_dates,
+        "weeks": daily
+            .chunks(7)
+            .enumerate()
+            .map(|(w, days)| week_summary(days, dates.get(w * 7..(w * 7 + days.len()).min(dates.len())).unwrap_or(&[])))
+            .collect::<Vec<_>>(),
+        "daily": daily,
+    })
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn extended(State(state): State<AppState>, Query(q): Query<ExtendedQuery>) -> Response {
+    let days = q.days.unwrap_or(MAX_DAYS);
+    if !(1..=MAX_DAYS).contains(&days) {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(format!("days 应在 1 到 {} 之间", MAX_DAYS)));
+    }
+    let locale = match i18n::Locale::parse(q.lang.as_deref()) {
+        Ok(l) => l,
+        Err(e) => return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(e)),
+    };
+    let scope = upstream_cache::scope(upstream_cache::Kind::Weather);
+    let key = ((q.lng * 100.0).round() as i32, (q.lat * 100.0).round() as i32, days, scope);
+    let cached = if mock::enabled() { None } else { CACHE.get(&key) };
+    let mut body = match cached {
+        Some(hit) => hit,
+        None => match weather::fetch_caiyun_daily(&state, q.lng, q.lat, days).await {
+            Ok(raw) => {
+                let body = build(&raw, q.lng, days);
+                if !mock::enabled() {
+                    CACHE.insert(key, body.clone());
+                }
+                body
+            }
+            Err(e) => return respond::json(e.status(), &ErrorResp::new(e.to_string())),
+        },
+    };
+    if let Some(locale) = locale {
+        for day in body["daily"].as_array_mut().into_iter().flatten() {
+            i18n::localize_day(day, locale);
+        }
+    }
+    respond::json(StatusCode::OK, &body)
+}
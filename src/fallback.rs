@@ -0,0 +1,52 @@
+// 未匹配路由的兜底：/api 下及非浏览器请求返回 JSON 错误 { error, code: "NOT_FOUND", request_id }，浏览器请求返回 404 页面
+// - SPA_FALLBACK=1：接受 HTML 的 GET 请求（/api、/static 之外）改为返回首页，交给前端路由处理
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::Query,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+
+use crate::{base_path, config::env_flag, request_id, respond, ssr, ErrorResp};
+
+static SPA_FALLBACK: Lazy<bool> = Lazy::new(|| env_flag("SPA_FALLBACK"));
+
+fn accepts_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"))
+}
+
+fn not_found_page(path: &str) -> Response {
+    let ctx = minijinja::context! { path, request_id => request_id::current() };
+    match ssr::TEMPLATES.get_template("404.html").and_then(|t| t.render(ctx)) {
+        Ok(html) => (
+            StatusCode::NOT_FOUND,
+            [(header::CONTENT_TYPE, HeaderValue::from_static("text/html; charset=utf-8"))],
+            base_path::rewrite_html(&html),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("render 404.html failed: {}", e);
+            (StatusCode::NOT_FOUND, "页面不存在").into_response()
+        }
+    }
+}
+
+pub async fn handler(method: Method, uri: Uri, headers: HeaderMap) -> Response {
+    let path = uri.path();
+    let is_api = path == "/api" || path.starts_with("/api/");
+    if is_api || !accepts_html(&headers) {
+        let msg = if is_api { "接口不存在" } else { "页面不存在" };
+        return respond::json(StatusCode::NOT_FOUND, &ErrorResp::with_code("NOT_FOUND", msg));
+    }
+    if *SPA_FALLBACK && (method == Method::GET || method == Method::HEAD) && !path.starts_with("/static/") {
+        let query = Query::<HashMap<String, String>>::try_from_uri(&uri).map(|q| q.0).unwrap_or_default();
+        return crate::index(headers, Query(query)).await.into_response();
+    }
+    not_found_page(path)
+}
@@ -0,0 +1,188 @@
+// 功能开关：实验性接口与数据源可在部署时关闭（先上线、默认不暴露），无需重新编译；关闭的接口返回 404（code: FEATURE_DISABLED）
+// - FEATURE_FLAGS：逗号分隔的 `名称=on|off`，如 voice=off,provider_open_meteo=off
+// - FEATURE_FLAGS_FILE：TOML 文件，每行 `名称 = true|false`，优先于 FEATURE_FLAGS；由定时任务 feature_flags_reload
+//   每分钟重新读取（SCHEDULE_FEATURE_FLAGS_RELOAD 可调整，修改后也可经管理接口立即运行该任务），内容无效时保留原值并在自检中报错
+// 开关一览见 FLAGS；当前取值由 /api/v1/config 的 flags 与 /api/v1/admin/diagnostics 给出
+// 新增实验性功能时在 FLAGS 中登记（默认 false 即为暗发布），接口路由加 gate 层，其他代码调用 enabled
+
+use std::{collections::BTreeMap, sync::RwLock};
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use once_cell::sync::Lazy;
+use tracing::{info, warn};
+
+use crate::{
+    config::{env_list, env_nonempty},
+    provider, respond, scheduler, ErrorResp,
+};
+
+// 名称、默认值、说明；数据源开关名为 provider_<WEATHER_PROVIDERS 中的名称>
+const FLAGS: &[(&str, bool, &str)] = &[
+    ("graphql", true, "GraphQL 接口 /graphql（需编译 graphql feature）"),
+    ("extended_forecast", true, "15 天预报 /api/v1/weather/extended"),
+    ("brief", true, "一句话播报 /api/v1/weather/brief"),
+    ("voice", true, "语音助手接口 /api/v1/voice"),
+    ("provider_caiyun", true, "数据源：彩云"),
+    ("provider_qweather", true, "数据源：和风天气"),
+    ("provider_open_meteo", true, "数据源：Open-Meteo"),
+    ("provider_openweathermap", true, "数据源：OpenWeatherMap"),
+];
+
+struct Flags {
+    values: BTreeMap<&'static str, bool>,
+    // 文件上次成功应用的内容，未变化时不重复解析
+    file_content: Option<String>,
+    // 最近一次读取文件失败的原因
+    error: Option<String>,
+}
+
+static FLAGS_STATE: Lazy<RwLock<Flags>> = Lazy::new(|| {
+    RwLock::new(Flags {
+        values: FLAGS.iter().map(|(name, default, _)| (*name, *default)).collect(),
+        file_content: None,
+        error: None,
+    })
+});
+
+fn lookup(name: &str) -> anyhow::Result<&'static str> {
+    FLAGS.iter().map(|(n, _, _)| *n).find(|n| *n == name).ok_or_else(|| {
+        let known = FLAGS.iter().map(|(n, _, _)| *n).collect::<Vec<_>>().join(", ");
+        anyhow::anyhow!("未知的功能开关 {}（可选：{}）", name, known)
+    })
+}
+
+fn parse_bool(name: &str, raw: &str) -> anyhow::Result<bool> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => anyhow::bail!("功能开关 {} 的值无效: {}（应为 on 或 off）", name, raw),
+    }
+}
+
+// 默认值叠加 FEATURE_FLAGS
+fn from_env() -> anyhow::Result<BTreeMap<&'static str, bool>> {
+    let mut values: BTreeMap<&'static str, bool> = FLAGS.iter().map(|(name, default, _)| (*name, *default)).collect();
+    for item in env_list("FEATURE_FLAGS").unwrap_or_default() {
+        let Some((name, value)) = item.split_once('=') else {
+            anyhow::bail!("FEATURE_FLAGS 中的项应为 名称=on|off: {}", item);
+        };
+        let name = lookup(name.trim())?;
+        values.insert(name, parse_bool(name, value)?);
+    }
+    Ok(values)
+}
+
+// 在 FEATURE_FLAGS 的基础上应用文件内容
+fn apply_file(text: &str) -> anyhow::Result<BTreeMap<&'static str, bool>> {
+    let table: BTreeMap<String, toml::Value> = toml::from_str(text)?;
+    let mut values = from_env()?;
+    for (name, value) in table {
+        let name = lookup(&name)?;
+        let value = value.as_bool().ok_or_else(|| anyhow::anyhow!("功能开关 {} 应为 true 或 false", name))?;
+        values.insert(name, value);
+    }
+    validate(&values)?;
+    Ok(values)
+}
+
+// 至少保留一个已配置的数据源
+fn validate(values: &BTreeMap<&'static str, bool>) -> anyhow::Result<()> {
+    let names = provider::names();
+    if !names.iter().any(|n| values.get(format!("provider_{}", n).as_str()).copied().unwrap_or(true)) {
+        anyhow::bail!("WEATHER_PROVIDERS 中的数据源（{}）不能全部关闭", names.join(", "));
+    }
+    Ok(())
+}
+
+fn read_file(path: &str) -> anyhow::Result<String> {
+    std::fs::read_to_string(path).map_err(|e| anyhow::anyhow!("无法读取 {}: {}", path, e))
+}
+
+// 重新读取 FEATURE_FLAGS_FILE；内容未变化时跳过
+fn reload(path: &str) -> anyhow::Result<()> {
+    let result = read_file(path).and_then(|text| {
+        if FLAGS_STATE.read().unwrap().file_content.as_deref() == Some(text.as_str()) {
+            return Ok(None);
+        }
+        let values = apply_file(&text).map_err(|e| anyhow::anyhow!("{} 无效: {}", path, e))?;
+        Ok(Some((text, values)))
+    });
+    let mut flags = FLAGS_STATE.write().unwrap();
+    match result {
+        Ok(Some((text, values))) => {
+            let changed: Vec<String> = values
+                .iter()
+                .filter(|(name, value)| flags.values.get(*name) != Some(*value))
+                .map(|(name, value)| format!("{}={}", name, if *value { "on" } else { "off" }))
+                .collect();
+            if !changed.is_empty() {
+                info!(changed = %changed.join(","), "feature flags reloaded");
+            }
+            flags.values = values;
+            flags.file_content = Some(text);
+            flags.error = None;
+            Ok(())
+        }
+        Ok(None) => {
+            flags.error = None;
+            Ok(())
+        }
+        Err(e) => {
+            warn!("feature flags reload failed, keeping previous values: {}", e);
+            flags.error = Some(e.to_string());
+            Err(e)
+        }
+    }
+}
+
+// 须在 provider::init_from_env 之后调用（校验数据源开关）
+pub fn init_from_env() -> anyhow::Result<()> {
+    let values = from_env()?;
+    validate(&values)?;
+    FLAGS_STATE.write().unwrap().values = values;
+    let Some(path) = env_nonempty("FEATURE_FLAGS_FILE") else {
+        return Ok(());
+    };
+    reload(&path)?;
+    scheduler::register("feature_flags_reload", "* * * * *", move || {
+        let path = path.clone();
+        async move { reload(&path) }
+    })
+}
+
+pub fn enabled(name: &str) -> bool {
+    FLAGS_STATE.read().unwrap().values.get(name).copied().unwrap_or(false)
+}
+
+// 数据源是否启用；未登记的数据源视为启用
+pub fn provider_enabled(name: &str) -> bool {
+    FLAGS_STATE.read().unwrap().values.get(format!("provider_{}", name).as_str()).copied().unwrap_or(true)
+}
+
+// 名称 → 当前取值
+pub fn snapshot() -> BTreeMap<&'static str, bool> {
+    FLAGS_STATE.read().unwrap().values.clone()
+}
+
+// 自检摘要：关闭的开关，以及最近一次重新读取失败的原因
+pub fn summary() -> Result<String, String> {
+    let flags = FLAGS_STATE.read().unwrap();
+    if let Some(e) = &flags.error {
+        return Err(format!("{}（沿用上次的取值）", e));
+    }
+    let off: Vec<&str> = flags.values.iter().filter(|(_, on)| !**on).map(|(name, _)| *name).collect();
+    Ok(if off.is_empty() { format!("{} 个开关全部开启", flags.values.len()) } else { format!("已关闭: {}", off.join(", ")) })
+}
+
+// 路由层：`.layer(from_fn_with_state("voice", feature_flags::gate))`，开关关闭时返回 404
+pub async fn gate(State(name): State<&'static str>, req: Request, next: Next) -> Response {
+    if enabled(name) {
+        return next.run(req).await;
+    }
+    respond::json(StatusCode::NOT_FOUND, &ErrorResp::with_code("FEATURE_DISABLED", format!("功能 {} 未开启", name)))
+}
@@ -0,0 +1,46 @@
+// GeoJSON 输出：?format=geojson 时把天气/地点结果包装为 Feature / FeatureCollection，可直接加入 Leaflet、MapLibre 图层
+// 坐标按 GeoJSON 规范为 [经度, 纬度]；properties 为原响应字段
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::location::Place;
+
+// ?format= 的取值；缺省为普通 JSON
+#[derive(PartialEq)]
+pub enum Format {
+    Json,
+    GeoJson,
+}
+
+impl Format {
+    pub fn parse(raw: Option<&str>) -> Result<Self, String> {
+        match raw {
+            None | Some("json") => Ok(Self::Json),
+            Some("geojson") => Ok(Self::GeoJson),
+            Some(other) => Err(format!("不支持的格式: {}（可选 json、geojson）", other)),
+        }
+    }
+}
+
+fn point(lng: f64, lat: f64) -> Value {
+    json!({ "type": "Point", "coordinates": [lng, lat] })
+}
+
+pub fn feature<T: Serialize>(lng: f64, lat: f64, properties: &T) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": point(lng, lat),
+        "properties": properties,
+    })
+}
+
+pub fn places(places: &[Place]) -> Value {
+    json!({
+        "type": "FeatureCollection",
+        "features": places
+            .iter()
+            .map(|p| feature(p.lng, p.lat, &json!({ "name": p.name, "address": p.address })))
+            .collect::<Vec<_>>(),
+    })
+}
@@ -0,0 +1,246 @@
+// GraphQL 接口（需 `graphql` feature）：POST /graphql 执行查询，客户端按需选取字段
+// - GET /graphql：GraphiQL 调试页面，仅 debug 构建或 GRAPHIQL=1 时提供
+
+use async_graphql::{http::GraphiQLSource, EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+use crate::{config::env_flag, location, redact, weather, AppState};
+
+type WeatherSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+static SCHEMA: Lazy<WeatherSchema> =
+    Lazy::new(|| Schema::build(QueryRoot, EmptyMutation, EmptySubscription).finish());
+
+fn text(v: &Value, key: &str) -> String {
+    v.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string()
+}
+
+fn int(v: &Value, key: &str) -> i64 {
+    v.get(key).and_then(|v| v.as_i64()).unwrap_or(0)
+}
+
+fn float(v: &Value, key: &str) -> f64 {
+    v.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0)
+}
+
+fn items(v: &Value) -> impl Iterator<Item = &Value> {
+    v.as_array().into_iter().flatten()
+}
+
+#[derive(SimpleObject)]
+struct Current {
+    temperature: i64,
+    apparent_temperature: i64,
+    humidity: i64,
+    wind_speed: i64,
+    wind_direction: i64,
+    pressure: i64,
+    visibility: Option<f64>,
+    skycon: String,
+    icon: String,
+    desc: String,
+    aqi: Option<i64>,
+    aqi_desc: Option<String>,
+    dew_point: i64,
+    cloud_cover: i64,
+    solar_radiation: Option<f64>,
+    uv_index: Option<i64>,
+    uv_category: Option<String>,
+}
+
+#[derive(SimpleObject)]
+struct Hourly {
+    time: i64,
+    temperature: i64,
+    skycon: String,
+    icon: String,
+    desc: String,
+    // mm/h
+    precipitation: f64,
+    precipitation_probability: i64,
+    humidity: i64,
+    wind_speed: i64,
+    wind_direction: i64,
+}
+
+#[derive(SimpleObject)]
+struct Daily {
+    date: String,
+    weekday: String,
+    relative_day: String,
+    max_temp: i64,
+    min_temp: i64,
+    skycon: String,
+    icon: String,
+    desc: String,
+    skycon_day: String,
+    skycon_night: String,
+    // 日降水总量 mm
+    precipitation_total: f64,
+    precipitation_probability: i64,
+    humidity_avg: i64,
+    wind_max_speed: i64,
+    wind_max_direction: i64,
+    pressure_avg: i64,
+}
+
+#[derive(SimpleObject)]
+struct Alert {
+    title: String,
+    code: String,
+    status: String,
+    description: String,
+    source: String,
+    pub_time: Option<i64>,
+}
+
+#[derive(SimpleObject)]
+struct Weather {
+    current: Current,
+    hourly: Vec<Hourly>,
+    daily: Vec<Daily>,
+    forecast_keypoint: String,
+    alerts: Vec<Alert>,
+}
+
+#[derive(SimpleObject)]
+struct Place {
+    lat: f64,
+    lng: f64,
+    name: String,
+    address: String,
+}
+
+fn alerts_of(v: &Value) -> Vec<Alert> {
+    items(v)
+        .map(|a| Alert {
+            title: text(a, "title"),
+            code: text(a, "code"),
+            status: text(a, "status"),
+            description: text(a, "description"),
+            source: text(a, "source"),
+            pub_time: a.get("pub_time").and_then(|v| v.as_i64()),
+        })
+        .collect()
+}
+
+impl From<weather::WeatherData> for Weather {
+    fn from(data: weather::WeatherData) -> Self {
+        let cur = &data.current;
+        let current = Current {
+            temperature: cur.temperature,
+            apparent_temperature: cur.apparent_temperature,
+            humidity: cur.humidity,
+            wind_speed: cur.wind_speed,
+            wind_direction: cur.wind_direction,
+            pressure: cur.pressure,
+            visibility: cur.visibility.as_f64(),
+            skycon: cur.skycon.as_str().unwrap_or("").to_string(),
+            icon: text(&cur.weather_info, "icon"),
+            desc: text(&cur.weather_info, "desc"),
+            aqi: cur.air_quality.pointer("/aqi/chn").and_then(|v| v.as_i64()),
+            aqi_desc: cur
+                .air_quality
+                .pointer("/description/chn")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            dew_point: cur.dew_point,
+            cloud_cover: cur.cloud_cover,
+            solar_radiation: cur.solar_radiation,
+            uv_index: cur.uv_index,
+            uv_category: cur.uv_category.map(str::to_string),
+        };
+        let hourly = items(&data.hourly)
+            .map(|h| Hourly {
+                time: int(h, "time"),
+                temperature: int(h, "temperature"),
+                skycon: text(h, "skycon"),
+                icon: h.get("weather_info").map(|w| text(w, "icon")).unwrap_or_default(),
+                desc: h.get("weather_info").map(|w| text(w, "desc")).unwrap_or_default(),
+                precipitation: float(h, "precipitation"),
+                precipitation_probability: int(h, "precipitation_probability"),
+                humidity: int(h, "humidity"),
+                wind_speed: int(h, "wind_speed"),
+                wind_direction: int(h, "wind_direction"),
+            })
+            .collect();
+        let daily = items(&data.daily)
+            .map(|d| Daily {
+                date: text(d, "date"),
+                weekday: text(d, "weekday"),
+                relative_day: text(d, "relativeDay"),
+                max_temp: int(d, "max_temp"),
+                min_temp: int(d, "min_temp"),
+                skycon: text(d, "skycon"),
+                icon: d.get("weather_info").map(|w| text(w, "icon")).unwrap_or_default(),
+                desc: d.get("weather_info").map(|w| text(w, "desc")).unwrap_or_default(),
+                skycon_day: text(d, "skycon_day"),
+                skycon_night: text(d, "skycon_night"),
+                precipitation_total: d.get("precipitation").map(|p| float(p, "total")).unwrap_or_default(),
+                precipitation_probability: d.get("precipitation").map(|p| int(p, "probability")).unwrap_or_default(),
+                humidity_avg: d.get("humidity").map(|h| int(h, "avg")).unwrap_or_default(),
+                wind_max_speed: d.get("wind_max").map(|w| int(w, "speed")).unwrap_or_default(),
+                wind_max_direction: d.get("wind_max").map(|w| int(w, "direction")).unwrap_or_default(),
+                pressure_avg: d.get("pressure").map(|p| int(p, "avg")).unwrap_or_default(),
+            })
+            .collect();
+        Weather {
+            current,
+            hourly,
+            daily,
+            forecast_keypoint: data.forecast_keypoint.as_str().unwrap_or("").to_string(),
+            alerts: alerts_of(&data.alerts),
+        }
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    // 实况、逐小时与逐日预报
+    async fn weather(&self, ctx: &async_graphql::Context<'_>, lat: f64, lng: f64) -> async_graphql::Result<Weather> {
+        let state = ctx.data::<AppState>()?;
+        Ok(weather::fetch(state, lng, lat).await?.into())
+    }
+
+    // 地点关键字搜索（高德）
+    async fn search_location(&self, ctx: &async_graphql::Context<'_>, q: String) -> async_graphql::Result<Vec<Place>> {
+        let state = ctx.data::<AppState>()?;
+        Ok(location::search(state, q.trim())
+            .await
+            .into_iter()
+            .map(|p| Place { lat: p.lat, lng: p.lng, name: p.name, address: p.address })
+            .collect())
+    }
+
+    // 气象预警
+    async fn alerts(&self, ctx: &async_graphql::Context<'_>, lat: f64, lng: f64) -> async_graphql::Result<Vec<Alert>> {
+        let state = ctx.data::<AppState>()?;
+        Ok(alerts_of(&weather::fetch(state, lng, lat).await?.alerts))
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn execute(State(state): State<AppState>, Json(req): Json<async_graphql::Request>) -> Json<async_graphql::Response> {
+    let mut resp = SCHEMA.execute(req.data(state)).await;
+    for error in &mut resp.errors {
+        error.message = redact::redact(&error.message);
+    }
+    Json(resp)
+}
+
+pub async fn graphiql() -> Response {
+    if cfg!(debug_assertions) || env_flag("GRAPHIQL") {
+        Html(GraphiQLSource::build().endpoint("/graphql").finish()).into_response()
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
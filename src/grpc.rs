@@ -0,0 +1,207 @@
+// gRPC 服务（需 `grpc` feature）：proto/weather.proto，独立端口 GRPC_PORT
+// 与 HTTP API 共用 weather::fetch / location::search 及上游客户端
+
+use std::{net::SocketAddr, pin::Pin, time::Duration};
+
+use futures_util::Stream;
+use serde_json::Value;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+use crate::{location, redact, weather, AppState};
+
+pub mod pb {
+    tonic::include_proto!("caiyun.weather.v1");
+}
+
+use pb::weather_service_server::{WeatherService, WeatherServiceServer};
+
+const DEFAULT_WATCH_SECS: u64 = 300;
+const MIN_WATCH_SECS: u64 = 60;
+
+fn text(v: &Value, key: &str) -> String {
+    v.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string()
+}
+
+fn int(v: &Value, key: &str) -> i64 {
+    v.get(key).and_then(|v| v.as_i64()).unwrap_or(0)
+}
+
+fn float(v: &Value, key: &str) -> f64 {
+    v.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0)
+}
+
+fn info_text(v: &Value, key: &str) -> String {
+    v.get("weather_info").map(|w| text(w, key)).unwrap_or_default()
+}
+
+fn items(v: &Value) -> impl Iterator<Item = &Value> {
+    v.as_array().into_iter().flatten()
+}
+
+impl From<&weather::WeatherCurrent> for pb::Current {
+    fn from(cur: &weather::WeatherCurrent) -> Self {
+        Self {
+            temperature: cur.temperature,
+            apparent_temperature: cur.apparent_temperature,
+            humidity: cur.humidity,
+            wind_speed: cur.wind_speed,
+            wind_direction: cur.wind_direction,
+            pressure: cur.pressure,
+            visibility: cur.visibility.as_f64(),
+            skycon: cur.skycon.as_str().unwrap_or("").to_string(),
+            icon: text(&cur.weather_info, "icon"),
+            desc: text(&cur.weather_info, "desc"),
+            aqi: cur.air_quality.pointer("/aqi/chn").and_then(|v| v.as_i64()),
+            aqi_desc: cur
+                .air_quality
+                .pointer("/description/chn")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            dew_point: cur.dew_point,
+            cloud_cover: cur.cloud_cover,
+            solar_radiation: cur.solar_radiation,
+            uv_index: cur.uv_index,
+            uv_category: cur.uv_category.map(str::to_string),
+        }
+    }
+}
+
+impl From<weather::WeatherData> for pb::WeatherReply {
+    fn from(data: weather::WeatherData) -> Self {
+        Self {
+            current: Some((&data.current).into()),
+            hourly: items(&data.hourly)
+                .map(|h| pb::Hourly {
+                    time: int(h, "time"),
+                    temperature: int(h, "temperature"),
+                    skycon: text(h, "skycon"),
+                    icon: info_text(h, "icon"),
+                    desc: info_text(h, "desc"),
+                    precipitation: float(h, "precipitation"),
+                    precipitation_probability: int(h, "precipitation_probability"),
+                    humidity: int(h, "humidity"),
+                    wind_speed: int(h, "wind_speed"),
+                    wind_direction: int(h, "wind_direction"),
+                })
+                .collect(),
+            daily: items(&data.daily)
+                .map(|d| pb::Daily {
+                    date: text(d, "date"),
+                    weekday: text(d, "weekday"),
+                    relative_day: text(d, "relativeDay"),
+                    max_temp: int(d, "max_temp"),
+                    min_temp: int(d, "min_temp"),
+                    skycon: text(d, "skycon"),
+                    icon: info_text(d, "icon"),
+                    desc: info_text(d, "desc"),
+                    skycon_day: text(d, "skycon_day"),
+                    skycon_night: text(d, "skycon_night"),
+                    precipitation_total: d.get("precipitation").map(|p| float(p, "total")).unwrap_or_default(),
+                    precipitation_probability: d.get("precipitation").map(|p| int(p, "probability")).unwrap_or_default(),
+                    humidity_avg: d.get("humidity").map(|h| int(h, "avg")).unwrap_or_default(),
+                    wind_max_speed: d.get("wind_max").map(|w| int(w, "speed")).unwrap_or_default(),
+                    wind_max_direction: d.get("wind_max").map(|w| int(w, "direction")).unwrap_or_default(),
+                    pressure_avg: d.get("pressure").map(|p| int(p, "avg")).unwrap_or_default(),
+                })
+                .collect(),
+            forecast_keypoint: data.forecast_keypoint.as_str().unwrap_or("").to_string(),
+            alerts: items(&data.alerts)
+                .map(|a| pb::Alert {
+                    title: text(a, "title"),
+                    code: text(a, "code"),
+                    status: text(a, "status"),
+                    description: text(a, "description"),
+                    source: text(a, "source"),
+                    pub_time: a.get("pub_time").and_then(|v| v.as_i64()),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<weather::FetchError> for Status {
+    fn from(e: weather::FetchError) -> Self {
+        match e {
+            weather::FetchError::Format(_) => Status::internal(redact::redact(&e.to_string())),
+            weather::FetchError::Mock(_) => Status::invalid_argument(e.to_string()),
+            _ => Status::unavailable(redact::redact(&e.to_string())),
+        }
+    }
+}
+
+// tonic::Status 体积较大，与 tonic 接口签名保持一致
+#[allow(clippy::result_large_err)]
+fn check_coords(lat: f64, lng: f64) -> Result<(), Status> {
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lng) {
+        return Err(Status::invalid_argument("经纬度超出范围"));
+    }
+    Ok(())
+}
+
+struct Service {
+    state: AppState,
+}
+
+#[tonic::async_trait]
+impl WeatherService for Service {
+    async fn get_weather(&self, req: Request<pb::WeatherRequest>) -> Result<Response<pb::WeatherReply>, Status> {
+        let pb::WeatherRequest { lat, lng } = req.into_inner();
+        check_coords(lat, lng)?;
+        let data = weather::fetch(&self.state, lng, lat).await?;
+        Ok(Response::new(data.into()))
+    }
+
+    async fn get_realtime(&self, req: Request<pb::WeatherRequest>) -> Result<Response<pb::Current>, Status> {
+        let pb::WeatherRequest { lat, lng } = req.into_inner();
+        check_coords(lat, lng)?;
+        let data = weather::fetch(&self.state, lng, lat).await?;
+        Ok(Response::new((&data.current).into()))
+    }
+
+    async fn search_location(&self, req: Request<pb::SearchRequest>) -> Result<Response<pb::SearchReply>, Status> {
+        let q = req.into_inner().q;
+        if q.trim().is_empty() {
+            return Err(Status::invalid_argument("缺少 q"));
+        }
+        let places = location::search(&self.state, q.trim())
+            .await
+            .into_iter()
+            .map(|p| pb::Place { lat: p.lat, lng: p.lng, name: p.name, address: p.address })
+            .collect();
+        Ok(Response::new(pb::SearchReply { places }))
+    }
+
+    type WatchWeatherStream = Pin<Box<dyn Stream<Item = Result<pb::WeatherReply, Status>> + Send>>;
+
+    async fn watch_weather(&self, req: Request<pb::WatchRequest>) -> Result<Response<Self::WatchWeatherStream>, Status> {
+        let pb::WatchRequest { lat, lng, interval_seconds } = req.into_inner();
+        check_coords(lat, lng)?;
+        let secs = match interval_seconds {
+            0 => DEFAULT_WATCH_SECS,
+            n => (n as u64).max(MIN_WATCH_SECS),
+        };
+        let mut ticker = tokio::time::interval(Duration::from_secs(secs));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // 立即推送一次，之后按间隔推送；gRPC 流中的错误会终止流，因此上游失败时跳过本次
+        let stream = futures_util::stream::unfold((self.state.clone(), ticker), move |(state, mut ticker)| async move {
+            loop {
+                ticker.tick().await;
+                match weather::fetch(&state, lng, lat).await {
+                    Ok(data) => return Some((Ok(pb::WeatherReply::from(data)), (state, ticker))),
+                    Err(e) => tracing::warn!("watch_weather 获取失败: {}", e),
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+pub async fn serve(state: AppState, addr: SocketAddr) -> anyhow::Result<()> {
+    info!("grpc listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(WeatherServiceServer::new(Service { state }))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
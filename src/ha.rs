@@ -0,0 +1,126 @@
+// Home Assistant 兼容接口：/api/v1/ha/weather 输出 HA weather 平台使用的属性名，可直接作为 REST 天气实体数据源
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::{Days, Duration, DurationRound, Local, TimeZone, Utc};
+use serde_json::{json, Value};
+
+use crate::{respond, weather, AppState, ErrorResp, WeatherQuery};
+
+// skycon → HA condition（https://developers.home-assistant.io/docs/core/entity/weather/#recommended-values-for-state-and-condition）
+fn ha_condition(skycon: &str) -> &'static str {
+    match skycon {
+        "CLEAR_DAY" => "sunny",
+        "CLEAR_NIGHT" => "clear-night",
+        "PARTLY_CLOUDY_DAY" | "PARTLY_CLOUDY_NIGHT" => "partlycloudy",
+        "CLOUDY" => "cloudy",
+        "LIGHT_RAIN" | "MODERATE_RAIN" => "rainy",
+        "HEAVY_RAIN" | "STORM_RAIN" => "pouring",
+        "HAIL" => "hail",
+        "SLEET" => "snowy-rainy",
+        "LIGHT_SNOW" | "MODERATE_SNOW" | "HEAVY_SNOW" | "STORM_SNOW" => "snowy",
+        "FOG" | "LIGHT_HAZE" | "MODERATE_HAZE" | "HEAVY_HAZE" => "fog",
+        "WIND" => "windy",
+        _ => "exceptional",
+    }
+}
+
+fn skycon_of(v: &Value) -> &str {
+    v.get("skycon").and_then(|v| v.as_str()).unwrap_or("")
+}
+
+fn to_ha(data: &weather::WeatherData) -> Value {
+    let cur = &data.current;
+    let condition = ha_condition(cur.skycon.as_str().unwrap_or(""));
+
+    // 逐日：从本地今天 0 点起算
+    let today = Local::now().date_naive();
+    let forecast: Vec<Value> = data
+        .daily
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(i, d)| {
+            let date = today.checked_add_days(Days::new(i as u64)).unwrap_or(today);
+            let datetime = date
+                .and_hms_opt(0, 0, 0)
+                .and_then(|t| Local.from_local_datetime(&t).single())
+                .map(|t| t.to_rfc3339());
+            json!({
+                "datetime": datetime,
+                "condition": ha_condition(skycon_of(d)),
+                "native_temperature": d.get("max_temp"),
+                "temperature": d.get("max_temp"),
+                "native_templow": d.get("min_temp"),
+                "templow": d.get("min_temp"),
+                "native_precipitation": d.pointer("/precipitation/total"),
+                "precipitation_probability": d.pointer("/precipitation/probability"),
+                "humidity": d.pointer("/humidity/avg"),
+                "native_wind_speed": d.pointer("/wind_max/speed"),
+                "wind_bearing": d.pointer("/wind_max/direction"),
+                "native_pressure": d.pointer("/pressure/avg"),
+            })
+        })
+        .collect();
+
+    // 逐小时：从当前整点起算
+    let hour = Utc::now().duration_trunc(Duration::hours(1)).unwrap_or_else(|_| Utc::now());
+    let forecast_hourly: Vec<Value> = data
+        .hourly
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(i, h)| {
+            json!({
+                "datetime": (hour + Duration::hours(i as i64)).to_rfc3339(),
+                "condition": ha_condition(skycon_of(h)),
+                "native_temperature": h.get("temperature"),
+                "temperature": h.get("temperature"),
+                "native_precipitation": h.get("precipitation"),
+                "precipitation_probability": h.get("precipitation_probability"),
+                "humidity": h.get("humidity"),
+                "native_wind_speed": h.get("wind_speed"),
+                "wind_bearing": h.get("wind_direction"),
+            })
+        })
+        .collect();
+
+    json!({
+        "condition": condition,
+        "native_temperature": cur.temperature,
+        "temperature": cur.temperature,
+        "native_apparent_temperature": cur.apparent_temperature,
+        "apparent_temperature": cur.apparent_temperature,
+        "humidity": cur.humidity,
+        "native_dew_point": cur.dew_point,
+        "dew_point": cur.dew_point,
+        "cloud_coverage": cur.cloud_cover,
+        "native_pressure": cur.pressure,
+        "pressure": cur.pressure,
+        "native_wind_speed": cur.wind_speed,
+        "wind_speed": cur.wind_speed,
+        "wind_bearing": cur.wind_direction,
+        "native_visibility": cur.visibility,
+        "visibility": cur.visibility,
+        "temperature_unit": "°C",
+        "pressure_unit": "hPa",
+        "wind_speed_unit": "km/h",
+        "visibility_unit": "km",
+        "attribution": "彩云天气",
+        "forecast": forecast,
+        "forecast_hourly": forecast_hourly,
+    })
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn weather(State(state): State<AppState>, accept: respond::Accept, Query(q): Query<WeatherQuery>) -> impl IntoResponse {
+    match weather::fetch(&state, q.lng, q.lat).await {
+        Ok(data) => respond::negotiated(&accept, StatusCode::OK, &to_ha(&data)),
+        Err(e) => respond::json(e.status(), &ErrorResp::new(e.to_string())),
+    }
+}
@@ -0,0 +1,149 @@
+// 对冲请求：上游首个请求超过近期延迟分位数仍未返回时再发一次，取先成功者，降低弱网下的长尾延迟
+// 对冲请求数按预算限制（令牌桶，按主请求数的比例补充），避免放大上游额度消耗
+// - HEDGE_REQUESTS=1：启用（目前用于彩云天气接口）
+// - HEDGE_PERCENTILE：触发阈值取近期成功请求延迟的分位数，默认 95
+// - HEDGE_MIN_MS / HEDGE_MAX_MS：阈值上下限，默认 200 / 3000；样本不足时使用上限
+// - HEDGE_BUDGET_PERCENT：对冲请求占主请求的比例上限，默认 5
+
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::{Lazy, OnceCell};
+use tracing::debug;
+
+use crate::config::{env_flag, env_parse};
+
+// 参与计算分位数的最近样本数与最少样本数
+const WINDOW: usize = 200;
+const MIN_SAMPLES: usize = 20;
+// 令牌桶容量：允许短时间内连续对冲的次数
+const BURST: f64 = 3.0;
+
+struct Config {
+    percentile: f64,
+    min: Duration,
+    max: Duration,
+    budget: f64,
+}
+
+static CONFIG: OnceCell<Option<Config>> = OnceCell::new();
+
+struct Tracker {
+    latencies: VecDeque<Duration>,
+    tokens: f64,
+}
+
+static TRACKERS: Lazy<Mutex<HashMap<&'static str, Tracker>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    if !env_flag("HEDGE_REQUESTS") {
+        let _ = CONFIG.set(None);
+        return Ok(());
+    }
+    let percentile = env_parse::<f64>("HEDGE_PERCENTILE")?.unwrap_or(95.0);
+    if !(percentile > 0.0 && percentile < 100.0) {
+        anyhow::bail!("HEDGE_PERCENTILE 应在 0 到 100 之间");
+    }
+    let min = Duration::from_millis(env_parse("HEDGE_MIN_MS")?.unwrap_or(200));
+    let max = Duration::from_millis(env_parse("HEDGE_MAX_MS")?.unwrap_or(3000));
+    if min > max {
+        anyhow::bail!("HEDGE_MIN_MS 不应大于 HEDGE_MAX_MS");
+    }
+    let budget = env_parse::<f64>("HEDGE_BUDGET_PERCENT")?.unwrap_or(5.0);
+    if !(0.0..=100.0).contains(&budget) {
+        anyhow::bail!("HEDGE_BUDGET_PERCENT 应在 0 到 100 之间");
+    }
+    let _ = CONFIG.set(Some(Config { percentile, min, max, budget: budget / 100.0 }));
+    Ok(())
+}
+
+// 记录一次主请求并返回对冲阈值
+fn begin(upstream: &'static str, config: &Config) -> Duration {
+    let mut trackers = TRACKERS.lock().unwrap();
+    let tracker = trackers
+        .entry(upstream)
+        .or_insert_with(|| Tracker { latencies: VecDeque::with_capacity(WINDOW), tokens: BURST });
+    tracker.tokens = (tracker.tokens + config.budget).min(BURST);
+    if tracker.latencies.len() < MIN_SAMPLES {
+        return config.max;
+    }
+    let mut sorted: Vec<Duration> = tracker.latencies.iter().copied().collect();
+    sorted.sort_unstable();
+    let idx = ((sorted.len() as f64 * config.percentile / 100.0).ceil() as usize).clamp(1, sorted.len()) - 1;
+    sorted[idx].clamp(config.min, config.max)
+}
+
+fn take_token(upstream: &'static str) -> bool {
+    let mut trackers = TRACKERS.lock().unwrap();
+    match trackers.get_mut(upstream) {
+        Some(tracker) if tracker.tokens >= 1.0 => {
+            tracker.tokens -= 1.0;
+            true
+        }
+        _ => false,
+    }
+}
+
+fn record(upstream: &'static str, latency: Duration) {
+    if let Some(tracker) = TRACKERS.lock().unwrap().get_mut(upstream) {
+        if tracker.latencies.len() == WINDOW {
+            tracker.latencies.pop_front();
+        }
+        tracker.latencies.push_back(latency);
+    }
+}
+
+// 执行 attempt；超过阈值且预算允许时并发第二次，先成功者胜出，两者都失败时返回后完成者的错误
+pub async fn run<T, E, F, Fut>(upstream: &'static str, mut attempt: F) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let Some(config) = CONFIG.get().and_then(Option::as_ref) else {
+        return attempt().await;
+    };
+    let threshold = begin(upstream, config);
+    let started = Instant::now();
+    let first = attempt();
+    tokio::pin!(first);
+    tokio::select! {
+        res = &mut first => {
+            if res.is_ok() {
+                record(upstream, started.elapsed());
+            }
+            return res;
+        }
+        _ = tokio::time::sleep(threshold) => {}
+    }
+    if !take_token(upstream) {
+        let res = first.await;
+        if res.is_ok() {
+            record(upstream, started.elapsed());
+        }
+        return res;
+    }
+
+    debug!(upstream, threshold_ms = threshold.as_millis() as u64, "hedging upstream request");
+    let hedged_at = Instant::now();
+    let second = attempt();
+    tokio::pin!(second);
+    let (res, hedge_won) = tokio::select! {
+        res = &mut first => (res, false),
+        res = &mut second => (res, true),
+    };
+    let (res, from_hedge) = match (res, hedge_won) {
+        (Ok(v), won) => (Ok(v), won),
+        (Err(_), false) => (second.await, true),
+        (Err(_), true) => (first.await, false),
+    };
+    if res.is_ok() {
+        // 对冲请求的耗时从其发出时计，避免样本被阈值抬高
+        record(upstream, if from_hedge { hedged_at.elapsed() } else { started.elapsed() });
+        debug!(upstream, from_hedge, "hedged upstream request finished");
+    }
+    res
+}
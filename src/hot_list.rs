@@ -0,0 +1,207 @@
+// 热门地点自动预取：按坐标（两位小数，与上游结果缓存的网格相同）统计请求频率，请求最多的前 N 个地点列入热门名单，
+// 由定时任务 hot_list_prefetch 提前刷新其上游结果缓存，冷下来的地点自动移出，无需手工维护预热列表
+// - HOT_LIST_SIZE：热门名单长度，设置后开启（需开启上游结果缓存 WEATHER_CACHE_SECS）
+// - HOT_LIST_TRACKED：最多统计的地点数，默认 1000，超出时淘汰最久没有请求的地点
+// - HOT_LIST_MIN_REQUESTS：列入名单所需的请求数（按衰减后的计数），默认 3；降到一半以下时移出
+// 任务默认每 5 分钟运行（SCHEDULE_HOT_LIST_PREFETCH 可调整），每次运行后计数减半，名单反映最近一段时间的流量
+// 当前名单与计数见管理接口 /api/v1/admin/hot-list
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Mutex,
+    time::Instant,
+};
+
+use axum::{
+    extract::{MatchedPath, Query, Request},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{config::env_parse, respond, scheduler, upstream_cache, weather, AppState, ErrorResp};
+
+// 每次运行后计数乘以该系数；低于 FORGET 的地点不再统计
+const DECAY: f64 = 0.5;
+const FORGET: f64 = 0.05;
+
+// 坐标乘以 100 取整
+type Key = (i32, i32);
+
+struct Tracked {
+    score: f64,
+    last_seen: Instant,
+}
+
+struct HotList {
+    size: usize,
+    capacity: usize,
+    min_requests: f64,
+    tracked: Mutex<HashMap<Key, Tracked>>,
+    hot: Mutex<BTreeSet<Key>>,
+}
+
+static HOT_LIST: OnceCell<HotList> = OnceCell::new();
+
+fn key(lng: f64, lat: f64) -> Key {
+    ((lng * 100.0).round() as i32, (lat * 100.0).round() as i32)
+}
+
+fn coords(key: Key) -> (f64, f64) {
+    (key.0 as f64 / 100.0, key.1 as f64 / 100.0)
+}
+
+fn format_key(key: Key) -> String {
+    let (lng, lat) = coords(key);
+    format!("{:.2},{:.2}", lng, lat)
+}
+
+// 读取配置（需在 upstream_cache::init_from_env 之后）；未设置 HOT_LIST_SIZE 时不做任何事
+pub fn init_from_env() -> anyhow::Result<()> {
+    let Some(size) = env_parse::<usize>("HOT_LIST_SIZE")?.filter(|n| *n > 0) else {
+        return Ok(());
+    };
+    if !upstream_cache::enabled(upstream_cache::Kind::Weather) {
+        anyhow::bail!("HOT_LIST_SIZE 需要开启上游结果缓存（WEATHER_CACHE_SECS）");
+    }
+    let capacity = env_parse::<usize>("HOT_LIST_TRACKED")?.unwrap_or(1000).max(size);
+    let min_requests = env_parse::<f64>("HOT_LIST_MIN_REQUESTS")?.unwrap_or(3.0);
+    if !min_requests.is_finite() || min_requests <= 0.0 {
+        anyhow::bail!("HOT_LIST_MIN_REQUESTS 应为正数");
+    }
+    let _ = HOT_LIST.set(HotList {
+        size,
+        capacity,
+        min_requests,
+        tracked: Mutex::new(HashMap::new()),
+        hot: Mutex::new(BTreeSet::new()),
+    });
+    Ok(())
+}
+
+// 注册预取任务（需先 init_from_env）
+pub fn spawn(state: AppState) -> anyhow::Result<()> {
+    let Some(list) = HOT_LIST.get() else {
+        return Ok(());
+    };
+    info!("hot list enabled (top {} of up to {} location(s))", list.size, list.capacity);
+    scheduler::register("hot_list_prefetch", "*/5 * * * *", move || {
+        let state = state.clone();
+        async move { list.prefetch(&state).await }
+    })
+}
+
+impl HotList {
+    fn record(&self, key: Key) {
+        let mut tracked = self.tracked.lock().unwrap();
+        if tracked.len() >= self.capacity && !tracked.contains_key(&key) {
+            if let Some(lru) = tracked.iter().min_by_key(|(_, t)| t.last_seen).map(|(k, _)| *k) {
+                tracked.remove(&lru);
+            }
+        }
+        let now = Instant::now();
+        let entry = tracked.entry(key).or_insert(Tracked { score: 0.0, last_seen: now });
+        entry.score += 1.0;
+        entry.last_seen = now;
+    }
+
+    // 按当前计数调整名单，之后衰减计数；返回新的名单
+    fn rebalance(&self) -> Vec<Key> {
+        let mut tracked = self.tracked.lock().unwrap();
+        let mut hot = self.hot.lock().unwrap();
+        let score = |k: &Key| tracked.get(k).map_or(0.0, |t| t.score);
+
+        // 仍在名单中的地点降到阈值一半以下才移出，避免在阈值附近反复进出
+        let demoted: Vec<Key> = hot.iter().filter(|k| score(k) < self.min_requests / 2.0).copied().collect();
+        let mut ranked: Vec<(&Key, f64)> = tracked
+            .iter()
+            .filter(|(k, t)| t.score >= self.min_requests || (hot.contains(k) && !demoted.contains(k)))
+            .map(|(k, t)| (k, t.score))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let next: BTreeSet<Key> = ranked.into_iter().take(self.size).map(|(k, _)| *k).collect();
+
+        let promoted: Vec<String> = next.difference(&hot).copied().map(format_key).collect();
+        let removed: Vec<String> = hot.difference(&next).copied().map(format_key).collect();
+        if !promoted.is_empty() || !removed.is_empty() {
+            info!(promoted = %promoted.join(";"), demoted = %removed.join(";"), "hot list updated");
+        }
+        *hot = next;
+
+        tracked.retain(|_, t| {
+            t.score *= DECAY;
+            t.score >= FORGET
+        });
+        hot.iter().copied().collect()
+    }
+
+    async fn prefetch(&self, state: &AppState) -> anyhow::Result<()> {
+        let mut failed = 0;
+        for key in self.rebalance() {
+            let (lng, lat) = coords(key);
+            if let Err(e) = weather::fetch_raw(state, lng, lat).await {
+                warn!(location = %format_key(key), "hot list prefetch failed: {}", e);
+                failed += 1;
+            }
+        }
+        if failed > 0 {
+            anyhow::bail!("{} 个地点预取失败", failed);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct Coords {
+    lng: f64,
+    lat: f64,
+}
+
+// 统计命中路由且带 lng/lat 参数的请求
+pub async fn middleware(req: Request, next: Next) -> Response {
+    if let (Some(list), Some(_)) = (HOT_LIST.get(), req.extensions().get::<MatchedPath>()) {
+        if let Ok(q) = Query::<Coords>::try_from_uri(req.uri()) {
+            if q.lng.is_finite() && q.lat.is_finite() {
+                list.record(key(q.lng, q.lat));
+            }
+        }
+    }
+    next.run(req).await
+}
+
+#[derive(Deserialize)]
+pub struct AdminQuery {
+    top: Option<usize>,
+}
+
+// 当前名单，以及计数最高的 top 个（默认 20）地点
+pub async fn admin_list(Query(q): Query<AdminQuery>) -> Response {
+    let Some(list) = HOT_LIST.get() else {
+        return respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("未开启热门地点预取（HOT_LIST_SIZE）"));
+    };
+    let tracked = list.tracked.lock().unwrap();
+    let hot = list.hot.lock().unwrap();
+    let describe = |k: &Key| {
+        let (lng, lat) = coords(*k);
+        let score = tracked.get(k).map_or(0.0, |t| t.score);
+        json!({ "lng": lng, "lat": lat, "requests": (score * 100.0).round() / 100.0, "hot": hot.contains(k) })
+    };
+    let mut ranked: Vec<(&Key, &Tracked)> = tracked.iter().collect();
+    ranked.sort_by(|a, b| b.1.score.total_cmp(&a.1.score).then_with(|| a.0.cmp(b.0)));
+    let top: Vec<_> = ranked.into_iter().take(q.top.unwrap_or(20)).map(|(k, _)| describe(k)).collect();
+    respond::json(
+        StatusCode::OK,
+        &json!({
+            "size": list.size,
+            "min_requests": list.min_requests,
+            "tracked": tracked.len(),
+            "capacity": list.capacity,
+            "hot": hot.iter().map(describe).collect::<Vec<_>>(),
+            "top": top,
+        }),
+    )
+}
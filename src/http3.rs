@@ -0,0 +1,99 @@
+// 实验性 HTTP/3（需 `http3` feature）：quinn + h3，在 UDP 端口上复用同一 Router
+// 由 server::serve_tls 在配置了 HTTP3_PORT 与证书文件时启动；请求体按流转交给 axum，响应体逐块写回
+
+use std::{io::BufReader, net::SocketAddr, sync::Arc};
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header, HeaderValue, Request},
+    Router,
+};
+use bytes::{Buf, Bytes};
+use futures_util::StreamExt;
+use h3::server::RequestStream;
+use tracing::{debug, info};
+
+type Stream = h3_quinn::BidiStream<Bytes>;
+
+fn server_config(cert: &str, key: &str) -> anyhow::Result<quinn::ServerConfig> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(std::fs::File::open(cert)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    let key = rustls_pemfile::private_key(&mut BufReader::new(std::fs::File::open(key)?))?
+        .ok_or_else(|| anyhow::anyhow!("{} 中没有私钥", key))?;
+    let mut tls = rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_protocol_versions(&[&rustls::version::TLS13])?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    tls.alpn_protocols = vec![b"h3".to_vec()];
+    let crypto = quinn::crypto::rustls::QuicServerConfig::try_from(tls)?;
+    Ok(quinn::ServerConfig::with_crypto(Arc::new(crypto)))
+}
+
+// 同步完成证书加载与端口绑定（配置错误在启动时暴露），连接处理在后台进行
+pub fn spawn(app: Router, addr: SocketAddr, cert: &str, key: &str) -> anyhow::Result<()> {
+    let endpoint = quinn::Endpoint::server(server_config(cert, key)?, addr)?;
+    info!("listening on https://{} (http/3, udp)", addr);
+    tokio::spawn(async move {
+        while let Some(incoming) = endpoint.accept().await {
+            let app = app.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_connection(incoming, app).await {
+                    debug!("http/3 connection error: {}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+async fn serve_connection(incoming: quinn::Incoming, app: Router) -> anyhow::Result<()> {
+    let conn = incoming.await?;
+    let peer = conn.remote_address();
+    let mut conn = h3::server::builder().build(h3_quinn::Connection::new(conn)).await?;
+    while let Some(resolver) = conn.accept().await? {
+        let app = app.clone();
+        tokio::spawn(async move {
+            match resolver.resolve_request().await {
+                Ok((req, stream)) => {
+                    if let Err(e) = handle(req, stream, peer, app).await {
+                        debug!("http/3 request error: {}", e);
+                    }
+                }
+                Err(e) => debug!("http/3 request error: {}", e),
+            }
+        });
+    }
+    Ok(())
+}
+
+async fn handle(req: Request<()>, stream: RequestStream<Stream, Bytes>, peer: SocketAddr, app: Router) -> anyhow::Result<()> {
+    let (mut send, recv) = stream.split();
+    let body = futures_util::stream::unfold(Some(recv), |recv| async move {
+        let mut recv = recv?;
+        match recv.recv_data().await {
+            Ok(Some(mut data)) => Some((Ok(data.copy_to_bytes(data.remaining())), Some(recv))),
+            Ok(None) => None,
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+    let (mut parts, ()) = req.into_parts();
+    // HTTP/3 以 :authority 代替 Host，部分处理函数依赖 Host 生成链接
+    if !parts.headers.contains_key(header::HOST) {
+        if let Some(host) = parts.uri.authority().and_then(|a| HeaderValue::from_str(a.as_str()).ok()) {
+            parts.headers.insert(header::HOST, host);
+        }
+    }
+    parts.extensions.insert(ConnectInfo(peer));
+    let req = Request::from_parts(parts, Body::from_stream(body));
+
+    let resp = tower::ServiceExt::oneshot(app, req).await?;
+    let (parts, body) = resp.into_parts();
+    send.send_response(axum::http::Response::from_parts(parts, ())).await?;
+    let mut body = body.into_data_stream();
+    while let Some(chunk) = body.next().await {
+        send.send_data(chunk?).await?;
+    }
+    send.finish().await?;
+    Ok(())
+}
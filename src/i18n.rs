@@ -0,0 +1,194 @@
+// 日期与数值的本地化：逐日、逐小时输出中的星期、相对日期、时段名称与展示用文本统一在此生成，处理函数不再自行拼接
+// 天气接口的 lang 参数（zh_CN、en_US；zh、en-US 等写法同 /api/v1/weather/brief）选择语言：
+// - 未指定时输出与以前相同（星期、相对日期为中文，不附加 display）
+// - 指定后 weekday、relativeDay、时段 label 按该语言给出，并为每一项附加 display：
+//   日期（“7月28日 周一” / “Mon, Jul 28”）、时刻（“14时” / “2 PM”）以及带单位的气温、降水、湿度、风速、气压
+// 数值千位分隔：en_US 为 1,013，zh_CN 不分隔；小数点均为 .
+
+use chrono::Weekday;
+use serde_json::{json, Value};
+
+use crate::weather;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Locale {
+    ZhCn,
+    EnUs,
+}
+
+// 周一到周日
+const WEEKDAYS_ZH: [&str; 7] = ["周一", "周二", "周三", "周四", "周五", "周六", "周日"];
+const WEEKDAYS_EN: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const MONTHS_EN: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+// 相对今天 -1 到 2 天
+const RELATIVE_ZH: [&str; 4] = ["昨天", "今天", "明天", "后天"];
+const RELATIVE_EN: [&str; 4] = ["Yesterday", "Today", "Tomorrow", "Day after tomorrow"];
+// 时段：0-6 夜间、6-12 上午、12-18 下午、18-24 晚上
+pub const PERIODS: [&str; 4] = ["night", "morning", "afternoon", "evening"];
+const PERIODS_ZH: [&str; 4] = ["夜间", "上午", "下午", "晚上"];
+const PERIODS_EN: [&str; 4] = ["Night", "Morning", "Afternoon", "Evening"];
+
+impl Locale {
+    // 语言部分为 zh 或 en 即可（zh-TW、en-GB 也归入这两种）；未指定时为 None
+    pub fn parse(raw: Option<&str>) -> Result<Option<Self>, String> {
+        let Some(raw) = raw.map(str::trim).filter(|s| !s.is_empty()) else {
+            return Ok(None);
+        };
+        let primary = raw.split(['_', '-']).next().unwrap_or_default().to_ascii_lowercase();
+        match primary.as_str() {
+            "zh" => Ok(Some(Self::ZhCn)),
+            "en" => Ok(Some(Self::EnUs)),
+            _ => Err(format!("不支持的语言 {}（可选：zh_CN, en_US）", raw)),
+        }
+    }
+
+    pub fn weekday(self, weekday: Weekday) -> &'static str {
+        let i = weekday.num_days_from_monday() as usize;
+        match self {
+            Self::ZhCn => WEEKDAYS_ZH[i],
+            Self::EnUs => WEEKDAYS_EN[i],
+        }
+    }
+
+    // 相对今天的天数；超出 -1..=2 时为空串
+    pub fn relative_day(self, offset: i64) -> &'static str {
+        let Some(i) = usize::try_from(offset + 1).ok().filter(|i| *i < RELATIVE_ZH.len()) else {
+            return "";
+        };
+        match self {
+            Self::ZhCn => RELATIVE_ZH[i],
+            Self::EnUs => RELATIVE_EN[i],
+        }
+    }
+
+    // PERIODS 的下标
+    pub fn period(self, index: usize) -> &'static str {
+        match self {
+            Self::ZhCn => PERIODS_ZH[index % 4],
+            Self::EnUs => PERIODS_EN[index % 4],
+        }
+    }
+
+    pub fn month_day(self, month: u32, day: u32, weekday: Weekday) -> String {
+        match self {
+            Self::ZhCn => format!("{}月{}日 {}", month, day, self.weekday(weekday)),
+            Self::EnUs => {
+                let month = MONTHS_EN[(month.clamp(1, 12) - 1) as usize];
+                format!("{}, {} {}", self.weekday(weekday), month, day)
+            }
+        }
+    }
+
+    // 当地小时 0-23
+    pub fn hour(self, hour: i64) -> String {
+        let hour = hour.rem_euclid(24);
+        match self {
+            Self::ZhCn => format!("{}时", hour),
+            Self::EnUs => match hour {
+                0 => "12 AM".to_string(),
+                12 => "12 PM".to_string(),
+                h if h < 12 => format!("{} AM", h),
+                h => format!("{} PM", h - 12),
+            },
+        }
+    }
+
+    // 保留 digits 位小数，整数部分按语言分组
+    pub fn number(self, value: f64, digits: usize) -> String {
+        let text = format!("{:.*}", digits, value);
+        if self == Self::ZhCn {
+            return text;
+        }
+        let (sign, unsigned) = text.strip_prefix('-').map_or(("", text.as_str()), |rest| ("-", rest));
+        let (int, frac) = unsigned.split_once('.').map_or((unsigned, None), |(i, f)| (i, Some(f)));
+        let mut grouped = String::with_capacity(text.len() + int.len() / 3);
+        for (i, c) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(c);
+        }
+        match frac {
+            Some(frac) => format!("{}{}.{}", sign, grouped, frac),
+            None => format!("{}{}", sign, grouped),
+        }
+    }
+
+    fn with_unit(self, value: Option<f64>, digits: usize, unit: &str) -> Value {
+        value.map_or(Value::Null, |v| {
+            let space = if unit == "°C" || unit == "%" { "" } else { " " };
+            Value::String(format!("{}{}{}", self.number(v, digits), space, unit))
+        })
+    }
+}
+
+fn weekday_index(name: &str) -> Option<Weekday> {
+    let i = WEEKDAYS_ZH.iter().position(|w| *w == name)?;
+    Weekday::try_from(i as u8).ok()
+}
+
+fn num(v: &Value, pointer: &str) -> Option<f64> {
+    v.pointer(pointer).and_then(Value::as_f64)
+}
+
+// 逐日预报中的一项（format_daily 的输出，星期与相对日期为中文）
+pub fn localize_day(day: &mut Value, locale: Locale) {
+    let weekday = day.get("weekday").and_then(Value::as_str).and_then(weekday_index);
+    let offset = day.get("relativeDay").and_then(Value::as_str).and_then(|r| RELATIVE_ZH.iter().position(|z| *z == r));
+    let month_day = day.get("date").and_then(Value::as_str).and_then(|d| {
+        let (m, d) = d.split_once('-')?;
+        Some((m.parse::<u32>().ok()?, d.parse::<u32>().ok()?))
+    });
+    let display = json!({
+        "date": month_day.zip(weekday).map(|((m, d), w)| locale.month_day(m, d, w)),
+        "max_temp": locale.with_unit(num(day, "/max_temp"), 0, "°C"),
+        "min_temp": locale.with_unit(num(day, "/min_temp"), 0, "°C"),
+        "precipitation": locale.with_unit(num(day, "/precipitation/total"), 1, "mm"),
+        "precipitation_probability": locale.with_unit(num(day, "/precipitation/probability"), 0, "%"),
+        "humidity": locale.with_unit(num(day, "/humidity/avg"), 0, "%"),
+        "wind_speed": locale.with_unit(num(day, "/wind_max/speed"), 0, "km/h"),
+        "pressure": locale.with_unit(num(day, "/pressure/avg"), 0, "hPa"),
+    });
+    if let Some(weekday) = weekday {
+        day["weekday"] = locale.weekday(weekday).into();
+    }
+    if let Some(i) = offset {
+        day["relativeDay"] = locale.relative_day(i as i64 - 1).into();
+    }
+    day["display"] = display;
+}
+
+// 逐小时预报中的一项；6 小时时段块（带 period）给出时段名称与气温范围
+pub fn localize_hour(hour: &mut Value, locale: Locale) {
+    if let Some(period) = hour.get("period").and_then(Value::as_str) {
+        let index = PERIODS.iter().position(|p| *p == period).unwrap_or(0);
+        let start = hour.get("start_hour").and_then(Value::as_i64).unwrap_or(0);
+        let temps = (locale.with_unit(num(hour, "/min_temp"), 0, "°C"), locale.with_unit(num(hour, "/max_temp"), 0, "°C"));
+        hour["label"] = locale.period(index).into();
+        hour["display"] = json!({
+            "time": locale.hour(start),
+            "min_temp": temps.0,
+            "max_temp": temps.1,
+            "precipitation": locale.with_unit(num(hour, "/precipitation"), 1, "mm"),
+            "precipitation_probability": locale.with_unit(num(hour, "/precipitation_probability"), 0, "%"),
+        });
+        return;
+    }
+    hour["display"] = json!({
+        "time": hour.get("time").and_then(Value::as_i64).map(|h| locale.hour(h)),
+        "temperature": locale.with_unit(num(hour, "/temperature"), 0, "°C"),
+        "precipitation": locale.with_unit(num(hour, "/precipitation"), 1, "mm/h"),
+        "precipitation_probability": locale.with_unit(num(hour, "/precipitation_probability"), 0, "%"),
+        "humidity": locale.with_unit(num(hour, "/humidity"), 0, "%"),
+        "wind_speed": locale.with_unit(num(hour, "/wind_speed"), 0, "km/h"),
+    });
+}
+
+pub fn localize(data: &mut weather::WeatherData, locale: Locale) {
+    for day in data.daily.as_array_mut().into_iter().flatten() {
+        localize_day(day, locale);
+    }
+    for hour in data.hourly.as_array_mut().into_iter().flatten() {
+        localize_hour(hour, locale);
+    }
+}
@@ -0,0 +1,146 @@
+// 天气图标：按图标集把 skycon 映射为图标，内置集合见 icons.json（emoji、weather-icons 字体类名、svg 文件名）
+// 组合图标（如“云遮月”）以 layers 给出从后到前的各层，icon 为最前一层，客户端自行叠放，不再下发 HTML
+// - ICON_SETS_FILE：额外的图标集 JSON，结构同 icons.json，同名集合覆盖内置；
+//   每个集合含 type（emoji/class/image）、icons（代码 → 图标或图标数组）、fallback，image 类可设 base_url 作为文件名前缀
+// - ICON_SET：API 默认使用的图标集，默认 emoji；请求可用 ?icons=<名称> 覆盖。页面、邮件、推送等始终使用 emoji
+
+use std::collections::HashMap;
+
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    config::env_nonempty,
+    tenant,
+    weather::{self, WeatherData},
+};
+
+const BUILTIN: &str = include_str!("../icons.json");
+const EMOJI: &str = "emoji";
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Emoji,
+    Class,
+    Image,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Layers {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Layers {
+    fn to_vec(&self) -> Vec<String> {
+        match self {
+            Layers::One(s) => vec![s.clone()],
+            Layers::Many(v) => v.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct IconSet {
+    #[serde(rename = "type")]
+    kind: Kind,
+    #[serde(default)]
+    base_url: String,
+    icons: HashMap<String, Layers>,
+    fallback: Layers,
+}
+
+static BUILTIN_SETS: Lazy<HashMap<String, IconSet>> =
+    Lazy::new(|| serde_json::from_str(BUILTIN).expect("icons.json 格式错误"));
+static CUSTOM_SETS: OnceCell<HashMap<String, IconSet>> = OnceCell::new();
+static DEFAULT_SET: OnceCell<String> = OnceCell::new();
+
+fn find(name: &str) -> Option<&'static IconSet> {
+    CUSTOM_SETS.get().and_then(|sets| sets.get(name)).or_else(|| BUILTIN_SETS.get(name))
+}
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    if let Some(path) = env_nonempty("ICON_SETS_FILE") {
+        let text = std::fs::read_to_string(&path).map_err(|e| anyhow::anyhow!("无法读取 {}: {}", path, e))?;
+        let sets: HashMap<String, IconSet> =
+            serde_json::from_str(&text).map_err(|e| anyhow::anyhow!("无法解析 {}: {}", path, e))?;
+        // 纯文本场景依赖 emoji 集合，不允许改为其他类型
+        if sets.get(EMOJI).is_some_and(|set| set.kind != Kind::Emoji) {
+            anyhow::bail!("{} 中的 emoji 图标集 type 必须为 emoji", path);
+        }
+        let _ = CUSTOM_SETS.set(sets);
+    }
+    let default = env_nonempty("ICON_SET").unwrap_or_else(|| EMOJI.into());
+    if find(&default).is_none() {
+        anyhow::bail!("ICON_SET 指定的图标集不存在: {}", default);
+    }
+    let _ = DEFAULT_SET.set(default);
+    Ok(())
+}
+
+// ?icons= 参数 → 图标集名称；未指定时为租户的图标集或 ICON_SET
+pub fn select(param: Option<&str>) -> Result<&str, String> {
+    match param.filter(|s| !s.is_empty()) {
+        Some(name) if find(name).is_some() => Ok(name),
+        Some(name) => Err(format!("未知的图标集: {}", name)),
+        None => Ok(tenant::current()
+            .and_then(|t| t.icon_set.as_deref())
+            .or(DEFAULT_SET.get().map(String::as_str))
+            .unwrap_or(EMOJI)),
+    }
+}
+
+fn layers(set: &IconSet, code: &str) -> Vec<String> {
+    let layers = set.icons.get(code).unwrap_or(&set.fallback).to_vec();
+    if set.kind == Kind::Image && !set.base_url.is_empty() {
+        return layers.into_iter().map(|file| format!("{}{}", set.base_url, file)).collect();
+    }
+    layers
+}
+
+// 图标各层（从后到前），未知集合按 emoji 处理
+pub fn icon_layers(set: &str, code: &str) -> Vec<String> {
+    let set = find(set).or_else(|| find(EMOJI)).expect("内置 emoji 图标集");
+    layers(set, code)
+}
+
+// { icon, desc, layers? }：单层图标省略 layers
+pub fn info(set: &str, code: &str) -> Value {
+    let layers = icon_layers(set, code);
+    let mut info = json!({ "icon": layers.last(), "desc": weather::skycon_desc(code) });
+    if layers.len() > 1 {
+        info["layers"] = json!(layers);
+    }
+    info
+}
+
+pub fn emoji_info(code: &str) -> Value {
+    info(EMOJI, code)
+}
+
+// 纯文本场景（卡片、消息推送）使用的单个 emoji
+pub fn plain(code: &str) -> String {
+    find(EMOJI).map(|set| layers(set, code)).and_then(|l| l.last().cloned()).unwrap_or_default()
+}
+
+// v1 天气数据按 emoji 生成，API 指定其他图标集时按 skycon 代码重新映射
+pub fn apply(data: &mut WeatherData, set: &str) {
+    if set == EMOJI {
+        return;
+    }
+    let code = data.current.skycon.as_str().unwrap_or("").to_string();
+    data.current.weather_info = info(set, &code);
+    let items = [&mut data.hourly, &mut data.daily];
+    for item in items.into_iter().filter_map(|v| v.as_array_mut()).flatten() {
+        for (code_key, info_key) in
+            [("skycon", "weather_info"), ("skycon_day", "weather_info_day"), ("skycon_night", "weather_info_night")]
+        {
+            if let Some(code) = item.get(code_key).and_then(|v| v.as_str()).map(str::to_string) {
+                item[info_key] = info(set, &code);
+            }
+        }
+    }
+}
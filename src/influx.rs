@@ -0,0 +1,100 @@
+// InfluxDB 行协议输出：/api/v1/weather/influx 将实况与空气质量渲染为一行数据，供 Telegraf http 输入直接抓取
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{location, weather, AppState};
+
+#[derive(Deserialize)]
+pub struct InfluxQuery {
+    lng: f64,
+    lat: f64,
+    measurement: Option<String>,
+}
+
+// measurement 需转义逗号与空格；tag 键/值还需转义等号
+fn escape_measurement(s: &str) -> String {
+    s.replace(',', "\\,").replace(' ', "\\ ")
+}
+
+fn escape_tag(s: &str) -> String {
+    s.replace(',', "\\,").replace('=', "\\=").replace(' ', "\\ ")
+}
+
+fn escape_string_field(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn render(measurement: &str, city: &str, lat: f64, lng: f64, data: &weather::WeatherData) -> String {
+    let cur = &data.current;
+    let mut fields = vec![
+        format!("temperature={}i", cur.temperature),
+        format!("apparent_temperature={}i", cur.apparent_temperature),
+        format!("humidity={}i", cur.humidity),
+        format!("wind_speed={}i", cur.wind_speed),
+        format!("wind_direction={}i", cur.wind_direction),
+        format!("pressure={}i", cur.pressure),
+        format!("dew_point={}i", cur.dew_point),
+        format!("cloud_cover={}i", cur.cloud_cover),
+    ];
+    if let Some(v) = cur.visibility.as_f64() {
+        fields.push(format!("visibility={}", v));
+    }
+    if let Some(v) = cur.solar_radiation {
+        fields.push(format!("solar_radiation={}", v));
+    }
+    let aq = &cur.air_quality;
+    let numeric = [
+        ("aqi", aq.pointer("/aqi/chn")),
+        ("pm25", aq.get("pm25")),
+        ("pm10", aq.get("pm10")),
+        ("o3", aq.get("o3")),
+        ("no2", aq.get("no2")),
+        ("so2", aq.get("so2")),
+        ("co", aq.get("co")),
+    ];
+    for (key, value) in numeric {
+        if let Some(n) = value.and_then(Value::as_f64) {
+            fields.push(format!("{}={}", key, n));
+        }
+    }
+    if let Some(s) = cur.skycon.as_str() {
+        fields.push(format!("skycon=\"{}\"", escape_string_field(s)));
+    }
+
+    let mut tags = format!("lat={:.4},lng={:.4}", lat, lng);
+    if !city.is_empty() {
+        tags = format!("city={},{}", escape_tag(city), tags);
+    }
+    let ts = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+    format!("{},{} {} {}\n", escape_measurement(measurement), tags, fields.join(","), ts)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn weather(State(state): State<AppState>, Query(q): Query<InfluxQuery>) -> Response {
+    let measurement = q.measurement.as_deref().map(str::trim).unwrap_or("weather");
+    if measurement.is_empty() {
+        return (StatusCode::BAD_REQUEST, "measurement 不能为空").into_response();
+    }
+    let (data, city) = tokio::join!(
+        weather::fetch(&state, q.lng, q.lat),
+        location::reverse_geocode(&state, q.lat, q.lng)
+    );
+    let data = match data {
+        Ok(d) => d,
+        Err(e) => return (e.status(), e.to_string()).into_response(),
+    };
+    // 逆地理失败时省略 city 标签，避免写入无意义的序列
+    let city = if city == location::UNKNOWN_PLACE { "" } else { city.as_str() };
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, HeaderValue::from_static("text/plain; charset=utf-8"))],
+        render(measurement, city, q.lat, q.lng, &data),
+    )
+        .into_response()
+}
@@ -0,0 +1,139 @@
+// JSONP：JSON 接口带 ?callback=fn 时包装为 `fn(...)` 脚本，供只能使用 <script> 的旧版挂件嵌入
+// 回调名仅允许 JS 标识符及点号路径（如 `jQuery123_456`、`app.onWeather`），防止脚本注入
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+const MAX_CALLBACK_LEN: usize = 64;
+// JSON 接口响应体上限，避免异常大响应占用内存
+const MAX_BODY: usize = 4 * 1024 * 1024;
+
+fn callback_param(query: Option<&str>) -> Option<String> {
+    query?
+        .split('&')
+        .filter_map(|kv| kv.split_once('='))
+        .find(|(k, _)| *k == "callback")
+        .and_then(|(_, v)| urlencoding::decode(v).ok().map(|v| v.into_owned()))
+}
+
+fn valid_callback(name: &str) -> bool {
+    name.len() <= MAX_CALLBACK_LEN
+        && name.split('.').all(|part| {
+            let mut chars = part.chars();
+            matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        })
+}
+
+pub async fn middleware(req: Request, next: Next) -> Response {
+    let Some(callback) = callback_param(req.uri().query()) else {
+        return next.run(req).await;
+    };
+    if !valid_callback(&callback) {
+        return (StatusCode::BAD_REQUEST, "callback 参数无效").into_response();
+    }
+
+    let resp = next.run(req).await;
+    let is_json = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json"));
+    if !is_json {
+        return resp;
+    }
+
+    let (mut parts, body) = resp.into_parts();
+    let body = match to_bytes(body, MAX_BODY).await {
+        Ok(b) => b,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    // 前置注释避免 Rosetta Flash 类攻击；U+2028/2029 在旧版 JS 中不能出现在字符串字面量内
+    let json = String::from_utf8_lossy(&body)
+        .replace('\u{2028}', "\\u2028")
+        .replace('\u{2029}', "\\u2029");
+    let script = format!("/**/ typeof {0} === 'function' && {0}({1});", callback, json);
+
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/javascript; charset=utf-8"),
+    );
+    parts.headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(script))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, Request, StatusCode},
+        middleware,
+        response::IntoResponse,
+        routing::get,
+        Json, Router,
+    };
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    use super::{callback_param, valid_callback, MAX_CALLBACK_LEN};
+
+    #[test]
+    fn accepts_identifiers_and_dotted_paths_only() {
+        for name in ["cb", "jQuery123_456", "$", "_private", "app.onWeather", "a.$b.c_1"] {
+            assert!(valid_callback(name), "{}", name);
+        }
+        for name in ["", "alert(1)//", "1cb", "a..b", ".a", "a.", "a-b", "a b", "a;b", "a[0]", "回调", "a\u{2028}"] {
+            assert!(!valid_callback(name), "{}", name);
+        }
+        assert!(valid_callback(&"a".repeat(MAX_CALLBACK_LEN)));
+        assert!(!valid_callback(&"a".repeat(MAX_CALLBACK_LEN + 1)));
+    }
+
+    #[test]
+    fn callback_is_read_from_the_decoded_query() {
+        assert_eq!(callback_param(Some("lng=1&callback=app.cb")).as_deref(), Some("app.cb"));
+        assert_eq!(callback_param(Some("callback=alert%281%29%2F%2F")).as_deref(), Some("alert(1)//"));
+        assert_eq!(callback_param(Some("jsonp=cb")), None);
+        assert_eq!(callback_param(None), None);
+    }
+
+    async fn call(uri: &str) -> (StatusCode, Option<String>, String) {
+        let app = Router::new()
+            .route("/json", get(|| async { Json(json!({ "text": "a\u{2028}b\u{2029}c" })) }))
+            .route("/text", get(|| async { "plain".into_response() }))
+            .layer(middleware::from_fn(super::middleware));
+        let resp = app.oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+        let content_type = resp.headers().get(header::CONTENT_TYPE).map(|v| v.to_str().unwrap().to_string());
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        (status, content_type, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn wraps_json_and_escapes_line_separators() {
+        let (status, content_type, body) = call("/json?callback=app.cb").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type.as_deref(), Some("application/javascript; charset=utf-8"));
+        assert_eq!(body, "/**/ typeof app.cb === 'function' && app.cb({\"text\":\"a\\u2028b\\u2029c\"});");
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_callbacks_and_leaves_other_responses_alone() {
+        let (status, _, _) = call("/json?callback=alert(1)//").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _, _) = call(&format!("/json?callback={}", "a".repeat(MAX_CALLBACK_LEN + 1))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, content_type, body) = call("/text?callback=cb").await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "plain"));
+        assert!(content_type.unwrap().starts_with("text/plain"));
+        let (_, content_type, body) = call("/json").await;
+        assert_eq!(content_type.as_deref(), Some("application/json"));
+        assert!(body.contains('\u{2028}'));
+    }
+}
@@ -0,0 +1,200 @@
+// 地点查询：关键字搜索（高德，和风城市查询回退）、坐标逆地理（美团优先，高德、和风回退）、IP 定位（美团）
+// IP 定位结果按 IP 缓存在进程内，容量满时淘汰最久未用的；失败也短暂缓存，上游故障时页面刷新不必每次等到超时
+// - GEOIP_CACHE_SECS：定位成功的缓存秒数，默认 3600，0 关闭
+// - GEOIP_NEGATIVE_CACHE_SECS：定位失败的缓存秒数，默认 60，0 关闭
+// - GEOIP_CACHE_SIZE：成功、失败各自缓存的 IP 数上限，默认 4096
+
+use std::{net::IpAddr, time::Duration};
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+use crate::{cache::TtlCache, config::env_parse, qweather, tenant, upstream, upstream_cache, upstream_url::UpstreamUrl, AppState};
+
+#[derive(Serialize, Clone)]
+pub struct Place {
+    pub lat: f64,
+    pub lng: f64,
+    pub name: String,
+    pub address: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct IpLocation {
+    pub lat: f64,
+    pub lng: f64,
+    pub address: String,
+}
+
+struct GeoIpCache {
+    located: Option<TtlCache<IpAddr, IpLocation>>,
+    failed: Option<TtlCache<IpAddr, ()>>,
+}
+
+static GEOIP_CACHE: OnceCell<GeoIpCache> = OnceCell::new();
+
+fn ip_cache<V: Clone>(key: &str, default_secs: u64, size: usize) -> anyhow::Result<Option<TtlCache<IpAddr, V>>> {
+    let secs = env_parse::<u64>(key)?.unwrap_or(default_secs);
+    Ok((secs > 0).then(|| TtlCache::new(Duration::from_secs(secs), size)))
+}
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    let size = env_parse::<usize>("GEOIP_CACHE_SIZE")?.unwrap_or(4096);
+    let _ = GEOIP_CACHE.set(GeoIpCache {
+        located: ip_cache("GEOIP_CACHE_SECS", 3600, size)?,
+        failed: ip_cache("GEOIP_NEGATIVE_CACHE_SECS", 60, size)?,
+    });
+    Ok(())
+}
+
+// IP → 粗略位置（美团官方接口），失败返回 None
+pub async fn locate_ip(state: &AppState, ip: IpAddr) -> Option<IpLocation> {
+    let cache = GEOIP_CACHE.get();
+    if let Some(hit) = cache.and_then(|c| c.located.as_ref()?.get(&ip)) {
+        return Some(hit);
+    }
+    if cache.and_then(|c| c.failed.as_ref()?.get(&ip)).is_some() {
+        return None;
+    }
+    let located = locate_ip_meituan(state, ip).await;
+    match (cache, &located) {
+        (Some(GeoIpCache { located: Some(c), .. }), Some(loc)) => c.insert(ip, loc.clone()),
+        (Some(GeoIpCache { failed: Some(c), .. }), None) => c.insert(ip, ()),
+        _ => {}
+    }
+    located
+}
+
+async fn locate_ip_meituan(state: &AppState, ip: IpAddr) -> Option<IpLocation> {
+    let url = UpstreamUrl::new(&state.base_urls.meituan).path("/locate/v2/ip/loc").query("rgeo", true).query("ip", ip);
+    let req = upstream::client("meituan")
+        .get(url.as_str())
+        .header("User-Agent", "Mozilla/5.0 (compatible; caiyun-rust/0.1)")
+        .header("Accept", "application/json")
+        .header("Referer", "https://i.meituan.com/");
+    let resp = upstream::send("meituan", "/locate/v2/ip/loc", req).await.ok()?;
+    let v = upstream::json::<serde_json::Value>(resp).await.ok()?;
+    let data = v.get("data")?;
+    let rgeo = data.get("rgeo").unwrap_or(&serde_json::Value::Null);
+    let address = rgeo.get("district").and_then(|x| x.as_str())
+        .or_else(|| rgeo.get("city").and_then(|x| x.as_str()))
+        .or_else(|| rgeo.get("province").and_then(|x| x.as_str()))
+        .unwrap_or(UNKNOWN_PLACE);
+    Some(IpLocation {
+        lat: data.get("lat")?.as_f64()?,
+        lng: data.get("lng")?.as_f64()?,
+        address: address.to_string(),
+    })
+}
+
+// 高德无结果（失败或未配置 key）时回退和风城市查询（需 QWEATHER_KEY），均失败返回空列表
+pub async fn search(state: &AppState, q: &str) -> Vec<Place> {
+    let places = search_amap(state, q).await;
+    if places.is_empty() {
+        return qweather::search(state, q).await;
+    }
+    places
+}
+
+async fn search_amap(state: &AppState, q: &str) -> Vec<Place> {
+    let Some(key) = tenant::amap_key(state) else {
+        return Vec::new();
+    };
+    let url = UpstreamUrl::new(&state.base_urls.amap)
+        .path("/v3/place/text")
+        .secret_query("key", &key)
+        .query("keywords", q)
+        .query("offset", 5)
+        .query("page", 1)
+        .query("extensions", "base");
+    let Ok(resp) = upstream::send("amap", "/v3/place/text", upstream::client("amap").get(url.as_str())).await else {
+        return Vec::new();
+    };
+    let Ok(v) = upstream::json::<serde_json::Value>(resp).await else {
+        return Vec::new();
+    };
+    v.get("pois")
+        .and_then(|v| v.as_array())
+        .map(|pois| {
+            pois.iter()
+                .take(5)
+                .filter_map(|poi| {
+                    let name = poi.get("name")?.as_str()?.to_string();
+                    let address = poi.get("address").and_then(|x| x.as_str()).unwrap_or("").to_string();
+                    let loc = poi.get("location")?.as_str()?; // "lng,lat"
+                    let mut it = loc.split(',');
+                    let lng = it.next()?.parse::<f64>().ok()?;
+                    let lat = it.next()?.parse::<f64>().ok()?;
+                    Some(Place { lat, lng, name, address })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub const UNKNOWN_PLACE: &str = "未知位置";
+
+// 坐标 → 地址：先用美团官方 latlng 接口，失败再尝试高德逆地理、和风城市查询（均可选），都失败返回“未知位置”
+// 结果经上游结果缓存（GEOCODE_CACHE_SECS）；“未知位置”不缓存
+pub async fn reverse_geocode(state: &AppState, lat: f64, lng: f64) -> String {
+    let state = state.clone();
+    // 后台刷新时沿用发起请求的租户的 key
+    let tenant = tenant::current();
+    let cached = upstream_cache::get_or_fetch(upstream_cache::Kind::Geocode, lng, lat, move || {
+        let state = state.clone();
+        tenant::scope(tenant, async move {
+            match lookup_address(&state, lat, lng).await {
+                address if address == UNKNOWN_PLACE => Err(()),
+                address => Ok(serde_json::Value::String(address)),
+            }
+        })
+    })
+    .await;
+    cached
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_else(|| UNKNOWN_PLACE.to_string())
+}
+
+async fn lookup_address(state: &AppState, lat: f64, lng: f64) -> String {
+    let mt_url = UpstreamUrl::new(&state.base_urls.meituan)
+        .path("/group/v1/city/latlng")
+        .segment(format_args!("{},{}", lat, lng))
+        .query("tag", 0);
+    let mt_req = upstream::client("meituan")
+        .get(mt_url.as_str())
+        .header("User-Agent", "Mozilla/5.0 (compatible; caiyun-rust/0.1)")
+        .header("Accept", "application/json")
+        .header("Referer", "https://i.meituan.com/");
+    if let Ok(resp) = upstream::send("meituan", "/group/v1/city/latlng/{lat},{lng}", mt_req).await {
+        if let Ok(v) = upstream::json::<serde_json::Value>(resp).await {
+            if let Some(data) = v.get("data") {
+                let address = data.get("detail").and_then(|x| x.as_str())
+                    .or_else(|| data.get("openCityName").and_then(|x| x.as_str()))
+                    .or_else(|| data.get("city").and_then(|x| x.as_str()))
+                    .unwrap_or(UNKNOWN_PLACE);
+                return address.to_string();
+            }
+        }
+    }
+
+    if let Some(key) = tenant::amap_key(state) {
+        let url = UpstreamUrl::new(&state.base_urls.amap)
+            .path("/v3/geocode/regeo")
+            .secret_query("key", &key)
+            .query("location", format_args!("{},{}", lng, lat))
+            .query("radius", 1000)
+            .query("extensions", "base");
+        if let Ok(resp) = upstream::send("amap", "/v3/geocode/regeo", upstream::client("amap").get(url.as_str())).await {
+            if let Ok(v) = upstream::json::<serde_json::Value>(resp).await {
+                if v.get("status").and_then(|s| s.as_str()) == Some("1") {
+                    if let Some(addr) = v.get("regeocode").and_then(|r| r.get("formatted_address")).and_then(|s| s.as_str()) {
+                        return addr.to_string();
+                    }
+                }
+            }
+        }
+    }
+
+    qweather::city_name(state, lat, lng).await.unwrap_or_else(|| UNKNOWN_PLACE.to_string())
+}
@@ -1,87 +1,212 @@
-use std::{net::SocketAddr, time::Duration};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 
 use axum::{
     extract::{Query, State},
-    http::{HeaderValue, StatusCode},
-    response::{Html, IntoResponse},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{Html, IntoResponse, Response},
     routing::{get},
-    Json, Router,
+    Router,
 };
-use once_cell::sync::Lazy;
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use tokio::fs;
 use tower::ServiceBuilder;
-use tower_http::{
-    compression::CompressionLayer,
-    cors::{Any, CorsLayer},
-    services::ServeDir,
-    set_header::SetResponseHeaderLayer,
-    trace::TraceLayer,
-};
-use tracing::info;
-use chrono::{Datelike, Timelike, Local, Days, Weekday};
-
-static CLIENT: Lazy<Client> = Lazy::new(|| {
-    Client::builder()
-        .http2_adaptive_window(true)
-        .gzip(true)
-        .brotli(true)
-        .timeout(Duration::from_secs(10))
-        .build()
-        .expect("reqwest client")
-});
+use tower_http::trace::TraceLayer;
+
+mod access_log;
+mod accounts;
+mod accuracy;
+mod admin;
+mod advice;
+mod air;
+mod alert_history;
+mod alerts;
+mod almanac;
+mod api;
+mod api_keys;
+mod assets;
+mod base_path;
+mod bench;
+mod body_limit;
+mod brief;
+mod cache;
+mod changes;
+mod chaos;
+mod client_config;
+mod client_ip;
+mod compression;
+mod config;
+mod cors;
+mod daylight;
+mod demo;
+#[cfg(feature = "email")]
+mod digest;
+mod dns;
+mod doctor;
+mod error_report;
+mod extended;
+mod fallback;
+mod feature_flags;
+mod geojson;
+#[cfg(feature = "graphql")]
+mod graphql;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod ha;
+mod hedge;
+mod hot_list;
+mod i18n;
+#[cfg(feature = "http3")]
+mod http3;
+mod icons;
+mod influx;
+mod jsonp;
+mod location;
+mod meteo;
+mod mock;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod nowcast;
+mod og_image;
+mod oidc;
+mod open_meteo;
+mod openweathermap;
+mod overload;
+mod provider;
+mod pwa;
+mod qweather;
+mod radar;
+mod redact;
+#[cfg(feature = "redis")]
+mod redis_cache;
+mod request_id;
+mod respond;
+mod route_timeout;
+mod scheduler;
+mod security_headers;
+mod server;
+mod share;
+mod signing;
+mod ssr;
+mod stats;
+mod store;
+mod telegram;
+mod telemetry;
+mod tenant;
+mod tips;
+mod trend;
+mod typhoon;
+mod upstream;
+mod upstream_budget;
+mod upstream_cache;
+mod upstream_url;
+mod voice;
+mod weather;
+mod weather_metrics;
+mod weather_v2;
+mod webhooks;
+
 
 #[derive(Clone)]
 struct AppState {
     caiyun_token: Option<String>,
     amap_key: Option<String>,
+    qweather_key: Option<String>,
+    base_urls: Arc<upstream::BaseUrls>,
 }
 
 #[derive(Deserialize)]
-struct WeatherQuery { lng: f64, lat: f64 }
+struct WeatherQuery {
+    lng: f64,
+    lat: f64,
+    format: Option<String>,
+    verbose: Option<String>,
+    icons: Option<String>,
+    granularity: Option<String>,
+    lang: Option<String>,
+}
 
 #[derive(Serialize)]
-struct ErrorResp { error: String }
+struct ErrorResp {
+    error: String,
+    // 机器可读的错误码（如 UPSTREAM_TIMEOUT），供客户端区分处理
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
 
-#[derive(Serialize)]
-struct WeatherCurrent {
-    temperature: i64,
-    apparent_temperature: i64,
-    humidity: i64,
-    wind_speed: i64,
-    wind_direction: i64,
-    pressure: i64,
-    visibility: serde_json::Value,
-    skycon: serde_json::Value,
-    weather_info: serde_json::Value,
-    air_quality: serde_json::Value,
+impl ErrorResp {
+    // 错误信息可能含上游地址，去掉其中的 token、key
+    fn new(error: impl Into<String>) -> Self {
+        Self { error: redact::redact(&error.into()), code: None, request_id: request_id::current() }
+    }
+
+    fn with_code(code: &'static str, error: impl Into<String>) -> Self {
+        Self { code: Some(code), ..Self::new(error) }
+    }
 }
 
-#[derive(Serialize)]
-struct WeatherData {
-    current: WeatherCurrent,
-    hourly: serde_json::Value,
-    daily: serde_json::Value,
-    forecast_keypoint: serde_json::Value,
+// 数据库错误只记日志，不把 rusqlite 的原始信息（表结构、约束、路径）返回给客户端
+#[track_caller]
+fn db_error(e: rusqlite::Error) -> Response {
+    tracing::warn!("database error at {}: {}", std::panic::Location::caller(), e);
+    respond::json(StatusCode::INTERNAL_SERVER_ERROR, &ErrorResp::new("数据库错误"))
+}
+
+// 读取并校验配置，初始化各模块；doctor 子命令复用以检查配置
+fn init_from_env() -> anyhow::Result<AppState> {
+    let state = AppState {
+        caiyun_token: config::env_nonempty("CAIYUN_API_TOKEN"),
+        amap_key: config::env_nonempty("AMAP_API_KEY"),
+        qweather_key: config::env_nonempty("QWEATHER_KEY"),
+        base_urls: Arc::new(upstream::BaseUrls::from_env()?),
+    };
+    access_log::init_from_env()?;
+    base_path::init_from_env()?;
+    advice::init_from_env()?;
+    tips::init_from_env()?;
+    brief::init_from_env()?;
+    webhooks::init_from_env()?;
+    upstream::init_from_env(&state.base_urls)?;
+    route_timeout::init_from_env()?;
+    hedge::init_from_env()?;
+    upstream_budget::init_from_env()?;
+    chaos::init_from_env()?;
+    signing::init_from_env()?;
+    changes::init_from_env()?;
+    alerts::init_from_env()?;
+    alert_history::init_from_env()?;
+    icons::init_from_env()?;
+    tenant::init_from_env()?;
+    location::init_from_env()?;
+    accounts::init_from_env()?;
+    oidc::init_from_env()?;
+    share::init_from_env()?;
+    upstream_cache::init_from_env()?;
+    hot_list::init_from_env()?;
+    provider::init_from_env()?;
+    feature_flags::init_from_env()?;
+    stats::init_from_env()?;
+    api_keys::init_from_env()?;
+    assets::init_from_env()?;
+    Ok(state)
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info,tower_http=info".into()),
-        )
-        .with_target(false)
-        .compact()
-        .init();
+    redact::install_panic_hook();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("bench") => return bench::run(&args[1..]).await,
+        Some("doctor") => return doctor::run().await,
+        _ => {}
+    }
+    let telemetry = telemetry::init()?;
+    let _error_report = error_report::init();
 
-    let state = AppState {
-        caiyun_token: std::env::var("CAIYUN_API_TOKEN").ok(),
-        amap_key: std::env::var("AMAP_API_KEY").ok(),
-    };
+    let state = init_from_env()?;
+    let access_log = access_log::start()?;
 
     let port: u16 = std::env::var("PORT").ok().and_then(|s| s.parse().ok()).unwrap_or(8000);
     // 可选从环境变量读取主机地址，默认 0.0.0.0
@@ -92,42 +217,122 @@ async fn main() -> anyhow::Result<()> {
         .parse()
         .unwrap_or(([0, 0, 0, 0], port).into());
 
-    let static_service = ServeDir::new("static");
+    // 可选 gRPC 服务：与 HTTP 同一主机，独立端口
+    if let Some(grpc_port) = config::env_parse::<u16>("GRPC_PORT")? {
+        #[cfg(feature = "grpc")]
+        {
+            let grpc_addr = SocketAddr::new(addr.ip(), grpc_port);
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = grpc::serve(state, grpc_addr).await {
+                    tracing::error!("grpc server failed: {}", e);
+                }
+            });
+        }
+        #[cfg(not(feature = "grpc"))]
+        tracing::warn!("已设置 GRPC_PORT={}，但未启用 `grpc` feature", grpc_port);
+    }
+
+    telegram::spawn_from_env(state.clone())?;
+    accuracy::spawn_from_env(state.clone())?;
+    webhooks::spawn(state.clone())?;
+    weather_metrics::spawn_from_env(state.clone())?;
+    hot_list::spawn(state.clone())?;
+    #[cfg(feature = "email")]
+    digest::spawn_from_env(state.clone())?;
+    #[cfg(not(feature = "email"))]
+    if config::env_nonempty("SMTP_HOST").is_some() {
+        tracing::warn!("已设置 SMTP_HOST，但未启用 `email` feature");
+    }
+    #[cfg(feature = "mqtt")]
+    mqtt::spawn_from_env(state.clone())?;
+    #[cfg(not(feature = "mqtt"))]
+    if config::env_nonempty("MQTT_URL").is_some() {
+        tracing::warn!("已设置 MQTT_URL，但未启用 `mqtt` feature");
+    }
 
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let cors = cors::layer_from_env()?;
+    let limiter = overload::from_env()?;
+    let max_body = body_limit::from_env()?;
+    let compression = compression::layer_from_env()?;
 
-    let app = Router::new()
-        .route("/api/weather", get(api_weather))
-        .route("/api/location/ip", get(api_location_ip))
-        .route("/api/location/geocode", get(api_location_geocode))
-        .route("/api/location/search", get(api_location_search))
+    // 页面与静态资源：附加安全响应头；JSON API 不附加
+    let security_headers = Arc::new(security_headers::SecurityHeaders::from_env()?);
+    let pages = Router::new()
         .route("/favicon.ico", get(favicon))
         .route("/", get(index))
         .route("/index.html", get(index))
-        .nest_service("/static", static_service)
+        .route("/w", get(ssr::default_page))
+        .route("/w/", get(ssr::default_page))
+        .route("/w/:city", get(ssr::weather_page))
+        .route("/s/:code", get(share::redirect))
+        .route("/manifest.webmanifest", get(pwa::manifest))
+        .route("/sw.js", get(pwa::service_worker))
+        .route("/offline.json", get(pwa::offline))
+        .nest_service("/static", assets::service())
+        .layer(axum::middleware::from_fn_with_state(security_headers, security_headers::middleware));
+
+    let app = api::router().route("/metrics/weather", get(weather_metrics::handler));
+    #[cfg(feature = "graphql")]
+    let app = app.route(
+        "/graphql",
+        get(graphql::graphiql)
+            .post(graphql::execute)
+            .layer(axum::middleware::from_fn_with_state("graphql", feature_flags::gate)),
+    );
+
+    // 路径前缀租户在前缀下挂载同一套路由
+    let app = tenant::nest(app.merge(pages).fallback(fallback::handler));
+    let app = app
         .with_state(state)
         .layer(ServiceBuilder::new()
-            // 先添加 Trace 和 Header，再压缩，最后加 CORS（CORS 放最后避免对 ResponseBody 的 Default 约束）
-            .layer(TraceLayer::new_for_http())
-            .layer(SetResponseHeaderLayer::if_not_present(
-                axum::http::header::CONTENT_TYPE,
-                HeaderValue::from_static("application/json; charset=utf-8"),
-            ))
-            .layer(CompressionLayer::new())
+            // 最外层生成/透传请求 ID，供 Trace span 使用
+            // 先添加 Trace，再压缩，最后加 CORS（CORS 放最后避免对 ResponseBody 的 Default 约束）
+            .layer(axum::middleware::from_fn(request_id::middleware))
+            // 访问日志在压缩层之外，记录实际发出的字节数
+            .layer(axum::middleware::from_fn(access_log::middleware))
+            .layer(axum::middleware::from_fn(mock::middleware))
+            .layer(TraceLayer::new_for_http()
+                .make_span_with(request_id::make_span)
+                .on_response(telemetry::on_response))
+            // 过载拒绝的 503 记入访问日志，但不作为错误上报
+            .layer(axum::middleware::from_fn_with_state(limiter, overload::middleware))
+            .layer(axum::middleware::from_fn(stats::middleware))
+            .layer(axum::middleware::from_fn(hot_list::middleware))
+            // 按主机名识别租户；路径前缀租户在路由内识别（tenant::nest）
+            .layer(axum::middleware::from_fn(tenant::middleware))
+            .layer(axum::middleware::from_fn(route_timeout::middleware))
+            .layer(axum::middleware::from_fn(upstream_cache::middleware))
+            .layer(axum::middleware::from_fn(error_report::middleware))
+            // 声明长度超限时直接拒绝；未声明长度的由 DefaultBodyLimit 在提取时截断
+            .layer(axum::middleware::from_fn_with_state(max_body, body_limit::middleware))
+            .layer(axum::extract::DefaultBodyLimit::max(max_body))
+            .layer(compression)
+            // 签名未压缩的响应体
+            .layer(axum::middleware::from_fn(signing::middleware))
             .layer(cors)
         );
-
-    info!("listening on {}", addr);
-    axum::serve(tokio::net::TcpListener::bind(addr).await?, app).await?;
-    Ok(())
+    // 路径前缀在所有中间件之外去掉
+    let app = base_path::nest(app);
+
+    // 收到 SIGTERM/SIGINT 并处理完进行中的请求后返回；写出访问日志与缓冲中的日志、链路数据后退出
+    let result = server::serve(app, addr).await;
+    tracing::info!("server stopped");
+    api_keys::flush();
+    upstream_cache::flush();
+    drop(access_log);
+    telemetry.shutdown();
+    result
 }
 
-async fn index() -> impl IntoResponse {
+async fn index(headers: HeaderMap, Query(query): Query<HashMap<String, String>>) -> impl IntoResponse {
     match fs::read_to_string("static/index.html").await {
-        Ok(s) => Html(s).into_response(),
+        // 引用带指纹的静态资源，首页本身每次协商
+        Ok(s) => (
+            [(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"))],
+            Html(base_path::rewrite_html(&og_image::inject_meta(&assets::rewrite(&s), &headers, &query))),
+        )
+            .into_response(),
         Err(_) => (StatusCode::NOT_FOUND, "index not found").into_response(),
     }
 }
@@ -143,388 +348,122 @@ async fn favicon() -> impl IntoResponse {
     )
 }
 
-// -------- helpers --------
-
-fn safe_round(v: &serde_json::Value, default_: i64) -> i64 {
-    v.as_f64().map(|n| n.round() as i64).unwrap_or(default_)
-}
-
-fn safe_number(v: &serde_json::Value, default_: i64) -> i64 {
-    v.as_f64().map(|n| n as i64).unwrap_or(default_)
-}
-
-fn safe_get<'a>(v: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
-    let mut cur = v;
-    for key in path.split('.') {
-        cur = cur.get(key)?;
-    }
-    Some(cur)
-}
+// -------- handlers --------
 
-fn skycon_info(s: &str) -> serde_json::Value {
-    // 简化：仅返回 code
-    let (icon, desc) = match s {
-        "CLEAR_DAY" => ("☀️", "晴"),
-        "CLEAR_NIGHT" => ("🌙", "晴（夜间）"),
-        "PARTLY_CLOUDY_DAY" => ("⛅", "多云"),
-        // 使用单一组合图标（HTML 片段），通过前端 CSS 层叠出“云遮月”。
-        // 这里返回 HTML，前端将用 innerHTML 渲染（见 static/script.js）。
-        "PARTLY_CLOUDY_NIGHT" => (
-            "<span class=\"icon-stacked\"><span class=\"i-back\">🌙</span><span class=\"i-front\">☁️</span></span>",
-            "多云（夜间）"
-        ),
-        "CLOUDY" => ("☁️", "阴"),
-        "LIGHT_RAIN" => ("🌧️", "小雨"),
-        "MODERATE_RAIN" => ("🌧️", "中雨"),
-        "HEAVY_RAIN" => ("⛈️", "大雨"),
-        "STORM_RAIN" => ("⛈️", "暴雨"),
-        "HAIL" => ("🌨️", "冰雹"),
-        "SLEET" => ("🌨️", "雨夹雪"),
-        "LIGHT_SNOW" => ("🌨️", "小雪"),
-        "MODERATE_SNOW" => ("🌨️", "中雪"),
-        "HEAVY_SNOW" => ("❄️", "大雪"),
-        "STORM_SNOW" => ("❄️", "暴雪"),
-        "FOG" => ("🌫️", "雾"),
-        "LIGHT_HAZE" => ("🌫️", "轻度霾"),
-        "MODERATE_HAZE" => ("🌫️", "中度霾"),
-        "HEAVY_HAZE" => ("🌫️", "重度霾"),
-        "DUST" => ("🌪️", "浮尘"),
-        "SAND" => ("🌪️", "沙尘"),
-        "WIND" => ("🌬️", "大风"),
-        other => ("?", other),
+#[tracing::instrument(skip_all)]
+async fn api_weather(State(state): State<AppState>, accept: respond::Accept, Query(q): Query<WeatherQuery>) -> Response {
+    let format = match geojson::Format::parse(q.format.as_deref()) {
+        Ok(f) => f,
+        Err(e) => return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(e)),
     };
-    serde_json::json!({"icon": icon, "desc": desc})
-}
-
-fn format_weather_data(raw: &serde_json::Value, longitude: f64) -> anyhow::Result<WeatherData> {
-    let result = raw
-        .get("result")
-        .ok_or_else(|| anyhow::anyhow!("缺少 result"))?;
-    let realtime = result.get("realtime").ok_or_else(|| anyhow::anyhow!("缺少 realtime"))?;
-    let hourly = result.get("hourly").unwrap_or(&serde_json::Value::Null).clone();
-    let daily = result.get("daily").unwrap_or(&serde_json::Value::Null).clone();
-
-    let skycon_code = realtime.get("skycon").and_then(|v| v.as_str()).unwrap_or("CLEAR_DAY");
-    let current = WeatherCurrent {
-        temperature: safe_round(realtime.get("temperature").unwrap_or(&serde_json::Value::Null), 0),
-        apparent_temperature: safe_round(realtime.get("apparent_temperature").unwrap_or(&serde_json::Value::Null), 0),
-        humidity: ((safe_get(realtime, "humidity").and_then(|v| v.as_f64()).unwrap_or(0.0)) * 100.0).round() as i64,
-        wind_speed: ((safe_get(realtime, "wind.speed").and_then(|v| v.as_f64()).unwrap_or(0.0)) * 3.6).round() as i64,
-        wind_direction: safe_number(safe_get(realtime, "wind.direction").unwrap_or(&serde_json::Value::Null), 0),
-        pressure: ((safe_get(realtime, "pressure").and_then(|v| v.as_f64()).unwrap_or(101325.0)) / 100.0).round() as i64,
-        visibility: realtime.get("visibility").cloned().unwrap_or(serde_json::Value::Null),
-        skycon: serde_json::Value::String(skycon_code.to_string()),
-        weather_info: skycon_info(skycon_code),
-        air_quality: realtime.get("air_quality").cloned().unwrap_or(serde_json::Value::Null),
+    let icon_set = match icons::select(q.icons.as_deref()) {
+        Ok(set) => set,
+        Err(e) => return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(e)),
     };
-
-    let forecast_keypoint = result
-        .get("forecast_keypoint")
-        .cloned()
-        .unwrap_or_else(|| serde_json::Value::String("天气提示".into()));
-
-    // 映射 hourly -> 前端结构
-    let hourly_arr: Vec<serde_json::Value> = hourly
-        .get("temperature")
-        .and_then(|v| v.as_array())
-        .cloned()
-        .unwrap_or_default();
-    let sky_arr: Vec<serde_json::Value> = hourly
-        .get("skycon")
-        .and_then(|v| v.as_array())
-        .cloned()
-        .unwrap_or_default();
-    let tz_offset_hours = (longitude / 15.0).round() as i64;
-    let utc_now = chrono::Utc::now();
-    let local_hour = (utc_now + chrono::TimeDelta::hours(tz_offset_hours)).hour() as i32;
-    let count = hourly_arr.len().min(sky_arr.len()).min(24);
-    let mut hourly_out = Vec::with_capacity(count);
-    for i in 0..count {
-        let temp_v = hourly_arr[i].get("value").unwrap_or(&serde_json::Value::Null);
-        let sky_v = sky_arr[i].get("value").and_then(|v| v.as_str()).unwrap_or("CLEAR_DAY");
-        let hour = ((local_hour + i as i32) % 24 + 24) % 24; // 0-23
-        hourly_out.push(serde_json::json!({
-            "time": hour,
-            "temperature": safe_round(temp_v, 0),
-            "skycon": sky_v,
-            "weather_info": skycon_info(sky_v),
-        }));
-    }
-
-    // 映射 daily -> 前端结构（取前 3 天）
-    let daily_temp: Vec<serde_json::Value> = daily
-        .get("temperature")
-        .and_then(|v| v.as_array())
-        .cloned()
-        .unwrap_or_default();
-    let daily_sky: Vec<serde_json::Value> = daily
-        .get("skycon")
-        .and_then(|v| v.as_array())
-        .cloned()
-        .unwrap_or_default();
-    let life_index = daily.get("life_index").cloned().unwrap_or(serde_json::Value::Null);
-    let today = Local::now().date_naive();
-    let mut daily_out = Vec::new();
-    let dcount = daily_temp.len().min(3);
-    for i in 0..dcount {
-        let date = today.checked_add_days(Days::new(i as u64)).unwrap_or(today);
-        let relative = match i { 0 => "今天", 1 => "明天", 2 => "后天", _ => "" };
-        let weekday = match date.weekday() {
-            Weekday::Mon => "周一",
-            Weekday::Tue => "周二",
-            Weekday::Wed => "周三",
-            Weekday::Thu => "周四",
-            Weekday::Fri => "周五",
-            Weekday::Sat => "周六",
-            Weekday::Sun => "周日",
-        };
-        let temp_obj = &daily_temp[i];
-        let sky = daily_sky.get(i).and_then(|v| v.get("value")).and_then(|v| v.as_str()).unwrap_or("CLEAR_DAY");
-
-        // 生活指数提取助手
-        let li = |key: &str| -> serde_json::Value {
-            life_index.get(key)
-                .and_then(|arr| arr.as_array())
-                .and_then(|arr| arr.get(i))
-                .cloned()
-                .unwrap_or_else(|| serde_json::json!({"index":"","desc":""}))
-        };
-
-        daily_out.push(serde_json::json!({
-            "date": format!("{:02}-{:02}", date.month(), date.day()),
-            "weekday": weekday,
-            "relativeDay": relative,
-            "max_temp": safe_round(temp_obj.get("max").unwrap_or(&serde_json::Value::Null), 0),
-            "min_temp": safe_round(temp_obj.get("min").unwrap_or(&serde_json::Value::Null), 0),
-            "skycon": sky,
-            "weather_info": skycon_info(sky),
-            "life_index": {
-                "ultraviolet": li("ultraviolet"),
-                "carWashing": li("carWashing"),
-                "dressing": li("dressing"),
-                "comfort": li("comfort"),
-                "coldRisk": li("coldRisk"),
+    let granularity = match weather::Granularity::parse(q.granularity.as_deref()) {
+        Ok(g) => g,
+        Err(e) => return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(e)),
+    };
+    let locale = match i18n::Locale::parse(q.lang.as_deref()) {
+        Ok(l) => l,
+        Err(e) => return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(e)),
+    };
+    let verbose = matches!(q.verbose.as_deref(), Some("1" | "true"));
+    // 超过路由超时时回退到该地点最近一次的结果，并以 Warning 头标明数据已过期
+    let (mut data, stale) = match route_timeout::within(weather::fetch_with(&state, q.lng, q.lat, verbose)).await {
+        Some(Ok(data)) => {
+            if !mock::enabled() && !demo::enabled() {
+                pwa::remember(&data);
             }
-        }));
+            weather::remember_recent(q.lng, q.lat, verbose, &data);
+            (data, false)
+        }
+        Some(Err(e)) => return respond::json(e.status(), &ErrorResp::new(e.to_string())),
+        None => match weather::recent(q.lng, q.lat, verbose) {
+            Some(data) => {
+                tracing::warn!("weather timed out, serving cached data");
+                (data, true)
+            }
+            None => return route_timeout::timeout_response(),
+        },
+    };
+    if granularity == weather::Granularity::SixHours {
+        data.hourly = weather::six_hour_blocks(&data.hourly);
     }
-
-    Ok(WeatherData {
-        current,
-        hourly: serde_json::Value::Array(hourly_out),
-        daily: serde_json::Value::Array(daily_out),
-        forecast_keypoint,
-    })
-}
-
-// -------- handlers --------
-
-async fn api_weather(State(state): State<AppState>, Query(q): Query<WeatherQuery>) -> impl IntoResponse {
-    if state.caiyun_token.is_none() {
-        // 返回模拟数据，字段结构一致（简化版）
-        let data = WeatherData {
-            current: WeatherCurrent {
-                temperature: 26,
-                apparent_temperature: 30,
-                humidity: 87,
-                wind_speed: 28,
-                wind_direction: 0,
-                pressure: 1007,
-                visibility: serde_json::json!(5.26),
-                skycon: serde_json::json!("MODERATE_RAIN"),
-                weather_info: serde_json::json!({"icon":"?","desc":"中雨"}),
-                air_quality: serde_json::json!({"aqi":{"chn":14},"description":{"chn":"优"},"pm25":9,"pm10":14,"o3":19}),
-            },
-            hourly: serde_json::json!(
-                (0..24).map(|i| {
-                    serde_json::json!({
-                        "time": i,
-                        "temperature": 26,
-                        "skycon": "MODERATE_RAIN",
-                        "weather_info": {"icon":"?","desc":"中雨"}
-                    })
-                }).collect::<Vec<_>>()
-            ),
-            daily: serde_json::json!([
-                {"date":"今日","weekday":"周几","relativeDay":"今天","max_temp":29,"min_temp":24,"skycon":"MODERATE_RAIN","weather_info":{"icon":"?","desc":"中雨"},"life_index":{"ultraviolet":{"index":"中","desc":"注意防晒"}}}
-            ]),
-            forecast_keypoint: serde_json::json!("注意携带雨具"),
-        };
-        return (StatusCode::OK, Json(data)).into_response();
+    icons::apply(&mut data, icon_set);
+    if let Some(locale) = locale {
+        i18n::localize(&mut data, locale);
     }
-
-    let url = format!(
-        "https://api.caiyunapp.com/v2.6/{}/{},{}{}",
-        state.caiyun_token.as_deref().unwrap(),
-        q.lng,
-        q.lat,
-        "/weather?alert=true&dailysteps=3&hourlysteps=24&lang=zh_CN"
-    );
-
-    match CLIENT.get(&url).send().await {
-        Ok(resp) => match resp.error_for_status() {
-            Ok(r) => match r.json::<serde_json::Value>().await {
-                Ok(json) => {
-                    // 校验 ok/status
-                    if json.get("status").and_then(|v| v.as_str()) == Some("ok") || json.get("result").is_some() {
-                        match format_weather_data(&json, q.lng) {
-                            Ok(data) => (StatusCode::OK, Json(data)).into_response(),
-                            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResp{ error: format!("数据格式化失败: {}", e)})).into_response(),
-                        }
-                    } else {
-                        (StatusCode::BAD_GATEWAY, Json(ErrorResp{ error: "上游返回异常".into()})).into_response()
-                    }
-                }
-                Err(e) => (StatusCode::BAD_GATEWAY, Json(ErrorResp{ error: format!("解析上游失败: {}", e)})).into_response(),
-            },
-            Err(e) => (StatusCode::BAD_GATEWAY, Json(ErrorResp{ error: format!("上游错误: {}", e)})).into_response(),
-        },
-        Err(e) => (StatusCode::BAD_GATEWAY, Json(ErrorResp{ error: format!("请求失败: {}", e)})).into_response(),
+    let mut res = match format {
+        geojson::Format::GeoJson => respond::geojson(StatusCode::OK, &geojson::feature(q.lng, q.lat, &data)),
+        geojson::Format::Json => respond::negotiated(&accept, StatusCode::OK, &data),
+    };
+    if stale {
+        res.headers_mut().insert(header::WARNING, HeaderValue::from_static("110 - \"Response is Stale\""));
     }
+    res
 }
 
 #[derive(Deserialize)]
 struct GeocodeQuery { lat: f64, lng: f64 }
 
-async fn api_location_geocode(Query(q): Query<GeocodeQuery>, State(state): State<AppState>) -> impl IntoResponse {
-    // 先用美团官方 latlng 接口
-    let mt_url = format!(
-        "https://apimobile.meituan.com/group/v1/city/latlng/{},{}?tag=0",
-        q.lat, q.lng
-    );
-    let mt_req = CLIENT
-        .get(mt_url)
-        .header("User-Agent", "Mozilla/5.0 (compatible; caiyun-rust/0.1)")
-        .header("Accept", "application/json")
-        .header("Referer", "https://i.meituan.com/");
-    if let Ok(Ok(resp)) = tokio::time::timeout(Duration::from_secs(3), mt_req.send()).await {
-        if let Ok(v) = resp.json::<serde_json::Value>().await {
-            if let Some(data) = v.get("data") {
-                let address = data.get("detail").and_then(|x| x.as_str())
-                    .or_else(|| data.get("openCityName").and_then(|x| x.as_str()))
-                    .or_else(|| data.get("city").and_then(|x| x.as_str()))
-                    .unwrap_or("未知位置");
-                return (StatusCode::OK, Json(serde_json::json!({"address": address}))).into_response();
-            }
-        }
-    }
-
-    // 失败再尝试高德逆地理（可选）
-    if let Some(key) = &state.amap_key {
-        let url = format!(
-            "https://restapi.amap.com/v3/geocode/regeo?key={}&location={},{}&radius=1000&extensions=base",
-            key, q.lng, q.lat
-        );
-        if let Ok(Ok(resp)) = tokio::time::timeout(Duration::from_secs(3), CLIENT.get(url).send()).await {
-            if let Ok(v) = resp.json::<serde_json::Value>().await {
-                if v.get("status").and_then(|s| s.as_str()) == Some("1") {
-                    if let Some(addr) = v.get("regeocode").and_then(|r| r.get("formatted_address")).and_then(|s| s.as_str()) {
-                        return (StatusCode::OK, Json(serde_json::json!({"address": addr}))).into_response();
-                    }
-                }
-            }
-        }
-    }
-
-    (StatusCode::OK, Json(serde_json::json!({"address": "未知位置"}))).into_response()
+#[tracing::instrument(skip_all)]
+async fn api_location_geocode(Query(q): Query<GeocodeQuery>, accept: respond::Accept, State(state): State<AppState>) -> impl IntoResponse {
+    let address = location::reverse_geocode(&state, q.lat, q.lng).await;
+    respond::negotiated(&accept, StatusCode::OK, &serde_json::json!({"address": address}))
 }
 
 #[derive(Deserialize)]
-struct SearchQuery { q: String }
+struct SearchQuery { q: String, format: Option<String> }
 
-async fn api_location_search(Query(qs): Query<SearchQuery>, State(state): State<AppState>) -> impl IntoResponse {
+#[tracing::instrument(skip_all)]
+async fn api_location_search(Query(qs): Query<SearchQuery>, accept: respond::Accept, State(state): State<AppState>) -> Response {
+    let format = match geojson::Format::parse(qs.format.as_deref()) {
+        Ok(f) => f,
+        Err(e) => return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(e)),
+    };
     let q = qs.q.trim();
     if q.is_empty() {
-        return (StatusCode::BAD_REQUEST, Json(ErrorResp{ error: "缺少 q".into()})).into_response();
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new("缺少 q"));
     }
 
-    // 高德 3s，失败返回空
-    if let Some(key) = &state.amap_key {
-        let url = format!(
-            "https://restapi.amap.com/v3/place/text?key={}&keywords={}&offset=5&page=1&extensions=base",
-            key,
-            urlencoding::encode(q)
-        );
-        if let Ok(rsp) = tokio::time::timeout(Duration::from_secs(3), CLIENT.get(url).send()).await {
-            if let Ok(resp) = rsp { if let Ok(v) = resp.json::<serde_json::Value>().await {
-                if v.get("pois").and_then(|v| v.as_array()).map(|a| !a.is_empty()).unwrap_or(false) {
-                    let results: Vec<serde_json::Value> = v["pois"].as_array().unwrap_or(&vec![]).iter().take(5).filter_map(|poi| {
-                        let name = poi.get("name")?.as_str()?.to_string();
-                        let addr = poi.get("address").and_then(|x| x.as_str()).unwrap_or("").to_string();
-                        let loc = poi.get("location")?.as_str()?; // "lng,lat"
-                        let mut it = loc.split(',');
-                        let lng = it.next()?.parse::<f64>().ok()?;
-                        let lat = it.next()?.parse::<f64>().ok()?;
-                        Some(serde_json::json!({"lat":lat,"lng":lng,"name":name,"address":addr}))
-                    }).collect();
-                    return (StatusCode::OK, Json(serde_json::json!({"results": results}))).into_response();
-                }
-            }}
-        }
+    // 高德优先，无结果时回退和风城市查询；均失败返回空列表
+    let results = location::search(&state, q).await;
+    if format == geojson::Format::GeoJson {
+        return respond::geojson(StatusCode::OK, &geojson::places(&results));
     }
-
-    // 仅使用高德；失败则返回空列表
-    (StatusCode::OK, Json(serde_json::json!({"results": []}))).into_response()
+    respond::negotiated(&accept, StatusCode::OK, &serde_json::json!({"results": results}))
 }
 
-async fn api_location_ip(State(_state): State<AppState>, headers: axum::http::HeaderMap) -> impl IntoResponse {
-    // 尽力从常见代理头中取真实 IP（支持 IPv4/IPv6，去端口/方括号）
-    let raw = headers
-        .get("cf-connecting-ip").and_then(|v| v.to_str().ok())
-        .or_else(|| headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()).and_then(|s| s.split(',').next()))
-        .or_else(|| headers.get("x-real-ip").and_then(|v| v.to_str().ok()))
-        .unwrap_or("")
-        .trim()
-        .to_string();
-
-    fn clean_ip(s: &str) -> Option<String> {
-        let s = s.trim();
-        if s.is_empty() { return None; }
-        // [IPv6]:port 形式
-        if let Some(end) = (s.starts_with('[')).then(|| s.find(']')).flatten() {
-            return Some(s[1..end].to_string());
-        }
-        // IPv4:port 形式
-        if s.contains('.') && s.contains(':') {
-            if let Some(idx) = s.rfind(':') { return Some(s[..idx].to_string()); }
-        }
-        Some(s.to_string())
-    }
-
-    let ip = clean_ip(&raw).unwrap_or_default();
-
-    // 简化：无法定位时返回北京默认坐标
-    let fallback = serde_json::json!({"lat": 39.9042, "lng": 116.4074, "address": "北京市"});
-
-    if ip.is_empty() {
-        return (StatusCode::OK, Json(fallback)).into_response();
+#[tracing::instrument(skip_all)]
+async fn api_location_ip(
+    State(state): State<AppState>,
+    accept: respond::Accept,
+    headers: axum::http::HeaderMap,
+    connect_info: Option<axum::extract::ConnectInfo<SocketAddr>>,
+) -> impl IntoResponse {
+    // 代理头优先，直连时用对端地址
+    let ip = client_ip::from_request(&headers, connect_info.map(|c| c.0));
+
+    // 无法定位时返回默认城市（租户可配置，默认北京）
+    let city = tenant::default_city();
+    let fallback = serde_json::json!({"lat": city.lat, "lng": city.lng, "address": city.name});
+
+    let Some(ip) = ip else {
+        return respond::negotiated(&accept, StatusCode::OK, &fallback);
+    };
+    // 局域网/本机访问：美团无法定位，不发请求，明确告知前端
+    if client_ip::is_local(ip) {
+        let mut local = fallback;
+        local["local_network"] = serde_json::json!(true);
+        return respond::negotiated(&accept, StatusCode::OK, &local);
     }
 
-    // 使用美团官方 IP 定位
-    let url = format!(
-        "https://apimobile.meituan.com/locate/v2/ip/loc?rgeo=true&ip={}",
-        urlencoding::encode(&ip)
-    );
-    let req = CLIENT
-        .get(url)
-        .header("User-Agent", "Mozilla/5.0 (compatible; caiyun-rust/0.1)")
-        .header("Accept", "application/json")
-        .header("Referer", "https://i.meituan.com/");
-    if let Ok(Ok(resp)) = tokio::time::timeout(Duration::from_secs(3), req.send()).await {
-        if let Ok(v) = resp.json::<serde_json::Value>().await {
-            let data = v.get("data").cloned().unwrap_or(serde_json::Value::Null);
-            let lat = data.get("lat").and_then(|x| x.as_f64());
-            let lng = data.get("lng").and_then(|x| x.as_f64());
-            let rgeo = data.get("rgeo").cloned().unwrap_or(serde_json::Value::Null);
-            let address = rgeo.get("district").and_then(|x| x.as_str())
-                .or_else(|| rgeo.get("city").and_then(|x| x.as_str()))
-                .or_else(|| rgeo.get("province").and_then(|x| x.as_str()))
-                .unwrap_or("北京市");
-            if let (Some(lat), Some(lng)) = (lat, lng) {
-                return (StatusCode::OK, Json(serde_json::json!({"lat": lat, "lng": lng, "address": address}))).into_response();
-            }
-        }
+    // 使用美团官方 IP 定位（按 IP 缓存，失败也短暂缓存）
+    if let Some(located) = location::locate_ip(&state, ip).await {
+        return respond::negotiated(&accept, StatusCode::OK, &located);
     }
 
     // 失败返回默认坐标
-    (StatusCode::OK, Json(fallback)).into_response()
+    respond::negotiated(&accept, StatusCode::OK, &fallback)
 }
@@ -0,0 +1,69 @@
+// 气象派生量：露点与体感温度（上游缺少 apparent_temperature 时使用）、紫外线指数分级
+// 温度 °C、相对湿度 0-100、风速 km/h
+
+// Magnus 公式，适用于 -45~60°C
+pub fn dew_point(temperature: f64, humidity: f64) -> f64 {
+    const A: f64 = 17.62;
+    const B: f64 = 243.12;
+    let rh = humidity.clamp(1.0, 100.0) / 100.0;
+    let gamma = rh.ln() + A * temperature / (B + temperature);
+    B * gamma / (A - gamma)
+}
+
+// 风寒指数（加拿大环境部 / NWS），仅在 ≤10°C 且风速 >4.8 km/h 时有意义
+fn wind_chill(temperature: f64, wind_speed: f64) -> f64 {
+    let v = wind_speed.powf(0.16);
+    13.12 + 0.6215 * temperature - 11.37 * v + 0.3965 * temperature * v
+}
+
+// 热指数（NWS Rothfusz 回归），公式使用华氏度
+fn heat_index(temperature: f64, humidity: f64) -> f64 {
+    let t = temperature * 9.0 / 5.0 + 32.0;
+    let rh = humidity;
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    let hi = if simple < 80.0 {
+        simple
+    } else {
+        -42.379 + 2.04901523 * t + 10.14333127 * rh
+            - 0.22475541 * t * rh
+            - 0.00683783 * t * t
+            - 0.05481717 * rh * rh
+            + 0.00122874 * t * t * rh
+            + 0.00085282 * t * rh * rh
+            - 0.00000199 * t * t * rh * rh
+    };
+    (hi - 32.0) * 5.0 / 9.0
+}
+
+// 低温有风用风寒，高温用热指数，其余取气温本身
+pub fn apparent_temperature(temperature: f64, humidity: f64, wind_speed: f64) -> f64 {
+    if temperature <= 10.0 && wind_speed > 4.8 {
+        wind_chill(temperature, wind_speed)
+    } else if temperature >= 27.0 {
+        heat_index(temperature, humidity).max(temperature)
+    } else {
+        temperature
+    }
+}
+
+// 彩云实况紫外线为 1-5 级（1 最弱 0-2、2 弱 3-4、3 中等 5-6、4 强 7-9、5 很强 10+，见 provider::ultraviolet），
+// 换算为 0-11 的紫外线指数（11 表示 11 及以上）：有短波辐射时按约 100 W/m² 对应 1 的粗略估算并限制在该级范围内，否则取该级中间值
+const UV_BANDS: [(f64, f64); 6] = [(0.0, 0.0), (0.0, 2.0), (3.0, 4.0), (5.0, 6.0), (7.0, 9.0), (10.0, 11.0)];
+
+pub fn uv_index(level: Option<f64>, dswrf: Option<f64>) -> Option<i64> {
+    let level = level?.round().clamp(0.0, 5.0) as usize;
+    let (lo, hi) = UV_BANDS[level];
+    let index = dswrf.map_or((lo + hi) / 2.0, |w| (w / 100.0).clamp(lo, hi));
+    Some(index.floor() as i64)
+}
+
+// WHO 分级
+pub fn uv_category(index: i64) -> &'static str {
+    match index {
+        ..=2 => "低",
+        3..=5 => "中等",
+        6..=7 => "高",
+        8..=10 => "很高",
+        _ => "极高",
+    }
+}
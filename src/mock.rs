@@ -0,0 +1,152 @@
+// 模拟数据：开启 MOCK_MODE 后天气接口不请求彩云，改为读取 fixtures/ 下与彩云响应同结构的 JSON
+// 请求可带 `?mock=<名称>` 选择 fixture（如 rain、storm_alerts），所有调用 weather::fetch 的接口均生效
+// fixture 中的时间会平移到当前整点/当天，便于前端按正常时间展示
+// - MOCK_MODE：1/true 开启；开启后即使配置了 CAIYUN_API_TOKEN 也返回模拟数据
+// - MOCK_FIXTURE：默认 fixture，默认 clear_day
+// - MOCK_FIXTURES_DIR：fixture 目录，默认 fixtures
+
+use std::path::PathBuf;
+
+use axum::{extract::Request, middleware::Next, response::Response};
+use chrono::{DateTime, Days, FixedOffset, Local, NaiveDate, NaiveTime, TimeDelta, Timelike};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+use crate::config::{env_flag, env_nonempty};
+
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M%:z";
+
+static ENABLED: Lazy<bool> = Lazy::new(|| env_flag("MOCK_MODE"));
+
+tokio::task_local! {
+    static SELECTED: String;
+}
+
+pub fn enabled() -> bool {
+    *ENABLED
+}
+
+fn fixtures_dir() -> PathBuf {
+    env_nonempty("MOCK_FIXTURES_DIR").unwrap_or_else(|| "fixtures".into()).into()
+}
+
+fn default_fixture() -> String {
+    env_nonempty("MOCK_FIXTURE").unwrap_or_else(|| "clear_day".into())
+}
+
+// 可用的 fixture 名称（按字母序）
+pub fn names() -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(fixtures_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok()?.file_name().to_str()?.strip_suffix(".json").map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+// 记录请求选择的 fixture；仅在模拟模式下生效
+pub async fn middleware(req: Request, next: Next) -> Response {
+    if !enabled() {
+        return next.run(req).await;
+    }
+    let selected = req.uri().query().and_then(|q| {
+        q.split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| *k == "mock")
+            .map(|(_, v)| v.to_string())
+    });
+    match selected {
+        Some(name) => SELECTED.scope(name, next.run(req)).await,
+        None => next.run(req).await,
+    }
+}
+
+// 读取当前请求选择的 fixture（未选择时为 MOCK_FIXTURE）
+pub fn load() -> Result<Value, String> {
+    let name = SELECTED.try_with(|s| s.clone()).unwrap_or_else(|_| default_fixture());
+    load_named(&name)
+}
+
+pub fn load_named(name: &str) -> Result<Value, String> {
+    // 名称只允许字母、数字与下划线，避免路径穿越
+    if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+        return Err(format!("无效的 fixture 名称: {}", name));
+    }
+    let path = fixtures_dir().join(format!("{}.json", name));
+    let bytes = std::fs::read(&path)
+        .map_err(|_| format!("fixture 不存在: {}（可选：{}）", name, names().join("、")))?;
+    let mut raw: Value =
+        serde_json::from_slice(&bytes).map_err(|e| format!("无法解析 {}: {}", path.display(), e))?;
+    rebase(&mut raw);
+    Ok(raw)
+}
+
+// 以首个逐小时时间为基准，把逐小时时间平移到当前整点、逐日日期平移到 fixture 时区的今天
+fn rebase(raw: &mut Value) {
+    let first_hour = raw
+        .pointer("/result/hourly/temperature/0/datetime")
+        .and_then(|v| v.as_str())
+        .and_then(|s| DateTime::parse_from_str(s, TIME_FORMAT).ok());
+    let first_day = raw
+        .pointer("/result/daily/temperature/0/date")
+        .and_then(|v| v.as_str())
+        .and_then(|s| DateTime::parse_from_str(s, TIME_FORMAT).ok());
+    let now = Local::now();
+    let hour = now.with_minute(0).and_then(|t| t.with_second(0)).and_then(|t| t.with_nanosecond(0)).unwrap_or(now);
+    let hours = first_hour.map_or(TimeDelta::zero(), |t| hour.fixed_offset() - t);
+    // “今天”取 fixture 所在时区的当前日期，与天气整形一致
+    let days = first_day.map_or(0, |d| (now.with_timezone(d.offset()).date_naive() - d.date_naive()).num_days());
+
+    if let Some(t) = raw.get_mut("server_time") {
+        *t = now.timestamp().into();
+    }
+    shift(raw, hours, days);
+}
+
+fn shift_date(date: NaiveDate, days: i64) -> NaiveDate {
+    let shifted = if days >= 0 {
+        date.checked_add_days(Days::new(days as u64))
+    } else {
+        date.checked_sub_days(Days::new(days.unsigned_abs()))
+    };
+    shifted.unwrap_or(date)
+}
+
+fn shift(v: &mut Value, hours: TimeDelta, days: i64) {
+    match v {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match (key.as_str(), value.as_str()) {
+                    ("datetime", Some(s)) => {
+                        if let Ok(t) = DateTime::<FixedOffset>::parse_from_str(s, TIME_FORMAT) {
+                            *value = (t + hours).format(TIME_FORMAT).to_string().into();
+                        }
+                    }
+                    ("date", Some(s)) => {
+                        if let Ok(t) = DateTime::<FixedOffset>::parse_from_str(s, TIME_FORMAT) {
+                            let date = shift_date(t.date_naive(), days);
+                            *value = format!("{}T00:00{}", date, t.format("%:z")).into();
+                        }
+                    }
+                    // 日出日落（astro 中的 HH:MM）随逐小时时间一起平移，保持 fixture 中 skycon 的昼夜关系
+                    ("time", Some(s)) => {
+                        if let Ok(t) = NaiveTime::parse_from_str(s, "%H:%M") {
+                            *value = (t + hours).format("%H:%M").to_string().into();
+                        }
+                    }
+                    ("pubtimestamp", _) => {
+                        if let Some(ts) = value.as_f64() {
+                            *value = (ts + hours.num_seconds() as f64).into();
+                        }
+                    }
+                    _ => shift(value, hours, days),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| shift(item, hours, days)),
+        _ => {}
+    }
+}
@@ -0,0 +1,129 @@
+// 短临降水文字描述：由逐分钟降水强度（result.minutely.precipitation_2h，mm/h）生成
+// 如“15 分钟后开始下小雨，约持续 40 分钟”，替代上游 forecast_keypoint，模拟数据与备用数据源同样适用
+// - NOWCAST_LANG：描述语言，zh（默认）或 en
+
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+use crate::config::env_nonempty;
+
+// 超过该强度视为有雨，与 minutely.will_rain 一致
+const RAIN_THRESHOLD: f64 = 0.03;
+// 短暂间歇不视为雨停
+const MIN_DRY_MINUTES: usize = 10;
+
+struct Templates {
+    // 强度等级：小雨、中雨、大雨、暴雨
+    levels: [&'static str; 4],
+    // {window} 为预报时长
+    dry: &'static str,
+    // {level} 强度，{start} 开始分钟，{duration} 持续分钟，{end} 结束分钟
+    raining_stops: &'static str,
+    raining_continues: &'static str,
+    starts_stops: &'static str,
+    starts_continues: &'static str,
+    window_2h: &'static str,
+    window_1h: &'static str,
+}
+
+const ZH: Templates = Templates {
+    levels: ["小雨", "中雨", "大雨", "暴雨"],
+    dry: "未来{window}不会下雨",
+    raining_stops: "正在下{level}，约 {end} 分钟后停止",
+    raining_continues: "正在下{level}，{window}内不会停",
+    starts_stops: "{start} 分钟后开始下{level}，约持续 {duration} 分钟",
+    starts_continues: "{start} 分钟后开始下{level}，{window}内不会停",
+    window_2h: "两小时",
+    window_1h: "一小时",
+};
+
+const EN: Templates = Templates {
+    levels: ["light rain", "moderate rain", "heavy rain", "torrential rain"],
+    dry: "No rain expected in the next {window}",
+    raining_stops: "{level} now, stopping in about {end} min",
+    raining_continues: "{level} now, continuing for the next {window}",
+    starts_stops: "{level} starting in {start} min, lasting about {duration} min",
+    starts_continues: "{level} starting in {start} min, continuing for the next {window}",
+    window_2h: "two hours",
+    window_1h: "hour",
+};
+
+static TEMPLATES: Lazy<&'static Templates> = Lazy::new(|| match env_nonempty("NOWCAST_LANG").as_deref() {
+    Some("en") => &EN,
+    _ => &ZH,
+});
+
+// 彩云雷达降水强度分级（mm/h）
+fn level(intensity: f64) -> usize {
+    match intensity {
+        v if v < 0.9 => 0,
+        v if v < 2.87 => 1,
+        v if v < 12.8 => 2,
+        _ => 3,
+    }
+}
+
+// 从 start 起首次连续 MIN_DRY_MINUTES 分钟无雨的位置；到末尾仍未停则为 None
+fn rain_end(series: &[f64], start: usize) -> Option<usize> {
+    let mut dry_from = None;
+    for (i, v) in series.iter().enumerate().skip(start) {
+        if *v > RAIN_THRESHOLD {
+            dry_from = None;
+        } else {
+            let from = *dry_from.get_or_insert(i);
+            if i + 1 - from >= MIN_DRY_MINUTES {
+                return Some(from);
+            }
+        }
+    }
+    // 末尾不足 MIN_DRY_MINUTES 的无雨也算停
+    dry_from
+}
+
+fn fill(template: &str, t: &Templates, window: &str, level: usize, start: usize, end: usize) -> String {
+    let text = template
+        .replace("{window}", window)
+        .replace("{level}", t.levels[level])
+        .replace("{start}", &start.to_string())
+        .replace("{end}", &end.to_string())
+        .replace("{duration}", &(end - start).to_string());
+    // 英文模板以强度开头时首字母大写
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_lowercase() => c.to_ascii_uppercase().to_string() + chars.as_str(),
+        _ => text,
+    }
+}
+
+// 逐分钟降水强度 → 描述；序列为空时返回 None
+pub fn summarize(series: &[f64]) -> Option<String> {
+    if series.is_empty() {
+        return None;
+    }
+    let t = *TEMPLATES;
+    let window = if series.len() > 60 { t.window_2h } else { t.window_1h };
+    let Some(start) = series.iter().position(|v| *v > RAIN_THRESHOLD) else {
+        return Some(fill(t.dry, t, window, 0, 0, 0));
+    };
+    let end = rain_end(series, start);
+    let span = &series[start..end.unwrap_or(series.len())];
+    let level = level(span.iter().copied().fold(0.0, f64::max));
+    let template = match (start, end) {
+        (0, Some(_)) => t.raining_stops,
+        (0, None) => t.raining_continues,
+        (_, Some(_)) => t.starts_stops,
+        (_, None) => t.starts_continues,
+    };
+    Some(fill(template, t, window, level, start, end.unwrap_or(series.len())))
+}
+
+// 从彩云结构的原始 JSON 生成描述
+pub fn keypoint(raw: &Value) -> Option<String> {
+    let series: Vec<f64> = raw
+        .pointer("/result/minutely/precipitation_2h")?
+        .as_array()?
+        .iter()
+        .map(|v| v.as_f64().unwrap_or(0.0))
+        .collect();
+    summarize(&series)
+}
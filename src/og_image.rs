@@ -0,0 +1,214 @@
+// 社交分享卡片：/api/v1/og-image?lng&lat（未指定时为默认城市）生成 1200×630 的天气卡片（SVG；启用 `og-png` feature 后支持 PNG）
+// 同时为首页注入 og:image / og:title 等 meta，分享链接时显示天气预览
+
+use std::{collections::HashMap, time::Duration};
+
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::{base_path, cache::TtlCache, icons, location, tenant, weather, AppState};
+
+pub const WIDTH: u32 = 1200;
+pub const HEIGHT: u32 = 630;
+
+// 键：坐标（保留 2 位小数）+ 格式
+static CARDS: Lazy<TtlCache<String, (&'static str, Bytes)>> =
+    Lazy::new(|| TtlCache::new(Duration::from_secs(600), 256));
+
+#[derive(Deserialize)]
+pub struct OgQuery {
+    lng: Option<f64>,
+    lat: Option<f64>,
+    format: Option<String>,
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn render_svg(city: &str, data: &weather::WeatherData) -> String {
+    let cur = &data.current;
+    let desc = cur.weather_info.get("desc").and_then(|v| v.as_str()).unwrap_or("");
+    let icon = icons::plain(cur.skycon.as_str().unwrap_or(""));
+    let today = data.daily.get(0);
+    let range = match (
+        today.and_then(|d| d.get("min_temp")).and_then(|v| v.as_i64()),
+        today.and_then(|d| d.get("max_temp")).and_then(|v| v.as_i64()),
+    ) {
+        (Some(min), Some(max)) => format!("{}° / {}°", min, max),
+        _ => String::new(),
+    };
+    let date = chrono::Local::now().format("%m月%d日").to_string();
+    let city: String = city.chars().take(18).collect();
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">
+<defs><linearGradient id="bg" x1="0" y1="0" x2="1" y2="1"><stop offset="0" stop-color="#4FC3F7"/><stop offset="1" stop-color="#1976D2"/></linearGradient></defs>
+<rect width="{w}" height="{h}" fill="url(#bg)"/>
+<g font-family="PingFang SC, Noto Sans CJK SC, Microsoft YaHei, sans-serif" fill="#FFFFFF">
+<text x="80" y="140" font-size="56" font-weight="600">{city}</text>
+<text x="80" y="200" font-size="32" fill="#E3F2FD">{date}</text>
+<text x="80" y="410" font-size="180" font-weight="600">{temp}°</text>
+<text x="80" y="490" font-size="44">{desc}  {range}</text>
+<text x="80" y="580" font-size="28" fill="#E3F2FD">彩云天气 · 湿度 {humidity}% · 风速 {wind} km/h</text>
+</g>
+<text x="1120" y="420" font-size="220" text-anchor="end">{icon}</text>
+</svg>"##,
+        w = WIDTH,
+        h = HEIGHT,
+        city = xml_escape(&city),
+        date = date,
+        temp = cur.temperature,
+        desc = xml_escape(desc),
+        range = range,
+        humidity = cur.humidity,
+        wind = cur.wind_speed,
+        icon = icon,
+    )
+}
+
+#[cfg(feature = "og-png")]
+fn rasterize(svg: &str) -> anyhow::Result<Vec<u8>> {
+    use resvg::{tiny_skia, usvg};
+
+    // 系统字体只加载一次；容器中需安装中文字体（如 fonts-noto-cjk）才能正确显示中文
+    static FONTS: Lazy<std::sync::Arc<usvg::fontdb::Database>> = Lazy::new(|| {
+        let mut db = usvg::fontdb::Database::new();
+        db.load_system_fonts();
+        std::sync::Arc::new(db)
+    });
+    let opt = usvg::Options {
+        fontdb: FONTS.clone(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &opt)?;
+    let mut pixmap = tiny_skia::Pixmap::new(WIDTH, HEIGHT).ok_or_else(|| anyhow::anyhow!("pixmap"))?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    Ok(pixmap.encode_png()?)
+}
+
+fn default_format() -> &'static str {
+    if cfg!(feature = "og-png") { "png" } else { "svg" }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn og_image(State(state): State<AppState>, Query(q): Query<OgQuery>) -> Response {
+    let city = tenant::default_city();
+    let lng = q.lng.unwrap_or(city.lng);
+    let lat = q.lat.unwrap_or(city.lat);
+    let format = match q.format.as_deref().unwrap_or(default_format()) {
+        "svg" => "svg",
+        #[cfg(feature = "og-png")]
+        "png" => "png",
+        other => {
+            return (StatusCode::BAD_REQUEST, format!("不支持的格式: {}", other)).into_response();
+        }
+    };
+
+    let key = format!("{:.2},{:.2},{}", lng, lat, format);
+    let (content_type, body) = match CARDS.get(&key) {
+        Some(hit) => hit,
+        None => {
+            let (data, city) = tokio::join!(
+                weather::fetch(&state, lng, lat),
+                location::reverse_geocode(&state, lat, lng)
+            );
+            let data = match data {
+                Ok(d) => d,
+                Err(e) => return (e.status(), e.to_string()).into_response(),
+            };
+            let svg = render_svg(&city, &data);
+            let card = match format {
+                #[cfg(feature = "og-png")]
+                "png" => match tokio::task::spawn_blocking(move || rasterize(&svg)).await {
+                    Ok(Ok(png)) => ("image/png", Bytes::from(png)),
+                    Ok(Err(e)) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("渲染失败: {}", e)).into_response(),
+                    Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("渲染失败: {}", e)).into_response(),
+                },
+                _ => ("image/svg+xml", Bytes::from(svg)),
+            };
+            CARDS.insert(key, card.clone());
+            card
+        }
+    };
+
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(content_type)),
+            (header::CACHE_CONTROL, HeaderValue::from_static("public, max-age=600")),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+// 由 Host 与 X-Forwarded-Proto 推断的地址（含 BASE_PATH）；两者均由客户端提供
+fn request_base(headers: &HeaderMap) -> String {
+    let host = headers
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("localhost");
+    let proto = headers
+        .get("x-forwarded-proto")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("http");
+    format!("{}://{}{}", proto, host, base_path::get())
+}
+
+// 站点对外地址（含 BASE_PATH）：优先 PUBLIC_URL；未设置时仅在 Host 属于按主机名识别的租户时按请求推断，否则为 None
+// 分享短链接与二维码会被转发和缓存，不能写入任意 Host
+pub fn public_base(headers: &HeaderMap) -> Option<String> {
+    if let Some(url) = base_path::public_url() {
+        return Some(url);
+    }
+    let host = headers.get(header::HOST).and_then(|v| v.to_str().ok())?;
+    tenant::current().filter(|t| t.serves_host(host)).map(|_| request_base(headers))
+}
+
+// 在 </head> 前注入 Open Graph / Twitter Card meta；?lng&lat 指定卡片位置，?name 用于标题
+// 首页不缓存（no-cache），未配置 PUBLIC_URL 时仍按请求推断图片地址
+pub fn inject_meta(html: &str, headers: &HeaderMap, query: &HashMap<String, String>) -> String {
+    let base = public_base(headers).unwrap_or_else(|| request_base(headers));
+    let coords = match (
+        query.get("lng").and_then(|v| v.parse::<f64>().ok()),
+        query.get("lat").and_then(|v| v.parse::<f64>().ok()),
+    ) {
+        (Some(lng), Some(lat)) => format!("?lng={}&lat={}", lng, lat),
+        _ => String::new(),
+    };
+    let image = format!("{}/api/v1/og-image{}", base, coords);
+    let site = tenant::current().and_then(|t| t.site_name.as_deref()).unwrap_or("彩云天气");
+    let title = match query.get("name").map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        Some(name) => format!("{}天气 - {}", name, site),
+        None => format!("{} - 实时天气与预报", site),
+    };
+    let meta = format!(
+        r#"    <meta property="og:type" content="website">
+    <meta property="og:title" content="{title}">
+    <meta property="og:description" content="实时天气、逐小时与未来几天预报">
+    <meta property="og:image" content="{image}">
+    <meta property="og:image:width" content="{w}">
+    <meta property="og:image:height" content="{h}">
+    <meta name="twitter:card" content="summary_large_image">
+"#,
+        title = xml_escape(&title),
+        image = xml_escape(&image),
+        w = WIDTH,
+        h = HEIGHT,
+    );
+    match html.find("</head>") {
+        Some(idx) => format!("{}{}{}", &html[..idx], meta, &html[idx..]),
+        None => html.to_string(),
+    }
+}
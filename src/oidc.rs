@@ -0,0 +1,248 @@
+// OIDC 登录：通过外部身份提供方（Authelia、Keycloak 等）登录，作为本地账户密码/邮件链接之外的方式
+// 授权码流程 + PKCE；state 同时写入 HttpOnly Cookie，回调时须与之一致（防止登录 CSRF），同一客户端 IP 发起登录的次数有上限。
+// 不解析未校验签名的 ID Token：用户身份以 access token 经后端请求 userinfo 端点取得
+// 身份按 (issuer, sub) 关联到用户：首次登录时若已有同邮箱用户且提供方声明邮箱已验证则关联，否则新建用户（不受 ACCOUNTS_ALLOW_REGISTER 限制）；
+// 已设置密码的用户注册时未验证邮箱，须先用密码登录，再在已登录状态下经提供方登录才会关联
+// - OIDC_ISSUER：身份提供方 issuer，设置后开启（需 ACCOUNTS=1）；端点由 {issuer}/.well-known/openid-configuration 发现
+// - OIDC_CLIENT_ID / OIDC_CLIENT_SECRET：客户端凭据，必填
+// - OIDC_REDIRECT_URL：回调地址，默认 PUBLIC_URL/api/v1/auth/oidc/callback，需在提供方处登记
+// - OIDC_SCOPES：默认 openid email profile
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+use axum::{
+    extract::{ConnectInfo, Query},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::{
+    accounts, base_path, cache::TtlCache, client_ip, config::env_nonempty, respond, upstream, upstream_url::UpstreamUrl, ErrorResp,
+};
+
+struct Config {
+    issuer: String,
+    client_id: String,
+    client_secret: String,
+    redirect_url: String,
+    scopes: String,
+    // 回调地址为 https 时 state Cookie 附加 Secure
+    secure_cookie: bool,
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: String,
+}
+
+const STATE_COOKIE: &str = "oidc_state";
+const PENDING_SECS: u64 = 600;
+// 每个客户端 IP 在 PENDING_SECS 内最多发起的登录次数
+const LOGINS_PER_CLIENT: u32 = 20;
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+static DISCOVERY: tokio::sync::OnceCell<Discovery> = tokio::sync::OnceCell::const_new();
+// state -> PKCE code_verifier，登录需在 10 分钟内完成
+static PENDING: Lazy<TtlCache<String, String>> =
+    Lazy::new(|| TtlCache::new(Duration::from_secs(PENDING_SECS), 1024));
+static LOGINS: Lazy<TtlCache<IpAddr, u32>> = Lazy::new(|| TtlCache::new(Duration::from_secs(PENDING_SECS), 10_000));
+
+// 需在 accounts::init_from_env 之后调用
+pub fn init_from_env() -> anyhow::Result<()> {
+    let Some(issuer) = env_nonempty("OIDC_ISSUER") else {
+        return Ok(());
+    };
+    if !accounts::enabled() {
+        anyhow::bail!("OIDC_ISSUER 需同时开启 ACCOUNTS=1");
+    }
+    let client_id = env_nonempty("OIDC_CLIENT_ID").ok_or_else(|| anyhow::anyhow!("设置 OIDC_ISSUER 时需配置 OIDC_CLIENT_ID"))?;
+    let client_secret =
+        env_nonempty("OIDC_CLIENT_SECRET").ok_or_else(|| anyhow::anyhow!("设置 OIDC_ISSUER 时需配置 OIDC_CLIENT_SECRET"))?;
+    let redirect_url = env_nonempty("OIDC_REDIRECT_URL")
+        .or_else(|| {
+            base_path::public_url().map(|u| format!("{}/api/v1/auth/oidc/callback", u))
+        })
+        .ok_or_else(|| anyhow::anyhow!("设置 OIDC_ISSUER 时需配置 OIDC_REDIRECT_URL 或 PUBLIC_URL"))?;
+    info!("OIDC login enabled (issuer {})", issuer);
+    let _ = CONFIG.set(Config {
+        issuer: issuer.trim_end_matches('/').to_string(),
+        client_id,
+        client_secret,
+        secure_cookie: redirect_url.starts_with("https://"),
+        redirect_url,
+        scopes: env_nonempty("OIDC_SCOPES").unwrap_or_else(|| "openid email profile".into()),
+    });
+    Ok(())
+}
+
+pub fn enabled() -> bool {
+    CONFIG.get().is_some()
+}
+
+// 首次登录时获取提供方元数据，失败时下次再试
+async fn discovery(cfg: &Config) -> anyhow::Result<&'static Discovery> {
+    DISCOVERY
+        .get_or_try_init(|| async {
+            let url = UpstreamUrl::new(&cfg.issuer).path("/.well-known/openid-configuration");
+            let req = upstream::client("oidc").get(url.as_str());
+            let resp = upstream::send("oidc", "/.well-known/openid-configuration", req).await?.error_for_status()?;
+            let discovery: Discovery = resp.json().await?;
+            if discovery.issuer.trim_end_matches('/') != cfg.issuer {
+                anyhow::bail!("提供方返回的 issuer 不一致: {}", discovery.issuer);
+            }
+            Ok(discovery)
+        })
+        .await
+}
+
+fn disabled_response() -> Response {
+    respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("未开启 OIDC 登录（OIDC_ISSUER）"))
+}
+
+fn provider_error(e: impl std::fmt::Display) -> Response {
+    warn!("OIDC login failed: {}", e);
+    respond::json(StatusCode::BAD_GATEWAY, &ErrorResp::new("身份提供方请求失败"))
+}
+
+fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+// 只在本次登录期间有效；SameSite=Lax 的 Cookie 会随提供方跳转回来的 GET 请求发送
+fn state_cookie(cfg: &Config, state: &str, max_age: u64) -> HeaderValue {
+    let secure = if cfg.secure_cookie { "; Secure" } else { "" };
+    let value = format!(
+        "{}={}; Path={}; HttpOnly; SameSite=Lax; Max-Age={}{}",
+        STATE_COOKIE,
+        state,
+        base_path::join("/"),
+        max_age,
+        secure
+    );
+    HeaderValue::from_str(&value).expect("cookie is ascii")
+}
+
+// 跳转到提供方的授权页
+pub async fn login(headers: HeaderMap, connect_info: Option<ConnectInfo<SocketAddr>>) -> Response {
+    let Some(cfg) = CONFIG.get() else {
+        return disabled_response();
+    };
+    if let Some(ip) = client_ip::from_request(&headers, connect_info.map(|c| c.0)) {
+        let (count, reset) = LOGINS.bump(ip);
+        if count > LOGINS_PER_CLIENT {
+            let mut resp = respond::json(
+                StatusCode::TOO_MANY_REQUESTS,
+                &ErrorResp::with_code("RATE_LIMITED", "登录请求过于频繁，请稍后重试"),
+            );
+            resp.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(reset.as_secs().max(1)));
+            return resp;
+        }
+    }
+    let discovery = match discovery(cfg).await {
+        Ok(d) => d,
+        Err(e) => return provider_error(e),
+    };
+    let (state, verifier) = (accounts::random_token(), accounts::random_token());
+    let mut url = match reqwest::Url::parse(&discovery.authorization_endpoint) {
+        Ok(url) => url,
+        Err(e) => return provider_error(e),
+    };
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &cfg.client_id)
+        .append_pair("redirect_uri", &cfg.redirect_url)
+        .append_pair("scope", &cfg.scopes)
+        .append_pair("state", &state)
+        .append_pair("code_challenge", &pkce_challenge(&verifier))
+        .append_pair("code_challenge_method", "S256");
+    let cookie = state_cookie(cfg, &state, PENDING_SECS);
+    PENDING.insert(state, verifier);
+    let mut resp = Redirect::to(url.as_str()).into_response();
+    resp.headers_mut().insert(header::SET_COOKIE, cookie);
+    resp
+}
+
+#[derive(Deserialize)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct UserInfo {
+    sub: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+}
+
+// 提供方回调：用授权码换取 access token，经 userinfo 取得身份后按 (issuer, sub) 登录或建号；无论结果如何都清除 state Cookie
+pub async fn callback(headers: HeaderMap, Query(q): Query<CallbackQuery>) -> Response {
+    let Some(cfg) = CONFIG.get() else {
+        return disabled_response();
+    };
+    let mut resp = complete(cfg, &headers, q).await;
+    resp.headers_mut().append(header::SET_COOKIE, state_cookie(cfg, "", 0));
+    resp
+}
+
+async fn complete(cfg: &Config, headers: &HeaderMap, q: CallbackQuery) -> Response {
+    if let Some(error) = q.error {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(format!("身份提供方拒绝登录: {}", error)));
+    }
+    // state 须与发起登录的浏览器中的 Cookie 一致，否则可能是他人发起的登录
+    let expected = accounts::cookie_value(headers, STATE_COOKIE);
+    let pending = q.state.filter(|s| expected.as_deref() == Some(s.as_str())).and_then(|s| PENDING.take(&s));
+    let (Some(code), Some(verifier)) = (q.code, pending) else {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new("登录请求无效或已过期，请重新登录"));
+    };
+    let discovery = match discovery(cfg).await {
+        Ok(d) => d,
+        Err(e) => return provider_error(e),
+    };
+    let req = upstream::client("oidc")
+        .post(&discovery.token_endpoint)
+        .basic_auth(&cfg.client_id, Some(&cfg.client_secret))
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", cfg.redirect_url.as_str()),
+            ("code_verifier", verifier.as_str()),
+        ]);
+    let token: TokenResponse = match upstream::send("oidc", "token_endpoint", req).await.and_then(|r| r.error_for_status()) {
+        Ok(resp) => match resp.json().await {
+            Ok(t) => t,
+            Err(e) => return provider_error(e),
+        },
+        Err(e) => return provider_error(e),
+    };
+    let req = upstream::client("oidc").get(&discovery.userinfo_endpoint).bearer_auth(&token.access_token);
+    let info: UserInfo = match upstream::send("oidc", "userinfo_endpoint", req).await.and_then(|r| r.error_for_status()) {
+        Ok(resp) => match resp.json().await {
+            Ok(info) => info,
+            Err(e) => return provider_error(e),
+        },
+        Err(e) => return provider_error(e),
+    };
+    if info.sub.is_empty() {
+        return respond::json(StatusCode::UNAUTHORIZED, &ErrorResp::new("身份提供方未返回 sub"));
+    }
+    accounts::login_with_identity(headers, discovery.issuer.clone(), info.sub, info.email, info.email_verified).await
+}
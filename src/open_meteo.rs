@@ -0,0 +1,243 @@
+// Open-Meteo 数据源：免 key 的全球预报，转换为彩云同结构；无预警与空气质量
+// 分钟级降水取 15 分钟粒度的未来 2 小时，按分钟展开
+// WMO 天气代码映射为彩云 skycon；风速 m/s、气压 Pa、湿度 0-1，与彩云一致
+
+use chrono::FixedOffset;
+use futures_util::future::BoxFuture;
+use serde_json::{json, Value};
+
+use crate::{
+    error_report,
+    provider::{self, WeatherProvider},
+    upstream,
+    upstream_url::UpstreamUrl,
+    weather::FetchError,
+    AppState,
+};
+
+const CURRENT: &str = "temperature_2m,relative_humidity_2m,apparent_temperature,is_day,weather_code,cloud_cover,\
+    pressure_msl,wind_speed_10m,wind_direction_10m,visibility,shortwave_radiation";
+const HOURLY: &str = "temperature_2m,relative_humidity_2m,precipitation,precipitation_probability,weather_code,\
+    wind_speed_10m,wind_direction_10m,is_day";
+const DAILY: &str = "weather_code,temperature_2m_max,temperature_2m_min,temperature_2m_mean,precipitation_sum,\
+    precipitation_hours,precipitation_probability_max,wind_speed_10m_max,wind_direction_10m_dominant,uv_index_max,\
+    relative_humidity_2m_mean,relative_humidity_2m_min,relative_humidity_2m_max,\
+    pressure_msl_mean,pressure_msl_min,pressure_msl_max";
+
+pub struct OpenMeteo;
+
+impl WeatherProvider for OpenMeteo {
+    fn name(&self) -> &'static str {
+        "open_meteo"
+    }
+
+    fn fetch<'a>(&'a self, state: &'a AppState, lng: f64, lat: f64) -> BoxFuture<'a, Result<Value, FetchError>> {
+        Box::pin(fetch(state, lng, lat))
+    }
+
+    fn alerts(&self) -> bool {
+        false
+    }
+}
+
+async fn fetch(state: &AppState, lng: f64, lat: f64) -> Result<Value, FetchError> {
+    let url = UpstreamUrl::new(&state.base_urls.open_meteo)
+        .path("/v1/forecast")
+        .query("latitude", lat)
+        .query("longitude", lng)
+        .query("current", CURRENT)
+        .query("hourly", HOURLY)
+        .query("daily", DAILY)
+        .query("minutely_15", "precipitation")
+        .query("forecast_minutely_15", 8)
+        .query("timezone", "auto")
+        .query("timeformat", "unixtime")
+        .query("wind_speed_unit", "ms")
+        .query("forecast_days", 3)
+        .query("forecast_hours", 24);
+    let resp = upstream::send("open_meteo", "/v1/forecast", upstream::client("open_meteo").get(url.as_str()))
+        .await
+        .map_err(FetchError::Request)?
+        .error_for_status()
+        .map_err(FetchError::Status)?;
+    let json = upstream::json::<Value>(resp).await.map_err(|e| {
+        error_report::capture_upstream_error("open_meteo", &e);
+        FetchError::Parse(e)
+    })?;
+    to_caiyun(&json).ok_or_else(|| {
+        error_report::capture_upstream_error("open_meteo", &"unexpected response shape");
+        FetchError::Upstream
+    })
+}
+
+// WMO 天气代码 → 彩云 skycon
+fn skycon(code: i64, is_day: bool) -> &'static str {
+    match code {
+        0 if is_day => "CLEAR_DAY",
+        0 => "CLEAR_NIGHT",
+        1 | 2 if is_day => "PARTLY_CLOUDY_DAY",
+        1 | 2 => "PARTLY_CLOUDY_NIGHT",
+        3 => "CLOUDY",
+        45 | 48 => "FOG",
+        51 | 53 | 55 | 61 | 80 => "LIGHT_RAIN",
+        63 | 81 => "MODERATE_RAIN",
+        65 | 95 => "HEAVY_RAIN",
+        82 => "STORM_RAIN",
+        56 | 57 | 66 | 67 => "SLEET",
+        71 | 77 | 85 => "LIGHT_SNOW",
+        73 => "MODERATE_SNOW",
+        75 | 86 => "HEAVY_SNOW",
+        96 | 99 => "HAIL",
+        _ => "CLOUDY",
+    }
+}
+
+fn to_caiyun(v: &Value) -> Option<Value> {
+    let offset = FixedOffset::east_opt(v.get("utc_offset_seconds")?.as_i64()? as i32)?;
+    let current = v.get("current")?;
+    let cur = |key: &str| current.get(key).and_then(|v| v.as_f64());
+    let is_day = cur("is_day").unwrap_or(1.0) > 0.0;
+
+    let realtime = json!({
+        "temperature": cur("temperature_2m")?,
+        "apparent_temperature": cur("apparent_temperature"),
+        "humidity": cur("relative_humidity_2m").map(|h| h / 100.0),
+        "skycon": skycon(cur("weather_code").unwrap_or(3.0) as i64, is_day),
+        "cloudrate": cur("cloud_cover").map(|c| c / 100.0),
+        "pressure": cur("pressure_msl").map(|p| p * 100.0),
+        "visibility": cur("visibility").map(|m| m / 1000.0),
+        "dswrf": cur("shortwave_radiation"),
+        "wind": { "speed": cur("wind_speed_10m"), "direction": cur("wind_direction_10m") },
+    });
+
+    let hourly = v.get("hourly")?;
+    let series = |block: &Value, key: &str| -> Vec<Option<f64>> {
+        block
+            .get(key)
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().map(|v| v.as_f64()).collect())
+            .unwrap_or_default()
+    };
+    let times: Vec<i64> = hourly.get("time")?.as_array()?.iter().filter_map(|t| t.as_i64()).collect();
+    let (temp, hum, precip, prob, code, speed, dir, day) = (
+        series(hourly, "temperature_2m"),
+        series(hourly, "relative_humidity_2m"),
+        series(hourly, "precipitation"),
+        series(hourly, "precipitation_probability"),
+        series(hourly, "weather_code"),
+        series(hourly, "wind_speed_10m"),
+        series(hourly, "wind_direction_10m"),
+        series(hourly, "is_day"),
+    );
+    let at = |arr: &[Option<f64>], i: usize| arr.get(i).copied().flatten();
+    let hour_items = |f: &dyn Fn(usize, String) -> Value| -> Vec<Value> {
+        times.iter().enumerate().map(|(i, t)| f(i, provider::datetime(*t, offset))).collect()
+    };
+
+    let daily = v.get("daily")?;
+    let days: Vec<i64> = daily.get("time")?.as_array()?.iter().filter_map(|t| t.as_i64()).collect();
+    let d = |key: &str| series(daily, key);
+    let (tmax, tmin, tmean, psum, phours, pprob, wmax, wdir, uvi, dcode) = (
+        d("temperature_2m_max"),
+        d("temperature_2m_min"),
+        d("temperature_2m_mean"),
+        d("precipitation_sum"),
+        d("precipitation_hours"),
+        d("precipitation_probability_max"),
+        d("wind_speed_10m_max"),
+        d("wind_direction_10m_dominant"),
+        d("uv_index_max"),
+        d("weather_code"),
+    );
+    let (hmean, hmin, hmax, pmean, pmin, pmax) = (
+        d("relative_humidity_2m_mean"),
+        d("relative_humidity_2m_min"),
+        d("relative_humidity_2m_max"),
+        d("pressure_msl_mean"),
+        d("pressure_msl_min"),
+        d("pressure_msl_max"),
+    );
+    let day_items = |f: &dyn Fn(usize, String) -> Value| -> Vec<Value> {
+        days.iter().enumerate().map(|(i, t)| f(i, provider::date(*t, offset))).collect()
+    };
+    let scaled = |arr: &[Option<f64>], i: usize, scale: f64| at(arr, i).map(|v| v * scale);
+
+    // 每 15 分钟降水量 mm → 彩云的 mm/h 强度
+    let precipitation_2h: Vec<f64> = v
+        .get("minutely_15")
+        .map(|m| series(m, "precipitation"))
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|p| [p.unwrap_or(0.0) * 4.0; 15])
+        .collect();
+
+    let mut raw = json!({
+        "status": "ok",
+        "tzshift": offset.local_minus_utc(),
+        "server_time": chrono::Utc::now().timestamp(),
+        "result": {
+            "realtime": realtime,
+            "hourly": {
+                "temperature": hour_items(&|i, t| json!({ "datetime": t, "value": at(&temp, i) })),
+                "skycon": hour_items(&|i, t| json!({
+                    "datetime": t,
+                    "value": skycon(at(&code, i).unwrap_or(3.0) as i64, at(&day, i).unwrap_or(1.0) > 0.0),
+                })),
+                "precipitation": hour_items(&|i, t| json!({
+                    "datetime": t,
+                    "value": at(&precip, i).unwrap_or(0.0),
+                    "probability": at(&prob, i).unwrap_or(0.0),
+                })),
+                "humidity": hour_items(&|i, t| json!({ "datetime": t, "value": scaled(&hum, i, 0.01) })),
+                "wind": hour_items(&|i, t| json!({ "datetime": t, "speed": at(&speed, i), "direction": at(&dir, i) })),
+            },
+            "daily": {
+                "temperature": day_items(&|i, date| json!({
+                    "date": date, "max": at(&tmax, i), "min": at(&tmin, i), "avg": at(&tmean, i),
+                })),
+                "skycon": day_items(&|i, date| json!({
+                    "date": date, "value": skycon(at(&dcode, i).unwrap_or(3.0) as i64, true),
+                })),
+                // 彩云逐日降水为小时强度：均值按日总量 / 24，峰值以降水时段内的平均强度近似
+                "precipitation": day_items(&|i, date| {
+                    let sum = at(&psum, i).unwrap_or(0.0);
+                    let hours = at(&phours, i).unwrap_or(0.0);
+                    json!({
+                        "date": date,
+                        "avg": sum / 24.0,
+                        "max": if hours > 0.0 { sum / hours } else { 0.0 },
+                        "probability": at(&pprob, i).unwrap_or(0.0),
+                    })
+                }),
+                "humidity": day_items(&|i, date| json!({
+                    "date": date,
+                    "avg": scaled(&hmean, i, 0.01),
+                    "min": scaled(&hmin, i, 0.01),
+                    "max": scaled(&hmax, i, 0.01),
+                })),
+                "wind": day_items(&|i, date| json!({
+                    "date": date, "max": { "speed": at(&wmax, i), "direction": at(&wdir, i) },
+                })),
+                "pressure": day_items(&|i, date| json!({
+                    "date": date,
+                    "avg": scaled(&pmean, i, 100.0),
+                    "min": scaled(&pmin, i, 100.0),
+                    "max": scaled(&pmax, i, 100.0),
+                })),
+                "life_index": {
+                    "ultraviolet": day_items(&|i, date| {
+                        let mut uv = provider::ultraviolet(at(&uvi, i).unwrap_or(0.0));
+                        uv["date"] = date.into();
+                        uv
+                    }),
+                },
+            },
+            "alert": { "content": [] },
+        },
+    });
+    // 部分地区无 15 分钟数据，此时不输出 minutely，融合模式下由其他数据源补齐
+    if !precipitation_2h.is_empty() {
+        raw["result"]["minutely"] = json!({ "description": "", "precipitation_2h": precipitation_2h });
+    }
+    Some(raw)
+}
@@ -0,0 +1,192 @@
+// OpenWeatherMap 数据源（One Call 3.0，需 OPENWEATHERMAP_API_KEY），转换为彩云同结构
+// 分钟级降水只有 1 小时，无空气质量；预警保留标题、发布方与描述
+
+use chrono::FixedOffset;
+use futures_util::future::BoxFuture;
+use serde_json::{json, Value};
+
+use crate::{
+    error_report,
+    provider::{self, WeatherProvider},
+    upstream,
+    upstream_url::UpstreamUrl,
+    weather::FetchError,
+    AppState,
+};
+
+pub struct OpenWeatherMap {
+    pub key: Option<String>,
+}
+
+impl WeatherProvider for OpenWeatherMap {
+    fn name(&self) -> &'static str {
+        "openweathermap"
+    }
+
+    fn fetch<'a>(&'a self, state: &'a AppState, lng: f64, lat: f64) -> BoxFuture<'a, Result<Value, FetchError>> {
+        Box::pin(async move {
+            let Some(key) = self.key.as_deref() else {
+                return Err(FetchError::NotConfigured("OPENWEATHERMAP_API_KEY"));
+            };
+            fetch(state, key, lng, lat).await
+        })
+    }
+}
+
+async fn fetch(state: &AppState, key: &str, lng: f64, lat: f64) -> Result<Value, FetchError> {
+    let url = UpstreamUrl::new(&state.base_urls.openweathermap)
+        .path("/data/3.0/onecall")
+        .query("lat", lat)
+        .query("lon", lng)
+        .secret_query("appid", key)
+        .query("units", "metric")
+        .query("lang", "zh_cn");
+    let resp = upstream::send("openweathermap", "/data/3.0/onecall", upstream::client("openweathermap").get(url.as_str()))
+        .await
+        .map_err(FetchError::Request)?
+        .error_for_status()
+        .map_err(FetchError::Status)?;
+    let json = upstream::json::<Value>(resp).await.map_err(|e| {
+        error_report::capture_upstream_error("openweathermap", &e);
+        FetchError::Parse(e)
+    })?;
+    to_caiyun(&json).ok_or_else(|| {
+        error_report::capture_upstream_error("openweathermap", &"unexpected response shape");
+        FetchError::Upstream
+    })
+}
+
+// 天气状况代码（https://openweathermap.org/weather-conditions）→ 彩云 skycon；图标以 n 结尾为夜间
+fn skycon(weather: &Value) -> &'static str {
+    let first = weather.get(0);
+    let id = first.and_then(|w| w.get("id")).and_then(|v| v.as_i64()).unwrap_or(803);
+    let night = first
+        .and_then(|w| w.get("icon"))
+        .and_then(|v| v.as_str())
+        .is_some_and(|icon| icon.ends_with('n'));
+    match id {
+        200..=232 | 502 | 522 => "HEAVY_RAIN",
+        300..=321 | 500 | 520 => "LIGHT_RAIN",
+        501 | 521 => "MODERATE_RAIN",
+        503 | 504 | 531 => "STORM_RAIN",
+        511 | 611..=616 => "SLEET",
+        600 | 620 => "LIGHT_SNOW",
+        601 | 621 => "MODERATE_SNOW",
+        602 | 622 => "HEAVY_SNOW",
+        701 | 741 => "FOG",
+        711 => "LIGHT_HAZE",
+        721 => "MODERATE_HAZE",
+        731 | 761 | 762 => "DUST",
+        751 => "SAND",
+        771 | 781 => "WIND",
+        800 if night => "CLEAR_NIGHT",
+        800 => "CLEAR_DAY",
+        801 | 802 if night => "PARTLY_CLOUDY_NIGHT",
+        801 | 802 => "PARTLY_CLOUDY_DAY",
+        _ => "CLOUDY",
+    }
+}
+
+fn num(v: &Value, path: &str) -> Option<f64> {
+    path.split('.').try_fold(v, |cur, key| cur.get(key))?.as_f64()
+}
+
+fn to_caiyun(v: &Value) -> Option<Value> {
+    let offset = FixedOffset::east_opt(v.get("timezone_offset")?.as_i64()? as i32)?;
+    let current = v.get("current")?;
+    let empty = Vec::new();
+    let list = |key: &str| v.get(key).and_then(|v| v.as_array()).unwrap_or(&empty);
+    let hourly: Vec<&Value> = list("hourly").iter().take(24).collect();
+    let daily: Vec<&Value> = list("daily").iter().take(3).collect();
+    let hour_items = |f: &dyn Fn(&Value) -> Value| -> Vec<Value> {
+        hourly
+            .iter()
+            .map(|h| {
+                let mut item = f(h);
+                item["datetime"] = provider::datetime(h.get("dt").and_then(|v| v.as_i64()).unwrap_or(0), offset).into();
+                item
+            })
+            .collect()
+    };
+    let day_items = |f: &dyn Fn(&Value) -> Value| -> Vec<Value> {
+        daily
+            .iter()
+            .map(|d| {
+                let mut item = f(d);
+                item["date"] = provider::date(d.get("dt").and_then(|v| v.as_i64()).unwrap_or(0), offset).into();
+                item
+            })
+            .collect()
+    };
+    // 逐日降水为日总量 mm，换算为彩云的平均小时强度
+    let day_precip = |d: &Value| num(d, "rain").unwrap_or(0.0) + num(d, "snow").unwrap_or(0.0);
+    let hour_precip = |h: &Value| num(h, "rain.1h").unwrap_or(0.0) + num(h, "snow.1h").unwrap_or(0.0);
+
+    let alerts: Vec<Value> = list("alerts")
+        .iter()
+        .map(|a| {
+            json!({
+                "title": a.get("event").and_then(|v| v.as_str()).unwrap_or(""),
+                "code": "",
+                "status": "",
+                "description": a.get("description").and_then(|v| v.as_str()).unwrap_or(""),
+                "source": a.get("sender_name").and_then(|v| v.as_str()).unwrap_or(""),
+                "pubtimestamp": a.get("start").and_then(|v| v.as_i64()),
+            })
+        })
+        .collect();
+    let minutely: Vec<f64> = list("minutely").iter().map(|m| num(m, "precipitation").unwrap_or(0.0)).collect();
+
+    Some(json!({
+        "status": "ok",
+        "tzshift": offset.local_minus_utc(),
+        "server_time": chrono::Utc::now().timestamp(),
+        "result": {
+            "realtime": {
+                "temperature": num(current, "temp")?,
+                "apparent_temperature": num(current, "feels_like"),
+                "humidity": num(current, "humidity").map(|h| h / 100.0),
+                "skycon": skycon(current.get("weather").unwrap_or(&Value::Null)),
+                "cloudrate": num(current, "clouds").map(|c| c / 100.0),
+                "pressure": num(current, "pressure").map(|p| p * 100.0),
+                "visibility": num(current, "visibility").map(|m| m / 1000.0),
+                "wind": { "speed": num(current, "wind_speed"), "direction": num(current, "wind_deg") },
+            },
+            "minutely": { "description": "", "precipitation_2h": minutely },
+            "hourly": {
+                "temperature": hour_items(&|h| json!({ "value": num(h, "temp") })),
+                "skycon": hour_items(&|h| json!({ "value": skycon(h.get("weather").unwrap_or(&Value::Null)) })),
+                "precipitation": hour_items(&|h| json!({
+                    "value": hour_precip(h),
+                    "probability": num(h, "pop").unwrap_or(0.0) * 100.0,
+                })),
+                "humidity": hour_items(&|h| json!({ "value": num(h, "humidity").map(|v| v / 100.0) })),
+                "wind": hour_items(&|h| json!({ "speed": num(h, "wind_speed"), "direction": num(h, "wind_deg") })),
+            },
+            "daily": {
+                "temperature": day_items(&|d| json!({
+                    "max": num(d, "temp.max"), "min": num(d, "temp.min"), "avg": num(d, "temp.day"),
+                })),
+                "skycon": day_items(&|d| json!({ "value": skycon(d.get("weather").unwrap_or(&Value::Null)) })),
+                "precipitation": day_items(&|d| json!({
+                    "avg": day_precip(d) / 24.0,
+                    "max": day_precip(d) / 24.0,
+                    "probability": num(d, "pop").unwrap_or(0.0) * 100.0,
+                })),
+                "humidity": day_items(&|d| {
+                    let h = num(d, "humidity").map(|v| v / 100.0);
+                    json!({ "avg": h, "min": h, "max": h })
+                }),
+                "wind": day_items(&|d| json!({ "max": { "speed": num(d, "wind_speed"), "direction": num(d, "wind_deg") } })),
+                "pressure": day_items(&|d| {
+                    let p = num(d, "pressure").map(|v| v * 100.0);
+                    json!({ "avg": p, "min": p, "max": p })
+                }),
+                "life_index": {
+                    "ultraviolet": day_items(&|d| provider::ultraviolet(num(d, "uvi").unwrap_or(0.0))),
+                },
+            },
+            "alert": { "content": alerts },
+        },
+    }))
+}
@@ -0,0 +1,265 @@
+// 天气数据源：各数据源统一输出彩云 v2.6 同结构的原始 JSON，v1/v2 整形、趋势记录等后续流程不区分来源
+// - WEATHER_PROVIDERS：数据源顺序，逗号分隔，默认 caiyun；可选 caiyun、qweather、open_meteo、openweathermap
+//   前一个失败（额度用尽、上游故障、超时）时依次尝试下一个；未配置密钥的数据源直接跳过
+// - OPENWEATHERMAP_API_KEY：OpenWeatherMap One Call 3.0 的 key；和风天气使用 QWEATHER_KEY；Open-Meteo 无需 key
+// - WEATHER_BLEND：1 开启融合模式，每次同时请求所有已配置的数据源（额度消耗成倍增加）：
+//   以顺序中首个成功的为主，主数据源缺失的数据块由其他数据源补齐，并交叉比对实况气温，结果附带 sources 元数据
+// - WEATHER_BLEND_BLOCKS：指定数据块来源，逗号分隔的 `块=数据源`，块为 realtime/minutely/hourly/daily/alert，
+//   如 `minutely=caiyun,daily=open_meteo`；指定的数据源失败时沿用主数据源
+// - WEATHER_BLEND_TOLERANCE：各数据源实况气温相差超过该值（°C）时标记不一致，默认 3
+
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, FixedOffset};
+use futures_util::future::{join_all, BoxFuture};
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+
+use crate::{
+    config::{env_flag, env_list, env_nonempty, env_parse},
+    feature_flags, open_meteo, openweathermap, qweather,
+    weather::{self, FetchError},
+    AppState,
+};
+
+pub trait WeatherProvider: Send + Sync {
+    // 与 WEATHER_PROVIDERS 中的名称一致，同时作为上游名
+    fn name(&self) -> &'static str;
+
+    fn fetch<'a>(&'a self, state: &'a AppState, lng: f64, lat: f64) -> BoxFuture<'a, Result<Value, FetchError>>;
+
+    // 是否提供预警；不提供的数据源 alert.content 恒为空
+    fn alerts(&self) -> bool {
+        true
+    }
+}
+
+static PROVIDERS: OnceCell<Vec<Box<dyn WeatherProvider>>> = OnceCell::new();
+static BLEND: OnceCell<Blend> = OnceCell::new();
+
+const BLOCKS: &[&str] = &["realtime", "minutely", "hourly", "daily", "alert"];
+
+struct Blend {
+    // (块, 数据源)
+    blocks: Vec<(&'static str, &'static str)>,
+    tolerance: f64,
+}
+
+fn build(name: &str) -> anyhow::Result<Box<dyn WeatherProvider>> {
+    Ok(match name {
+        "caiyun" => Box::new(weather::Caiyun),
+        "qweather" => Box::new(qweather::QWeather),
+        "open_meteo" => Box::new(open_meteo::OpenMeteo),
+        "openweathermap" => Box::new(openweathermap::OpenWeatherMap { key: env_nonempty("OPENWEATHERMAP_API_KEY") }),
+        other => anyhow::bail!("WEATHER_PROVIDERS 含未知数据源: {}（可选 caiyun、qweather、open_meteo、openweathermap）", other),
+    })
+}
+
+// 启动时读取并校验数据源顺序
+pub fn init_from_env() -> anyhow::Result<()> {
+    let names = env_list("WEATHER_PROVIDERS").unwrap_or_else(|| vec!["caiyun".into()]);
+    let mut providers: Vec<Box<dyn WeatherProvider>> = Vec::with_capacity(names.len());
+    for name in &names {
+        let provider = build(&name.to_ascii_lowercase())?;
+        if providers.iter().any(|p| p.name() == provider.name()) {
+            anyhow::bail!("WEATHER_PROVIDERS 中 {} 重复", name);
+        }
+        providers.push(provider);
+    }
+    if providers.is_empty() {
+        anyhow::bail!("WEATHER_PROVIDERS 不能为空");
+    }
+
+    if env_flag("WEATHER_BLEND") {
+        let mut blocks = Vec::new();
+        for item in env_list("WEATHER_BLEND_BLOCKS").unwrap_or_default() {
+            let parsed = item.split_once('=').and_then(|(block, name)| {
+                let block = BLOCKS.iter().find(|b| **b == block.trim())?;
+                let name = providers.iter().find(|p| p.name() == name.trim().to_ascii_lowercase())?.name();
+                Some((*block, name))
+            });
+            let Some(pair) = parsed else {
+                anyhow::bail!("无法解析 WEATHER_BLEND_BLOCKS 中的项: {}（数据源须在 WEATHER_PROVIDERS 中）", item);
+            };
+            blocks.push(pair);
+        }
+        let tolerance = env_parse("WEATHER_BLEND_TOLERANCE")?.unwrap_or(3.0);
+        let _ = BLEND.set(Blend { blocks, tolerance });
+    }
+    let _ = PROVIDERS.set(providers);
+    Ok(())
+}
+
+fn providers() -> &'static [Box<dyn WeatherProvider>] {
+    PROVIDERS.get_or_init(|| vec![Box::new(weather::Caiyun)])
+}
+
+// 未被功能开关关闭的数据源，按顺序
+fn active() -> Vec<&'static dyn WeatherProvider> {
+    providers().iter().map(|p| p.as_ref()).filter(|p| feature_flags::provider_enabled(p.name())).collect()
+}
+
+// WEATHER_PROVIDERS 中的数据源名，按顺序
+pub fn names() -> Vec<&'static str> {
+    providers().iter().map(|p| p.name()).collect()
+}
+
+// 是否有数据源提供预警
+pub fn has_alerts() -> bool {
+    active().iter().any(|p| p.alerts())
+}
+
+// 自检：逐个请求每个数据源一次（不回退、不经缓存），返回各自的结果与耗时
+pub async fn probe_each(state: &AppState, lng: f64, lat: f64) -> Vec<(&'static str, Duration, Result<(), FetchError>)> {
+    join_all(providers().iter().map(|p| async move {
+        let started = Instant::now();
+        let result = p.fetch(state, lng, lat).await.map(|_| ());
+        (p.name(), started.elapsed(), result)
+    }))
+    .await
+}
+
+// 全部失败时返回首个实际发生的错误（均未配置时返回首个未配置错误）
+fn keep_first(first_err: &mut Option<FetchError>, name: &str, e: FetchError) {
+    if !matches!(e, FetchError::NotConfigured(_)) {
+        tracing::warn!(provider = name, error = %e, "weather provider failed");
+    }
+    match first_err {
+        Some(FetchError::NotConfigured(_)) if !matches!(e, FetchError::NotConfigured(_)) => *first_err = Some(e),
+        None => *first_err = Some(e),
+        _ => {}
+    }
+}
+
+// 按顺序请求，返回首个成功的结果；开启融合模式时并发请求全部数据源后合并
+pub async fn fetch(state: &AppState, lng: f64, lat: f64) -> Result<Value, FetchError> {
+    if let Some(blend) = BLEND.get() {
+        return fetch_blended(blend, state, lng, lat).await;
+    }
+    let mut first_err: Option<FetchError> = None;
+    for provider in active() {
+        match provider.fetch(state, lng, lat).await {
+            Ok(raw) => return Ok(raw),
+            Err(e) => keep_first(&mut first_err, provider.name(), e),
+        }
+    }
+    Err(first_err.unwrap_or(FetchError::Upstream))
+}
+
+// 数据块是否有内容：缺失、null、空数组/空对象（含 alert.content 为空）视为缺失
+fn has_block(raw: &Value, block: &str) -> bool {
+    match raw.pointer(&format!("/result/{}", block)) {
+        None | Some(Value::Null) => false,
+        Some(Value::Object(map)) if block == "alert" => {
+            map.get("content").and_then(|c| c.as_array()).is_some_and(|c| !c.is_empty())
+        }
+        Some(Value::Object(map)) => !map.is_empty(),
+        Some(_) => true,
+    }
+}
+
+async fn fetch_blended(blend: &Blend, state: &AppState, lng: f64, lat: f64) -> Result<Value, FetchError> {
+    let providers = active();
+    let results = join_all(providers.iter().map(|p| p.fetch(state, lng, lat))).await;
+
+    let mut first_err: Option<FetchError> = None;
+    let mut fetched: Vec<(&'static str, Value)> = Vec::new();
+    let mut report = Vec::new();
+    for (provider, result) in providers.iter().zip(results) {
+        match result {
+            Ok(raw) => {
+                let temperature = raw.pointer("/result/realtime/temperature").and_then(|v| v.as_f64());
+                report.push(json!({ "name": provider.name(), "ok": true, "temperature": temperature }));
+                fetched.push((provider.name(), raw));
+            }
+            // 未配置的数据源不计入
+            Err(FetchError::NotConfigured(key)) => keep_first(&mut first_err, provider.name(), FetchError::NotConfigured(key)),
+            Err(e) => {
+                report.push(json!({ "name": provider.name(), "ok": false, "error": e.to_string() }));
+                keep_first(&mut first_err, provider.name(), e);
+            }
+        }
+    }
+    let Some(((primary, _), rest)) = fetched.split_first() else {
+        return Err(first_err.unwrap_or(FetchError::Upstream));
+    };
+    let raw_of = |name: &str| fetched.iter().find(|(n, _)| *n == name).map(|(_, raw)| raw);
+
+    // 各数据块来源：显式指定且成功的数据源 > 主数据源 > 首个有该块的其他数据源
+    let mut merged = fetched[0].1.clone();
+    let mut blocks = serde_json::Map::new();
+    for block in BLOCKS {
+        let pinned = blend
+            .blocks
+            .iter()
+            .filter(|(b, _)| b == block)
+            .find_map(|(_, name)| raw_of(name).filter(|raw| has_block(raw, block)).map(|raw| (*name, raw)));
+        let source = pinned.or_else(|| {
+            if has_block(&merged, block) {
+                return None;
+            }
+            rest.iter().find(|(_, raw)| has_block(raw, block)).map(|(name, raw)| (*name, raw))
+        });
+        let name = match source {
+            Some((name, raw)) => {
+                if let (Some(result), Some(v)) = (merged.get_mut("result"), raw.pointer(&format!("/result/{}", block))) {
+                    result[*block] = v.clone();
+                }
+                name
+            }
+            None if has_block(&merged, block) => *primary,
+            None => continue,
+        };
+        blocks.insert(block.to_string(), name.into());
+    }
+
+    let temperatures: Vec<f64> =
+        fetched.iter().filter_map(|(_, raw)| raw.pointer("/result/realtime/temperature")?.as_f64()).collect();
+    // 字段名均为单词，v1（snake_case）与 v2（camelCase）原样输出
+    let spread = (temperatures.len() > 1).then(|| {
+        let max = temperatures.iter().copied().fold(f64::MIN, f64::max);
+        let min = temperatures.iter().copied().fold(f64::MAX, f64::min);
+        ((max - min) * 10.0).round() / 10.0
+    });
+    let disagreement = spread.is_some_and(|s| s > blend.tolerance);
+    if disagreement {
+        tracing::warn!(?spread, lng, lat, "weather providers disagree on temperature");
+    }
+    merged["sources"] = json!({
+        "primary": primary,
+        "blocks": blocks,
+        "providers": report,
+        "spread": spread,
+        "disagreement": disagreement,
+    });
+    Ok(merged)
+}
+
+// ---- 供各数据源转换为彩云结构使用 ----
+
+// 彩云时间格式（无秒）
+pub fn datetime(ts: i64, offset: FixedOffset) -> String {
+    DateTime::from_timestamp(ts, 0)
+        .unwrap_or_default()
+        .with_timezone(&offset)
+        .format("%Y-%m-%dT%H:%M%:z")
+        .to_string()
+}
+
+// 当天 00:00 的彩云日期格式
+pub fn date(ts: i64, offset: FixedOffset) -> String {
+    let local = DateTime::from_timestamp(ts, 0).unwrap_or_default().with_timezone(&offset);
+    format!("{}T00:00{}", local.date_naive(), local.format("%:z"))
+}
+
+// 紫外线指数 → 彩云生活指数等级（1 最弱 … 5 很强）
+pub fn ultraviolet(uvi: f64) -> Value {
+    let (index, desc) = match uvi {
+        v if v < 3.0 => ("1", "最弱"),
+        v if v < 5.0 => ("2", "弱"),
+        v if v < 7.0 => ("3", "中等"),
+        v if v < 10.0 => ("4", "强"),
+        _ => ("5", "很强"),
+    };
+    json!({ "index": index, "desc": desc })
+}
@@ -0,0 +1,106 @@
+// PWA 支持：生成 manifest、在根路径提供 Service Worker、离线兜底天气数据
+// - PWA_NAME / PWA_SHORT_NAME：应用名称（默认 彩云天气 / 天气）
+// - PWA_THEME_COLOR / PWA_BACKGROUND_COLOR：主题色与启动背景色
+
+use std::sync::Mutex;
+
+use axum::{
+    http::{header, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+
+use crate::{base_path, config::env_nonempty, weather};
+
+const ICON_SIZES: &[u32] = &[72, 96, 128, 144, 152, 192, 384, 512];
+
+static MANIFEST: Lazy<String> = Lazy::new(|| {
+    let icons: Vec<Value> = ICON_SIZES
+        .iter()
+        .map(|s| {
+            json!({
+                "src": format!("{1}/static/icons/icon-{0}x{0}.png", s, base_path::get()),
+                "sizes": format!("{0}x{0}", s),
+                "type": "image/png",
+                "purpose": "any maskable",
+            })
+        })
+        .collect();
+    json!({
+        "name": env_nonempty("PWA_NAME").unwrap_or_else(|| "彩云天气".into()),
+        "short_name": env_nonempty("PWA_SHORT_NAME").unwrap_or_else(|| "天气".into()),
+        "description": "基于彩云天气API的响应式天气查看应用",
+        "id": base_path::join("/"),
+        "start_url": base_path::join("/"),
+        "scope": base_path::join("/"),
+        "display": "standalone",
+        "orientation": "portrait",
+        "theme_color": env_nonempty("PWA_THEME_COLOR").unwrap_or_else(|| "#1976d2".into()),
+        "background_color": env_nonempty("PWA_BACKGROUND_COLOR").unwrap_or_else(|| "#ffffff".into()),
+        "categories": ["weather", "utilities"],
+        "lang": "zh-CN",
+        "icons": icons,
+    })
+    .to_string()
+});
+
+// 最近一次成功获取的真实天气数据，供 /offline.json 兜底
+static LAST_KNOWN: Lazy<Mutex<Option<Value>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn remember(data: &weather::WeatherData) {
+    if let Ok(v) = serde_json::to_value(data) {
+        *LAST_KNOWN.lock().unwrap() = Some(v);
+    }
+}
+
+pub async fn manifest() -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("application/manifest+json; charset=utf-8")),
+            (header::CACHE_CONTROL, HeaderValue::from_static("public, max-age=86400")),
+        ],
+        MANIFEST.as_str(),
+    )
+}
+
+// 从根路径提供 static/sw.js，使 Service Worker 作用域覆盖整个站点；不缓存以便及时更新
+pub async fn service_worker() -> Response {
+    match tokio::fs::read("static/sw.js").await {
+        Ok(body) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, HeaderValue::from_static("text/javascript; charset=utf-8")),
+                (header::CACHE_CONTROL, HeaderValue::from_static("no-cache")),
+                (HeaderName::from_static("service-worker-allowed"), HeaderValue::from_static("/")),
+            ],
+            body,
+        )
+            .into_response(),
+        Err(_) => (StatusCode::NOT_FOUND, "sw.js not found").into_response(),
+    }
+}
+
+// 离线兜底：Service Worker 安装时预缓存，网络与 API 缓存均不可用时返回
+// 有最近一次真实数据时返回之，否则返回示例数据；均带 offline 标记
+pub async fn offline() -> Response {
+    let mut data = LAST_KNOWN
+        .lock()
+        .unwrap()
+        .clone()
+        .or_else(|| weather::sample().and_then(|d| serde_json::to_value(d).ok()))
+        .unwrap_or_else(|| json!({}));
+    if let Some(obj) = data.as_object_mut() {
+        obj.insert("offline".into(), Value::Bool(true));
+    }
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("application/json; charset=utf-8")),
+            (header::CACHE_CONTROL, HeaderValue::from_static("no-cache")),
+        ],
+        data.to_string(),
+    )
+        .into_response()
+}
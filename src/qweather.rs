@@ -0,0 +1,304 @@
+// 和风天气（QWeather）数据源：实况、逐小时、逐日为必需，分钟级降水、预警、生活指数尽力获取，转换为彩云同结构
+// 城市查询（GeoAPI）同时作为地点搜索与逆地理的回退，见 location.rs
+// - QWEATHER_KEY：和风天气 Web API key；未配置时数据源跳过
+// - QWEATHER_API_URL：天气 API 地址，默认 https://devapi.qweather.com（付费订阅为 https://api.qweather.com 或专属 API Host）
+// - QWEATHER_GEO_URL：GeoAPI 地址，默认 https://geoapi.qweather.com
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use futures_util::future::BoxFuture;
+use serde_json::{json, Value};
+
+use crate::{
+    error_report,
+    location::Place,
+    provider::{self, WeatherProvider},
+    upstream,
+    upstream_url::UpstreamUrl,
+    weather::FetchError,
+    AppState,
+};
+
+// 生活指数类型 → 彩云 life_index 键
+const INDICES: &[(&str, &str)] = &[("2", "carWashing"), ("3", "dressing"), ("5", "ultraviolet"), ("8", "comfort"), ("9", "coldRisk")];
+
+pub struct QWeather;
+
+impl WeatherProvider for QWeather {
+    fn name(&self) -> &'static str {
+        "qweather"
+    }
+
+    fn fetch<'a>(&'a self, state: &'a AppState, lng: f64, lat: f64) -> BoxFuture<'a, Result<Value, FetchError>> {
+        Box::pin(fetch(state, lng, lat))
+    }
+}
+
+// 和风接口 HTTP 状态恒为 200，业务状态在 body.code；非 "200" 视为上游异常（含额度用尽 402/429）
+async fn get(state: &AppState, key: &str, url_template: &'static str, query: &[(&str, &str)]) -> Result<Value, FetchError> {
+    let url = query
+        .iter()
+        .fold(UpstreamUrl::new(&state.base_urls.qweather).path(url_template), |url, (k, v)| url.query(k, v))
+        .secret_query("key", key);
+    let resp = upstream::send("qweather", url_template, upstream::client("qweather").get(url.as_str()))
+        .await
+        .map_err(FetchError::Request)?
+        .error_for_status()
+        .map_err(FetchError::Status)?;
+    let json = upstream::json::<Value>(resp).await.map_err(|e| {
+        error_report::capture_upstream_error("qweather", &e);
+        FetchError::Parse(e)
+    })?;
+    match json.get("code").and_then(|v| v.as_str()) {
+        Some("200") => Ok(json),
+        code => {
+            error_report::capture_upstream_error("qweather", &format_args!("{} code={}", url_template, code.unwrap_or("unknown")));
+            Err(FetchError::Upstream)
+        }
+    }
+}
+
+async fn fetch(state: &AppState, lng: f64, lat: f64) -> Result<Value, FetchError> {
+    let Some(key) = state.qweather_key.as_deref() else {
+        return Err(FetchError::NotConfigured("QWEATHER_KEY"));
+    };
+    let location = format!("{:.2},{:.2}", lng, lat);
+    let types = INDICES.iter().map(|(t, _)| *t).collect::<Vec<_>>().join(",");
+    let indices = [("location", location.as_str()), ("type", types.as_str())];
+    let location = [("location", location.as_str())];
+    let (now, hourly, daily, minutely, warning, indices) = tokio::join!(
+        get(state, key, "/v7/weather/now", &location),
+        get(state, key, "/v7/weather/24h", &location),
+        get(state, key, "/v7/weather/3d", &location),
+        get(state, key, "/v7/minutely/5m", &location),
+        get(state, key, "/v7/warning/now", &location),
+        get(state, key, "/v7/indices/3d", &indices),
+    );
+    // 分钟级降水仅覆盖国内，预警与生活指数缺失时不影响主体数据
+    let optional = |r: Result<Value, FetchError>| r.unwrap_or(Value::Null);
+    to_caiyun(&now?, &hourly?, &daily?, &optional(minutely), &optional(warning), &optional(indices)).ok_or_else(|| {
+        error_report::capture_upstream_error("qweather", &"unexpected response shape");
+        FetchError::Upstream
+    })
+}
+
+// 天气图标代码（https://dev.qweather.com/docs/resource/icons/）→ 彩云 skycon；15x、35x、45x 为夜间
+fn skycon(icon: &str) -> &'static str {
+    match icon.parse::<u32>().unwrap_or(999) {
+        100 => "CLEAR_DAY",
+        150 => "CLEAR_NIGHT",
+        101..=103 => "PARTLY_CLOUDY_DAY",
+        151..=153 => "PARTLY_CLOUDY_NIGHT",
+        104 => "CLOUDY",
+        300 | 305 | 309 | 314 | 350 | 399 => "LIGHT_RAIN",
+        306 | 315 => "MODERATE_RAIN",
+        301 | 302 | 307 | 351 => "HEAVY_RAIN",
+        303 | 308 | 310..=312 | 316..=318 => "STORM_RAIN",
+        304 => "HAIL",
+        313 | 404..=406 | 456 => "SLEET",
+        400 | 407 | 408 | 457 | 499 => "LIGHT_SNOW",
+        401 | 409 => "MODERATE_SNOW",
+        402 | 410 => "HEAVY_SNOW",
+        403 => "STORM_SNOW",
+        500 | 501 | 509 | 510 | 514 | 515 => "FOG",
+        502 => "LIGHT_HAZE",
+        511 => "MODERATE_HAZE",
+        512 | 513 => "HEAVY_HAZE",
+        503 | 507 | 508 => "SAND",
+        504 => "DUST",
+        _ => "CLOUDY",
+    }
+}
+
+// 和风数值均为字符串
+fn num(v: &Value, key: &str) -> Option<f64> {
+    v.get(key)?.as_str()?.parse().ok()
+}
+
+fn text<'a>(v: &'a Value, key: &str) -> &'a str {
+    v.get(key).and_then(|v| v.as_str()).unwrap_or("")
+}
+
+// 时间形如 2024-06-01T08:00+08:00，与彩云一致
+fn parse_time(s: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M%:z").ok()
+}
+
+fn list<'a>(v: &'a Value, key: &str) -> &'a [Value] {
+    v.get(key).and_then(|v| v.as_array()).map(Vec::as_slice).unwrap_or(&[])
+}
+
+fn to_caiyun(now: &Value, hourly: &Value, daily: &Value, minutely: &Value, warning: &Value, indices: &Value) -> Option<Value> {
+    let current = now.get("now")?;
+    let offset = *parse_time(text(current, "obsTime"))?.offset();
+    let kmh = |v: Option<f64>| v.map(|s| s / 3.6);
+
+    let hours = list(hourly, "hourly");
+    let hour_items = |f: &dyn Fn(&Value) -> Value| -> Vec<Value> {
+        hours
+            .iter()
+            .map(|h| {
+                let mut item = f(h);
+                item["datetime"] = text(h, "fxTime").into();
+                item
+            })
+            .collect()
+    };
+    let days = list(daily, "daily");
+    let day_items = |f: &dyn Fn(&Value) -> Value| -> Vec<Value> {
+        days.iter()
+            .filter_map(|d| {
+                let date = NaiveDate::parse_from_str(text(d, "fxDate"), "%Y-%m-%d").ok()?;
+                let mut item = f(d);
+                item["date"] = format!("{}T00:00{}", date, offset).into();
+                Some(item)
+            })
+            .collect()
+    };
+
+    // 逐 5 分钟降水量 mm → 彩云的 mm/h 强度，按分钟展开为 120 个点
+    let precipitation_2h: Vec<f64> = list(minutely, "minutely")
+        .iter()
+        .flat_map(|m| [num(m, "precip").unwrap_or(0.0) * 12.0; 5])
+        .collect();
+
+    let alerts: Vec<Value> = list(warning, "warning")
+        .iter()
+        .map(|w| {
+            json!({
+                "title": text(w, "title"),
+                "code": text(w, "type"),
+                "status": text(w, "status"),
+                "description": text(w, "text"),
+                "source": text(w, "sender"),
+                "pubtimestamp": parse_time(text(w, "pubTime")).map(|t| t.timestamp()),
+            })
+        })
+        .collect();
+
+    // 生活指数按类型分组，每组按日期排列
+    let mut life_index = serde_json::Map::new();
+    for (kind, key) in INDICES {
+        let items: Vec<Value> = list(indices, "daily")
+            .iter()
+            .filter(|i| text(i, "type") == *kind)
+            .map(|i| json!({ "index": text(i, "level"), "desc": text(i, "category") }))
+            .collect();
+        if !items.is_empty() {
+            life_index.insert(key.to_string(), items.into());
+        }
+    }
+    // 指数接口不可用时，紫外线等级由逐日紫外线指数换算
+    if !life_index.contains_key("ultraviolet") {
+        life_index.insert(
+            "ultraviolet".into(),
+            day_items(&|d| provider::ultraviolet(num(d, "uvIndex").unwrap_or(0.0))).into(),
+        );
+    }
+
+    Some(json!({
+        "status": "ok",
+        "tzshift": offset.local_minus_utc(),
+        "server_time": chrono::Utc::now().timestamp(),
+        "result": {
+            "realtime": {
+                "temperature": num(current, "temp")?,
+                "apparent_temperature": num(current, "feelsLike"),
+                "humidity": num(current, "humidity").map(|h| h / 100.0),
+                "skycon": skycon(text(current, "icon")),
+                "cloudrate": num(current, "cloud").map(|c| c / 100.0),
+                "pressure": num(current, "pressure").map(|p| p * 100.0),
+                "visibility": num(current, "vis"),
+                "wind": { "speed": kmh(num(current, "windSpeed")), "direction": num(current, "wind360") },
+            },
+            "minutely": { "description": text(minutely, "summary"), "precipitation_2h": precipitation_2h },
+            "hourly": {
+                "temperature": hour_items(&|h| json!({ "value": num(h, "temp") })),
+                "skycon": hour_items(&|h| json!({ "value": skycon(text(h, "icon")) })),
+                "precipitation": hour_items(&|h| json!({
+                    "value": num(h, "precip").unwrap_or(0.0),
+                    "probability": num(h, "pop").unwrap_or(0.0),
+                })),
+                "humidity": hour_items(&|h| json!({ "value": num(h, "humidity").map(|v| v / 100.0) })),
+                "wind": hour_items(&|h| json!({ "speed": kmh(num(h, "windSpeed")), "direction": num(h, "wind360") })),
+            },
+            "daily": {
+                "temperature": day_items(&|d| {
+                    let (max, min) = (num(d, "tempMax"), num(d, "tempMin"));
+                    json!({ "max": max, "min": min, "avg": max.zip(min).map(|(a, b)| (a + b) / 2.0) })
+                }),
+                "skycon": day_items(&|d| json!({ "value": skycon(text(d, "iconDay")) })),
+                "skycon_08h_20h": day_items(&|d| json!({ "value": skycon(text(d, "iconDay")) })),
+                "skycon_20h_32h": day_items(&|d| json!({ "value": skycon(text(d, "iconNight")) })),
+                // 逐日降水为日总量 mm，换算为平均小时强度；无降水概率
+                "precipitation": day_items(&|d| {
+                    let avg = num(d, "precip").unwrap_or(0.0) / 24.0;
+                    json!({ "avg": avg, "max": avg, "probability": 0 })
+                }),
+                "humidity": day_items(&|d| {
+                    let h = num(d, "humidity").map(|v| v / 100.0);
+                    json!({ "avg": h, "min": h, "max": h })
+                }),
+                "wind": day_items(&|d| json!({
+                    "max": { "speed": kmh(num(d, "windSpeedDay")), "direction": num(d, "wind360Day") },
+                })),
+                "pressure": day_items(&|d| {
+                    let p = num(d, "pressure").map(|v| v * 100.0);
+                    json!({ "avg": p, "min": p, "max": p })
+                }),
+                "life_index": life_index,
+            },
+            "alert": { "content": alerts },
+        },
+    }))
+}
+
+// ---- 城市查询（GeoAPI） ----
+
+async fn lookup(state: &AppState, location: &str, number: usize) -> Vec<Place> {
+    let Some(key) = state.qweather_key.as_deref() else {
+        return Vec::new();
+    };
+    let url = UpstreamUrl::new(&state.base_urls.qweather_geo)
+        .path("/v2/city/lookup")
+        .query("location", location)
+        .query("number", number)
+        .query("lang", "zh")
+        .secret_query("key", key);
+    let Ok(resp) = upstream::send("qweather", "/v2/city/lookup", upstream::client("qweather").get(url.as_str())).await else {
+        return Vec::new();
+    };
+    let Ok(v) = upstream::json::<Value>(resp).await else {
+        return Vec::new();
+    };
+    if v.get("code").and_then(|c| c.as_str()) != Some("200") {
+        return Vec::new();
+    }
+    list(&v, "location")
+        .iter()
+        .filter_map(|loc| {
+            let name = text(loc, "name");
+            // 地址：省 市（与名称相同的层级省略）
+            let address: Vec<&str> = [text(loc, "adm1"), text(loc, "adm2")]
+                .into_iter()
+                .filter(|s| !s.is_empty() && *s != name)
+                .collect();
+            Some(Place {
+                lat: num(loc, "lat")?,
+                lng: num(loc, "lon")?,
+                name: name.to_string(),
+                address: address.join(" "),
+            })
+        })
+        .collect()
+}
+
+// 按名称搜索城市
+pub async fn search(state: &AppState, q: &str) -> Vec<Place> {
+    lookup(state, q, 5).await
+}
+
+// 坐标 → 最近的城市，返回“省 市 区”形式的名称
+pub async fn city_name(state: &AppState, lat: f64, lng: f64) -> Option<String> {
+    let place = lookup(state, &format!("{:.2},{:.2}", lng, lat), 1).await.into_iter().next()?;
+    Some(if place.address.is_empty() { place.name } else { format!("{} {}", place.address, place.name) })
+}
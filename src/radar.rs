@@ -0,0 +1,131 @@
+// 降水雷达瓦片代理：/api/v1/radar/{z}/{x}/{y}.png 转发到上游瓦片服务，前端地图图层不接触上游 key
+// 瓦片先查内存缓存，再查磁盘缓存（可选），都未命中才请求上游；雷达数据更新快，缓存时间宜短
+// - RADAR_TILE_URL：上游瓦片地址模板，`{z}`/`{x}`/`{y}` 替换为瓦片坐标，可含 key；未设置时接口返回 503
+// - RADAR_CACHE_SECS：瓦片缓存秒数，默认 300（同时作为响应的 Cache-Control max-age）
+// - RADAR_CACHE_DIR：磁盘缓存目录，未设置时只用内存缓存
+// 缓存过期后带上次的 ETag/Last-Modified 重新请求，上游返回 304 时沿用原瓦片
+
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use axum::{
+    body::Bytes,
+    extract::Path,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+
+use crate::{
+    cache::TtlCache,
+    config::{env_nonempty, env_parse},
+    upstream,
+    upstream_url::UpstreamUrl,
+};
+
+const MAX_ZOOM: u32 = 18;
+
+static TTL: Lazy<Duration> =
+    Lazy::new(|| Duration::from_secs(env_parse("RADAR_CACHE_SECS").ok().flatten().unwrap_or(300)));
+static TILE_URL: Lazy<Option<String>> = Lazy::new(|| env_nonempty("RADAR_TILE_URL"));
+static CACHE_DIR: Lazy<Option<PathBuf>> = Lazy::new(|| env_nonempty("RADAR_CACHE_DIR").map(PathBuf::from));
+
+// (Content-Type, 图片)
+type Tile = (String, Bytes);
+
+// 键：(z, x, y)
+static TILES: Lazy<TtlCache<(u32, u32, u32), Tile>> = Lazy::new(|| TtlCache::new(*TTL, 2048));
+
+fn disk_path(z: u32, x: u32, y: u32) -> Option<PathBuf> {
+    CACHE_DIR.as_ref().map(|dir| dir.join(format!("{}/{}/{}.png", z, x, y)))
+}
+
+// 按修改时间判断磁盘缓存是否过期
+async fn read_disk(path: &PathBuf) -> Option<Bytes> {
+    let meta = tokio::fs::metadata(path).await.ok()?;
+    let age = SystemTime::now().duration_since(meta.modified().ok()?).ok()?;
+    if age >= *TTL {
+        return None;
+    }
+    tokio::fs::read(path).await.ok().map(Bytes::from)
+}
+
+async fn write_disk(path: &PathBuf, body: &Bytes) {
+    let tmp = path.with_extension("png.tmp");
+    let result = async {
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&tmp, body).await?;
+        tokio::fs::rename(&tmp, path).await
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!(error = %e, path = %path.display(), "radar tile cache write failed");
+    }
+}
+
+// 内存缓存过期后向上游重新验证，瓦片未更新时上游只需返回 304
+static REVALIDATOR: Lazy<upstream::Revalidator> = Lazy::new(|| upstream::Revalidator::new(2048));
+
+async fn fetch_tile(template: &str, z: u32, x: u32, y: u32) -> Result<Tile, StatusCode> {
+    let (z, x, y) = (z.to_string(), x.to_string(), y.to_string());
+    let url = UpstreamUrl::template(template, &[("z", &z), ("x", &x), ("y", &y)]);
+    // 上游无此瓦片时透传 404，其余错误统一 502
+    let tile = REVALIDATOR.get("radar", "radar tile", url.as_str()).await.map_err(|e| match e {
+        upstream::RevalidateError::Status(StatusCode::NOT_FOUND) => StatusCode::NOT_FOUND,
+        _ => StatusCode::BAD_GATEWAY,
+    })?;
+    Ok((tile.content_type.unwrap_or_else(|| "image/png".to_string()), tile.body))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn tile(Path((z, x, file)): Path<(u32, u32, String)>) -> Response {
+    let Some(template) = TILE_URL.as_deref() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "未配置 RADAR_TILE_URL").into_response();
+    };
+    let Some(y) = file.strip_suffix(".png").and_then(|y| y.parse::<u32>().ok()) else {
+        return (StatusCode::NOT_FOUND, "瓦片不存在").into_response();
+    };
+    if z > MAX_ZOOM || x >= 1 << z || y >= 1 << z {
+        return (StatusCode::NOT_FOUND, "瓦片不存在").into_response();
+    }
+
+    let key = (z, x, y);
+    let path = disk_path(z, x, y);
+    let (content_type, body) = match TILES.get(&key) {
+        Some(hit) => hit,
+        None => {
+            let cached = match &path {
+                Some(p) => read_disk(p).await.map(|b| ("image/png".to_string(), b)),
+                None => None,
+            };
+            let tile = match cached {
+                Some(tile) => tile,
+                None => match fetch_tile(template, z, x, y).await {
+                    Ok(tile) => {
+                        if let Some(p) = &path {
+                            write_disk(p, &tile.1).await;
+                        }
+                        tile
+                    }
+                    Err(status) => return (status, "获取雷达瓦片失败").into_response(),
+                },
+            };
+            TILES.insert(key, tile.clone());
+            tile
+        }
+    };
+
+    let content_type = HeaderValue::from_str(&content_type).unwrap_or(HeaderValue::from_static("image/png"));
+    let cache_control = HeaderValue::from_str(&format!("public, max-age={}", TTL.as_secs()))
+        .unwrap_or(HeaderValue::from_static("no-cache"));
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, content_type), (header::CACHE_CONTROL, cache_control)],
+        body,
+    )
+        .into_response()
+}
@@ -0,0 +1,152 @@
+// Redis 缓存后端：多实例共享上游结果缓存条目与单飞锁（需 `redis` feature）
+// - REDIS_URL：如 `redis://:password@10.0.0.5:6379/0`，TLS 用 `rediss://`
+// - REDIS_PREFIX：键前缀，默认 `caiyun:`，多个部署共用同一 Redis 时区分
+// 条目的过期时间含 stale 窗口，由 Redis 自行清理；首次使用时才建立连接，Redis 不可用时视为未命中并直接请求上游
+
+use std::time::Duration;
+
+use futures_util::{future::BoxFuture, StreamExt};
+use redis::{aio::ConnectionManager, AsyncCommands, Client};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+use crate::{
+    config::env_nonempty,
+    upstream_cache::{key_matches, now_ms, Backend, Entry},
+};
+
+pub struct RedisBackend {
+    client: Client,
+    conn: OnceCell<ConnectionManager>,
+    prefix: String,
+    stale: i64,
+}
+
+impl RedisBackend {
+    pub fn from_env(url: &str, stale: i64) -> anyhow::Result<Self> {
+        let client = Client::open(url).map_err(|e| anyhow::anyhow!("REDIS_URL 无效: {}", e))?;
+        let prefix = env_nonempty("REDIS_PREFIX").unwrap_or_else(|| "caiyun:".into());
+        info!("upstream cache backend: redis (prefix {})", prefix);
+        Ok(RedisBackend { client, conn: OnceCell::new(), prefix, stale })
+    }
+
+    // ConnectionManager 断线后自动重连，可廉价克隆
+    async fn conn(&self) -> redis::RedisResult<ConnectionManager> {
+        self.conn.get_or_try_init(|| ConnectionManager::new(self.client.clone())).await.cloned()
+    }
+
+    async fn try_get(&self, key: &str) -> redis::RedisResult<Option<String>> {
+        self.conn().await?.get(format!("{}{}", self.prefix, key)).await
+    }
+
+    async fn try_put(&self, key: &str, entry: &Entry) -> redis::RedisResult<()> {
+        let retain = (entry.expires_at + self.stale - now_ms()).max(1) as u64;
+        let value = serde_json::to_string(entry).unwrap_or_default();
+        self.conn().await?.pset_ex(format!("{}{}", self.prefix, key), value, retain).await
+    }
+
+    async fn try_lock_inner(&self, key: &str, ttl: Duration) -> redis::RedisResult<bool> {
+        let reply: Option<String> = redis::cmd("SET")
+            .arg(format!("{}lock:{}", self.prefix, key))
+            .arg(1)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl.as_millis() as u64)
+            .query_async(&mut self.conn().await?)
+            .await?;
+        Ok(reply.is_some())
+    }
+
+    async fn try_unlock(&self, key: &str) -> redis::RedisResult<()> {
+        self.conn().await?.del(format!("{}lock:{}", self.prefix, key)).await
+    }
+
+    // SCAN 遍历前缀下的条目 key（不含锁），返回去掉前缀后的 key
+    async fn scan(&self, prefix: &str) -> redis::RedisResult<Vec<String>> {
+        let mut conn = self.conn().await?;
+        let pattern = format!("{}*", self.prefix);
+        let keys: Vec<String> = conn.scan_match::<_, String>(&pattern).await?.collect().await;
+        Ok(keys
+            .into_iter()
+            .filter_map(|k| k.strip_prefix(&self.prefix).map(str::to_string))
+            .filter(|k| !k.starts_with("lock:") && key_matches(k, prefix))
+            .collect())
+    }
+
+    async fn try_list(&self, prefix: &str) -> redis::RedisResult<Vec<(String, Entry)>> {
+        let keys = self.scan(prefix).await?;
+        let mut listed = Vec::with_capacity(keys.len());
+        for chunk in keys.chunks(200) {
+            let values: Vec<Option<String>> = redis::cmd("MGET")
+                .arg(chunk.iter().map(|k| format!("{}{}", self.prefix, k)).collect::<Vec<_>>())
+                .query_async(&mut self.conn().await?)
+                .await?;
+            // 扫描后已过期的条目为 nil
+            for (key, value) in chunk.iter().zip(values) {
+                if let Some(entry) = value.and_then(|v| serde_json::from_str(&v).ok()) {
+                    listed.push((key.clone(), entry));
+                }
+            }
+        }
+        Ok(listed)
+    }
+
+    async fn try_purge(&self, prefix: &str) -> redis::RedisResult<usize> {
+        let keys = self.scan(prefix).await?;
+        let mut purged = 0;
+        for chunk in keys.chunks(200) {
+            let keys: Vec<String> = chunk.iter().map(|k| format!("{}{}", self.prefix, k)).collect();
+            purged += self.conn().await?.del::<_, usize>(keys).await?;
+        }
+        Ok(purged)
+    }
+}
+
+impl Backend for RedisBackend {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Entry>> {
+        Box::pin(async move {
+            match self.try_get(key).await {
+                // 无法解析的条目（格式变更等）视为未命中，稍后被覆盖
+                Ok(value) => value.and_then(|v| serde_json::from_str(&v).ok()),
+                Err(e) => {
+                    warn!("redis get {} failed: {}", key, e);
+                    None
+                }
+            }
+        })
+    }
+
+    fn put<'a>(&'a self, key: &'a str, entry: Entry) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            if let Err(e) = self.try_put(key, &entry).await {
+                warn!("redis set {} failed: {}", key, e);
+            }
+        })
+    }
+
+    // Redis 出错时按已取得锁处理，各实例自行请求上游
+    fn try_lock<'a>(&'a self, key: &'a str, ttl: Duration) -> BoxFuture<'a, bool> {
+        Box::pin(async move {
+            self.try_lock_inner(key, ttl).await.unwrap_or_else(|e| {
+                warn!("redis lock {} failed: {}", key, e);
+                true
+            })
+        })
+    }
+
+    fn unlock<'a>(&'a self, key: &'a str) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            if let Err(e) = self.try_unlock(key).await {
+                warn!("redis unlock {} failed: {}", key, e);
+            }
+        })
+    }
+
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<(String, Entry)>>> {
+        Box::pin(async move { Ok(self.try_list(prefix).await?) })
+    }
+
+    fn purge<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, anyhow::Result<usize>> {
+        Box::pin(async move { Ok(self.try_purge(prefix).await?) })
+    }
+}
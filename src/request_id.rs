@@ -0,0 +1,84 @@
+// 请求 ID：生成/透传 X-Request-Id，写入 tracing span、错误响应与上游请求，便于按 ID 串联日志
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+pub static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static CURRENT: String;
+}
+
+// 当前请求的 ID（仅在请求处理任务内可用）
+pub fn current() -> Option<String> {
+    CURRENT.try_with(|id| id.clone()).ok()
+}
+
+// 在阻塞线程池执行 f，沿用当前请求的 ID 与 tracing span（task-local 不随 spawn_blocking 传递）
+pub fn spawn_blocking<R: Send + 'static>(f: impl FnOnce() -> R + Send + 'static) -> tokio::task::JoinHandle<R> {
+    let (id, span) = (current(), tracing::Span::current());
+    tokio::task::spawn_blocking(move || {
+        let _entered = span.enter();
+        match id {
+            Some(id) => CURRENT.sync_scope(id, f),
+            None => f(),
+        }
+    })
+}
+
+// 客户端传入的 ID 仅接受短小的可见 ASCII，避免日志注入；否则重新生成
+fn accept_incoming(v: &HeaderValue) -> Option<String> {
+    let s = v.to_str().ok()?.trim();
+    let ok = !s.is_empty()
+        && s.len() <= 64
+        && s.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_.:".contains(&b));
+    ok.then(|| s.to_string())
+}
+
+pub async fn middleware(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(accept_incoming)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let value = HeaderValue::from_str(&id).expect("request id is ascii");
+    req.headers_mut().insert(X_REQUEST_ID.clone(), value.clone());
+
+    let mut resp = CURRENT.scope(id, next.run(req)).await;
+    resp.headers_mut().insert(X_REQUEST_ID.clone(), value);
+    resp
+}
+
+// TraceLayer 的 span：带上 request_id（由外层 middleware 保证已写入请求头）
+pub fn make_span<B>(req: &axum::http::Request<B>) -> tracing::Span {
+    let id = req
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("-");
+    // 命中的路由模板（如 /api/weather），未命中时为空
+    let route = req
+        .extensions()
+        .get::<axum::extract::MatchedPath>()
+        .map(|p| p.as_str())
+        .unwrap_or("");
+    tracing::info_span!(
+        "request",
+        method = %req.method(),
+        uri = %req.uri().path(),
+        route,
+        request_id = %id,
+    )
+}
+
+// 上游请求透传当前请求 ID
+pub fn propagate(rb: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match current() {
+        Some(id) => rb.header(X_REQUEST_ID.as_str(), id),
+        None => rb,
+    }
+}
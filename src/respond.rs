@@ -0,0 +1,212 @@
+// 响应工具：各处理函数显式设置 Content-Type，并按 Accept 头协商输出格式
+// 支持 JSON（默认）、MessagePack 与 CBOR，后两者面向嵌入式/IoT 客户端
+// JSON 序列化到每个线程复用的缓冲区，高并发下避免每个响应从零开始反复扩容
+
+use std::{cell::RefCell, convert::Infallible};
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use bytes::{BufMut, BytesMut};
+use serde::Serialize;
+
+pub const JSON: &str = "application/json";
+pub const MSGPACK: &str = "application/msgpack";
+pub const CBOR: &str = "application/cbor";
+
+// 按服务端偏好排序；Accept 为 */* 时返回 JSON
+const OFFERED: &[&str] = &[JSON, MSGPACK, CBOR];
+
+struct MediaRange {
+    ty: String,
+    subtype: String,
+    q: f32,
+}
+
+// 解析后的 Accept 头；缺省视为 */*
+pub struct Accept(Vec<MediaRange>);
+
+impl Accept {
+    pub fn parse(raw: &str) -> Self {
+        let ranges = raw
+            .split(',')
+            .filter_map(|part| {
+                let mut params = part.split(';');
+                let (ty, subtype) = params.next()?.trim().split_once('/')?;
+                let q = params
+                    .filter_map(|p| p.trim().strip_prefix("q="))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                Some(MediaRange {
+                    ty: ty.trim().to_ascii_lowercase(),
+                    subtype: subtype.trim().to_ascii_lowercase(),
+                    q,
+                })
+            })
+            .collect();
+        Self(ranges)
+    }
+
+    // 从服务端提供的格式（按偏好排序）中选出客户端最想要的；都不可接受时返回 None
+    pub fn negotiate(&self, offered: &[&'static str]) -> Option<&'static str> {
+        if self.0.is_empty() {
+            return offered.first().copied();
+        }
+        let mut best: Option<(f32, &'static str)> = None;
+        for &candidate in offered {
+            let (ty, subtype) = candidate.split_once('/').unwrap_or((candidate, ""));
+            // 取最具体的匹配项的 q 值
+            let q = self
+                .0
+                .iter()
+                .filter_map(|r| {
+                    let specificity = match (r.ty.as_str(), r.subtype.as_str()) {
+                        ("*", "*") => 0,
+                        (t, "*") if t == ty => 1,
+                        (t, s) if t == ty && s == subtype => 2,
+                        _ => return None,
+                    };
+                    Some((specificity, r.q))
+                })
+                .max_by_key(|(specificity, _)| *specificity)
+                .map(|(_, q)| q)
+                .unwrap_or(0.0);
+            if q > 0.0 && !matches!(best, Some((bq, _)) if bq >= q) {
+                best = Some((q, candidate));
+            }
+        }
+        best.map(|(_, mime)| mime)
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Accept {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .map(Accept::parse)
+            .unwrap_or(Accept(Vec::new())))
+    }
+}
+
+// 缓冲区每次至少预留的容量，能装下一份完整的天气响应
+const JSON_BUFFER_CAPACITY: usize = 32 * 1024;
+
+thread_local! {
+    // 取出的 Bytes 与缓冲区共享同一块内存；响应发送完毕释放后，下次 reserve 会直接回收整块内存而不是重新分配
+    static JSON_BUFFER: RefCell<BytesMut> = RefCell::new(BytesMut::with_capacity(JSON_BUFFER_CAPACITY));
+}
+
+fn to_json_bytes<T: Serialize>(value: &T) -> serde_json::Result<Bytes> {
+    JSON_BUFFER.with_borrow_mut(|buf| {
+        buf.reserve(JSON_BUFFER_CAPACITY);
+        let written = serde_json::to_writer((&mut *buf).writer(), value);
+        // 出错时也要取走已写入的部分，保持缓冲区为空
+        let body = buf.split().freeze();
+        written.map(|_| body)
+    })
+}
+
+// JSON 响应（application/json; charset=utf-8）
+pub fn json<T: Serialize>(status: StatusCode, value: &T) -> Response {
+    match to_json_bytes(value) {
+        Ok(body) => (
+            status,
+            [(header::CONTENT_TYPE, HeaderValue::from_static("application/json; charset=utf-8"))],
+            body,
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("序列化失败: {}", e)).into_response(),
+    }
+}
+
+// GeoJSON 响应（application/geo+json，RFC 7946）
+pub fn geojson<T: Serialize>(status: StatusCode, value: &T) -> Response {
+    let mut resp = json(status, value);
+    if resp.status() == status {
+        resp.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static("application/geo+json"));
+    }
+    resp
+}
+
+// 按 Accept 协商输出格式（JSON / MessagePack / CBOR），不可接受时返回 406
+pub fn negotiated<T: Serialize>(accept: &Accept, status: StatusCode, value: &T) -> Response {
+    let mut resp = match accept.negotiate(OFFERED) {
+        Some(MSGPACK) => binary(status, MSGPACK, rmp_serde::to_vec_named(value).map_err(|e| e.to_string())),
+        Some(CBOR) => {
+            let mut buf = Vec::new();
+            binary(status, CBOR, ciborium::into_writer(value, &mut buf).map(|_| buf).map_err(|e| e.to_string()))
+        }
+        Some(_) => json(status, value),
+        None => not_acceptable(OFFERED),
+    };
+    resp.headers_mut().insert(header::VARY, HeaderValue::from_static("accept"));
+    resp
+}
+
+fn binary(status: StatusCode, content_type: &'static str, body: Result<Vec<u8>, String>) -> Response {
+    match body {
+        Ok(body) => (status, [(header::CONTENT_TYPE, HeaderValue::from_static(content_type))], body).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("序列化失败: {}", e)).into_response(),
+    }
+}
+
+fn not_acceptable(offered: &[&str]) -> Response {
+    (
+        StatusCode::NOT_ACCEPTABLE,
+        [(header::CONTENT_TYPE, HeaderValue::from_static("text/plain; charset=utf-8"))],
+        format!("支持的格式: {}", offered.join(", ")),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header, StatusCode};
+    use serde_json::json;
+
+    use super::{negotiated, Accept, CBOR, JSON, MSGPACK, OFFERED};
+
+    fn pick(accept: &str) -> Option<&'static str> {
+        Accept::parse(accept).negotiate(OFFERED)
+    }
+
+    #[test]
+    fn negotiates_by_quality_then_server_preference() {
+        assert_eq!(pick(""), Some(JSON));
+        assert_eq!(pick("*/*"), Some(JSON));
+        assert_eq!(pick("application/msgpack"), Some(MSGPACK));
+        assert_eq!(pick("Application/CBOR"), Some(CBOR));
+        assert_eq!(pick("application/json;q=0.5, application/cbor"), Some(CBOR));
+        assert_eq!(pick("application/cbor, application/msgpack"), Some(MSGPACK));
+        assert_eq!(pick("application/*;q=0.8, application/msgpack;q=0.9"), Some(MSGPACK));
+        assert_eq!(pick("text/html, */*;q=0.1"), Some(JSON));
+    }
+
+    #[test]
+    fn most_specific_range_wins_and_zero_quality_excludes() {
+        assert_eq!(pick("application/*, application/json;q=0"), Some(MSGPACK));
+        assert_eq!(pick("*/*;q=0.1, application/json;q=0"), Some(MSGPACK));
+        assert_eq!(pick("text/html"), None);
+        assert_eq!(pick("application/*;q=0"), None);
+    }
+
+    #[test]
+    fn unacceptable_requests_get_406_and_every_response_varies_on_accept() {
+        let resp = negotiated(&Accept::parse("text/html"), StatusCode::OK, &json!({ "a": 1 }));
+        assert_eq!(resp.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(resp.headers()[header::VARY], "accept");
+        let resp = negotiated(&Accept::parse("application/msgpack"), StatusCode::CREATED, &json!({ "a": 1 }));
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], MSGPACK);
+        assert_eq!(resp.headers()[header::VARY], "accept");
+    }
+}
@@ -0,0 +1,113 @@
+// 路由超时：限制单个请求的总处理时间，上游卡住时不让客户端一直等待
+// 超时返回 504 与结构化错误 { error, code: "UPSTREAM_TIMEOUT", request_id }；/api/weather 超时时优先回退到最近的缓存数据
+// - ROUTE_TIMEOUT_SECS：默认超时秒数，默认 8，可带小数；0 表示不限制
+// - ROUTE_TIMEOUTS：按路由覆盖，逗号分隔的 `路由=秒数`，路由不含 /api、/api/vN 前缀，如 `/weather=3,/location/search=1.5`
+
+use std::{collections::HashMap, time::Duration};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use once_cell::sync::OnceCell;
+use tokio::time::Instant;
+
+use crate::{
+    config::{env_list, env_parse},
+    respond, ErrorResp,
+};
+
+pub const CODE: &str = "UPSTREAM_TIMEOUT";
+
+// 留给自行处理超时的 handler（如天气回退缓存）先返回的余量
+const GRACE: Duration = Duration::from_millis(100);
+
+struct Timeouts {
+    default: Option<Duration>,
+    routes: HashMap<String, Option<Duration>>,
+}
+
+static TIMEOUTS: OnceCell<Timeouts> = OnceCell::new();
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+fn secs(key: &str, raw: f64) -> anyhow::Result<Option<Duration>> {
+    if !raw.is_finite() || raw < 0.0 {
+        anyhow::bail!("{} 应为非负秒数", key);
+    }
+    Ok((raw > 0.0).then(|| Duration::from_secs_f64(raw)))
+}
+
+// 启动时读取并校验
+pub fn init_from_env() -> anyhow::Result<()> {
+    let default = secs("ROUTE_TIMEOUT_SECS", env_parse("ROUTE_TIMEOUT_SECS")?.unwrap_or(8.0))?;
+    let mut routes = HashMap::new();
+    for item in env_list("ROUTE_TIMEOUTS").unwrap_or_default() {
+        let parsed = item
+            .split_once('=')
+            .and_then(|(route, v)| Some((route.trim(), v.trim().parse::<f64>().ok()?)))
+            .filter(|(route, _)| route.starts_with('/'));
+        let Some((route, v)) = parsed else {
+            anyhow::bail!("无法解析 ROUTE_TIMEOUTS 中的项: {}", item);
+        };
+        routes.insert(route.to_string(), secs("ROUTE_TIMEOUTS", v)?);
+    }
+    let _ = TIMEOUTS.set(Timeouts { default, routes });
+    Ok(())
+}
+
+// 去掉 /api/vN、/api 前缀，使各版本与旧路径别名共用一项配置
+pub fn route_key(path: &str) -> &str {
+    let Some(rest) = path.strip_prefix("/api") else {
+        return path;
+    };
+    if let Some(after) = rest.strip_prefix("/v") {
+        let digits = after.bytes().take_while(u8::is_ascii_digit).count();
+        if digits > 0 && after[digits..].starts_with('/') {
+            return &after[digits..];
+        }
+    }
+    rest
+}
+
+fn timeout_for(path: &str) -> Option<Duration> {
+    let timeouts = TIMEOUTS.get()?;
+    match timeouts.routes.get(route_key(path)) {
+        Some(t) => *t,
+        None => timeouts.default,
+    }
+}
+
+pub fn timeout_response() -> Response {
+    respond::json(
+        StatusCode::GATEWAY_TIMEOUT,
+        &ErrorResp::with_code(CODE, "上游响应超时，请稍后重试"),
+    )
+}
+
+// 在当前请求的截止时间内等待 fut；超时返回 None。请求不受超时限制时直接等待
+pub async fn within<F: std::future::Future>(fut: F) -> Option<F::Output> {
+    match DEADLINE.try_with(|d| *d) {
+        Ok(deadline) => tokio::time::timeout_at(deadline, fut).await.ok(),
+        Err(_) => Some(fut.await),
+    }
+}
+
+pub async fn middleware(req: Request, next: Next) -> Response {
+    let path = req.extensions().get::<MatchedPath>().map(|p| p.as_str().to_string());
+    let Some(timeout) = path.as_deref().and_then(timeout_for) else {
+        return next.run(req).await;
+    };
+    let deadline = Instant::now() + timeout;
+    match tokio::time::timeout_at(deadline + GRACE, DEADLINE.scope(deadline, next.run(req))).await {
+        Ok(res) => res,
+        Err(_) => {
+            tracing::warn!(route = path.as_deref().unwrap_or(""), ?timeout, "request timed out");
+            timeout_response()
+        }
+    }
+}
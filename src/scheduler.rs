@@ -0,0 +1,179 @@
+// 定时任务：具名任务 + cron 表达式（服务器本地时区），同一任务上一次未结束时跳过本次触发
+// - SCHEDULE_<任务名大写>：覆盖默认表达式，如 SCHEDULE_MQTT_PUBLISH="*/5 * * * *"；设为 off 禁用该任务
+// - 表达式为 5 段（分 时 日 月 周）或带秒的 6 段
+// 运行状态通过管理接口 /api/v1/admin/jobs 查看；任务 panic 记为一次失败，不影响之后的触发
+
+use std::{
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use chrono::{DateTime, Local};
+use cron::Schedule;
+use futures_util::FutureExt;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tracing::{info, warn, Instrument};
+
+use crate::{config::env_nonempty, upstream_budget};
+
+type JobFuture = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+type JobFn = Box<dyn Fn() -> JobFuture + Send + Sync>;
+
+#[derive(Serialize, Clone, Default)]
+pub struct JobStatus {
+    name: &'static str,
+    schedule: String,
+    running: bool,
+    next_run: Option<DateTime<Local>>,
+    last_started: Option<DateTime<Local>>,
+    last_duration_ms: Option<u64>,
+    last_error: Option<String>,
+    runs: u64,
+    failures: u64,
+    // 因上一次仍在运行而跳过的次数
+    skipped: u64,
+}
+
+struct Job {
+    run: JobFn,
+    status: Mutex<JobStatus>,
+}
+
+static JOBS: Lazy<Mutex<Vec<Arc<Job>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+fn parse_schedule(expr: &str) -> anyhow::Result<Schedule> {
+    // cron crate 需要秒字段；5 段表达式补 0 秒
+    let full = if expr.split_whitespace().count() == 5 {
+        format!("0 {}", expr)
+    } else {
+        expr.to_string()
+    };
+    Schedule::from_str(&full).map_err(|e| anyhow::anyhow!("无法解析 cron 表达式 {}: {}", expr, e))
+}
+
+// 注册任务并开始调度；表达式可被 SCHEDULE_<NAME> 覆盖
+pub fn register<F, Fut>(name: &'static str, default_expr: &str, f: F) -> anyhow::Result<()>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let env_key = format!("SCHEDULE_{}", name.to_ascii_uppercase());
+    let expr = env_nonempty(&env_key).unwrap_or_else(|| default_expr.to_string());
+    if expr.eq_ignore_ascii_case("off") {
+        info!(job = name, "job disabled by {}", env_key);
+        return Ok(());
+    }
+    let schedule = parse_schedule(&expr)?;
+
+    let job = Arc::new(Job {
+        run: Box::new(move || Box::pin(f())),
+        status: Mutex::new(JobStatus { name, schedule: expr.clone(), ..Default::default() }),
+    });
+    JOBS.lock().unwrap().push(job.clone());
+    info!(job = name, schedule = %expr, "job registered");
+
+    tokio::spawn(async move {
+        loop {
+            let Some(next) = schedule.upcoming(Local).next() else {
+                warn!(job = name, "cron 表达式没有后续触发时间，停止调度");
+                return;
+            };
+            job.status.lock().unwrap().next_run = Some(next);
+            let wait = (next - Local::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(wait).await;
+            trigger(&job);
+        }
+    });
+    Ok(())
+}
+
+// 立即执行一次，返回执行任务的句柄；若任务仍在运行则记为跳过并返回 None
+fn trigger(job: &Arc<Job>) -> Option<tokio::task::JoinHandle<JobStatus>> {
+    let name = {
+        let mut status = job.status.lock().unwrap();
+        if status.running {
+            status.skipped += 1;
+            warn!(job = status.name, "previous run still in progress, skipped");
+            return None;
+        }
+        status.running = true;
+        status.last_started = Some(Local::now());
+        status.name
+    };
+    let job = job.clone();
+    let handle = tokio::spawn(
+        async move {
+            let started = Instant::now();
+            // 任务中的上游请求按后台优先级排队，不挤占用户请求；panic 也要清除 running，否则之后每次都会被跳过
+            let run = upstream_budget::background(async { (job.run)().await });
+            let result = AssertUnwindSafe(run).catch_unwind().await.unwrap_or_else(|panic| {
+                let msg = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                Err(anyhow::anyhow!("任务 panic: {}", msg))
+            });
+            let mut status = job.status.lock().unwrap();
+            status.running = false;
+            status.runs += 1;
+            status.last_duration_ms = Some(started.elapsed().as_millis() as u64);
+            match result {
+                Ok(()) => status.last_error = None,
+                Err(e) => {
+                    warn!("job failed: {}", e);
+                    status.failures += 1;
+                    status.last_error = Some(e.to_string());
+                }
+            }
+            status.clone()
+        }
+        .instrument(tracing::info_span!("job", name)),
+    );
+    Some(handle)
+}
+
+pub fn statuses() -> Vec<JobStatus> {
+    JOBS.lock()
+        .unwrap()
+        .iter()
+        .map(|job| job.status.lock().unwrap().clone())
+        .collect()
+}
+
+// 手动触发；任务不存在返回 None，正在运行时为 Some(None)
+pub fn run_now(name: &str) -> Option<Option<tokio::task::JoinHandle<JobStatus>>> {
+    let job = JOBS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|job| job.status.lock().unwrap().name == name)
+        .cloned()?;
+    Some(trigger(&job))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::{trigger, Job, JobStatus};
+
+    #[tokio::test]
+    async fn panicking_job_does_not_stay_running() {
+        let job = Arc::new(Job {
+            run: Box::new(|| Box::pin(async { panic!("boom") })),
+            status: Mutex::new(JobStatus { name: "panics", ..Default::default() }),
+        });
+        for runs in 1..=2 {
+            let status = trigger(&job).expect("上一次已结束，不应跳过").await.unwrap();
+            assert!(!status.running);
+            assert_eq!((status.runs, status.failures, status.skipped), (runs, runs, 0));
+            assert!(status.last_error.unwrap().contains("boom"));
+        }
+    }
+}
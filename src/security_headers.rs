@@ -0,0 +1,78 @@
+// 安全响应头：仅作用于 HTML/静态资源路由，JSON API 不受影响
+// - SECURITY_HEADERS=0：整体关闭
+// - CONTENT_SECURITY_POLICY：覆盖默认 CSP（默认兼容首页的内联脚本与 Google Fonts）
+// - REFERRER_POLICY：默认 strict-origin-when-cross-origin
+// - HSTS_MAX_AGE：设置后输出 Strict-Transport-Security（仅应在 HTTPS 部署下开启）
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::config::{env_nonempty, env_parse};
+
+const DEFAULT_CSP: &str = "default-src 'self'; \
+    script-src 'self' 'unsafe-inline'; \
+    style-src 'self' 'unsafe-inline' https://fonts.googleapis.com; \
+    font-src 'self' https://fonts.gstatic.com; \
+    img-src 'self' data:; \
+    connect-src 'self'; \
+    manifest-src 'self'; \
+    worker-src 'self'; \
+    base-uri 'self'; \
+    frame-ancestors 'none'";
+
+#[derive(Default)]
+pub struct SecurityHeaders {
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl SecurityHeaders {
+    pub fn from_env() -> anyhow::Result<Self> {
+        if env_nonempty("SECURITY_HEADERS").as_deref() == Some("0") {
+            return Ok(Self::default());
+        }
+        let value = |key: &str, v: String| {
+            HeaderValue::from_str(&v).map_err(|_| anyhow::anyhow!("{} 含非法字符", key))
+        };
+        let mut headers = vec![
+            (
+                header::CONTENT_SECURITY_POLICY,
+                value(
+                    "CONTENT_SECURITY_POLICY",
+                    env_nonempty("CONTENT_SECURITY_POLICY").unwrap_or_else(|| DEFAULT_CSP.to_string()),
+                )?,
+            ),
+            (
+                header::REFERRER_POLICY,
+                value(
+                    "REFERRER_POLICY",
+                    env_nonempty("REFERRER_POLICY").unwrap_or_else(|| "strict-origin-when-cross-origin".into()),
+                )?,
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
+        ];
+        if let Some(max_age) = env_parse::<u64>("HSTS_MAX_AGE")? {
+            headers.push((
+                header::STRICT_TRANSPORT_SECURITY,
+                value("HSTS_MAX_AGE", format!("max-age={}; includeSubDomains", max_age))?,
+            ));
+        }
+        Ok(Self { headers })
+    }
+}
+
+pub async fn middleware(State(cfg): State<Arc<SecurityHeaders>>, req: Request, next: Next) -> Response {
+    let mut resp = next.run(req).await;
+    let headers = resp.headers_mut();
+    for (name, value) in &cfg.headers {
+        if !headers.contains_key(name) {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+    resp
+}
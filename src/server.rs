@@ -0,0 +1,442 @@
+// 监听与服务：默认 HTTP over TCP；可选 HTTPS（需 `tls` feature）
+// - LISTEN：监听地址，`host:port`（默认由 HOST/PORT 组成）、`unix:/run/caiyun.sock` 或 `systemd`（继承 socket 激活传入的 fd）
+//   未设置 LISTEN 但进程由 systemd socket 激活启动时自动使用继承的 socket
+// - TLS_CERT/TLS_KEY：使用 PEM 证书文件
+// - ACME_DOMAIN：通过 Let's Encrypt（TLS-ALPN-01）自动签发与续期
+// - HTTP_REDIRECT_PORT：启用 HTTPS 时额外监听该端口，将 HTTP 请求 308 跳转到 HTTPS
+// - H2C=1：明文监听（TCP/unix/systemd）同时接受 HTTP/2 prior knowledge（h2c），供无反代的 gRPC-Web/内网客户端复用连接
+// - HTTP3_PORT：额外在该 UDP 端口提供实验性 HTTP/3（需 `http3` feature 与 TLS_CERT/TLS_KEY），HTTPS 响应附带 Alt-Svc
+// 收到 SIGTERM/SIGINT 后停止接受新连接，等待进行中的请求处理完再返回，main 随后正常退出（flush 日志与链路数据）
+// - SHUTDOWN_TIMEOUT：等待进行中连接的最长秒数，默认 30，超时后不再等待
+
+use std::{net::SocketAddr, time::Duration};
+
+use axum::Router;
+use tokio::sync::watch;
+use tracing::info;
+
+use crate::config::{env_flag, env_list, env_nonempty, env_parse};
+
+enum Listen {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(std::path::PathBuf),
+    #[cfg(unix)]
+    Systemd,
+}
+
+#[cfg_attr(not(feature = "tls"), allow(dead_code))]
+enum Tls {
+    Off,
+    Files { cert: String, key: String },
+    Acme {
+        domains: Vec<String>,
+        contacts: Vec<String>,
+        cache_dir: String,
+        production: bool,
+    },
+}
+
+fn listen_from_env(default_addr: SocketAddr) -> anyhow::Result<Listen> {
+    let Some(listen) = env_nonempty("LISTEN") else {
+        #[cfg(unix)]
+        if systemd_fds() > 0 {
+            return Ok(Listen::Systemd);
+        }
+        return Ok(Listen::Tcp(default_addr));
+    };
+    #[cfg(unix)]
+    {
+        if let Some(path) = listen.strip_prefix("unix:") {
+            return Ok(Listen::Unix(path.into()));
+        }
+        if listen == "systemd" {
+            return Ok(Listen::Systemd);
+        }
+    }
+    listen
+        .parse()
+        .map(Listen::Tcp)
+        .map_err(|_| anyhow::anyhow!("无法解析 LISTEN={}", listen))
+}
+
+fn tls_from_env() -> anyhow::Result<Tls> {
+    if let Some(domains) = env_list("ACME_DOMAIN") {
+        return Ok(Tls::Acme {
+            domains,
+            contacts: env_list("ACME_EMAIL")
+                .unwrap_or_default()
+                .into_iter()
+                .map(|e| format!("mailto:{}", e))
+                .collect(),
+            cache_dir: env_nonempty("ACME_CACHE_DIR").unwrap_or_else(|| "acme-cache".into()),
+            // 默认使用 Let's Encrypt 正式环境；ACME_STAGING=1 时用测试环境，避免调试时触发频率限制
+            production: !env_flag("ACME_STAGING"),
+        });
+    }
+    match (env_nonempty("TLS_CERT"), env_nonempty("TLS_KEY")) {
+        (Some(cert), Some(key)) => Ok(Tls::Files { cert, key }),
+        (None, None) => Ok(Tls::Off),
+        _ => anyhow::bail!("TLS_CERT 与 TLS_KEY 需同时设置"),
+    }
+}
+
+pub async fn serve(app: Router, addr: SocketAddr) -> anyhow::Result<()> {
+    if env_nonempty("HTTP3_PORT").is_some() {
+        #[cfg(not(feature = "http3"))]
+        tracing::warn!("已设置 HTTP3_PORT，但未启用 `http3` feature");
+        #[cfg(feature = "http3")]
+        if !matches!(tls_from_env()?, Tls::Files { .. }) {
+            anyhow::bail!("HTTP3_PORT 需配合证书文件 TLS_CERT/TLS_KEY 使用");
+        }
+    }
+    match (listen_from_env(addr)?, tls_from_env()?) {
+        (Listen::Tcp(addr), Tls::Off) => {
+            info!("listening on http://{}{}", addr, if h2c() { " (h2c)" } else { "" });
+            serve_tcp(tokio::net::TcpListener::bind(addr).await?, app).await
+        }
+        #[cfg(feature = "tls")]
+        (Listen::Tcp(addr), tls) => serve_tls(app, addr, tls).await,
+        #[cfg(not(feature = "tls"))]
+        (Listen::Tcp(_), _) => anyhow::bail!("已配置 TLS_CERT/TLS_KEY 或 ACME_DOMAIN，但未启用 `tls` feature"),
+        #[cfg(unix)]
+        (Listen::Unix(path), Tls::Off) => {
+            // 清理上次退出残留的 socket 文件
+            if std::fs::symlink_metadata(&path).is_ok() {
+                std::fs::remove_file(&path)?;
+            }
+            let listener = tokio::net::UnixListener::bind(&path)?;
+            // LISTEN_MODE=660 等（八进制），便于反代进程访问
+            if let Some(mode) = env_nonempty("LISTEN_MODE") {
+                use std::os::unix::fs::PermissionsExt;
+                let mode = u32::from_str_radix(&mode, 8)
+                    .map_err(|_| anyhow::anyhow!("无法解析 LISTEN_MODE={}", mode))?;
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))?;
+            }
+            info!("listening on unix:{}", path.display());
+            serve_unix(listener, app).await
+        }
+        #[cfg(unix)]
+        (Listen::Systemd, Tls::Off) => match take_systemd_listener()? {
+            Inherited::Tcp(l) => {
+                info!("listening on inherited tcp socket {}", l.local_addr()?);
+                serve_tcp(tokio::net::TcpListener::from_std(l)?, app).await
+            }
+            Inherited::Unix(l) => {
+                info!("listening on inherited unix socket");
+                serve_unix(tokio::net::UnixListener::from_std(l)?, app).await
+            }
+        },
+        #[cfg(unix)]
+        _ => anyhow::bail!("HTTPS 仅支持 TCP 监听（LISTEN=host:port）"),
+    }
+}
+
+// systemd socket 激活传入的 fd 数量（LISTEN_PID 需为本进程）
+#[cfg(unix)]
+fn systemd_fds() -> usize {
+    let for_us = env_parse::<u32>("LISTEN_PID")
+        .ok()
+        .flatten()
+        .is_some_and(|pid| pid == std::process::id());
+    if !for_us {
+        return 0;
+    }
+    env_parse("LISTEN_FDS").ok().flatten().unwrap_or(0)
+}
+
+#[cfg(unix)]
+enum Inherited {
+    Tcp(std::net::TcpListener),
+    Unix(std::os::unix::net::UnixListener),
+}
+
+// 取 sd_listen_fds 的第一个 fd（SD_LISTEN_FDS_START = 3）
+#[cfg(unix)]
+fn take_systemd_listener() -> anyhow::Result<Inherited> {
+    use std::os::fd::{FromRawFd, IntoRawFd};
+
+    if systemd_fds() == 0 {
+        anyhow::bail!("LISTEN=systemd，但未收到 systemd 传入的 socket（LISTEN_FDS/LISTEN_PID）");
+    }
+    // 避免子进程误继承
+    std::env::remove_var("LISTEN_FDS");
+    std::env::remove_var("LISTEN_PID");
+
+    // SAFETY: fd 3 由 systemd 按 sd_listen_fds 协议传入且仅在此处接管一次
+    let tcp = unsafe { std::net::TcpListener::from_raw_fd(3) };
+    if tcp.local_addr().is_ok() {
+        tcp.set_nonblocking(true)?;
+        return Ok(Inherited::Tcp(tcp));
+    }
+    // 非 inet 地址族，按 unix socket 处理
+    let unix = unsafe { std::os::unix::net::UnixListener::from_raw_fd(tcp.into_raw_fd()) };
+    unix.set_nonblocking(true)?;
+    Ok(Inherited::Unix(unix))
+}
+
+fn h2c() -> bool {
+    env_flag("H2C")
+}
+
+fn shutdown_timeout() -> Duration {
+    let secs = env_parse::<u64>("SHUTDOWN_TIMEOUT").ok().flatten().unwrap_or(30);
+    Duration::from_secs(secs)
+}
+
+// SIGTERM（systemd、容器停止）或 SIGINT（Ctrl-C）
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("无法监听 SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("无法监听 SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+    info!("shutdown signal received, draining connections");
+}
+
+// 明文监听的连接：每个连接持有一个 watch::Receiver，退出时通知连接关闭并等待所有 Receiver 释放
+// http1 的 UpgradeableConnection 不在 hyper_util GracefulShutdown 支持之列，因此直接按同样的方式实现
+struct Connections {
+    notify: watch::Sender<bool>,
+    watcher: watch::Receiver<bool>,
+}
+
+impl Connections {
+    fn new() -> Self {
+        let (notify, watcher) = watch::channel(false);
+        Self { notify, watcher }
+    }
+
+    // 明文连接：默认仅 HTTP/1.1，H2C=1 时按连接前言自动识别 HTTP/2
+    // TCP 连接的对端地址以 ConnectInfo<SocketAddr> 放入请求扩展；Unix 套接字没有对端地址
+    fn spawn<I>(&self, io: I, peer: Option<SocketAddr>, app: Router)
+    where
+        I: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+    {
+        use hyper_util::{
+            rt::{TokioExecutor, TokioIo},
+            server::conn::auto,
+        };
+
+        let mut shutdown = self.watcher.clone();
+        tokio::spawn(async move {
+            let service = hyper::service::service_fn(move |mut req: axum::http::Request<hyper::body::Incoming>| {
+                if let Some(peer) = peer {
+                    req.extensions_mut().insert(axum::extract::ConnectInfo(peer));
+                }
+                tower::Service::call(&mut app.clone(), req)
+            });
+            let io = TokioIo::new(io);
+            // 收到退出通知后完成当前请求再关闭（HTTP/1.1 关闭 keep-alive，HTTP/2 发送 GOAWAY）
+            let result = if h2c() {
+                let builder = auto::Builder::new(TokioExecutor::new());
+                let conn = builder.serve_connection_with_upgrades(io, service);
+                tokio::pin!(conn);
+                tokio::select! {
+                    result = conn.as_mut() => result,
+                    _ = shutdown.changed() => {
+                        conn.as_mut().graceful_shutdown();
+                        conn.await
+                    }
+                }
+            } else {
+                // auto::Builder 在支持 upgrade 时会忽略 http1_only，因此直接使用 http1 连接
+                let conn = hyper::server::conn::http1::Builder::new().serve_connection(io, service).with_upgrades();
+                tokio::pin!(conn);
+                let result = tokio::select! {
+                    result = conn.as_mut() => result,
+                    _ = shutdown.changed() => {
+                        conn.as_mut().graceful_shutdown();
+                        conn.await
+                    }
+                };
+                result.map_err(Into::into)
+            };
+            if let Err(e) = result {
+                tracing::debug!("connection error: {}", e);
+            }
+        });
+    }
+
+    // 通知所有连接关闭，最多等待 SHUTDOWN_TIMEOUT
+    async fn drain(self) {
+        let Self { notify, watcher } = self;
+        drop(watcher);
+        let _ = notify.send(true);
+        let timeout = shutdown_timeout();
+        if tokio::time::timeout(timeout, notify.closed()).await.is_err() {
+            tracing::warn!("{} connection(s) still open after {:?}, exiting", notify.receiver_count(), timeout);
+        }
+    }
+}
+
+// 文件描述符耗尽、对端中止等 accept 错误是临时的：记录后稍后重试，不退出
+async fn accept_failed(e: std::io::Error) {
+    tracing::error!("accept failed: {}", e);
+    tokio::time::sleep(Duration::from_secs(1)).await;
+}
+
+async fn serve_tcp(listener: tokio::net::TcpListener, app: Router) -> anyhow::Result<()> {
+    let connections = Connections::new();
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = &mut shutdown => break,
+        };
+        match accepted {
+            Ok((socket, peer)) => {
+                let _ = socket.set_nodelay(true);
+                connections.spawn(socket, Some(peer), app.clone());
+            }
+            Err(e) => accept_failed(e).await,
+        }
+    }
+    drop(listener);
+    connections.drain().await;
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn serve_unix(listener: tokio::net::UnixListener, app: Router) -> anyhow::Result<()> {
+    let connections = Connections::new();
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = &mut shutdown => break,
+        };
+        match accepted {
+            Ok((socket, _)) => connections.spawn(socket, None, app.clone()),
+            Err(e) => accept_failed(e).await,
+        }
+    }
+    drop(listener);
+    connections.drain().await;
+    Ok(())
+}
+
+#[cfg(feature = "tls")]
+async fn serve_tls(app: Router, addr: SocketAddr, tls: Tls) -> anyhow::Result<()> {
+    use futures_util::StreamExt;
+
+    // reqwest 与 ACME 均基于 ring，统一安装为进程默认实现
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    if let Some(port) = env_parse::<u16>("HTTP_REDIRECT_PORT")? {
+        let redirect_addr = SocketAddr::new(addr.ip(), port);
+        tokio::spawn(async move {
+            if let Err(e) = serve_https_redirect(redirect_addr, addr.port()).await {
+                tracing::error!("http redirect listener failed: {}", e);
+            }
+        });
+    }
+
+    // 收到退出信号后停止 accept，等待进行中的请求（最多 SHUTDOWN_TIMEOUT）
+    let handle = axum_server::Handle::new();
+    tokio::spawn({
+        let handle = handle.clone();
+        async move {
+            shutdown_signal().await;
+            handle.graceful_shutdown(Some(shutdown_timeout()));
+        }
+    });
+
+    match tls {
+        Tls::Files { cert, key } => {
+            #[cfg(feature = "http3")]
+            let app = match env_parse::<u16>("HTTP3_PORT")? {
+                Some(port) => {
+                    crate::http3::spawn(app.clone(), SocketAddr::new(addr.ip(), port), &cert, &key)?;
+                    // 告知浏览器可切换到 HTTP/3
+                    let alt_svc = axum::http::HeaderValue::from_str(&format!("h3=\":{}\"; ma=86400", port))?;
+                    app.layer(tower_http::set_header::SetResponseHeaderLayer::if_not_present(
+                        axum::http::header::ALT_SVC,
+                        alt_svc,
+                    ))
+                }
+                None => app,
+            };
+            let config = axum_server::tls_rustls::RustlsConfig::from_pem_file(&cert, &key).await?;
+            info!("listening on https://{} (cert: {})", addr, cert);
+            axum_server::bind_rustls(addr, config)
+                .handle(handle)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>()).await?;
+        }
+        Tls::Acme { domains, contacts, cache_dir, production } => {
+            let mut state = rustls_acme::AcmeConfig::new(&domains)
+                .contact(contacts)
+                .cache(rustls_acme::caches::DirCache::new(cache_dir))
+                .directory_lets_encrypt(production)
+                .state();
+            let acceptor = state.axum_acceptor(state.default_rustls_config());
+            // 驱动证书签发/续期
+            tokio::spawn(async move {
+                while let Some(event) = state.next().await {
+                    match event {
+                        Ok(ok) => info!("acme: {:?}", ok),
+                        Err(err) => tracing::warn!("acme error: {}", err),
+                    }
+                }
+            });
+            info!("listening on https://{} (acme: {})", addr, domains.join(","));
+            axum_server::bind(addr)
+                .acceptor(acceptor)
+                .handle(handle)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>()).await?;
+        }
+        Tls::Off => unreachable!("serve_tls 仅处理 TLS 模式"),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "tls")]
+async fn serve_https_redirect(addr: SocketAddr, https_port: u16) -> anyhow::Result<()> {
+    use axum::{
+        http::{header, StatusCode, Uri},
+        response::{IntoResponse, Redirect},
+    };
+
+    let redirect = move |headers: axum::http::HeaderMap, uri: Uri| async move {
+        let host = headers
+            .get(header::HOST)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|h| h.parse::<axum::http::uri::Authority>().ok());
+        let Some(host) = host else {
+            return StatusCode::BAD_REQUEST.into_response();
+        };
+        let authority = if https_port == 443 {
+            host.host().to_string()
+        } else {
+            format!("{}:{}", host.host(), https_port)
+        };
+        let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+        Redirect::permanent(&format!("https://{}{}", authority, path)).into_response()
+    };
+
+    info!("redirecting http://{} to https", addr);
+    let app = Router::new().fallback(redirect);
+    axum::serve(tokio::net::TcpListener::bind(addr).await?, app).await?;
+    Ok(())
+}
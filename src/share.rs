@@ -0,0 +1,326 @@
+// 分享短链接：为地点（经纬度 + 名称）生成短码，/s/<短码> 跳转到带 ?lng&lat&name 的首页（同时带上分享卡片 meta），便于用短信等发送
+// 同一地点（坐标按 4 位小数、名称相同）重复创建时返回已有短码；同一客户端每小时最多新建 CREATES_PER_CLIENT 个
+// - SHARE_LINKS：1 开启
+// - SHARE_DB：数据库路径，默认 DATA_DIR/share.sqlite3
+// - SHARE_RETENTION_DAYS：短链接保留天数，创建超过该天数的由 share_prune 清理，默认 365
+// /api/v1/share/<短码>/qr.png 为指向短链接的二维码，供分享对话框直接展示
+// 短链接地址基于 PUBLIC_URL（或按主机名识别的租户的 Host），不信任请求中的任意 Host；无法确定时不返回 url，二维码返回 503
+
+use std::{net::SocketAddr, sync::Mutex, time::Duration};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum::{
+    body::Bytes,
+    extract::{ConnectInfo, Path, Query},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use once_cell::sync::{Lazy, OnceCell};
+use qrcode::{Color, QrCode};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{
+    base_path,
+    cache::TtlCache,
+    client_ip,
+    config::{env_flag, env_nonempty, env_parse},
+    db_error, fallback, og_image, respond, scheduler, ErrorResp,
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS links (
+        code TEXT PRIMARY KEY,
+        lng REAL NOT NULL,
+        lat REAL NOT NULL,
+        name TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        UNIQUE (lng, lat, name)
+    );
+";
+
+const ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const CODE_LEN: usize = 7;
+const MAX_NAME_CHARS: usize = 50;
+// 二维码四周留白的模块数（规范要求至少 4）
+const QUIET_ZONE: usize = 4;
+// 每小时同一客户端 IP 最多新建的短链接数（取回已有短码不计）
+const CREATES_PER_CLIENT: u32 = 30;
+
+// 新建短链接计数，键为客户端 IP
+static CREATES: Lazy<TtlCache<String, u32>> = Lazy::new(|| TtlCache::new(Duration::from_secs(3600), 10_000));
+
+// 键：短链接地址 + 每模块像素数
+static QR_CODES: Lazy<TtlCache<(String, usize), Bytes>> =
+    Lazy::new(|| TtlCache::new(Duration::from_secs(3600), 256));
+
+struct Store {
+    conn: Mutex<Connection>,
+    retention_days: i64,
+}
+
+static STORE: OnceCell<Store> = OnceCell::new();
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    if !env_flag("SHARE_LINKS") {
+        return Ok(());
+    }
+    let retention_days = env_parse::<i64>("SHARE_RETENTION_DAYS")?.unwrap_or(365);
+    if retention_days <= 0 {
+        anyhow::bail!("SHARE_RETENTION_DAYS 应为正整数");
+    }
+    let path = env_nonempty("SHARE_DB").unwrap_or_else(|| {
+        let dir = env_nonempty("DATA_DIR").unwrap_or_else(|| "data".into());
+        format!("{}/share.sqlite3", dir.trim_end_matches('/'))
+    });
+    if let Some(dir) = std::path::Path::new(&path).parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let conn = Connection::open(&path).map_err(|e| anyhow::anyhow!("无法打开 {}: {}", path, e))?;
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
+    conn.execute_batch(SCHEMA)?;
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM links", [], |r| r.get(0))?;
+    info!("share links enabled ({} link(s))", count);
+    let _ = STORE.set(Store { conn: Mutex::new(conn), retention_days });
+    scheduler::register("share_prune", "40 3 * * *", || async {
+        let Some(store) = STORE.get() else { return Ok(()) };
+        let cutoff = Utc::now().timestamp() - store.retention_days * 86_400;
+        let removed = store.conn.lock().unwrap().execute("DELETE FROM links WHERE created_at < ?1", [cutoff])?;
+        if removed > 0 {
+            info!("pruned {} expired share link(s)", removed);
+        }
+        Ok(())
+    })
+}
+
+fn disabled_response() -> Response {
+    respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("未开启分享短链接（SHARE_LINKS=1）"))
+}
+
+fn random_code() -> String {
+    (0..CODE_LEN).map(|_| ALPHABET[OsRng.next_u32() as usize % ALPHABET.len()] as char).collect()
+}
+
+fn round4(v: f64) -> f64 {
+    (v * 10_000.0).round() / 10_000.0
+}
+
+#[derive(Serialize)]
+struct Link {
+    code: String,
+    lng: f64,
+    lat: f64,
+    name: String,
+    created_at: String,
+}
+
+fn find(conn: &Connection, code: &str) -> rusqlite::Result<Option<Link>> {
+    conn.query_row("SELECT code, lng, lat, name, created_at FROM links WHERE code = ?1", [code], |r| {
+        Ok(Link {
+            code: r.get(0)?,
+            lng: r.get(1)?,
+            lat: r.get(2)?,
+            name: r.get(3)?,
+            created_at: DateTime::from_timestamp(r.get(4)?, 0).unwrap_or_default().to_rfc3339(),
+        })
+    })
+    .optional()
+}
+
+pub fn enabled() -> bool {
+    STORE.get().is_some()
+}
+
+// 短码只含字母数字，其他输入直接视为不存在
+fn lookup(code: &str) -> rusqlite::Result<Option<Link>> {
+    let Some(store) = STORE.get() else {
+        return Ok(None);
+    };
+    if code.len() != CODE_LEN || !code.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return Ok(None);
+    }
+    find(&store.conn.lock().unwrap(), code)
+}
+
+#[derive(Deserialize)]
+pub struct CreateBody {
+    lng: f64,
+    lat: f64,
+    #[serde(default)]
+    name: String,
+}
+
+// POST /api/v1/share：创建（或取回已有的）短链接，返回 { code, url, lng, lat, name, created_at }
+pub async fn create(
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(body): Json<CreateBody>,
+) -> Response {
+    let Some(store) = STORE.get() else {
+        return disabled_response();
+    };
+    if !(-180.0..=180.0).contains(&body.lng) || !(-90.0..=90.0).contains(&body.lat) {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new("经纬度超出范围"));
+    }
+    let name = body.name.trim();
+    if name.chars().count() > MAX_NAME_CHARS {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(format!("name 不能超过 {} 个字符", MAX_NAME_CHARS)));
+    }
+    let (lng, lat) = (round4(body.lng), round4(body.lat));
+    let conn = store.conn.lock().unwrap();
+    let existing = conn
+        .query_row("SELECT code FROM links WHERE lng = ?1 AND lat = ?2 AND name = ?3", params![lng, lat, name], |r| {
+            r.get::<_, String>(0)
+        })
+        .optional();
+    let (status, code) = match existing {
+        Ok(Some(code)) => (StatusCode::OK, code),
+        Ok(None) => {
+            if let Some(ip) = client_ip::from_request(&headers, connect_info.map(|c| c.0)) {
+                let (count, reset) = CREATES.bump(ip.to_string());
+                if count > CREATES_PER_CLIENT {
+                    let mut resp = respond::json(
+                        StatusCode::TOO_MANY_REQUESTS,
+                        &ErrorResp::with_code("RATE_LIMITED", "创建短链接过于频繁，请稍后重试"),
+                    );
+                    resp.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(reset.as_secs().max(1)));
+                    return resp;
+                }
+            }
+            // 短码冲突时换一个重试
+            let mut inserted = None;
+            for _ in 0..5 {
+                let code = random_code();
+                match conn.execute(
+                    "INSERT OR IGNORE INTO links (code, lng, lat, name, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![code, lng, lat, name, Utc::now().timestamp()],
+                ) {
+                    Ok(1) => {
+                        inserted = Some(code);
+                        break;
+                    }
+                    Ok(_) => continue,
+                    Err(e) => return db_error(e),
+                }
+            }
+            match inserted {
+                Some(code) => (StatusCode::CREATED, code),
+                None => return respond::json(StatusCode::SERVICE_UNAVAILABLE, &ErrorResp::new("生成短码失败，请重试")),
+            }
+        }
+        Err(e) => return db_error(e),
+    };
+    match find(&conn, &code) {
+        Ok(Some(link)) => {
+            let url = og_image::public_base(&headers).map(|base| format!("{}/s/{}", base, link.code));
+            let mut value = json!(link);
+            value["url"] = json!(url);
+            respond::json(status, &value)
+        }
+        Ok(None) => db_error(rusqlite::Error::QueryReturnedNoRows),
+        Err(e) => db_error(e),
+    }
+}
+
+// GET /api/v1/share/<短码>：解析短链接
+pub async fn resolve(Path(code): Path<String>) -> Response {
+    if STORE.get().is_none() {
+        return disabled_response();
+    }
+    match lookup(&code) {
+        Ok(Some(link)) => respond::json(StatusCode::OK, &link),
+        Ok(None) => respond::json(StatusCode::NOT_FOUND, &ErrorResp::with_code("NOT_FOUND", "短链接不存在")),
+        Err(e) => db_error(e),
+    }
+}
+
+// GET /s/<短码>：跳转到首页；用相对地址，路径前缀租户下同样落在 /前缀/
+pub async fn redirect(Path(code): Path<String>, method: Method, uri: Uri, headers: HeaderMap) -> Response {
+    match lookup(&code) {
+        Ok(Some(link)) => {
+            let mut target = format!("../?lng={}&lat={}", link.lng, link.lat);
+            if !link.name.is_empty() {
+                target.push_str(&format!("&name={}", urlencoding::encode(&link.name)));
+            }
+            Redirect::to(&target).into_response()
+        }
+        Ok(None) => fallback::handler(method, uri, headers).await,
+        Err(e) => db_error(e),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct QrQuery {
+    scale: Option<usize>,
+}
+
+// 黑白灰度 PNG，每个模块 scale×scale 像素
+fn render_qr(url: &str, scale: usize) -> anyhow::Result<Vec<u8>> {
+    let code = QrCode::new(url.as_bytes())?;
+    let modules = code.width();
+    let colors = code.to_colors();
+    let size = (modules + QUIET_ZONE * 2) * scale;
+    let mut pixels = vec![255u8; size * size];
+    for (i, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let (x, y) = ((i % modules + QUIET_ZONE) * scale, (i / modules + QUIET_ZONE) * scale);
+        for row in pixels[y * size..].chunks_mut(size).take(scale) {
+            row[x..x + scale].fill(0);
+        }
+    }
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+    Ok(png)
+}
+
+// GET /api/v1/share/<短码>/qr.png?scale=8：指向短链接的二维码；短链接不变，可缓存一天
+// 地址来自 PUBLIC_URL 时允许共享缓存；来自租户 Host 时仅客户端缓存
+pub async fn qr(Path(code): Path<String>, Query(q): Query<QrQuery>, headers: HeaderMap) -> Response {
+    if STORE.get().is_none() {
+        return disabled_response();
+    }
+    let link = match lookup(&code) {
+        Ok(Some(link)) => link,
+        Ok(None) => return respond::json(StatusCode::NOT_FOUND, &ErrorResp::with_code("NOT_FOUND", "短链接不存在")),
+        Err(e) => return db_error(e),
+    };
+    let Some(base) = og_image::public_base(&headers) else {
+        return respond::json(StatusCode::SERVICE_UNAVAILABLE, &ErrorResp::new("未配置 PUBLIC_URL，无法生成二维码"));
+    };
+    let scale = q.scale.unwrap_or(8).clamp(1, 32);
+    let key = (format!("{}/s/{}", base, link.code), scale);
+    let body = match QR_CODES.get(&key) {
+        Some(hit) => hit,
+        None => match render_qr(&key.0, scale) {
+            Ok(png) => {
+                let png = Bytes::from(png);
+                QR_CODES.insert(key, png.clone());
+                png
+            }
+            Err(e) => {
+                warn!("render share QR code failed: {}", e);
+                return respond::json(StatusCode::INTERNAL_SERVER_ERROR, &ErrorResp::new(format!("生成二维码失败: {}", e)));
+            }
+        },
+    };
+    let cache_control =
+        if base_path::public_url().is_some() { "public, max-age=86400" } else { "private, max-age=86400" };
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("image/png")),
+            (header::CACHE_CONTROL, HeaderValue::from_static(cache_control)),
+        ],
+        body,
+    )
+        .into_response()
+}
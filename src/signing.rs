@@ -0,0 +1,81 @@
+// 响应签名：供经由不可信 CDN 缓存 API 响应的下游校验内容未被篡改
+// 为 API 响应（路径含 /api/，包括路径前缀租户）附加：
+//   X-Signature-Timestamp: <生成响应时的 Unix 秒>
+//   X-Signature: sha256=<HMAC-SHA256(密钥, "<时间戳>.<响应体>") 的十六进制>
+// 签名的是压缩前的响应体（CDN 或客户端解压后的字节）；时间戳一并签名，下游可据此拒绝过旧的缓存，校验方法见 README「响应签名」
+// - RESPONSE_SIGNING_SECRET：共享密钥，设置后开启（建议至少 32 字节随机串）
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::OnceCell;
+use ring::hmac;
+use tracing::{info, warn};
+
+use crate::config::env_nonempty;
+
+const SIGNATURE_HEADER: &str = "x-signature";
+const TIMESTAMP_HEADER: &str = "x-signature-timestamp";
+
+static KEY: OnceCell<hmac::Key> = OnceCell::new();
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    let Some(secret) = env_nonempty("RESPONSE_SIGNING_SECRET") else {
+        return Ok(());
+    };
+    if secret.len() < 16 {
+        anyhow::bail!("RESPONSE_SIGNING_SECRET 过短，至少 16 个字符");
+    }
+    info!("response signing enabled");
+    let _ = KEY.set(hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()));
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn sign(key: &hmac::Key, timestamp: i64, body: &[u8]) -> String {
+    let mut ctx = hmac::Context::with_key(key);
+    ctx.update(timestamp.to_string().as_bytes());
+    ctx.update(b".");
+    ctx.update(body);
+    format!("sha256={}", hex(ctx.sign().as_ref()))
+}
+
+// 须位于压缩层之内，读取的是未压缩的响应体；事件流等不定长响应不签名
+pub async fn middleware(req: Request, next: Next) -> Response {
+    let Some(key) = KEY.get() else {
+        return next.run(req).await;
+    };
+    if !req.uri().path().contains("/api/") {
+        return next.run(req).await;
+    }
+    let resp = next.run(req).await;
+    let streaming = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("text/event-stream"));
+    if streaming {
+        return resp;
+    }
+    let (mut parts, body) = resp.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("read response body for signing failed: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let timestamp = chrono::Utc::now().timestamp();
+    if let Ok(signature) = HeaderValue::from_str(&sign(key, timestamp, &bytes)) {
+        parts.headers.insert(SIGNATURE_HEADER, signature);
+        parts.headers.insert(TIMESTAMP_HEADER, HeaderValue::from(timestamp));
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
@@ -0,0 +1,94 @@
+// 服务端渲染的天气页：/w/{city}，无需 JavaScript，便于搜索引擎收录
+// 城市名经高德搜索得到坐标；也可用 ?lng=&lat= 直接指定坐标（此时城市名仅用于展示）
+// /w/ 跳转到默认城市（租户 default_city 或 DEFAULT_*）的页面
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::{base_path, location, tenant, weather, AppState};
+
+// 模板在编译期嵌入；_daily.html、_icon.html 为页面与邮件摘要共用的片段
+pub static TEMPLATES: Lazy<minijinja::Environment<'static>> = Lazy::new(|| {
+    let mut env = minijinja::Environment::new();
+    env.add_template("_icon.html", include_str!("../templates/_icon.html"))
+        .expect("_icon.html template");
+    env.add_template("_daily.html", include_str!("../templates/_daily.html"))
+        .expect("_daily.html template");
+    env.add_template("weather.html", include_str!("../templates/weather.html"))
+        .expect("weather.html template");
+    env.add_template("digest.html", include_str!("../templates/digest.html"))
+        .expect("digest.html template");
+    env.add_template("404.html", include_str!("../templates/404.html"))
+        .expect("404.html template");
+    env
+});
+
+#[derive(Deserialize)]
+pub struct CoordsQuery {
+    lng: Option<f64>,
+    lat: Option<f64>,
+}
+
+fn render(status: StatusCode, ctx: minijinja::Value) -> Response {
+    match TEMPLATES.get_template("weather.html").and_then(|t| t.render(ctx)) {
+        Ok(html) => (
+            status,
+            [
+                (header::CONTENT_TYPE, HeaderValue::from_static("text/html; charset=utf-8")),
+                (header::CACHE_CONTROL, HeaderValue::from_static("public, max-age=300")),
+            ],
+            base_path::rewrite_html(&html),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("render weather.html failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "页面渲染失败").into_response()
+        }
+    }
+}
+
+// 用相对地址，路径前缀租户下同样落在 /前缀/w/ 下
+pub async fn default_page(uri: Uri) -> Response {
+    let city = tenant::default_city();
+    let base = if uri.path().ends_with('/') { "./" } else { "w/" };
+    let target = format!("{}{}?lng={}&lat={}", base, urlencoding::encode(&city.name), city.lng, city.lat);
+    Redirect::temporary(&target).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn weather_page(
+    State(state): State<AppState>,
+    Path(city): Path<String>,
+    Query(q): Query<CoordsQuery>,
+) -> Response {
+    let city = city.trim().to_string();
+    let canonical = format!("/w/{}", urlencoding::encode(&city));
+    let updated = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+
+    let place = match (q.lng, q.lat) {
+        (Some(lng), Some(lat)) => Some(location::Place { lat, lng, name: city.clone(), address: String::new() }),
+        _ => location::search(&state, &city).await.into_iter().next(),
+    };
+    let Some(place) = place else {
+        return render(
+            StatusCode::NOT_FOUND,
+            minijinja::context! { city, canonical, updated, error => "未找到该城市，请检查名称后重试" },
+        );
+    };
+
+    match weather::fetch(&state, place.lng, place.lat).await {
+        Ok(weather) => render(
+            StatusCode::OK,
+            minijinja::context! { city, canonical, updated, address => place.address, weather },
+        ),
+        Err(e) => render(
+            e.status(),
+            minijinja::context! { city, canonical, updated, address => place.address, error => e.to_string() },
+        ),
+    }
+}
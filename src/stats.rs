@@ -0,0 +1,229 @@
+// 使用统计：按天汇总各路由请求数、独立客户端估算与热门地点，经管理接口 /api/v1/admin/stats 查看
+// 不保存 IP 与精确坐标：客户端只计入 HyperLogLog 寄存器，坐标取一位小数（约 10 km）
+// - USAGE_STATS：1 开启，数据存于 DATA_DIR/usage_stats.json，每分钟落盘一次（任务名 usage_stats）
+// - USAGE_STATS_RETENTION_DAYS：保留天数，默认 30
+// 客户端依据 CF-Connecting-IP / X-Forwarded-For / X-Real-IP 区分，直连且无代理头的请求不计入独立客户端
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::{Hash, Hasher},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use axum::{
+    extract::{MatchedPath, Query, Request},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{Local, NaiveDate};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::{
+    config::{env_flag, env_parse},
+    respond, route_timeout, scheduler,
+    store::JsonStore,
+    ErrorResp,
+};
+
+// HyperLogLog 寄存器数（2^8），标准误差约 6.5%
+const REGISTER_BITS: u32 = 8;
+// 每天最多记录的不同地点数，超出后只累加已有地点
+const MAX_LOCATIONS: usize = 5000;
+
+#[derive(Serialize, Deserialize, Default)]
+struct Usage {
+    // 按日期升序
+    days: Vec<Day>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Day {
+    date: NaiveDate,
+    requests: u64,
+    routes: BTreeMap<String, u64>,
+    clients: Vec<u8>,
+    // "经度,纬度"（一位小数）→ 请求数
+    locations: HashMap<String, u64>,
+}
+
+impl Day {
+    fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            requests: 0,
+            routes: BTreeMap::new(),
+            clients: vec![0; 1 << REGISTER_BITS],
+            locations: HashMap::new(),
+        }
+    }
+}
+
+struct Stats {
+    usage: JsonStore<Usage>,
+    retention: usize,
+    dirty: AtomicBool,
+}
+
+static STATS: OnceCell<Stats> = OnceCell::new();
+
+// 启动时读取配置并注册落盘任务
+pub fn init_from_env() -> anyhow::Result<()> {
+    if !env_flag("USAGE_STATS") {
+        return Ok(());
+    }
+    let retention = env_parse::<usize>("USAGE_STATS_RETENTION_DAYS")?.unwrap_or(30).max(1);
+    let stats = Stats { usage: JsonStore::open("usage_stats")?, retention, dirty: AtomicBool::new(false) };
+    let stats = STATS.get_or_init(|| stats);
+    info!("usage stats enabled ({} day(s) stored)", stats.usage.read(|u| u.days.len()));
+    scheduler::register("usage_stats", "* * * * *", || async {
+        let Some(stats) = STATS.get() else { return Ok(()) };
+        if stats.dirty.swap(false, Ordering::Relaxed) {
+            stats.usage.save()?;
+        }
+        Ok(())
+    })
+}
+
+fn client_of(headers: &HeaderMap) -> Option<&str> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    header("cf-connecting-ip")
+        .or_else(|| header("x-forwarded-for").and_then(|s| s.split(',').next()))
+        .or_else(|| header("x-real-ip"))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+#[derive(Deserialize)]
+struct Coords {
+    lng: f64,
+    lat: f64,
+}
+
+// 各天的寄存器可按位取最大值合并，得到区间内的独立客户端估算
+fn add_client(registers: &mut [u8], client: &str) {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    client.hash(&mut hasher);
+    let hash = hasher.finish();
+    let index = (hash >> (64 - REGISTER_BITS)) as usize;
+    let rank = ((hash << REGISTER_BITS) | (1 << (REGISTER_BITS - 1))).leading_zeros() as u8 + 1;
+    registers[index] = registers[index].max(rank);
+}
+
+fn estimate(registers: &[u8]) -> u64 {
+    let m = registers.len() as f64;
+    let zeros = registers.iter().filter(|r| **r == 0).count();
+    if zeros == registers.len() {
+        return 0;
+    }
+    let sum: f64 = registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+    let raw = 0.7213 / (1.0 + 1.079 / m) * m * m / sum;
+    // 小基数时用线性计数修正
+    let estimate = if raw <= 2.5 * m && zeros > 0 { m * (m / zeros as f64).ln() } else { raw };
+    estimate.round() as u64
+}
+
+fn record(stats: &Stats, route: &str, client: Option<&str>, location: Option<String>) {
+    let today = Local::now().date_naive();
+    stats.usage.modify(|usage| {
+        if usage.days.last().map(|d| d.date) != Some(today) {
+            usage.days.push(Day::new(today));
+            let excess = usage.days.len().saturating_sub(stats.retention);
+            usage.days.drain(..excess);
+        }
+        let Some(day) = usage.days.last_mut() else { return };
+        day.requests += 1;
+        *day.routes.entry(route.to_string()).or_default() += 1;
+        if let Some(client) = client {
+            add_client(&mut day.clients, client);
+        }
+        if let Some(key) = location {
+            let full = day.locations.len() >= MAX_LOCATIONS;
+            match day.locations.get_mut(&key) {
+                Some(count) => *count += 1,
+                None if !full => {
+                    day.locations.insert(key, 1);
+                }
+                None => {}
+            }
+        }
+    });
+    stats.dirty.store(true, Ordering::Relaxed);
+}
+
+// 只统计命中路由的请求；路由去掉 /api、/api/vN 前缀合并各版本
+pub async fn middleware(req: Request, next: Next) -> Response {
+    if let (Some(stats), Some(matched)) = (STATS.get(), req.extensions().get::<MatchedPath>()) {
+        let location = Query::<Coords>::try_from_uri(req.uri())
+            .ok()
+            .filter(|q| q.lng.is_finite() && q.lat.is_finite())
+            .map(|q| format!("{:.1},{:.1}", q.lng, q.lat));
+        record(stats, route_timeout::route_key(matched.as_str()), client_of(req.headers()), location);
+    }
+    next.run(req).await
+}
+
+#[derive(Deserialize)]
+pub struct StatsQuery {
+    days: Option<usize>,
+    top: Option<usize>,
+}
+
+fn top_locations(locations: &HashMap<String, u64>, top: usize) -> Vec<serde_json::Value> {
+    let mut sorted: Vec<(&String, &u64)> = locations.iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    sorted
+        .into_iter()
+        .take(top)
+        .filter_map(|(key, count)| {
+            let (lng, lat) = key.split_once(',')?;
+            Some(json!({ "lng": lng.parse::<f64>().ok()?, "lat": lat.parse::<f64>().ok()?, "requests": count }))
+        })
+        .collect()
+}
+
+// 最近 N 天（默认 7）的每日汇总（新的在前）与区间合计；独立客户端合计由寄存器合并估算
+pub async fn admin_stats(Query(q): Query<StatsQuery>) -> Response {
+    let Some(stats) = STATS.get() else {
+        return respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("未开启使用统计（USAGE_STATS=1）"));
+    };
+    let days = q.days.unwrap_or(7).max(1);
+    let top = q.top.unwrap_or(10);
+    let body = stats.usage.read(|usage| {
+        let recent: Vec<&Day> = usage.days.iter().rev().take(days).collect();
+        let mut total = Day::new(Local::now().date_naive());
+        for day in &recent {
+            total.requests += day.requests;
+            for (route, count) in &day.routes {
+                *total.routes.entry(route.clone()).or_default() += count;
+            }
+            for (r, d) in total.clients.iter_mut().zip(&day.clients) {
+                *r = (*r).max(*d);
+            }
+            for (key, count) in &day.locations {
+                *total.locations.entry(key.clone()).or_default() += count;
+            }
+        }
+        let summary = |day: &Day| {
+            json!({
+                "requests": day.requests,
+                "unique_clients": estimate(&day.clients),
+                "routes": day.routes,
+                "top_locations": top_locations(&day.locations, top),
+            })
+        };
+        let daily: Vec<serde_json::Value> = recent
+            .iter()
+            .map(|day| {
+                let mut v = summary(day);
+                v["date"] = day.date.to_string().into();
+                v
+            })
+            .collect();
+        json!({ "days": daily, "total": summary(&total) })
+    });
+    respond::json(StatusCode::OK, &body)
+}
@@ -0,0 +1,64 @@
+// 本地持久化：每类数据一个 JSON 文件，存放于 DATA_DIR（默认 data/）
+// 写入先落临时文件再重命名，避免进程中断时留下半截文件
+
+use std::{
+    path::PathBuf,
+    sync::{Mutex, RwLock},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::config::env_nonempty;
+
+fn data_dir() -> PathBuf {
+    env_nonempty("DATA_DIR").unwrap_or_else(|| "data".into()).into()
+}
+
+pub struct JsonStore<T> {
+    path: PathBuf,
+    data: RwLock<T>,
+    // 串行化落盘，避免并发写同一临时文件
+    saving: Mutex<()>,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> JsonStore<T> {
+    // 读取 DATA_DIR/{name}.json；文件不存在时为空数据，格式错误时报错以免覆盖
+    pub fn open(name: &str) -> anyhow::Result<Self> {
+        let path = data_dir().join(format!("{}.json", name));
+        let data = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| anyhow::anyhow!("无法解析 {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => T::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, data: RwLock::new(data), saving: Mutex::new(()) })
+    }
+
+    pub fn read<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.data.read().unwrap())
+    }
+
+    // 修改并立即落盘；落盘失败时内存中的修改仍保留，下次写入时重试
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> anyhow::Result<R> {
+        let result = self.modify(f);
+        self.save()?;
+        Ok(result)
+    }
+
+    // 只改内存，由调用方择机 save，适合高频写入
+    pub fn modify<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.data.write().unwrap())
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let _saving = self.saving.lock().unwrap();
+        let bytes = serde_json::to_vec_pretty(&*self.data.read().unwrap())?;
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
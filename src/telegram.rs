@@ -0,0 +1,286 @@
+// Telegram 机器人：设置 TELEGRAM_BOT_TOKEN 后以长轮询处理命令，并向订阅的会话推送降雨提醒
+// - 命令：/weather <城市>、/rain [城市]、/subscribe <城市>、/unsubscribe
+// - TELEGRAM_ALLOWED_CHATS：可选，逗号分隔的 chat id 白名单
+// - 降雨检查由定时任务 telegram_rain 执行（默认每 10 分钟，SCHEDULE_TELEGRAM_RAIN 可调整）
+// - 开启预警历史（ALERT_HISTORY=1）时，同一任务对新出现的预警推送一次，已通知的预警不再重复
+// - TELEGRAM_API_URL：可选，自建 Bot API 服务地址，默认 https://api.telegram.org
+// 订阅保存在 DATA_DIR/telegram_subscriptions.json
+
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::{
+    alert_history,
+    config::{env_list, env_nonempty},
+    icons, location, scheduler,
+    store::JsonStore,
+    upstream,
+    upstream_url::UpstreamUrl,
+    weather, AppState,
+};
+
+// getUpdates 长轮询秒数，需小于请求超时
+const POLL_TIMEOUT_SECS: u64 = 50;
+
+const HELP: &str = "可用命令：\n\
+    /weather <城市> 查看天气（已订阅时可省略城市）\n\
+    /rain [城市] 未来两小时降水\n\
+    /subscribe <城市> 订阅该地降雨提醒\n\
+    /unsubscribe 取消订阅";
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Subscription {
+    name: String,
+    lng: f64,
+    lat: f64,
+    // 已针对本轮降雨发送过提醒，雨停后复位
+    #[serde(default)]
+    rain_notified: bool,
+}
+
+type Subscriptions = BTreeMap<i64, Subscription>;
+
+struct Bot {
+    api_base: String,
+    token: String,
+    state: AppState,
+    allowed: Vec<i64>,
+    subs: JsonStore<Subscriptions>,
+}
+
+// 读取配置并启动长轮询与降雨提醒任务；未设置 TELEGRAM_BOT_TOKEN 时不做任何事
+pub fn spawn_from_env(state: AppState) -> anyhow::Result<()> {
+    let Some(token) = env_nonempty("TELEGRAM_BOT_TOKEN") else {
+        return Ok(());
+    };
+    let allowed = env_list("TELEGRAM_ALLOWED_CHATS")
+        .unwrap_or_default()
+        .iter()
+        .map(|id| id.parse::<i64>().map_err(|_| anyhow::anyhow!("无法解析 TELEGRAM_ALLOWED_CHATS 中的 {}", id)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let bot = Arc::new(Bot {
+        api_base: env_nonempty("TELEGRAM_API_URL")
+            .unwrap_or_else(|| "https://api.telegram.org".into())
+            .trim_end_matches('/')
+            .to_string(),
+        token,
+        state,
+        allowed,
+        subs: JsonStore::open("telegram_subscriptions")?,
+    });
+
+    info!("telegram bot started ({} subscription(s))", bot.subs.read(|s| s.len()));
+    tokio::spawn(bot.clone().poll_loop());
+    scheduler::register("telegram_rain", "*/10 * * * *", move || bot.clone().check_rain())
+}
+
+impl Bot {
+    async fn call(&self, method: &str, body: Value, timeout: Duration) -> anyhow::Result<Value> {
+        let url = UpstreamUrl::new(&self.api_base).path("/bot").secret(&self.token).segment(method);
+        let rb = upstream::client("telegram").post(url.as_str()).json(&body).timeout(timeout);
+        let v: Value = upstream::json(upstream::send("telegram", "/bot{token}/{method}", rb).await?).await?;
+        if v.get("ok").and_then(|v| v.as_bool()) != Some(true) {
+            anyhow::bail!("telegram {} 失败: {}", method, v.get("description").and_then(|d| d.as_str()).unwrap_or(""));
+        }
+        Ok(v.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    async fn send(&self, chat_id: i64, text: &str) {
+        let body = json!({ "chat_id": chat_id, "text": text, "disable_web_page_preview": true });
+        if let Err(e) = self.call("sendMessage", body, Duration::from_secs(10)).await {
+            warn!(chat_id, "telegram sendMessage failed: {}", e);
+        }
+    }
+
+    async fn poll_loop(self: Arc<Self>) {
+        let mut offset = 0i64;
+        loop {
+            let body = json!({ "offset": offset, "timeout": POLL_TIMEOUT_SECS, "allowed_updates": ["message"] });
+            let updates = match self.call("getUpdates", body, Duration::from_secs(POLL_TIMEOUT_SECS + 10)).await {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("telegram getUpdates failed: {}", e);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+            for update in updates.as_array().into_iter().flatten() {
+                if let Some(id) = update.get("update_id").and_then(|v| v.as_i64()) {
+                    offset = offset.max(id + 1);
+                }
+                let Some(msg) = update.get("message") else { continue };
+                let chat_id = msg.pointer("/chat/id").and_then(|v| v.as_i64());
+                let text = msg.get("text").and_then(|v| v.as_str());
+                if let (Some(chat_id), Some(text)) = (chat_id, text) {
+                    if self.allowed.is_empty() || self.allowed.contains(&chat_id) {
+                        let reply = self.handle(chat_id, text).await;
+                        self.send(chat_id, &reply).await;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn handle(&self, chat_id: i64, text: &str) -> String {
+        let text = text.trim();
+        if !text.starts_with('/') {
+            return HELP.to_string();
+        }
+        let (cmd, arg) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        // 群组中命令形如 /weather@bot_name
+        let cmd = cmd.split('@').next().unwrap_or(cmd);
+        let arg = arg.trim();
+        match cmd {
+            "/weather" => match self.resolve(chat_id, arg).await {
+                Ok(place) => match weather::fetch(&self.state, place.lng, place.lat).await {
+                    Ok(data) => format_forecast(&place.name, &data),
+                    Err(e) => format!("获取天气失败：{}", e),
+                },
+                Err(msg) => msg,
+            },
+            "/rain" => match self.resolve(chat_id, arg).await {
+                Ok(place) => match weather::fetch(&self.state, place.lng, place.lat).await {
+                    Ok(data) => format_rain(&place.name, &data),
+                    Err(e) => format!("获取天气失败：{}", e),
+                },
+                Err(msg) => msg,
+            },
+            "/subscribe" => {
+                if arg.is_empty() {
+                    return "用法：/subscribe <城市>".into();
+                }
+                let place = match self.search(arg).await {
+                    Ok(p) => p,
+                    Err(msg) => return msg,
+                };
+                let name = place.name.clone();
+                match self.subs.update(|s| s.insert(chat_id, place)) {
+                    Ok(_) => format!("已订阅 {} 的降雨提醒", name),
+                    Err(e) => format!("订阅失败：{}", e),
+                }
+            }
+            "/unsubscribe" => match self.subs.update(|s| s.remove(&chat_id)) {
+                Ok(Some(old)) => format!("已取消 {} 的订阅", old.name),
+                Ok(None) => "当前没有订阅".into(),
+                Err(e) => format!("取消订阅失败：{}", e),
+            },
+            _ => HELP.to_string(),
+        }
+    }
+
+    async fn search(&self, q: &str) -> Result<Subscription, String> {
+        location::search(&self.state, q)
+            .await
+            .into_iter()
+            .next()
+            .map(|p| Subscription { name: p.name, lng: p.lng, lat: p.lat, rain_notified: false })
+            .ok_or_else(|| format!("未找到“{}”", q))
+    }
+
+    // 有参数时按城市名搜索，否则使用该会话的订阅地点
+    async fn resolve(&self, chat_id: i64, arg: &str) -> Result<Subscription, String> {
+        if !arg.is_empty() {
+            return self.search(arg).await;
+        }
+        self.subs
+            .read(|s| s.get(&chat_id).cloned())
+            .ok_or_else(|| "请提供城市名，例如：/weather 北京".to_string())
+    }
+
+    // 降雨状态变化时提醒：开始下雨前推送一次，雨停后复位
+    async fn check_rain(self: Arc<Self>) -> anyhow::Result<()> {
+        let subs = self.subs.read(|s| s.clone());
+        let mut failed = 0;
+        for (chat_id, sub) in subs {
+            let data = match weather::fetch(&self.state, sub.lng, sub.lat).await {
+                Ok(d) => d,
+                Err(e) => {
+                    warn!(chat_id, "telegram rain check failed: {}", e);
+                    failed += 1;
+                    continue;
+                }
+            };
+            for a in data.alerts.as_array().into_iter().flatten() {
+                let Some(id) = alert_history::alert_id(a) else { continue };
+                if alert_history::first_notice(&id, &format!("telegram:{}", chat_id)) {
+                    self.send(chat_id, &format_alert(&sub.name, a)).await;
+                }
+            }
+            let will_rain = data.minutely.get("will_rain").and_then(|v| v.as_bool()).unwrap_or(false);
+            if will_rain == sub.rain_notified {
+                continue;
+            }
+            if will_rain {
+                self.send(chat_id, &format!("🌧️ 降雨提醒\n{}", format_rain(&sub.name, &data))).await;
+            }
+            let updated = self.subs.update(|s| {
+                if let Some(s) = s.get_mut(&chat_id) {
+                    s.rain_notified = will_rain;
+                }
+            });
+            if let Err(e) = updated {
+                warn!("保存订阅失败: {}", e);
+            }
+        }
+        if failed > 0 {
+            anyhow::bail!("{} 个订阅降雨检查失败", failed);
+        }
+        Ok(())
+    }
+}
+
+fn format_forecast(name: &str, data: &weather::WeatherData) -> String {
+    let cur = &data.current;
+    let desc = cur.weather_info.get("desc").and_then(|v| v.as_str()).unwrap_or("");
+    let mut lines = vec![
+        format!("📍 {}", name),
+        format!(
+            "{} {} {}°C（体感 {}°C）",
+            icons::plain(cur.skycon.as_str().unwrap_or("")),
+            desc,
+            cur.temperature,
+            cur.apparent_temperature
+        ),
+        format!("湿度 {}% · 风速 {} km/h", cur.humidity, cur.wind_speed),
+    ];
+    if let Some(keypoint) = data.forecast_keypoint.as_str().filter(|s| !s.is_empty()) {
+        lines.push(keypoint.to_string());
+    }
+    lines.push(String::new());
+    for d in data.daily.as_array().into_iter().flatten() {
+        let text = |key: &str| d.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+        let info = d.get("weather_info").cloned().unwrap_or(Value::Null);
+        lines.push(format!(
+            "{} {} {} {}~{}°C",
+            text("relativeDay"),
+            icons::plain(&text("skycon")),
+            info.get("desc").and_then(|v| v.as_str()).unwrap_or(""),
+            d.get("min_temp").and_then(|v| v.as_i64()).unwrap_or(0),
+            d.get("max_temp").and_then(|v| v.as_i64()).unwrap_or(0),
+        ));
+    }
+    for a in data.alerts.as_array().into_iter().flatten() {
+        if let Some(title) = a.get("title").and_then(|v| v.as_str()) {
+            lines.push(format!("⚠️ {}", title));
+        }
+    }
+    lines.join("\n")
+}
+
+fn format_alert(name: &str, alert: &Value) -> String {
+    let text = |key: &str| alert.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    format!("⚠️ 预警提醒\n📍 {}\n{}\n{}", name, text("title"), text("description"))
+}
+
+fn format_rain(name: &str, data: &weather::WeatherData) -> String {
+    let desc = data
+        .minutely
+        .get("description")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .unwrap_or("暂无分钟级降水数据");
+    format!("📍 {}\n{}", name, desc)
+}
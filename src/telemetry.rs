@@ -0,0 +1,157 @@
+// 日志与链路追踪初始化：控制台/文件日志（文本或 JSON）+（可选，需 `otel` feature）OTLP 导出到 Jaeger/Tempo 等
+// 控制台与文件日志写出前经 redact 去掉 token、key
+
+use tracing_subscriber::{
+    fmt::MakeWriter, layer::SubscriberExt, registry::LookupSpan, util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+use crate::redact::Redacting;
+
+// 持有导出器与日志写线程，释放时 flush 未写出的内容
+// 被信号直接杀死时缓冲中的 span 与日志会丢失，因此服务优雅退出后由 main 调用 shutdown
+pub struct Guard {
+    _log_writers: Vec<tracing_appender::non_blocking::WorkerGuard>,
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+}
+
+impl Guard {
+    // 先导出剩余 span，再等日志写线程写完（字段按声明顺序释放）
+    pub fn shutdown(self) {
+        drop(self);
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("otel shutdown failed: {e}");
+            }
+        }
+    }
+}
+
+type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync + 'static>;
+
+// LOG_FORMAT=json 输出 JSON 行（含当前 span 的 request_id/route），否则为紧凑文本
+fn fmt_layer<S, W>(json: bool, ansi: bool, writer: W) -> BoxedLayer<S>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let base = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi)
+        .with_target(false);
+    if json {
+        base.json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed()
+    } else {
+        base.compact().boxed()
+    }
+}
+
+pub fn init() -> anyhow::Result<Guard> {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| "info,tower_http=info".into());
+    let json = std::env::var("LOG_FORMAT")
+        .map(|s| s.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+
+    let mut log_writers = Vec::new();
+    let mut layers = vec![fmt_layer(json, true, Redacting(std::io::stdout))];
+    // LOG_FILE=logs/caiyun.log：按天轮转为 logs/caiyun.log.YYYY-MM-DD
+    if let Some(path) = std::env::var("LOG_FILE").ok().filter(|s| !s.trim().is_empty()) {
+        let path = std::path::Path::new(&path);
+        let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(std::path::Path::new("."));
+        let name = path
+            .file_name()
+            .ok_or_else(|| anyhow::anyhow!("LOG_FILE 缺少文件名"))?;
+        let (writer, guard) = tracing_appender::non_blocking(tracing_appender::rolling::daily(dir, name));
+        layers.push(fmt_layer(json, false, Redacting(writer)));
+        log_writers.push(guard);
+    }
+
+    // OTEL_EXPORTER_OTLP_ENDPOINT 为空则不启用导出
+    let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .ok()
+        .filter(|s| !s.trim().is_empty());
+
+    #[cfg(feature = "otel")]
+    {
+        let (otel, provider) = match endpoint.as_deref() {
+            Some(ep) => {
+                let (layer, provider) = otel_layer(ep)?;
+                (Some(layer), Some(provider))
+            }
+            None => (None, None),
+        };
+        tracing_subscriber::registry().with(filter).with(layers).with(otel).init();
+        if let Some(ep) = endpoint {
+            tracing::info!("otlp trace export enabled: {}", ep);
+        }
+        Ok(Guard { _log_writers: log_writers, provider })
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        tracing_subscriber::registry().with(filter).with(layers).init();
+        if endpoint.is_some() {
+            tracing::warn!("OTEL_EXPORTER_OTLP_ENDPOINT 已设置，但未启用 `otel` feature，忽略");
+        }
+        Ok(Guard { _log_writers: log_writers })
+    }
+}
+
+// 每个响应记录一条日志：状态码与耗时（span 中已带 request_id/route）
+pub fn on_response<B>(resp: &axum::http::Response<B>, latency: std::time::Duration, _span: &tracing::Span) {
+    tracing::info!(
+        status = resp.status().as_u16(),
+        latency_ms = latency.as_millis() as u64,
+        "response"
+    );
+}
+
+#[cfg(feature = "otel")]
+fn otel_layer<S>(
+    endpoint: &str,
+) -> anyhow::Result<(
+    tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>,
+    opentelemetry_sdk::trace::TracerProvider,
+)>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::WithExportConfig;
+
+    // 与 OTLP/HTTP 规范一致：仅给出基础地址时补全 /v1/traces
+    let endpoint = endpoint.trim_end_matches('/');
+    let endpoint = if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{}/v1/traces", endpoint)
+    };
+    let service_name = std::env::var("OTEL_SERVICE_NAME")
+        .unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string());
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()?;
+    let provider = opentelemetry_sdk::trace::TracerProvider::builder()
+        .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+        .with_resource(opentelemetry_sdk::Resource::new(vec![
+            opentelemetry::KeyValue::new("service.name", service_name),
+        ]))
+        .build();
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+    opentelemetry::global::set_tracer_provider(provider.clone());
+    Ok((tracing_opentelemetry::layer().with_tracer(tracer), provider))
+}
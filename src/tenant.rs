@@ -0,0 +1,294 @@
+// 多租户：同一实例为多个小站点提供服务，按请求的主机名或路径前缀识别租户，租户可使用自己的上游 key、速率限制、图标集与默认城市
+// 租户定义在 TOML 文件中（格式见 tenants.example.toml），未匹配任何租户的请求按全局配置处理
+// - TENANTS_FILE：租户定义文件；启动时校验，主机名/路径前缀重复或图标集未知时报错
+// - DEFAULT_LNG / DEFAULT_LAT / DEFAULT_ADDRESS：全局默认城市（须同时设置），未匹配租户或租户未设 default_city 时使用，默认北京
+// 路径前缀租户的页面与接口挂在前缀下（如 /acme/、/acme/api/v1/weather）；主机名匹配优先于路径前缀
+
+use std::{future::Future, sync::Mutex};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Router,
+};
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    base_path,
+    config::{env_nonempty, env_parse},
+    icons, index, redact, respond, AppState, ErrorResp,
+};
+
+// 与已有顶层路由冲突的路径前缀
+const RESERVED_PREFIXES: &[&str] = &["/api", "/static", "/w", "/graphql", "/metrics"];
+
+#[derive(Deserialize)]
+struct TenantFile {
+    #[serde(default)]
+    tenants: Vec<TenantConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TenantConfig {
+    name: String,
+    #[serde(default)]
+    hosts: Vec<String>,
+    path_prefix: Option<String>,
+    caiyun_token: Option<String>,
+    amap_key: Option<String>,
+    rate_limit_per_minute: Option<u32>,
+    icon_set: Option<String>,
+    site_name: Option<String>,
+    default_city: Option<City>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct City {
+    pub name: String,
+    pub lng: f64,
+    pub lat: f64,
+}
+
+pub struct Tenant {
+    pub name: String,
+    hosts: Vec<String>,
+    path_prefix: Option<String>,
+    caiyun_token: Option<String>,
+    amap_key: Option<String>,
+    rate_limit_per_minute: Option<u32>,
+    pub icon_set: Option<String>,
+    pub site_name: Option<String>,
+    pub default_city: Option<City>,
+    // (分钟序号, 本分钟 API 请求数)
+    window: Mutex<(i64, u32)>,
+}
+
+static TENANTS: OnceCell<Vec<Tenant>> = OnceCell::new();
+static DEFAULT_CITY: OnceCell<City> = OnceCell::new();
+
+tokio::task_local! {
+    static CURRENT: &'static Tenant;
+}
+
+fn validate(cfg: &TenantConfig) -> anyhow::Result<()> {
+    if cfg.name.trim().is_empty() {
+        anyhow::bail!("租户缺少 name");
+    }
+    if cfg.hosts.is_empty() && cfg.path_prefix.is_none() {
+        anyhow::bail!("租户 {} 需设置 hosts 或 path_prefix", cfg.name);
+    }
+    if let Some(prefix) = &cfg.path_prefix {
+        let valid = prefix.len() > 1 && prefix.starts_with('/') && !prefix.ends_with('/') && !prefix.contains(':');
+        if !valid {
+            anyhow::bail!("租户 {} 的 path_prefix 应形如 /acme: {}", cfg.name, prefix);
+        }
+        if RESERVED_PREFIXES.iter().any(|r| prefix == r || prefix.starts_with(&format!("{}/", r))) {
+            anyhow::bail!("租户 {} 的 path_prefix 与已有路由冲突: {}", cfg.name, prefix);
+        }
+    }
+    if let Some(set) = &cfg.icon_set {
+        icons::select(Some(set)).map_err(|e| anyhow::anyhow!("租户 {}: {}", cfg.name, e))?;
+    }
+    if let Some(city) = &cfg.default_city {
+        if !(-180.0..=180.0).contains(&city.lng) || !(-90.0..=90.0).contains(&city.lat) {
+            anyhow::bail!("租户 {} 的 default_city 坐标无效", cfg.name);
+        }
+    }
+    Ok(())
+}
+
+fn default_city_from_env() -> anyhow::Result<Option<City>> {
+    let (lng, lat, name) = (env_parse::<f64>("DEFAULT_LNG")?, env_parse::<f64>("DEFAULT_LAT")?, env_nonempty("DEFAULT_ADDRESS"));
+    match (lng, lat, name) {
+        (None, None, None) => Ok(None),
+        (Some(lng), Some(lat), Some(name)) => {
+            if !(-180.0..=180.0).contains(&lng) || !(-90.0..=90.0).contains(&lat) {
+                anyhow::bail!("DEFAULT_LNG/DEFAULT_LAT 超出范围");
+            }
+            Ok(Some(City { name, lng, lat }))
+        }
+        _ => anyhow::bail!("DEFAULT_LNG、DEFAULT_LAT 与 DEFAULT_ADDRESS 需同时设置"),
+    }
+}
+
+// 需在 icons::init_from_env 之后调用（校验图标集）
+pub fn init_from_env() -> anyhow::Result<()> {
+    if let Some(city) = default_city_from_env()? {
+        info!("default city: {} ({}, {})", city.name, city.lng, city.lat);
+        let _ = DEFAULT_CITY.set(city);
+    }
+    let Some(path) = env_nonempty("TENANTS_FILE") else {
+        return Ok(());
+    };
+    let text = std::fs::read_to_string(&path).map_err(|e| anyhow::anyhow!("无法读取 {}: {}", path, e))?;
+    let file: TenantFile = toml::from_str(&text).map_err(|e| anyhow::anyhow!("{} 无效: {}", path, e))?;
+    let mut tenants: Vec<Tenant> = Vec::new();
+    for cfg in file.tenants {
+        for secret in [&cfg.caiyun_token, &cfg.amap_key].into_iter().flatten() {
+            redact::register(secret);
+        }
+        validate(&cfg).map_err(|e| anyhow::anyhow!("{} 无效: {}", path, e))?;
+        let hosts: Vec<String> = cfg.hosts.iter().map(|h| h.trim().to_ascii_lowercase()).collect();
+        for t in &tenants {
+            if t.name == cfg.name {
+                anyhow::bail!("{} 无效: 租户名 {} 重复", path, cfg.name);
+            }
+            if let Some(host) = hosts.iter().find(|h| t.hosts.contains(h)) {
+                anyhow::bail!("{} 无效: 主机名 {} 同时属于租户 {} 与 {}", path, host, t.name, cfg.name);
+            }
+            if cfg.path_prefix.is_some() && t.path_prefix == cfg.path_prefix {
+                anyhow::bail!("{} 无效: 租户 {} 与 {} 的 path_prefix 相同", path, t.name, cfg.name);
+            }
+        }
+        tenants.push(Tenant {
+            name: cfg.name,
+            hosts,
+            path_prefix: cfg.path_prefix,
+            caiyun_token: cfg.caiyun_token.filter(|s| !s.is_empty()),
+            amap_key: cfg.amap_key.filter(|s| !s.is_empty()),
+            rate_limit_per_minute: cfg.rate_limit_per_minute.filter(|n| *n > 0),
+            icon_set: cfg.icon_set,
+            site_name: cfg.site_name.filter(|s| !s.is_empty()),
+            default_city: cfg.default_city,
+            window: Mutex::new((0, 0)),
+        });
+    }
+    info!("{} tenant(s) loaded from {}", tenants.len(), path);
+    let _ = TENANTS.set(tenants);
+    Ok(())
+}
+
+// 当前请求所属的租户（仅在请求处理任务内可用）
+pub fn current() -> Option<&'static Tenant> {
+    CURRENT.try_with(|t| *t).ok()
+}
+
+// 在指定租户下运行 future，用于请求结束后仍会执行的后台任务（如缓存刷新）
+pub async fn scope<F: Future>(tenant: Option<&'static Tenant>, fut: F) -> F::Output {
+    match tenant {
+        Some(t) => CURRENT.scope(t, fut).await,
+        None => fut.await,
+    }
+}
+
+// 彩云 token / 高德 key：租户未配置时使用全局配置
+pub fn caiyun_token(state: &AppState) -> Option<String> {
+    current().and_then(|t| t.caiyun_token.clone()).or_else(|| state.caiyun_token.clone())
+}
+
+pub fn amap_key(state: &AppState) -> Option<String> {
+    current().and_then(|t| t.amap_key.clone()).or_else(|| state.amap_key.clone())
+}
+
+// 默认城市：IP 定位失败、/w/ 页面与分享卡片未指定坐标时使用；租户设置 > DEFAULT_* > 北京
+pub fn default_city() -> City {
+    current()
+        .and_then(|t| t.default_city.clone())
+        .or_else(|| DEFAULT_CITY.get().cloned())
+        .unwrap_or_else(|| City { name: "北京市".into(), lng: 116.4074, lat: 39.9042 })
+}
+
+// 按租户名查找（管理接口使用）
+pub fn by_name(name: &str) -> Option<&'static Tenant> {
+    TENANTS.get()?.iter().find(|t| t.name == name)
+}
+
+impl Tenant {
+    // 是否自带彩云 token / 高德 key（上游结果需与全局配置分开缓存）
+    pub fn has_caiyun_token(&self) -> bool {
+        self.caiyun_token.is_some()
+    }
+
+    pub fn has_amap_key(&self) -> bool {
+        self.amap_key.is_some()
+    }
+
+    // Host 是否为该租户配置的主机名（可带端口）
+    pub fn serves_host(&self, host: &str) -> bool {
+        self.hosts.contains(&normalize_host(host))
+    }
+
+    // 本分钟的 API 请求数达到上限时返回距下一分钟的秒数
+    fn admit(&self) -> Result<(), u64> {
+        let Some(limit) = self.rate_limit_per_minute else {
+            return Ok(());
+        };
+        let now = chrono::Utc::now().timestamp();
+        let mut window = self.window.lock().unwrap();
+        if window.0 != now / 60 {
+            *window = (now / 60, 0);
+        }
+        if window.1 >= limit {
+            return Err((60 - now % 60) as u64);
+        }
+        window.1 += 1;
+        Ok(())
+    }
+}
+
+async fn enter(tenant: &'static Tenant, req: Request, next: Next) -> Response {
+    if req.uri().path().starts_with("/api/") {
+        if let Err(retry_after) = tenant.admit() {
+            let mut resp = respond::json(
+                StatusCode::TOO_MANY_REQUESTS,
+                &ErrorResp::with_code("RATE_LIMITED", "请求过于频繁，请稍后重试"),
+            );
+            resp.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            return resp;
+        }
+    }
+    CURRENT.scope(tenant, next.run(req)).await
+}
+
+// 去掉端口；IPv6 字面量带方括号，不做租户匹配
+fn normalize_host(host: &str) -> String {
+    host.rsplit_once(':').filter(|(h, _)| !h.contains(']')).map_or(host, |(h, _)| h).to_ascii_lowercase()
+}
+
+fn host(req: &Request) -> Option<String> {
+    let host = req.headers().get(header::HOST).and_then(|v| v.to_str().ok()).or_else(|| req.uri().host())?;
+    Some(normalize_host(host))
+}
+
+// 按 Host 识别租户；路径前缀租户的首页统一为 /前缀/，以便页面中的相对地址落在前缀下
+pub async fn middleware(req: Request, next: Next) -> Response {
+    let Some(tenants) = TENANTS.get() else {
+        return next.run(req).await;
+    };
+    let path = req.uri().path();
+    if tenants.iter().any(|t| t.path_prefix.as_deref() == Some(path)) {
+        let query = req.uri().query().map(|q| format!("?{}", q)).unwrap_or_default();
+        return Redirect::permanent(&format!("{}/{}", base_path::join(path), query)).into_response();
+    }
+    match host(&req).and_then(|host| tenants.iter().find(|t| t.hosts.contains(&host))) {
+        Some(t) => enter(t, req, next).await,
+        None => next.run(req).await,
+    }
+}
+
+async fn by_prefix(State(tenant): State<&'static Tenant>, req: Request, next: Next) -> Response {
+    // 主机名已匹配到租户时以主机名为准
+    match current() {
+        Some(_) => next.run(req).await,
+        None => enter(tenant, req, next).await,
+    }
+}
+
+// 为设置了 path_prefix 的租户把整套路由再挂到前缀下
+pub fn nest(app: Router<AppState>) -> Router<AppState> {
+    let Some(tenants) = TENANTS.get() else {
+        return app;
+    };
+    tenants.iter().fold(app.clone(), |router, t| match &t.path_prefix {
+        Some(prefix) => router
+            .route(&format!("{}/", prefix), get(index).layer(axum::middleware::from_fn_with_state(t, by_prefix)))
+            .nest(prefix, app.clone().layer(axum::middleware::from_fn_with_state(t, by_prefix))),
+        None => router,
+    })
+}
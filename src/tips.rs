@@ -0,0 +1,185 @@
+// 出行与穿衣提示：按规则（tips.toml）把温度、风、降水、AQI、紫外线等条件映射为有序的提示（如“带伞”“穿外套”“不宜晨练”）
+// 规则为 TOML：每条含 tip、priority 与 when（条件列表，全部满足才生效），条件写法见 tips.toml 开头
+// - TIPS_RULES_FILE：自定义规则文件，设置后替换内置规则；启动时校验，指标或运算符无效时报错
+
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+
+use crate::config::env_nonempty;
+
+const BUILTIN: &str = include_str!("../tips.toml");
+
+// 规则可引用的指标，缺失的数据为 None
+#[derive(Default)]
+pub struct Metrics {
+    pub temperature: Option<f64>,
+    pub apparent_temperature: Option<f64>,
+    pub min_temp: Option<f64>,
+    pub max_temp: Option<f64>,
+    pub humidity: Option<f64>,
+    pub wind_speed: Option<f64>,
+    pub aqi: Option<f64>,
+    pub uv_index: Option<f64>,
+    pub rain_probability: Option<f64>,
+    pub precipitation: Option<f64>,
+    pub will_rain: bool,
+}
+
+impl Metrics {
+    pub fn get(&self, name: &str) -> Option<f64> {
+        match name {
+            "temperature" => self.temperature,
+            "apparent_temperature" => self.apparent_temperature,
+            "min_temp" => self.min_temp,
+            "max_temp" => self.max_temp,
+            "temp_range" => Some(self.max_temp? - self.min_temp?),
+            "humidity" => self.humidity,
+            "wind_speed" => self.wind_speed,
+            "aqi" => self.aqi,
+            "uv_index" => self.uv_index,
+            "rain_probability" => self.rain_probability,
+            "precipitation" => self.precipitation,
+            "will_rain" => Some(if self.will_rain { 1.0 } else { 0.0 }),
+            _ => None,
+        }
+    }
+}
+
+const METRICS: &[&str] = &[
+    "temperature",
+    "apparent_temperature",
+    "min_temp",
+    "max_temp",
+    "temp_range",
+    "humidity",
+    "wind_speed",
+    "aqi",
+    "uv_index",
+    "rain_probability",
+    "precipitation",
+    "will_rain",
+];
+
+#[derive(Clone, Copy)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+// 也用于 webhook 规则（webhooks.rs）
+pub struct Condition {
+    pub metric: &'static str,
+    op: Op,
+    value: f64,
+}
+
+impl Condition {
+    // "rain_probability >= 50"
+    pub fn parse(text: &str) -> anyhow::Result<Condition> {
+        let parts: Vec<&str> = text.split_whitespace().collect();
+        let [metric, op, value] = parts.as_slice() else {
+            anyhow::bail!("条件应为 `指标 运算符 数值`: {}", text);
+        };
+        let metric = METRICS
+            .iter()
+            .find(|m| *m == metric)
+            .ok_or_else(|| anyhow::anyhow!("未知指标 {}（可用：{}）", metric, METRICS.join(", ")))?;
+        let op = match *op {
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            "==" => Op::Eq,
+            "!=" => Op::Ne,
+            other => anyhow::bail!("未知运算符 {}: {}", other, text),
+        };
+        let value = value.parse().map_err(|_| anyhow::anyhow!("数值无效: {}", text))?;
+        Ok(Condition { metric, op, value })
+    }
+
+    pub fn matches(&self, metrics: &Metrics) -> bool {
+        let Some(v) = metrics.get(self.metric) else {
+            return false;
+        };
+        match self.op {
+            Op::Lt => v < self.value,
+            Op::Le => v <= self.value,
+            Op::Gt => v > self.value,
+            Op::Ge => v >= self.value,
+            Op::Eq => v == self.value,
+            Op::Ne => v != self.value,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RuleFile {
+    #[serde(default)]
+    rules: Vec<RawRule>,
+}
+
+#[derive(Deserialize)]
+struct RawRule {
+    tip: String,
+    #[serde(default)]
+    priority: i64,
+    when: Vec<String>,
+}
+
+struct Rule {
+    tip: String,
+    priority: i64,
+    when: Vec<Condition>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Tip {
+    pub tip: String,
+    pub priority: i64,
+}
+
+fn parse(text: &str) -> anyhow::Result<Vec<Rule>> {
+    let file: RuleFile = toml::from_str(text)?;
+    file.rules
+        .into_iter()
+        .map(|r| {
+            if r.when.is_empty() {
+                anyhow::bail!("规则“{}”缺少 when 条件", r.tip);
+            }
+            let when = r.when.iter().map(|c| Condition::parse(c)).collect::<anyhow::Result<_>>()?;
+            Ok(Rule { tip: r.tip, priority: r.priority, when })
+        })
+        .collect()
+}
+
+static BUILTIN_RULES: Lazy<Vec<Rule>> = Lazy::new(|| parse(BUILTIN).expect("tips.toml 格式错误"));
+static CUSTOM_RULES: OnceCell<Vec<Rule>> = OnceCell::new();
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    if let Some(path) = env_nonempty("TIPS_RULES_FILE") {
+        let text = std::fs::read_to_string(&path).map_err(|e| anyhow::anyhow!("无法读取 {}: {}", path, e))?;
+        let rules = parse(&text).map_err(|e| anyhow::anyhow!("{} 无效: {}", path, e))?;
+        let _ = CUSTOM_RULES.set(rules);
+    }
+    Lazy::force(&BUILTIN_RULES);
+    Ok(())
+}
+
+// 满足条件的提示，按优先级从高到低；同一提示取最高优先级
+pub fn evaluate(metrics: &Metrics) -> Vec<Tip> {
+    let rules = CUSTOM_RULES.get().unwrap_or(&BUILTIN_RULES);
+    let mut tips: Vec<Tip> = Vec::new();
+    for rule in rules.iter().filter(|r| r.when.iter().all(|c| c.matches(metrics))) {
+        match tips.iter_mut().find(|t| t.tip == rule.tip) {
+            Some(t) => t.priority = t.priority.max(rule.priority),
+            None => tips.push(Tip { tip: rule.tip.clone(), priority: rule.priority }),
+        }
+    }
+    // 稳定排序：同优先级保持规则文件中的顺序
+    tips.sort_by_key(|t| std::cmp::Reverse(t.priority));
+    tips
+}
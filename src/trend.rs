@@ -0,0 +1,106 @@
+// 气压/气温趋势：按地点记录最近几小时的实况观测（内存时间序列），与约 3 小时前比较得出上升/下降/平稳
+// 地点按经纬度保留两位小数（约 1 km）归并；同一地点 10 分钟内只记一次；历史不足 1 小时时不给出趋势
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+const WINDOW_HOURS: i64 = 6;
+const MIN_INTERVAL_MINUTES: i64 = 10;
+const MAX_LOCATIONS: usize = 10_000;
+// 3 小时变化超过该值视为上升/下降
+const PRESSURE_THRESHOLD_HPA: f64 = 1.0;
+const TEMPERATURE_THRESHOLD_C: f64 = 1.0;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    Rising,
+    Falling,
+    Steady,
+}
+
+#[derive(Clone, Copy)]
+struct Observation {
+    at: DateTime<Utc>,
+    temperature: f64,
+    pressure: f64,
+}
+
+type Key = (i32, i32);
+
+static SERIES: Lazy<Mutex<HashMap<Key, VecDeque<Observation>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn key(lng: f64, lat: f64) -> Key {
+    ((lng * 100.0).round() as i32, (lat * 100.0).round() as i32)
+}
+
+// 从彩云原始响应记录一次观测；温度 °C，气压换算为 hPa
+pub fn record(lng: f64, lat: f64, raw: &serde_json::Value) {
+    let realtime = raw.pointer("/result/realtime");
+    let field = |k: &str| realtime.and_then(|r| r.get(k)).and_then(|v| v.as_f64());
+    let (Some(temperature), Some(pressure)) = (field("temperature"), field("pressure")) else {
+        return;
+    };
+    let now = Utc::now();
+    let key = key(lng, lat);
+    let mut series = SERIES.lock().unwrap();
+    if series.len() >= MAX_LOCATIONS && !series.contains_key(&key) {
+        let cutoff = now - Duration::hours(WINDOW_HOURS);
+        series.retain(|_, obs| obs.back().is_some_and(|o| o.at > cutoff));
+        // 活跃地点超过上限时淘汰最久未更新的一个
+        if series.len() >= MAX_LOCATIONS {
+            let stalest = series.iter().min_by_key(|(_, obs)| obs.back().map(|o| o.at)).map(|(k, _)| *k);
+            if let Some(stalest) = stalest {
+                series.remove(&stalest);
+            }
+        }
+    }
+    let obs = series.entry(key).or_default();
+    if obs.back().is_some_and(|last| now - last.at < Duration::minutes(MIN_INTERVAL_MINUTES)) {
+        return;
+    }
+    obs.push_back(Observation { at: now, temperature, pressure: pressure / 100.0 });
+    while obs.front().is_some_and(|o| now - o.at > Duration::hours(WINDOW_HOURS)) {
+        obs.pop_front();
+    }
+}
+
+fn classify(delta: f64, threshold: f64) -> Trend {
+    if delta >= threshold {
+        Trend::Rising
+    } else if delta <= -threshold {
+        Trend::Falling
+    } else {
+        Trend::Steady
+    }
+}
+
+// (气压趋势, 气温趋势)；以最接近 3 小时前的观测为基准，不足 3 小时的按比例折算
+pub fn trends(lng: f64, lat: f64) -> (Option<Trend>, Option<Trend>) {
+    let series = SERIES.lock().unwrap();
+    let Some(obs) = series.get(&key(lng, lat)) else {
+        return (None, None);
+    };
+    let Some(latest) = obs.back() else {
+        return (None, None);
+    };
+    let target = latest.at - Duration::hours(3);
+    let Some(base) = obs
+        .iter()
+        .filter(|o| latest.at - o.at >= Duration::hours(1))
+        .min_by_key(|o| (o.at - target).num_seconds().abs())
+    else {
+        return (None, None);
+    };
+    let scale = 3.0 * 3600.0 / (latest.at - base.at).num_seconds() as f64;
+    (
+        Some(classify((latest.pressure - base.pressure) * scale, PRESSURE_THRESHOLD_HPA)),
+        Some(classify((latest.temperature - base.temperature) * scale, TEMPERATURE_THRESHOLD_C)),
+    )
+}
@@ -0,0 +1,270 @@
+// 台风路径：代理公开台风数据源，归一化为 GeoJSON FeatureCollection 供前端地图叠加
+// 默认数据源为浙江省水利厅台风网（当前活跃台风列表 + 单个台风详情），格式相同的镜像可通过环境变量替换
+// - TYPHOON_URL：活跃台风列表地址，默认 https://typhoon.slt.zj.gov.cn/Api/TyhoonActivity
+// - TYPHOON_DETAIL_URL：单个台风详情地址，`{id}` 替换为台风编号；列表项不含路径点时使用
+// - TYPHOON_CACHE_SECS：归一化结果缓存秒数，默认 600
+// 缓存过期后以 If-None-Match/If-Modified-Since 重新请求数据源，未变化时上游只需返回 304
+//
+// 输出要素（properties.kind）：track 实况路径线、point 实况路径点、forecast 各机构预报路径线
+// 传入 lng/lat 时只返回实况或预报路径经过该点 radius km（默认 1000）范围内的台风
+
+use std::time::Duration;
+
+use axum::{
+    extract::Query,
+    http::{header, HeaderValue, StatusCode},
+    response::Response,
+};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    cache::TtlCache,
+    config::{env_nonempty, env_parse},
+    respond, upstream,
+    upstream_url::UpstreamUrl,
+    ErrorResp,
+};
+
+const DEFAULT_URL: &str = "https://typhoon.slt.zj.gov.cn/Api/TyhoonActivity";
+const DEFAULT_DETAIL_URL: &str = "https://typhoon.slt.zj.gov.cn/Api/TyphoonInfo/{id}";
+const DEFAULT_RADIUS_KM: f64 = 1000.0;
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+// 只缓存一份（全部活跃台风），按位置过滤在缓存之后进行
+static STORMS: Lazy<TtlCache<&'static str, Vec<Storm>>> = Lazy::new(|| {
+    let secs = env_parse("TYPHOON_CACHE_SECS").ok().flatten().unwrap_or(600);
+    TtlCache::new(Duration::from_secs(secs), 1)
+});
+
+#[derive(Deserialize)]
+pub struct TyphoonQuery {
+    lng: Option<f64>,
+    lat: Option<f64>,
+    radius: Option<f64>,
+}
+
+#[derive(Clone)]
+struct TrackPoint {
+    lng: f64,
+    lat: f64,
+    properties: Value,
+}
+
+#[derive(Clone)]
+struct Storm {
+    id: String,
+    name: String,
+    en_name: String,
+    points: Vec<TrackPoint>,
+    // (机构, 预报点)
+    forecasts: Vec<(String, Vec<TrackPoint>)>,
+}
+
+// 数据源中数值常以字符串给出
+fn num(v: &Value, key: &str) -> Option<f64> {
+    match v.get(key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn text(v: &Value, key: &str) -> String {
+    match v.get(key) {
+        Some(Value::String(s)) => s.trim().to_string(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+// 路径点：风速 m/s，气压 hPa，移速 km/h，风圈半径 km
+fn track_point(p: &Value) -> Option<TrackPoint> {
+    let lng = num(p, "lng")?;
+    let lat = num(p, "lat")?;
+    let properties = json!({
+        "time": text(p, "time"),
+        "strength": text(p, "strong"),
+        "power": num(p, "power"),
+        "wind_speed": num(p, "speed"),
+        "pressure": num(p, "pressure"),
+        "move_direction": text(p, "movedirection"),
+        "move_speed": num(p, "movespeed"),
+        "radius7": num(p, "radius7"),
+        "radius10": num(p, "radius10"),
+        "radius12": num(p, "radius12"),
+    });
+    Some(TrackPoint { lng, lat, properties })
+}
+
+fn parse_storm(v: &Value) -> Option<Storm> {
+    let points: Vec<TrackPoint> = v.get("points")?.as_array()?.iter().filter_map(track_point).collect();
+    // 预报取最新实况点上的各机构预报
+    let forecasts = v
+        .get("points")
+        .and_then(|p| p.as_array())
+        .and_then(|p| p.last())
+        .and_then(|p| p.get("forecast"))
+        .and_then(|f| f.as_array())
+        .map(|agencies| {
+            agencies
+                .iter()
+                .filter_map(|a| {
+                    let pts: Vec<TrackPoint> =
+                        a.get("forecastpoints")?.as_array()?.iter().filter_map(track_point).collect();
+                    (!pts.is_empty()).then(|| (text(a, "tm"), pts))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(Storm {
+        id: text(v, "tfbh"),
+        name: text(v, "name"),
+        en_name: text(v, "enname"),
+        points,
+        forecasts,
+    })
+}
+
+// 列表与详情都带条件请求，台风未更新时上游返回 304，仍按原内容重新归一化
+static REVALIDATOR: Lazy<upstream::Revalidator> = Lazy::new(|| upstream::Revalidator::new(64));
+
+async fn get_json(url: &str, url_template: &'static str) -> anyhow::Result<Value> {
+    let resp = REVALIDATOR.get("typhoon", url_template, url).await?;
+    Ok(upstream::parse_json(resp.body.to_vec())?)
+}
+
+async fn fetch_storms() -> anyhow::Result<Vec<Storm>> {
+    let url = env_nonempty("TYPHOON_URL").unwrap_or_else(|| DEFAULT_URL.to_string());
+    let list = get_json(&url, "typhoon list").await?;
+    // 兼容直接返回数组或 { data: [...] } 两种形态
+    let items = list
+        .as_array()
+        .or_else(|| list.get("data").and_then(|d| d.as_array()))
+        .ok_or_else(|| anyhow::anyhow!("台风列表格式无法识别"))?;
+
+    let detail_url = env_nonempty("TYPHOON_DETAIL_URL").unwrap_or_else(|| DEFAULT_DETAIL_URL.to_string());
+    let mut storms = Vec::with_capacity(items.len());
+    for item in items {
+        if item.get("points").is_some() {
+            storms.extend(parse_storm(item));
+            continue;
+        }
+        let id = text(item, "tfbh");
+        if id.is_empty() {
+            continue;
+        }
+        let url = UpstreamUrl::template(&detail_url, &[("id", &id)]);
+        let detail = get_json(url.as_str(), "typhoon detail").await?;
+        // 详情可能是对象或单元素数组
+        let detail = detail.as_array().and_then(|a| a.first()).unwrap_or(&detail);
+        storms.extend(parse_storm(detail));
+    }
+    Ok(storms)
+}
+
+pub fn distance_km(lng1: f64, lat1: f64, lng2: f64, lat2: f64) -> f64 {
+    let (p1, p2) = (lat1.to_radians(), lat2.to_radians());
+    let dp = p2 - p1;
+    let dl = (lng2 - lng1).to_radians();
+    let a = (dp / 2.0).sin().powi(2) + p1.cos() * p2.cos() * (dl / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+// 实况与预报路径上距该点最近的距离
+fn nearest_km(storm: &Storm, lng: f64, lat: f64) -> Option<f64> {
+    storm
+        .points
+        .iter()
+        .chain(storm.forecasts.iter().flat_map(|(_, pts)| pts))
+        .map(|p| distance_km(lng, lat, p.lng, p.lat))
+        .min_by(|a, b| a.total_cmp(b))
+}
+
+fn line(points: &[TrackPoint]) -> Value {
+    json!({
+        "type": "LineString",
+        "coordinates": points.iter().map(|p| [p.lng, p.lat]).collect::<Vec<_>>(),
+    })
+}
+
+fn features(storm: &Storm, distance: Option<f64>) -> Vec<Value> {
+    let mut out = Vec::new();
+    let base = json!({ "id": storm.id, "name": storm.name, "en_name": storm.en_name });
+    let with = |extra: Value| {
+        let mut props = base.clone();
+        if let (Some(props), Some(extra)) = (props.as_object_mut(), extra.as_object()) {
+            props.extend(extra.clone());
+        }
+        props
+    };
+
+    out.push(json!({
+        "type": "Feature",
+        "geometry": line(&storm.points),
+        "properties": with(json!({
+            "kind": "track",
+            "latest": storm.points.last().map(|p| &p.properties),
+            "distance_km": distance.map(|d| d.round()),
+        })),
+    }));
+    for p in &storm.points {
+        let mut props = with(p.properties.clone());
+        props["kind"] = json!("point");
+        out.push(json!({
+            "type": "Feature",
+            "geometry": { "type": "Point", "coordinates": [p.lng, p.lat] },
+            "properties": props,
+        }));
+    }
+    // 预报路径从最新实况点起笔，便于与实况线衔接
+    for (agency, pts) in &storm.forecasts {
+        let mut pts = pts.clone();
+        if let Some(latest) = storm.points.last() {
+            pts.insert(0, latest.clone());
+        }
+        out.push(json!({
+            "type": "Feature",
+            "geometry": line(&pts),
+            "properties": with(json!({ "kind": "forecast", "agency": agency })),
+        }));
+    }
+    out
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn typhoon(Query(q): Query<TyphoonQuery>) -> Response {
+    let storms = match STORMS.get(&"active") {
+        Some(hit) => hit,
+        None => match fetch_storms().await {
+            Ok(storms) => {
+                STORMS.insert("active", storms.clone());
+                storms
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "typhoon fetch failed");
+                return respond::json(StatusCode::BAD_GATEWAY, &ErrorResp::new(format!("获取台风数据失败: {}", e)));
+            }
+        },
+    };
+
+    let near = q.lng.zip(q.lat);
+    let radius = q.radius.unwrap_or(DEFAULT_RADIUS_KM);
+    let features: Vec<Value> = storms
+        .iter()
+        .filter(|s| !s.points.is_empty())
+        .filter_map(|s| match near {
+            Some((lng, lat)) => {
+                let d = nearest_km(s, lng, lat)?;
+                (d <= radius).then(|| features(s, Some(d)))
+            }
+            None => Some(features(s, None)),
+        })
+        .flatten()
+        .collect();
+
+    let mut res = respond::json(StatusCode::OK, &json!({ "type": "FeatureCollection", "features": features }));
+    res.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("public, max-age=300"));
+    res
+}
@@ -0,0 +1,117 @@
+// 彩云请求预算：限制每分钟发往彩云的请求数，用户请求优先，后台请求排队匀速发送，避免后台任务挤占用户的额度
+// 用户请求从不等待，但计入预算；后台请求（定时任务、缓存的后台刷新）只在最近 60 秒的请求数低于「预算 - 保留份额」时发送，
+// 且相邻两次至少间隔 60/预算 秒，多个后台请求按到达顺序排队
+// - CAIYUN_RPM：每分钟请求数上限，设置后开启
+// - CAIYUN_RPM_RESERVE_PERCENT：为用户请求保留的比例，默认 20
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::OnceCell;
+use tracing::{debug, info};
+
+use crate::config::env_parse;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+struct Budget {
+    // 后台请求可使用的窗口内请求数
+    background_limit: usize,
+    interval: Duration,
+    // 最近 60 秒内各请求的发送时间
+    sent: Mutex<VecDeque<Instant>>,
+    // 后台请求排队（tokio Mutex 按先来先得），内含上一次后台请求的发送时间
+    queue: tokio::sync::Mutex<Option<Instant>>,
+}
+
+static BUDGET: OnceCell<Budget> = OnceCell::new();
+
+tokio::task_local! {
+    static BACKGROUND: ();
+}
+
+pub fn init_from_env() -> anyhow::Result<()> {
+    let Some(rpm) = env_parse::<u32>("CAIYUN_RPM")? else {
+        return Ok(());
+    };
+    if rpm == 0 {
+        anyhow::bail!("CAIYUN_RPM 应为正整数");
+    }
+    let reserve = env_parse::<f64>("CAIYUN_RPM_RESERVE_PERCENT")?.unwrap_or(20.0);
+    if !(0.0..100.0).contains(&reserve) {
+        anyhow::bail!("CAIYUN_RPM_RESERVE_PERCENT 应在 0 到 100 之间（不含 100）");
+    }
+    let background_limit = ((rpm as f64 * (1.0 - reserve / 100.0)).floor() as usize).max(1);
+    info!(rpm, background_limit, "caiyun request budget enabled");
+    let _ = BUDGET.set(Budget {
+        background_limit,
+        interval: WINDOW / rpm,
+        sent: Mutex::new(VecDeque::new()),
+        queue: tokio::sync::Mutex::new(None),
+    });
+    Ok(())
+}
+
+// 以后台优先级运行 future（其中的彩云请求排队发送）
+pub async fn background<F: Future>(fut: F) -> F::Output {
+    BACKGROUND.scope((), fut).await
+}
+
+fn is_background() -> bool {
+    BACKGROUND.try_with(|_| ()).is_ok()
+}
+
+fn prune(sent: &mut VecDeque<Instant>, now: Instant) {
+    while sent.front().is_some_and(|t| now.duration_since(*t) >= WINDOW) {
+        sent.pop_front();
+    }
+}
+
+impl Budget {
+    // 可以发送时记录并返回 None，否则返回需等待的时长
+    fn try_send(&self, last_background: Option<Instant>) -> Option<Duration> {
+        let now = Instant::now();
+        let mut sent = self.sent.lock().unwrap();
+        prune(&mut sent, now);
+        let pace = last_background.map_or(Duration::ZERO, |t| (t + self.interval).saturating_duration_since(now));
+        // 窗口已满时等到足够多的旧请求移出窗口
+        let full = match sent.len().checked_sub(self.background_limit) {
+            Some(over) => (sent[over] + WINDOW).saturating_duration_since(now),
+            None => Duration::ZERO,
+        };
+        let wait = pace.max(full);
+        if wait.is_zero() {
+            sent.push_back(now);
+            None
+        } else {
+            Some(wait)
+        }
+    }
+}
+
+// 由 upstream::send 在发往彩云前调用
+pub async fn acquire(upstream: &str) {
+    let Some(budget) = BUDGET.get().filter(|_| upstream == "caiyun") else {
+        return;
+    };
+    if !is_background() {
+        let now = Instant::now();
+        let mut sent = budget.sent.lock().unwrap();
+        prune(&mut sent, now);
+        sent.push_back(now);
+        return;
+    }
+    let mut last = budget.queue.lock().await;
+    let started = Instant::now();
+    while let Some(wait) = budget.try_send(*last) {
+        tokio::time::sleep(wait).await;
+    }
+    *last = Some(Instant::now());
+    if started.elapsed() >= Duration::from_millis(100) {
+        debug!(waited_ms = started.elapsed().as_millis() as u64, "background caiyun request paced");
+    }
+}
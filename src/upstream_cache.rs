@@ -0,0 +1,651 @@
+// 上游结果缓存：天气原始数据与逆地理结果按坐标网格缓存
+// 过期后在 CACHE_STALE_SECS 内仍返回旧数据（响应附 Warning: 110）并在后台刷新；开启持久化后写入 SQLite，重启时载入，
+// 避免每次部署后所有请求同时打到上游。同一条目未命中时只有一个请求访问上游，其余等待其结果（单飞）
+// - WEATHER_CACHE_SECS：天气数据缓存秒数，默认 0（不缓存），建议 300
+// - GEOCODE_CACHE_SECS：逆地理结果缓存秒数，默认 0（不缓存），建议 86400
+// - CACHE_STALE_SECS：过期后仍可返回旧数据的秒数，默认 3600
+// - CACHE_MAX_ENTRIES：内存中保留的条目上限，默认 10000
+// - CACHE_PERSIST=1：持久化到 CACHE_DB（默认 DATA_DIR/cache.sqlite3），过期条目由任务 cache_prune 每小时清理；
+//   写库由单独的写入线程按顺序执行，请求只更新内存，退出时 flush 等待写完
+// - REDIS_URL：改用 Redis 存储（需 `redis` feature，见 redis_cache.rs），多实例共享条目与单飞锁，此时忽略上面两项
+// 自带彩云 token / 高德 key 的租户单独缓存（key 末尾附 `@租户名`），不与全局配置或其他租户共用条目
+// 管理接口（admin.rs 挂载）：列出条目、按前缀或全部清除，以及查看某坐标的请求会命中什么
+
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    future::Future,
+    sync::{mpsc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Query, Request},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use futures_util::future::BoxFuture;
+use once_cell::sync::OnceCell;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::{
+    config::{env_flag, env_nonempty, env_parse},
+    respond, scheduler,
+    tenant::{self, Tenant},
+    upstream_budget, ErrorResp,
+};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS entries (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL,
+        stored_at INTEGER NOT NULL,
+        expires_at INTEGER NOT NULL
+    );
+";
+
+// 单飞锁的最长持有时间（持有者异常退出时由此兜底），以及等待者查看结果的间隔
+const LOCK_TTL: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Weather,
+    Geocode,
+}
+
+impl Kind {
+    fn prefix(self) -> &'static str {
+        match self {
+            Kind::Weather => "weather",
+            Kind::Geocode => "geocode",
+        }
+    }
+}
+
+// 时间均为 Unix 毫秒
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub value: Value,
+    pub stored_at: i64,
+    pub expires_at: i64,
+}
+
+// 缓存存储：本地（内存，可选 SQLite 持久化）或 Redis
+// 条目需保留到 expires_at 之后的 stale 窗口结束；锁用于单飞，获取失败表示已有请求在访问上游
+pub trait Backend: Send + Sync {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Entry>>;
+
+    fn put<'a>(&'a self, key: &'a str, entry: Entry) -> BoxFuture<'a, ()>;
+
+    fn try_lock<'a>(&'a self, key: &'a str, ttl: Duration) -> BoxFuture<'a, bool>;
+
+    fn unlock<'a>(&'a self, key: &'a str) -> BoxFuture<'a, ()>;
+
+    // 列出 / 删除 key_matches(key, prefix) 的条目，prefix 为空时表示全部
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<(String, Entry)>>>;
+
+    fn purge<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, anyhow::Result<usize>>;
+
+    // 清理超出 stale 窗口的条目；自带过期的存储无需实现
+    fn prune(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    // 等待尚未落盘的写入完成（退出前调用）；直接写入的存储无需实现
+    fn flush(&self) {}
+}
+
+struct Cache {
+    backend: Box<dyn Backend>,
+    weather_ttl: i64,
+    geocode_ttl: i64,
+    stale: i64,
+}
+
+static CACHE: OnceCell<Cache> = OnceCell::new();
+
+tokio::task_local! {
+    static SERVED_STALE: Cell<bool>;
+}
+
+pub fn now_ms() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+fn secs_ms(key: &str, default: u64) -> anyhow::Result<i64> {
+    Ok(env_parse::<u64>(key)?.unwrap_or(default).saturating_mul(1000) as i64)
+}
+
+struct Local {
+    entries: Mutex<HashMap<String, Entry>>,
+    stale: i64,
+    max_entries: usize,
+    // 开启持久化时为写入线程的队列
+    writer: Option<mpsc::Sender<Op>>,
+    // key -> 锁到期时间
+    locks: Mutex<HashMap<String, Instant>>,
+}
+
+// 持久化操作，由写入线程依次执行，避免请求路径上的同步磁盘 I/O 与锁竞争
+enum Op {
+    Put(String, Entry),
+    // 按前缀删除，结果为删除的条目数
+    Purge(String, tokio::sync::oneshot::Sender<anyhow::Result<usize>>),
+    // 删除 expires_at 早于该时刻的条目
+    Prune(i64),
+    // 之前的操作都已执行
+    Flush(mpsc::Sender<()>),
+}
+
+fn purge_db(conn: &Connection, prefix: &str) -> anyhow::Result<usize> {
+    let keys = conn
+        .prepare("SELECT key FROM entries")?
+        .query_map([], |r| r.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut removed = 0;
+    for key in keys.iter().filter(|k| key_matches(k, prefix)) {
+        removed += conn.execute("DELETE FROM entries WHERE key = ?1", [key])?;
+    }
+    Ok(removed)
+}
+
+fn spawn_writer(conn: Connection) -> anyhow::Result<mpsc::Sender<Op>> {
+    let (tx, rx) = mpsc::channel::<Op>();
+    std::thread::Builder::new().name("cache-writer".into()).spawn(move || {
+        for op in rx {
+            match op {
+                Op::Put(key, entry) => {
+                    let result = conn.execute(
+                        "INSERT OR REPLACE INTO entries (key, value, stored_at, expires_at) VALUES (?1, ?2, ?3, ?4)",
+                        params![key, entry.value.to_string(), entry.stored_at, entry.expires_at],
+                    );
+                    if let Err(e) = result {
+                        warn!("persist cache entry failed: {}", e);
+                    }
+                }
+                Op::Purge(prefix, done) => {
+                    let _ = done.send(purge_db(&conn, &prefix));
+                }
+                Op::Prune(cutoff) => match conn.execute("DELETE FROM entries WHERE expires_at < ?1", [cutoff]) {
+                    Ok(removed) => info!("pruned {} expired cache entr(ies)", removed),
+                    Err(e) => warn!("prune cache entries failed: {}", e),
+                },
+                Op::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    })?;
+    Ok(tx)
+}
+
+fn open_db(stale: i64, max_entries: usize) -> anyhow::Result<(Connection, HashMap<String, Entry>)> {
+    let path = env_nonempty("CACHE_DB").unwrap_or_else(|| {
+        let dir = env_nonempty("DATA_DIR").unwrap_or_else(|| "data".into());
+        format!("{}/cache.sqlite3", dir.trim_end_matches('/'))
+    });
+    if let Some(dir) = std::path::Path::new(&path).parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let conn = Connection::open(&path).map_err(|e| anyhow::anyhow!("无法打开 {}: {}", path, e))?;
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
+    conn.execute_batch(SCHEMA)?;
+    conn.execute("DELETE FROM entries WHERE expires_at < ?1", [now_ms() - stale])?;
+    let mut entries = HashMap::new();
+    {
+        let mut stmt = conn.prepare("SELECT key, value, stored_at, expires_at FROM entries ORDER BY stored_at DESC LIMIT ?1")?;
+        let rows = stmt.query_map([max_entries as i64], |r| {
+            Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, i64>(2)?, r.get::<_, i64>(3)?))
+        })?;
+        for row in rows {
+            let (key, value, stored_at, expires_at) = row?;
+            // 无法解析的条目（格式变更等）直接丢弃
+            if let Ok(value) = serde_json::from_str(&value) {
+                entries.insert(key, Entry { value, stored_at, expires_at });
+            }
+        }
+    }
+    info!("loaded {} cache entr(ies) from {}", entries.len(), path);
+    Ok((conn, entries))
+}
+
+impl Local {
+    fn from_env(stale: i64) -> anyhow::Result<Self> {
+        let max_entries = env_parse::<usize>("CACHE_MAX_ENTRIES")?.unwrap_or(10_000).max(1);
+        let (writer, entries) = if env_flag("CACHE_PERSIST") {
+            let (conn, entries) = open_db(stale, max_entries)?;
+            (Some(spawn_writer(conn)?), entries)
+        } else {
+            (None, HashMap::new())
+        };
+        Ok(Local { entries: Mutex::new(entries), stale, max_entries, writer, locks: Mutex::new(HashMap::new()) })
+    }
+
+    // 写入线程只在进程退出时结束，发送失败仅记录
+    fn send(&self, op: Op) {
+        if let Some(writer) = &self.writer {
+            if writer.send(op).is_err() {
+                warn!("cache writer has stopped");
+            }
+        }
+    }
+
+    fn insert(&self, key: &str, entry: Entry) {
+        let persisted = self.writer.is_some().then(|| entry.clone());
+        self.insert_memory(key, entry);
+        if let Some(entry) = persisted {
+            self.send(Op::Put(key.to_string(), entry));
+        }
+    }
+
+    fn insert_memory(&self, key: &str, entry: Entry) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries && !entries.contains_key(key) {
+            let cutoff = now_ms() - self.stale;
+            entries.retain(|_, e| e.expires_at >= cutoff);
+            if entries.len() >= self.max_entries {
+                if let Some(oldest) = entries.iter().min_by_key(|(_, e)| e.stored_at).map(|(k, _)| k.clone()) {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        entries.insert(key.to_string(), entry);
+    }
+}
+
+impl Backend for Local {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Entry>> {
+        let entry = self.entries.lock().unwrap().get(key).cloned();
+        Box::pin(async move { entry })
+    }
+
+    fn put<'a>(&'a self, key: &'a str, entry: Entry) -> BoxFuture<'a, ()> {
+        self.insert(key, entry);
+        Box::pin(async {})
+    }
+
+    fn try_lock<'a>(&'a self, key: &'a str, ttl: Duration) -> BoxFuture<'a, bool> {
+        let now = Instant::now();
+        let mut locks = self.locks.lock().unwrap();
+        let acquired = match locks.get(key) {
+            Some(until) if *until > now => false,
+            _ => {
+                locks.insert(key.to_string(), now + ttl);
+                true
+            }
+        };
+        Box::pin(async move { acquired })
+    }
+
+    fn unlock<'a>(&'a self, key: &'a str) -> BoxFuture<'a, ()> {
+        self.locks.lock().unwrap().remove(key);
+        Box::pin(async {})
+    }
+
+    fn list<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<(String, Entry)>>> {
+        let listed = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(k, _)| key_matches(k, prefix))
+            .map(|(k, e)| (k.clone(), e.clone()))
+            .collect();
+        Box::pin(async move { Ok(listed) })
+    }
+
+    fn purge<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, anyhow::Result<usize>> {
+        let purged = {
+            let mut entries = self.entries.lock().unwrap();
+            let before = entries.len();
+            entries.retain(|k, _| !key_matches(k, prefix));
+            before - entries.len()
+        };
+        Box::pin(async move {
+            let Some(writer) = &self.writer else {
+                return Ok(purged);
+            };
+            // 库中可能有超出 CACHE_MAX_ENTRIES 未载入内存的条目，一并删除并按库中数量计
+            let (done, removed) = tokio::sync::oneshot::channel();
+            writer.send(Op::Purge(prefix.to_string(), done)).map_err(|_| anyhow::anyhow!("缓存写入线程已退出"))?;
+            let removed = removed.await.map_err(|_| anyhow::anyhow!("缓存写入线程已退出"))??;
+            Ok(purged.max(removed))
+        })
+    }
+
+    fn prune(&self) -> anyhow::Result<()> {
+        let cutoff = now_ms() - self.stale;
+        self.entries.lock().unwrap().retain(|_, e| e.expires_at >= cutoff);
+        self.send(Op::Prune(cutoff));
+        Ok(())
+    }
+
+    fn flush(&self) {
+        let (done, flushed) = mpsc::channel();
+        if self.writer.as_ref().is_some_and(|w| w.send(Op::Flush(done)).is_ok())
+            && flushed.recv_timeout(Duration::from_secs(5)).is_err()
+        {
+            warn!("cache writer did not finish within 5s");
+        }
+    }
+}
+
+fn backend_from_env(stale: i64) -> anyhow::Result<Box<dyn Backend>> {
+    if let Some(url) = env_nonempty("REDIS_URL") {
+        #[cfg(feature = "redis")]
+        return Ok(Box::new(crate::redis_cache::RedisBackend::from_env(&url, stale)?));
+        #[cfg(not(feature = "redis"))]
+        warn!("已设置 REDIS_URL={}，但未启用 `redis` feature，使用本地缓存", url);
+    }
+    let local = Local::from_env(stale)?;
+    if local.writer.is_some() {
+        scheduler::register("cache_prune", "0 * * * *", || async {
+            if let Some(cache) = CACHE.get() {
+                cache.backend.prune()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(Box::new(local))
+}
+
+// 启动时读取配置；开启持久化时载入未过期太久的条目并注册清理任务
+pub fn init_from_env() -> anyhow::Result<()> {
+    let weather_ttl = secs_ms("WEATHER_CACHE_SECS", 0)?;
+    let geocode_ttl = secs_ms("GEOCODE_CACHE_SECS", 0)?;
+    if weather_ttl == 0 && geocode_ttl == 0 {
+        return Ok(());
+    }
+    let stale = secs_ms("CACHE_STALE_SECS", 3600)?;
+    let backend = backend_from_env(stale)?;
+    let _ = CACHE.set(Cache { backend, weather_ttl, geocode_ttl, stale });
+    Ok(())
+}
+
+// 等待持久化写入完成，退出前调用
+pub fn flush() {
+    if let Some(cache) = CACHE.get() {
+        cache.backend.flush();
+    }
+}
+
+// 该类条目是否开启缓存
+pub fn enabled(kind: Kind) -> bool {
+    CACHE.get().is_some_and(|c| c.ttl(kind) > 0)
+}
+
+impl Cache {
+    fn ttl(&self, kind: Kind) -> i64 {
+        match kind {
+            Kind::Weather => self.weather_ttl,
+            Kind::Geocode => self.geocode_ttl,
+        }
+    }
+
+    // 返回写入时间
+    async fn store(&self, key: &str, value: Value, ttl: i64) -> i64 {
+        let now = now_ms();
+        self.backend.put(key, Entry { value, stored_at: now, expires_at: now + ttl }).await;
+        now
+    }
+
+    async fn fresh(&self, key: &str) -> Option<Entry> {
+        self.backend.get(key).await.filter(|e| e.expires_at > now_ms())
+    }
+}
+
+// 持有单飞锁期间若请求被取消（如路由超时），释放时仍需解锁
+struct LockGuard {
+    cache: &'static Cache,
+    key: String,
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        let cache = self.cache;
+        let key = std::mem::take(&mut self.key);
+        tokio::spawn(async move { cache.backend.unlock(&key).await });
+    }
+}
+
+// 条目所属的租户：租户自带该类上游的 token / key 时为租户名，否则与全局配置共用（None）
+fn scope_of(kind: Kind, tenant: Option<&'static Tenant>) -> Option<&'static str> {
+    let own = |t: &Tenant| match kind {
+        Kind::Weather => t.has_caiyun_token(),
+        Kind::Geocode => t.has_amap_key(),
+    };
+    tenant.filter(|t| own(t)).map(|t| t.name.as_str())
+}
+
+// 当前请求的条目所属租户，整形结果等派生缓存也需按此区分
+pub fn scope(kind: Kind) -> Option<&'static str> {
+    scope_of(kind, tenant::current())
+}
+
+// 天气按两位小数（约 1 km）归并，地址需要更细，按三位小数（约 100 m）
+fn key(kind: Kind, lng: f64, lat: f64, scope: Option<&str>) -> String {
+    let key = match kind {
+        Kind::Weather => format!("{}:{:.2},{:.2}", kind.prefix(), lng, lat),
+        Kind::Geocode => format!("{}:{:.3},{:.3}", kind.prefix(), lng, lat),
+    };
+    match scope {
+        Some(name) => format!("{}@{}", key, name),
+        None => key,
+    }
+}
+
+// prefix 可为完整 key 的前缀（如 `weather:116.4`），也可只是坐标部分（如 `116.4`，同时匹配天气与地址）
+pub fn key_matches(key: &str, prefix: &str) -> bool {
+    key.starts_with(prefix) || key.split_once(':').is_some_and(|(_, coords)| coords.starts_with(prefix))
+}
+
+fn mark_stale() {
+    let _ = SERVED_STALE.try_with(|s| s.set(true));
+}
+
+// 命中未过期条目直接返回；过期但在 stale 窗口内时返回旧值并后台刷新；否则调用 fetch 并写入缓存
+// 未命中时先取单飞锁，取不到则等待持锁者写入结果，等待超过 LOCK_TTL 后自行请求
+// fetch 需可在后台任务中再次调用，因此要求 'static
+pub async fn get_or_fetch<F, Fut, E>(kind: Kind, lng: f64, lat: f64, fetch: F) -> Result<Value, E>
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Value, E>> + Send + 'static,
+{
+    get_or_fetch_stamped(kind, lng, lat, fetch).await.map(|(value, _)| value)
+}
+
+// 同 get_or_fetch，另返回所用条目的写入时间（未开启缓存时为 None）：时间相同即同一份上游结果，
+// 调用方可据此复用由它派生的结果，而无需比较内容
+pub async fn get_or_fetch_stamped<F, Fut, E>(kind: Kind, lng: f64, lat: f64, fetch: F) -> Result<(Value, Option<i64>), E>
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Value, E>> + Send + 'static,
+{
+    let Some(cache) = CACHE.get().filter(|c| c.ttl(kind) > 0) else {
+        return Ok((fetch().await?, None));
+    };
+    let key = key(kind, lng, lat, scope(kind));
+    let ttl = cache.ttl(kind);
+    let now = now_ms();
+    match cache.backend.get(&key).await {
+        Some(entry) if entry.expires_at > now => return Ok((entry.value, Some(entry.stored_at))),
+        Some(entry) if entry.expires_at + cache.stale > now => {
+            mark_stale();
+            if cache.backend.try_lock(&key, LOCK_TTL).await {
+                tokio::spawn(async move {
+                    let _guard = LockGuard { cache, key: key.clone() };
+                    let Ok(value) = upstream_budget::background(fetch()).await else {
+                        return;
+                    };
+                    cache.store(&key, value, ttl).await;
+                });
+            }
+            return Ok((entry.value, Some(entry.stored_at)));
+        }
+        _ => {}
+    }
+
+    let deadline = Instant::now() + LOCK_TTL;
+    let mut waited = false;
+    let locked = loop {
+        if cache.backend.try_lock(&key, LOCK_TTL).await {
+            break true;
+        }
+        if Instant::now() >= deadline {
+            break false;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+        waited = true;
+        if let Some(entry) = cache.fresh(&key).await {
+            return Ok((entry.value, Some(entry.stored_at)));
+        }
+    };
+    let _guard = locked.then(|| LockGuard { cache, key: key.clone() });
+    // 等待期间持锁者可能刚写入并解锁
+    if waited {
+        if let Some(entry) = cache.fresh(&key).await {
+            return Ok((entry.value, Some(entry.stored_at)));
+        }
+    }
+    let value = fetch().await?;
+    let stored_at = cache.store(&key, value.clone(), ttl).await;
+    Ok((value, Some(stored_at)))
+}
+
+// 本请求用到了过期缓存时附加 Warning 头
+pub async fn middleware(req: Request, next: Next) -> Response {
+    SERVED_STALE
+        .scope(Cell::new(false), async move {
+            let mut res = next.run(req).await;
+            if SERVED_STALE.with(Cell::get) && !res.headers().contains_key(header::WARNING) {
+                res.headers_mut().insert(header::WARNING, HeaderValue::from_static("110 - \"Response is Stale\""));
+            }
+            res
+        })
+        .await
+}
+
+fn disabled_response() -> Response {
+    respond::json(
+        StatusCode::NOT_FOUND,
+        &ErrorResp::new("未开启上游结果缓存（WEATHER_CACHE_SECS/GEOCODE_CACHE_SECS）"),
+    )
+}
+
+fn backend_error(e: anyhow::Error) -> Response {
+    warn!("cache admin failed: {}", e);
+    respond::json(StatusCode::INTERNAL_SERVER_ERROR, &ErrorResp::new("缓存存储访问失败"))
+}
+
+fn state(cache: &Cache, entry: &Entry, now: i64) -> &'static str {
+    if entry.expires_at > now {
+        "fresh"
+    } else if entry.expires_at + cache.stale > now {
+        "stale"
+    } else {
+        "expired"
+    }
+}
+
+fn describe(cache: &Cache, key: &str, entry: &Entry, now: i64) -> Value {
+    json!({
+        "key": key,
+        "state": state(cache, entry, now),
+        "age_secs": (now - entry.stored_at) / 1000,
+        // 负数表示已过期的秒数
+        "ttl_secs": (entry.expires_at - now).div_euclid(1000),
+        "size": entry.value.to_string().len(),
+    })
+}
+
+#[derive(Deserialize)]
+pub struct AdminQuery {
+    prefix: Option<String>,
+    limit: Option<usize>,
+}
+
+// 条目按 key 排序，默认最多返回 100 条；total 为匹配的总数
+pub async fn admin_list(Query(q): Query<AdminQuery>) -> Response {
+    let Some(cache) = CACHE.get() else {
+        return disabled_response();
+    };
+    let mut entries = match cache.backend.list(q.prefix.as_deref().unwrap_or("")).await {
+        Ok(entries) => entries,
+        Err(e) => return backend_error(e),
+    };
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let now = now_ms();
+    let listed: Vec<Value> = entries
+        .iter()
+        .take(q.limit.unwrap_or(100))
+        .map(|(key, entry)| describe(cache, key, entry, now))
+        .collect();
+    respond::json(StatusCode::OK, &json!({ "total": entries.len(), "entries": listed }))
+}
+
+// 未指定 prefix 时清空全部条目
+pub async fn admin_purge(Query(q): Query<AdminQuery>) -> Response {
+    let Some(cache) = CACHE.get() else {
+        return disabled_response();
+    };
+    let prefix = q.prefix.as_deref().unwrap_or("");
+    match cache.backend.purge(prefix).await {
+        Ok(purged) => {
+            info!(prefix, purged, "cache purged");
+            respond::json(StatusCode::OK, &json!({ "purged": purged }))
+        }
+        Err(e) => backend_error(e),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LookupQuery {
+    lng: f64,
+    lat: f64,
+    // 按该租户的请求演练
+    tenant: Option<String>,
+}
+
+// 演练：给出该坐标的天气与地址请求此刻会命中的 key 与结果（hit/stale/miss/disabled），不访问上游也不改动缓存
+pub async fn admin_lookup(Query(q): Query<LookupQuery>) -> Response {
+    let Some(cache) = CACHE.get() else {
+        return disabled_response();
+    };
+    let tenant = match q.tenant.as_deref().filter(|s| !s.is_empty()) {
+        Some(name) => match tenant::by_name(name) {
+            Some(t) => Some(t),
+            None => return respond::json(StatusCode::NOT_FOUND, &ErrorResp::new(format!("租户不存在: {}", name))),
+        },
+        None => None,
+    };
+    let now = now_ms();
+    let mut body = serde_json::Map::new();
+    for kind in [Kind::Weather, Kind::Geocode] {
+        let key = key(kind, q.lng, q.lat, scope_of(kind, tenant));
+        let info = if cache.ttl(kind) == 0 {
+            json!({ "key": key, "result": "disabled" })
+        } else {
+            match cache.backend.get(&key).await {
+                Some(entry) => {
+                    let mut info = describe(cache, &key, &entry, now);
+                    let result = match state(cache, &entry, now) {
+                        "fresh" => "hit",
+                        "stale" => "stale",
+                        _ => "miss",
+                    };
+                    info["result"] = result.into();
+                    info
+                }
+                None => json!({ "key": key, "result": "miss" }),
+            }
+        };
+        body.insert(kind.prefix().to_string(), info);
+    }
+    respond::json(StatusCode::OK, &Value::Object(body))
+}
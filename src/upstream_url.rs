@@ -0,0 +1,161 @@
+// 上游请求地址构造：路径段与查询参数逐个编码，token、key 等以 secret 方式加入
+// Debug 与 Display 输出中 secret 显示为 ***，可直接写入日志；发请求时用 as_str 取完整地址
+//   UpstreamUrl::new(&base).path("/v2.6/").secret(token).segment(format_args!("{},{}", lng, lat)).path("/weather").query("lang", "zh_CN")
+// 基础地址原样保留（去掉末尾 /），由调用方在启动时校验
+// 可配置的地址模板（如 RADAR_TILE_URL）用 template 构造，占位符的值一律编码
+
+use std::fmt::{self, Display, Write};
+
+const REDACTED: &str = "***";
+
+#[derive(Clone)]
+pub struct UpstreamUrl {
+    url: String,
+    // 同一地址，secret 替换为 REDACTED
+    redacted: String,
+    has_query: bool,
+}
+
+// 路径中保留 RFC 3986 的 pchar（非保留字符、子分隔符、: 与 @），其余按 UTF-8 字节百分号编码
+fn encode_path(raw: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for b in raw.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => out.push(b as char),
+            b'-' | b'.' | b'_' | b'~' | b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' | b':'
+            | b'@' => out.push(b as char),
+            b'/' if keep_slash => out.push('/'),
+            _ => {
+                let _ = write!(out, "%{:02X}", b);
+            }
+        }
+    }
+    out
+}
+
+impl UpstreamUrl {
+    pub fn new(base: &str) -> Self {
+        let base = base.trim_end_matches('/');
+        Self { url: base.to_string(), redacted: base.to_string(), has_query: false }
+    }
+
+    // 模板中的 {name} 替换为编码后的值（路径与查询参数中均安全），模板其余部分原样保留
+    pub fn template(template: &str, vars: &[(&str, &str)]) -> Self {
+        let mut url = template.to_string();
+        for (name, value) in vars {
+            url = url.replace(&format!("{{{}}}", name), &urlencoding::encode(value));
+        }
+        Self { redacted: url.clone(), has_query: url.contains('?'), url }
+    }
+
+    fn push(mut self, plain: &str, shown: &str) -> Self {
+        debug_assert!(!self.has_query, "路径须在查询参数之前");
+        self.url.push_str(plain);
+        self.redacted.push_str(shown);
+        self
+    }
+
+    // 固定路径，如 "/v2.6/"；保留其中的 /
+    pub fn path(self, path: &str) -> Self {
+        let encoded = encode_path(path, true);
+        self.push(&encoded, &encoded)
+    }
+
+    // 一个路径段：先补 /，其中的 / 与 ? 等一并编码
+    pub fn segment(self, value: impl Display) -> Self {
+        let encoded = format!("/{}", encode_path(&value.to_string(), false));
+        self.push(&encoded, &encoded)
+    }
+
+    // 直接接在当前路径后的敏感片段（不补 /），如彩云 token、Telegram 的 bot<token>
+    pub fn secret(self, value: &str) -> Self {
+        self.push(&encode_path(value, false), REDACTED)
+    }
+
+    fn pair(mut self, key: &str, plain: &str, shown: &str) -> Self {
+        let sep = if self.has_query { '&' } else { '?' };
+        self.has_query = true;
+        let key = urlencoding::encode(key);
+        let _ = write!(self.url, "{}{}={}", sep, key, plain);
+        let _ = write!(self.redacted, "{}{}={}", sep, key, shown);
+        self
+    }
+
+    pub fn query(self, key: &str, value: impl Display) -> Self {
+        let encoded = urlencoding::encode(&value.to_string()).into_owned();
+        self.pair(key, &encoded, &encoded)
+    }
+
+    pub fn secret_query(self, key: &str, value: &str) -> Self {
+        let encoded = urlencoding::encode(value).into_owned();
+        self.pair(key, &encoded, REDACTED)
+    }
+
+    // 完整地址，只用于发出请求
+    pub fn as_str(&self) -> &str {
+        &self.url
+    }
+}
+
+impl fmt::Display for UpstreamUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.redacted)
+    }
+}
+
+impl fmt::Debug for UpstreamUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("UpstreamUrl").field(&self.redacted).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UpstreamUrl;
+
+    #[test]
+    fn builds_caiyun_style_path_with_secret_token() {
+        let url = UpstreamUrl::new("https://api.caiyunapp.com/")
+            .path("/v2.6/")
+            .secret("tok/en?")
+            .segment(format_args!("{},{}", 116.41, -39.9))
+            .path("/weather")
+            .query("alert", true)
+            .query("dailysteps", 3);
+        assert_eq!(url.as_str(), "https://api.caiyunapp.com/v2.6/tok%2Fen%3F/116.41,-39.9/weather?alert=true&dailysteps=3");
+        assert_eq!(url.to_string(), "https://api.caiyunapp.com/v2.6/***/116.41,-39.9/weather?alert=true&dailysteps=3");
+        assert!(!format!("{:?}", url).contains("tok"));
+    }
+
+    #[test]
+    fn encodes_query_values_and_redacts_secret_params() {
+        let url = UpstreamUrl::new("https://restapi.amap.com")
+            .path("/v3/place/text")
+            .secret_query("key", "k&y=1")
+            .query("keywords", "北京 朝阳&x=1")
+            .query("offset", 5);
+        assert_eq!(
+            url.as_str(),
+            "https://restapi.amap.com/v3/place/text?key=k%26y%3D1&keywords=%E5%8C%97%E4%BA%AC%20%E6%9C%9D%E9%98%B3%26x%3D1&offset=5"
+        );
+        assert_eq!(
+            url.to_string(),
+            "https://restapi.amap.com/v3/place/text?key=***&keywords=%E5%8C%97%E4%BA%AC%20%E6%9C%9D%E9%98%B3%26x%3D1&offset=5"
+        );
+    }
+
+    #[test]
+    fn segments_escape_separators_and_secrets_attach_inline() {
+        let url = UpstreamUrl::new("https://api.telegram.org").path("/bot").secret("123:ABC").segment("send/Message?");
+        assert_eq!(url.as_str(), "https://api.telegram.org/bot123:ABC/send%2FMessage%3F");
+        assert_eq!(url.to_string(), "https://api.telegram.org/bot***/send%2FMessage%3F");
+    }
+
+    #[test]
+    fn template_placeholders_are_encoded() {
+        let url = UpstreamUrl::template("https://tiles.test/{z}/{x}/{y}.png?key=abc", &[("z", "3"), ("x", "4"), ("y", "5")]);
+        assert_eq!(url.as_str(), "https://tiles.test/3/4/5.png?key=abc");
+        let url = UpstreamUrl::template("https://typhoon.test/Api/TyphoonInfo/{id}", &[("id", "../2501?x=1&{id}")]);
+        assert_eq!(url.as_str(), "https://typhoon.test/Api/TyphoonInfo/..%2F2501%3Fx%3D1%26%7Bid%7D");
+    }
+}
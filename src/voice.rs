@@ -0,0 +1,86 @@
+// 语音助手适配：POST /api/v1/voice 接收智能音箱技能（小爱、Alexa 等）转发的意图，返回可直接朗读的文本与卡片
+// 请求体 { "intent": "weather", "city": "上海", "day": "tomorrow", "lang": "zh_CN" }：
+//   city 省略时为默认城市；day 为 0-2、today/tomorrow/day_after_tomorrow 或 今天/明天/后天，默认今天；lang 同 /api/v1/weather/brief
+// 响应 { speech, card: { title, text, icon, city, date, display_date, weekday, min_temp, max_temp, skycon }, lang, day }
+// 城市按地点搜索取第一个结果，播报文本由 brief 模块生成；各平台的请求/响应格式由技能端自行转换
+
+use axum::{extract::State, http::StatusCode, response::Response, Json};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{brief, i18n, location, respond, tenant, weather, AppState, ErrorResp};
+
+#[derive(Deserialize)]
+pub struct VoiceRequest {
+    intent: String,
+    #[serde(default)]
+    city: Option<String>,
+    // 数字或字符串均可
+    #[serde(default)]
+    day: Option<Value>,
+    #[serde(default)]
+    lang: Option<String>,
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn voice(State(state): State<AppState>, Json(req): Json<VoiceRequest>) -> Response {
+    if !req.intent.trim().eq_ignore_ascii_case("weather") {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(format!("不支持的意图: {}", req.intent)));
+    }
+    let lang = match brief::resolve_lang(req.lang.as_deref()) {
+        Ok(lang) => lang,
+        Err(e) => return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(e)),
+    };
+    let day = match &req.day {
+        None | Some(Value::Null) => Some(0),
+        Some(Value::String(s)) => brief::parse_day(s),
+        Some(other) => brief::parse_day(&other.to_string()),
+    };
+    let Some(day) = day else {
+        return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(format!("day 应在 0 到 {} 之间", brief::MAX_DAY)));
+    };
+
+    let (name, lng, lat) = match req.city.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        Some(city) => match location::search(&state, city).await.into_iter().next() {
+            Some(place) => (place.name, place.lng, place.lat),
+            None => {
+                return respond::json(StatusCode::NOT_FOUND, &ErrorResp::new(format!("未找到城市: {}", city)));
+            }
+        },
+        None => {
+            let city = tenant::default_city();
+            (city.name, city.lng, city.lat)
+        }
+    };
+    let data = match weather::fetch(&state, lng, lat).await {
+        Ok(d) => d,
+        Err(e) => return respond::json(e.status(), &ErrorResp::new(e.to_string())),
+    };
+    let Some(forecast) = brief::forecast_day(&data, day) else {
+        return respond::json(StatusCode::NOT_FOUND, &ErrorResp::new("数据源没有该日的预报"));
+    };
+    let speech = match brief::compose(&data, &name, lang, day) {
+        Ok(text) => text,
+        Err(e) => {
+            return respond::json(StatusCode::INTERNAL_SERVER_ERROR, &ErrorResp::new(format!("生成播报失败: {}", e)));
+        }
+    };
+    // 卡片上的星期与日期按播报语言给出；自定义模板中 zh/en 以外的语言保留默认（中文）
+    let mut forecast = forecast.clone();
+    if let Ok(Some(locale)) = i18n::Locale::parse(Some(lang)) {
+        i18n::localize_day(&mut forecast, locale);
+    }
+    let card = json!({
+        "title": format!("{} · {}", name, brief::day_label(lang, day)),
+        "text": speech,
+        "icon": forecast.pointer("/weather_info/icon"),
+        "city": name,
+        "date": forecast.get("date"),
+        "display_date": forecast.pointer("/display/date"),
+        "weekday": forecast.get("weekday"),
+        "min_temp": forecast.get("min_temp"),
+        "max_temp": forecast.get("max_temp"),
+        "skycon": forecast.get("skycon"),
+    });
+    respond::json(StatusCode::OK, &json!({ "speech": speech, "card": card, "lang": lang, "day": day }))
+}
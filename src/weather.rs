@@ -0,0 +1,719 @@
+// 天气：请求数据源（默认彩云）并整形为前端需要的数据结构
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use chrono::{Datelike, Days, FixedOffset, NaiveDate, Timelike};
+use futures_util::future::BoxFuture;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::{
+    advice, alert_history, alerts, almanac, changes, demo, icons,
+    cache::TtlCache,
+    config::env_parse,
+    daylight::Daylight,
+    error_report, hedge, i18n, meteo, mock, nowcast,
+    provider::{self, WeatherProvider},
+    tenant, trend, upstream, upstream_cache,
+    upstream_url::UpstreamUrl,
+    AppState,
+};
+
+#[derive(Serialize, Clone)]
+pub struct WeatherCurrent {
+    pub temperature: i64,
+    pub apparent_temperature: i64,
+    pub humidity: i64,
+    pub wind_speed: i64,
+    pub wind_direction: i64,
+    pub pressure: i64,
+    pub visibility: serde_json::Value,
+    pub skycon: serde_json::Value,
+    pub weather_info: serde_json::Value,
+    pub air_quality: serde_json::Value,
+    pub dew_point: i64,
+    // 云量 %
+    pub cloud_cover: i64,
+    // 短波辐射 W/m²
+    pub solar_radiation: Option<f64>,
+    // 紫外线指数 0-11 与分级（低/中等/高/很高/极高）
+    pub uv_index: Option<i64>,
+    pub uv_category: Option<&'static str>,
+    // 与约 3 小时前相比；历史不足时为 null
+    pub pressure_trend: Option<trend::Trend>,
+    pub temperature_trend: Option<trend::Trend>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct WeatherData {
+    pub current: WeatherCurrent,
+    pub hourly: serde_json::Value,
+    pub daily: serde_json::Value,
+    pub forecast_keypoint: serde_json::Value,
+    pub alerts: serde_json::Value,
+    pub minutely: serde_json::Value,
+    pub advice: advice::Advice,
+    // 融合模式下各数据块的来源与气温交叉比对结果
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sources: Option<serde_json::Value>,
+}
+
+// 获取天气失败的原因，决定返回给客户端的状态码
+#[derive(Debug, thiserror::Error)]
+pub enum FetchError {
+    #[error("请求失败: {0}")]
+    Request(reqwest::Error),
+    #[error("上游错误: {0}")]
+    Status(reqwest::Error),
+    #[error("解析上游失败: {0}")]
+    Parse(upstream::BodyError),
+    #[error("上游返回异常")]
+    Upstream,
+    #[error("数据格式化失败: {0}")]
+    Format(anyhow::Error),
+    #[error("未配置 {0}（本地开发可设置 MOCK_MODE=1 或 DEMO_MODE=1 使用模拟数据）")]
+    NotConfigured(&'static str),
+    #[error("{0}")]
+    Mock(String),
+}
+
+impl FetchError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::Format(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotConfigured(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Mock(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::BAD_GATEWAY,
+        }
+    }
+}
+
+fn safe_round(v: &serde_json::Value, default_: i64) -> i64 {
+    v.as_f64().map(|n| n.round() as i64).unwrap_or(default_)
+}
+
+fn safe_number(v: &serde_json::Value, default_: i64) -> i64 {
+    v.as_f64().map(|n| n as i64).unwrap_or(default_)
+}
+
+fn safe_get<'a>(v: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let mut cur = v;
+    for key in path.split('.') {
+        cur = cur.get(key)?;
+    }
+    Some(cur)
+}
+
+// 路径处的数组（借用），缺失或不是数组时为空
+fn array_at<'a>(v: &'a serde_json::Value, path: &str) -> &'a [serde_json::Value] {
+    safe_get(v, path).and_then(|v| v.as_array()).map_or(&[], Vec::as_slice)
+}
+
+// skycon → 中文描述；未知代码原样返回。图标见 icons.rs
+pub fn skycon_desc(s: &str) -> &str {
+    match s {
+        "CLEAR_DAY" => "晴",
+        "CLEAR_NIGHT" => "晴（夜间）",
+        "PARTLY_CLOUDY_DAY" => "多云",
+        "PARTLY_CLOUDY_NIGHT" => "多云（夜间）",
+        "CLOUDY" => "阴",
+        "LIGHT_RAIN" => "小雨",
+        "MODERATE_RAIN" => "中雨",
+        "HEAVY_RAIN" => "大雨",
+        "STORM_RAIN" => "暴雨",
+        "HAIL" => "冰雹",
+        "SLEET" => "雨夹雪",
+        "LIGHT_SNOW" => "小雪",
+        "MODERATE_SNOW" => "中雪",
+        "HEAVY_SNOW" => "大雪",
+        "STORM_SNOW" => "暴雪",
+        "FOG" => "雾",
+        "LIGHT_HAZE" => "轻度霾",
+        "MODERATE_HAZE" => "中度霾",
+        "HEAVY_HAZE" => "重度霾",
+        "DUST" => "浮尘",
+        "SAND" => "沙尘",
+        "WIND" => "大风",
+        other => other,
+    }
+}
+
+fn round_to(v: f64, digits: i32) -> f64 {
+    let scale = 10f64.powi(digits);
+    (v * scale).round() / scale
+}
+
+// 能见度 km，上游常带多位小数，保留 1 位
+fn compact_visibility(v: Option<&serde_json::Value>) -> serde_json::Value {
+    v.and_then(|v| v.as_f64()).map_or(serde_json::Value::Null, |km| serde_json::json!(round_to(km, 1)))
+}
+
+// 只保留 AQI、等级描述与六项污染物；浓度 μg/m³ 取整，CO 为 mg/m³ 保留 1 位
+fn compact_air_quality(v: Option<&serde_json::Value>) -> serde_json::Value {
+    let Some(aq) = v.filter(|v| v.is_object()) else {
+        return serde_json::Value::Null;
+    };
+    let mut out = serde_json::Map::new();
+    for key in ["aqi", "description"] {
+        if let Some(by_standard) = aq.get(key).and_then(|v| v.as_object()) {
+            let kept: serde_json::Map<_, _> = ["chn", "usa"]
+                .iter()
+                .filter_map(|std| {
+                    let v = by_standard.get(*std)?;
+                    let v = v.as_f64().map_or_else(|| v.clone(), |n| serde_json::json!(n.round() as i64));
+                    Some((std.to_string(), v))
+                })
+                .collect();
+            out.insert(key.into(), kept.into());
+        }
+    }
+    for (key, digits) in [("pm25", 0), ("pm10", 0), ("o3", 0), ("so2", 0), ("no2", 0), ("co", 1)] {
+        if let Some(n) = aq.get(key).and_then(|v| v.as_f64()) {
+            let v = if digits == 0 { serde_json::json!(n.round() as i64) } else { serde_json::json!(round_to(n, digits)) };
+            out.insert(key.into(), v);
+        }
+    }
+    out.into()
+}
+
+// 地点时区：彩云响应的 tzshift（秒），其他数据源缺失时按经度每 15° 一小时估算
+pub fn location_offset(raw: &serde_json::Value, longitude: f64) -> FixedOffset {
+    raw.get("tzshift")
+        .and_then(|v| v.as_i64())
+        .and_then(|s| FixedOffset::east_opt(s as i32))
+        .or_else(|| FixedOffset::east_opt((longitude / 15.0).round() as i32 * 3600))
+        .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap())
+}
+
+// 逐日预报整形，最多取 limit 天；/api/weather 取 3 天，扩展预报按请求的天数
+pub fn format_daily(raw: &serde_json::Value, longitude: f64, limit: usize) -> Vec<serde_json::Value> {
+    let daily = raw.pointer("/result/daily").unwrap_or(&serde_json::Value::Null);
+    let daily_temp = array_at(daily, "temperature");
+    let daily_sky = array_at(daily, "skycon");
+    let life_index = daily.get("life_index").unwrap_or(&serde_json::Value::Null);
+    // “今天”按地点所在时区计算，与服务器时区无关
+    let today = chrono::Utc::now().with_timezone(&location_offset(raw, longitude)).date_naive();
+    let dcount = daily_temp.len().min(limit);
+    let mut daily_out = Vec::with_capacity(dcount);
+    for (i, temp_obj) in daily_temp.iter().enumerate().take(dcount) {
+        // 上游日期形如 2025-07-28T00:00+08:00，已是当地日期；缺失时按顺序推算
+        let date = temp_obj
+            .get("date")
+            .and_then(|v| v.as_str())
+            .and_then(|s| NaiveDate::parse_from_str(s.get(..10)?, "%Y-%m-%d").ok())
+            .unwrap_or_else(|| today.checked_add_days(Days::new(i as u64)).unwrap_or(today));
+        // 默认输出中文；其他语言由 i18n::localize 在处理函数中转换
+        let relative = i18n::Locale::ZhCn.relative_day((date - today).num_days());
+        let weekday = i18n::Locale::ZhCn.weekday(date.weekday());
+        let sky = daily_sky.get(i).and_then(|v| v.get("value")).and_then(|v| v.as_str()).unwrap_or("CLEAR_DAY");
+        // 其余逐日字段：daily.<key>[i].<path>，缺失时为 0
+        let day = |key: &str, path: &str| -> f64 {
+            daily
+                .get(key)
+                .and_then(|arr| arr.get(i))
+                .and_then(|v| safe_get(v, path))
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0)
+        };
+        let half_day_sky = |key: &str| -> &str {
+            daily
+                .get(key)
+                .and_then(|arr| arr.get(i))
+                .and_then(|v| v.get("value"))
+                .and_then(|v| v.as_str())
+                .unwrap_or(sky)
+        };
+        let (sky_day, sky_night) = (half_day_sky("skycon_08h_20h"), half_day_sky("skycon_20h_32h"));
+        let almanac = almanac::for_date(date);
+
+        // 生活指数提取助手
+        let li = |key: &str| -> serde_json::Value {
+            life_index.get(key)
+                .and_then(|arr| arr.as_array())
+                .and_then(|arr| arr.get(i))
+                .cloned()
+                .unwrap_or_else(|| serde_json::json!({"index":"","desc":""}))
+        };
+
+        daily_out.push(serde_json::json!({
+            "date": format!("{:02}-{:02}", date.month(), date.day()),
+            "weekday": weekday,
+            "relativeDay": relative,
+            "max_temp": safe_round(temp_obj.get("max").unwrap_or(&serde_json::Value::Null), 0),
+            "min_temp": safe_round(temp_obj.get("min").unwrap_or(&serde_json::Value::Null), 0),
+            "skycon": sky,
+            "weather_info": icons::emoji_info(sky),
+            "skycon_day": sky_day,
+            "weather_info_day": icons::emoji_info(sky_day),
+            "skycon_night": sky_night,
+            "weather_info_night": icons::emoji_info(sky_night),
+            // 彩云逐日降水为小时强度（mm/h），日总量按平均强度 × 24 估算
+            "precipitation": {
+                "total": (day("precipitation", "avg") * 24.0 * 10.0).round() / 10.0,
+                "max": (day("precipitation", "max") * 100.0).round() / 100.0,
+                "probability": day("precipitation", "probability").round() as i64,
+            },
+            "humidity": {
+                "avg": (day("humidity", "avg") * 100.0).round() as i64,
+                "min": (day("humidity", "min") * 100.0).round() as i64,
+                "max": (day("humidity", "max") * 100.0).round() as i64,
+            },
+            "wind_max": {
+                "speed": (day("wind", "max.speed") * 3.6).round() as i64,
+                "direction": day("wind", "max.direction").round() as i64,
+            },
+            "pressure": {
+                "avg": (day("pressure", "avg") / 100.0).round() as i64,
+                "min": (day("pressure", "min") / 100.0).round() as i64,
+                "max": (day("pressure", "max") / 100.0).round() as i64,
+            },
+            "lunar": almanac.lunar,
+            "solar_term": almanac.solar_term,
+            "moon": almanac.moon,
+            "life_index": {
+                "ultraviolet": li("ultraviolet"),
+                "carWashing": li("carWashing"),
+                "dressing": li("dressing"),
+                "comfort": li("comfort"),
+                "coldRisk": li("coldRisk"),
+            }
+        }));
+    }
+    daily_out
+}
+
+// verbose 时 visibility/air_quality 原样透传上游数据
+fn format_weather_data(raw: &serde_json::Value, longitude: f64, verbose: bool) -> anyhow::Result<WeatherData> {
+    let result = raw
+        .get("result")
+        .ok_or_else(|| anyhow::anyhow!("缺少 result"))?;
+    let realtime = result.get("realtime").ok_or_else(|| anyhow::anyhow!("缺少 realtime"))?;
+    // 以下均借用上游文档中的子树，只在输出处复制必要的标量
+    let hourly = result.get("hourly").unwrap_or(&serde_json::Value::Null);
+
+    let skycon_code = realtime.get("skycon").and_then(|v| v.as_str()).unwrap_or("CLEAR_DAY");
+    let temperature = realtime.get("temperature").and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let humidity = safe_get(realtime, "humidity").and_then(|v| v.as_f64()).unwrap_or(0.0) * 100.0;
+    let wind_speed = safe_get(realtime, "wind.speed").and_then(|v| v.as_f64()).unwrap_or(0.0) * 3.6;
+    // 上游偶尔缺少体感温度，按风寒/热指数自行计算
+    let apparent = realtime
+        .get("apparent_temperature")
+        .and_then(|v| v.as_f64())
+        .unwrap_or_else(|| meteo::apparent_temperature(temperature, humidity, wind_speed));
+    let uv_index = meteo::uv_index(
+        safe_get(realtime, "life_index.ultraviolet.index").and_then(|v| v.as_f64().or_else(|| v.as_str()?.parse().ok())),
+        safe_get(realtime, "dswrf").and_then(|v| v.as_f64()),
+    );
+    let current = WeatherCurrent {
+        temperature: temperature.round() as i64,
+        apparent_temperature: apparent.round() as i64,
+        humidity: humidity.round() as i64,
+        wind_speed: wind_speed.round() as i64,
+        wind_direction: safe_number(safe_get(realtime, "wind.direction").unwrap_or(&serde_json::Value::Null), 0),
+        pressure: ((safe_get(realtime, "pressure").and_then(|v| v.as_f64()).unwrap_or(101325.0)) / 100.0).round() as i64,
+        visibility: if verbose {
+            realtime.get("visibility").cloned().unwrap_or(serde_json::Value::Null)
+        } else {
+            compact_visibility(realtime.get("visibility"))
+        },
+        skycon: serde_json::Value::String(skycon_code.to_string()),
+        weather_info: icons::emoji_info(skycon_code),
+        air_quality: if verbose {
+            realtime.get("air_quality").cloned().unwrap_or(serde_json::Value::Null)
+        } else {
+            compact_air_quality(realtime.get("air_quality"))
+        },
+        dew_point: meteo::dew_point(temperature, humidity).round() as i64,
+        cloud_cover: (safe_get(realtime, "cloudrate").and_then(|v| v.as_f64()).unwrap_or(0.0) * 100.0).round() as i64,
+        solar_radiation: safe_get(realtime, "dswrf").and_then(|v| v.as_f64()),
+        uv_index,
+        uv_category: uv_index.map(meteo::uv_category),
+        pressure_trend: None,
+        temperature_trend: None,
+    };
+
+    // 有逐分钟降水时由本地生成，不依赖上游文案
+    let forecast_keypoint = nowcast::keypoint(raw)
+        .map(serde_json::Value::String)
+        .or_else(|| result.get("forecast_keypoint").cloned())
+        .unwrap_or_else(|| serde_json::Value::String("天气提示".into()));
+
+    // 映射 hourly -> 前端结构
+    let hourly_arr = array_at(hourly, "temperature");
+    let sky_arr = array_at(hourly, "skycon");
+    // 降水强度 mm/h 与概率 %、相对湿度 0-1、风速/风向，缺失时按 0 处理
+    let precip_arr = array_at(hourly, "precipitation");
+    let humidity_arr = array_at(hourly, "humidity");
+    let wind_arr = array_at(hourly, "wind");
+    // 逐小时空气质量（中国标准 AQI 与 PM2.5 μg/m³），其他数据源可能没有，此时为 null
+    let aqi_arr = array_at(hourly, "air_quality.aqi");
+    let pm25_arr = array_at(hourly, "air_quality.pm25");
+    // 与逐日预报相同按地点时区（tzshift）计算当地小时，新疆等地按经度估算会差出两小时
+    let local_hour = chrono::Utc::now().with_timezone(&location_offset(raw, longitude)).hour() as i32;
+    let daylight = Daylight::from_result(result);
+    let count = hourly_arr.len().min(sky_arr.len()).min(24);
+    let mut hourly_out = Vec::with_capacity(count);
+    for i in 0..count {
+        let temp_v = hourly_arr[i].get("value").unwrap_or(&serde_json::Value::Null);
+        let sky_v = sky_arr[i].get("value").and_then(|v| v.as_str()).unwrap_or("CLEAR_DAY");
+        let sky_v = daylight.skycon(sky_v, sky_arr[i].get("datetime").and_then(|v| v.as_str()).unwrap_or(""));
+        let hour = ((local_hour + i as i32) % 24 + 24) % 24; // 0-23
+        let at = |arr: &[serde_json::Value], key: &str| arr.get(i).and_then(|v| v.get(key)).and_then(|v| v.as_f64());
+        hourly_out.push(serde_json::json!({
+            "time": hour,
+            "temperature": safe_round(temp_v, 0),
+            "skycon": sky_v,
+            "weather_info": icons::emoji_info(sky_v),
+            "precipitation": (at(precip_arr, "value").unwrap_or(0.0) * 100.0).round() / 100.0,
+            "precipitation_probability": at(precip_arr, "probability").unwrap_or(0.0).round() as i64,
+            "humidity": (at(humidity_arr, "value").unwrap_or(0.0) * 100.0).round() as i64,
+            "wind_speed": (at(wind_arr, "speed").unwrap_or(0.0) * 3.6).round() as i64,
+            "wind_direction": at(wind_arr, "direction").unwrap_or(0.0).round() as i64,
+            "aqi": aqi_arr.get(i).and_then(|v| v.pointer("/value/chn")).and_then(|v| v.as_f64()).map(|n| n.round() as i64),
+            "pm25": at(pm25_arr, "value").map(|n| n.round() as i64),
+        }));
+    }
+
+    // 映射 daily -> 前端结构（取前 3 天）
+    let daily_out = format_daily(raw, longitude, 3);
+
+    // 预警信息（alert=true 时返回）
+    let alerts: Vec<serde_json::Value> = safe_get(result, "alert.content")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .map(|a| {
+                    let text = |key: &str| a.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+                    serde_json::json!({
+                        "id": text("alertId"),
+                        "title": text("title"),
+                        "code": text("code"),
+                        "status": text("status"),
+                        "description": text("description"),
+                        "source": text("source"),
+                        "pub_time": a.get("pubtimestamp").and_then(|v| v.as_i64()),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // 未来两小时降水：描述与是否有雨（逐分钟降水强度超过 0.03 mm/h 视为有雨）
+    let minutely = result.get("minutely").unwrap_or(&serde_json::Value::Null);
+    let will_rain = minutely
+        .get("precipitation_2h")
+        .and_then(|v| v.as_array())
+        .is_some_and(|arr| arr.iter().any(|v| v.as_f64().unwrap_or(0.0) > 0.03));
+    let minutely = serde_json::json!({
+        "description": minutely
+            .get("description")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .or_else(|| nowcast::keypoint(raw))
+            .unwrap_or_default(),
+        "will_rain": will_rain,
+    });
+
+    let daily = serde_json::Value::Array(daily_out);
+    Ok(WeatherData {
+        advice: advice_of(&current, &daily, &minutely),
+        current,
+        hourly: serde_json::Value::Array(hourly_out),
+        daily,
+        forecast_keypoint,
+        alerts: serde_json::Value::Array(alerts),
+        minutely,
+        sources: raw.get("sources").cloned(),
+    })
+}
+
+fn advice_of(current: &WeatherCurrent, daily: &serde_json::Value, minutely: &serde_json::Value) -> advice::Advice {
+    let days = daily.as_array().map(Vec::as_slice).unwrap_or(&[]);
+    let today = |pointer: &str| days.first().and_then(|d| d.pointer(pointer)).and_then(|v| v.as_f64());
+    advice::evaluate(&advice::Conditions {
+        temperature: current.temperature as f64,
+        apparent_temperature: current.apparent_temperature as f64,
+        humidity: current.humidity as f64,
+        wind_speed: current.wind_speed as f64,
+        aqi: current.air_quality.pointer("/aqi/chn").and_then(|v| v.as_i64()),
+        uv_level: days
+            .first()
+            .and_then(|d| d.pointer("/life_index/ultraviolet/index"))
+            .and_then(|v| v.as_str())
+            .and_then(|s| s.parse().ok()),
+        skycon: current.skycon.as_str().unwrap_or("").to_string(),
+        will_rain: minutely.get("will_rain").and_then(|v| v.as_bool()).unwrap_or(false),
+        rain_probability: days
+            .iter()
+            .take(2)
+            .filter_map(|d| d.pointer("/precipitation/probability").and_then(|v| v.as_f64()))
+            .fold(0.0, f64::max),
+        uv_index: current.uv_index,
+        today_min_temp: today("/min_temp"),
+        today_max_temp: today("/max_temp"),
+        today_rain_probability: today("/precipitation/probability"),
+        today_precipitation: today("/precipitation/total"),
+    })
+}
+
+// 彩云数据源
+pub struct Caiyun;
+
+impl WeatherProvider for Caiyun {
+    fn name(&self) -> &'static str {
+        "caiyun"
+    }
+
+    fn fetch<'a>(&'a self, state: &'a AppState, lng: f64, lat: f64) -> BoxFuture<'a, Result<serde_json::Value, FetchError>> {
+        Box::pin(fetch_caiyun(state, lng, lat))
+    }
+}
+
+async fn fetch_caiyun(state: &AppState, lng: f64, lat: f64) -> Result<serde_json::Value, FetchError> {
+    let Some(token) = tenant::caiyun_token(state) else {
+        return Err(FetchError::NotConfigured("CAIYUN_API_TOKEN"));
+    };
+
+    let url = UpstreamUrl::new(&state.base_urls.caiyun)
+        .path("/v2.6/")
+        .secret(&token)
+        .segment(format_args!("{},{}", lng, lat))
+        .path("/weather")
+        .query("alert", true)
+        .query("dailysteps", 3)
+        .query("hourlysteps", 24)
+        .query("lang", "zh_CN");
+
+    // 响应体读取也计入对冲：慢的往往是传输而非首字节
+    let json = hedge::run("caiyun", || async {
+        let resp = upstream::send("caiyun", "/v2.6/{token}/{lng},{lat}/weather", upstream::client("caiyun").get(url.as_str()))
+            .await
+            .map_err(FetchError::Request)?
+            .error_for_status()
+            .map_err(FetchError::Status)?;
+        upstream::json::<serde_json::Value>(resp).await.map_err(|e| {
+            error_report::capture_upstream_error("caiyun", &e);
+            FetchError::Parse(e)
+        })
+    })
+    .await?;
+    // 校验 ok/status
+    if json.get("status").and_then(|v| v.as_str()) != Some("ok") && json.get("result").is_none() {
+        let status = json.get("status").and_then(|v| v.as_str()).unwrap_or("unknown");
+        error_report::capture_upstream_error("caiyun", &format_args!("status={}", status));
+        return Err(FetchError::Upstream);
+    }
+    Ok(json)
+}
+
+// 彩云逐日预报（/daily），天数由 dailysteps 指定；套餐不支持的天数上游不返回，由调用方标出缺失。
+// 只有彩云提供，不经数据源回退与上游结果缓存；模拟模式下返回所选 fixture，演示模式下返回生成的数据
+pub async fn fetch_caiyun_daily(state: &AppState, lng: f64, lat: f64, days: usize) -> Result<serde_json::Value, FetchError> {
+    if mock::enabled() {
+        return mock::load().map_err(FetchError::Mock);
+    }
+    if demo::enabled() {
+        return Ok(demo::generate(lng, lat, days));
+    }
+    let Some(token) = tenant::caiyun_token(state) else {
+        return Err(FetchError::NotConfigured("CAIYUN_API_TOKEN"));
+    };
+    let url = UpstreamUrl::new(&state.base_urls.caiyun)
+        .path("/v2.6/")
+        .secret(&token)
+        .segment(format_args!("{},{}", lng, lat))
+        .path("/daily")
+        .query("dailysteps", days)
+        .query("lang", "zh_CN");
+    let resp = upstream::send("caiyun", "/v2.6/{token}/{lng},{lat}/daily", upstream::client("caiyun").get(url.as_str()))
+        .await
+        .map_err(FetchError::Request)?
+        .error_for_status()
+        .map_err(FetchError::Status)?;
+    let json: serde_json::Value = upstream::json(resp).await.map_err(FetchError::Parse)?;
+    if json.get("status").and_then(|v| v.as_str()) != Some("ok") || json.pointer("/result/daily").is_none() {
+        return Err(FetchError::Upstream);
+    }
+    Ok(json)
+}
+
+// 请求彩云同结构的原始 JSON（按 WEATHER_PROVIDERS 顺序回退，经上游结果缓存）；模拟模式下返回所选 fixture，演示模式下返回生成的数据
+pub async fn fetch_raw(state: &AppState, lng: f64, lat: f64) -> Result<serde_json::Value, FetchError> {
+    fetch_raw_stamped(state, lng, lat).await.map(|(json, _)| json)
+}
+
+// 同 fetch_raw，另返回上游结果缓存条目的写入时间（见 upstream_cache::get_or_fetch_stamped）
+async fn fetch_raw_stamped(state: &AppState, lng: f64, lat: f64) -> Result<(serde_json::Value, Option<i64>), FetchError> {
+    if mock::enabled() {
+        return Ok((mock::load().map_err(FetchError::Mock)?, None));
+    }
+    if demo::enabled() {
+        return Ok((demo::generate(lng, lat, 3), None));
+    }
+    let state = state.clone();
+    let tenant = tenant::current();
+    upstream_cache::get_or_fetch_stamped(upstream_cache::Kind::Weather, lng, lat, move || {
+        let state = state.clone();
+        tenant::scope(tenant, async move { fetch_upstream(&state, lng, lat).await })
+    })
+    .await
+}
+
+// 同 fetch_raw，但跳过上游结果缓存，供排查接口对照上游当前返回
+pub async fn fetch_raw_uncached(state: &AppState, lng: f64, lat: f64) -> Result<serde_json::Value, FetchError> {
+    if mock::enabled() {
+        return mock::load().map_err(FetchError::Mock);
+    }
+    if demo::enabled() {
+        return Ok(demo::generate(lng, lat, 3));
+    }
+    fetch_upstream(state, lng, lat).await
+}
+
+async fn fetch_upstream(state: &AppState, lng: f64, lat: f64) -> Result<serde_json::Value, FetchError> {
+    let json = provider::fetch(state, lng, lat).await?;
+    trend::record(lng, lat, &json);
+    changes::record(lng, lat, &json);
+    alert_history::record(lng, lat, &json);
+    Ok(json)
+}
+
+pub async fn fetch(state: &AppState, lng: f64, lat: f64) -> Result<WeatherData, FetchError> {
+    fetch_with(state, lng, lat, false).await
+}
+
+// verbose：保留上游 visibility/air_quality 原始块（不取整、不裁剪字段）
+pub async fn fetch_with(state: &AppState, lng: f64, lat: f64, verbose: bool) -> Result<WeatherData, FetchError> {
+    let (mut json, stored_at) = fetch_raw_stamped(state, lng, lat).await?;
+    let key = (lng.to_bits(), lat.to_bits(), verbose, upstream_cache::scope(upstream_cache::Kind::Weather));
+    let version = stored_at.map(|t| (t, chrono::Utc::now().timestamp() / 900));
+    let mut data = match (FORMATTED.get(&key), version) {
+        (Some((prev, data)), Some(version)) if prev == version => data,
+        _ => {
+            alerts::retain(&mut json, lng, lat);
+            let data = format_weather_data(&json, lng, verbose).map_err(|e| {
+                error_report::capture_upstream_error("caiyun", &e);
+                FetchError::Format(e)
+            })?;
+            if let Some(version) = version {
+                FORMATTED.insert(key, (version, data.clone()));
+            }
+            data
+        }
+    };
+    (data.current.pressure_trend, data.current.temperature_trend) = trend::trends(lng, lat);
+    Ok(data)
+}
+
+// 同一条上游结果缓存条目只整形一次：按条目的写入时间识别，无需序列化或比较原始 JSON；未开启缓存时每次整形。
+// 整形结果含按当前时刻推算的逐小时时刻与“今天/明天”，版本中并入所在的 15 分钟时段（兼容半小时、45 分时区）
+// 键：(经度, 纬度, verbose, 自带 token 的租户)，值：((条目写入时间, 时段), 整形结果)
+type Formatted = TtlCache<(u64, u64, bool, Option<&'static str>), ((i64, i64), WeatherData)>;
+
+static FORMATTED: Lazy<Formatted> = Lazy::new(|| TtlCache::new(Duration::from_secs(900), 4096));
+
+// 逐小时预报的粒度：1h 为原始 24 小时，6h 按当地时段归并
+#[derive(Clone, Copy, PartialEq)]
+pub enum Granularity {
+    Hour,
+    SixHours,
+}
+
+impl Granularity {
+    pub fn parse(raw: Option<&str>) -> Result<Self, String> {
+        match raw {
+            None | Some("1h") => Ok(Self::Hour),
+            Some("6h") => Ok(Self::SixHours),
+            Some(other) => Err(format!("不支持的粒度: {}（可选 1h、6h）", other)),
+        }
+    }
+}
+
+// 逐小时预报归并为时段块：连续落在同一时段的小时合为一块（首尾两块可能不足 6 小时），
+// 给出最低/最高气温、出现最多的天气（并列取先出现的）、累计降水量与最大降水概率
+pub fn six_hour_blocks(hourly: &serde_json::Value) -> serde_json::Value {
+    let hours = hourly.as_array().map(Vec::as_slice).unwrap_or_default();
+    let mut blocks: Vec<&[serde_json::Value]> = Vec::new();
+    let mut start = 0;
+    let period_of = |h: &serde_json::Value| h.get("time").and_then(|v| v.as_i64()).unwrap_or(0).rem_euclid(24) / 6;
+    for i in 1..=hours.len() {
+        if i == hours.len() || period_of(&hours[i]) != period_of(&hours[start]) {
+            blocks.push(&hours[start..i]);
+            start = i;
+        }
+    }
+    let out: Vec<serde_json::Value> = blocks
+        .into_iter()
+        .map(|block| {
+            let num = |h: &serde_json::Value, key: &str| h.get(key).and_then(|v| v.as_f64());
+            let temps: Vec<f64> = block.iter().filter_map(|h| num(h, "temperature")).collect();
+            let mut skycons: Vec<(&str, usize)> = Vec::new();
+            for code in block.iter().filter_map(|h| h.get("skycon").and_then(|v| v.as_str())) {
+                match skycons.iter_mut().find(|(c, _)| *c == code) {
+                    Some((_, n)) => *n += 1,
+                    None => skycons.push((code, 1)),
+                }
+            }
+            let skycon = skycons
+                .iter()
+                .fold(None::<(&str, usize)>, |best, &(c, n)| match best {
+                    Some((_, m)) if m >= n => best,
+                    _ => Some((c, n)),
+                })
+                .map(|(c, _)| c)
+                .unwrap_or("CLEAR_DAY");
+            let first_hour = block[0].get("time").and_then(|v| v.as_i64()).unwrap_or(0).rem_euclid(24);
+            // 按当地小时 / 6 取时段，名称默认中文
+            let index = (first_hour / 6) as usize;
+            let (period, label) = (i18n::PERIODS[index], i18n::Locale::ZhCn.period(index));
+            let precipitation: f64 = block.iter().filter_map(|h| num(h, "precipitation")).sum();
+            serde_json::json!({
+                "period": period,
+                "label": label,
+                "start_hour": first_hour,
+                "hours": block.len(),
+                "min_temp": temps.iter().copied().reduce(f64::min).map(|t| t.round() as i64),
+                "max_temp": temps.iter().copied().reduce(f64::max).map(|t| t.round() as i64),
+                "skycon": skycon,
+                "weather_info": icons::emoji_info(skycon),
+                "precipitation": (precipitation * 100.0).round() / 100.0,
+                "precipitation_probability": block
+                    .iter()
+                    .filter_map(|h| h.get("precipitation_probability").and_then(|v| v.as_i64()))
+                    .max()
+                    .unwrap_or(0),
+            })
+        })
+        .collect();
+    serde_json::Value::Array(out)
+}
+
+// 最近一次成功的整形结果，按约 1 km 网格、verbose 与自带 token 的租户归并；路由超时时作为回退
+type Recent = TtlCache<(i32, i32, bool, Option<&'static str>), WeatherData>;
+
+static RECENT: Lazy<Recent> = Lazy::new(|| {
+    let secs = env_parse("WEATHER_STALE_SECS").ok().flatten().unwrap_or(3600);
+    TtlCache::new(Duration::from_secs(secs), 4096)
+});
+
+fn grid(lng: f64, lat: f64) -> (i32, i32) {
+    ((lng * 100.0).round() as i32, (lat * 100.0).round() as i32)
+}
+
+pub fn remember_recent(lng: f64, lat: f64, verbose: bool, data: &WeatherData) {
+    let (x, y) = grid(lng, lat);
+    RECENT.insert((x, y, verbose, upstream_cache::scope(upstream_cache::Kind::Weather)), data.clone());
+}
+
+pub fn recent(lng: f64, lat: f64, verbose: bool) -> Option<WeatherData> {
+    let (x, y) = grid(lng, lat);
+    RECENT.get(&(x, y, verbose, upstream_cache::scope(upstream_cache::Kind::Weather)))
+}
+
+// 离线兜底等场景使用的示例数据：默认 fixture 的整形结果
+pub fn sample() -> Option<WeatherData> {
+    let raw = mock::load_named("clear_day").ok()?;
+    format_weather_data(&raw, 116.4074, false).ok()
+}
@@ -0,0 +1,149 @@
+// 天气数值的 OpenMetrics 导出：/metrics/weather 以 gauge 给出配置地点的实况（气温、体感温度、湿度、AQI、PM2.5、风速），
+// 标签 location 为地点名称，Prometheus 直接抓取即可在 Grafana 中绘制各城市的曲线，无需另写 exporter
+// - WEATHER_METRICS_LOCATIONS：`名称:经度,纬度`，分号分隔；设置后开启，未设置时该路径返回 404
+// - 数据由定时任务 weather_metrics 刷新（默认每 10 分钟，可用 SCHEDULE_WEATHER_METRICS 调整），启动时先刷新一次；
+//   抓取只读取最近一次的结果，不会触发上游请求
+// 某地点刷新失败时保留上一次的数值，weather_up 记为 0，可按 weather_last_update_timestamp_seconds 判断数据是否过期
+
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::OnceCell;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::{
+    config::{env_locations, NamedLocation},
+    scheduler, weather, AppState,
+};
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+// (指标名, 说明)；顺序即输出顺序
+const GAUGES: &[(&str, &str)] = &[
+    ("weather_temperature_celsius", "实况气温"),
+    ("weather_apparent_temperature_celsius", "体感温度"),
+    ("weather_humidity_percent", "相对湿度"),
+    ("weather_aqi", "空气质量指数（中国标准）"),
+    ("weather_pm25_micrograms_per_cubic_meter", "PM2.5 浓度"),
+    ("weather_wind_speed_kmh", "风速"),
+];
+
+#[derive(Default)]
+struct Sample {
+    // 与 GAUGES 一一对应，缺失的数据为 None（不输出该序列）
+    values: Vec<Option<f64>>,
+    up: bool,
+    updated: Option<i64>,
+}
+
+struct Exporter {
+    locations: Vec<NamedLocation>,
+    samples: Mutex<BTreeMap<String, Sample>>,
+}
+
+static EXPORTER: OnceCell<Exporter> = OnceCell::new();
+
+// 读取配置并注册刷新任务；未设置 WEATHER_METRICS_LOCATIONS 时不做任何事
+pub fn spawn_from_env(state: AppState) -> anyhow::Result<()> {
+    let Some(locations) = env_locations("WEATHER_METRICS_LOCATIONS")? else {
+        return Ok(());
+    };
+    info!("weather metrics exporting {} location(s)", locations.len());
+    let exporter = EXPORTER.get_or_init(|| Exporter { locations, samples: Mutex::new(BTreeMap::new()) });
+    scheduler::register("weather_metrics", "*/10 * * * *", move || {
+        let state = state.clone();
+        async move { exporter.refresh(&state).await }
+    })?;
+    scheduler::run_now("weather_metrics");
+    Ok(())
+}
+
+fn values(data: &weather::WeatherData) -> Vec<Option<f64>> {
+    let cur = &data.current;
+    let aq = &cur.air_quality;
+    vec![
+        Some(cur.temperature as f64),
+        Some(cur.apparent_temperature as f64),
+        Some(cur.humidity as f64),
+        aq.pointer("/aqi/chn").and_then(Value::as_f64),
+        aq.get("pm25").and_then(Value::as_f64),
+        Some(cur.wind_speed as f64),
+    ]
+}
+
+impl Exporter {
+    async fn refresh(&self, state: &AppState) -> anyhow::Result<()> {
+        let mut failed = 0;
+        for loc in &self.locations {
+            let result = weather::fetch(state, loc.lng, loc.lat).await;
+            let mut samples = self.samples.lock().unwrap();
+            let sample = samples.entry(loc.name.clone()).or_default();
+            match result {
+                Ok(data) => {
+                    sample.values = values(&data);
+                    sample.up = true;
+                    sample.updated = Some(chrono::Utc::now().timestamp());
+                }
+                Err(e) => {
+                    warn!(location = %loc.name, "weather metrics refresh failed: {}", e);
+                    sample.up = false;
+                    failed += 1;
+                }
+            }
+        }
+        if failed > 0 {
+            anyhow::bail!("{} 个地点刷新失败", failed);
+        }
+        Ok(())
+    }
+
+    fn render(&self) -> String {
+        let samples = self.samples.lock().unwrap();
+        let mut out = String::new();
+        for (i, (name, help)) in GAUGES.iter().enumerate() {
+            let _ = writeln!(out, "# TYPE {} gauge\n# HELP {} {}", name, name, help);
+            for (location, sample) in samples.iter() {
+                if let Some(v) = sample.values.get(i).copied().flatten() {
+                    let _ = writeln!(out, "{}{{location=\"{}\"}} {}", name, escape_label(location), v);
+                }
+            }
+        }
+        out.push_str("# TYPE weather_up gauge\n# HELP weather_up 最近一次刷新是否成功\n");
+        for (location, sample) in samples.iter() {
+            let _ = writeln!(out, "weather_up{{location=\"{}\"}} {}", escape_label(location), u8::from(sample.up));
+        }
+        out.push_str("# TYPE weather_last_update_timestamp_seconds gauge\n# HELP weather_last_update_timestamp_seconds 最近一次成功刷新的时间\n");
+        for (location, sample) in samples.iter() {
+            if let Some(ts) = sample.updated {
+                let _ = writeln!(out, "weather_last_update_timestamp_seconds{{location=\"{}\"}} {}", escape_label(location), ts);
+            }
+        }
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+// 标签值需转义反斜杠、双引号与换行
+fn escape_label(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+// GET /metrics/weather
+pub async fn handler() -> Response {
+    let Some(exporter) = EXPORTER.get() else {
+        return (StatusCode::NOT_FOUND, "未开启天气指标导出（WEATHER_METRICS_LOCATIONS）").into_response();
+    };
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE)),
+            (header::CACHE_CONTROL, HeaderValue::from_static("no-store")),
+        ],
+        exporter.render(),
+    )
+        .into_response()
+}
@@ -0,0 +1,481 @@
+// v2 天气响应：/api/v2/weather
+// - 全部为强类型结构，字段统一 camelCase；时间为 ISO-8601（带时区偏移），日期为 YYYY-MM-DD
+// - 数值不再取整，单位集中在 units 中声明
+// 直接由彩云原始 JSON 转换，不经过 v1 的整形结果；未配置 token 时转换一份同结构的模拟数据
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    advice, alerts, almanac,
+    daylight::Daylight,
+    error_report, icons, meteo, nowcast, respond, trend,
+    weather::{self, FetchError},
+    AppState, ErrorResp, WeatherQuery,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherV2 {
+    pub location: Coordinates,
+    pub updated_at: DateTime<FixedOffset>,
+    pub units: Units,
+    pub current: Current,
+    pub hourly: Vec<Hourly>,
+    pub daily: Vec<Daily>,
+    pub forecast_keypoint: String,
+    pub minutely: Minutely,
+    pub alerts: Vec<Alert>,
+    pub advice: advice::Advice,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sources: Option<Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Coordinates {
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Units {
+    pub temperature: &'static str,
+    pub humidity: &'static str,
+    pub wind_speed: &'static str,
+    pub wind_direction: &'static str,
+    pub pressure: &'static str,
+    pub visibility: &'static str,
+    pub precipitation: &'static str,
+    pub cloud_cover: &'static str,
+    pub solar_radiation: &'static str,
+    // 颗粒物与气体浓度；co 单独使用 mg/m³
+    pub concentration: &'static str,
+    pub co: &'static str,
+}
+
+const UNITS: Units = Units {
+    temperature: "°C",
+    humidity: "%",
+    wind_speed: "km/h",
+    wind_direction: "°",
+    pressure: "hPa",
+    visibility: "km",
+    precipitation: "mm/h",
+    cloud_cover: "%",
+    solar_radiation: "W/m²",
+    concentration: "μg/m³",
+    co: "mg/m³",
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    // 彩云 skycon 代码，如 CLEAR_DAY
+    pub code: String,
+    pub description: String,
+    // 图标集中的最前一层
+    pub icon: String,
+    // 组合图标的各层（从后到前），单层图标省略
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layers: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Current {
+    pub temperature: f64,
+    pub apparent_temperature: f64,
+    pub humidity: f64,
+    pub wind_speed: f64,
+    pub wind_direction: f64,
+    pub pressure: f64,
+    pub visibility: Option<f64>,
+    pub dew_point: f64,
+    pub cloud_cover: f64,
+    pub solar_radiation: Option<f64>,
+    // 紫外线指数 0-11 与分级
+    pub uv_index: Option<i64>,
+    pub uv_category: Option<&'static str>,
+    // 与约 3 小时前相比：rising / falling / steady
+    pub pressure_trend: Option<trend::Trend>,
+    pub temperature_trend: Option<trend::Trend>,
+    pub condition: Condition,
+    pub air_quality: Option<AirQuality>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AirQuality {
+    // 中国标准 AQI
+    pub aqi: Option<i64>,
+    pub category: Option<String>,
+    pub pm25: Option<f64>,
+    pub pm10: Option<f64>,
+    pub o3: Option<f64>,
+    pub so2: Option<f64>,
+    pub no2: Option<f64>,
+    pub co: Option<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hourly {
+    pub time: DateTime<FixedOffset>,
+    pub temperature: f64,
+    pub condition: Condition,
+    pub precipitation_intensity: f64,
+    // 0-100
+    pub precipitation_probability: f64,
+    pub humidity: f64,
+    pub wind_speed: f64,
+    pub wind_direction: f64,
+    // 中国标准 AQI 与 PM2.5（μg/m³），数据源未提供时为 null
+    pub aqi: Option<i64>,
+    pub pm25: Option<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Daily {
+    pub date: NaiveDate,
+    pub temperature_max: f64,
+    pub temperature_min: f64,
+    pub condition: Condition,
+    // 白天 08-20 时 / 夜间 20-次日 08 时
+    pub condition_day: Condition,
+    pub condition_night: Condition,
+    pub precipitation: DailyPrecipitation,
+    pub humidity: Range,
+    pub wind_max: Wind,
+    pub pressure: Range,
+    pub life_index: LifeIndex,
+    pub lunar: almanac::Lunar,
+    pub solar_term: Option<&'static str>,
+    pub moon: almanac::Moon,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyPrecipitation {
+    // 日总量 mm，按平均强度 × 24 估算
+    pub total: f64,
+    pub max_intensity: f64,
+    pub probability: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Range {
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Wind {
+    pub speed: f64,
+    pub direction: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LifeIndex {
+    pub ultraviolet: Option<IndexLevel>,
+    pub car_washing: Option<IndexLevel>,
+    pub dressing: Option<IndexLevel>,
+    pub comfort: Option<IndexLevel>,
+    pub cold_risk: Option<IndexLevel>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexLevel {
+    pub level: String,
+    pub description: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Minutely {
+    pub description: String,
+    pub will_rain: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub title: String,
+    pub code: String,
+    pub status: String,
+    pub description: String,
+    pub source: String,
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+fn num(v: &Value, path: &str) -> Option<f64> {
+    path.split('.').try_fold(v, |cur, key| cur.get(key))?.as_f64()
+}
+
+fn text(v: &Value, key: &str) -> String {
+    v.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string()
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+// 彩云时间形如 2024-06-01T08:00+08:00（无秒），兼容标准 RFC 3339
+fn parse_time(s: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(s)
+        .or_else(|_| DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M%:z"))
+        .ok()
+}
+
+fn condition(code: &str, set: &str) -> Condition {
+    let mut layers = icons::icon_layers(set, code);
+    Condition {
+        code: code.to_string(),
+        description: weather::skycon_desc(code).to_string(),
+        icon: layers.last().cloned().unwrap_or_default(),
+        layers: (layers.len() > 1).then(|| std::mem::take(&mut layers)),
+    }
+}
+
+fn array<'a>(v: &'a Value, key: &str) -> &'a [Value] {
+    v.get(key).and_then(|v| v.as_array()).map(Vec::as_slice).unwrap_or(&[])
+}
+
+fn from_raw(raw: &Value, lng: f64, lat: f64, set: &str) -> anyhow::Result<WeatherV2> {
+    let result = raw.get("result").ok_or_else(|| anyhow::anyhow!("缺少 result"))?;
+    let realtime = result.get("realtime").ok_or_else(|| anyhow::anyhow!("缺少 realtime"))?;
+
+    let offset = raw
+        .get("tzshift")
+        .and_then(|v| v.as_i64())
+        .and_then(|s| FixedOffset::east_opt(s as i32))
+        .unwrap_or_else(|| *Local::now().offset());
+    let updated_at = raw
+        .get("server_time")
+        .and_then(|v| v.as_i64())
+        .and_then(|t| DateTime::from_timestamp(t, 0))
+        .unwrap_or_else(Utc::now)
+        .with_timezone(&offset);
+
+    let air_quality = realtime.get("air_quality").filter(|v| v.is_object()).map(|aq| AirQuality {
+        aqi: aq.get("aqi").and_then(|v| v.get("chn")).and_then(|v| v.as_i64()),
+        category: aq
+            .get("description")
+            .and_then(|v| v.get("chn"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        pm25: num(aq, "pm25"),
+        pm10: num(aq, "pm10"),
+        o3: num(aq, "o3"),
+        so2: num(aq, "so2"),
+        no2: num(aq, "no2"),
+        co: num(aq, "co"),
+    });
+    let temperature = num(realtime, "temperature").unwrap_or(0.0);
+    let humidity = num(realtime, "humidity").unwrap_or(0.0) * 100.0;
+    let wind_speed = num(realtime, "wind.speed").unwrap_or(0.0) * 3.6;
+    let (pressure_trend, temperature_trend) = trend::trends(lng, lat);
+    let uv_level = realtime
+        .pointer("/life_index/ultraviolet/index")
+        .and_then(|v| v.as_f64().or_else(|| v.as_str()?.parse().ok()));
+    let uv_index = meteo::uv_index(uv_level, num(realtime, "dswrf"));
+    let current = Current {
+        temperature: round1(temperature),
+        apparent_temperature: round1(
+            num(realtime, "apparent_temperature")
+                .unwrap_or_else(|| meteo::apparent_temperature(temperature, humidity, wind_speed)),
+        ),
+        humidity: round1(humidity),
+        wind_speed: round1(wind_speed),
+        wind_direction: round1(num(realtime, "wind.direction").unwrap_or(0.0)),
+        pressure: round1(num(realtime, "pressure").unwrap_or(101325.0) / 100.0),
+        visibility: num(realtime, "visibility"),
+        dew_point: round1(meteo::dew_point(temperature, humidity)),
+        cloud_cover: round1(num(realtime, "cloudrate").unwrap_or(0.0) * 100.0),
+        solar_radiation: num(realtime, "dswrf"),
+        uv_index,
+        uv_category: uv_index.map(meteo::uv_category),
+        pressure_trend,
+        temperature_trend,
+        condition: condition(realtime.get("skycon").and_then(|v| v.as_str()).unwrap_or("CLEAR_DAY"), set),
+        air_quality,
+    };
+
+    let hourly_block = result.get("hourly").unwrap_or(&Value::Null);
+    let hourly_sky = array(hourly_block, "skycon");
+    let hourly_at = |key: &str, i: usize, field: &str| {
+        array(hourly_block, key).get(i).and_then(|v| num(v, field)).unwrap_or(0.0)
+    };
+    let hourly_air = hourly_block.get("air_quality").unwrap_or(&Value::Null);
+    let daylight = Daylight::from_result(result);
+    let hourly = array(hourly_block, "temperature")
+        .iter()
+        .zip(hourly_sky)
+        .take(24)
+        .enumerate()
+        .filter_map(|(i, (temp, sky))| {
+            let datetime = temp.get("datetime")?.as_str()?;
+            let code = sky.get("value").and_then(|v| v.as_str()).unwrap_or("CLEAR_DAY");
+            Some(Hourly {
+                time: parse_time(datetime)?,
+                temperature: round1(num(temp, "value")?),
+                condition: condition(daylight.skycon(code, datetime), set),
+                precipitation_intensity: (hourly_at("precipitation", i, "value") * 100.0).round() / 100.0,
+                precipitation_probability: round1(hourly_at("precipitation", i, "probability")),
+                humidity: round1(hourly_at("humidity", i, "value") * 100.0),
+                wind_speed: round1(hourly_at("wind", i, "speed") * 3.6),
+                wind_direction: round1(hourly_at("wind", i, "direction")),
+                aqi: array(hourly_air, "aqi").get(i).and_then(|v| num(v, "value.chn")).map(|n| n.round() as i64),
+                pm25: array(hourly_air, "pm25").get(i).and_then(|v| num(v, "value")).map(round1),
+            })
+        })
+        .collect();
+
+    let daily_block = result.get("daily").unwrap_or(&Value::Null);
+    let life = daily_block.get("life_index").unwrap_or(&Value::Null);
+    let index_at = |key: &str, i: usize| {
+        array(life, key).get(i).map(|v| IndexLevel { level: text(v, "index"), description: text(v, "desc") })
+    };
+    let daily_at = |key: &str, i: usize, path: &str| {
+        array(daily_block, key).get(i).and_then(|v| num(v, path)).unwrap_or(0.0)
+    };
+    let range_at = |key: &str, i: usize, scale: f64| Range {
+        min: round1(daily_at(key, i, "min") * scale),
+        max: round1(daily_at(key, i, "max") * scale),
+        avg: round1(daily_at(key, i, "avg") * scale),
+    };
+    let daily: Vec<Daily> = array(daily_block, "temperature")
+        .iter()
+        .enumerate()
+        .filter_map(|(i, temp)| {
+            let date = parse_time(temp.get("date")?.as_str()?)?.date_naive();
+            let almanac = almanac::for_date(date);
+            let sky_of = |key: &str| array(daily_block, key).get(i).and_then(|v| v.get("value")).and_then(|v| v.as_str());
+            let sky = sky_of("skycon").unwrap_or("CLEAR_DAY");
+            Some(Daily {
+                date,
+                temperature_max: round1(num(temp, "max")?),
+                temperature_min: round1(num(temp, "min")?),
+                condition: condition(sky, set),
+                condition_day: condition(sky_of("skycon_08h_20h").unwrap_or(sky), set),
+                condition_night: condition(sky_of("skycon_20h_32h").unwrap_or(sky), set),
+                precipitation: DailyPrecipitation {
+                    total: round1(daily_at("precipitation", i, "avg") * 24.0),
+                    max_intensity: (daily_at("precipitation", i, "max") * 100.0).round() / 100.0,
+                    probability: round1(daily_at("precipitation", i, "probability")),
+                },
+                humidity: range_at("humidity", i, 100.0),
+                wind_max: Wind {
+                    speed: round1(daily_at("wind", i, "max.speed") * 3.6),
+                    direction: round1(daily_at("wind", i, "max.direction")),
+                },
+                pressure: range_at("pressure", i, 0.01),
+                life_index: LifeIndex {
+                    ultraviolet: index_at("ultraviolet", i),
+                    car_washing: index_at("carWashing", i),
+                    dressing: index_at("dressing", i),
+                    comfort: index_at("comfort", i),
+                    cold_risk: index_at("coldRisk", i),
+                },
+                lunar: almanac.lunar,
+                solar_term: almanac.solar_term,
+                moon: almanac.moon,
+            })
+        })
+        .collect();
+
+    let minutely_block = result.get("minutely").unwrap_or(&Value::Null);
+    let minutely = Minutely {
+        description: Some(text(minutely_block, "description"))
+            .filter(|s| !s.is_empty())
+            .or_else(|| nowcast::keypoint(raw))
+            .unwrap_or_default(),
+        will_rain: array(minutely_block, "precipitation_2h")
+            .iter()
+            .any(|v| v.as_f64().unwrap_or(0.0) > 0.03),
+    };
+
+    let alert_block = result.get("alert").unwrap_or(&Value::Null);
+    let alerts = array(alert_block, "content")
+        .iter()
+        .map(|a| Alert {
+            title: text(a, "title"),
+            code: text(a, "code"),
+            status: text(a, "status"),
+            description: text(a, "description"),
+            source: text(a, "source"),
+            published_at: a
+                .get("pubtimestamp")
+                .and_then(|v| v.as_i64())
+                .and_then(|t| DateTime::from_timestamp(t, 0)),
+        })
+        .collect();
+
+    let advice = advice::evaluate(&advice::Conditions {
+        temperature: current.temperature,
+        apparent_temperature: current.apparent_temperature,
+        humidity: current.humidity,
+        wind_speed: current.wind_speed,
+        aqi: current.air_quality.as_ref().and_then(|aq| aq.aqi),
+        uv_level: daily
+            .first()
+            .and_then(|d| d.life_index.ultraviolet.as_ref())
+            .and_then(|uv| uv.level.parse().ok()),
+        skycon: current.condition.code.clone(),
+        will_rain: minutely.will_rain,
+        rain_probability: daily.iter().take(2).map(|d| d.precipitation.probability).fold(0.0, f64::max),
+        uv_index: current.uv_index,
+        today_min_temp: daily.first().map(|d| d.temperature_min),
+        today_max_temp: daily.first().map(|d| d.temperature_max),
+        today_rain_probability: daily.first().map(|d| d.precipitation.probability),
+        today_precipitation: daily.first().map(|d| d.precipitation.total),
+    });
+
+    Ok(WeatherV2 {
+        location: Coordinates { longitude: lng, latitude: lat },
+        updated_at,
+        units: UNITS,
+        current,
+        hourly,
+        daily,
+        forecast_keypoint: nowcast::keypoint(raw).unwrap_or_else(|| text(result, "forecast_keypoint")),
+        minutely,
+        alerts,
+        advice,
+        sources: raw.get("sources").cloned(),
+    })
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn weather(State(state): State<AppState>, accept: respond::Accept, Query(q): Query<WeatherQuery>) -> Response {
+    let set = match icons::select(q.icons.as_deref()) {
+        Ok(set) => set,
+        Err(e) => return respond::json(StatusCode::BAD_REQUEST, &ErrorResp::new(e)),
+    };
+    let mut raw = match weather::fetch_raw(&state, q.lng, q.lat).await {
+        Ok(raw) => raw,
+        Err(e) => return respond::json(e.status(), &ErrorResp::new(e.to_string())),
+    };
+    alerts::retain(&mut raw, q.lng, q.lat);
+    match from_raw(&raw, q.lng, q.lat, set) {
+        Ok(data) => respond::negotiated(&accept, StatusCode::OK, &data),
+        Err(e) => {
+            error_report::capture_upstream_error("caiyun", &e);
+            let e = FetchError::Format(e);
+            respond::json(e.status(), &ErrorResp::new(e.to_string()))
+        }
+    }
+}
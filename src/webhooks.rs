@@ -0,0 +1,335 @@
+// 天气条件触发的 webhook：按规则定时检查指定地点的天气，条件全部满足时向规则中的地址发送请求（如「AQI > 150 时通知」「明天最低气温 < 0 时提醒」）
+// 条件写法与出行提示规则相同（见 tips.toml 开头）；day = "tomorrow" 时 min_temp、max_temp、temp_range、rain_probability、precipitation 取明天
+// - WEBHOOK_RULES_FILE：规则文件（格式见 webhooks.example.toml）；启动时校验条件、地址与请求体模板
+// - 检查由定时任务 webhook_rules 执行（默认每 15 分钟，可用 SCHEDULE_WEBHOOK_RULES 调整），同一地点只取一次天气
+// 触发后在 cooldown_minutes（默认 360）内不再触发；发送失败不计为触发，下次检查时重试
+// 上次触发时间保存在 DATA_DIR/webhook_rules.json，重启后不会重复触发；最近一次检查结果见管理接口 /api/v1/admin/webhooks
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use axum::{http::StatusCode, response::Response};
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::OnceCell;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    Method, Url,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::{
+    config::env_nonempty,
+    respond, scheduler,
+    store::JsonStore,
+    tips::{Condition, Metrics},
+    upstream, weather, AppState,
+};
+
+const DEFAULT_COOLDOWN_MINUTES: i64 = 360;
+
+#[derive(Deserialize)]
+struct RuleFile {
+    #[serde(default)]
+    rules: Vec<RawRule>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+struct Location {
+    name: String,
+    lng: f64,
+    lat: f64,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Day {
+    #[default]
+    Today,
+    Tomorrow,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRule {
+    name: String,
+    location: Location,
+    #[serde(default)]
+    day: Day,
+    when: Vec<String>,
+    url: String,
+    method: Option<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    content_type: Option<String>,
+    // minijinja 模板；未设置时发送默认的 JSON
+    body: Option<String>,
+    cooldown_minutes: Option<i64>,
+}
+
+struct Rule {
+    name: String,
+    location: Location,
+    day: Day,
+    when: Vec<Condition>,
+    conditions: Vec<String>,
+    url: Url,
+    method: Method,
+    headers: HeaderMap,
+    has_body_template: bool,
+    cooldown: Duration,
+}
+
+// 最近一次检查的结果，供管理接口查看
+#[derive(Serialize, Clone, Default)]
+struct RuleStatus {
+    matched: Option<bool>,
+    metrics: BTreeMap<&'static str, Option<f64>>,
+    last_checked: Option<DateTime<Utc>>,
+    last_fired: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+struct Rules {
+    rules: Vec<Rule>,
+    templates: minijinja::Environment<'static>,
+    // 规则名 -> 上次触发时间
+    fired: JsonStore<HashMap<String, DateTime<Utc>>>,
+    status: Mutex<HashMap<String, RuleStatus>>,
+}
+
+static RULES: OnceCell<Rules> = OnceCell::new();
+
+fn parse_rule(raw: RawRule, templates: &mut minijinja::Environment<'static>) -> anyhow::Result<Rule> {
+    let name = raw.name.trim().to_string();
+    if name.is_empty() {
+        anyhow::bail!("规则缺少 name");
+    }
+    let loc = &raw.location;
+    if !(-180.0..=180.0).contains(&loc.lng) || !(-90.0..=90.0).contains(&loc.lat) {
+        anyhow::bail!("规则 {} 的 location 坐标无效", name);
+    }
+    if raw.when.is_empty() {
+        anyhow::bail!("规则 {} 缺少 when 条件", name);
+    }
+    let when = raw
+        .when
+        .iter()
+        .map(|c| Condition::parse(c))
+        .collect::<anyhow::Result<_>>()
+        .map_err(|e| anyhow::anyhow!("规则 {}: {}", name, e))?;
+    let url = Url::parse(&raw.url).map_err(|e| anyhow::anyhow!("规则 {} 的 url 无效: {}", name, e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        anyhow::bail!("规则 {} 的 url 仅支持 http/https", name);
+    }
+    let method = match raw.method.as_deref().map(str::to_ascii_uppercase).as_deref() {
+        None | Some("POST") => Method::POST,
+        Some("PUT") => Method::PUT,
+        Some("GET") => Method::GET,
+        Some(other) => anyhow::bail!("规则 {} 的 method 应为 POST/PUT/GET: {}", name, other),
+    };
+    let mut headers = HeaderMap::new();
+    for (key, value) in &raw.headers {
+        let key = HeaderName::from_bytes(key.as_bytes()).map_err(|_| anyhow::anyhow!("规则 {} 的请求头名无效: {}", name, key))?;
+        let value = HeaderValue::from_str(value).map_err(|_| anyhow::anyhow!("规则 {} 的请求头 {} 取值无效", name, key))?;
+        headers.insert(key, value);
+    }
+    let content_type = raw.content_type.as_deref().unwrap_or("application/json");
+    let content_type =
+        HeaderValue::from_str(content_type).map_err(|_| anyhow::anyhow!("规则 {} 的 content_type 无效", name))?;
+    headers.insert(CONTENT_TYPE, content_type);
+    let has_body_template = raw.body.is_some();
+    if let Some(body) = raw.body {
+        templates
+            .add_template_owned(name.clone(), body)
+            .map_err(|e| anyhow::anyhow!("规则 {} 的 body 模板无效: {}", name, e))?;
+    }
+    let cooldown = raw.cooldown_minutes.unwrap_or(DEFAULT_COOLDOWN_MINUTES);
+    if cooldown < 0 {
+        anyhow::bail!("规则 {} 的 cooldown_minutes 不能为负", name);
+    }
+    Ok(Rule {
+        name,
+        location: raw.location,
+        day: raw.day,
+        when,
+        conditions: raw.when,
+        url,
+        method,
+        headers,
+        has_body_template,
+        cooldown: Duration::minutes(cooldown),
+    })
+}
+
+fn load(path: &str) -> anyhow::Result<(Vec<Rule>, minijinja::Environment<'static>)> {
+    let text = std::fs::read_to_string(path).map_err(|e| anyhow::anyhow!("无法读取 {}: {}", path, e))?;
+    let file: RuleFile = toml::from_str(&text).map_err(|e| anyhow::anyhow!("{} 无效: {}", path, e))?;
+    let mut templates = minijinja::Environment::new();
+    let mut rules: Vec<Rule> = Vec::new();
+    for raw in file.rules {
+        let rule = parse_rule(raw, &mut templates).map_err(|e| anyhow::anyhow!("{} 无效: {}", path, e))?;
+        if rules.iter().any(|r| r.name == rule.name) {
+            anyhow::bail!("{} 无效: 规则名 {} 重复", path, rule.name);
+        }
+        rules.push(rule);
+    }
+    Ok((rules, templates))
+}
+
+// 读取并校验规则；未设置 WEBHOOK_RULES_FILE 时不做任何事
+pub fn init_from_env() -> anyhow::Result<()> {
+    let Some(path) = env_nonempty("WEBHOOK_RULES_FILE") else {
+        return Ok(());
+    };
+    let (rules, templates) = load(&path)?;
+    let fired = JsonStore::open("webhook_rules")?;
+    info!("{} webhook rule(s) loaded from {}", rules.len(), path);
+    let _ = RULES.set(Rules { rules, templates, fired, status: Mutex::new(HashMap::new()) });
+    Ok(())
+}
+
+// 注册检查任务（需先 init_from_env）
+pub fn spawn(state: AppState) -> anyhow::Result<()> {
+    let Some(rules) = RULES.get() else {
+        return Ok(());
+    };
+    scheduler::register("webhook_rules", "*/15 * * * *", move || {
+        let state = state.clone();
+        async move { rules.check_all(&state).await }
+    })
+}
+
+// 规则可引用的指标；日指标按 day 取今天或明天
+fn metrics(data: &weather::WeatherData, day: Day) -> Metrics {
+    let cur = &data.current;
+    let daily = data.daily.get(if day == Day::Tomorrow { 1 } else { 0 }).unwrap_or(&Value::Null);
+    let num = |v: Option<&Value>| v.and_then(Value::as_f64);
+    Metrics {
+        temperature: Some(cur.temperature as f64),
+        apparent_temperature: Some(cur.apparent_temperature as f64),
+        min_temp: num(daily.get("min_temp")),
+        max_temp: num(daily.get("max_temp")),
+        humidity: Some(cur.humidity as f64),
+        wind_speed: Some(cur.wind_speed as f64),
+        aqi: num(cur.air_quality.pointer("/aqi/chn")),
+        uv_index: cur.uv_index.map(|uv| uv as f64),
+        rain_probability: num(daily.pointer("/precipitation/probability")),
+        precipitation: num(daily.pointer("/precipitation/total")),
+        will_rain: data.minutely.get("will_rain").and_then(Value::as_bool).unwrap_or(false),
+    }
+}
+
+impl Rules {
+    async fn check_all(&self, state: &AppState) -> anyhow::Result<()> {
+        // 同一地点的规则共用一次天气结果
+        let mut fetched: Vec<((f64, f64), Result<weather::WeatherData, String>)> = Vec::new();
+        let mut failed = 0;
+        for rule in &self.rules {
+            let key = (rule.location.lng, rule.location.lat);
+            let i = match fetched.iter().position(|(k, _)| *k == key) {
+                Some(i) => i,
+                None => {
+                    let result = weather::fetch(state, key.0, key.1).await.map_err(|e| e.to_string());
+                    fetched.push((key, result));
+                    fetched.len() - 1
+                }
+            };
+            let result = match &fetched[i].1 {
+                Ok(data) => self.check(rule, data).await,
+                Err(e) => Err(anyhow::anyhow!("获取天气失败: {}", e)),
+            };
+            if let Err(e) = result {
+                warn!(rule = %rule.name, "webhook rule failed: {}", e);
+                self.status.lock().unwrap().entry(rule.name.clone()).or_default().last_error = Some(e.to_string());
+                failed += 1;
+            }
+        }
+        if failed > 0 {
+            anyhow::bail!("{} 条 webhook 规则执行失败", failed);
+        }
+        Ok(())
+    }
+
+    async fn check(&self, rule: &Rule, data: &weather::WeatherData) -> anyhow::Result<()> {
+        let now = Utc::now();
+        let metrics = metrics(data, rule.day);
+        let matched = rule.when.iter().all(|c| c.matches(&metrics));
+        let values: BTreeMap<&'static str, Option<f64>> = rule.when.iter().map(|c| (c.metric, metrics.get(c.metric))).collect();
+        let last_fired = self.fired.read(|f| f.get(&rule.name).copied());
+        {
+            let mut status = self.status.lock().unwrap();
+            let status = status.entry(rule.name.clone()).or_default();
+            status.matched = Some(matched);
+            status.metrics = values.clone();
+            status.last_checked = Some(now);
+            status.last_fired = last_fired;
+            status.last_error = None;
+        }
+        if !matched || last_fired.is_some_and(|t| now - t < rule.cooldown) {
+            return Ok(());
+        }
+
+        let mut context = json!({
+            "rule": rule.name,
+            "location": rule.location,
+            "day": if rule.day == Day::Tomorrow { "tomorrow" } else { "today" },
+            "when": rule.conditions,
+            "metrics": values,
+            "fired_at": now.to_rfc3339(),
+        });
+        let body = if rule.has_body_template {
+            // 模板中还可引用完整的天气数据（与 /api/v1/weather 相同）
+            context["weather"] = serde_json::to_value(data)?;
+            self.templates.get_template(&rule.name)?.render(&context)?
+        } else {
+            context.to_string()
+        };
+        let mut rb = upstream::client("webhook").request(rule.method.clone(), rule.url.clone()).headers(rule.headers.clone());
+        if rule.method != Method::GET {
+            rb = rb.body(body);
+        }
+        // 状态码错误会带上完整地址，去掉后再记入 last_error（地址中常含密钥）
+        upstream::send("webhook", "{url}", rb).await?.error_for_status().map_err(|e| e.without_url())?;
+        info!(rule = %rule.name, "webhook fired");
+
+        if let Err(e) = self.fired.update(|f| f.insert(rule.name.clone(), now)) {
+            warn!("保存 webhook 触发记录失败: {}", e);
+        }
+        self.status.lock().unwrap().entry(rule.name.clone()).or_default().last_fired = Some(now);
+        Ok(())
+    }
+}
+
+// GET /api/v1/admin/webhooks：各规则与最近一次检查结果；地址只显示主机，避免泄露其中的密钥
+pub async fn admin_list() -> Response {
+    let Some(rules) = RULES.get() else {
+        return respond::json(StatusCode::OK, &json!({ "rules": [] }));
+    };
+    let status = rules.status.lock().unwrap();
+    let list: Vec<Value> = rules
+        .rules
+        .iter()
+        .map(|r| {
+            let mut v = json!({
+                "name": r.name,
+                "location": r.location,
+                "when": r.conditions,
+                "host": r.url.host_str(),
+                "cooldown_minutes": r.cooldown.num_minutes(),
+            });
+            let s = status.get(&r.name).cloned().unwrap_or_else(|| RuleStatus {
+                last_fired: rules.fired.read(|f| f.get(&r.name).copied()),
+                ..Default::default()
+            });
+            if let (Value::Object(v), Value::Object(s)) = (&mut v, json!(s)) {
+                v.extend(s);
+            }
+            v
+        })
+        .collect();
+    respond::json(StatusCode::OK, &json!({ "rules": list }))
+}
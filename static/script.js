@@ -7,7 +7,8 @@
 if ('serviceWorker' in navigator) {
   window.addEventListener('load', async () => {
     try {
-      const registration = await navigator.serviceWorker.register('/static/sw.js');
+      // 相对地址：部署在路径前缀下时注册 /前缀/sw.js，作用域为该前缀
+      const registration = await navigator.serviceWorker.register('sw.js', { scope: './' });
       console.log('Service Worker 注册成功:', registration.scope);
       
       // 监听更新
@@ -168,6 +169,12 @@ class WeatherApp {
     this.isLoading = false;
     this.favoriteLocations = this.loadFavoriteLocations();
     this.defaultLocation = this.loadDefaultLocation();
+    // 服务端运行时配置（/api/v1/config），加载前按 API 的公制单位显示
+    this.config = {
+      default_city: null,
+      units: { temperature: '°C', wind_speed: 'km/h', visibility: 'km', pressure: 'hPa', concentration: 'μg/m³' },
+      features: {}
+    };
 
     // 跟踪事件监听器以便清理
     this.eventListeners = [];
@@ -191,10 +198,26 @@ class WeatherApp {
     // 立即设置基于时间的背景
     this.updateTimeBasedBackground();
     console.log('[WeatherApp] 背景更新完成');
+    this.configReady = this.loadConfig();
     this.checkLocationPermission();
     console.log('[WeatherApp] 开始检查位置权限...');
   }
 
+  // 读取服务端运行时配置（默认城市、单位、功能开关等），失败时沿用构造时的默认值
+  async loadConfig() {
+    try {
+      const response = await fetch('api/v1/config');
+      if (!response.ok) {
+        throw new Error(`HTTP ${response.status}: ${response.statusText}`);
+      }
+      const config = await response.json();
+      this.config = { ...this.config, ...config, units: { ...this.config.units, ...config.units } };
+      console.log('[配置] 加载完成:', this.config);
+    } catch (error) {
+      console.error('[配置] 加载失败:', error);
+    }
+  }
+
   // 缓存DOM元素引用
   cacheDOMElements() {
     const elements = [
@@ -302,7 +325,20 @@ class WeatherApp {
   // 检查位置权限并自动获取位置
   async checkLocationPermission() {
     console.log('[初始化] 开始checkLocationPermission');
-    
+    // 默认城市等依赖服务端配置
+    await this.configReady;
+
+    // 分享链接（/s/<短码> 跳转而来）带有 ?lng=&lat=&name=，优先使用
+    const params = new URLSearchParams(window.location.search);
+    if (params.has('lng') && params.has('lat')) {
+      const lng = this.validateNumber(params.get('lng'), NaN, -180, 180);
+      const lat = this.validateNumber(params.get('lat'), NaN, -90, 90);
+      if (!isNaN(lng) && !isNaN(lat)) {
+        await this.selectLocation(lng, lat, params.get('name') || '');
+        return;
+      }
+    }
+
     // 优先检查是否有默认位置
     if (this.defaultLocation) {
       console.log('加载默认位置:', this.defaultLocation);
@@ -321,8 +357,8 @@ class WeatherApp {
     } catch (ipError) {
       console.error('[初始化] IP定位失败:', ipError);
       this.handleError(ipError, 'IP定位');
-      // 直接加载默认位置（北京）
-      await this.loadBeijingWeather();
+      // 直接加载服务端配置的默认城市
+      await this.loadDefaultCityWeather();
     }
     
     // 异步尝试GPS定位，不阻塞页面
@@ -412,6 +448,7 @@ class WeatherApp {
       await this.getLocationByIP();
     } catch (ipError) {
       console.error('IP 定位也失败:', ipError);
+      const cityName = (this.config.default_city && this.config.default_city.name) || '默认城市';
       // 显示更友好的错误信息和建议
       this.showError(`
         <div style="text-align: center;">
@@ -423,12 +460,12 @@ class WeatherApp {
             <li>位置服务被禁用</li>
             <li>防火墙或网络限制</li>
           </ul>
-          <p><strong>正在为您显示北京天气，您也可以手动选择位置</strong></p>
+          <p><strong>正在为您显示${cityName}天气，您也可以手动选择位置</strong></p>
         </div>
       `);
 
-      // 直接加载默认位置（北京）
-      await this.loadBeijingWeather();
+      // 直接加载服务端配置的默认城市
+      await this.loadDefaultCityWeather();
     }
   }
 
@@ -436,7 +473,7 @@ class WeatherApp {
   async getLocationByIP() {
     console.log('[IP定位] 开始获取IP位置...');
     try {
-      const response = await fetch('api/location/ip');
+      const response = await fetch('api/v1/location/ip');
       console.log('[IP定位] API响应状态:', response.status);
 
       if (!response.ok) {
@@ -450,6 +487,10 @@ class WeatherApp {
         throw new Error(data.error);
       }
 
+      if (data.local_network) {
+        console.log('[IP定位] 局域网访问，无法按 IP 定位，使用默认城市');
+      }
+
       this.currentLocation = { lat: data.lat, lng: data.lng };
 
       console.log('IP 定位成功:', this.currentLocation, '地址:', data.address);
@@ -463,14 +504,18 @@ class WeatherApp {
     }
   }
 
-  // 加载默认位置（北京）
-  async loadBeijingWeather() {
+  // 加载服务端配置的默认城市
+  async loadDefaultCityWeather() {
     try {
-      console.log('加载默认位置：北京');
-      this.currentLocation = { lat: 39.9042, lng: 116.4074 };
+      const city = this.config.default_city;
+      if (!city) {
+        throw new Error('未能读取默认城市配置');
+      }
+      console.log('加载默认位置：', city.name);
+      this.currentLocation = { lat: city.lat, lng: city.lng };
 
       // 获取天气数据
-      await this.fetchWeatherData(116.4074, 39.9042, '北京市');
+      await this.fetchWeatherData(city.lng, city.lat, city.name);
 
     } catch (error) {
       this.handleError(error, '加载默认位置');
@@ -545,7 +590,7 @@ class WeatherApp {
     this.showLoading('正在获取天气信息...');
 
     try {
-      const url = `api/weather?lng=${lng}&lat=${lat}`;
+      const url = `api/v1/weather?lng=${lng}&lat=${lat}`;
       console.log('发送请求到:', url);
       
       // 使用请求管理器，自动处理缓存和去重
@@ -569,7 +614,7 @@ class WeatherApp {
   // 获取详细地址
   async getDetailedAddress(lng, lat) {
     try {
-      const response = await fetch(`api/location/geocode?lng=${lng}&lat=${lat}`);
+      const response = await fetch(`api/v1/location/geocode?lng=${lng}&lat=${lat}`);
 
       if (!response.ok) {
         return '未知位置';
@@ -630,7 +675,7 @@ class WeatherApp {
 
     try {
       // 使用请求管理器
-      const url = `api/location/search?q=${encodeURIComponent(query)}`;
+      const url = `api/v1/location/search?q=${encodeURIComponent(query)}`;
       const data = await requestManager.fetch(url);
 
       if (data.results && data.results.length > 0) {
@@ -693,7 +738,7 @@ class WeatherApp {
   displayWeatherData(locationName = null) {
     if (!this.weatherData) return;
 
-    const { current, hourly, daily, forecast_keypoint } = this.weatherData;
+    const { current, hourly, daily, forecast_keypoint, advice } = this.weatherData;
 
     // 更新当前天气
     this.updateCurrentWeather(current);
@@ -707,8 +752,8 @@ class WeatherApp {
     // 更新3天预报
     this.updateDailyForecast(daily);
 
-    // 更新生活指数提醒
-    this.updateWeatherTips(daily);
+    // 更新出行提示
+    this.updateWeatherTips(advice);
 
     // 更新位置和时间信息
     this.updateLocationInfo(forecast_keypoint, locationName);
@@ -733,28 +778,25 @@ class WeatherApp {
     const pressure = this.validateNumber(current.pressure, '--', 0);
 
     // 批量更新DOM以减少重排
+    const units = this.config.units;
     const updates = [
       { element: this.domElements.currentTemp, content: temperature },
-      { element: this.domElements.weatherIcon, content: current.weather_info.icon || '❓' },
       { element: this.domElements.weatherDesc, content: current.weather_info.desc || '未知' },
-      { element: this.domElements.feelsLike, content: `体感温度 ${apparentTemp}°C` },
+      { element: this.domElements.feelsLike, content: `体感温度 ${apparentTemp}${units.temperature}` },
       { element: this.domElements.humidity, content: `${humidity}%` },
-      { element: this.domElements.windSpeed, content: `${windSpeed} km/h` },
-      { element: this.domElements.visibility, content: `${visibility} km` },
-      { element: this.domElements.pressure, content: `${pressure} hPa` }
+      { element: this.domElements.windSpeed, content: `${windSpeed} ${units.wind_speed}` },
+      { element: this.domElements.visibility, content: `${visibility} ${units.visibility}` },
+      { element: this.domElements.pressure, content: `${pressure} ${units.pressure}` }
     ];
 
     // 使用requestAnimationFrame批量更新，减少DOM操作
     requestAnimationFrame(() => {
       updates.forEach(({ element, content }) => {
-        if (!element) return;
-        // weatherIcon 允许后端返回 HTML（例如云遮月组合图标）
-        if (element === this.domElements.weatherIcon && typeof content === 'string' && content.trim().startsWith('<')) {
-          element.innerHTML = content;
-        } else {
-          element.textContent = content;
-        }
+        if (element) element.textContent = content;
       });
+      if (this.domElements.weatherIcon) {
+        this.domElements.weatherIcon.innerHTML = this.iconHtml(current.weather_info);
+      }
 
       // 更新基于时间的背景
       this.updateTimeBasedBackground();
@@ -808,9 +850,21 @@ class WeatherApp {
     const pm25El = document.getElementById('pm25');
     const pm10El = document.getElementById('pm10');
     const o3El = document.getElementById('o3');
-    if (pm25El) pm25El.textContent = `${airQuality.pm25 || '--'} μg/m³`;
-    if (pm10El) pm10El.textContent = `${airQuality.pm10 || '--'} μg/m³`;
-    if (o3El) o3El.textContent = `${airQuality.o3 || '--'} μg/m³`;
+    const unit = this.config.units.concentration;
+    if (pm25El) pm25El.textContent = `${airQuality.pm25 || '--'} ${unit}`;
+    if (pm10El) pm10El.textContent = `${airQuality.pm10 || '--'} ${unit}`;
+    if (o3El) o3El.textContent = `${airQuality.o3 || '--'} ${unit}`;
+  }
+
+  // 天气图标：组合图标按 layers 从后到前叠放（如云遮月），内容一律转义
+  iconHtml(info) {
+    const escape = (s) => String(s ?? '').replace(/[&<>"']/g, (c) => `&#${c.charCodeAt(0)};`);
+    const layers = info && Array.isArray(info.layers) ? info.layers : null;
+    if (!layers) return escape((info && info.icon) || '❓');
+    const spans = layers.map((layer, i) =>
+      `<span class="${i === layers.length - 1 ? 'i-front' : 'i-back'}">${escape(layer)}</span>`
+    ).join('');
+    return `<span class="icon-stacked">${spans}</span>`;
   }
 
   // 更新24小时预报 - 优化DOM操作，使用DocumentFragment
@@ -826,7 +880,7 @@ class WeatherApp {
       div.className = 'hourly-item';
       div.innerHTML = `
         <div class="hourly-time">${item.time}:00</div>
-        <div class="hourly-icon">${item.weather_info.icon}</div>
+        <div class="hourly-icon">${this.iconHtml(item.weather_info)}</div>
         <div class="hourly-temp">${item.temperature}°</div>
       `;
       fragment.appendChild(div);
@@ -849,14 +903,16 @@ class WeatherApp {
       const div = document.createElement('div');
       div.className = 'daily-item';
       div.setAttribute('data-weekday', item.weekday);
+      // 农历：节气当天显示节气名
+      const lunar = item.lunar ? ` · ${item.solar_term || item.lunar.text}` : '';
       div.innerHTML = `
         <div class="daily-left">
           <div class="daily-relative-day">${item.relativeDay || item.weekday}</div>
-          <div class="daily-weekday">${item.weekday}</div>
+          <div class="daily-weekday">${item.weekday}${lunar}</div>
         </div>
         <div class="daily-right">
           <div class="daily-weather">
-            <div class="daily-icon">${item.weather_info.icon}</div>
+            <div class="daily-icon">${this.iconHtml(item.weather_info)}</div>
             <div class="daily-desc">${item.weather_info.desc}</div>
           </div>
           <div class="daily-temp-range">${item.min_temp}° / ${item.max_temp}°</div>
@@ -870,53 +926,21 @@ class WeatherApp {
     container.appendChild(fragment);
   }
 
-  // 更新生活指数提醒
-  updateWeatherTips(daily) {
-    if (!daily || !daily[0] || !daily[0].life_index) {
-      return;
-    }
-
-    const todayLifeIndex = daily[0].life_index;
-    const tips = [];
-
-    // 生活指数图标映射
-    const indexIcons = {
-      ultraviolet: '☀️',
-      carWashing: '🚗',
-      dressing: '👕',
-      comfort: '😊',
-      coldRisk: '🤧'
-    };
-
-    // 生活指数名称映射
-    const indexNames = {
-      ultraviolet: '紫外线',
-      carWashing: '洗车',
-      dressing: '穿衣',
-      comfort: '舒适度',
-      coldRisk: '感冒'
-    };
+  // 更新出行提示（服务端按规则生成，已按优先级排序）
+  updateWeatherTips(advice) {
+    const tips = (advice && advice.tips) || [];
+    const tipsContainer = document.getElementById('weatherTips');
+    const tipsCard = document.getElementById('weatherTipsCard');
+    if (!tipsContainer || !tipsCard) return;
 
-    // 生成提醒信息
-    Object.keys(indexIcons).forEach(key => {
-      const indexData = todayLifeIndex[key];
-      if (indexData && indexData.desc && indexData.desc !== '暂无数据') {
-        tips.push(`${indexIcons[key]} ${indexNames[key]}: ${indexData.desc}`);
-      }
+    tipsContainer.textContent = '';
+    tips.forEach(({ tip }) => {
+      const div = document.createElement('div');
+      div.className = 'weather-tip-item';
+      div.textContent = `💡 ${tip}`;
+      tipsContainer.appendChild(div);
     });
-
-    // 显示生活指数提醒
-    if (tips.length > 0) {
-      const tipsContainer = document.getElementById('weatherTips');
-      const tipsCard = document.getElementById('weatherTipsCard');
-
-      if (tipsContainer && tipsCard) {
-        tipsContainer.innerHTML = tips.map(tip =>
-          `<div class="weather-tip-item">${tip}</div>`
-        ).join('');
-        tipsCard.style.display = 'block';
-      }
-    }
+    tipsCard.style.display = tips.length > 0 ? 'block' : 'none';
   }
 
   // 更新位置和时间信息
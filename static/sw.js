@@ -1,27 +1,34 @@
-const CACHE_NAME = 'caiyun-weather-v2';  // 增加版本号触发更新
-const STATIC_CACHE_NAME = 'caiyun-weather-static-v2';
-const API_CACHE_NAME = 'caiyun-weather-api-v2';
+const CACHE_NAME = 'caiyun-weather-v5';  // 增加版本号触发更新
+const STATIC_CACHE_NAME = 'caiyun-weather-static-v5';
+const API_CACHE_NAME = 'caiyun-weather-api-v5';
+
+// 站点根路径：sw.js 位于站点根下，部署在路径前缀（BASE_PATH）或前缀租户下时为 /前缀/
+const BASE = new URL('./', self.location).pathname;
+
+// 离线兜底数据（服务端最近一次成功获取的天气）
+const OFFLINE_URL = BASE + 'offline.json';
 
 // 只缓存关键资源，加快安装速度
 const CRITICAL_ASSETS = [
-  '/',
-  '/static/styles.css',
-  '/static/script.js',
-  '/static/manifest.json'
+  BASE,
+  BASE + 'static/styles.css',
+  BASE + 'static/script.js',
+  BASE + 'manifest.webmanifest',
+  OFFLINE_URL
   // 移除大图片文件，改为运行时缓存
 ];
 
 // 大资源文件列表（运行时按需缓存）
 const LAZY_CACHE_ASSETS = [
-  '/static/day.png',
-  '/static/night.webp'
+  BASE + 'static/day.png',
+  BASE + 'static/night.webp'
 ];
 
 const API_ROUTES = [
-  '/api/weather',
-  '/api/location/ip',
-  '/api/location/geocode',
-  '/api/location/search'
+  BASE + 'api/v1/weather',
+  BASE + 'api/v1/location/ip',
+  BASE + 'api/v1/location/geocode',
+  BASE + 'api/v1/location/search'
 ];
 
 self.addEventListener('install', event => {
@@ -76,7 +83,7 @@ self.addEventListener('fetch', event => {
     return;
   }
 
-  if (url.pathname.startsWith('/api/')) {
+  if (url.pathname.startsWith(BASE + 'api/')) {
     event.respondWith(
       handleApiRequest(request)
     );
@@ -107,7 +114,7 @@ async function handleStaticRequest(request) {
     
     if (request.mode === 'navigate') {
       const cache = await caches.open(STATIC_CACHE_NAME);
-      return cache.match('/');
+      return cache.match(BASE);
     }
     
     throw error;
@@ -137,6 +144,12 @@ async function handleApiRequest(request) {
           });
           
           cache.put(cacheKey, cachedResponse);
+
+          // 同步刷新离线兜底数据，离线时展示最近一次看到的天气
+          if (url.pathname === BASE + 'api/v1/weather') {
+            const offlineCopy = response.clone();
+            caches.open(STATIC_CACHE_NAME).then(c => c.put(OFFLINE_URL, offlineCopy));
+          }
         }
         return response;
       });
@@ -165,6 +178,14 @@ async function handleApiRequest(request) {
       console.log('网络失败，使用过期缓存:', cacheKey);
       return cachedResponse;
     }
+
+    if (url.pathname === BASE + 'api/v1/weather') {
+      const offline = await caches.match(OFFLINE_URL);
+      if (offline) {
+        console.log('网络失败，使用离线兜底数据');
+        return offline;
+      }
+    }
     
     throw error;
   }
@@ -0,0 +1,55 @@
+// 压测子命令：对模拟模式的服务回放请求列表，输出按路由的统计；parse 比较 JSON 解析器
+
+mod common;
+
+use std::process::Command;
+
+use common::Server;
+
+fn bench(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_caiyun-weather-rust"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .arg("bench")
+        .args(args)
+        .output()
+        .expect("run bench")
+}
+
+#[tokio::test]
+async fn bench_reports_latency_per_route() {
+    let server = Server::start(&[("MOCK_MODE", "1")]).await;
+
+    let out = bench(&["--url", &server.base, "--duration", "0.5", "--concurrency", "4", "--max-p99-ms", "5000"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{}\n{}", stdout, String::from_utf8_lossy(&out.stderr));
+    for route in ["/api/v1/weather ", "/api/v2/weather ", "/api/v1/ha/weather ", "total "] {
+        let line = stdout.lines().find(|l| l.starts_with(route)).unwrap_or_else(|| panic!("{}\n{}", route, stdout));
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        assert!(columns[1].parse::<u64>().unwrap() > 0, "{}", line);
+        assert_eq!(columns.last(), Some(&"0"), "{}", line);
+    }
+
+    // 请求失败时（未开启的功能返回 404）以非零状态退出
+    let traffic = std::env::temp_dir().join(format!("caiyun-bench-{}.txt", std::process::id()));
+    std::fs::write(&traffic, "GET /api/v1/accuracy?lng=116.4&lat=39.9\n").unwrap();
+    let out = bench(&["--url", &server.base, "--duration", "0.2", "--traffic", traffic.to_str().unwrap(), "--max-p99-ms", "5000"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("/api/v1/accuracy 失败"));
+    let _ = std::fs::remove_file(&traffic);
+}
+
+#[test]
+fn bench_parse_times_each_parser() {
+    let out = bench(&["parse", "--iterations", "3"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{}\n{}", stdout, String::from_utf8_lossy(&out.stderr));
+    let mut parsers = vec!["serde_json "];
+    if cfg!(feature = "simd-json") {
+        parsers.push("simd-json ");
+    }
+    for parser in parsers {
+        let line = stdout.lines().find(|l| l.starts_with(parser)).unwrap_or_else(|| panic!("{}\n{}", parser, stdout));
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        assert!(columns[3].parse::<f64>().unwrap() > 0.0, "{}", line);
+    }
+}
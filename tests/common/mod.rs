@@ -0,0 +1,232 @@
+// 集成测试公共部分：以子进程启动服务二进制，测试通过 HTTP 访问
+
+use std::{
+    net::TcpListener,
+    process::{Child, Command, Stdio},
+    time::Duration,
+};
+
+pub struct Server {
+    child: Child,
+    pub base: String,
+}
+
+// 可能影响行为的环境变量，启动前清除，避免开发者本机配置干扰测试
+const CLEARED: &[&str] = &[
+    "CAIYUN_API_TOKEN",
+    "CAIYUN_API_URL",
+    "AMAP_API_KEY",
+    "AMAP_API_URL",
+    "MEITUAN_API_URL",
+    "ADMIN_TOKEN",
+    "PUBLIC_URL",
+    "MOCK_MODE",
+    "MOCK_FIXTURE",
+    "MOCK_FIXTURES_DIR",
+    "DEMO_MODE",
+    "TELEGRAM_BOT_TOKEN",
+    "MQTT_URL",
+    "SMTP_HOST",
+    "GRPC_PORT",
+    "SENTRY_DSN",
+    "OTEL_EXPORTER_OTLP_ENDPOINT",
+    "LOG_FILE",
+    "HTTP_PROXY",
+    "SOCKS_PROXY",
+    "NO_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "HTTPS_PROXY",
+    "all_proxy",
+    "ALL_PROXY",
+    "no_proxy",
+    "CAIYUN_PROXY",
+    "AMAP_PROXY",
+    "MEITUAN_PROXY",
+    "UPSTREAM_TIMEOUT_SECS",
+    "CAIYUN_TIMEOUT_SECS",
+    "AMAP_TIMEOUT_SECS",
+    "MEITUAN_TIMEOUT_SECS",
+    "MAX_IN_FLIGHT",
+    "MAX_QUEUED",
+    "RETRY_AFTER_SECS",
+    "ROUTE_TIMEOUT_SECS",
+    "ROUTE_TIMEOUTS",
+    "WEATHER_PROVIDERS",
+    "OPENWEATHERMAP_API_KEY",
+    "OPEN_METEO_API_URL",
+    "OPENWEATHERMAP_API_URL",
+    "QWEATHER_KEY",
+    "QWEATHER_API_URL",
+    "QWEATHER_GEO_URL",
+    "WEATHER_BLEND",
+    "WEATHER_BLEND_BLOCKS",
+    "WEATHER_BLEND_TOLERANCE",
+    "NOWCAST_LANG",
+    "DATA_DIR",
+    "USAGE_STATS",
+    "HOT_LIST_SIZE",
+    "HOT_LIST_TRACKED",
+    "HOT_LIST_MIN_REQUESTS",
+    "USAGE_STATS_RETENTION_DAYS",
+    "API_KEYS",
+    "API_KEYS_DB",
+    "API_KEY_TIERS",
+    "ASSET_FINGERPRINT",
+    "SPA_FALLBACK",
+    "UPSTREAM_MAX_BODY_BYTES",
+    "MAX_REQUEST_BODY_BYTES",
+    "COMPRESSION",
+    "COMPRESSION_LEVEL",
+    "COMPRESSION_MIN_BYTES",
+    "COMPRESSION_SKIP_TYPES",
+    "H2C",
+    "HTTP3_PORT",
+    "UPSTREAM_DNS",
+    "UPSTREAM_DNS_CACHE_SIZE",
+    "UPSTREAM_DNS_MIN_TTL_SECS",
+    "UPSTREAM_HOSTS",
+    "HEDGE_REQUESTS",
+    "HEDGE_PERCENTILE",
+    "HEDGE_MIN_MS",
+    "HEDGE_MAX_MS",
+    "HEDGE_BUDGET_PERCENT",
+    "CAIYUN_RPM",
+    "CAIYUN_RPM_RESERVE_PERCENT",
+    "WEATHER_CACHE_SECS",
+    "GEOCODE_CACHE_SECS",
+    "CACHE_STALE_SECS",
+    "CACHE_MAX_ENTRIES",
+    "CACHE_PERSIST",
+    "CACHE_DB",
+    "REDIS_URL",
+    "REDIS_PREFIX",
+    "CHANGES_TEMPERATURE_DELTA",
+    "ICON_SETS_FILE",
+    "ICON_SET",
+    "TIPS_RULES_FILE",
+    "BRIEF_TEMPLATES_FILE",
+    "FEATURE_FLAGS",
+    "FEATURE_FLAGS_FILE",
+    "ALERT_MATCH_ADCODE",
+    "ALERT_RADIUS_KM",
+    "ALERT_HISTORY",
+    "ALERT_HISTORY_DB",
+    "ALERT_HISTORY_RETENTION_DAYS",
+    "ACCURACY_LOCATIONS",
+    "ACCURACY_DB",
+    "TENANTS_FILE",
+    "ACCOUNTS",
+    "ACCOUNTS_DB",
+    "ACCOUNTS_SESSION_DAYS",
+    "ACCOUNTS_ALLOW_REGISTER",
+    "OIDC_ISSUER",
+    "OIDC_CLIENT_ID",
+    "OIDC_CLIENT_SECRET",
+    "OIDC_REDIRECT_URL",
+    "OIDC_SCOPES",
+    "SHARE_LINKS",
+    "SHARE_DB",
+    "SHARE_RETENTION_DAYS",
+    "GEOIP_CACHE_SECS",
+    "GEOIP_NEGATIVE_CACHE_SECS",
+    "GEOIP_CACHE_SIZE",
+    "DEFAULT_LNG",
+    "DEFAULT_LAT",
+    "DEFAULT_ADDRESS",
+    "WEBHOOK_RULES_FILE",
+    "WEATHER_METRICS_LOCATIONS",
+    "CHAOS_FAILURE_PERCENT",
+    "CHAOS_DELAY_PERCENT",
+    "CHAOS_DELAY_MS",
+    "CHAOS_UPSTREAMS",
+    "CHAOS_ALLOW_RELEASE",
+    "RESPONSE_SIGNING_SECRET",
+    "RADAR_TILE_URL",
+    "RADAR_CACHE_SECS",
+    "RADAR_CACHE_DIR",
+    "ACCESS_LOG_FILE",
+    "ACCESS_LOG_FORMAT",
+    "ACCESS_LOG_ROTATION",
+    "ACCESS_LOG_TRUSTED_PROXIES",
+    "BASE_PATH",
+    "SHUTDOWN_TIMEOUT",
+];
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .map(|a| a.port())
+        .expect("free port")
+}
+
+// 以给定环境变量运行服务二进制（已清除 CLEARED 中的变量），用于子命令
+#[allow(dead_code)]
+pub fn command(envs: &[(&str, &str)]) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_caiyun-weather-rust"));
+    cmd.current_dir(env!("CARGO_MANIFEST_DIR")).env("RUST_LOG", "warn");
+    for key in CLEARED {
+        cmd.env_remove(key);
+    }
+    cmd.envs(envs.iter().copied());
+    cmd
+}
+
+impl Server {
+    pub async fn start(envs: &[(&str, &str)]) -> Server {
+        let port = free_port();
+        let mut cmd = command(envs);
+        cmd.env("HOST", "127.0.0.1")
+            .env("PORT", port.to_string())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        let child = cmd.spawn().expect("spawn server");
+        let server = Server { child, base: format!("http://127.0.0.1:{}", port) };
+
+        for _ in 0..100 {
+            if tokio::net::TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+                return server;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("server did not start on port {}", port);
+    }
+
+    pub async fn get(&self, path: &str) -> reqwest::Response {
+        reqwest::get(format!("{}{}", self.base, path)).await.expect("request")
+    }
+
+    // 并非每个测试文件都会用到
+    #[allow(dead_code)]
+    pub async fn get_json(&self, path: &str) -> serde_json::Value {
+        let resp = self.get(path).await;
+        assert!(resp.status().is_success(), "{} -> {}", path, resp.status());
+        resp.json().await.expect("json body")
+    }
+}
+
+impl Server {
+    // 发送 SIGTERM 并等待进程退出
+    #[allow(dead_code)]
+    pub async fn terminate(&mut self) -> std::process::ExitStatus {
+        let status = Command::new("kill")
+            .args(["-TERM", &self.child.id().to_string()])
+            .status()
+            .expect("kill");
+        assert!(status.success(), "kill -TERM failed");
+        for _ in 0..100 {
+            if let Some(status) = self.child.try_wait().expect("wait") {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("server did not exit after SIGTERM");
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,141 @@
+// 页面与静态资源：指纹改写、缓存头、压缩，以及明文监听的协议协商
+
+mod common;
+
+use common::Server;
+
+#[tokio::test]
+async fn index_references_fingerprinted_assets() {
+    let server = Server::start(&[]).await;
+
+    let index = server.get("/").await;
+    assert_eq!(index.headers()["cache-control"], "no-cache");
+    let html = index.text().await.unwrap();
+    let start = html.find("/static/styles.").expect("stylesheet link");
+    let href = &html[start..start + html[start..].find('"').unwrap()];
+    assert!(!html.contains("\"/static/styles.css\""));
+    assert!(href.ends_with(".css") && href.len() == "/static/styles.".len() + 8 + ".css".len(), "{}", href);
+
+    let hashed = server.get(href).await;
+    assert_eq!(hashed.status(), 200);
+    assert_eq!(hashed.headers()["cache-control"], "public, max-age=31536000, immutable");
+    assert_eq!(hashed.headers()["content-type"], "text/css");
+    let plain = server.get("/static/styles.css").await;
+    assert_eq!(plain.headers()["cache-control"], "no-cache");
+    assert_eq!(plain.text().await.unwrap(), hashed.text().await.unwrap());
+}
+
+#[tokio::test]
+async fn fingerprinting_can_be_disabled() {
+    let server = Server::start(&[("ASSET_FINGERPRINT", "0")]).await;
+
+    let html = server.get("/").await.text().await.unwrap();
+    assert!(html.contains("\"/static/styles.css\""));
+}
+
+async fn get_html(server: &Server, path: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{}{}", server.base, path))
+        .header("accept", "text/html,application/xhtml+xml,*/*;q=0.8")
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn unknown_paths_get_404_page_or_json_envelope() {
+    let server = Server::start(&[]).await;
+
+    let api = server.get("/api/v1/nope").await;
+    assert_eq!(api.status(), 404);
+    let body: serde_json::Value = api.json().await.unwrap();
+    assert_eq!(body["code"], "NOT_FOUND");
+    assert!(body["request_id"].is_string());
+
+    let page = get_html(&server, "/no/such/page").await;
+    assert_eq!(page.status(), 404);
+    assert!(page.headers()["content-type"].to_str().unwrap().starts_with("text/html"));
+    assert!(page.text().await.unwrap().contains("页面不存在"));
+}
+
+#[tokio::test]
+async fn spa_fallback_serves_index_for_client_routes() {
+    let server = Server::start(&[("SPA_FALLBACK", "1")]).await;
+
+    let page = get_html(&server, "/city/beijing").await;
+    assert_eq!(page.status(), 200);
+    assert!(page.text().await.unwrap().contains("/static/styles."));
+    assert_eq!(get_html(&server, "/api/v1/nope").await.status(), 404);
+}
+
+// 关闭 reqwest 自动解压，保留原始 Content-Encoding
+#[tokio::test]
+async fn base_path_prefixes_routes_and_page_links() {
+    let server = Server::start(&[("BASE_PATH", "/weather/")]).await;
+    let client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap();
+
+    let bare = client.get(format!("{}/weather?lng=1", server.base)).send().await.unwrap();
+    assert_eq!(bare.status(), 308);
+    assert_eq!(bare.headers()["location"], "/weather/?lng=1");
+
+    let html = server.get("/weather/").await.text().await.unwrap();
+    assert!(html.contains("href=\"/weather/manifest.webmanifest\""));
+    assert!(!html.contains("href=\"/static/"));
+    let start = html.find("/weather/static/styles.").expect("prefixed stylesheet link");
+    let href = &html[start..start + html[start..].find('"').unwrap()];
+    assert_eq!(server.get(href).await.status(), 200);
+
+    let manifest: serde_json::Value = server.get("/weather/manifest.webmanifest").await.json().await.unwrap();
+    assert_eq!(manifest["start_url"], "/weather/");
+    assert!(manifest["icons"][0]["src"].as_str().unwrap().starts_with("/weather/static/icons/"));
+
+    // 前缀以外的路径不再提供；前缀下的未知接口仍返回 JSON 信封
+    assert_eq!(server.get("/static/styles.css").await.status(), 404);
+    let api = server.get("/weather/api/v1/nope").await;
+    assert_eq!(api.status(), 404);
+    assert_eq!(api.json::<serde_json::Value>().await.unwrap()["code"], "NOT_FOUND");
+
+    let out = common::command(&[("BASE_PATH", "weather")]).arg("doctor").output().expect("run doctor");
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("BASE_PATH"));
+}
+
+async fn content_encoding(server: &Server, path: &str, accept: &str) -> Option<String> {
+    let client = reqwest::Client::builder().no_gzip().no_brotli().build().unwrap();
+    let resp = client
+        .get(format!("{}{}", server.base, path))
+        .header("accept-encoding", accept)
+        .send()
+        .await
+        .unwrap();
+    resp.headers().get("content-encoding").map(|v| v.to_str().unwrap().to_string())
+}
+
+#[tokio::test]
+async fn compression_skips_small_and_image_responses() {
+    let server = Server::start(&[("ASSET_FINGERPRINT", "0")]).await;
+
+    assert_eq!(content_encoding(&server, "/static/script.js", "zstd").await.as_deref(), Some("zstd"));
+    assert_eq!(content_encoding(&server, "/static/script.js", "br").await.as_deref(), Some("br"));
+    assert_eq!(content_encoding(&server, "/favicon.ico", "br, gzip").await, None);
+    assert_eq!(content_encoding(&server, "/api/v1/nope", "gzip").await, None);
+
+    let server = Server::start(&[("COMPRESSION", "gzip"), ("COMPRESSION_MIN_BYTES", "0")]).await;
+    assert_eq!(content_encoding(&server, "/static/script.js", "zstd, br").await, None);
+    assert_eq!(content_encoding(&server, "/api/v1/nope", "gzip").await.as_deref(), Some("gzip"));
+}
+
+#[tokio::test]
+async fn h2c_prior_knowledge_only_when_enabled() {
+    let client = reqwest::Client::builder().http2_prior_knowledge().build().unwrap();
+
+    let server = Server::start(&[("H2C", "1")]).await;
+    let resp = client.get(format!("{}/static/styles.css", server.base)).send().await.unwrap();
+    assert_eq!(resp.version(), reqwest::Version::HTTP_2);
+    assert_eq!(resp.status(), 200);
+    // HTTP/1.1 客户端不受影响
+    assert_eq!(server.get("/static/styles.css").await.version(), reqwest::Version::HTTP_11);
+
+    let server = Server::start(&[]).await;
+    assert!(client.get(format!("{}/static/styles.css", server.base)).send().await.is_err());
+}
@@ -0,0 +1,55 @@
+// 格式化输出的快照测试：以 MOCK_MODE 读取 tests/fixtures 下抓取的彩云响应，锁定 v1/v2 天气接口的 JSON 结构
+// 更新快照：cargo insta review（或 INSTA_UPDATE=always cargo test）
+
+mod common;
+
+use common::Server;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+// (fixture, 经度, 纬度)
+const CASES: &[(&str, f64, f64)] = &[
+    ("clear_day", 116.4074, 39.9042),
+    ("storm_alerts", 116.4074, 39.9042),
+    ("missing_life_index", 116.4074, 39.9042),
+    ("polar_timezone", -156.7886, 71.2906),
+];
+
+async fn mock_server() -> Server {
+    Server::start(&[("MOCK_MODE", "1"), ("MOCK_FIXTURES_DIR", FIXTURES)]).await
+}
+
+#[tokio::test]
+async fn v1_weather() {
+    let server = mock_server().await;
+    for (fixture, lng, lat) in CASES {
+        let body = server.get_json(&format!("/api/v1/weather?lng={}&lat={}&mock={}", lng, lat, fixture)).await;
+        // 与当前时间相关的字段（逐小时时刻、日期、农历与月相）不参与比较
+        insta::assert_json_snapshot!(format!("v1_{}", fixture), body, {
+            ".hourly[].time" => "[hour]",
+            ".daily[].date" => "[date]",
+            ".daily[].weekday" => "[weekday]",
+            ".daily[].life_index.*.date" => "[date]",
+            ".daily[].lunar" => "[lunar]",
+            ".daily[].solar_term" => "[solar_term]",
+            ".daily[].moon" => "[moon]",
+        });
+    }
+}
+
+#[tokio::test]
+async fn v2_weather() {
+    let server = mock_server().await;
+    for (fixture, lng, lat) in CASES {
+        let body = server.get_json(&format!("/api/v2/weather?lng={}&lat={}&mock={}", lng, lat, fixture)).await;
+        insta::assert_json_snapshot!(format!("v2_{}", fixture), body, {
+            ".updatedAt" => "[datetime]",
+            ".hourly[].time" => "[datetime]",
+            ".daily[].date" => "[date]",
+            ".daily[].lunar" => "[lunar]",
+            ".daily[].solarTerm" => "[solar_term]",
+            ".daily[].moon" => "[moon]",
+            ".alerts[].publishedAt" => "[datetime]",
+        });
+    }
+}
@@ -0,0 +1,2888 @@
+// 上游集成测试：wiremock 模拟彩云/高德/美团，验证各接口的状态码、超时、回退链与错误信封
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::Server;
+use serde_json::{json, Value};
+use wiremock::{
+    matchers::{header, method, path, path_regex, query_param},
+    Mock, MockBuilder, MockServer, ResponseTemplate,
+};
+
+const TOKEN: &str = "test-token";
+
+fn fixture(name: &str) -> Value {
+    let path = format!("{}/tests/fixtures/{}.json", env!("CARGO_MANIFEST_DIR"), name);
+    serde_json::from_slice(&std::fs::read(path).expect("fixture")).expect("fixture json")
+}
+
+// 各上游共用一个模拟服务，按路径区分
+async fn start(upstream: &MockServer, extra: &[(&str, &str)]) -> Server {
+    let uri = upstream.uri();
+    let mut envs = vec![
+        ("CAIYUN_API_TOKEN", TOKEN),
+        ("CAIYUN_API_URL", uri.as_str()),
+        ("AMAP_API_URL", uri.as_str()),
+        ("MEITUAN_API_URL", uri.as_str()),
+        ("OPEN_METEO_API_URL", uri.as_str()),
+        ("OPENWEATHERMAP_API_URL", uri.as_str()),
+        ("QWEATHER_API_URL", uri.as_str()),
+        ("QWEATHER_GEO_URL", uri.as_str()),
+    ];
+    envs.extend_from_slice(extra);
+    Server::start(&envs).await
+}
+
+fn caiyun_weather() -> MockBuilder {
+    Mock::given(method("GET")).and(path_regex(r"^/v2\.6/test-token/[-0-9.]+,[-0-9.]+/weather$"))
+}
+
+// 错误响应：JSON 信封 { error, request_id }，request_id 与响应头一致
+async fn assert_error_envelope(resp: reqwest::Response, status: u16, contains: &str) {
+    assert_eq!(resp.status().as_u16(), status);
+    let header_id = resp
+        .headers()
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .expect("x-request-id header");
+    let body: Value = resp.json().await.expect("json error body");
+    let error = body["error"].as_str().expect("error message");
+    assert!(error.contains(contains), "error {:?} should contain {:?}", error, contains);
+    assert_eq!(body["request_id"].as_str(), Some(header_id.as_str()));
+}
+
+#[tokio::test]
+async fn weather_formats_upstream_response() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .and(query_param("lang", "zh_CN"))
+        .and(query_param("alert", "true"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("storm_alerts")))
+        .expect(2)
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[]).await;
+
+    let v1 = server.get_json("/api/v1/weather?lng=116.4074&lat=39.9042").await;
+    assert_eq!(v1["current"]["skycon"], "STORM_RAIN");
+    assert_eq!(v1["alerts"].as_array().map(Vec::len), Some(2));
+    assert_eq!(v1["minutely"]["will_rain"], true);
+
+    let v2 = server.get_json("/api/v2/weather?lng=116.4074&lat=39.9042").await;
+    assert_eq!(v2["current"]["condition"]["code"], "STORM_RAIN");
+}
+
+#[tokio::test]
+async fn weather_upstream_http_error_is_bad_gateway() {
+    let upstream = MockServer::start().await;
+    caiyun_weather().respond_with(ResponseTemplate::new(500)).mount(&upstream).await;
+    let server = start(&upstream, &[]).await;
+
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_error_envelope(resp, 502, "上游错误").await;
+}
+
+#[tokio::test]
+async fn upstream_errors_redact_the_token_from_responses_and_diagnostics() {
+    let upstream = MockServer::start().await;
+    caiyun_weather().respond_with(ResponseTemplate::new(403)).mount(&upstream).await;
+    let server = start(&upstream, &[("ADMIN_TOKEN", "s3cret")]).await;
+
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(resp.status(), 502);
+    let error = resp.json::<Value>().await.unwrap()["error"].as_str().unwrap().to_string();
+    assert!(error.contains("/v2.6/***/116.4,39.9/weather"), "{}", error);
+    assert!(!error.contains(TOKEN), "{}", error);
+
+    let body = reqwest::Client::new()
+        .get(format!("{}/api/v1/admin/diagnostics", server.base))
+        .bearer_auth("s3cret")
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains("403"), "{}", body);
+    assert!(!body.contains(TOKEN), "{}", body);
+}
+
+#[tokio::test]
+async fn weather_upstream_status_failed_is_bad_gateway() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "failed", "error": "token is invalid" })))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[]).await;
+
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_error_envelope(resp, 502, "上游返回异常").await;
+}
+
+#[tokio::test]
+async fn weather_upstream_invalid_json_is_bad_gateway() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_string("<html>maintenance</html>"))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[]).await;
+
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_error_envelope(resp, 502, "解析上游失败").await;
+}
+
+#[tokio::test]
+async fn weather_without_token_is_service_unavailable() {
+    let upstream = MockServer::start().await;
+    // 留空等同未配置
+    let server = start(&upstream, &[("CAIYUN_API_TOKEN", "")]).await;
+
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_error_envelope(resp, 503, "CAIYUN_API_TOKEN").await;
+    let resp = server.get("/api/v2/weather?lng=116.4&lat=39.9").await;
+    assert_error_envelope(resp, 503, "CAIYUN_API_TOKEN").await;
+    assert!(upstream.received_requests().await.unwrap_or_default().is_empty());
+}
+
+#[tokio::test]
+async fn weather_rejects_invalid_query() {
+    let upstream = MockServer::start().await;
+    let server = start(&upstream, &[]).await;
+
+    assert_eq!(server.get("/api/v1/weather?lng=116.4").await.status().as_u16(), 400);
+    assert_eq!(server.get("/api/v1/weather?lng=abc&lat=39.9").await.status().as_u16(), 400);
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9&format=xml").await;
+    assert_error_envelope(resp, 400, "不支持的格式").await;
+}
+
+#[tokio::test]
+async fn geocode_prefers_meituan() {
+    let upstream = MockServer::start().await;
+    Mock::given(path("/group/v1/city/latlng/39.9,116.4"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": { "detail": "东城区天安门", "city": "北京" } })))
+        .mount(&upstream)
+        .await;
+    Mock::given(path("/v3/geocode/regeo")).respond_with(ResponseTemplate::new(500)).expect(0).mount(&upstream).await;
+    let server = start(&upstream, &[("AMAP_API_KEY", "amap-key")]).await;
+
+    let body = server.get_json("/api/v1/location/geocode?lng=116.4&lat=39.9").await;
+    assert_eq!(body["address"], "东城区天安门");
+}
+
+#[tokio::test]
+async fn geocode_falls_back_to_amap_then_unknown() {
+    let upstream = MockServer::start().await;
+    Mock::given(path_regex(r"^/group/v1/city/latlng/")).respond_with(ResponseTemplate::new(503)).mount(&upstream).await;
+    Mock::given(path("/v3/geocode/regeo"))
+        .and(query_param("key", "amap-key"))
+        .and(query_param("location", "116.4,39.9"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "status": "1",
+            "regeocode": { "formatted_address": "北京市东城区东华门街道" }
+        })))
+        .mount(&upstream)
+        .await;
+
+    let with_amap = start(&upstream, &[("AMAP_API_KEY", "amap-key")]).await;
+    let body = with_amap.get_json("/api/v1/location/geocode?lng=116.4&lat=39.9").await;
+    assert_eq!(body["address"], "北京市东城区东华门街道");
+
+    // 未配置高德 key 时美团失败即返回未知位置
+    let without_amap = start(&upstream, &[]).await;
+    let body = without_amap.get_json("/api/v1/location/geocode?lng=116.4&lat=39.9").await;
+    assert_eq!(body["address"], "未知位置");
+}
+
+#[tokio::test]
+async fn search_times_out_to_empty_results() {
+    let upstream = MockServer::start().await;
+    Mock::given(path("/v3/place/text"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "pois": [{ "name": "慢", "address": "", "location": "116.4,39.9" }] }))
+                .set_delay(Duration::from_secs(5)),
+        )
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("AMAP_API_KEY", "amap-key")]).await;
+
+    let started = Instant::now();
+    let body = server.get_json("/api/v1/location/search?q=%E5%8C%97%E4%BA%AC").await;
+    assert_eq!(body, json!({ "results": [] }));
+    assert!(started.elapsed() < Duration::from_secs(5), "search should give up before the upstream answers");
+}
+
+#[tokio::test]
+async fn weather_respects_per_upstream_timeout() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})).set_delay(Duration::from_secs(5)))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("CAIYUN_API_TOKEN", "test-token"), ("CAIYUN_TIMEOUT_SECS", "0.5")]).await;
+
+    let started = Instant::now();
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(resp.status(), 502);
+    assert!(started.elapsed() < Duration::from_secs(5), "CAIYUN_TIMEOUT_SECS should cut the request short");
+}
+
+#[tokio::test]
+async fn search_maps_amap_pois() {
+    let upstream = MockServer::start().await;
+    Mock::given(path("/v3/place/text"))
+        .and(query_param("keywords", "北京"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "pois": [
+                { "name": "北京站", "address": "毛家湾胡同甲13号", "location": "116.427,39.903" },
+                { "name": "坐标缺失", "address": "" }
+            ]
+        })))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("AMAP_API_KEY", "amap-key")]).await;
+
+    let body = server.get_json("/api/v1/location/search?q=%E5%8C%97%E4%BA%AC").await;
+    assert_eq!(
+        body,
+        json!({ "results": [{ "name": "北京站", "address": "毛家湾胡同甲13号", "lng": 116.427, "lat": 39.903 }] })
+    );
+    assert_error_envelope(server.get("/api/v1/location/search?q=%20").await, 400, "缺少 q").await;
+}
+
+#[tokio::test]
+async fn ip_location_uses_meituan_and_falls_back_to_beijing() {
+    let upstream = MockServer::start().await;
+    Mock::given(path("/locate/v2/ip/loc"))
+        .and(query_param("ip", "203.0.113.7"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "lat": 31.23, "lng": 121.47, "rgeo": { "province": "上海市", "city": "上海市", "district": "黄浦区" } }
+        })))
+        .mount(&upstream)
+        .await;
+    Mock::given(path("/locate/v2/ip/loc")).respond_with(ResponseTemplate::new(500)).mount(&upstream).await;
+    let server = start(&upstream, &[]).await;
+    let client = reqwest::Client::new();
+    let located = |ip: &'static str| {
+        client
+            .get(format!("{}/api/v1/location/ip", server.base))
+            .header("x-forwarded-for", ip)
+            .send()
+    };
+
+    let body: Value = located("203.0.113.7:5123, 10.0.0.1").await.unwrap().json().await.unwrap();
+    assert_eq!(body, json!({ "lat": 31.23, "lng": 121.47, "address": "黄浦区" }));
+
+    let body: Value = located("198.51.100.1").await.unwrap().json().await.unwrap();
+    assert_eq!(body, json!({ "lat": 39.9042, "lng": 116.4074, "address": "北京市" }));
+}
+
+#[tokio::test]
+async fn default_location_is_configurable() {
+    let upstream = MockServer::start().await;
+    Mock::given(path("/locate/v2/ip/loc")).respond_with(ResponseTemplate::new(500)).mount(&upstream).await;
+    let server = start(
+        &upstream,
+        &[("DEFAULT_LNG", "113.2644"), ("DEFAULT_LAT", "23.1291"), ("DEFAULT_ADDRESS", "广州市")],
+    )
+    .await;
+    let client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap();
+
+    let guangzhou = json!({ "lat": 23.1291, "lng": 113.2644, "address": "广州市" });
+    let resp = client.get(format!("{}/api/v1/location/ip", server.base)).header("x-forwarded-for", "198.51.100.1");
+    assert_eq!(resp.send().await.unwrap().json::<Value>().await.unwrap(), guangzhou);
+
+    // 前端运行时配置
+    let config = client.get(format!("{}/api/v1/config", server.base)).send().await.unwrap().json::<Value>().await.unwrap();
+    assert_eq!(config["default_city"], json!({ "name": "广州市", "lng": 113.2644, "lat": 23.1291 }));
+
+    // 服务端渲染页的默认城市
+    for (path, location) in [("/w/", "./%E5%B9%BF%E5%B7%9E%E5%B8%82"), ("/w", "w/%E5%B9%BF%E5%B7%9E%E5%B8%82")] {
+        let resp = client.get(format!("{}{}", server.base, path)).send().await.unwrap();
+        assert_eq!(resp.status(), 307);
+        assert_eq!(resp.headers()["location"], format!("{}?lng=113.2644&lat=23.1291", location).as_str());
+    }
+
+    // 只设置了部分变量时启动失败
+    let out = common::command(&[("DEFAULT_LNG", "113.2644")]).arg("doctor").output().expect("run doctor");
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("DEFAULT_ADDRESS"));
+}
+
+#[tokio::test]
+async fn ip_location_caches_results_and_failures() {
+    let upstream = MockServer::start().await;
+    Mock::given(path("/locate/v2/ip/loc"))
+        .and(query_param("ip", "203.0.113.7"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "lat": 31.23, "lng": 121.47, "rgeo": { "city": "上海市", "district": "黄浦区" } }
+        })))
+        .expect(1)
+        .mount(&upstream)
+        .await;
+    // 上游故障：失败结果在 GEOIP_NEGATIVE_CACHE_SECS 内不再重试
+    Mock::given(path("/locate/v2/ip/loc"))
+        .and(query_param("ip", "198.51.100.1"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(300)).set_body_string("oops"))
+        .expect(2)
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("GEOIP_NEGATIVE_CACHE_SECS", "1")]).await;
+    let client = reqwest::Client::new();
+    let located = |ip: &'static str| {
+        let req = client.get(format!("{}/api/v1/location/ip", server.base)).header("x-forwarded-for", ip);
+        async move { req.send().await.unwrap().json::<Value>().await.unwrap() }
+    };
+
+    for _ in 0..3 {
+        assert_eq!(located("203.0.113.7").await["address"], "黄浦区");
+    }
+    assert_eq!(located("198.51.100.1").await["address"], "北京市");
+    let started = Instant::now();
+    assert_eq!(located("198.51.100.1").await["address"], "北京市");
+    assert!(started.elapsed() < Duration::from_millis(300), "{:?}", started.elapsed());
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(located("198.51.100.1").await["address"], "北京市");
+}
+
+#[tokio::test]
+async fn ip_location_handles_ipv6_and_local_networks() {
+    let upstream = MockServer::start().await;
+    for ip in ["2001:db8::7", "203.0.113.9"] {
+        Mock::given(path("/locate/v2/ip/loc"))
+            .and(query_param("ip", ip))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "lat": 22.54, "lng": 114.06, "rgeo": { "city": "深圳市", "district": ip } }
+            })))
+            .expect(1)
+            .mount(&upstream)
+            .await;
+    }
+    let server = start(&upstream, &[]).await;
+    let client = reqwest::Client::new();
+    let located = |ip: Option<&'static str>| {
+        let mut req = client.get(format!("{}/api/v1/location/ip", server.base));
+        if let Some(ip) = ip {
+            req = req.header("x-forwarded-for", ip);
+        }
+        async move { req.send().await.unwrap().json::<Value>().await.unwrap() }
+    };
+
+    assert_eq!(located(Some("[2001:db8::7]:443")).await["address"], "2001:db8::7");
+    assert_eq!(located(Some("::ffff:203.0.113.9")).await["address"], "203.0.113.9");
+
+    // 没有代理头时用连接的对端地址（127.0.0.1），与局域网地址一样不请求美团
+    let local = json!({ "lat": 39.9042, "lng": 116.4074, "address": "北京市", "local_network": true });
+    assert_eq!(located(None).await, local);
+    for ip in ["192.168.1.20", "fe80::1%eth0", "[fd00::1]:8080", "100.64.0.1"] {
+        assert_eq!(located(Some(ip)).await, local, "{}", ip);
+    }
+}
+
+#[tokio::test]
+async fn raw_weather_requires_admin_token() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("ADMIN_TOKEN", "s3cret")]).await;
+
+    assert_error_envelope(server.get("/api/v1/weather/raw?lng=116.4&lat=39.9").await, 401, "未授权").await;
+    let raw: Value = reqwest::Client::new()
+        .get(format!("{}/api/v1/weather/raw?lng=116.4&lat=39.9", server.base))
+        .bearer_auth("s3cret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(raw, fixture("clear_day"));
+}
+
+#[tokio::test]
+async fn upstream_requests_go_through_configured_proxy() {
+    // 模拟服务兼作 HTTP 代理：上游主机不可解析，只有经代理才能到达
+    let proxy = MockServer::start().await;
+    Mock::given(path("/group/v1/city/latlng/39.9,116.4"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": { "detail": "经代理" } })))
+        .expect(1)
+        .mount(&proxy)
+        .await;
+    let caiyun = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .expect(1)
+        .mount(&caiyun)
+        .await;
+    let proxy_uri = proxy.uri();
+    let caiyun_uri = caiyun.uri();
+    let server = Server::start(&[
+        ("CAIYUN_API_TOKEN", TOKEN),
+        ("CAIYUN_API_URL", caiyun_uri.as_str()),
+        ("MEITUAN_API_URL", "http://meituan.invalid"),
+        ("HTTP_PROXY", proxy_uri.as_str()),
+        // 彩云单独直连
+        ("CAIYUN_PROXY", "direct"),
+    ])
+    .await;
+
+    let body = server.get_json("/api/v1/location/geocode?lng=116.4&lat=39.9").await;
+    assert_eq!(body["address"], "经代理");
+    let weather = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(weather["current"]["skycon"], "CLEAR_DAY");
+}
+
+#[tokio::test]
+async fn sheds_load_beyond_max_in_flight() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(fixture("clear_day")).set_delay(Duration::from_millis(800)),
+        )
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("MAX_IN_FLIGHT", "1"), ("RETRY_AFTER_SECS", "7")]).await;
+
+    let slow = server.get("/api/v1/weather?lng=116.4&lat=39.9");
+    let shed = async {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        server.get("/api/v1/weather?lng=116.4&lat=39.9").await
+    };
+    let (slow, shed) = tokio::join!(slow, shed);
+    assert_eq!(slow.status(), 200);
+    assert_eq!(shed.headers()["retry-after"], "7");
+    assert_error_envelope(shed, 503, "服务繁忙").await;
+}
+
+#[tokio::test]
+async fn sigterm_drains_in_flight_requests_before_exit() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(fixture("clear_day")).set_delay(Duration::from_millis(800)),
+        )
+        .mount(&upstream)
+        .await;
+    let mut server = start(&upstream, &[]).await;
+    let base = server.base.clone();
+
+    let slow = tokio::spawn(async move { reqwest::get(format!("{}/api/v1/weather?lng=116.4&lat=39.9", base)).await });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let status = server.terminate().await;
+    assert!(status.success(), "graceful shutdown should exit cleanly: {:?}", status);
+    let slow = slow.await.expect("join").expect("in-flight request completes");
+    assert_eq!(slow.status(), 200);
+    assert!(reqwest::get(format!("{}/", server.base)).await.is_err(), "listener closed after shutdown");
+}
+
+#[tokio::test]
+async fn sigterm_flushes_buffered_log_file() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let dir = std::env::temp_dir().join(format!("caiyun-logs-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let file = dir.join("caiyun.log");
+    let mut server =
+        start(&upstream, &[("LOG_FILE", file.to_str().unwrap()), ("LOG_FORMAT", "json"), ("RUST_LOG", "info")]).await;
+    server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert!(server.terminate().await.success());
+
+    // 按天轮转，文件名带日期后缀
+    let logs: String = std::fs::read_dir(&dir)
+        .expect("log dir")
+        .map(|e| std::fs::read_to_string(e.expect("entry").path()).expect("log file"))
+        .collect();
+    assert!(logs.contains("\"response\""), "request log written: {}", logs);
+    assert!(logs.lines().last().is_some_and(|l| l.contains("server stopped")), "last line flushed on exit: {}", logs);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn weather_route_timeout_without_cache_is_structured_error() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")).set_delay(Duration::from_secs(3)))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("ROUTE_TIMEOUTS", "/weather=0.5")]).await;
+
+    let started = Instant::now();
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert!(started.elapsed() < Duration::from_secs(3), "route timeout should answer before the upstream");
+    assert_eq!(resp.status(), 504);
+    let body: Value = resp.json().await.expect("json error body");
+    assert_eq!(body["code"], "UPSTREAM_TIMEOUT");
+}
+
+#[tokio::test]
+async fn weather_route_timeout_falls_back_to_cached_data() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .up_to_n_times(1)
+        .mount(&upstream)
+        .await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")).set_delay(Duration::from_secs(3)))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("ROUTE_TIMEOUTS", "/weather=0.5")]).await;
+
+    let fresh = server.get("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(fresh.status(), 200);
+    assert!(fresh.headers().get("warning").is_none());
+    let fresh: Value = fresh.json().await.expect("json");
+
+    // 旧路径别名与 v1 共用同一项路由超时
+    let stale = server.get("/api/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(stale.status(), 200);
+    assert_eq!(stale.headers()["warning"], "110 - \"Response is Stale\"");
+    let stale: Value = stale.json().await.expect("json");
+    assert_eq!(stale["current"], fresh["current"]);
+}
+
+#[tokio::test]
+async fn weather_falls_back_to_open_meteo() {
+    let upstream = MockServer::start().await;
+    caiyun_weather().respond_with(ResponseTemplate::new(429)).mount(&upstream).await;
+    Mock::given(path("/v1/forecast"))
+        .and(query_param("latitude", "39.9"))
+        .and(query_param("timeformat", "unixtime"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("open_meteo")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("WEATHER_PROVIDERS", "caiyun,open_meteo")]).await;
+
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["current"]["temperature"], 27);
+    assert_eq!(body["current"]["skycon"], "PARTLY_CLOUDY_DAY");
+    assert_eq!(body["current"]["humidity"], 48);
+    assert_eq!(body["current"]["pressure"], 1006);
+    assert_eq!(body["hourly"].as_array().map(Vec::len), Some(24));
+    assert_eq!(body["hourly"][11]["skycon"], "MODERATE_RAIN");
+    assert_eq!(body["daily"][0]["max_temp"], 31);
+    assert_eq!(body["daily"][0]["precipitation"]["total"], 2.2);
+    assert_eq!(body["daily"][0]["life_index"]["ultraviolet"]["index"], "4");
+
+    let v2 = server.get_json("/api/v2/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(v2["daily"][0]["date"], "2024-06-13");
+    assert_eq!(v2["current"]["condition"]["code"], "PARTLY_CLOUDY_DAY");
+}
+
+#[tokio::test]
+async fn chaos_injects_failures_and_delays() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .expect(0)
+        .mount(&upstream)
+        .await;
+    Mock::given(path("/v1/forecast"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("open_meteo")))
+        .mount(&upstream)
+        .await;
+
+    // 彩云调用全部失败（不发出请求），回退到 Open-Meteo
+    let server = start(
+        &upstream,
+        &[("WEATHER_PROVIDERS", "caiyun,open_meteo"), ("CHAOS_FAILURE_PERCENT", "100"), ("CHAOS_UPSTREAMS", "caiyun")],
+    )
+    .await;
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["current"]["temperature"], 27);
+
+    // 全部上游延迟
+    let server = start(
+        &upstream,
+        &[("WEATHER_PROVIDERS", "open_meteo"), ("CHAOS_DELAY_PERCENT", "100"), ("CHAOS_DELAY_MS", "400")],
+    )
+    .await;
+    let started = Instant::now();
+    server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert!(started.elapsed() >= Duration::from_millis(400));
+
+    let out = common::command(&[("CHAOS_FAILURE_PERCENT", "150")]).arg("doctor").output().expect("run doctor");
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("CHAOS_FAILURE_PERCENT"));
+}
+
+#[tokio::test]
+async fn responses_are_signed_with_hmac() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let secret = "0123456789abcdef0123456789abcdef";
+    let server = start(&upstream, &[("RESPONSE_SIGNING_SECRET", secret)]).await;
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+
+    // 压缩传输时签名的是解压后的响应体
+    let client = reqwest::Client::builder().gzip(true).build().unwrap();
+    for path in ["/api/v1/weather?lng=116.4&lat=39.9", "/api/v1/weather?lng=1000&lat=39.9"] {
+        let resp = client.get(format!("{}{}", server.base, path)).header("accept-encoding", "gzip").send().await.unwrap();
+        let timestamp = resp.headers()["x-signature-timestamp"].to_str().unwrap().to_string();
+        let signature = resp.headers()["x-signature"].to_str().unwrap().to_string();
+        let body = resp.bytes().await.unwrap();
+        let age = chrono::Utc::now().timestamp() - timestamp.parse::<i64>().unwrap();
+        assert!((0..5).contains(&age));
+        let hex = signature.strip_prefix("sha256=").expect("sha256= prefix");
+        let raw: Vec<u8> = (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect();
+        let message = [timestamp.as_bytes(), b".", &body].concat();
+        assert!(ring::hmac::verify(&key, &message, &raw).is_ok(), "{} signature mismatch", path);
+        // 篡改后校验失败
+        let tampered = [timestamp.as_bytes(), b".", &body, b" "].concat();
+        assert!(ring::hmac::verify(&key, &tampered, &raw).is_err());
+    }
+
+    // 页面与静态资源不签名
+    let resp = server.get("/").await;
+    assert!(resp.headers().get("x-signature").is_none());
+}
+
+#[tokio::test]
+async fn keypoint_generated_from_minutely_precipitation() {
+    let upstream = MockServer::start().await;
+    let mut body = fixture("open_meteo");
+    body["minutely_15"] = json!({ "time": [], "precipitation": [0.0, 0.1, 0.2, 0.2, 0.0, 0.0, 0.0, 0.0] });
+    Mock::given(path("/v1/forecast"))
+        .and(query_param("minutely_15", "precipitation"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("WEATHER_PROVIDERS", "open_meteo")]).await;
+
+    let v1 = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(v1["forecast_keypoint"], "15 分钟后开始下小雨，约持续 45 分钟");
+    assert_eq!(v1["minutely"]["description"], "15 分钟后开始下小雨，约持续 45 分钟");
+    assert_eq!(v1["minutely"]["will_rain"], true);
+    let v2 = server.get_json("/api/v2/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(v2["forecastKeypoint"], "15 分钟后开始下小雨，约持续 45 分钟");
+
+    let server = start(&upstream, &[("WEATHER_PROVIDERS", "open_meteo"), ("NOWCAST_LANG", "en")]).await;
+    let v1 = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(v1["forecast_keypoint"], "Light rain starting in 15 min, lasting about 45 min");
+}
+
+#[tokio::test]
+async fn weather_skips_unconfigured_openweathermap() {
+    let upstream = MockServer::start().await;
+    Mock::given(path("/v1/forecast"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("open_meteo")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("WEATHER_PROVIDERS", "openweathermap,open_meteo")]).await;
+
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["current"]["temperature"], 27);
+}
+
+#[tokio::test]
+async fn weather_from_openweathermap() {
+    let upstream = MockServer::start().await;
+    Mock::given(path("/data/3.0/onecall"))
+        .and(query_param("appid", "owm-key"))
+        .and(query_param("units", "metric"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("openweathermap")))
+        .mount(&upstream)
+        .await;
+    let server = start(
+        &upstream,
+        &[("WEATHER_PROVIDERS", "openweathermap"), ("OPENWEATHERMAP_API_KEY", "owm-key")],
+    )
+    .await;
+
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["current"]["temperature"], 25);
+    assert_eq!(body["current"]["skycon"], "LIGHT_RAIN");
+    assert_eq!(body["current"]["wind_speed"], 18);
+    assert_eq!(body["minutely"]["will_rain"], true);
+    assert_eq!(body["hourly"][20]["skycon"], "CLOUDY");
+    assert_eq!(body["daily"][0]["skycon"], "MODERATE_RAIN");
+    assert_eq!(body["daily"][0]["precipitation"]["total"], 4.8);
+    assert_eq!(body["daily"][2]["life_index"]["ultraviolet"]["desc"], "很强");
+    assert_eq!(body["alerts"][0]["title"], "暴雨蓝色预警");
+    assert_eq!(body["alerts"][0]["source"], "北京市气象台");
+}
+
+#[tokio::test]
+async fn weather_reports_primary_error_when_all_providers_fail() {
+    let upstream = MockServer::start().await;
+    caiyun_weather().respond_with(ResponseTemplate::new(500)).mount(&upstream).await;
+    Mock::given(path("/v1/forecast")).respond_with(ResponseTemplate::new(400)).mount(&upstream).await;
+    let server = start(&upstream, &[("WEATHER_PROVIDERS", "caiyun,open_meteo")]).await;
+
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_error_envelope(resp, 502, "500 Internal Server Error").await;
+}
+
+// 和风天气：各接口 body.code 为 "200"，数值为字符串
+async fn mount_qweather(upstream: &MockServer) {
+    let hour = |i: u32, icon: &str, pop: &str| {
+        json!({ "fxTime": format!("2024-06-13T{:02}:00+08:00", 8 + i), "temp": "26", "icon": icon, "wind360": "90",
+                "windSpeed": "18", "humidity": "65", "pop": pop, "precip": "0.0" })
+    };
+    let day = |date: &str, day_icon: &str, night_icon: &str| {
+        json!({ "fxDate": date, "tempMax": "30", "tempMin": "21", "iconDay": day_icon, "iconNight": night_icon,
+                "wind360Day": "180", "windSpeedDay": "25", "humidity": "70", "precip": "12.0", "pressure": "1002",
+                "uvIndex": "8" })
+    };
+    let responses = [
+        ("/v7/weather/now", json!({ "code": "200", "now": {
+            "obsTime": "2024-06-13T08:00+08:00", "temp": "25", "feelsLike": "27", "icon": "305", "text": "小雨",
+            "wind360": "90", "windSpeed": "18", "humidity": "82", "precip": "0.6", "pressure": "1003", "vis": "9",
+            "cloud": "90"
+        }})),
+        ("/v7/weather/24h", json!({ "code": "200", "hourly": [hour(0, "305", "80"), hour(1, "101", "20"), hour(2, "104", "10")] })),
+        ("/v7/weather/3d", json!({ "code": "200", "daily": [
+            day("2024-06-13", "306", "151"), day("2024-06-14", "100", "150"), day("2024-06-15", "104", "104")
+        ]})),
+        ("/v7/minutely/5m", json!({ "code": "200", "summary": "未来两小时有小雨", "minutely": [
+            { "fxTime": "2024-06-13T08:05+08:00", "precip": "0.05", "type": "rain" }
+        ]})),
+        ("/v7/warning/now", json!({ "code": "200", "warning": [{
+            "sender": "北京市气象台", "pubTime": "2024-06-13T07:30+08:00", "title": "北京市气象台发布暴雨蓝色预警",
+            "status": "active", "type": "1003", "text": "预计今天白天有暴雨。"
+        }]})),
+        // 生活指数接口失败时紫外线等级由逐日紫外线指数换算
+        ("/v7/indices/3d", json!({ "code": "403" })),
+    ];
+    for (route, body) in responses {
+        Mock::given(path(route))
+            .and(query_param("key", "qw-key"))
+            .and(query_param("location", "116.40,39.90"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(upstream)
+            .await;
+    }
+}
+
+#[tokio::test]
+async fn weather_from_qweather() {
+    let upstream = MockServer::start().await;
+    mount_qweather(&upstream).await;
+    let server = start(&upstream, &[("WEATHER_PROVIDERS", "qweather"), ("QWEATHER_KEY", "qw-key")]).await;
+
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["current"]["temperature"], 25);
+    assert_eq!(body["current"]["skycon"], "LIGHT_RAIN");
+    assert_eq!(body["current"]["wind_speed"], 18);
+    assert_eq!(body["current"]["pressure"], 1003);
+    assert_eq!(body["minutely"]["description"], "未来两小时有小雨");
+    assert_eq!(body["minutely"]["will_rain"], true);
+    assert_eq!(body["hourly"][1]["skycon"], "PARTLY_CLOUDY_DAY");
+    assert_eq!(body["hourly"][0]["precipitation_probability"], 80);
+    assert_eq!(body["daily"][0]["skycon_night"], "PARTLY_CLOUDY_NIGHT");
+    assert_eq!(body["daily"][0]["precipitation"]["total"], 12.0);
+    assert_eq!(body["daily"][0]["life_index"]["ultraviolet"]["index"], "4");
+    assert_eq!(body["alerts"][0]["source"], "北京市气象台");
+}
+
+#[tokio::test]
+async fn weather_falls_back_when_qweather_quota_exhausted() {
+    let upstream = MockServer::start().await;
+    Mock::given(path_regex(r"^/v7/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "code": "402" })))
+        .mount(&upstream)
+        .await;
+    Mock::given(path("/v1/forecast"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("open_meteo")))
+        .mount(&upstream)
+        .await;
+    let server =
+        start(&upstream, &[("WEATHER_PROVIDERS", "qweather,open_meteo"), ("QWEATHER_KEY", "qw-key")]).await;
+
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["current"]["temperature"], 27);
+}
+
+#[tokio::test]
+async fn location_falls_back_to_qweather_city_lookup() {
+    let upstream = MockServer::start().await;
+    Mock::given(path_regex(r"^/group/v1/city/latlng/")).respond_with(ResponseTemplate::new(503)).mount(&upstream).await;
+    Mock::given(path("/v2/city/lookup"))
+        .and(query_param("location", "杭州"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "code": "200", "location": [
+            { "name": "杭州", "lat": "30.28746", "lon": "120.15358", "adm2": "杭州", "adm1": "浙江省" },
+            { "name": "余杭", "lat": "30.41875", "lon": "120.30173", "adm2": "杭州", "adm1": "浙江省" }
+        ]})))
+        .mount(&upstream)
+        .await;
+    Mock::given(path("/v2/city/lookup"))
+        .and(query_param("location", "116.40,39.90"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "code": "200", "location": [
+            { "name": "东城", "lat": "39.91", "lon": "116.41", "adm2": "北京", "adm1": "北京市" }
+        ]})))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("QWEATHER_KEY", "qw-key")]).await;
+
+    let body = server.get_json("/api/v1/location/search?q=%E6%9D%AD%E5%B7%9E").await;
+    assert_eq!(
+        body["results"],
+        json!([
+            { "lat": 30.28746, "lng": 120.15358, "name": "杭州", "address": "浙江省" },
+            { "lat": 30.41875, "lng": 120.30173, "name": "余杭", "address": "浙江省 杭州" }
+        ])
+    );
+    let body = server.get_json("/api/v1/location/geocode?lng=116.4&lat=39.9").await;
+    assert_eq!(body["address"], "北京市 北京 东城");
+}
+
+#[tokio::test]
+async fn blend_pins_blocks_and_flags_temperature_disagreement() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    Mock::given(path("/v1/forecast"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("open_meteo")))
+        .mount(&upstream)
+        .await;
+    let server = start(
+        &upstream,
+        &[
+            ("WEATHER_PROVIDERS", "caiyun,open_meteo,openweathermap"),
+            ("WEATHER_BLEND", "1"),
+            ("WEATHER_BLEND_BLOCKS", "daily=open_meteo"),
+            ("WEATHER_BLEND_TOLERANCE", "2"),
+        ],
+    )
+    .await;
+
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    // 实况与分钟级来自彩云，逐日来自 Open-Meteo
+    assert_eq!(body["current"]["temperature"], 31);
+    assert_eq!(body["minutely"]["description"], "未来两小时不会下雨，放心出门吧");
+    assert_eq!(body["daily"][0]["max_temp"], 31);
+    assert_eq!(body["daily"][0]["precipitation"]["total"], 2.2);
+    let sources = &body["sources"];
+    assert_eq!(sources["primary"], "caiyun");
+    assert_eq!(sources["blocks"]["daily"], "open_meteo");
+    assert_eq!(sources["blocks"]["realtime"], "caiyun");
+    // 未配置 key 的 OpenWeatherMap 不计入
+    assert_eq!(sources["providers"].as_array().map(Vec::len), Some(2));
+    assert_eq!(sources["spread"], 3.2);
+    assert_eq!(sources["disagreement"], true);
+
+    let v2 = server.get_json("/api/v2/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(v2["sources"]["blocks"]["daily"], "open_meteo");
+}
+
+#[tokio::test]
+async fn blend_fills_missing_blocks_from_other_providers() {
+    let upstream = MockServer::start().await;
+    caiyun_weather().respond_with(ResponseTemplate::new(500)).mount(&upstream).await;
+    Mock::given(path("/v1/forecast"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("open_meteo")))
+        .mount(&upstream)
+        .await;
+    Mock::given(path("/data/3.0/onecall"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("openweathermap")))
+        .mount(&upstream)
+        .await;
+    let server = start(
+        &upstream,
+        &[
+            ("WEATHER_PROVIDERS", "caiyun,open_meteo,openweathermap"),
+            ("OPENWEATHERMAP_API_KEY", "owm-key"),
+            ("WEATHER_BLEND", "1"),
+        ],
+    )
+    .await;
+
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["current"]["temperature"], 27);
+    assert_eq!(body["alerts"][0]["title"], "暴雨蓝色预警");
+    let sources = &body["sources"];
+    assert_eq!(sources["primary"], "open_meteo");
+    assert_eq!(sources["blocks"]["hourly"], "open_meteo");
+    assert_eq!(sources["blocks"]["minutely"], "openweathermap");
+    assert_eq!(sources["blocks"]["alert"], "openweathermap");
+    assert_eq!(sources["providers"][0]["ok"], false);
+    assert_eq!(sources["disagreement"], false);
+}
+
+#[tokio::test]
+async fn usage_stats_count_routes_clients_and_locations() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let data_dir = std::env::temp_dir().join(format!("caiyun-stats-{}", std::process::id()));
+    let data_dir = data_dir.to_string_lossy();
+    let server =
+        start(&upstream, &[("USAGE_STATS", "1"), ("ADMIN_TOKEN", "s3cret"), ("DATA_DIR", data_dir.as_ref())]).await;
+
+    let client = reqwest::Client::new();
+    for (path, ip) in [
+        ("/api/v1/weather?lng=116.41&lat=39.92", "203.0.113.1"),
+        ("/api/weather?lng=116.38&lat=39.94", "203.0.113.2"),
+        ("/api/v2/weather?lng=121.47&lat=31.23", "203.0.113.1"),
+    ] {
+        let resp = client.get(format!("{}{}", server.base, path)).header("x-forwarded-for", ip).send().await.unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    let stats: Value = client
+        .get(format!("{}/api/v1/admin/stats", server.base))
+        .bearer_auth("s3cret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let total = &stats["total"];
+    assert_eq!(total["routes"]["/weather"], 3);
+    assert_eq!(total["unique_clients"], 2);
+    assert_eq!(total["top_locations"][0], json!({ "lng": 116.4, "lat": 39.9, "requests": 2 }));
+    assert_eq!(stats["days"].as_array().map(Vec::len), Some(1));
+    let _ = std::fs::remove_dir_all(data_dir.as_ref());
+}
+
+#[tokio::test]
+async fn hot_list_promotes_busy_locations_and_prefetches_them() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let server = start(
+        &upstream,
+        &[
+            ("WEATHER_CACHE_SECS", "1"),
+            ("CACHE_STALE_SECS", "0"),
+            ("HOT_LIST_SIZE", "1"),
+            ("HOT_LIST_MIN_REQUESTS", "2"),
+            // 只由下面手动触发；默认每 5 分钟一次，测试恰好跨过整点时会多衰减一次
+            ("SCHEDULE_HOT_LIST_PREFETCH", "0 0 1 1 *"),
+            ("ADMIN_TOKEN", "s3cret"),
+        ],
+    )
+    .await;
+    for path in [
+        "/api/v1/weather?lng=116.41&lat=39.92",
+        "/api/v1/weather?lng=116.412&lat=39.921",
+        "/api/v1/weather?lng=116.41&lat=39.92",
+        "/api/v1/weather?lng=121.47&lat=31.23",
+    ] {
+        assert_eq!(server.get(path).await.status(), 200);
+    }
+    let busy_fetches = || async {
+        let requests = upstream.received_requests().await.unwrap();
+        requests.iter().filter(|r| r.url.path().contains("116.41,39.92")).count()
+    };
+    assert_eq!(busy_fetches().await, 1);
+
+    // 等缓存过期后运行预取任务，热门地点应重新请求上游
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("{}/api/v1/admin/jobs/hot_list_prefetch/run", server.base))
+        .bearer_auth("s3cret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 202);
+    for _ in 0..50 {
+        if busy_fetches().await == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(busy_fetches().await, 2);
+    let list: Value = client
+        .get(format!("{}/api/v1/admin/hot-list", server.base))
+        .bearer_auth("s3cret")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(list["hot"].as_array().map(Vec::len), Some(1));
+    assert_eq!(list["hot"][0]["lng"], 116.41);
+    assert_eq!(list["hot"][0]["lat"], 39.92);
+    // 计数已衰减一半
+    assert_eq!(list["top"][0]["requests"], 1.5);
+    assert_eq!(list["top"][1], json!({ "lng": 121.47, "lat": 31.23, "requests": 0.5, "hot": false }));
+    assert_eq!(list["tracked"], 2);
+}
+
+#[tokio::test]
+async fn api_keys_enforce_daily_quota_and_rate_tiers() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let db = std::env::temp_dir().join(format!("caiyun-keys-{}.sqlite3", std::process::id()));
+    let db = db.to_string_lossy();
+    let envs = [
+        ("API_KEYS", "required"),
+        ("API_KEYS_DB", db.as_ref()),
+        ("API_KEY_TIERS", "free=0,trial=1"),
+        ("ADMIN_TOKEN", "s3cret"),
+    ];
+    let mut server = start(&upstream, &envs).await;
+    let client = reqwest::Client::new();
+    let admin = |req: reqwest::RequestBuilder| req.bearer_auth("s3cret").send();
+
+    let weather = format!("{}/api/v1/weather?lng=116.4&lat=39.9", server.base);
+    assert_error_envelope(server.get("/api/v1/weather?lng=116.4&lat=39.9").await, 401, "缺少 API key").await;
+    let resp = client.get(&weather).header("x-api-key", "ck_unknown").send().await.unwrap();
+    assert_error_envelope(resp, 401, "无效").await;
+
+    let created = client.post(format!("{}/api/v1/admin/keys", server.base)).json(&json!({ "name": "app", "daily_quota": 2 }));
+    let created = admin(created).await.unwrap();
+    assert_eq!(created.status(), 201);
+    let created: Value = created.json().await.unwrap();
+    let key = created["key"].as_str().unwrap().to_string();
+    assert_eq!(created["tier"], "free");
+
+    for _ in 0..2 {
+        let resp = client.get(&weather).header("x-api-key", &key).send().await.unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+    let resp = client.get(format!("{}&api_key={}", weather, key)).send().await.unwrap();
+    assert!(resp.headers().contains_key("retry-after"));
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "QUOTA_EXCEEDED");
+
+    // 用量在内存中计数，退出时写入数据库，重启后额度仍然用完
+    assert!(server.terminate().await.success());
+    let server = start(&upstream, &envs).await;
+    let weather = format!("{}/api/v1/weather?lng=116.4&lat=39.9", server.base);
+    let body: Value = client.get(&weather).header("x-api-key", &key).send().await.unwrap().json().await.unwrap();
+    assert_eq!(body["code"], "QUOTA_EXCEEDED");
+
+    let detail = admin(client.get(format!("{}/api/v1/admin/keys/{}", server.base, key))).await.unwrap();
+    let detail: Value = detail.json().await.unwrap();
+    assert_eq!(detail["requests_today"], 2);
+    assert_eq!(detail["daily"][0]["requests"], 2);
+
+    // 取消额度、改为每分钟 1 次的等级
+    let updated: Value = admin(
+        client
+            .patch(format!("{}/api/v1/admin/keys/{}", server.base, key))
+            .json(&json!({ "daily_quota": null, "tier": "trial" })),
+    )
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(updated["daily_quota"], Value::Null);
+    assert_eq!(client.get(&weather).header("x-api-key", &key).send().await.unwrap().status(), 200);
+    let body: Value = client.get(&weather).header("x-api-key", &key).send().await.unwrap().json().await.unwrap();
+    assert_eq!(body["code"], "RATE_LIMITED");
+
+    admin(client.patch(format!("{}/api/v1/admin/keys/{}", server.base, key)).json(&json!({ "disabled": true })))
+        .await
+        .unwrap();
+    let resp = client.get(&weather).header("x-api-key", &key).send().await.unwrap();
+    assert_eq!(resp.status(), 401);
+    let deleted = admin(client.delete(format!("{}/api/v1/admin/keys/{}", server.base, key))).await.unwrap();
+    assert_eq!(deleted.status(), 204);
+    let listed = admin(client.get(format!("{}/api/v1/admin/keys", server.base))).await.unwrap();
+    let listed: Value = listed.json().await.unwrap();
+    assert_eq!(listed["keys"], json!([]));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", db, suffix));
+    }
+}
+
+#[tokio::test]
+async fn oversized_upstream_body_is_bad_gateway() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("UPSTREAM_MAX_BODY_BYTES", "1024")]).await;
+
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_error_envelope(resp, 502, "超过 1024 字节").await;
+}
+
+#[tokio::test]
+async fn oversized_request_body_is_rejected() {
+    let upstream = MockServer::start().await;
+    let server = start(&upstream, &[("ADMIN_TOKEN", "s3cret"), ("MAX_REQUEST_BODY_BYTES", "64")]).await;
+
+    let resp = reqwest::Client::new()
+        .post(format!("{}/api/v1/admin/keys", server.base))
+        .bearer_auth("s3cret")
+        .json(&json!({ "name": "x".repeat(100) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 413);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "PAYLOAD_TOO_LARGE");
+}
+
+#[tokio::test]
+async fn pinned_upstream_host_skips_dns() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .expect(1)
+        .mount(&upstream)
+        .await;
+    // .invalid 保留域名不会被任何 DNS 解析
+    let pinned = upstream.uri().replace("127.0.0.1", "caiyun.invalid");
+    let server = start(
+        &upstream,
+        &[("CAIYUN_API_URL", pinned.as_str()), ("UPSTREAM_HOSTS", "caiyun.invalid=127.0.0.1")],
+    )
+    .await;
+
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert!(body["current"].is_object());
+}
+
+#[tokio::test]
+async fn slow_caiyun_request_is_hedged() {
+    let upstream = MockServer::start().await;
+    // 首个请求卡住，对冲的第二个请求立即返回
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")).set_delay(Duration::from_secs(5)))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&upstream)
+        .await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("HEDGE_REQUESTS", "1"), ("HEDGE_MAX_MS", "200")]).await;
+
+    let started = Instant::now();
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert!(body["current"].is_object());
+    assert!(started.elapsed() < Duration::from_secs(2), "took {:?}", started.elapsed());
+    assert_eq!(upstream.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn persisted_cache_survives_restart_and_serves_stale_while_refreshing() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let db = std::env::temp_dir().join(format!("caiyun-cache-{}.sqlite3", std::process::id()));
+    let db = db.to_str().unwrap().to_string();
+    let envs = [("WEATHER_CACHE_SECS", "1"), ("CACHE_PERSIST", "1"), ("CACHE_DB", db.as_str())];
+
+    let mut server = start(&upstream, &envs).await;
+    server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    server.get_json("/api/v2/weather?lng=116.401&lat=39.899").await;
+    assert_eq!(upstream.received_requests().await.unwrap().len(), 1);
+    // 写库在后台进行，正常退出时等待写完
+    assert!(server.terminate().await.success());
+
+    // 重启后条目已过期：先返回旧数据并标记，同时后台刷新
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let server = start(&upstream, &envs).await;
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["warning"], "110 - \"Response is Stale\"");
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(upstream.received_requests().await.unwrap().len(), 2);
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert!(resp.headers().get("warning").is_none());
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", db, suffix));
+    }
+}
+
+#[tokio::test]
+async fn concurrent_cache_misses_share_one_upstream_request() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(fixture("clear_day"))
+                .set_delay(Duration::from_millis(300)),
+        )
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("WEATHER_CACHE_SECS", "60")]).await;
+
+    let (a, b, c) = tokio::join!(
+        server.get("/api/v1/weather?lng=116.4&lat=39.9"),
+        server.get("/api/v1/weather?lng=116.4&lat=39.9"),
+        server.get("/api/v2/weather?lng=116.401&lat=39.9"),
+    );
+    for resp in [a, b, c] {
+        assert_eq!(resp.status(), 200);
+    }
+    assert_eq!(upstream.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn admin_cache_lists_looks_up_and_purges_entries() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("WEATHER_CACHE_SECS", "60"), ("ADMIN_TOKEN", "s3cret")]).await;
+    server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    server.get_json("/api/v1/weather?lng=121.47&lat=31.23").await;
+    let client = reqwest::Client::new();
+    let admin = |req: reqwest::RequestBuilder| async {
+        req.bearer_auth("s3cret").send().await.unwrap().json::<Value>().await.unwrap()
+    };
+    let cache = format!("{}/api/v1/admin/cache", server.base);
+
+    let listed = admin(client.get(&cache)).await;
+    assert_eq!(listed["total"], 2);
+    let entry = &listed["entries"][0];
+    assert_eq!(entry["key"], "weather:116.40,39.90");
+    assert_eq!(entry["state"], "fresh");
+    assert!(entry["ttl_secs"].as_i64().unwrap() > 50);
+    assert!(entry["size"].as_u64().unwrap() > 0);
+
+    let lookup = admin(client.get(format!("{}/lookup?lng=116.401&lat=39.899", cache))).await;
+    assert_eq!(lookup["weather"]["result"], "hit");
+    assert_eq!(lookup["geocode"]["result"], "disabled");
+    let lookup = admin(client.get(format!("{}/lookup?lng=113.26&lat=23.13", cache))).await;
+    assert_eq!(lookup["weather"]["result"], "miss");
+
+    assert_eq!(admin(client.delete(format!("{}?prefix=116.", cache))).await["purged"], 1);
+    assert_eq!(admin(client.get(&cache)).await["entries"][0]["key"], "weather:121.47,31.23");
+    assert_eq!(admin(client.delete(&cache)).await["purged"], 1);
+    assert_eq!(admin(client.get(&cache)).await["total"], 0);
+    // 未经缓存的请求会重新访问上游
+    server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(upstream.received_requests().await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn weather_changes_compares_latest_fetch_with_previous() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&upstream)
+        .await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("storm_alerts")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[]).await;
+
+    let first = server.get_json("/api/v1/weather/changes?lng=116.4&lat=39.9").await;
+    assert!(first["previous_at"].is_null());
+    assert_eq!(first["changes"], json!([]));
+
+    let second = server.get_json("/api/v1/weather/changes?lng=116.4&lat=39.9").await;
+    assert!(second["previous_at"].is_string());
+    let changes = second["changes"].as_array().unwrap();
+    let types: Vec<&str> = changes.iter().map(|c| c["type"].as_str().unwrap()).collect();
+    assert_eq!(types, ["temperature", "skycon", "alert", "alert", "rain_starting"]);
+    assert_eq!(changes[0]["delta"], -5.8);
+    assert_eq!(changes[1]["to"], "STORM_RAIN");
+    assert_eq!(changes[2]["id"], "11000041600000_20250728073000");
+    assert_eq!(changes[4]["in_minutes"], 0);
+}
+
+#[tokio::test]
+async fn hourly_air_quality_endpoint() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[]).await;
+
+    let body = server.get_json("/api/v1/weather/air/hourly?lng=116.4&lat=39.9").await;
+    let hourly = body["hourly"].as_array().unwrap();
+    assert_eq!(hourly.len(), 24);
+    assert_eq!(hourly[0], json!({ "time": "2025-07-28T08:00+08:00", "aqi": 23, "aqi_usa": 38, "category": "优", "pm25": 9 }));
+}
+
+#[tokio::test]
+async fn icon_set_is_selectable_per_request() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[]).await;
+
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["current"]["weather_info"], json!({ "icon": "☀️", "desc": "晴" }));
+    let night = &body["daily"][1]["weather_info_night"];
+    assert_eq!(night["layers"], json!(["🌙", "☁️"]));
+    assert_eq!(night["icon"], "☁️");
+
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9&icons=weather-icons").await;
+    assert_eq!(body["current"]["weather_info"]["icon"], "wi wi-day-sunny");
+    assert_eq!(body["hourly"][12]["weather_info"]["icon"], "wi wi-night-clear");
+    assert_eq!(body["daily"][1]["weather_info_night"], json!({ "icon": "wi wi-night-alt-cloudy", "desc": "多云（夜间）" }));
+
+    let body = server.get_json("/api/v2/weather?lng=116.4&lat=39.9&icons=svg").await;
+    assert_eq!(body["current"]["condition"]["icon"], "clear-day.svg");
+
+    assert_eq!(server.get("/api/v1/weather?lng=116.4&lat=39.9&icons=nope").await.status().as_u16(), 400);
+    assert_eq!(server.get("/api/v2/weather?lng=116.4&lat=39.9&icons=nope").await.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn hourly_skycon_follows_sunrise_and_sunset() {
+    let mut raw = fixture("clear_day");
+    let sky = &mut raw["result"]["hourly"]["skycon"];
+    // 08:00 与 23:00 的昼夜变体与实际相反；日落为 19:36
+    sky[0]["value"] = json!("PARTLY_CLOUDY_NIGHT");
+    sky[15]["value"] = json!("CLEAR_DAY");
+    sky[16]["value"] = json!("CLOUDY");
+    let upstream = MockServer::start().await;
+    caiyun_weather().respond_with(ResponseTemplate::new(200).set_body_json(raw)).mount(&upstream).await;
+    let server = start(&upstream, &[]).await;
+
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    let skycon = |i: usize| body["hourly"][i]["skycon"].as_str().unwrap().to_string();
+    assert_eq!(skycon(0), "PARTLY_CLOUDY_DAY");
+    assert_eq!(skycon(11), "CLEAR_DAY");
+    assert_eq!(skycon(12), "CLEAR_NIGHT");
+    assert_eq!(skycon(15), "CLEAR_NIGHT");
+    assert_eq!(skycon(16), "CLOUDY");
+    assert_eq!(body["hourly"][15]["weather_info"]["icon"], "🌙");
+
+    let body = server.get_json("/api/v2/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["hourly"][0]["condition"]["code"], "PARTLY_CLOUDY_DAY");
+    assert_eq!(body["hourly"][15]["condition"]["code"], "CLEAR_NIGHT");
+}
+
+#[tokio::test]
+async fn daily_labels_use_location_timezone() {
+    // UTC+14：服务器（UTC）与当地日期常常不同，标签应以当地日期为准
+    let offset = chrono::FixedOffset::east_opt(14 * 3600).unwrap();
+    let today = chrono::Utc::now().with_timezone(&offset).date_naive();
+    let mut raw = fixture("clear_day");
+    raw["tzshift"] = json!(14 * 3600);
+    for (i, day) in raw["result"]["daily"]["temperature"].as_array_mut().unwrap().iter_mut().enumerate() {
+        let date = today + chrono::Days::new(i as u64) - chrono::Days::new(1);
+        day["date"] = json!(format!("{}T00:00+14:00", date));
+    }
+    let upstream = MockServer::start().await;
+    caiyun_weather().respond_with(ResponseTemplate::new(200).set_body_json(raw)).mount(&upstream).await;
+    let server = start(&upstream, &[]).await;
+
+    let body = server.get_json("/api/v1/weather?lng=-157.4&lat=1.9").await;
+    let daily = body["daily"].as_array().unwrap();
+    let labels: Vec<&str> = daily.iter().map(|d| d["relativeDay"].as_str().unwrap()).collect();
+    assert_eq!(labels, ["昨天", "今天", "明天"]);
+    assert_eq!(daily[1]["date"], today.format("%m-%d").to_string());
+}
+
+#[tokio::test]
+async fn hourly_labels_use_location_timezone() {
+    // 乌鲁木齐约 87.6°E，按经度估算为 UTC+6，实际使用北京时间（tzshift）
+    let upstream = MockServer::start().await;
+    caiyun_weather().respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day"))).mount(&upstream).await;
+    let server = start(&upstream, &[]).await;
+    let offset = chrono::FixedOffset::east_opt(8 * 3600).unwrap();
+    let local_hour = || chrono::Timelike::hour(&chrono::Utc::now().with_timezone(&offset)) as i64;
+
+    let before = local_hour();
+    let body = server.get_json("/api/v1/weather?lng=87.6&lat=43.8").await;
+    let blocks = server.get_json("/api/v1/weather?lng=87.6&lat=43.8&granularity=6h").await;
+    let expected = [before, local_hour()];
+    assert!(expected.contains(&body["hourly"][0]["time"].as_i64().unwrap()), "{}", body["hourly"][0]);
+    let periods = ["night", "morning", "afternoon", "evening"];
+    let period = blocks["hourly"][0]["period"].as_str().unwrap();
+    assert!(expected.iter().any(|h| periods[*h as usize / 6] == period), "{}", blocks["hourly"][0]);
+}
+
+#[tokio::test]
+async fn custom_tip_rules_replace_builtin_rules() {
+    let rules = std::env::temp_dir().join(format!("caiyun-tips-{}.toml", std::process::id()));
+    std::fs::write(
+        &rules,
+        r#"
+[[rules]]
+tip = "适合晒被子"
+priority = 10
+when = ["humidity < 60", "rain_probability < 20"]
+
+[[rules]]
+tip = "穿短袖"
+priority = 50
+when = ["temperature >= 25"]
+
+[[rules]]
+tip = "带伞"
+priority = 90
+when = ["will_rain == 1"]
+"#,
+    )
+    .unwrap();
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("TIPS_RULES_FILE", rules.to_str().unwrap())]).await;
+
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["advice"]["tips"], json!([{ "tip": "穿短袖", "priority": 50 }, { "tip": "适合晒被子", "priority": 10 }]));
+    let body = server.get_json("/api/v2/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["advice"]["tips"][0]["tip"], "穿短袖");
+    let _ = std::fs::remove_file(&rules);
+}
+
+#[tokio::test]
+async fn alerts_are_filtered_by_adcode_and_distance() {
+    let mut raw = fixture("storm_alerts");
+    let alert = &mut raw["result"]["alert"];
+    alert["adcodes"] = json!([{ "adcode": 110000, "name": "北京市" }, { "adcode": 110105, "name": "朝阳区" }]);
+    let content = alert["content"].as_array_mut().unwrap();
+    let template = content[1].clone();
+    // 同城较远的平谷区（约 70 km）与邻近的东城区
+    for (adcode, title, latlon) in [
+        ("110117", "平谷区气象台发布大风蓝色预警", [40.14, 117.12]),
+        ("110101", "东城区气象台发布高温黄色预警", [39.93, 116.42]),
+    ] {
+        let mut a = template.clone();
+        a["adcode"] = json!(adcode);
+        a["alertId"] = json!(format!("{}_20250728080000", adcode));
+        a["title"] = json!(title);
+        a["latlon"] = json!(latlon);
+        content.push(a);
+    }
+    let upstream = MockServer::start().await;
+    caiyun_weather().respond_with(ResponseTemplate::new(200).set_body_json(raw)).mount(&upstream).await;
+    let titles = |body: &Value| -> Vec<String> {
+        body["alerts"].as_array().unwrap().iter().map(|a| a["title"].as_str().unwrap().to_string()).collect()
+    };
+    let path = "/api/v1/weather?lng=116.4074&lat=39.9042";
+
+    let server = start(&upstream, &[]).await;
+    assert_eq!(titles(&server.get_json(path).await).len(), 4);
+
+    // 行政区划：省级预警覆盖该点，区县预警只保留所在区
+    let server = start(&upstream, &[("ALERT_MATCH_ADCODE", "1")]).await;
+    let kept = titles(&server.get_json(path).await);
+    assert_eq!(kept.len(), 2);
+    assert!(kept.iter().all(|t| t.starts_with("北京市")));
+
+    // 距离：没有 latlon 的预警不参与判断
+    let server = start(&upstream, &[("ALERT_RADIUS_KM", "20")]).await;
+    let kept = titles(&server.get_json(path).await);
+    assert_eq!(kept.len(), 3);
+    assert!(!kept.iter().any(|t| t.starts_with("平谷")));
+
+    // 两者同时开启：满足其一即保留
+    let server = start(&upstream, &[("ALERT_MATCH_ADCODE", "1"), ("ALERT_RADIUS_KM", "20")]).await;
+    let body = server.get_json("/api/v2/weather?lng=116.4074&lat=39.9042").await;
+    assert_eq!(body["alerts"].as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn alert_history_lists_recent_alerts_for_the_area() {
+    let db = std::env::temp_dir().join(format!("caiyun-alerts-{}.sqlite3", std::process::id()));
+    let _ = std::fs::remove_file(&db);
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("storm_alerts")))
+        .mount(&upstream)
+        .await;
+
+    let server = start(&upstream, &[]).await;
+    assert_eq!(server.get("/api/v1/alerts/history?lng=116.4&lat=39.9").await.status().as_u16(), 404);
+
+    let server = start(&upstream, &[("ALERT_HISTORY", "1"), ("ALERT_HISTORY_DB", db.to_str().unwrap())]).await;
+    let body = server.get_json("/api/v1/weather?lng=116.4074&lat=39.9042").await;
+    assert_eq!(body["alerts"][0]["id"], "11000041600000_20250728073000");
+    // 再次请求上游同一预警只更新最近出现时间
+    server.get_json("/api/v1/weather?lng=116.41&lat=39.91").await;
+
+    let body = server.get_json("/api/v1/alerts/history?lng=116.4&lat=39.9&days=3").await;
+    assert_eq!(body["days"], 3);
+    let alerts = body["alerts"].as_array().unwrap();
+    assert_eq!(alerts.len(), 2);
+    let red = alerts.iter().find(|a| a["id"] == "11000041600000_20250728073000").unwrap();
+    assert_eq!(red["severity"], "红色");
+    assert_eq!(red["region"], "北京市");
+    assert_eq!(red["adcode"], "110000");
+    assert_eq!(red["published_at"], "2025-07-27T23:30:00+00:00");
+    // 相邻网格以外的地点没有记录
+    let body = server.get_json("/api/v1/alerts/history?lng=121.47&lat=31.23").await;
+    assert_eq!(body["alerts"], json!([]));
+    let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+async fn accuracy_compares_forecasts_with_observed_extremes() {
+    // 逐日预报从今天开始；第一次记录时今天最高 33°C，之后的预报修正为 35°C
+    let offset = chrono::FixedOffset::east_opt(8 * 3600).unwrap();
+    let today = chrono::Utc::now().with_timezone(&offset).date_naive();
+    let mut raw = fixture("clear_day");
+    for (i, day) in raw["result"]["daily"]["temperature"].as_array_mut().unwrap().iter_mut().enumerate() {
+        day["date"] = json!(format!("{}T00:00+08:00", today + chrono::Days::new(i as u64)));
+    }
+    let mut revised = raw.clone();
+    revised["result"]["daily"]["temperature"][0]["max"] = json!(35.0);
+    let upstream = MockServer::start().await;
+    caiyun_weather().respond_with(ResponseTemplate::new(200).set_body_json(raw)).up_to_n_times(1).mount(&upstream).await;
+    caiyun_weather().respond_with(ResponseTemplate::new(200).set_body_json(revised)).mount(&upstream).await;
+    let server = start(&upstream, &[]).await;
+    assert_eq!(server.get("/api/v1/accuracy?lng=116.4&lat=39.9").await.status().as_u16(), 404);
+
+    let db = std::env::temp_dir().join(format!("caiyun-accuracy-{}.sqlite3", std::process::id()));
+    let _ = std::fs::remove_file(&db);
+    let server = start(
+        &upstream,
+        &[("ACCURACY_LOCATIONS", "beijing:116.4074,39.9042"), ("ACCURACY_DB", db.to_str().unwrap()), ("ADMIN_TOKEN", "s3cret")],
+    )
+    .await;
+    assert_eq!(server.get("/api/v1/accuracy?lng=121.47&lat=31.23").await.status().as_u16(), 404);
+
+    // 昨天完整的 24 次实况（20 ~ 31.5°C）及提前 1、2 天的预报；前天只有 5 次实况，不计入
+    let day = |n: u64| (today - chrono::Days::new(n)).to_string();
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    for hour in 0..24 {
+        conn.execute(
+            "INSERT INTO observations (location, date, hour, temperature) VALUES ('beijing', ?1, ?2, ?3)",
+            rusqlite::params![day(1), hour, 20.0 + hour as f64 / 2.0],
+        )
+        .unwrap();
+    }
+    for hour in 0..5 {
+        conn.execute(
+            "INSERT INTO observations (location, date, hour, temperature) VALUES ('beijing', ?1, ?2, 25)",
+            rusqlite::params![day(2), hour],
+        )
+        .unwrap();
+    }
+    for (issued, target, lead, high, low) in [(3, 1, 2, 30.0, 19.0), (2, 1, 1, 33.0, 21.0), (3, 2, 1, 26.0, 20.0)] {
+        conn.execute(
+            "INSERT INTO forecasts (location, issued, target, lead_days, high, low) VALUES ('beijing', ?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![day(issued), day(target), lead, high, low],
+        )
+        .unwrap();
+    }
+    drop(conn);
+
+    // 立即执行两次记录任务并等待完成，写入今天的实况与预报
+    for _ in 0..2 {
+        let resp = reqwest::Client::new()
+            .post(format!("{}/api/v1/admin/jobs/accuracy_record/run?wait=true", server.base))
+            .bearer_auth("s3cret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let job: Value = resp.json().await.unwrap();
+        assert!(job["last_error"].is_null(), "{}", job);
+    }
+    // 同一天发布的预报保留第一次
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    let high: f64 = conn
+        .query_row("SELECT high FROM forecasts WHERE issued = ?1 AND lead_days = 0", [day(0)], |r| r.get(0))
+        .unwrap();
+    assert_eq!(high, 33.0);
+    drop(conn);
+    let body = server.get_json("/api/v1/accuracy?lng=116.41&lat=39.91&days=7").await;
+    assert_eq!(body["location"]["name"], "beijing");
+    assert_eq!(body["today"], json!({ "date": day(0), "observed": { "high": 30.5, "low": 30.5, "samples": 1 } }));
+    assert_eq!(
+        body["leads"],
+        json!([
+            { "lead_days": 1, "samples": 1, "high": { "mae": 1.5, "bias": 1.5 }, "low": { "mae": 1.0, "bias": 1.0 } },
+            { "lead_days": 2, "samples": 1, "high": { "mae": 1.5, "bias": -1.5 }, "low": { "mae": 1.0, "bias": -1.0 } },
+        ])
+    );
+    let history = body["history"].as_array().unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0]["date"], day(1));
+    assert_eq!(history[0]["observed"], json!({ "high": 31.5, "low": 20.0, "samples": 24 }));
+    let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+async fn weather_metrics_export_gauges_per_location() {
+    let upstream = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v2\.6/test-token/121\.47,31\.23/weather$"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&upstream)
+        .await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("WEATHER_METRICS_LOCATIONS", "北京:116.41,39.91;上海:121.47,31.23")]).await;
+
+    // 启动时先刷新一次
+    let mut text = String::new();
+    for _ in 0..50 {
+        let resp = server.get("/metrics/weather").await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()["content-type"], "application/openmetrics-text; version=1.0.0; charset=utf-8");
+        text = resp.text().await.unwrap();
+        if text.contains("weather_up{location=\"上海\"}") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let lines: Vec<&str> = text.lines().collect();
+    for line in [
+        "# TYPE weather_temperature_celsius gauge",
+        "weather_aqi{location=\"北京\"} 23",
+        "weather_humidity_percent{location=\"北京\"} 45",
+        "weather_pm25_micrograms_per_cubic_meter{location=\"北京\"} 9",
+        "weather_up{location=\"北京\"} 1",
+        "weather_up{location=\"上海\"} 0",
+    ] {
+        assert!(lines.contains(&line), "missing {:?} in\n{}", line, text);
+    }
+    // 刷新失败的地点没有数值
+    assert!(!text.contains("weather_aqi{location=\"上海\"}"));
+    assert_eq!(lines.last(), Some(&"# EOF"));
+
+    // 未配置时 404
+    let server = start(&upstream, &[]).await;
+    assert_eq!(server.get("/metrics/weather").await.status(), 404);
+}
+
+#[tokio::test]
+async fn webhook_failures_do_not_expose_the_url() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    Mock::given(method("POST")).and(path("/hook/T0P-SECRET")).respond_with(ResponseTemplate::new(500)).mount(&upstream).await;
+    let file = std::env::temp_dir().join(format!("caiyun-webhooks-fail-{}.toml", std::process::id()));
+    let data_dir = std::env::temp_dir().join(format!("caiyun-webhooks-fail-{}", std::process::id()));
+    std::fs::write(
+        &file,
+        format!(
+            "[[rules]]\nname = \"hot\"\nlocation = {{ name = \"北京\", lng = 116.41, lat = 39.91 }}\nwhen = [\"temperature >= 30\"]\nurl = \"{}/hook/T0P-SECRET?key=abc\"\n",
+            upstream.uri()
+        ),
+    )
+    .unwrap();
+    let server = start(
+        &upstream,
+        &[
+            ("WEBHOOK_RULES_FILE", file.to_str().unwrap()),
+            ("ADMIN_TOKEN", "s3cret"),
+            ("DATA_DIR", data_dir.to_str().unwrap()),
+        ],
+    )
+    .await;
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("{}/api/v1/admin/jobs/webhook_rules/run?wait=true", server.base))
+        .bearer_auth("s3cret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client.get(format!("{}/api/v1/admin/webhooks", server.base)).bearer_auth("s3cret").send().await.unwrap();
+    let text = resp.text().await.unwrap();
+    let rules: Value = serde_json::from_str(&text).unwrap();
+    let error = rules["rules"][0]["last_error"].as_str().unwrap();
+    assert!(error.contains("500"), "{}", error);
+    assert!(!text.contains("T0P-SECRET") && !text.contains("key=abc"), "{}", text);
+    let _ = std::fs::remove_file(&file);
+    let _ = std::fs::remove_dir_all(&data_dir);
+}
+
+#[tokio::test]
+async fn webhook_rules_fire_once_per_cooldown() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/hook/fine"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&upstream)
+        .await;
+    let file = std::env::temp_dir().join(format!("caiyun-webhooks-{}.toml", std::process::id()));
+    let data_dir = std::env::temp_dir().join(format!("caiyun-webhooks-{}", std::process::id()));
+    std::fs::write(
+        &file,
+        format!(
+            r#"
+[[rules]]
+name = "fine"
+location = {{ name = "北京", lng = 116.41, lat = 39.91 }}
+when = ["temperature >= 30", "aqi < 50"]
+url = "{base}/hook/fine?key=secret"
+headers = {{ Authorization = "Bearer hook-token" }}
+body = '{{"text": {{{{ (location.name ~ " AQI " ~ metrics.aqi)|tojson }}}}, "tomorrow_max": {{{{ weather.daily[1].max_temp }}}}}}'
+
+[[rules]]
+name = "freeze"
+location = {{ name = "北京", lng = 116.41, lat = 39.91 }}
+day = "tomorrow"
+when = ["min_temp < 0"]
+url = "{base}/hook/freeze"
+"#,
+            base = upstream.uri()
+        ),
+    )
+    .unwrap();
+    let server = start(
+        &upstream,
+        &[
+            ("WEBHOOK_RULES_FILE", file.to_str().unwrap()),
+            ("ADMIN_TOKEN", "s3cret"),
+            ("DATA_DIR", data_dir.to_str().unwrap()),
+        ],
+    )
+    .await;
+    let client = reqwest::Client::new();
+
+    // 连续执行两次：第二次仍满足条件，但在冷却期内不再发送
+    for run in 1..=2 {
+        let resp = client
+            .post(format!("{}/api/v1/admin/jobs/webhook_rules/run", server.base))
+            .bearer_auth("s3cret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 202);
+        for _ in 0..50 {
+            let jobs: Value =
+                client.get(format!("{}/api/v1/admin/jobs", server.base)).bearer_auth("s3cret").send().await.unwrap().json().await.unwrap();
+            let job = jobs["jobs"].as_array().unwrap().iter().find(|j| j["name"] == "webhook_rules").unwrap().clone();
+            if job["runs"] == run && job["running"] == false {
+                assert_eq!(job["last_error"], Value::Null);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    let hooks: Vec<_> = upstream.received_requests().await.unwrap().into_iter().filter(|r| r.url.path().starts_with("/hook/")).collect();
+    assert_eq!(hooks.len(), 1);
+    assert_eq!(hooks[0].headers["authorization"], "Bearer hook-token");
+    assert_eq!(hooks[0].headers["content-type"], "application/json");
+    let body: Value = serde_json::from_slice(&hooks[0].body).unwrap();
+    assert_eq!(body, json!({ "text": "北京 AQI 23.0", "tomorrow_max": 32 }));
+
+    let resp = client.get(format!("{}/api/v1/admin/webhooks", server.base)).bearer_auth("s3cret").send().await.unwrap();
+    let rules = resp.json::<Value>().await.unwrap()["rules"].clone();
+    assert_eq!(rules[0]["name"], "fine");
+    assert_eq!(rules[0]["host"], "127.0.0.1");
+    assert_eq!(rules[0]["matched"], true);
+    assert!(rules[0]["last_fired"].is_string());
+    assert_eq!(rules[1]["matched"], false);
+    assert_eq!(rules[1]["metrics"], json!({ "min_temp": 24.0 }));
+    assert_eq!(rules[1]["last_fired"], Value::Null);
+
+    // 未知指标：启动前校验失败
+    std::fs::write(&file, "[[rules]]\nname = \"x\"\nlocation = { name = \"北京\", lng = 116.41, lat = 39.91 }\nwhen = [\"snow > 1\"]\nurl = \"http://127.0.0.1/\"\n").unwrap();
+    let out = common::command(&[("WEBHOOK_RULES_FILE", file.to_str().unwrap())]).arg("doctor").output().expect("run doctor");
+    assert!(!out.status.success());
+    let _ = std::fs::remove_file(&file);
+    let _ = std::fs::remove_dir_all(&data_dir);
+}
+
+#[tokio::test]
+async fn tenants_resolve_by_host_and_path_prefix() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v2\.6/acme-token/[-0-9.]+,[-0-9.]+/weather$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("storm_alerts")))
+        .mount(&upstream)
+        .await;
+    let file = std::env::temp_dir().join(format!("caiyun-tenants-{}.toml", std::process::id()));
+    std::fs::write(
+        &file,
+        r#"
+[[tenants]]
+name = "acme"
+hosts = ["weather.acme.test"]
+path_prefix = "/acme"
+caiyun_token = "acme-token"
+icon_set = "weather-icons"
+
+[[tenants]]
+name = "beta"
+path_prefix = "/beta"
+rate_limit_per_minute = 2
+default_city = { name = "广州市", lng = 113.2644, lat = 23.1291 }
+"#,
+    )
+    .unwrap();
+    // 开启上游结果缓存：自带 token 的租户不能命中全局 token 缓存的同一坐标
+    let server = start(
+        &upstream,
+        &[("TENANTS_FILE", file.to_str().unwrap()), ("WEATHER_CACHE_SECS", "300"), ("ADMIN_TOKEN", "s3cret")],
+    )
+    .await;
+    let client = reqwest::Client::new();
+
+    // 未匹配租户：全局 token 与默认图标集
+    let body = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["alerts"], json!([]));
+    assert_eq!(body["current"]["weather_info"]["icon"], "☀️");
+
+    // 主机名与路径前缀都指向 acme：使用其 token 与图标集
+    let resp = client
+        .get(format!("{}/api/v1/weather?lng=116.4&lat=39.9", server.base))
+        .header("host", "Weather.Acme.Test:8080")
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["alerts"].as_array().map(Vec::len), Some(2));
+    assert!(body["current"]["weather_info"]["icon"].as_str().unwrap().starts_with("wi "));
+    let body = server.get_json("/acme/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(body["alerts"].as_array().map(Vec::len), Some(2));
+    let page = server.get("/acme").await;
+    assert_eq!(page.status(), 200);
+    assert_eq!(page.url().path(), "/acme/");
+
+    // beta：默认城市与每分钟 2 次的 API 限制
+    let body = server.get_json("/beta/api/v1/location/ip").await;
+    assert_eq!(body, json!({ "lat": 23.1291, "lng": 113.2644, "address": "广州市", "local_network": true }));
+    server.get_json("/beta/api/v1/weather?lng=116.4&lat=39.9").await;
+    let resp = server.get("/beta/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert!(resp.headers().contains_key("retry-after"));
+    assert_error_envelope(resp, 429, "请求过于频繁").await;
+    assert_eq!(server.get("/api/v1/weather?lng=116.4&lat=39.9").await.status(), 200);
+
+    // 缓存条目：acme 单独一条，未自带 token 的 beta 与全局共用
+    let lookup = |tenant: &str| {
+        client
+            .get(format!("{}/api/v1/admin/cache/lookup?lng=116.4&lat=39.9&tenant={}", server.base, tenant))
+            .bearer_auth("s3cret")
+            .send()
+    };
+    let body: Value = lookup("acme").await.unwrap().json().await.unwrap();
+    assert_eq!(body["weather"]["key"], "weather:116.40,39.90@acme");
+    assert_eq!(body["weather"]["result"], "hit");
+    let body: Value = lookup("beta").await.unwrap().json().await.unwrap();
+    assert_eq!(body["weather"]["key"], "weather:116.40,39.90");
+    assert_eq!(lookup("nope").await.unwrap().status(), 404);
+    let _ = std::fs::remove_file(&file);
+}
+
+#[tokio::test]
+async fn accounts_keep_preferences_across_sessions() {
+    let upstream = MockServer::start().await;
+    let server = start(&upstream, &[]).await;
+    assert_eq!(server.get("/api/v1/me").await.status().as_u16(), 404);
+
+    let db = std::env::temp_dir().join(format!("caiyun-accounts-{}.sqlite3", std::process::id()));
+    let _ = std::fs::remove_file(&db);
+    let server = start(&upstream, &[("ACCOUNTS", "1"), ("ACCOUNTS_DB", db.to_str().unwrap())]).await;
+    let client = reqwest::Client::new();
+    let url = |path: &str| format!("{}/api/v1{}", server.base, path);
+    let session = |resp: &reqwest::Response| {
+        let cookie = resp.headers()["set-cookie"].to_str().unwrap().to_string();
+        assert!(cookie.contains("HttpOnly") && cookie.contains("SameSite=Lax"), "{}", cookie);
+        cookie.split(';').next().unwrap().to_string()
+    };
+
+    let register = |password: &'static str| {
+        client.post(url("/auth/register")).json(&json!({ "email": "Alice@Example.com", "password": password })).send()
+    };
+    assert_error_envelope(register("short").await.unwrap(), 400, "至少 8 个字符").await;
+    let resp = register("correct horse").await.unwrap();
+    assert_eq!(resp.status(), 201);
+    let laptop = session(&resp);
+    assert_eq!(register("correct horse").await.unwrap().status(), 409);
+
+    let me: Value = client.get(url("/me")).header("cookie", &laptop).send().await.unwrap().json().await.unwrap();
+    assert_eq!(me["user"]["email"], "alice@example.com");
+    let resp = server.get("/api/v1/me").await;
+    assert_eq!(resp.status(), 401);
+    assert_eq!(resp.json::<Value>().await.unwrap()["code"], "LOGIN_REQUIRED");
+
+    let login = |password: &'static str| {
+        client.post(url("/auth/login")).json(&json!({ "email": "alice@example.com", "password": password })).send()
+    };
+    assert_error_envelope(login("wrong password").await.unwrap(), 401, "邮箱或密码错误").await;
+    let resp = login("correct horse").await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let phone = session(&resp);
+
+    // 笔记本上保存的偏好在手机上可见
+    let defaults: Value = client.get(url("/me/preferences")).header("cookie", &phone).send().await.unwrap().json().await.unwrap();
+    assert_eq!(defaults["units"], "metric");
+    assert_eq!(defaults["locations"], json!([]));
+    let prefs = json!({
+        "locations": [{ "name": "家", "lng": 116.4, "lat": 39.9 }],
+        "units": "imperial",
+        "language": "en_US",
+        "notifications": { "rain": true, "alerts": false, "digest_hour": 7 },
+    });
+    let resp = client.put(url("/me/preferences")).header("cookie", &laptop).json(&prefs).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let saved: Value = client.get(url("/me/preferences")).header("cookie", &phone).send().await.unwrap().json().await.unwrap();
+    assert_eq!(saved, prefs);
+    let invalid = json!({ "notifications": { "digest_hour": 24 } });
+    let resp = client.put(url("/me/preferences")).header("cookie", &laptop).json(&invalid).send().await.unwrap();
+    assert_error_envelope(resp, 400, "digest_hour").await;
+
+    // 退出只影响当前会话
+    let resp = client.post(url("/auth/logout")).header("cookie", &laptop).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+    assert!(resp.headers()["set-cookie"].to_str().unwrap().contains("Max-Age=0"));
+    assert_eq!(client.get(url("/me")).header("cookie", &laptop).send().await.unwrap().status(), 401);
+    assert_eq!(client.get(url("/me")).header("cookie", &phone).send().await.unwrap().status(), 200);
+
+    // 未配置 PUBLIC_URL 与邮件服务时无法发送登录链接
+    let resp = client.post(url("/auth/link")).json(&json!({ "email": "bob@example.com" })).send().await.unwrap();
+    assert_eq!(resp.status(), 503);
+    let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+async fn login_links_need_confirmation_and_are_rate_limited() {
+    let upstream = MockServer::start().await;
+    let db = std::env::temp_dir().join(format!("caiyun-links-{}.sqlite3", std::process::id()));
+    let _ = std::fs::remove_file(&db);
+    let server = start(
+        &upstream,
+        &[("ACCOUNTS", "1"), ("ACCOUNTS_DB", db.to_str().unwrap()), ("PUBLIC_URL", "http://weather.test")],
+    )
+    .await;
+    let client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap();
+    let url = |path: &str| format!("{}/api/v1{}", server.base, path);
+
+    // 邮件中的链接（未启用 email feature，直接写库）
+    let token = "ab".repeat(32);
+    let expires = chrono::Utc::now().timestamp() + 600;
+    rusqlite::Connection::open(&db)
+        .unwrap()
+        .execute(
+            "INSERT INTO login_links (token, email, expires_at) VALUES (?1, 'dave@example.com', ?2)",
+            rusqlite::params![token, expires],
+        )
+        .unwrap();
+
+    // 打开链接（含邮件客户端预取）只显示确认页，不登录也不消耗链接；确认页下发 CSRF Cookie
+    let mut csrf = String::new();
+    for _ in 0..2 {
+        let resp = client.get(url(&format!("/auth/link?token={}", token))).send().await.unwrap();
+        assert_eq!(resp.status(), 200);
+        let cookie = resp.headers()["set-cookie"].to_str().unwrap().to_string();
+        assert!(cookie.starts_with("link_csrf=") && cookie.contains("SameSite=Strict"), "{}", cookie);
+        csrf = cookie["link_csrf=".len()..].split(';').next().unwrap().to_string();
+        let page = resp.text().await.unwrap();
+        assert!(page.contains("method=\"post\"") && page.contains(&token) && page.contains(&csrf), "{}", page);
+    }
+    assert_eq!(client.get(url("/auth/link?token=<script>")).send().await.unwrap().status(), 401);
+
+    // 他站代提交（没有确认页下发的 Cookie）被拒绝，也不消耗链接
+    let form = [("token", token.as_str()), ("csrf", csrf.as_str())];
+    let resp = client.post(url("/auth/link/confirm")).form(&form).send().await.unwrap();
+    assert_error_envelope(resp, 403, "登录确认已失效").await;
+    let forged = client.post(url("/auth/link/confirm")).header("cookie", "link_csrf=ff").form(&form).send();
+    assert_eq!(forged.await.unwrap().status(), 403);
+
+    // 提交确认后登录，链接只能用一次
+    let cookie = format!("link_csrf={}", csrf);
+    let confirm = || client.post(url("/auth/link/confirm")).header("cookie", &cookie).form(&form).send();
+    let resp = confirm().await.unwrap();
+    assert_eq!(resp.status(), 303);
+    let cookies: Vec<_> = resp.headers().get_all("set-cookie").iter().map(|v| v.to_str().unwrap()).collect();
+    assert!(cookies[0].starts_with("session="), "{:?}", cookies);
+    assert!(cookies[1].starts_with("link_csrf=;") && cookies[1].contains("Max-Age=0"), "{:?}", cookies);
+    assert_error_envelope(confirm().await.unwrap(), 401, "无效或已过期").await;
+    assert_eq!(client.get(url(&format!("/auth/link?token={}", token))).send().await.unwrap().status(), 401);
+
+    // 同一邮箱最多 3 次（发送失败也计入），同一客户端最多 10 次
+    let request = |email: String| client.post(url("/auth/link")).json(&json!({ "email": email })).send();
+    for _ in 0..3 {
+        assert_eq!(request("erin@example.com".into()).await.unwrap().status(), 503);
+    }
+    let resp = request("Erin@Example.com".into()).await.unwrap();
+    assert!(resp.headers().contains_key("retry-after"));
+    assert_error_envelope(resp, 429, "过于频繁").await;
+    for i in 0..6 {
+        assert_eq!(request(format!("user{}@example.com", i)).await.unwrap().status(), 503);
+    }
+    assert_eq!(request("frank@example.com".into()).await.unwrap().status(), 429);
+    let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+async fn oidc_login_maps_subject_to_the_same_user() {
+    let idp = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/.well-known/openid-configuration"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "issuer": idp.uri(),
+            "authorization_endpoint": format!("{}/authorize", idp.uri()),
+            "token_endpoint": format!("{}/token", idp.uri()),
+            "userinfo_endpoint": format!("{}/userinfo", idp.uri()),
+        })))
+        .expect(1)
+        .mount(&idp)
+        .await;
+    let db = std::env::temp_dir().join(format!("caiyun-oidc-{}.sqlite3", std::process::id()));
+    let _ = std::fs::remove_file(&db);
+    let server = start(
+        &idp,
+        &[
+            ("ACCOUNTS", "1"),
+            ("ACCOUNTS_DB", db.to_str().unwrap()),
+            ("OIDC_ISSUER", &idp.uri()),
+            ("OIDC_CLIENT_ID", "caiyun"),
+            ("OIDC_CLIENT_SECRET", "s3cret"),
+            ("PUBLIC_URL", "http://weather.test"),
+        ],
+    )
+    .await;
+    let client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap();
+    let url = |p: &str| format!("{}/api/v1{}", server.base, p);
+    let cookie = |resp: &reqwest::Response, name: &str| {
+        resp.headers()
+            .get_all("set-cookie")
+            .iter()
+            .map(|v| v.to_str().unwrap().to_string())
+            .find(|c| c.starts_with(&format!("{}=", name)))
+    };
+
+    // 发起登录，返回 state 与浏览器中的 state Cookie
+    let begin = || {
+        let client = &client;
+        async move {
+            let resp = client.get(url("/auth/oidc/login")).send().await.unwrap();
+            assert_eq!(resp.status(), 303);
+            let set = cookie(&resp, "oidc_state").expect("state cookie");
+            assert!(set.contains("HttpOnly") && set.contains("SameSite=Lax"), "{}", set);
+            let location = reqwest::Url::parse(resp.headers()["location"].to_str().unwrap()).unwrap();
+            assert_eq!(location.path(), "/authorize");
+            let param = |k: &str| location.query_pairs().find(|(n, _)| n == k).map(|(_, v)| v.into_owned()).unwrap();
+            assert_eq!(param("redirect_uri"), "http://weather.test/api/v1/auth/oidc/callback");
+            assert_eq!(param("code_challenge_method"), "S256");
+            (param("state"), set.split(';').next().unwrap().to_string())
+        }
+    };
+    // 完成一次登录：身份经 userinfo 端点取得；session 为已登录用户的会话 Cookie
+    let sign_in = |sub: &'static str, email: &'static str, verified: bool, session: Option<String>| {
+        let (client, idp) = (&client, &idp);
+        async move {
+            let (state, state_cookie) = begin().await;
+            let code = format!("code-{}", state);
+            let access_token = format!("at-{}", state);
+            Mock::given(method("POST"))
+                .and(path("/token"))
+                .and(wiremock::matchers::body_string_contains(code.as_str()))
+                .and(wiremock::matchers::header_exists("authorization"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "access_token": access_token })))
+                .mount(idp)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/userinfo"))
+                .and(header("authorization", format!("Bearer {}", access_token).as_str()))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(json!({ "sub": sub, "email": email, "email_verified": verified })),
+                )
+                .mount(idp)
+                .await;
+            let cookies = [Some(state_cookie), session].into_iter().flatten().collect::<Vec<_>>().join("; ");
+            client
+                .get(url(&format!("/auth/oidc/callback?code={}&state={}", code, state)))
+                .header("cookie", cookies)
+                .send()
+                .await
+                .unwrap()
+        }
+    };
+    let session = |resp: &reqwest::Response| {
+        assert_eq!(resp.status(), 303);
+        assert_eq!(resp.headers()["location"], "/");
+        // state Cookie 随之清除
+        assert!(cookie(resp, "oidc_state").unwrap().contains("Max-Age=0"));
+        cookie(resp, "session").unwrap().split(';').next().unwrap().to_string()
+    };
+    let me = |cookie: String| {
+        let client = &client;
+        async move { client.get(url("/me")).header("cookie", cookie).send().await.unwrap().json::<Value>().await.unwrap() }
+    };
+
+    let first = me(session(&sign_in("user-42", "Carol@Example.com", false, None).await)).await;
+    assert_eq!(first["user"]["email"], "carol@example.com");
+    // 提供方中的邮箱改变后仍是同一用户
+    let again = me(session(&sign_in("user-42", "carol@new.example.com", false, None).await)).await;
+    assert_eq!(again["user"]["id"], first["user"]["id"]);
+
+    // 回调未带发起登录时的 state Cookie（他人构造的回调链接）、未知或已使用过的 state
+    let (state, _) = begin().await;
+    let resp = client.get(url(&format!("/auth/oidc/callback?code=x&state={}", state))).send().await.unwrap();
+    assert_error_envelope(resp, 400, "已过期").await;
+    let resp = client
+        .get(url("/auth/oidc/callback?code=x&state=unknown"))
+        .header("cookie", "oidc_state=unknown")
+        .send()
+        .await
+        .unwrap();
+    assert_error_envelope(resp, 400, "已过期").await;
+
+    // 已有密码账户：即使提供方已验证邮箱也不自动关联，先用密码登录后再关联
+    let resp = client
+        .post(url("/auth/register"))
+        .json(&json!({ "email": "grace@example.com", "password": "correct horse" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let password_session = cookie(&resp, "session").unwrap().split(';').next().unwrap().to_string();
+    let owner = me(password_session.clone()).await;
+    let resp = sign_in("user-7", "grace@example.com", true, None).await;
+    assert_eq!(resp.status(), 409);
+    assert_eq!(resp.json::<Value>().await.unwrap()["code"], "LOGIN_TO_LINK");
+    let linked = me(session(&sign_in("user-7", "grace@example.com", true, Some(password_session)).await)).await;
+    assert_eq!(linked["user"]["id"], owner["user"]["id"]);
+    let later = me(session(&sign_in("user-7", "grace@example.com", true, None).await)).await;
+    assert_eq!(later["user"]["id"], owner["user"]["id"]);
+
+    // 同一客户端 10 分钟内最多发起 20 次登录
+    for _ in 0..14 {
+        begin().await;
+    }
+    let resp = client.get(url("/auth/oidc/login")).send().await.unwrap();
+    assert!(resp.headers().contains_key("retry-after"));
+    assert_error_envelope(resp, 429, "过于频繁").await;
+    let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+async fn share_links_redirect_to_the_location() {
+    let upstream = MockServer::start().await;
+    let db = std::env::temp_dir().join(format!("caiyun-share-{}.sqlite3", std::process::id()));
+    let _ = std::fs::remove_file(&db);
+    let server = start(
+        &upstream,
+        &[("SHARE_LINKS", "1"), ("SHARE_DB", db.to_str().unwrap()), ("PUBLIC_URL", "https://weather.example.com")],
+    )
+    .await;
+    let client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap();
+    let create = |body: Value| client.post(format!("{}/api/v1/share", server.base)).json(&body).send();
+
+    let resp = create(json!({ "lng": 121.473701, "lat": 31.230416, "name": "上海 外滩" })).await.unwrap();
+    assert_eq!(resp.status(), 201);
+    let link: Value = resp.json().await.unwrap();
+    let code = link["code"].as_str().unwrap().to_string();
+    assert_eq!(code.len(), 7);
+    assert_eq!(link["url"], format!("https://weather.example.com/s/{}", code));
+    assert_eq!((link["lng"].as_f64(), link["lat"].as_f64()), (Some(121.4737), Some(31.2304)));
+
+    // 同一地点复用短码
+    let again = create(json!({ "lng": 121.47370, "lat": 31.23042, "name": " 上海 外滩 " })).await.unwrap();
+    assert_eq!(again.status(), 200);
+    assert_eq!(again.json::<Value>().await.unwrap()["code"], code.as_str());
+    assert_error_envelope(create(json!({ "lng": 200.0, "lat": 31.2 })).await.unwrap(), 400, "经纬度").await;
+
+    let resp = client.get(format!("{}/s/{}", server.base, code)).send().await.unwrap();
+    assert_eq!(resp.status(), 303);
+    assert_eq!(resp.headers()["location"], "../?lng=121.4737&lat=31.2304&name=%E4%B8%8A%E6%B5%B7%20%E5%A4%96%E6%BB%A9");
+    let resolved = server.get_json(&format!("/api/v1/share/{}", code)).await;
+    assert_eq!(resolved["name"], "上海 外滩");
+    assert_error_envelope(server.get("/api/v1/share/nope").await, 404, "短链接不存在").await;
+
+    // 二维码：版本 3（29×29 模块）加四周各 4 个模块留白
+    let resp = server.get(&format!("/api/v1/share/{}/qr.png?scale=4", code)).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "image/png");
+    assert_eq!(resp.headers()["cache-control"], "public, max-age=86400");
+    let png = resp.bytes().await.unwrap();
+    let info = png::Decoder::new(std::io::Cursor::new(png)).read_info().unwrap().info().clone();
+    assert_eq!((info.width, info.height), (37 * 4, 37 * 4));
+    assert_eq!(server.get("/api/v1/share/nope/qr.png").await.status(), 404);
+    assert_eq!(server.get("/s/nope").await.status(), 404);
+    let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+async fn share_links_are_rate_limited_and_pruned() {
+    let upstream = MockServer::start().await;
+    let db = std::env::temp_dir().join(format!("caiyun-share-limit-{}.sqlite3", std::process::id()));
+    let _ = std::fs::remove_file(&db);
+    let server = start(
+        &upstream,
+        &[("SHARE_LINKS", "1"), ("SHARE_DB", db.to_str().unwrap()), ("SHARE_RETENTION_DAYS", "30"), ("ADMIN_TOKEN", "s3cret")],
+    )
+    .await;
+    let client = reqwest::Client::new();
+    let create = |name: String| {
+        client.post(format!("{}/api/v1/share", server.base)).json(&json!({ "lng": 116.4, "lat": 39.9, "name": name })).send()
+    };
+
+    // 同一客户端每小时最多新建 30 个，取回已有短码不计
+    for i in 0..30 {
+        assert_eq!(create(format!("地点{}", i)).await.unwrap().status(), 201);
+    }
+    assert_eq!(create("地点0".into()).await.unwrap().status(), 200);
+    let resp = create("地点30".into()).await.unwrap();
+    assert!(resp.headers().contains_key("retry-after"));
+    assert_error_envelope(resp, 429, "过于频繁").await;
+
+    // 创建超过保留天数的由 share_prune 清理
+    let old = chrono::Utc::now().timestamp() - 31 * 86_400;
+    rusqlite::Connection::open(&db)
+        .unwrap()
+        .execute("UPDATE links SET created_at = ?1 WHERE name = '地点0'", [old])
+        .unwrap();
+    let resp = client
+        .post(format!("{}/api/v1/admin/jobs/share_prune/run?wait=true", server.base))
+        .bearer_auth("s3cret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let count: i64 = rusqlite::Connection::open(&db)
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM links", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(count, 29);
+    let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+async fn share_links_do_not_trust_arbitrary_host_headers() {
+    let upstream = MockServer::start().await;
+    let db = std::env::temp_dir().join(format!("caiyun-share-host-{}.sqlite3", std::process::id()));
+    let file = std::env::temp_dir().join(format!("caiyun-share-tenants-{}.toml", std::process::id()));
+    let _ = std::fs::remove_file(&db);
+    std::fs::write(&file, "[[tenants]]\nname = \"acme\"\nhosts = [\"weather.acme.test\"]\n").unwrap();
+    let server = start(
+        &upstream,
+        &[("SHARE_LINKS", "1"), ("SHARE_DB", db.to_str().unwrap()), ("TENANTS_FILE", file.to_str().unwrap())],
+    )
+    .await;
+    let client = reqwest::Client::new();
+    let create = |host: &str| {
+        client
+            .post(format!("{}/api/v1/share", server.base))
+            .header("host", host)
+            .header("x-forwarded-proto", "https")
+            .json(&json!({ "lng": 116.4, "lat": 39.9, "name": "北京" }))
+            .send()
+    };
+    let qr = |host: &str, code: &str| {
+        client.get(format!("{}/api/v1/share/{}/qr.png", server.base, code)).header("host", host).send()
+    };
+
+    // 未配置 PUBLIC_URL：任意 Host 不写入短链接地址，也不生成二维码
+    let link: Value = create("evil.example").await.unwrap().json().await.unwrap();
+    let code = link["code"].as_str().unwrap().to_string();
+    assert!(link["url"].is_null(), "{}", link);
+    assert_error_envelope(qr("evil.example", &code).await.unwrap(), 503, "PUBLIC_URL").await;
+
+    // 按主机名识别的租户：使用其 Host，二维码只允许客户端缓存
+    let link: Value = create("weather.acme.test:8443").await.unwrap().json().await.unwrap();
+    assert_eq!(link["url"], format!("https://weather.acme.test:8443/s/{}", code));
+    let resp = qr("weather.acme.test:8443", &code).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["cache-control"], "private, max-age=86400");
+    let _ = std::fs::remove_file(&db);
+    let _ = std::fs::remove_file(&file);
+}
+
+#[tokio::test]
+async fn background_jobs_are_paced_behind_user_requests() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let db = std::env::temp_dir().join(format!("caiyun-budget-{}.sqlite3", std::process::id()));
+    let _ = std::fs::remove_file(&db);
+    // 每分钟 120 次：后台请求至少间隔 0.5 秒
+    let server = start(
+        &upstream,
+        &[
+            ("CAIYUN_RPM", "120"),
+            ("ACCURACY_LOCATIONS", "a:116.40,39.90;b:121.47,31.23;c:113.26,23.13"),
+            ("ACCURACY_DB", db.to_str().unwrap()),
+            ("ADMIN_TOKEN", "s3cret"),
+        ],
+    )
+    .await;
+    let admin = reqwest::Client::new();
+    let resp = admin
+        .post(format!("{}/api/v1/admin/jobs/accuracy_record/run", server.base))
+        .bearer_auth("s3cret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 202);
+
+    // 任务排队期间用户请求不等待
+    for _ in 0..3 {
+        let started = Instant::now();
+        assert_eq!(server.get("/api/v1/weather?lng=116.4&lat=39.9").await.status(), 200);
+        assert!(started.elapsed() < Duration::from_millis(400), "{:?}", started.elapsed());
+    }
+
+    let mut job = Value::Null;
+    for _ in 0..50 {
+        let body: Value = admin
+            .get(format!("{}/api/v1/admin/jobs", server.base))
+            .bearer_auth("s3cret")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        job = body["jobs"].as_array().unwrap().iter().find(|j| j["name"] == "accuracy_record").unwrap().clone();
+        if job["runs"] == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(job["runs"], 1);
+    assert!(job["last_error"].is_null(), "{}", job);
+    assert!(job["last_duration_ms"].as_u64().unwrap() >= 950, "{}", job);
+    let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+async fn doctor_reports_each_check() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("storm_alerts")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("ADMIN_TOKEN", "s3cret"), ("WEATHER_PROVIDERS", "caiyun,qweather")]).await;
+
+    let resp = reqwest::Client::new()
+        .get(format!("{}/api/v1/admin/diagnostics", server.base))
+        .bearer_auth("s3cret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["ok"], true);
+    let status = |name: &str| {
+        let check = body["checks"].as_array().unwrap().iter().find(|c| c["name"] == name);
+        check.unwrap_or_else(|| panic!("{} missing: {}", name, body))["status"].clone()
+    };
+    assert_eq!(status("config"), "ok");
+    assert_eq!(status("dns 127.0.0.1"), "ok");
+    assert_eq!(status("provider caiyun"), "ok");
+    // 未配置 QWEATHER_KEY
+    assert_eq!(status("provider qweather"), "skip");
+    assert_eq!(status("static"), "ok");
+    assert_eq!(status("templates"), "ok");
+
+    // 命令行：密钥被拒时标红并以非零状态退出，输出中不含 token
+    let rejecting = MockServer::start().await;
+    caiyun_weather().respond_with(ResponseTemplate::new(401)).mount(&rejecting).await;
+    let uri = rejecting.uri();
+    let out = common::command(&[
+        ("CAIYUN_API_TOKEN", TOKEN),
+        ("CAIYUN_API_URL", uri.as_str()),
+        ("MEITUAN_API_URL", uri.as_str()),
+    ])
+    .arg("doctor")
+    .output()
+    .expect("run doctor");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(!out.status.success(), "{}", stdout);
+    assert!(stdout.lines().any(|l| l.starts_with("✗ provider caiyun") && l.contains("401")), "{}", stdout);
+    assert!(stdout.lines().any(|l| l.starts_with("✓ static")), "{}", stdout);
+    assert!(!stdout.contains(TOKEN), "{}", stdout);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("1 项检查未通过"));
+
+    // 配置无效时只报告配置项
+    let out = common::command(&[("WEATHER_PROVIDERS", "nope")]).arg("doctor").output().expect("run doctor");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(!out.status.success());
+    assert!(stdout.starts_with("✗ config") && stdout.contains("nope"), "{}", stdout);
+}
+
+#[tokio::test]
+async fn radar_tiles_are_revalidated_with_etag() {
+    let upstream = MockServer::start().await;
+    let tile = b"\x89PNG fake tile".to_vec();
+    Mock::given(method("GET"))
+        .and(path("/tiles/3/4/5.png"))
+        .and(header("if-none-match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .with_priority(1)
+        .expect(1)
+        .mount(&upstream)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/tiles/3/4/5.png"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("etag", "\"v1\"")
+                .set_body_raw(tile.clone(), "image/png"),
+        )
+        .expect(1)
+        .mount(&upstream)
+        .await;
+    let template = format!("{}/tiles/{{z}}/{{x}}/{{y}}.png", upstream.uri());
+    // 缓存 0 秒：每次都回源，第二次带上 ETag，上游 304 后仍返回原瓦片
+    let server = start(&upstream, &[("RADAR_TILE_URL", template.as_str()), ("RADAR_CACHE_SECS", "0")]).await;
+    for _ in 0..2 {
+        let resp = server.get("/api/v1/radar/3/4/5.png").await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()["content-type"], "image/png");
+        assert_eq!(resp.bytes().await.unwrap().as_ref(), tile.as_slice());
+    }
+}
+
+#[tokio::test]
+async fn access_log_records_requests() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let dir = std::env::temp_dir().join(format!("caiyun-access-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let read_lines = |file: &std::path::Path, n: usize| {
+        let file = file.to_path_buf();
+        async move {
+            for _ in 0..50 {
+                let text = std::fs::read_to_string(&file).unwrap_or_default();
+                if text.lines().count() >= n {
+                    return text;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            panic!("access log {} has fewer than {} lines", file.display(), n);
+        }
+    };
+    // 不自动解压：日志记录的是压缩后实际发出的字节数
+    let client = reqwest::Client::builder().no_gzip().no_brotli().build().unwrap();
+
+    // json：来自可信代理（本机）时取 X-Forwarded-For 中最右侧的不可信地址
+    let json_log = dir.join("access.json.log");
+    let server = start(
+        &upstream,
+        &[
+            ("ACCESS_LOG_FILE", json_log.to_str().unwrap()),
+            ("ACCESS_LOG_FORMAT", "json"),
+            ("ACCESS_LOG_ROTATION", "never"),
+            ("ACCESS_LOG_TRUSTED_PROXIES", "127.0.0.1,10.0.0.0/8"),
+        ],
+    )
+    .await;
+    let resp = client
+        .get(format!("{}/api/v1/weather?lng=116.4&lat=39.9", server.base))
+        .header("x-forwarded-for", "203.0.113.1, 198.51.100.7, 10.1.2.3")
+        .header("accept-encoding", "gzip")
+        .send()
+        .await
+        .unwrap();
+    let body = resp.bytes().await.unwrap();
+    let text = read_lines(&json_log, 1).await;
+    let line: Value = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+    assert_eq!(line["client_ip"], "198.51.100.7");
+    assert_eq!(line["method"], "GET");
+    assert_eq!(line["path"], "/api/v1/weather?lng=116.4&lat=39.9");
+    assert_eq!(line["route"], "/api/v1/weather");
+    assert_eq!(line["status"], 200);
+    assert_eq!(line["bytes"], body.len());
+    assert!(line["latency_ms"].is_u64());
+    drop(server);
+
+    // combined：未配置可信代理时忽略转发头，记录对端地址
+    let clf_log = dir.join("access.log");
+    let server = start(
+        &upstream,
+        &[("ACCESS_LOG_FILE", clf_log.to_str().unwrap()), ("ACCESS_LOG_ROTATION", "never")],
+    )
+    .await;
+    client
+        .get(format!("{}/api/v1/weather?lng=121.47&lat=31.23", server.base))
+        .header("x-forwarded-for", "198.51.100.7")
+        .header("user-agent", "probe/1.0")
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap();
+    let text = read_lines(&clf_log, 1).await;
+    let line = text.lines().next().unwrap();
+    assert!(line.starts_with("127.0.0.1 - - ["), "{}", line);
+    assert!(line.contains("] \"GET /api/v1/weather?lng=121.47&lat=31.23 HTTP/1.1\" 200 "), "{}", line);
+    assert!(line.contains(" \"-\" \"probe/1.0\" "), "{}", line);
+    drop(server);
+
+    let out = common::command(&[("ACCESS_LOG_FILE", "access.log"), ("ACCESS_LOG_TRUSTED_PROXIES", "10.0.0.0/40")])
+        .arg("doctor")
+        .output()
+        .expect("run doctor");
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("ACCESS_LOG_TRUSTED_PROXIES"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn hourly_forecast_aggregates_into_six_hour_blocks() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[]).await;
+
+    let hourly = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await["hourly"].clone();
+    let hourly = hourly.as_array().unwrap();
+    let blocks = server.get_json("/api/v1/weather?lng=116.4&lat=39.9&granularity=6h").await["hourly"].clone();
+    let blocks = blocks.as_array().unwrap();
+
+    // 24 小时从当前时刻起，首尾两块可能不足 6 小时
+    assert!((4..=5).contains(&blocks.len()), "{:?}", blocks);
+    assert_eq!(blocks.iter().map(|b| b["hours"].as_u64().unwrap()).sum::<u64>(), hourly.len() as u64);
+    let periods = ["night", "morning", "afternoon", "evening"];
+    for pair in blocks.windows(2) {
+        let (a, b) = (pair[0]["period"].as_str().unwrap(), pair[1]["period"].as_str().unwrap());
+        let ia = periods.iter().position(|p| *p == a).unwrap();
+        assert_eq!(periods[(ia + 1) % 4], b);
+    }
+    let temp = |h: &Value| h["temperature"].as_i64().unwrap();
+    let lowest = blocks.iter().map(|b| b["min_temp"].as_i64().unwrap()).min();
+    assert_eq!(lowest, hourly.iter().map(temp).min());
+    let highest = blocks.iter().map(|b| b["max_temp"].as_i64().unwrap()).max();
+    assert_eq!(highest, hourly.iter().map(temp).max());
+    let total = |items: &[Value]| items.iter().map(|h| h["precipitation"].as_f64().unwrap()).sum::<f64>();
+    assert!((total(blocks) - total(hourly)).abs() < 0.05);
+    assert!(blocks.iter().all(|b| b["skycon"].is_string() && b["weather_info"].is_object()));
+
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9&granularity=3h").await;
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn weather_lang_localizes_dates_and_numbers() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[]).await;
+
+    let zh = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert!(zh["daily"][0].get("display").is_none());
+    let en = server.get_json("/api/v1/weather?lng=116.4&lat=39.9&lang=en-US").await;
+    let (zh_day, en_day) = (&zh["daily"][0], &en["daily"][0]);
+    let weekdays = ["周一", "周二", "周三", "周四", "周五", "周六", "周日"];
+    let i = weekdays.iter().position(|w| *w == zh_day["weekday"]).unwrap();
+    assert_eq!(en_day["weekday"], ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"][i]);
+    // date 为 MM-DD
+    let (month, day) = zh_day["date"].as_str().unwrap().split_once('-').unwrap();
+    let months = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let month: usize = month.parse().unwrap();
+    let day: u32 = day.parse().unwrap();
+    assert_eq!(en_day["display"]["date"], format!("{}, {} {}", en_day["weekday"].as_str().unwrap(), months[month - 1], day));
+    let pressure = zh_day["pressure"]["avg"].as_i64().unwrap();
+    assert_eq!(en_day["display"]["pressure"], format!("{},{:03} hPa", pressure / 1000, pressure % 1000));
+    assert_eq!(en_day["display"]["max_temp"], format!("{}°C", zh_day["max_temp"]));
+
+    let cn = server.get_json("/api/v1/weather?lng=116.4&lat=39.9&lang=zh_CN").await;
+    assert_eq!(cn["daily"][0]["weekday"], zh_day["weekday"]);
+    assert_eq!(cn["daily"][0]["display"]["date"], format!("{}月{}日 {}", month, day, weekdays[i]));
+    assert_eq!(cn["daily"][0]["display"]["pressure"], format!("{} hPa", pressure));
+    let hour = cn["hourly"][0]["time"].as_i64().unwrap();
+    assert_eq!(cn["hourly"][0]["display"]["time"], format!("{}时", hour));
+
+    let blocks = server.get_json("/api/v1/weather?lng=116.4&lat=39.9&granularity=6h&lang=en").await;
+    let labels = ["Night", "Morning", "Afternoon", "Evening"];
+    assert!(blocks["hourly"].as_array().unwrap().iter().all(|b| labels.contains(&b["label"].as_str().unwrap())));
+    let en_hour = blocks["hourly"][0]["display"]["time"].as_str().unwrap();
+    assert!(en_hour.ends_with(" AM") || en_hour.ends_with(" PM"), "{}", en_hour);
+
+    let resp = server.get("/api/v1/weather?lng=116.4&lat=39.9&lang=fr").await;
+    assert_error_envelope(resp, 400, "不支持的语言").await;
+}
+
+#[tokio::test]
+async fn extended_forecast_flags_days_missing_from_the_plan() {
+    let upstream = MockServer::start().await;
+    // 套餐只返回 3 天
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v2\.6/test-token/[-0-9.]+,[-0-9.]+/daily$"))
+        .and(query_param("dailysteps", "10"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .expect(1)
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[]).await;
+
+    let body = server.get_json("/api/v1/weather/extended?lng=116.4&lat=39.9&days=10").await;
+    assert_eq!(body["requested_days"], 10);
+    assert_eq!(body["available_days"], 3);
+    assert_eq!(body["complete"], false);
+    assert_eq!(body["daily"].as_array().unwrap().len(), 3);
+    let missing = body["missing_dates"].as_array().unwrap();
+    assert_eq!(missing.len(), 7);
+    assert_eq!(missing[0], "2025-07-31");
+    assert_eq!(missing[6], "2025-08-06");
+
+    let weeks = body["weeks"].as_array().unwrap();
+    assert_eq!(weeks.len(), 1);
+    assert_eq!(weeks[0]["start"], "2025-07-28");
+    assert_eq!(weeks[0]["end"], "2025-07-30");
+    assert_eq!(weeks[0]["days"], 3);
+    let temps = |key: &str| body["daily"].as_array().unwrap().iter().map(|d| d[key].as_f64().unwrap()).collect::<Vec<_>>();
+    assert_eq!(weeks[0]["min_temp"].as_f64(), temps("min_temp").into_iter().reduce(f64::min));
+    assert_eq!(weeks[0]["max_temp"].as_f64(), temps("max_temp").into_iter().reduce(f64::max));
+
+    // 第二次命中缓存，不再请求上游
+    let again = server.get_json("/api/v1/weather/extended?lng=116.4&lat=39.9&days=10").await;
+    assert_eq!(again, body);
+
+    let resp = server.get("/api/v1/weather/extended?lng=116.4&lat=39.9&days=20").await;
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn brief_reads_the_forecast_in_the_requested_language() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[]).await;
+
+    let data = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    let today = &data["daily"][0];
+    let body = server.get_json("/api/v1/weather/brief?lng=116.4&lat=39.9").await;
+    assert_eq!(body["lang"], "zh_CN");
+    let text = body["text"].as_str().unwrap();
+    assert!(text.contains(&format!("最高 {}°C，最低 {}°C", today["max_temp"], today["min_temp"])), "{}", text);
+    assert!(text.contains("空气质量优"), "{}", text);
+    assert!(!text.contains("带伞"), "{}", text);
+
+    let body = server.get_json("/api/v1/weather/brief?lng=116.4&lat=39.9&lang=en-US").await;
+    assert_eq!(body["lang"], "en_US");
+    let text = body["text"].as_str().unwrap();
+    assert!(text.contains(&format!("a high of {}°C", today["max_temp"])), "{}", text);
+    assert!(text.contains("Air quality is excellent"), "{}", text);
+
+    let resp = server.get("/api/v1/weather/brief?lng=116.4&lat=39.9&lang=fr").await;
+    assert_eq!(resp.status(), 400);
+
+    let file = std::env::temp_dir().join(format!("brief-{}.toml", std::process::id()));
+    std::fs::write(&file, "[zh_CN]\ntemplate = \"{{ sky \"\naqi = [\"优\", \"良\", \"轻\", \"中\", \"重\", \"严重\"]\n").unwrap();
+    let out = common::command(&[("BRIEF_TEMPLATES_FILE", file.to_str().unwrap())]).arg("doctor").output().expect("run doctor");
+    assert!(!out.status.success());
+    let _ = std::fs::remove_file(&file);
+}
+
+#[tokio::test]
+async fn voice_intent_returns_speech_and_card_for_the_searched_city() {
+    let upstream = MockServer::start().await;
+    caiyun_weather()
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture("clear_day")))
+        .mount(&upstream)
+        .await;
+    Mock::given(path("/v3/place/text"))
+        .and(query_param("keywords", "上海"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "pois": [{ "name": "上海市", "address": "", "location": "121.47,31.23" }]
+        })))
+        .mount(&upstream)
+        .await;
+    Mock::given(path("/v3/place/text"))
+        .and(query_param("keywords", "不存在"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "pois": [] })))
+        .mount(&upstream)
+        .await;
+    let server = start(&upstream, &[("AMAP_API_KEY", "amap-key")]).await;
+    let client = reqwest::Client::new();
+    let ask = |body: Value| client.post(format!("{}/api/v1/voice", server.base)).json(&body).send();
+
+    let data = server.get_json("/api/v1/weather?lng=121.47&lat=31.23").await;
+    let tomorrow = &data["daily"][1];
+    let resp = ask(json!({ "intent": "weather", "city": "上海", "day": "tomorrow" })).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    let speech = body["speech"].as_str().unwrap();
+    assert!(speech.starts_with("上海市明天"), "{}", speech);
+    assert!(speech.contains(&format!("最高 {}°C", tomorrow["max_temp"])), "{}", speech);
+    // 实况 AQI 只用于播报今天
+    assert!(!speech.contains("空气质量"), "{}", speech);
+    assert_eq!(body["card"]["title"], "上海市 · 明天");
+    assert_eq!(body["card"]["text"], body["speech"]);
+    assert_eq!(body["card"]["date"], tomorrow["date"]);
+    assert_eq!(body["card"]["icon"], tomorrow["weather_info"]["icon"]);
+
+    // 省略城市时为默认城市
+    let body: Value = ask(json!({ "intent": "weather", "day": 0, "lang": "en" })).await.unwrap().json().await.unwrap();
+    assert_eq!(body["lang"], "en_US");
+    assert_eq!(body["card"]["city"], "北京市");
+    assert!(body["speech"].as_str().unwrap().contains("today"), "{}", body["speech"]);
+
+    let resp = ask(json!({ "intent": "weather", "city": "不存在" })).await.unwrap();
+    assert_eq!(resp.status(), 404);
+    let resp = ask(json!({ "intent": "music" })).await.unwrap();
+    assert_eq!(resp.status(), 400);
+    let resp = ask(json!({ "intent": "weather", "day": 5 })).await.unwrap();
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn frontend_config_reports_languages_units_and_features() {
+    let upstream = MockServer::start().await;
+    let server = start(&upstream, &[]).await;
+    let config = server.get_json("/api/v1/config").await;
+    assert_eq!(config["default_city"]["name"], "北京市");
+    assert_eq!(config["languages"], json!(["en_US", "zh_CN"]));
+    assert_eq!(config["default_language"], "zh_CN");
+    assert_eq!(config["units"]["temperature"], "°C");
+    assert_eq!(config["base_path"], "");
+    assert_eq!(config["features"]["alerts"], true);
+    assert_eq!(config["features"]["extended_forecast"], true);
+    assert_eq!(config["features"]["share"], false);
+    assert_eq!(config["features"]["accounts"], false);
+    // 不含任何密钥
+    assert!(!config.to_string().contains("test-token"));
+
+    // 只用 Open-Meteo 时没有预警，也没有彩云 token 可用于 15 天预报
+    let server = start(&upstream, &[("WEATHER_PROVIDERS", "open_meteo"), ("CAIYUN_API_TOKEN", "")]).await;
+    let config = server.get_json("/api/v1/config").await;
+    assert_eq!(config["features"]["alerts"], false);
+    assert_eq!(config["features"]["extended_forecast"], false);
+}
+
+#[tokio::test]
+async fn feature_flags_gate_endpoints_and_reload_from_file() {
+    let file = std::env::temp_dir().join(format!("flags-{}.toml", std::process::id()));
+    std::fs::write(&file, "voice = false\n").unwrap();
+    let upstream = MockServer::start().await;
+    let server = start(
+        &upstream,
+        &[
+            ("FEATURE_FLAGS", "brief=off,voice=on"),
+            ("FEATURE_FLAGS_FILE", file.to_str().unwrap()),
+            ("ADMIN_TOKEN", "s3cret"),
+        ],
+    )
+    .await;
+    let client = reqwest::Client::new();
+    let ask_voice = || client.post(format!("{}/api/v1/voice", server.base)).json(&json!({ "intent": "music" })).send();
+
+    // 文件优先于 FEATURE_FLAGS
+    let resp = ask_voice().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    assert_eq!(resp.json::<Value>().await.unwrap()["code"], "FEATURE_DISABLED");
+    let resp = server.get("/api/v1/weather/brief?lng=116.4&lat=39.9").await;
+    assert_eq!(resp.status(), 404);
+    let config = server.get_json("/api/v1/config").await;
+    assert_eq!(config["flags"]["voice"], false);
+    assert_eq!(config["flags"]["brief"], false);
+    assert_eq!(config["flags"]["graphql"], true);
+
+    // 修改文件后运行重新读取任务
+    std::fs::write(&file, "voice = true\n").unwrap();
+    let reload = || {
+        client.post(format!("{}/api/v1/admin/jobs/feature_flags_reload/run", server.base)).bearer_auth("s3cret").send()
+    };
+    assert_eq!(reload().await.unwrap().status(), 202);
+    let mut status = 404;
+    for _ in 0..50 {
+        // 意图无效返回 400，说明已通过开关
+        status = ask_voice().await.unwrap().status().as_u16();
+        if status != 404 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(status, 400);
+
+    // 文件内容无效时保留原值，自检报告失败
+    std::fs::write(&file, "voice = maybe\n").unwrap();
+    assert_eq!(reload().await.unwrap().status(), 202);
+    let mut check = Value::Null;
+    for _ in 0..50 {
+        let resp = client.get(format!("{}/api/v1/admin/diagnostics", server.base)).bearer_auth("s3cret").send();
+        let body = resp.await.unwrap().json::<Value>().await.unwrap();
+        check = body["checks"].as_array().unwrap().iter().find(|c| c["name"] == "feature_flags").cloned().unwrap();
+        if check["status"] == "fail" {
+            assert_eq!(body["flags"]["voice"], true);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(check["status"], "fail", "{}", check);
+    assert_eq!(ask_voice().await.unwrap().status(), 400);
+
+    // 未知开关在启动时报错
+    let out = common::command(&[("FEATURE_FLAGS", "nope=on")]).arg("doctor").output().expect("run doctor");
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("未知的功能开关"));
+    let _ = std::fs::remove_file(&file);
+}
+
+#[tokio::test]
+async fn demo_mode_generates_deterministic_weather_without_a_token() {
+    let upstream = MockServer::start().await;
+    Mock::given(method("GET")).respond_with(ResponseTemplate::new(500)).expect(0).mount(&upstream).await;
+    let uri = upstream.uri();
+    let server = Server::start(&[("DEMO_MODE", "1"), ("CAIYUN_API_URL", uri.as_str())]).await;
+
+    // 同一地点结果相同，相邻网格不同
+    let a = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    let b = server.get_json("/api/v1/weather?lng=116.4&lat=39.9").await;
+    assert_eq!(a["daily"], b["daily"]);
+    assert_eq!(a["hourly"], b["hourly"]);
+    let other = server.get_json("/api/v1/weather?lng=121.47&lat=31.23").await;
+    assert_ne!(a["hourly"], other["hourly"]);
+    assert_eq!(a["hourly"].as_array().map(Vec::len), Some(24));
+    assert_eq!(a["daily"].as_array().map(Vec::len), Some(3));
+    assert_eq!(server.get_json("/api/v1/config").await["features"]["demo"], true);
+
+    // 各纬度、各天合起来覆盖全部天气、预警与逐分钟降水
+    let mut skycons = std::collections::BTreeSet::new();
+    let (mut alerts, mut raining) = (0, 0);
+    for lat in (-60..=70).step_by(10) {
+        for lng in [-120, -30, 60, 150] {
+            let query = format!("lng={}.25&lat={}.5", lng, lat);
+            let v1 = server.get_json(&format!("/api/v1/weather?{}", query)).await;
+            let extended = server.get_json(&format!("/api/v1/weather/extended?{}&days=15", query)).await;
+            let daily = extended["daily"].as_array().unwrap();
+            assert_eq!(daily[..3], v1["daily"].as_array().unwrap()[..], "{}", query);
+            skycons.extend(v1["hourly"].as_array().unwrap().iter().map(|h| h["skycon"].as_str().unwrap().to_string()));
+            for key in ["skycon_day", "skycon_night"] {
+                skycons.extend(daily.iter().map(|d| d[key].as_str().unwrap().to_string()));
+            }
+            for d in daily {
+                assert!(d["min_temp"].as_i64() <= d["max_temp"].as_i64(), "{}", d);
+            }
+            alerts += v1["alerts"].as_array().unwrap().len();
+            raining += v1["minutely"]["will_rain"].as_bool().unwrap() as usize;
+        }
+    }
+    assert_eq!(skycons.len(), 22, "{:?}", skycons);
+    assert!(alerts > 0 && raining > 0, "alerts {} raining {}", alerts, raining);
+}